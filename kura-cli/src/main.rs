@@ -0,0 +1,309 @@
+mod repl;
+
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Subcommand, ValueEnum};
+use kura_lexer::{tokenize_all, Lexer};
+use kura_parser::dot::statements_to_dot;
+use kura_parser::passes::{check_comptime, lint_all};
+use kura_parser::sexpr::statements_to_sexpr;
+use kura_parser::{ParseError, Parser, Statement};
+use miette::{JSONReportHandler, NamedSource, Report};
+
+#[derive(clap::Parser)]
+#[command(
+    name = "rmb",
+    version,
+    about,
+    args_conflicts_with_subcommands = true,
+    arg_required_else_help = true
+)]
+struct Cli {
+    /// lexes the file and prints every token on its own line, `-` reads
+    /// from stdin
+    #[arg(long, value_name = "FILE")]
+    dump_tokens: Option<PathBuf>,
+    /// parses the file and prints its syntax tree, `-` reads from stdin
+    #[arg(long, value_name = "FILE", conflicts_with = "dump_tokens")]
+    dump_ast: Option<PathBuf>,
+    /// how `--dump-ast` prints the syntax tree
+    #[arg(long, value_enum, default_value_t = Emit::Debug, requires = "dump_ast")]
+    format: Emit,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// parses each file and prints its syntax tree
+    Parse {
+        /// files to parse, `-` reads from stdin
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// how the syntax tree is printed
+        #[arg(long, value_enum, default_value_t = Emit::Debug)]
+        emit: Emit,
+        /// writes the output to a file instead of stdout
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// parses each file and runs every lint on it, reporting warnings
+    Check {
+        /// files to check, `-` reads from stdin
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// reads entries line by line and echoes their syntax tree
+    Repl,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Emit {
+    /// rust's debug formatting of the tree
+    Debug,
+    /// the tree serialized as json, diagnostics are emitted as json too
+    Json,
+    /// one s-expression per statement
+    Sexpr,
+    /// a graphviz digraph of the tree
+    Dot,
+    /// the source, as written by the formatter
+    Source,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DiagnosticFormat {
+    Pretty,
+    Json,
+}
+
+/// a file given on the command line, along with the name its diagnostics
+/// are reported with
+struct Input {
+    name: String,
+    source: String,
+}
+
+impl Input {
+    fn read(path: &Path) -> Result<Self, std::io::Error> {
+        if path == Path::new("-") {
+            let mut source = String::new();
+            std::io::stdin().read_to_string(&mut source)?;
+            return Ok(Self {
+                name: String::from("<stdin>"),
+                source,
+            });
+        }
+
+        Ok(Self {
+            name: path.display().to_string(),
+            source: std::fs::read_to_string(path)?,
+        })
+    }
+
+    fn parse(&self) -> Result<Vec<Statement<'_>>, ParseError> {
+        let lexer = Lexer::new(&self.source);
+        Parser::new(&self.source, lexer).parse()
+    }
+
+    fn report(&self, report: Report, format: DiagnosticFormat) {
+        let report = report.with_source_code(NamedSource::new(&self.name, self.source.clone()));
+
+        match format {
+            DiagnosticFormat::Pretty => eprintln!("{report:?}"),
+            DiagnosticFormat::Json => {
+                let mut json = String::new();
+                JSONReportHandler::new()
+                    .render_report(&mut json, report.as_ref())
+                    .expect("writing to a string can't fail");
+                eprintln!("{json}");
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = <Cli as clap::Parser>::parse();
+
+    if let Some(path) = &cli.dump_tokens {
+        return match dump_tokens(path) {
+            true => ExitCode::SUCCESS,
+            false => ExitCode::FAILURE,
+        };
+    }
+    if let Some(path) = &cli.dump_ast {
+        let succeeded = match Input::read(path) {
+            Ok(input) => parse(&input, cli.format, &mut std::io::stdout()),
+            Err(e) => {
+                eprintln!("error: could not read `{}`: {e}", path.display());
+                false
+            }
+        };
+        return match succeeded {
+            true => ExitCode::SUCCESS,
+            false => ExitCode::FAILURE,
+        };
+    }
+    // clap shows the help when neither a flag nor a command is given
+    let Some(command) = &cli.command else {
+        unreachable!();
+    };
+
+    let files = match command {
+        Command::Parse { files, .. } | Command::Check { files } => files,
+        Command::Repl => {
+            return match repl::run(std::io::stdin().lock(), std::io::stdout()) {
+                Ok(()) => ExitCode::SUCCESS,
+                Err(e) => {
+                    eprintln!("error: {e}");
+                    ExitCode::FAILURE
+                }
+            };
+        }
+    };
+
+    let mut output: Box<dyn Write> = match command {
+        Command::Parse { output: Some(path), .. } => match File::create(path) {
+            Ok(file) => Box::new(file),
+            Err(e) => {
+                eprintln!("error: could not create `{}`: {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        _ => Box::new(std::io::stdout()),
+    };
+
+    // every file is processed even after one of them fails, so all of the
+    // errors are reported at once
+    let mut succeeded = true;
+    for path in files {
+        let input = match Input::read(path) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("error: could not read `{}`: {e}", path.display());
+                succeeded = false;
+                continue;
+            }
+        };
+
+        succeeded &= match command {
+            Command::Parse { emit, .. } => parse(&input, *emit, &mut output),
+            Command::Check { .. } => check(&input),
+            Command::Repl => unreachable!(),
+        };
+    }
+
+    match succeeded {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+/// prints every token of the file as `<start>-<end> <kind> <text>`, returns
+/// whether it lexed
+fn dump_tokens(path: &Path) -> bool {
+    let input = match Input::read(path) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("error: could not read `{}`: {e}", path.display());
+            return false;
+        }
+    };
+
+    let tokens = match tokenize_all(&input.source) {
+        Ok(tokens) => tokens,
+        Err(error) => {
+            input.report(Report::new(error), DiagnosticFormat::Pretty);
+            return false;
+        }
+    };
+
+    let mut output = std::io::stdout().lock();
+    for token in tokens {
+        let location = token.location;
+        let text = &input.source[location.start_byte..location.end_byte];
+        if let Err(e) = writeln!(
+            output,
+            "{}-{} {} {text}",
+            location.start_byte,
+            location.end_byte,
+            token.kind.name()
+        ) {
+            eprintln!("error: could not write the tokens of `{}`: {e}", input.name);
+            return false;
+        }
+    }
+
+    true
+}
+
+/// prints the syntax tree of the input, returns whether it parsed
+fn parse(input: &Input, emit: Emit, output: &mut dyn Write) -> bool {
+    let format = match emit {
+        Emit::Json => DiagnosticFormat::Json,
+        _ => DiagnosticFormat::Pretty,
+    };
+
+    let statements = match input.parse() {
+        Ok(statements) => statements,
+        Err(error) => {
+            input.report(Report::new(error), format);
+            return false;
+        }
+    };
+
+    let rendered = match emit {
+        Emit::Debug => format!("{statements:#?}\n"),
+        Emit::Json => {
+            let mut json = serde_json::to_string_pretty(&statements).expect("the ast is always serializable");
+            json.push('\n');
+            json
+        }
+        Emit::Sexpr => statements_to_sexpr(&statements),
+        Emit::Dot => statements_to_dot(&statements),
+        Emit::Source => match kura_fmt::format(&input.source) {
+            Ok(source) => source,
+            Err(error) => {
+                input.report(Report::new(error), format);
+                return false;
+            }
+        },
+    };
+
+    if let Err(e) = output.write_all(rendered.as_bytes()) {
+        eprintln!("error: could not write the output of `{}`: {e}", input.name);
+        return false;
+    }
+
+    true
+}
+
+/// reports warnings of every lint, which don't make the check fail, along
+/// with the errors of `comptime` blocks, which do
+fn check(input: &Input) -> bool {
+    let lexer = Lexer::new(&input.source);
+    let (statements, warnings) = match Parser::new(&input.source, lexer).parse_with_warnings() {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            input.report(Report::new(error), DiagnosticFormat::Pretty);
+            return false;
+        }
+    };
+
+    let errors = check_comptime(&statements);
+    let failed = !errors.is_empty();
+    errors
+        .into_iter()
+        .for_each(|error| input.report(error, DiagnosticFormat::Pretty));
+
+    warnings
+        .into_iter()
+        .map(Report::new)
+        .chain(lint_all(&statements))
+        .for_each(|warning| input.report(warning, DiagnosticFormat::Pretty));
+
+    !failed
+}
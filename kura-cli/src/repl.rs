@@ -0,0 +1,316 @@
+use std::io::{BufRead, Write};
+
+use kura_lexer::token::{Kind, Operator};
+use kura_lexer::Lexer;
+use kura_parser::sexpr::{statements_to_sexpr, to_sexpr};
+use kura_parser::warning::DiagnosticWarning;
+use kura_parser::{Expression, ParseError, Parser, Statement};
+use miette::{NamedSource, Report};
+
+pub const PROMPT: &str = ">> ";
+pub const CONTINUATION_PROMPT: &str = ".. ";
+
+#[derive(Debug)]
+pub enum Outcome {
+    /// the entry is not complete yet, or there is nothing to show
+    Continue,
+    /// a complete entry, echoed as an s-expression, along with the warnings
+    /// found in it
+    Parsed {
+        rendered: String,
+        warnings: Vec<Report>,
+    },
+    Failed(Failure),
+}
+
+/// an entry that failed to parse, along with its source so it can be
+/// reported
+#[derive(Debug)]
+pub struct Failure {
+    pub error: ParseError,
+    source: NamedSource<String>,
+}
+
+impl Failure {
+    pub fn into_report(self) -> Report {
+        Report::new(self.error).with_source_code(self.source)
+    }
+}
+
+/// the line accumulation of the repl, independent of any terminal. lines are
+/// accumulated until they parse, or fail to parse for a reason other than
+/// the input ending early
+#[derive(Debug, Default)]
+pub struct Repl {
+    pending: String,
+    entries: usize,
+    /// names declared on the session so far, most recent last
+    bindings: Vec<String>,
+}
+
+impl Repl {
+    pub fn prompt(&self) -> &'static str {
+        match self.pending.is_empty() {
+            true => PROMPT,
+            false => CONTINUATION_PROMPT,
+        }
+    }
+
+    pub fn bindings(&self) -> &[String] {
+        &self.bindings
+    }
+
+    pub fn feed(&mut self, line: &str) -> Outcome {
+        if self.pending.is_empty() && line.trim().is_empty() {
+            return Outcome::Continue;
+        }
+
+        self.pending.push_str(line);
+        if !self.pending.ends_with('\n') {
+            self.pending.push('\n');
+        }
+
+        match self.parse() {
+            Err(error) if error.is_incomplete() => Outcome::Continue,
+            Err(error) => {
+                let failure = self.take_error(error);
+                self.entries += 1;
+                Outcome::Failed(failure)
+            }
+            Ok((rendered, declared, warnings)) => {
+                let source = self.take_source();
+                let warnings = warnings
+                    .into_iter()
+                    .map(|warning| Report::new(warning).with_source_code(source.clone()))
+                    .collect();
+                self.bindings.extend(declared);
+                self.entries += 1;
+                Outcome::Parsed { rendered, warnings }
+            }
+        }
+    }
+
+    /// called when the input ends, reports the entry that was still waiting
+    /// for more lines, if any
+    pub fn finish(&mut self) -> Option<Failure> {
+        if self.pending.is_empty() {
+            return None;
+        }
+
+        match self.parse() {
+            Err(error) => Some(self.take_error(error)),
+            Ok(_) => unreachable!("complete entries are never left pending"),
+        }
+    }
+
+    /// parses the pending entry, returning it rendered along with the names
+    /// it declares and the warnings found in it
+    fn parse(&self) -> Result<(String, Vec<String>, Vec<DiagnosticWarning>), ParseError> {
+        let source = self.pending.as_str();
+
+        if starts_a_statement(source) {
+            let (statements, warnings) = Parser::new(source, Lexer::new(source)).parse_with_warnings()?;
+            let declared = statements
+                .iter()
+                .filter_map(|statement| match statement {
+                    Statement::Fun { name, .. } | Statement::Struct { name, .. } => Some(name.to_string()),
+                    _ => None,
+                })
+                .collect();
+            return Ok((
+                statements_to_sexpr(&statements).trim_end().to_string(),
+                declared,
+                warnings,
+            ));
+        }
+
+        let (expression, warnings) = Parser::parse_repl_line(source)?;
+        let declared = match &expression {
+            Expression::Var { name, .. } => vec![name.to_string()],
+            _ => vec![],
+        };
+        Ok((to_sexpr(&expression), declared, warnings))
+    }
+
+    fn take_error(&mut self, error: ParseError) -> Failure {
+        let source = self.take_source();
+        Failure { error, source }
+    }
+
+    /// takes the pending entry, named after its position on the session
+    fn take_source(&mut self) -> NamedSource<String> {
+        let source = std::mem::take(&mut self.pending);
+        NamedSource::new(format!("<repl:{}>", self.entries + 1), source)
+    }
+}
+
+/// whether the entry starts with a keyword only a statement can start with,
+/// like `fun` or `struct`. variables are declared as repl lines instead, so
+/// they can be used right away
+fn starts_a_statement(source: &str) -> bool {
+    let Some(Ok(token)) = Lexer::new(source).next() else {
+        return false;
+    };
+    matches!(
+        token.kind,
+        Kind::Fun | Kind::Struct | Kind::Impl | Kind::Mod | Kind::Extern | Kind::Use | Kind::Op(Operator::Hash)
+    )
+}
+
+pub fn run(input: impl BufRead, mut output: impl Write) -> std::io::Result<()> {
+    let mut repl = Repl::default();
+    let mut lines = input.lines();
+
+    loop {
+        write!(output, "{}", repl.prompt())?;
+        output.flush()?;
+
+        let Some(line) = lines.next().transpose()? else {
+            if let Some(failure) = repl.finish() {
+                eprintln!("{:?}", failure.into_report());
+            }
+            return Ok(());
+        };
+
+        // lists what the session declared so far, only between entries
+        if line.trim() == ":bindings" && repl.prompt() == PROMPT {
+            writeln!(output, "{}", repl.bindings().join(" "))?;
+            continue;
+        }
+
+        match repl.feed(&line) {
+            Outcome::Continue => {}
+            Outcome::Parsed { rendered, warnings } => {
+                warnings.into_iter().for_each(|warning| eprintln!("{warning:?}"));
+                writeln!(output, "{rendered}")?;
+            }
+            Outcome::Failed(failure) => eprintln!("{:?}", failure.into_report()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(outcome: Outcome) -> String {
+        match outcome {
+            Outcome::Parsed { rendered, .. } => rendered,
+            outcome => panic!("expected a parsed entry, got {outcome:?}"),
+        }
+    }
+
+    #[test]
+    fn multi_line_if_waits_for_the_closing_brace() {
+        let mut repl = Repl::default();
+
+        assert!(matches!(repl.feed("if x == 1 {"), Outcome::Continue));
+        assert_eq!(repl.prompt(), CONTINUATION_PROMPT);
+        assert!(matches!(repl.feed("    println(x);"), Outcome::Continue));
+
+        let rendered = parsed(repl.feed("}"));
+        assert_eq!(
+            rendered,
+            "(if (binary-op == (ident x) (uint-literal 1)) (block (call (ident println) (ident x))))"
+        );
+        assert_eq!(repl.prompt(), PROMPT);
+    }
+
+    #[test]
+    fn syntax_error_mid_continuation_discards_the_entry() {
+        let mut repl = Repl::default();
+
+        assert!(matches!(repl.feed("if x == 1 {"), Outcome::Continue));
+        let Outcome::Failed(failure) = repl.feed("const = 5;") else {
+            panic!("expected a syntax error");
+        };
+
+        assert!(!failure.error.is_incomplete());
+        assert_eq!(repl.prompt(), PROMPT);
+
+        // the next entry starts from scratch
+        parsed(repl.feed("1 + 2"));
+    }
+
+    #[test]
+    fn end_of_input_with_a_pending_continuation() {
+        let mut repl = Repl::default();
+
+        assert!(repl.finish().is_none());
+        assert!(matches!(repl.feed("fun main() {"), Outcome::Continue));
+
+        let failure = repl.finish().unwrap();
+        assert!(failure.error.is_incomplete());
+        assert_eq!(repl.prompt(), PROMPT);
+    }
+
+    #[test]
+    fn declarations_persist_across_entries() {
+        let mut repl = Repl::default();
+
+        parsed(repl.feed("const x = 1"));
+        assert!(matches!(repl.feed("var y = {"), Outcome::Continue));
+        assert_eq!(repl.bindings(), ["x"]);
+
+        assert!(matches!(repl.feed("x + 1 }"), Outcome::Parsed { .. }));
+        parsed(repl.feed("fun double(n: u64) => u64 { n * 2 }"));
+        assert_eq!(repl.bindings(), ["x", "y", "double"]);
+    }
+
+    #[test]
+    fn warnings_of_an_entry_are_reported() {
+        let mut repl = Repl::default();
+
+        let Outcome::Parsed { warnings, .. } = repl.feed("const x = y") else {
+            panic!("expected a parsed entry");
+        };
+        assert_eq!(warnings.len(), 1);
+
+        // and not carried over to the next entry
+        let Outcome::Parsed { warnings, .. } = repl.feed("const z = 1") else {
+            panic!("expected a parsed entry");
+        };
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn entries_are_routed_on_their_first_token() {
+        let mut repl = Repl::default();
+
+        // `funds` only starts like `fun`, it's an identifier
+        parsed(repl.feed("var funds = 1"));
+        assert_eq!(
+            parsed(repl.feed("funds + 1")),
+            "(binary-op + (ident funds) (uint-literal 1))"
+        );
+
+        parsed(repl.feed("struct P { x: i32 }"));
+        assert_eq!(repl.bindings(), ["funds", "P"]);
+    }
+
+    #[test]
+    fn failed_entries_keep_their_own_name() {
+        let mut repl = Repl::default();
+
+        let Outcome::Failed(first) = repl.feed("const = 5;") else {
+            panic!("expected a syntax error");
+        };
+        let Outcome::Failed(second) = repl.feed("const = 6;") else {
+            panic!("expected a syntax error");
+        };
+        assert_eq!(first.source.name(), "<repl:1>");
+        assert_eq!(second.source.name(), "<repl:2>");
+    }
+
+    #[test]
+    fn run_echoes_every_entry() {
+        let input = "const a = 1\nif a == 1 {\n2\n}\n:bindings\n";
+        let mut output = vec![];
+        run(input.as_bytes(), &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            ">> (const a (uint-literal 1))\n>> .. .. (if (binary-op == (ident a) (uint-literal 1)) (block (tail (uint-literal 2))))\n>> a\n>> "
+        );
+    }
+}
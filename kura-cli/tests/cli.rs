@@ -0,0 +1,235 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures");
+
+fn rmb() -> Command {
+    let mut command = Command::cargo_bin("rmb").unwrap();
+    command.current_dir(FIXTURES);
+    command
+}
+
+#[test]
+fn parse_prints_the_ast_to_stdout() {
+    rmb()
+        .args(["parse", "valid.rmb"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Fun {"))
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn syntax_errors_go_to_stderr() {
+    rmb()
+        .args(["parse", "syntax_error.rmb"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("syntax_error.rmb"))
+        .stderr(predicate::str::contains("expected `;`"));
+}
+
+#[test]
+fn parse_reads_from_stdin() {
+    let source = std::fs::read_to_string(format!("{FIXTURES}/valid.rmb")).unwrap();
+
+    rmb()
+        .args(["parse", "-"])
+        .write_stdin(source)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Fun {"));
+}
+
+#[test]
+fn every_file_is_processed_after_a_failure() {
+    rmb()
+        .args(["parse", "syntax_error.rmb", "valid.rmb", "missing.rmb"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("Fun {"))
+        .stderr(predicate::str::contains("syntax_error.rmb"))
+        .stderr(predicate::str::contains("could not read `missing.rmb`"));
+}
+
+#[test]
+fn check_warnings_dont_fail() {
+    rmb()
+        .args(["check", "warnings.rmb", "valid.rmb"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("never_read"))
+        .stderr(predicate::str::contains("cannot verify constness"))
+        .stderr(predicate::str::contains("variable `sum`").not());
+}
+
+#[test]
+fn check_warns_on_calls_to_deprecated_functions() {
+    rmb()
+        .args(["check", "-"])
+        .write_stdin(
+            "#[deprecated(since = \"0.2\", note = \"use `bar` instead\")]\nfun foo() {}\nfun main() { foo(); }",
+        )
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("`foo` is deprecated since 0.2"))
+        .stderr(predicate::str::contains("use `bar` instead"));
+}
+
+#[test]
+fn check_warns_on_mutable_globals() {
+    rmb()
+        .args(["check", "-"])
+        .write_stdin("var counter = 0;\nconst LIMIT = 10;\nfun main() { counter = LIMIT; }")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("`counter` is mutable global state"))
+        .stderr(predicate::str::contains("`LIMIT` is mutable").not());
+}
+
+#[test]
+fn check_fails_on_syntax_errors() {
+    rmb()
+        .args(["check", "-"])
+        .write_stdin("fun main() { const a = ; }")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("<stdin>"));
+}
+
+#[test]
+fn check_fails_on_comptime_blocks_that_are_not_constant() {
+    rmb()
+        .args(["check", "-"])
+        .write_stdin("fun main() { const a = comptime { 1 + 2 }; const b = comptime { a * 2 }; }")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("this can't be evaluated at compile time"));
+}
+
+fn emit(mode: &str) -> String {
+    let output = rmb()
+        .args(["parse", "valid.rmb", &format!("--emit={mode}")])
+        .output()
+        .unwrap();
+
+    assert!(output.status.success(), "--emit={mode} failed");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn every_emit_mode_renders_the_same_fixture() {
+    assert!(emit("debug").starts_with("[\n    Fun {"));
+
+    let json = serde_json::from_str::<serde_json::Value>(&emit("json")).unwrap();
+    assert_eq!(json[0]["Fun"]["name"], "add");
+
+    assert!(emit("sexpr").starts_with("(fun add (args"));
+    assert!(emit("dot").starts_with("digraph"));
+
+    // the formatted source has to parse back into the same tree
+    let source = emit("source");
+    rmb()
+        .args(["parse", "-"])
+        .write_stdin(source)
+        .assert()
+        .success()
+        .stdout(emit("debug"));
+}
+
+#[test]
+fn json_diagnostics_for_json_output() {
+    let output = rmb()
+        .args(["parse", "syntax_error.rmb", "--emit=json"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    let diagnostic = serde_json::from_slice::<serde_json::Value>(&output.stderr).unwrap();
+    assert_eq!(diagnostic["code"], "kura::syntax_error");
+    assert_eq!(diagnostic["filename"], "syntax_error.rmb");
+}
+
+#[test]
+fn output_is_written_to_a_file() {
+    let path = std::env::temp_dir().join(format!("rmb-output-{}.sexpr", std::process::id()));
+
+    rmb()
+        .args(["parse", "valid.rmb", "--emit=sexpr", "--output"])
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+
+    let written = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(written, emit("sexpr"));
+}
+
+#[test]
+fn unknown_emit_modes_are_usage_errors() {
+    rmb()
+        .args(["parse", "valid.rmb", "--emit=xml"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("invalid value 'xml'"));
+}
+
+#[test]
+fn tokens_are_dumped_one_per_line() {
+    rmb()
+        .args(["--dump-tokens", "-"])
+        .write_stdin("fun main() {\n    print(\"hi\");\n}")
+        .assert()
+        .success()
+        .stdout(concat!(
+            "0-3 Fun fun\n",
+            "4-8 Ident main\n",
+            "8-9 Op (\n",
+            "9-10 Op )\n",
+            "11-12 Op {\n",
+            "17-22 Ident print\n",
+            "22-23 Op (\n",
+            "23-27 String \"hi\"\n",
+            "27-28 Op )\n",
+            "28-29 Op ;\n",
+            "30-31 Op }\n",
+        ));
+}
+
+#[test]
+fn lex_errors_fail_the_token_dump() {
+    rmb()
+        .args(["--dump-tokens", "-"])
+        .write_stdin("const a = @;")
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("unexpected character `@`"));
+
+    rmb().args(["--dump-tokens", "valid.rmb", "parse"]).assert().code(2);
+}
+
+#[test]
+fn the_ast_is_dumped_like_parse_emits_it() {
+    for format in ["debug", "sexpr", "json"] {
+        rmb()
+            .args(["--dump-ast", "valid.rmb", "--format", format])
+            .assert()
+            .success()
+            .stdout(emit(format));
+    }
+
+    rmb()
+        .args(["--dump-ast", "valid.rmb"])
+        .assert()
+        .success()
+        .stdout(emit("debug"));
+    rmb()
+        .args(["--dump-ast", "syntax_error.rmb"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("expected `;`"));
+    rmb().args(["--format", "sexpr"]).assert().code(2);
+}
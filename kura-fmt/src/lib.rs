@@ -0,0 +1,1478 @@
+use kura_lexer::token::{Kind, Location, Operator, Token, Value};
+use kura_lexer::Lexer;
+use kura_parser::ops::{self, Associativity};
+use kura_parser::{DoStep, Expression, MatchArm, ParseError, Parser, Pattern, Statement, VariantKind};
+
+const INDENT: &str = "    ";
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// argument lists, enum variants and chains of binary operations that
+    /// would go past this column are wrapped, with one argument, field or
+    /// operand per line
+    pub max_width: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { max_width: 100 }
+    }
+}
+
+#[derive(Debug)]
+struct Comment<'fmt> {
+    text: &'fmt str,
+    location: Location,
+}
+
+/// parses `source` and re-emits it with the canonical style, keeping every
+/// comment around. sources that don't parse return the parser error as is
+pub fn format(source: &str) -> Result<String, ParseError> {
+    format_with_config(source, &Config::default())
+}
+
+pub fn format_with_config(source: &str, config: &Config) -> Result<String, ParseError> {
+    let lexer = Lexer::new(source);
+    let statements = Parser::new(source, lexer).parse()?;
+    let comments = collect_comments(source)?;
+
+    let mut formatter = Formatter::new(source, comments, config);
+    formatter.write_statements(&statements);
+
+    Ok(formatter.finish())
+}
+
+/// renders a tree that didn't come from a source, like one built by hand or
+/// by a pass, in the canonical style. strings and macros are written from
+/// their contents instead of the text they were parsed from
+pub fn print(statements: &[Statement<'_>]) -> String {
+    let config = Config::default();
+    let mut formatter = Formatter::new("", vec![], &config);
+    formatter.write_statements(statements);
+    formatter.finish()
+}
+
+fn collect_comments(source: &str) -> Result<Vec<Comment<'_>>, ParseError> {
+    let mut comments = vec![];
+
+    for token in Lexer::new(source).preserve_comments(true) {
+        let token = token?;
+        match token.kind {
+            Kind::LineComment(_) | Kind::BlockComment(_) | Kind::Shebang(_) => comments.push(Comment {
+                text: &source[token.location.start_byte..token.location.end_byte],
+                location: token.location,
+            }),
+            _ => (),
+        }
+    }
+
+    // we pop comments from the back as we go through the ast
+    comments.reverse();
+    Ok(comments)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Side {
+    Lhs,
+    Rhs,
+}
+
+/// function and macro calls, and assertions, are the only expressions that
+/// own their semicolon, which they keep when awaited
+fn is_call(expression: &Expression<'_>) -> bool {
+    match expression {
+        Expression::FunCall { .. }
+        | Expression::Macro { .. }
+        | Expression::Assert { .. }
+        | Expression::RuntimeTypeOf { .. }
+        | Expression::ArrayLen { .. }
+        | Expression::Sizeof { .. }
+        | Expression::InlineAsm { .. } => true,
+        Expression::Await { expr, .. } => is_call(expr),
+        _ => false,
+    }
+}
+
+// the binding power the parser gives to each binary operator, so we only
+// emit parenthesis where they are needed to keep the same tree
+fn precedence(operator: Operator) -> u8 {
+    ops::infix(operator).map_or(ops::LOWEST, |info| info.binding_power)
+}
+
+fn starts_with_block(expression: &Expression<'_>) -> bool {
+    match expression {
+        Expression::Block { .. } => true,
+        Expression::BinaryOp { lhs: first, .. }
+        | Expression::Elvis { condition: first, .. }
+        | Expression::SafeCast { expr: first, .. }
+        | Expression::FunCall { ident: first, .. }
+        | Expression::Await { expr: first, .. }
+        | Expression::FieldAccess { target: first, .. }
+        | Expression::TupleAccess { tuple: first, .. }
+        | Expression::Index { target: first, .. }
+        | Expression::Try { expr: first, .. } => starts_with_block(first),
+        _ => false,
+    }
+}
+
+struct Formatter<'fmt> {
+    source: &'fmt str,
+    comments: Vec<Comment<'fmt>>,
+    config: &'fmt Config,
+    output: String,
+    indent: usize,
+    // end of the last thing written, used to check for blank lines in the
+    // source between two items
+    last_end: usize,
+    // whether we are writing the condition of an `if`, where a block after
+    // a call is the body of the `if` rather than a trailing closure
+    in_condition: bool,
+    // whether nothing of the statement being written was written yet. an
+    // `unsafe` block starting a statement is parsed as a statement itself
+    statement_start: bool,
+}
+
+impl<'fmt> Formatter<'fmt> {
+    fn new(source: &'fmt str, comments: Vec<Comment<'fmt>>, config: &'fmt Config) -> Self {
+        Self {
+            source,
+            comments,
+            config,
+            output: String::new(),
+            indent: 0,
+            last_end: 0,
+            in_condition: false,
+            statement_start: false,
+        }
+    }
+
+    fn finish(mut self) -> String {
+        let trailing = self.output.trim_end().len();
+        self.output.truncate(trailing);
+        self.output.push('\n');
+        self.output
+    }
+
+    fn push(&mut self, text: &str) {
+        self.statement_start = false;
+        self.output.push_str(text);
+    }
+
+    fn new_line(&mut self) {
+        if self.output.is_empty() {
+            return;
+        }
+
+        self.output.push('\n');
+        for _ in 0..self.indent {
+            self.output.push_str(INDENT);
+        }
+    }
+
+    fn blank_line(&mut self) {
+        if !self.output.is_empty() {
+            self.output.push('\n');
+        }
+    }
+
+    fn column(&self) -> usize {
+        let line_start = self.output.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+        self.output[line_start..].chars().count()
+    }
+
+    fn has_blank_line(&self, start: usize, end: usize) -> bool {
+        start < end
+            && self
+                .source
+                .get(start..end)
+                .is_some_and(|text| text.matches('\n').count() > 1)
+    }
+
+    /// writes every comment that starts before `until` on its own line,
+    /// keeping a single blank line wherever the source had one or more
+    fn write_leading_comments(&mut self, until: usize, first: bool, force_blank: bool) {
+        let mut first = first;
+        let mut force_blank = force_blank;
+
+        while let Some(comment) = self.comments.pop_if(|comment| comment.location.start_byte < until) {
+            if force_blank || (!first && self.has_blank_line(self.last_end, comment.location.start_byte)) {
+                self.blank_line();
+            }
+            self.new_line();
+            self.push(comment.text);
+            self.last_end = comment.location.end_byte;
+            first = false;
+            force_blank = false;
+        }
+
+        if force_blank || (!first && self.has_blank_line(self.last_end, until)) {
+            self.blank_line();
+        }
+    }
+
+    /// comments that were inside of an item we wrote (but not inside any of
+    /// its blocks), or that are on the same line right after it
+    fn write_trailing_comments(&mut self, item_end: usize) {
+        self.last_end = item_end;
+
+        while let Some(comment) = self.comments.pop_if(|comment| {
+            let start = comment.location.start_byte;
+            start < item_end || !self.source[item_end..start].contains('\n')
+        }) {
+            self.push(" ");
+            self.push(comment.text);
+            self.last_end = self.last_end.max(comment.location.end_byte);
+        }
+    }
+
+    fn write_statements(&mut self, statements: &[Statement<'_>]) {
+        for (idx, statement) in statements.iter().enumerate() {
+            let location = statement.location();
+            // consecutive imports are kept together, and so are module and
+            // dependency declarations, and globals
+            let grouped = idx > 0
+                && matches!(
+                    (&statements[idx - 1], statement),
+                    (Statement::Mod { .. }, Statement::Mod { .. })
+                        | (Statement::ExternCrate { .. }, Statement::ExternCrate { .. })
+                        | (Statement::Use { .. }, Statement::Use { .. })
+                        | (Statement::Static { .. }, Statement::Static { .. })
+                );
+            self.write_leading_comments(location.start_byte, idx == 0, idx > 0 && !grouped);
+            self.new_line();
+            self.write_statement(statement);
+            self.write_trailing_comments(location.end_byte);
+        }
+
+        self.write_leading_comments(self.source.len(), statements.is_empty(), false);
+    }
+
+    fn write_statement(&mut self, statement: &Statement<'_>) {
+        match statement {
+            Statement::FunArgument { name, arg_type, .. } => {
+                self.push(name);
+                self.push(": ");
+                self.push(&arg_type.to_string());
+            }
+            Statement::Fun {
+                name,
+                generics,
+                arguments,
+                body,
+                return_type,
+                where_clause,
+                attributes,
+                is_unsafe,
+                location,
+                ..
+            } => {
+                for attribute in attributes {
+                    self.push(&attribute.to_string());
+                    self.new_line();
+                }
+                if *is_unsafe {
+                    self.push("unsafe ");
+                }
+                self.push("fun ");
+                self.push(name);
+                self.write_angled(generics);
+                self.push("(");
+                for (idx, argument) in arguments.iter().enumerate() {
+                    if idx > 0 {
+                        self.push(", ");
+                    }
+                    self.write_statement(argument);
+                }
+                self.push(")");
+
+                if let Some(return_type) = return_type {
+                    self.push(" => ");
+                    self.push(&return_type.to_string());
+                }
+
+                if !where_clause.is_empty() {
+                    let predicates = where_clause
+                        .iter()
+                        .map(|predicate| predicate.to_string())
+                        .collect::<Vec<_>>();
+                    self.push(&format!(" where {}", predicates.join(", ")));
+                }
+
+                self.push(" ");
+                self.write_block(body, None, true, *location);
+            }
+            Statement::Struct {
+                name,
+                generics,
+                fields,
+                attributes,
+                location,
+                ..
+            } => {
+                for attribute in attributes {
+                    self.push(&attribute.to_string());
+                    self.new_line();
+                }
+                self.push("struct ");
+                self.push(name);
+                self.write_angled(generics);
+                self.push(" ");
+                self.write_items(fields, false, *location, |formatter, field| {
+                    formatter.write_statement(field);
+                    formatter.push(",");
+                });
+            }
+            Statement::Impl {
+                generics,
+                trait_name,
+                target,
+                arguments,
+                functions,
+                location,
+            } => {
+                self.push("impl");
+                self.write_angled(generics);
+                self.push(" ");
+                if let Some(trait_name) = trait_name {
+                    self.push(trait_name);
+                    self.push(" for ");
+                }
+                self.push(target);
+                self.write_angled(arguments);
+                self.push(" ");
+                self.write_items(functions, true, *location, Self::write_statement);
+            }
+            Statement::Mod { name, .. } => {
+                self.push("mod ");
+                self.push(name);
+                self.push(";");
+            }
+            Statement::Use { tree, attributes, .. } => {
+                for attribute in attributes {
+                    self.push(&attribute.to_string());
+                    self.new_line();
+                }
+                self.push("use ");
+                self.push(&tree.to_string());
+                self.push(";");
+            }
+            Statement::ExternCrate {
+                name,
+                alias,
+                attributes,
+                ..
+            } => {
+                for attribute in attributes {
+                    self.push(&attribute.to_string());
+                    self.new_line();
+                }
+                self.push("extern crate ");
+                self.push(name);
+                if let Some(alias) = alias {
+                    self.push(" as ");
+                    self.push(alias);
+                }
+                self.push(";");
+            }
+            Statement::Static {
+                mutable,
+                name,
+                typ,
+                value,
+                attributes,
+                ..
+            } => {
+                for attribute in attributes {
+                    self.push(&attribute.to_string());
+                    self.new_line();
+                }
+                self.push(if *mutable { "var " } else { "const " });
+                self.push(name);
+                if let Some(typ) = typ {
+                    self.push(": ");
+                    self.push(&typ.to_string());
+                }
+                self.push(" = ");
+                self.write_value(value);
+                self.push(";");
+            }
+            Statement::MacroRules { name, rules, .. } => {
+                self.push("macro_rules! ");
+                self.push(name);
+                self.push(" {");
+                if rules.is_empty() {
+                    self.push("}");
+                    return;
+                }
+                self.indent += 1;
+                for rule in rules {
+                    self.new_line();
+                    self.push("(");
+                    self.write_tokens(&rule.pattern);
+                    self.push(") => {");
+                    if !rule.expansion.is_empty() {
+                        self.push(" ");
+                        self.write_tokens(&rule.expansion);
+                        self.push(" ");
+                    }
+                    self.push("};");
+                }
+                self.indent -= 1;
+                self.new_line();
+                self.push("}");
+            }
+        }
+    }
+
+    /// writes the tokens of a macro separated by a space, except before
+    /// commas and closing delimiters, and within the `$x:expr` of a pattern
+    fn write_tokens(&mut self, tokens: &[Token<'_>]) {
+        let is = |idx: Option<usize>, matches: fn(&Kind) -> bool| idx.is_some_and(|idx| matches(&tokens[idx].kind));
+        let ident = |kind: &Kind| matches!(kind, Kind::Value(Value::Ident(_)));
+        let dollar = |kind: &Kind| *kind == Kind::Op(Operator::Dollar);
+        let colon = |kind: &Kind| *kind == Kind::Op(Operator::Colon);
+
+        for (idx, token) in tokens.iter().enumerate() {
+            let before = |offset| idx.checked_sub(offset);
+            let glued = match token.kind {
+                Kind::Op(Operator::Comma | Operator::RightParen | Operator::RightBracket) => true,
+                // `$x`, `$x:` and `$x:expr`
+                Kind::Value(Value::Ident(_)) if is(before(1), dollar) => true,
+                Kind::Op(Operator::Colon) => is(before(1), ident) && is(before(2), dollar),
+                Kind::Value(Value::Ident(_)) => is(before(1), colon) && is(before(2), ident) && is(before(3), dollar),
+                _ => false,
+            };
+            if idx > 0 && !glued {
+                self.push(" ");
+            }
+            self.push(&token.kind.to_string());
+        }
+    }
+
+    /// writes generic parameters or arguments, like `<T, U: Show>`, if there
+    /// are any
+    fn write_angled(&mut self, items: &[impl std::fmt::Display]) {
+        if items.is_empty() {
+            return;
+        }
+        let items = items.iter().map(|item| item.to_string()).collect::<Vec<_>>();
+        self.push(&format!("<{}>", items.join(", ")));
+    }
+
+    /// writes the braced body of a struct or an impl, with every item on its
+    /// own line. items can be separated by a blank line
+    fn write_items(
+        &mut self,
+        items: &[Statement<'_>],
+        separated: bool,
+        location: Location,
+        write_item: impl Fn(&mut Self, &Statement<'_>),
+    ) {
+        self.push("{");
+
+        let has_comments = self
+            .comments
+            .last()
+            .is_some_and(|comment| comment.location.start_byte < location.end_byte);
+
+        if items.is_empty() && !has_comments {
+            self.push("}");
+            return;
+        }
+
+        self.indent += 1;
+        for (idx, item) in items.iter().enumerate() {
+            let item_location = item.location();
+            self.write_leading_comments(item_location.start_byte, idx == 0, separated && idx > 0);
+            self.new_line();
+            write_item(self, item);
+            self.write_trailing_comments(item_location.end_byte);
+        }
+
+        self.write_leading_comments(location.end_byte.saturating_sub(1), items.is_empty(), false);
+        self.indent -= 1;
+        self.new_line();
+        self.push("}");
+    }
+
+    fn write_block(
+        &mut self,
+        expressions: &[Expression<'_>],
+        tail: Option<&Expression<'_>>,
+        base_expr: bool,
+        location: Location,
+    ) {
+        let in_condition = std::mem::replace(&mut self.in_condition, false);
+        self.write_block_contents(expressions, tail, base_expr, location);
+        self.in_condition = in_condition;
+    }
+
+    fn write_block_contents(
+        &mut self,
+        expressions: &[Expression<'_>],
+        tail: Option<&Expression<'_>>,
+        base_expr: bool,
+        location: Location,
+    ) {
+        self.push("{");
+
+        let has_comments = self
+            .comments
+            .last()
+            .is_some_and(|comment| comment.location.start_byte < location.end_byte);
+
+        if expressions.is_empty() && tail.is_none() && !has_comments {
+            self.push("}");
+            return;
+        }
+
+        self.indent += 1;
+        for (idx, expression) in expressions.iter().chain(tail).enumerate() {
+            let expr_location = expression.location();
+            self.write_leading_comments(expr_location.start_byte, idx == 0, false);
+            self.new_line();
+            self.write_block_expression(expression, base_expr);
+            self.write_trailing_comments(expr_location.end_byte);
+        }
+
+        // comments right before the closing brace still belong to the block
+        self.write_leading_comments(
+            location.end_byte.saturating_sub(1),
+            expressions.is_empty() && tail.is_none(),
+            false,
+        );
+        self.indent -= 1;
+
+        self.new_line();
+        self.push("}");
+    }
+
+    /// writes an expression that appears directly inside of a block, which
+    /// is where semicolons are required
+    fn write_block_expression(&mut self, expression: &Expression<'_>, base_expr: bool) {
+        match expression {
+            Expression::Var {
+                mutable,
+                name,
+                typ,
+                value,
+                attributes,
+                ..
+            } => {
+                for attribute in attributes {
+                    self.push(&attribute.to_string());
+                    self.new_line();
+                }
+                self.push(if *mutable { "var " } else { "const " });
+                self.push(name);
+                if let Some(typ) = typ {
+                    self.push(": ");
+                    self.push(&typ.to_string());
+                }
+                self.push(" = ");
+                self.write_value(value);
+                self.push(";");
+            }
+            Expression::Assign { ident, value, .. } => {
+                self.write_expression(ident);
+                self.push(" = ");
+                self.write_value(value);
+                self.push(";");
+            }
+            Expression::CompoundAssign {
+                operator, ident, value, ..
+            } => {
+                self.write_expression(ident);
+                self.push(&format!(" {operator}= "));
+                self.write_value(value);
+                self.push(";");
+            }
+            Expression::Return { value, .. } => {
+                self.push("return ");
+                self.write_expression(value);
+                self.push(";");
+            }
+            Expression::Yield { value, .. } => {
+                self.push("yield");
+                if let Some(value) = value {
+                    self.push(" ");
+                    self.write_expression(value);
+                }
+                self.push(";");
+            }
+            Expression::FunCall { .. }
+            | Expression::Macro { .. }
+            | Expression::Assert { .. }
+            | Expression::RuntimeTypeOf { .. }
+            | Expression::ArrayLen { .. }
+            | Expression::Sizeof { .. }
+            | Expression::InlineAsm { .. }
+            | Expression::Await { .. }
+                if base_expr && is_call(expression) =>
+            {
+                self.write_expression(expression);
+                self.push(";");
+            }
+            _ => {
+                self.statement_start = base_expr;
+                self.write_expression(expression);
+            }
+        }
+    }
+
+    /// writes the arms of a `match` one per line, each ending in a comma
+    fn write_arms(&mut self, arms: &[MatchArm<'_>], location: Location) {
+        let has_comments = self
+            .comments
+            .last()
+            .is_some_and(|comment| comment.location.start_byte < location.end_byte);
+        if arms.is_empty() && !has_comments {
+            self.push(" {}");
+            return;
+        }
+
+        self.push(" {");
+        self.indent += 1;
+        for (idx, arm) in arms.iter().enumerate() {
+            self.write_leading_comments(arm.location.start_byte, idx == 0, false);
+            self.new_line();
+            self.write_pattern(&arm.pattern);
+            self.push(" => ");
+            self.write_expression(&arm.body);
+            self.push(",");
+            self.write_trailing_comments(arm.location.end_byte);
+        }
+        self.write_leading_comments(location.end_byte.saturating_sub(1), arms.is_empty(), false);
+        self.indent -= 1;
+        self.new_line();
+        self.push("}");
+    }
+
+    /// writes the steps of a `do` block, one per line, with a semicolon
+    /// after every step but the last one
+    fn write_do_steps(&mut self, steps: &[DoStep<'_>], location: Location) {
+        let has_comments = self
+            .comments
+            .last()
+            .is_some_and(|comment| comment.location.start_byte < location.end_byte);
+        if steps.is_empty() && !has_comments {
+            self.push(" {}");
+            return;
+        }
+
+        let in_condition = std::mem::replace(&mut self.in_condition, false);
+        self.push(" {");
+        self.indent += 1;
+        for (idx, step) in steps.iter().enumerate() {
+            let step_location = step.location();
+            self.write_leading_comments(step_location.start_byte, idx == 0, false);
+            self.new_line();
+            if let DoStep::Bind { name, .. } = step {
+                self.push(name);
+                self.push(" <- ");
+            }
+            match step.expr() {
+                // the `return` of a step leaves the semicolon to the block
+                Expression::Return { value, .. } => {
+                    self.push("return ");
+                    self.write_expression(value.as_ref());
+                }
+                expr => self.write_expression(expr),
+            }
+            if idx + 1 < steps.len() {
+                self.push(";");
+            }
+            self.write_trailing_comments(step_location.end_byte);
+        }
+        self.write_leading_comments(location.end_byte.saturating_sub(1), steps.is_empty(), false);
+        self.indent -= 1;
+        self.new_line();
+        self.push("}");
+        self.in_condition = in_condition;
+    }
+
+    fn write_pattern(&mut self, pattern: &Pattern<'_>) {
+        match pattern {
+            Pattern::Literal(literal) => self.write_expression(literal),
+            Pattern::Variant { path, kind, .. } => self.write_variant(&path.join("::"), kind, Self::write_pattern),
+            pattern => self.push(&pattern.to_string()),
+        }
+    }
+
+    /// writes an enum variant along with its values, like `Shape::Circle(r)`
+    /// or `Shape::Rect { w: 1, h: 2 }`
+    fn write_variant<T>(&mut self, path: &str, kind: &VariantKind<'_, T>, write: fn(&mut Self, &T)) {
+        self.push(path);
+        match kind {
+            VariantKind::Unit => {}
+            VariantKind::Tuple(elements) => {
+                self.push("(");
+                for (idx, element) in elements.iter().enumerate() {
+                    if idx > 0 {
+                        self.push(", ");
+                    }
+                    write(self, element);
+                }
+                self.push(")");
+            }
+            VariantKind::Struct(fields) if fields.is_empty() => self.push(" {}"),
+            VariantKind::Struct(fields) => {
+                self.push(" { ");
+                for (idx, field) in fields.iter().enumerate() {
+                    if idx > 0 {
+                        self.push(", ");
+                    }
+                    self.push(&field.name);
+                    self.push(": ");
+                    write(self, &field.value);
+                }
+                self.push(" }");
+            }
+        }
+    }
+
+    /// writes an enum variant built in an expression, wrapping its values one
+    /// per line when they don't fit in the current one
+    fn write_variant_expression(&mut self, path: &str, kind: &VariantKind<'_, Expression<'_>>) {
+        let fields = match kind {
+            VariantKind::Tuple(elements) => {
+                self.push(path);
+                self.write_argument_list(&elements.iter().collect::<Vec<_>>(), "(", ")");
+                return;
+            }
+            VariantKind::Struct(fields) => fields
+                .iter()
+                .map(|field| Some(format!("{}: {}", field.name, self.render_flat(&field.value)?)))
+                .collect::<Option<Vec<_>>>(),
+            VariantKind::Unit => None,
+        };
+
+        match fields {
+            // +5 accounts for the ` { ` and ` }` around the fields
+            Some(fields)
+                if !fields.is_empty()
+                    && self.column() + path.len() + fields.join(", ").len() + 5 > self.config.max_width =>
+            {
+                self.push(path);
+                self.push(" {");
+                self.indent += 1;
+                for field in fields {
+                    self.new_line();
+                    self.push(&field);
+                    self.push(",");
+                }
+                self.indent -= 1;
+                self.new_line();
+                self.push("}");
+            }
+            _ => self.write_variant(path, kind, Self::write_expression),
+        }
+    }
+
+    /// writes `lhs operator rhs` with every operand of the chain it starts on
+    /// its own line, after its operator. the chain is made of the operations
+    /// of the same binding power on the side the operator groups from, like
+    /// all of `a + b - c`
+    fn write_operation_chain(&mut self, operator: Operator, lhs: &Expression<'_>, rhs: &Expression<'_>) {
+        let mut first = lhs;
+        let mut rest = vec![(operator, rhs)];
+        let left_associative = !matches!(
+            ops::infix(operator).map(|info| info.associativity),
+            Some(Associativity::Right)
+        );
+        while let Expression::BinaryOp {
+            operator: inner,
+            lhs,
+            rhs,
+            ..
+        } = first
+        {
+            if !left_associative || precedence(*inner) != precedence(operator) {
+                break;
+            }
+            rest.push((*inner, rhs));
+            first = lhs;
+        }
+
+        self.write_operand(
+            rest.last().map_or(operator, |(operator, _)| *operator),
+            first,
+            Side::Lhs,
+        );
+        self.indent += 1;
+        for (operator, operand) in rest.into_iter().rev() {
+            self.new_line();
+            self.push(&operator.to_string());
+            self.push(" ");
+            self.write_operand(operator, operand, Side::Rhs);
+        }
+        self.indent -= 1;
+    }
+
+    /// whether `expression` fits in the current line when written flat. one
+    /// that can't be written flat fits as well, as wrapping it wouldn't help
+    fn fits_flat(&self, expression: &Expression<'_>) -> bool {
+        // flat renderings never wrap, which also keeps them from rendering
+        // each operand of a chain once more
+        if self.config.max_width == usize::MAX {
+            return true;
+        }
+        self.render_flat(expression)
+            .is_none_or(|flat| self.column() + flat.len() <= self.config.max_width)
+    }
+
+    /// the block of an `unsafe` or a `comptime` is parsed as a statement
+    /// only when the keyword starts one
+    fn write_keyword_block(&mut self, keyword: &str, body: &Expression<'_>, base_expr: bool) {
+        self.push(keyword);
+        self.push(" ");
+        match body {
+            Expression::Block {
+                expressions,
+                tail,
+                location,
+                ..
+            } => self.write_block(expressions, tail.as_deref(), base_expr, *location),
+            _ => self.write_expression(body),
+        }
+    }
+
+    /// right hand side of variables and assignments, where a block is parsed
+    /// without requiring semicolons after function calls
+    fn write_value(&mut self, value: &Expression<'_>) {
+        match value {
+            Expression::Block {
+                expressions,
+                tail,
+                location,
+                ..
+            } => self.write_block(expressions, tail.as_deref(), false, *location),
+            // a value starting with `{` is parsed as a block alone, so an
+            // expression that only begins with one has to be wrapped
+            _ if starts_with_block(value) => {
+                self.push("(");
+                self.write_expression(value);
+                self.push(")");
+            }
+            _ => self.write_expression(value),
+        }
+    }
+
+    fn write_expression(&mut self, expression: &Expression<'_>) {
+        match expression {
+            Expression::Var { .. }
+            | Expression::Assign { .. }
+            | Expression::CompoundAssign { .. }
+            | Expression::Return { .. }
+            | Expression::Yield { .. } => self.write_block_expression(expression, false),
+            Expression::Bool { value, .. } => self.push(&value.to_string()),
+            Expression::Ident { name, .. } => self.push(name),
+            Expression::Path { segments, .. } => self.push(&segments.join("::")),
+            Expression::UintLiteral { value, size, .. } => {
+                self.push(&value.to_string());
+                if let Some(size) = size {
+                    self.push(&size.to_string());
+                }
+            }
+            Expression::IntLiteral { value, size, .. } => {
+                self.push(&value.to_string());
+                if let Some(size) = size {
+                    self.push(&size.to_string());
+                }
+            }
+            Expression::FloatLiteral { value, size, .. } => {
+                let mut value = value.to_string();
+                // `2.0` displays as `2`, which would come back as an integer
+                if !value.contains('.') {
+                    value.push_str(".0");
+                }
+                self.push(&value);
+                if let Some(size) = size {
+                    self.push(&size.to_string());
+                }
+            }
+            // strings are written as they were, escapes and embedded
+            // expressions included
+            Expression::StringLiteral { location, .. }
+            | Expression::Interpolated { location, .. }
+            | Expression::Macro { location, .. } => match self.source.get(location.start_byte..location.end_byte) {
+                Some(text) if !text.is_empty() => self.push(text),
+                _ => self.write_unparsed(expression),
+            },
+            Expression::Block {
+                expressions,
+                tail,
+                location,
+                ..
+            } => self.write_block(expressions, tail.as_deref(), true, *location),
+            Expression::UnsafeBlock { body, .. } => self.write_keyword_block("unsafe", body, self.statement_start),
+            Expression::ComptimeBlock { body, .. } => self.write_keyword_block("comptime", body, self.statement_start),
+            Expression::Defer { body, .. } => self.write_keyword_block("defer", body, self.statement_start),
+            Expression::DoBlock { steps, location } => {
+                self.push("do");
+                self.write_do_steps(steps, *location);
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                self.push("if ");
+                let in_condition = std::mem::replace(&mut self.in_condition, true);
+                self.write_expression(condition);
+                self.in_condition = in_condition;
+                self.push(" ");
+                self.write_expression(truthy);
+                for branch in falsy {
+                    self.push(" else ");
+                    self.write_expression(branch);
+                }
+            }
+            Expression::Match {
+                scrutinee,
+                arms,
+                location,
+            } => {
+                self.push("match ");
+                let in_condition = std::mem::replace(&mut self.in_condition, true);
+                self.write_expression(scrutinee);
+                self.in_condition = false;
+                self.write_arms(arms, *location);
+                self.in_condition = in_condition;
+            }
+            Expression::FunCall { ident, arguments, .. } => {
+                self.write_postfix_target(ident, true);
+                match arguments.split_last() {
+                    Some((
+                        Expression::Lambda {
+                            parameters,
+                            return_type: None,
+                            body,
+                            ..
+                        },
+                        rest,
+                    )) if parameters.is_empty() && !self.in_condition => {
+                        self.write_arguments(&rest.iter().collect::<Vec<_>>());
+                        self.push(" ");
+                        self.write_expression(body);
+                    }
+                    _ => self.write_arguments(&arguments.iter().collect::<Vec<_>>()),
+                }
+            }
+            Expression::Assert { condition, message, .. } => {
+                self.push("assert");
+                let arguments = [condition.as_ref()].into_iter().chain(message.as_deref());
+                self.write_arguments(&arguments.collect::<Vec<_>>());
+            }
+            Expression::RuntimeTypeOf { expr, .. } => {
+                self.push("typeof!");
+                self.write_arguments(&[expr.as_ref()]);
+            }
+            Expression::ArrayLen { array, .. } => {
+                self.push("len!");
+                self.write_arguments(&[array.as_ref()]);
+            }
+            Expression::Splat { expr, .. } => {
+                self.push("splat!");
+                self.write_arguments(&[expr.as_ref()]);
+            }
+            Expression::Sizeof { typ, .. } => self.push(&format!("sizeof<{typ}>()")),
+            Expression::InlineAsm {
+                template,
+                outputs,
+                inputs,
+                clobbers,
+                ..
+            } => {
+                self.push(&format!("asm!(\"{template}\""));
+                // sections are only written up to the last one holding
+                // something, as every one after it can be left out
+                let sections = match (outputs.is_empty(), inputs.is_empty(), clobbers.is_empty()) {
+                    (_, _, false) => 3,
+                    (_, false, true) => 2,
+                    (false, true, true) => 1,
+                    (true, true, true) => 0,
+                };
+                for operands in [outputs, inputs].into_iter().take(sections) {
+                    self.push(" :");
+                    for (position, operand) in operands.iter().enumerate() {
+                        self.push(if position == 0 { " " } else { ", " });
+                        self.push(&format!("\"{}\"(", operand.constraint));
+                        self.write_expression(&operand.expr);
+                        self.push(")");
+                    }
+                }
+                if sections == 3 {
+                    self.push(" :");
+                    for (position, clobber) in clobbers.iter().enumerate() {
+                        self.push(if position == 0 { " " } else { ", " });
+                        self.push(&format!("\"{clobber}\""));
+                    }
+                }
+                self.push(")");
+            }
+            Expression::SafeCast { expr, typ, .. } => {
+                self.write_prefix_operand(expr);
+                self.push(" try_as ");
+                self.push(&typ.to_string());
+            }
+            Expression::Array { elements, .. } => {
+                self.write_delimited(&elements.iter().collect::<Vec<_>>(), "[", "]");
+            }
+            // the trailing comma is what makes a single element a tuple
+            Expression::Tuple { elements, .. } if elements.len() == 1 => {
+                let in_condition = std::mem::replace(&mut self.in_condition, false);
+                self.push("(");
+                self.write_expression(&elements[0]);
+                self.push(",)");
+                self.in_condition = in_condition;
+            }
+            Expression::Tuple { elements, .. } => {
+                self.write_delimited(&elements.iter().collect::<Vec<_>>(), "(", ")");
+            }
+            // the braces of a struct variant would be the body of an `if`
+            Expression::EnumVariant {
+                path,
+                kind: kind @ VariantKind::Struct(_),
+                ..
+            } if self.in_condition => {
+                self.in_condition = false;
+                self.push("(");
+                self.write_variant_expression(&path.join("::"), kind);
+                self.push(")");
+                self.in_condition = true;
+            }
+            Expression::EnumVariant { path, kind, .. } => {
+                let in_condition = std::mem::replace(&mut self.in_condition, false);
+                self.write_variant_expression(&path.join("::"), kind);
+                self.in_condition = in_condition;
+            }
+            Expression::Lambda {
+                parameters,
+                parameter_types,
+                return_type,
+                body,
+                ..
+            } => {
+                self.push("fun(");
+                for (idx, (parameter, typ)) in parameters.iter().zip(parameter_types).enumerate() {
+                    if idx > 0 {
+                        self.push(", ");
+                    }
+                    self.write_expression(parameter);
+                    if let Some(typ) = typ {
+                        self.push(": ");
+                        self.push(&typ.to_string());
+                    }
+                }
+                self.push(") ");
+                if let Some(return_type) = return_type {
+                    self.push("=> ");
+                    self.push(&return_type.to_string());
+                    self.push(" ");
+                }
+                self.write_expression(body);
+            }
+            Expression::Let { name, value, body, .. } => {
+                self.push("let ");
+                self.push(name);
+                self.push(" = ");
+                self.write_expression(value);
+                self.push(" in ");
+                self.write_expression(body);
+            }
+            Expression::Ref { mutable, expr, .. } => {
+                self.push(if *mutable { "&mut " } else { "&" });
+                self.write_prefix_operand(expr);
+            }
+            Expression::Deref { expr, .. } => {
+                self.push("*");
+                self.write_prefix_operand(expr);
+            }
+            Expression::Not { expr, .. } => {
+                self.push("!");
+                self.write_prefix_operand(expr);
+            }
+            Expression::Await { expr: target, .. }
+            | Expression::FieldAccess { target, .. }
+            | Expression::TupleAccess { tuple: target, .. }
+            | Expression::Try { expr: target, .. } => {
+                self.write_postfix_target(target, false);
+                match expression {
+                    Expression::FieldAccess { field, .. } => {
+                        self.push(".");
+                        self.push(field);
+                    }
+                    Expression::TupleAccess { index, .. } => self.push(&format!(".{index}")),
+                    Expression::Try { .. } => self.push("?"),
+                    _ => self.push(".await"),
+                }
+            }
+            Expression::Index { target, index, .. } => {
+                self.write_postfix_target(target, true);
+                let in_condition = std::mem::replace(&mut self.in_condition, false);
+                self.push("[");
+                self.write_expression(index);
+                self.push("]");
+                self.in_condition = in_condition;
+            }
+            Expression::BinaryOp { operator, lhs, rhs, .. } if !self.fits_flat(expression) => {
+                self.write_operation_chain(*operator, lhs, rhs);
+            }
+            Expression::BinaryOp { operator, lhs, rhs, .. } => {
+                self.write_operand(*operator, lhs, Side::Lhs);
+                self.push(" ");
+                self.push(&operator.to_string());
+                self.push(" ");
+                self.write_operand(*operator, rhs, Side::Rhs);
+            }
+            Expression::Elvis { condition, default, .. } => {
+                self.write_operand(Operator::Elvis, condition, Side::Lhs);
+                self.push(" ?: ");
+                self.write_operand(Operator::Elvis, default, Side::Rhs);
+            }
+        }
+    }
+
+    /// writes a string or a macro from the tree alone, for trees that have
+    /// no source to take their text from
+    fn write_unparsed(&mut self, expression: &Expression<'_>) {
+        match expression {
+            Expression::StringLiteral { value, .. } => {
+                self.push("\"");
+                self.push(&escape(value));
+                self.push("\"");
+            }
+            Expression::Interpolated {
+                literals, expressions, ..
+            } => {
+                self.push("\"");
+                for (idx, literal) in literals.iter().enumerate() {
+                    if let Expression::StringLiteral { value, .. } = literal {
+                        self.push(&escape(value));
+                    }
+                    if let Some(embedded) = expressions.get(idx) {
+                        let mut formatter = Formatter::new(self.source, vec![], self.config);
+                        formatter.write_expression(embedded);
+                        self.push("{");
+                        self.push(&formatter.output);
+                        self.push("}");
+                    }
+                }
+                self.push("\"");
+            }
+            Expression::Macro { name, tokens, .. } => {
+                self.push(name);
+                self.push("!(");
+                self.write_tokens(tokens);
+                self.push(")");
+            }
+            _ => self.write_expression(expression),
+        }
+    }
+
+    fn write_operand(&mut self, parent: Operator, operand: &Expression<'_>, side: Side) {
+        let operator = match operand {
+            Expression::BinaryOp { operator, .. } => Some(*operator),
+            Expression::Elvis { .. } => Some(Operator::Elvis),
+            _ => None,
+        };
+        let needs_parens = match (operand, operator) {
+            (_, Some(operator)) => {
+                // an operand of the same binding power only keeps its place
+                // without parenthesis on the side the parent groups from
+                let groups_from = match ops::infix(parent).map(|info| info.associativity) {
+                    Some(Associativity::Right) => Side::Rhs,
+                    _ => Side::Lhs,
+                };
+                match precedence(operator) == precedence(parent) {
+                    true => side != groups_from,
+                    false => precedence(operator) < precedence(parent),
+                }
+            }
+            // the body of a `let` would take whatever comes after it
+            (Expression::Let { .. }, _) => true,
+            // a `<` after a type opens its generic arguments
+            (Expression::SafeCast { .. }, _) => side == Side::Lhs && parent == Operator::Less,
+            _ => false,
+        };
+
+        if needs_parens {
+            self.push("(");
+            self.write_expression(operand);
+            self.push(")");
+        } else {
+            self.write_expression(operand);
+        }
+    }
+
+    fn write_prefix_operand(&mut self, operand: &Expression<'_>) {
+        match operand {
+            Expression::BinaryOp { .. }
+            | Expression::Elvis { .. }
+            | Expression::Let { .. }
+            | Expression::SafeCast { .. } => {
+                self.push("(");
+                self.write_expression(operand);
+                self.push(")");
+            }
+            _ => self.write_expression(operand),
+        }
+    }
+
+    /// writes what a postfix operator applies to. `delimited` is whether the
+    /// operator opens with a `(` or `[`, which would start a new expression
+    /// after anything ending in braces
+    fn write_postfix_target(&mut self, target: &Expression<'_>, delimited: bool) {
+        match target {
+            // a prefix operator would take the whole postfix chain, and the
+            // dot would be lexed as part of a number
+            Expression::BinaryOp { .. }
+            | Expression::UintLiteral { .. }
+            | Expression::IntLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::Elvis { .. }
+            | Expression::Let { .. }
+            | Expression::Ref { .. }
+            | Expression::Deref { .. }
+            | Expression::Not { .. }
+            | Expression::SafeCast { .. } => {
+                self.push("(");
+                self.write_expression(target);
+                self.push(")");
+            }
+            Expression::Block { .. }
+            | Expression::If { .. }
+            | Expression::Match { .. }
+            | Expression::UnsafeBlock { .. }
+            | Expression::ComptimeBlock { .. }
+            | Expression::Defer { .. }
+            | Expression::DoBlock { .. }
+            | Expression::Lambda { .. }
+                if delimited =>
+            {
+                let in_condition = std::mem::replace(&mut self.in_condition, false);
+                self.push("(");
+                self.write_expression(target);
+                self.push(")");
+                self.in_condition = in_condition;
+            }
+            _ => self.write_expression(target),
+        }
+    }
+
+    fn write_arguments(&mut self, arguments: &[&Expression<'_>]) {
+        self.write_delimited(arguments, "(", ")");
+    }
+
+    /// writes `arguments` between `open` and `close`, wrapping them one per
+    /// line when they don't fit in the current one
+    fn write_delimited(&mut self, arguments: &[&Expression<'_>], open: &str, close: &str) {
+        let in_condition = std::mem::replace(&mut self.in_condition, false);
+        self.write_argument_list(arguments, open, close);
+        self.in_condition = in_condition;
+    }
+
+    fn write_argument_list(&mut self, arguments: &[&Expression<'_>], open: &str, close: &str) {
+        let flat = arguments
+            .iter()
+            .map(|argument| self.render_flat(argument))
+            .collect::<Option<Vec<_>>>();
+
+        match flat {
+            Some(flat_arguments) => {
+                let flat = flat_arguments.join(", ");
+                // +2 accounts for the delimiters around the arguments
+                if flat_arguments.is_empty() || self.column() + flat.len() + 2 <= self.config.max_width {
+                    self.push(open);
+                    self.push(&flat);
+                    self.push(close);
+                    return;
+                }
+
+                // each argument is written at its own indentation, where it
+                // can wrap in turn when it still doesn't fit
+                self.push(open);
+                self.indent += 1;
+                for argument in arguments {
+                    self.new_line();
+                    self.write_expression(argument);
+                    self.push(",");
+                }
+                self.indent -= 1;
+                self.new_line();
+                self.push(close);
+            }
+            // some argument spans multiple lines (like a block), so wrapping
+            // would only make it harder to read
+            None => {
+                self.push(open);
+                for (idx, argument) in arguments.iter().enumerate() {
+                    if idx > 0 {
+                        self.push(", ");
+                    }
+                    self.write_expression(argument);
+                }
+                self.push(close);
+            }
+        }
+    }
+
+    /// renders an expression in a single line, or `None` if it can't fit in one
+    fn render_flat(&self, expression: &Expression<'_>) -> Option<String> {
+        let config = Config { max_width: usize::MAX };
+        let mut formatter = Formatter::new(self.source, vec![], &config);
+        formatter.write_expression(expression);
+
+        match formatter.output.contains('\n') {
+            true => None,
+            false => Some(formatter.output),
+        }
+    }
+}
+
+/// escapes `value` so it reads back the same inside of a string literal
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            '\0' => escaped.push_str("\\0"),
+            '\\' | '"' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '{' | '}' => {
+                escaped.push(c);
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let source = r#"
+            // the answer
+            fun   answer(a:i32,b : i32)=>i32{
+            const x:i32=a+b*(2 - 1); // trailing
+            var y = { const z = 1;
+            z };
+
+
+            /* before the if */
+            if x==y { return x; } else if y!=x { return y; } else { y = (x - 1) - (y - 2); }
+            calculate(first_argument_with_a_long_name, second_argument_with_a_long_name, third_argument_too);
+            x
+            }
+            // the end"#;
+
+        let formatted = format(source).unwrap();
+        let reformatted = format(&formatted).unwrap();
+
+        assert_eq!(formatted, reformatted);
+        insta::assert_snapshot!(formatted);
+    }
+
+    #[test]
+    fn wrapping_respects_max_width() {
+        let source = "fun main() { call(first, second, third); }";
+
+        let wide = format_with_config(source, &Config { max_width: 80 }).unwrap();
+        let narrow = format_with_config(source, &Config { max_width: 20 }).unwrap();
+
+        assert_eq!(wide, "fun main() {\n    call(first, second, third);\n}\n");
+        assert_eq!(
+            narrow,
+            "fun main() {\n    call(\n        first,\n        second,\n        third,\n    );\n}\n"
+        );
+    }
+
+    #[test]
+    fn operations_and_variants_wrap_at_max_width() {
+        let source = "fun main() {
+            var total = first_value + second_value * factor - third_value + fourth_value;
+            var shape = Shape::Rect { width: measure(left, right), height: top - bottom };
+            draw(Point::At(horizontal_position, vertical_position));
+        }";
+
+        let wide = format_with_config(source, &Config { max_width: 80 }).unwrap();
+        let narrow = format_with_config(source, &Config { max_width: 40 }).unwrap();
+
+        assert_eq!(
+            wide,
+            "fun main() {
+    var total = first_value + second_value * factor - third_value + fourth_value;
+    var shape = Shape::Rect {
+        width: measure(left, right),
+        height: top - bottom,
+    };
+    draw(Point::At(horizontal_position, vertical_position));
+}
+"
+        );
+        assert_eq!(
+            narrow,
+            "fun main() {
+    var total = first_value
+        + second_value * factor
+        - third_value
+        + fourth_value;
+    var shape = Shape::Rect {
+        width: measure(left, right),
+        height: top - bottom,
+    };
+    draw(
+        Point::At(
+            horizontal_position,
+            vertical_position,
+        ),
+    );
+}
+"
+        );
+        assert_eq!(format_with_config(&narrow, &Config { max_width: 40 }).unwrap(), narrow);
+    }
+
+    #[test]
+    fn arguments_wrap_in_turn_once_on_their_own_line() {
+        let source = "fun main() {
+            call_something(argument_one + argument_two + argument_three, another_argument_here);
+        }";
+
+        let narrow = format_with_config(source, &Config { max_width: 40 }).unwrap();
+        assert_eq!(
+            narrow,
+            "fun main() {
+    call_something(
+        argument_one
+            + argument_two
+            + argument_three,
+        another_argument_here,
+    );
+}
+"
+        );
+        assert!(narrow.lines().all(|line| line.len() <= 40), "{narrow}");
+        assert_eq!(format_with_config(&narrow, &Config { max_width: 40 }).unwrap(), narrow);
+    }
+
+    #[test]
+    fn syntax_errors_are_returned_untouched() {
+        let source = "fun main( {}";
+
+        let lexer = Lexer::new(source);
+        let parse_error = Parser::new(source, lexer).parse().unwrap_err();
+
+        assert_eq!(format(source).unwrap_err(), parse_error);
+    }
+}
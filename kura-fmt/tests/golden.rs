@@ -0,0 +1,32 @@
+use std::path::Path;
+
+// every `<name>.input.rmb` fixture must format to its `<name>.expected.rmb`
+// counterpart, and formatting the expected output must not change it
+#[test]
+fn golden_fixtures() {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut checked = 0;
+
+    for entry in std::fs::read_dir(&fixtures).unwrap() {
+        let path = entry.unwrap().path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some(fixture) = name.strip_suffix(".input.rmb") else {
+            continue;
+        };
+
+        let input = std::fs::read_to_string(&path).unwrap();
+        let expected = std::fs::read_to_string(fixtures.join(format!("{fixture}.expected.rmb"))).unwrap();
+
+        let formatted = kura_fmt::format(&input).unwrap_or_else(|e| panic!("{fixture}: {e}"));
+        assert_eq!(formatted, expected, "{fixture} did not format as expected");
+
+        let reformatted = kura_fmt::format(&expected).unwrap_or_else(|e| panic!("{fixture}: {e}"));
+        assert_eq!(reformatted, expected, "{fixture} is not idempotent");
+
+        checked += 1;
+    }
+
+    assert!(checked > 0, "no fixtures found in {}", fixtures.display());
+}
@@ -0,0 +1,1055 @@
+use std::borrow::Cow;
+
+use kura_fmt::print;
+use kura_lexer::token::{FloatSizes, IntSizes, Kind, Location, Operator, Token, UIntSizes, Value};
+use kura_lexer::Lexer;
+use kura_parser::sexpr::statements_to_sexpr;
+use kura_parser::{
+    AsmOperand, Attribute, DoStep, Expression, GenericParam, Lifetime, LifetimeParam, MacroRule, MatchArm, Parser,
+    Pattern, Statement, TypeExpr, UseTree, VariantField, VariantKind, WherePredicate,
+};
+use proptest::prelude::*;
+
+// random trees are printed, parsed back and compared, which checks the
+// printer and the parser against each other. the generator only builds trees
+// the grammar can express, so a failure is a bug in one of them.
+//
+// runs proptest's default of 256 cases, set `PROPTEST_CASES` for longer runs
+
+type Expr = Expression<'static>;
+
+const KEYWORDS: &[&str] = &[
+    "var",
+    "const",
+    "match",
+    "if",
+    "else",
+    "fun",
+    "struct",
+    "enum",
+    "impl",
+    "for",
+    "let",
+    "in",
+    "return",
+    "yield",
+    "comptime",
+    "do",
+    "defer",
+    "where",
+    "true",
+    "false",
+    "await",
+    "unsafe",
+    "mut",
+    "macro_rules",
+    "assert",
+    "mod",
+    "extern",
+    "crate",
+    "use",
+    "Self",
+    "sizeof",
+    "try_as",
+];
+
+fn at() -> Location {
+    Location::default()
+}
+
+fn name() -> impl Strategy<Value = Cow<'static, str>> {
+    "[a-z][a-z0-9_]{0,5}"
+        .prop_filter("keywords are not names", |name| !KEYWORDS.contains(&name.as_str()))
+        .prop_map(Cow::Owned)
+}
+
+fn ident() -> impl Strategy<Value = Expr> {
+    name().prop_map(|name| Expression::Ident { name, location: at() })
+}
+
+/// the path of an enum variant, like `Color::Red`. `modules` tells how many
+/// names come before the variant
+fn variant_path(modules: std::ops::Range<usize>) -> impl Strategy<Value = Vec<Cow<'static, str>>> {
+    (prop::collection::vec(name(), modules), "[A-Z][a-z]{1,5}").prop_map(|(mut path, variant)| {
+        path.push(Cow::Owned(variant));
+        path
+    })
+}
+
+fn variant_kind<T: std::fmt::Debug + Clone>(
+    inner: impl Strategy<Value = T> + Clone + 'static,
+) -> impl Strategy<Value = VariantKind<'static, T>> {
+    let field = (name(), inner.clone()).prop_map(|(name, value)| VariantField {
+        name,
+        value,
+        location: at(),
+    });
+    prop_oneof![
+        Just(VariantKind::Unit),
+        prop::collection::vec(inner, 0..3).prop_map(VariantKind::Tuple),
+        prop::collection::vec(field, 0..3).prop_map(VariantKind::Struct),
+    ]
+}
+
+fn type_name() -> impl Strategy<Value = Cow<'static, str>> {
+    prop_oneof![
+        Just("i32"),
+        Just("u64"),
+        Just("f64"),
+        Just("bool"),
+        Just("String"),
+        Just("Point"),
+        Just("std::io::File"),
+    ]
+    .prop_map(Cow::Borrowed)
+}
+
+fn typ() -> BoxedStrategy<TypeExpr<'static>> {
+    let named = type_name().prop_map(|name| TypeExpr::Named { name, location: at() });
+    let leaf = prop_oneof![
+        6 => named,
+        1 => Just(TypeExpr::Never { location: at() }),
+    ];
+
+    leaf.prop_recursive(3, 16, 3, |inner| {
+        let types = prop::collection::vec(inner.clone(), 0..3);
+        prop_oneof![
+            (types.clone(), prop::option::of(inner.clone())).prop_map(|(parameters, returns)| TypeExpr::Fn {
+                parameters,
+                returns: returns.map(Box::new),
+                location: at(),
+            }),
+            (inner.clone(), 0..64u64).prop_map(|(element, length)| TypeExpr::Array {
+                element: Box::new(element),
+                length,
+                location: at(),
+            }),
+            // a single type in parenthesis is that type, so a tuple of one
+            // element is printed with a trailing comma
+            types.prop_map(|elements| TypeExpr::Tuple {
+                elements,
+                location: at(),
+            }),
+            (any::<bool>(), inner.clone()).prop_map(|(mutable, pointee)| TypeExpr::Ptr {
+                mutable,
+                pointee: Box::new(pointee),
+                location: at(),
+            }),
+            (type_name(), prop::collection::vec(inner.clone(), 1..3)).prop_map(|(name, arguments)| {
+                TypeExpr::Generic {
+                    name,
+                    arguments,
+                    location: at(),
+                }
+            }),
+            inner.clone().prop_map(|bound| TypeExpr::ImplTrait {
+                bound: Box::new(bound),
+                location: at(),
+            }),
+            (any::<bool>(), prop::option::of(lifetime()), inner).prop_map(|(mutable, lifetime, referent)| {
+                TypeExpr::Ref {
+                    mutable,
+                    lifetime: lifetime.map_or(Lifetime::Elided, Lifetime::Named),
+                    referent: Box::new(referent),
+                    location: at(),
+                }
+            }),
+        ]
+    })
+    .boxed()
+}
+
+fn lifetime() -> impl Strategy<Value = Cow<'static, str>> {
+    prop_oneof![Just("a"), Just("b"), Just("static")].prop_map(Cow::Borrowed)
+}
+
+/// a bound of a where predicate, which can be written like the signature
+/// of a function or take a lifetime
+fn bound() -> impl Strategy<Value = TypeExpr<'static>> {
+    prop_oneof![
+        typ(),
+        (
+            prop_oneof![Just("Fn"), Just("FnMut")],
+            prop::collection::vec(typ(), 0..3),
+            prop::option::of(typ()),
+        )
+            .prop_map(|(name, parameters, returns)| TypeExpr::FnTrait {
+                name: Cow::Borrowed(name),
+                parameters,
+                returns: returns.map(Box::new),
+                location: at(),
+            }),
+        (type_name(), lifetime()).prop_map(|(name, lifetime)| TypeExpr::Generic {
+            name,
+            arguments: vec![TypeExpr::Lifetime {
+                name: lifetime,
+                location: at(),
+            }],
+            location: at(),
+        }),
+    ]
+}
+
+fn where_clause() -> impl Strategy<Value = Vec<WherePredicate<'static>>> {
+    let lifetime_param = lifetime().prop_map(|name| LifetimeParam { name, location: at() });
+    let predicate = (
+        prop::collection::vec(lifetime_param, 0..3),
+        typ(),
+        prop::collection::vec(bound(), 1..3),
+    )
+        .prop_map(|(lifetimes, bounded, bounds)| WherePredicate {
+            lifetimes,
+            bounded,
+            bounds,
+            location: at(),
+        });
+    prop::collection::vec(predicate, 0..3)
+}
+
+fn string() -> impl Strategy<Value = Cow<'static, str>> {
+    r#"[a-zA-Z0-9 {}"\\\n\t]{0,8}"#.prop_map(Cow::Owned)
+}
+
+fn literal() -> impl Strategy<Value = Expr> {
+    let uint_size = prop::option::of(prop_oneof![
+        Just(UIntSizes::U8),
+        Just(UIntSizes::U16),
+        Just(UIntSizes::U32),
+        Just(UIntSizes::U64),
+        Just(UIntSizes::Usize),
+    ]);
+    let int_size = prop::option::of(prop_oneof![
+        Just(IntSizes::I8),
+        Just(IntSizes::I16),
+        Just(IntSizes::I32),
+        Just(IntSizes::I64),
+        Just(IntSizes::Isize),
+    ]);
+    let float_size = prop::option::of(prop_oneof![
+        Just(FloatSizes::F8),
+        Just(FloatSizes::F16),
+        Just(FloatSizes::F32),
+        Just(FloatSizes::F64),
+    ]);
+
+    prop_oneof![
+        // a sized literal is rejected unless it fits its size, so values are
+        // shifted down until they do
+        (any::<u64>(), uint_size).prop_map(|(mut value, size)| {
+            while size.as_ref().is_some_and(|size| !size.fits(value)) {
+                value >>= 8;
+            }
+            Expression::UintLiteral {
+                value,
+                size,
+                location: at(),
+            }
+        }),
+        // non-negative numbers are always read back as unsigned
+        (i64::MIN..0, int_size).prop_map(|(mut value, size)| {
+            // shifting keeps the sign, so the value stays negative
+            while size.as_ref().is_some_and(|size| !size.fits(value)) {
+                value >>= 8;
+            }
+            Expression::IntLiteral {
+                value,
+                size,
+                location: at(),
+            }
+        }),
+        // eighths are exact in binary, so they print and parse back as is
+        (0u32..100_000, float_size).prop_map(|(value, size)| Expression::FloatLiteral {
+            value: f64::from(value) / 8.0,
+            size,
+            location: at(),
+        }),
+        any::<bool>().prop_map(|value| Expression::Bool { value, location: at() }),
+        string().prop_map(|value| Expression::StringLiteral { value, location: at() }),
+    ]
+}
+
+fn leaf() -> impl Strategy<Value = Expr> {
+    prop_oneof![
+        3 => ident(),
+        1 => prop::collection::vec(name(), 2..4).prop_map(|segments| Expression::Path { segments, location: at() }),
+        3 => literal(),
+        1 => interpolated(),
+        1 => macro_call(),
+    ]
+}
+
+/// a string embedding simple expressions, which keeps quotes out of it
+fn interpolated() -> impl Strategy<Value = Expr> {
+    let embedded = prop_oneof![
+        ident(),
+        (ident(), ident()).prop_map(|(lhs, rhs)| binary(Operator::Plus, lhs, rhs)),
+    ];
+
+    prop::collection::vec((string(), embedded), 1..3)
+        .prop_flat_map(|parts| (Just(parts), string()))
+        .prop_map(|(parts, last)| {
+            let (literals, expressions): (Vec<_>, Vec<_>) = parts.into_iter().unzip();
+            let literals = literals
+                .into_iter()
+                .chain([last])
+                .map(|value| Expression::StringLiteral { value, location: at() })
+                .collect();
+            Expression::Interpolated {
+                literals,
+                expressions,
+                location: at(),
+            }
+        })
+}
+
+fn macro_call() -> impl Strategy<Value = Expr> {
+    let token = prop_oneof![
+        name().prop_map(|name| Kind::Value(Value::Ident(name))),
+        Just(Kind::Op(Operator::Comma)),
+        Just(Kind::Op(Operator::Plus)),
+    ];
+
+    let name = name().prop_filter("builtin macros have their own grammar", |name| {
+        !["typeof", "len", "asm", "splat"].contains(&name.as_ref())
+    });
+    (name, prop::collection::vec(token, 0..4)).prop_map(|(name, kinds)| Expression::Macro {
+        name,
+        tokens: kinds.into_iter().map(|kind| Token::new(kind, at())).collect(),
+        location: at(),
+    })
+}
+
+/// inline assembly is only accepted inside of an unsafe block, so it is
+/// generated as the statement of one
+fn inline_asm(inner: impl Strategy<Value = Expr> + Clone + 'static) -> impl Strategy<Value = Expr> {
+    // strings in inline assembly are taken as written, so nothing is escaped
+    let text = || "[a-z0-9 ,%=]{0,8}".prop_map(Cow::Owned);
+    let operands = prop::collection::vec(
+        (text(), inner).prop_map(|(constraint, expr)| AsmOperand {
+            constraint,
+            expr,
+            location: at(),
+        }),
+        0..3,
+    );
+
+    (
+        text(),
+        operands.clone(),
+        operands,
+        prop::collection::vec(text(), 0..3),
+        ident(),
+    )
+        .prop_map(|(template, outputs, inputs, clobbers, tail)| {
+            let asm = Expression::InlineAsm {
+                template,
+                outputs,
+                inputs,
+                clobbers,
+                location: at(),
+            };
+            Expression::UnsafeBlock {
+                body: Box::new(Expression::Block {
+                    expressions: vec![asm],
+                    tail: Some(Box::new(tail)),
+                    reachable: None,
+                    location: at(),
+                }),
+                location: at(),
+            }
+        })
+}
+
+fn macro_rule() -> impl Strategy<Value = MacroRule<'static>> {
+    let token = prop_oneof![
+        name().prop_map(|name| Kind::Value(Value::Ident(name))),
+        Just(Kind::Op(Operator::Dollar)),
+        Just(Kind::Op(Operator::Colon)),
+        Just(Kind::Op(Operator::Comma)),
+        Just(Kind::Op(Operator::Plus)),
+    ];
+    let tokens = prop::collection::vec(token, 0..5)
+        .prop_map(|kinds| kinds.into_iter().map(|kind| Token::new(kind, at())).collect::<Vec<_>>());
+
+    (tokens.clone(), tokens).prop_map(|(pattern, expansion)| MacroRule {
+        pattern,
+        expansion,
+        location: at(),
+    })
+}
+
+fn binary(operator: Operator, lhs: Expr, rhs: Expr) -> Expr {
+    Expression::BinaryOp {
+        operator,
+        lhs: Box::new(lhs),
+        rhs: Box::new(rhs),
+        typ: None,
+        location: at(),
+    }
+}
+
+fn call(callee: Expr, arguments: Vec<Expr>) -> Expr {
+    Expression::FunCall {
+        ident: Box::new(callee),
+        arguments,
+        location: at(),
+        tail_call: false,
+    }
+}
+
+/// an argument of a call, the only place a `splat!` can be in
+fn argument(inner: impl Strategy<Value = Expr> + Clone + 'static) -> impl Strategy<Value = Expr> {
+    prop_oneof![
+        4 => inner.clone(),
+        1 => inner.prop_map(|expr| Expression::Splat { expr: Box::new(expr), location: at() }),
+    ]
+}
+
+fn callee() -> impl Strategy<Value = Expr> {
+    prop_oneof![
+        3 => ident(),
+        1 => prop::collection::vec(name(), 2..4).prop_map(|segments| Expression::Path { segments, location: at() }),
+    ]
+}
+
+fn is_postfix_target(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expression::Ident { .. }
+            | Expression::FunCall { .. }
+            | Expression::FieldAccess { .. }
+            | Expression::TupleAccess { .. }
+            | Expression::Index { .. }
+            | Expression::Try { .. }
+            | Expression::Await { .. }
+            | Expression::RuntimeTypeOf { .. }
+            | Expression::ArrayLen { .. }
+            | Expression::Sizeof { .. }
+    )
+}
+
+/// an expression that produces a value, nesting at most a few levels deep
+fn value() -> BoxedStrategy<Expr> {
+    leaf().prop_recursive(4, 48, 3, |inner| {
+        let operator = prop_oneof![
+            Just(Operator::Plus),
+            Just(Operator::Minus),
+            Just(Operator::Star),
+            Just(Operator::Slash),
+            Just(Operator::And),
+            Just(Operator::EqualEqual),
+            Just(Operator::NotEqual),
+            Just(Operator::Or),
+            Just(Operator::Less),
+            Just(Operator::LessEqual),
+            Just(Operator::Greater),
+            Just(Operator::GreaterEqual),
+        ];
+        let target = inner.clone().prop_filter("only names, calls and postfixes take a postfix", is_postfix_target);
+
+        prop_oneof![
+            3 => (operator, inner.clone(), inner.clone()).prop_map(|(operator, lhs, rhs)| binary(operator, lhs, rhs)),
+            1 => (inner.clone(), inner.clone()).prop_map(|(condition, default)| Expression::Elvis {
+                condition: Box::new(condition),
+                default: Box::new(default),
+                location: at(),
+            }),
+            2 => (callee(), prop::collection::vec(argument(inner.clone()), 0..3)).prop_map(|(callee, arguments)| call(callee, arguments)),
+            1 => (target.clone(), name()).prop_map(|(target, field)| Expression::FieldAccess {
+                target: Box::new(target),
+                field,
+                location: at(),
+            }),
+            1 => (target.clone(), 0..4u32).prop_map(|(tuple, index)| Expression::TupleAccess {
+                tuple: Box::new(tuple),
+                index,
+                location: at(),
+            }),
+            1 => (target.clone(), inner.clone()).prop_map(|(target, index)| Expression::Index {
+                target: Box::new(target),
+                index: Box::new(index),
+                location: at(),
+            }),
+            1 => target.clone().prop_map(|expr| Expression::Try { expr: Box::new(expr), location: at() }),
+            1 => target.prop_map(|expr| Expression::Await { expr: Box::new(expr), location: at() }),
+            1 => inner.clone().prop_map(|expr| Expression::RuntimeTypeOf { expr: Box::new(expr), location: at() }),
+            1 => prop::collection::vec(inner.clone(), 0..3).prop_map(|elements| Expression::Array { elements, location: at() }),
+            1 => prop::collection::vec(inner.clone(), 0..4).prop_map(|elements| Expression::Tuple { elements, location: at() }),
+            1 => inner.clone().prop_map(|array| Expression::ArrayLen { array: Box::new(array), location: at() }),
+            1 => typ().prop_map(|typ| Expression::Sizeof { typ: Box::new(typ), location: at() }),
+            1 => (inner.clone(), typ()).prop_map(|(expr, typ)| Expression::SafeCast {
+                expr: Box::new(expr),
+                typ: Box::new(typ),
+                location: at(),
+            }),
+            1 => if_expression(inner.clone()),
+            1 => match_expression(inner.clone()),
+            1 => (
+                prop::collection::vec((ident(), prop::option::of(typ())), 0..3),
+                prop::option::of(typ()),
+                block(inner.clone()),
+            )
+                .prop_map(|(parameters, return_type, body)| {
+                    let (parameters, parameter_types) = parameters.into_iter().unzip();
+                    Expression::Lambda {
+                        parameters,
+                        parameter_types,
+                        return_type: return_type.map(Box::new),
+                        body: Box::new(body),
+                        location: at(),
+                    }
+                }),
+            1 => (name(), inner.clone(), inner.clone()).prop_map(|(name, value, body)| Expression::Let {
+                name,
+                value: Box::new(value),
+                body: Box::new(body),
+                location: at(),
+            }),
+            1 => (variant_path(1..3), variant_kind(inner.clone())).prop_map(|(path, kind)| Expression::EnumVariant {
+                path,
+                kind,
+                location: at(),
+            }),
+            1 => block(inner.clone()).prop_map(|body| Expression::UnsafeBlock {
+                body: Box::new(body),
+                location: at(),
+            }),
+            1 => inline_asm(inner.clone()),
+            1 => block(inner.clone()).prop_map(|body| Expression::ComptimeBlock {
+                body: Box::new(body),
+                location: at(),
+            }),
+            1 => block(inner.clone()).prop_map(|body| Expression::Defer {
+                body: Box::new(body),
+                location: at(),
+            }),
+            1 => do_block(inner.clone()),
+            1 => (any::<bool>(), inner.clone()).prop_map(|(mutable, expr)| Expression::Ref {
+                mutable,
+                expr: Box::new(expr),
+                location: at(),
+            }),
+            1 => inner.clone().prop_map(|expr| Expression::Deref {
+                expr: Box::new(expr),
+                location: at(),
+            }),
+            1 => inner.clone().prop_map(|expr| Expression::Not {
+                expr: Box::new(expr),
+                location: at(),
+            }),
+            1 => block(inner),
+        ]
+    })
+    .boxed()
+}
+
+fn if_expression(inner: impl Strategy<Value = Expr> + Clone + 'static) -> impl Strategy<Value = Expr> {
+    let falsy = prop_oneof![
+        Just(vec![]),
+        block(inner.clone()).prop_map(|block| vec![block]),
+        (inner.clone(), block(inner.clone())).prop_map(|(condition, truthy)| vec![Expression::If {
+            condition: Box::new(condition),
+            truthy: Box::new(truthy),
+            falsy: vec![],
+            location: at(),
+        }]),
+    ];
+
+    (inner.clone(), block(inner), falsy).prop_map(|(condition, truthy, falsy)| Expression::If {
+        condition: Box::new(condition),
+        truthy: Box::new(truthy),
+        falsy,
+        location: at(),
+    })
+}
+
+fn do_block(inner: impl Strategy<Value = Expr> + Clone + 'static) -> impl Strategy<Value = Expr> {
+    let step = prop_oneof![
+        (name(), inner.clone()).prop_map(|(name, expr)| DoStep::Bind {
+            name,
+            expr,
+            location: at(),
+        }),
+        inner.clone().prop_map(DoStep::Seq),
+        inner.prop_map(|value| DoStep::Seq(Expression::Return {
+            value: Box::new(value),
+            location: at(),
+        })),
+    ];
+    prop::collection::vec(step, 0..4).prop_map(|steps| Expression::DoBlock { steps, location: at() })
+}
+
+fn match_expression(inner: impl Strategy<Value = Expr> + Clone + 'static) -> impl Strategy<Value = Expr> {
+    let pattern = prop_oneof![
+        Just(Pattern::Wildcard { location: at() }),
+        literal().prop_map(|literal| Pattern::Literal(Box::new(literal))),
+        name().prop_map(|name| Pattern::Binding { name, location: at() }),
+        prop::collection::vec(name(), 2..4).prop_map(|segments| Pattern::Path {
+            segments,
+            location: at()
+        }),
+    ]
+    .prop_recursive(2, 8, 3, |inner| {
+        // a single name without any values is a binding
+        (variant_path(0..2), variant_kind(inner))
+            .prop_filter("a variant of a single name takes values", |(path, kind)| {
+                path.len() > 1 || !matches!(kind, VariantKind::Unit)
+            })
+            .prop_map(|(path, kind)| Pattern::Variant {
+                path,
+                kind,
+                location: at(),
+            })
+    });
+    let arm = (pattern, inner.clone()).prop_map(|(pattern, body)| MatchArm {
+        pattern,
+        body,
+        location: at(),
+    });
+
+    (inner, prop::collection::vec(arm, 0..3)).prop_map(|(scrutinee, arms)| Expression::Match {
+        scrutinee: Box::new(scrutinee),
+        arms,
+        location: at(),
+    })
+}
+
+/// something that is only allowed directly inside of a block, where it owns
+/// its semicolon
+fn block_statement(inner: impl Strategy<Value = Expr> + Clone + 'static) -> impl Strategy<Value = Expr> {
+    let attributes = prop::collection::vec(
+        prop_oneof![Just("shadow"), Just("unused")].prop_map(|lint| Attribute {
+            name: Cow::Borrowed("allow"),
+            arguments: vec![Cow::Borrowed(lint)],
+            values: vec![],
+            location: at(),
+        }),
+        0..2,
+    );
+
+    prop_oneof![
+        (
+            any::<bool>(),
+            name(),
+            prop::option::of(typ()),
+            inner.clone(),
+            attributes
+        )
+            .prop_map(|(mutable, name, typ, value, attributes)| Expression::Var {
+                mutable,
+                name,
+                typ: typ.map(Box::new),
+                value: Box::new(value),
+                attributes,
+                location: at(),
+            }),
+        (ident(), inner.clone()).prop_map(|(ident, value)| Expression::Assign {
+            ident: Box::new(ident),
+            value: Box::new(value),
+            location: at(),
+        }),
+        (
+            prop_oneof![
+                Just(Operator::Plus),
+                Just(Operator::Minus),
+                Just(Operator::Star),
+                Just(Operator::Slash),
+            ],
+            ident(),
+            inner.clone()
+        )
+            .prop_map(|(operator, ident, value)| Expression::CompoundAssign {
+                operator,
+                ident: Box::new(ident),
+                value: Box::new(value),
+                location: at(),
+            }),
+        inner.clone().prop_map(|value| Expression::Return {
+            value: Box::new(value),
+            location: at(),
+        }),
+        prop::option::of(inner.clone()).prop_map(|value| Expression::Yield {
+            value: value.map(Box::new),
+            location: at(),
+        }),
+        (inner.clone(), prop::option::of(inner.clone())).prop_map(|(condition, message)| Expression::Assert {
+            condition: Box::new(condition),
+            message: message.map(Box::new),
+            location: at(),
+        }),
+        (callee(), prop::collection::vec(argument(inner), 0..3), any::<bool>()).prop_map(
+            |(callee, arguments, awaited)| {
+                let call = call(callee, arguments);
+                match awaited {
+                    true => Expression::Await {
+                        expr: Box::new(call),
+                        location: at(),
+                    },
+                    false => call,
+                }
+            }
+        ),
+    ]
+}
+
+/// whether `expr` is printed starting with `token`
+fn starts_with(expr: &Expr, token: fn(&Expr) -> bool) -> bool {
+    match expr {
+        Expression::BinaryOp { lhs, .. }
+        | Expression::Elvis { condition: lhs, .. }
+        | Expression::SafeCast { expr: lhs, .. } => starts_with(lhs, token),
+        Expression::FieldAccess { target, .. }
+        | Expression::TupleAccess { tuple: target, .. }
+        | Expression::Index { target, .. }
+        | Expression::Try { expr: target, .. }
+        | Expression::Await { expr: target, .. } => starts_with(target, token),
+        expr => token(expr),
+    }
+}
+
+fn is_block(expr: &Expr) -> bool {
+    matches!(expr, Expression::Block { .. })
+}
+
+fn is_prefix(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expression::Ref { .. } | Expression::Deref { .. } | Expression::Not { .. }
+    )
+}
+
+/// whether `expr` is printed starting with a `(` or `[`, including when
+/// what it starts with is wrapped in parenthesis
+fn starts_with_delimiter(expr: &Expr) -> bool {
+    let parenthesized = |expr: &Expr| {
+        matches!(
+            expr,
+            Expression::BinaryOp { .. }
+                | Expression::Elvis { .. }
+                | Expression::Let { .. }
+                | Expression::SafeCast { .. }
+                | Expression::Ref { .. }
+                | Expression::Deref { .. }
+                | Expression::Not { .. }
+                | Expression::UintLiteral { .. }
+                | Expression::IntLiteral { .. }
+                | Expression::FloatLiteral { .. }
+        )
+    };
+    match expr {
+        Expression::Array { .. } | Expression::Tuple { .. } => true,
+        Expression::BinaryOp { lhs: first, .. }
+        | Expression::Elvis { condition: first, .. }
+        | Expression::SafeCast { expr: first, .. }
+        | Expression::FieldAccess { target: first, .. }
+        | Expression::TupleAccess { tuple: first, .. }
+        | Expression::Index { target: first, .. }
+        | Expression::Try { expr: first, .. }
+        | Expression::Await { expr: first, .. } => parenthesized(first) || starts_with_delimiter(first),
+        _ => false,
+    }
+}
+
+/// statements followed by an optional value. a value can't be followed by
+/// anything, as the next expression could be read as part of it. a call
+/// can't be followed by a block, which would be read as its trailing closure,
+/// and neither a call, an await, an assert, a `typeof!` nor a `len!` can be followed by a `*` or `&&`, which
+/// would be read as an operator taking them, nor by a `(` or `[`, which
+/// would be read as calling or indexing them
+fn body(inner: impl Strategy<Value = Expr> + Clone + 'static) -> impl Strategy<Value = Vec<Expr>> {
+    (
+        prop::collection::vec(block_statement(inner.clone()), 0..3),
+        prop::option::of(inner),
+    )
+        .prop_map(|(mut statements, tail)| {
+            let ambiguous = match (statements.last(), &tail) {
+                (Some(Expression::FunCall { .. }), Some(tail)) => {
+                    starts_with(tail, is_block) || starts_with(tail, is_prefix) || starts_with_delimiter(tail)
+                }
+                (
+                    Some(
+                        Expression::Await { .. }
+                        | Expression::Assert { .. }
+                        | Expression::RuntimeTypeOf { .. }
+                        | Expression::ArrayLen { .. }
+                        | Expression::Sizeof { .. }
+                        | Expression::InlineAsm { .. },
+                    ),
+                    Some(tail),
+                ) => starts_with(tail, is_prefix) || starts_with_delimiter(tail),
+                _ => false,
+            };
+            if !ambiguous {
+                statements.extend(tail);
+            }
+            statements
+        })
+}
+
+/// whether `expr` is parsed along with a semicolon at the base of a block,
+/// but not in a block that is a value
+fn is_call(expr: &Expr) -> bool {
+    match expr {
+        Expression::Await { expr, .. } => is_call(expr),
+        expr => matches!(
+            expr,
+            Expression::FunCall { .. }
+                | Expression::Macro { .. }
+                | Expression::Assert { .. }
+                | Expression::RuntimeTypeOf { .. }
+                | Expression::ArrayLen { .. }
+                | Expression::Sizeof { .. }
+                | Expression::InlineAsm { .. }
+        ),
+    }
+}
+
+/// a block whose last expression is its tail unless it ends in a semicolon.
+/// whether a call is the tail depends on where the block is, so blocks never
+/// end in one
+fn block(inner: impl Strategy<Value = Expr> + Clone + 'static) -> impl Strategy<Value = Expr> {
+    body(inner).prop_map(|mut expressions| {
+        while expressions.last().is_some_and(is_call) {
+            expressions.pop();
+        }
+        let tail = expressions.pop_if(|last| {
+            !matches!(
+                last,
+                Expression::Var { .. }
+                    | Expression::Assign { .. }
+                    | Expression::CompoundAssign { .. }
+                    | Expression::Return { .. }
+                    | Expression::Yield { .. }
+            )
+        });
+        Expression::Block {
+            expressions,
+            tail: tail.map(Box::new),
+            reachable: None,
+            location: at(),
+        }
+    })
+}
+
+fn function() -> impl Strategy<Value = Statement<'static>> {
+    let argument = (name(), typ()).prop_map(|(name, arg_type)| Statement::FunArgument {
+        name,
+        arg_type: Box::new(arg_type),
+        location: at(),
+    });
+
+    let attribute = prop_oneof![
+        Just(("test", None)),
+        Just(("should_panic", None)),
+        Just(("should_panic", Some("overflow"))),
+    ]
+    .prop_map(|(name, expected)| Attribute {
+        name: Cow::Borrowed(name),
+        arguments: vec![],
+        values: expected
+            .map(|expected| (Cow::Borrowed("expected"), Cow::Borrowed(expected)))
+            .into_iter()
+            .collect(),
+        location: at(),
+    });
+
+    (
+        name(),
+        generics(),
+        prop::collection::vec(argument, 0..3),
+        body(value()),
+        prop::option::of(typ()),
+        where_clause(),
+        prop::collection::vec(attribute, 0..2),
+        any::<bool>(),
+    )
+        .prop_map(
+            |(name, generics, arguments, body, return_type, where_clause, attributes, is_unsafe)| Statement::Fun {
+                name,
+                generics,
+                arguments,
+                body,
+                return_type: return_type.map(Box::new),
+                where_clause,
+                attributes,
+                inline: None,
+                is_unsafe,
+                doc: vec![],
+                location: at(),
+            },
+        )
+}
+
+/// trees a `use` can import. globs are only generated behind a path, as one
+/// at the root of the program doesn't parse
+fn use_tree() -> impl Strategy<Value = UseTree<'static>> {
+    name().prop_map(UseTree::Leaf).prop_recursive(3, 12, 3, |inner| {
+        prop_oneof![
+            (name(), prop_oneof![3 => inner.clone(), 1 => Just(UseTree::Glob)]).prop_map(|(segment, child)| {
+                UseTree::Path {
+                    segment,
+                    child: Box::new(child),
+                }
+            }),
+            prop::collection::vec(inner, 0..3).prop_map(UseTree::Group),
+        ]
+    })
+}
+
+fn generics() -> impl Strategy<Value = Vec<GenericParam<'static>>> {
+    let param = (name(), prop::collection::vec(typ(), 0..3)).prop_map(|(name, bounds)| GenericParam {
+        name,
+        bounds,
+        location: at(),
+    });
+    prop::collection::vec(param, 0..3)
+}
+
+fn item() -> impl Strategy<Value = Statement<'static>> {
+    let field = (name(), typ()).prop_map(|(name, arg_type)| Statement::FunArgument {
+        name,
+        arg_type: Box::new(arg_type),
+        location: at(),
+    });
+    let derives = prop::option::of(prop::collection::vec(prop_oneof![Just("Debug"), Just("Display")], 1..3)).prop_map(
+        |derives| {
+            derives
+                .map(|derives| Attribute {
+                    name: Cow::Borrowed("derive"),
+                    arguments: derives.into_iter().map(Cow::Borrowed).collect(),
+                    values: vec![],
+                    location: at(),
+                })
+                .into_iter()
+                .collect::<Vec<_>>()
+        },
+    );
+
+    prop_oneof![
+        4 => function(),
+        1 => (name(), generics(), prop::collection::vec(field, 0..3), derives).prop_map(
+            |(name, generics, fields, attributes)| Statement::Struct {
+                name,
+                generics,
+                fields,
+                attributes,
+                doc: vec![],
+                location: at(),
+            }
+        ),
+        1 => (
+            generics(),
+            prop::option::of(name()),
+            name(),
+            prop::collection::vec(typ(), 0..3),
+            prop::collection::vec(function(), 0..2)
+        )
+            .prop_map(|(generics, trait_name, target, arguments, functions)| Statement::Impl {
+                generics,
+                trait_name,
+                target,
+                arguments,
+                functions,
+                location: at(),
+            }),
+        1 => (name(), prop::collection::vec(macro_rule(), 0..3)).prop_map(|(name, rules)| Statement::MacroRules {
+            name,
+            rules,
+            location: at(),
+        }),
+        1 => name().prop_map(|name| Statement::Mod { name, location: at() }),
+        1 => (
+            use_tree(),
+            prop::option::of(Just(Attribute {
+                name: Cow::Borrowed("allow"),
+                arguments: vec![Cow::Borrowed("glob_import")],
+                values: vec![],
+                location: at(),
+            })),
+        )
+            .prop_map(|(tree, attributes)| Statement::Use {
+                tree,
+                attributes: attributes.into_iter().collect(),
+                location: at(),
+            }),
+        1 => (
+            prop::option::of(name()),
+            prop::option::of(name()),
+            prop::collection::vec(
+                prop_oneof![Just("macro_use"), Just("no_link")].prop_map(|name| Attribute {
+                    name: Cow::Borrowed(name),
+                    arguments: vec![],
+                    values: vec![],
+                    location: at(),
+                }),
+                0..2,
+            ),
+        )
+            .prop_map(|(name, alias, attributes)| {
+                // `self` always needs an alias
+                let (name, alias) = match name {
+                    Some(name) => (name, alias),
+                    None => (Cow::Borrowed("self"), Some(alias.unwrap_or(Cow::Borrowed("root")))),
+                };
+                Statement::ExternCrate {
+                    is_self: name == "self",
+                    name,
+                    alias,
+                    attributes,
+                    location: at(),
+                }
+            }),
+        1 => (
+            any::<bool>(),
+            name(),
+            prop::option::of(typ()),
+            value(),
+            prop::collection::vec(
+                Just(Attribute {
+                    name: Cow::Borrowed("allow"),
+                    arguments: vec![Cow::Borrowed("mutable_global")],
+                    values: vec![],
+                    location: at(),
+                }),
+                0..2,
+            ),
+        )
+            .prop_map(|(mutable, name, typ, value, attributes)| Statement::Static {
+                mutable,
+                name,
+                typ: typ.map(Box::new),
+                value: Box::new(value),
+                attributes,
+                location: at(),
+            }),
+    ]
+}
+
+fn program() -> impl Strategy<Value = Vec<Statement<'static>>> {
+    prop::collection::vec(item(), 1..4)
+}
+
+fn parse(source: &str) -> Result<Vec<Statement<'_>>, String> {
+    Parser::new(source, Lexer::new(source))
+        .parse()
+        .map_err(|error| format!("{error:?}\n{source}"))
+}
+
+proptest! {
+    #[test]
+    fn printed_trees_parse_back_to_themselves(program in program()) {
+        let source = print(&program);
+        let parsed = parse(&source).map_err(TestCaseError::fail)?;
+
+        // s-expressions hold everything but locations
+        prop_assert_eq!(statements_to_sexpr(&parsed), statements_to_sexpr(&program), "{}", source);
+    }
+
+    #[test]
+    fn printing_is_a_fixed_point(program in program()) {
+        let source = print(&program);
+        let parsed = parse(&source).map_err(TestCaseError::fail)?;
+
+        prop_assert_eq!(print(&parsed), source);
+    }
+}
@@ -0,0 +1,127 @@
+mod lower;
+
+use std::fmt::{self, Write};
+
+use kura_lexer::token::Location;
+pub use lower::{
+    lower, lower_expression, LowerError, ARRAY_FUNCTION, ARRAY_LEN_FUNCTION, ASSERT_FUNCTION, INDEX_FUNCTION,
+    RUNTIME_TYPE_OF_FUNCTION, SAFE_CAST_FUNCTION, TUPLE_FUNCTION,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Label(pub u32);
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "L{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Op {
+    PushInt(i64),
+    PushUInt(u64),
+    PushFloat(f64),
+    PushBool(bool),
+    PushString(String),
+    /// value of expressions that don't produce anything, like an `if`
+    /// without an `else` or a block ending in a declaration
+    PushUnit,
+    Pop,
+    LoadLocal(u32),
+    StoreLocal(u32),
+    /// names that are not declared in the function, resolved at runtime
+    LoadGlobal(String),
+    /// replaces the value on top of the stack with one of its fields
+    LoadField(String),
+    Add,
+    Sub,
+    Mul,
+    Div,
+    CmpEq,
+    CmpNe,
+    /// marks the position `Jump` and `JumpIfFalse` go to
+    Label(Label),
+    Jump(Label),
+    JumpIfFalse(Label),
+    Call(String, u32),
+    Return,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::PushInt(value) => write!(f, "push_int {value}"),
+            Op::PushUInt(value) => write!(f, "push_uint {value}"),
+            Op::PushFloat(value) => write!(f, "push_float {value:?}"),
+            Op::PushBool(value) => write!(f, "push_bool {value}"),
+            Op::PushString(value) => write!(f, "push_string {value:?}"),
+            Op::PushUnit => write!(f, "push_unit"),
+            Op::Pop => write!(f, "pop"),
+            Op::LoadLocal(slot) => write!(f, "load_local {slot}"),
+            Op::StoreLocal(slot) => write!(f, "store_local {slot}"),
+            Op::LoadGlobal(name) => write!(f, "load_global {name}"),
+            Op::LoadField(name) => write!(f, "load_field {name}"),
+            Op::Add => write!(f, "add"),
+            Op::Sub => write!(f, "sub"),
+            Op::Mul => write!(f, "mul"),
+            Op::Div => write!(f, "div"),
+            Op::CmpEq => write!(f, "cmp_eq"),
+            Op::CmpNe => write!(f, "cmp_ne"),
+            Op::Label(label) => write!(f, "{label}:"),
+            Op::Jump(label) => write!(f, "jump {label}"),
+            Op::JumpIfFalse(label) => write!(f, "jump_if_false {label}"),
+            Op::Call(name, argc) => write!(f, "call {name} {argc}"),
+            Op::Return => write!(f, "return"),
+        }
+    }
+}
+
+/// a single instruction, along with the location of the expression it was
+/// lowered from so runtime errors can point back to the source
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instr {
+    pub op: Op,
+    pub location: Location,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+    pub name: String,
+    pub params: u32,
+    /// amount of slots the function needs, arguments included
+    pub locals: u32,
+    pub code: Vec<Instr>,
+}
+
+/// disassembles a list of instructions, one per line
+pub fn dump(code: &[Instr]) -> String {
+    let mut output = String::new();
+
+    for instr in code {
+        match instr.op {
+            Op::Label(_) => writeln!(output, "{}", instr.op),
+            _ => writeln!(output, "    {}", instr.op),
+        }
+        .expect("writing to a string can't fail");
+    }
+
+    output
+}
+
+pub fn dump_functions(functions: &[Function]) -> String {
+    let mut output = String::new();
+
+    for (idx, function) in functions.iter().enumerate() {
+        if idx > 0 {
+            output.push('\n');
+        }
+        output.push_str(&format!(
+            "fun {} (params: {}, locals: {})\n",
+            function.name, function.params, function.locals
+        ));
+        output.push_str(&dump(&function.code));
+    }
+
+    output
+}
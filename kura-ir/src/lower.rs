@@ -0,0 +1,1043 @@
+use kura_lexer::token::{Location, Operator};
+use kura_parser::const_eval::{size_of, ConstExprEvaluator, EvalError, LiteralValue};
+use kura_parser::constant::{eval_const, ConstValue};
+use kura_parser::passes::{desugar_compound_assign, AWAIT_FUNCTION, FORMAT_FUNCTION};
+use kura_parser::{Expression, MatchArm, Pattern, Statement, VariantKind};
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::{Function, Instr, Label, Op};
+
+/// name of the built-in function a failed assertion calls, with its message
+/// as the only argument
+pub const ASSERT_FUNCTION: &str = "__assert_failed__";
+
+/// name of the built-in function `typeof!` calls, giving back the `TypeTag`
+/// of its only argument
+pub const RUNTIME_TYPE_OF_FUNCTION: &str = "__runtime_type_of__";
+
+/// name of the built-in function `try_as` calls, with the value and then the
+/// name of the type to convert it into. it gives back `Some` of the
+/// converted value when it fits, and `None` otherwise
+pub const SAFE_CAST_FUNCTION: &str = "__try_as__";
+
+/// name of the built-in function array literals call, building an array out
+/// of every argument it is given
+pub const ARRAY_FUNCTION: &str = "__array__";
+
+/// name of the built-in function tuple literals call, building a tuple out
+/// of every argument it is given
+pub const TUPLE_FUNCTION: &str = "__tuple__";
+
+/// name of the built-in function `len!` calls when the length of its array
+/// isn't known at compile time
+pub const ARRAY_LEN_FUNCTION: &str = "__array_len__";
+
+/// name of the built-in function `target[index]` calls, with the target and
+/// then the index
+pub const INDEX_FUNCTION: &str = "__index__";
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum LowerError {
+    #[error("operator `{operator}` can't be lowered yet")]
+    #[diagnostic(code(kura::ir::unsupported_operator))]
+    UnsupportedOperator {
+        operator: Operator,
+        #[label("used here")]
+        span: SourceSpan,
+    },
+    #[error("only variables can be assigned to")]
+    #[diagnostic(code(kura::ir::invalid_assignment))]
+    InvalidAssignment {
+        #[label("this is not a variable")]
+        span: SourceSpan,
+    },
+    #[error("cannot assign to `{name}`, as it is not declared")]
+    #[diagnostic(code(kura::ir::undeclared_assignment))]
+    UndeclaredAssignment {
+        name: String,
+        #[label("not declared in this function")]
+        span: SourceSpan,
+    },
+    #[error("only functions can be called by name")]
+    #[diagnostic(code(kura::ir::invalid_callee))]
+    InvalidCallee {
+        #[label("this is not a function name")]
+        span: SourceSpan,
+    },
+    #[error("macro `{name}!` must be expanded before it can be lowered")]
+    #[diagnostic(code(kura::ir::unexpanded_macro))]
+    UnexpandedMacro {
+        name: String,
+        #[label("invoked here")]
+        span: SourceSpan,
+    },
+    #[error("lambdas can't be lowered yet")]
+    #[diagnostic(code(kura::ir::unsupported_lambda))]
+    UnsupportedLambda {
+        #[label("defined here")]
+        span: SourceSpan,
+    },
+    #[error("generators can't be lowered yet")]
+    #[diagnostic(code(kura::ir::unsupported_yield))]
+    UnsupportedYield {
+        #[label("yielded here")]
+        span: SourceSpan,
+    },
+    #[error("`do` blocks can't be lowered until they are desugared")]
+    #[diagnostic(code(kura::ir::unsupported_do))]
+    UnsupportedDo {
+        #[label("written here")]
+        span: SourceSpan,
+    },
+    #[error("struct variants and matching on the values of a variant can't be lowered yet")]
+    #[diagnostic(code(kura::ir::unsupported_variant))]
+    UnsupportedVariant {
+        #[label("used here")]
+        span: SourceSpan,
+    },
+    /// a `comptime` block whose value isn't known at compile time
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Comptime(#[from] EvalError),
+    #[error("`?` can't be lowered yet")]
+    #[diagnostic(code(kura::ir::unsupported_try))]
+    UnsupportedTry {
+        #[label("used here")]
+        span: SourceSpan,
+    },
+    #[error("`defer` can't be lowered yet")]
+    #[diagnostic(code(kura::ir::unsupported_defer))]
+    UnsupportedDefer {
+        #[label("used here")]
+        span: SourceSpan,
+    },
+    #[error("the size of `{typ}` isn't known at compile time")]
+    #[diagnostic(code(kura::ir::unsupported_sizeof))]
+    UnsupportedSizeof {
+        typ: String,
+        #[label("asked here")]
+        span: SourceSpan,
+    },
+    #[error("references can't be lowered yet")]
+    #[diagnostic(code(kura::ir::unsupported_reference))]
+    UnsupportedReference {
+        #[label("used here")]
+        span: SourceSpan,
+    },
+    #[error("spreading arguments with `splat!` can't be lowered yet")]
+    #[diagnostic(code(kura::ir::unsupported_splat))]
+    UnsupportedSplat {
+        #[label("spread here")]
+        span: SourceSpan,
+    },
+    #[error("inline assembly can't be lowered")]
+    #[diagnostic(code(kura::ir::unsupported_asm))]
+    UnsupportedInlineAsm {
+        #[label("used here")]
+        span: SourceSpan,
+    },
+}
+
+/// lowers every function in the program into its own instruction list
+pub fn lower(statements: &[Statement<'_>]) -> Result<Vec<Function>, LowerError> {
+    let mut functions = vec![];
+
+    for statement in statements {
+        let Statement::Fun {
+            name,
+            arguments,
+            body,
+            location,
+            ..
+        } = statement
+        else {
+            continue;
+        };
+
+        let mut lowerer = Lowerer {
+            params: Some(arguments.len() as u32),
+            ..Lowerer::default()
+        };
+        lowerer.scopes.push(vec![]);
+        for argument in arguments {
+            if let Statement::FunArgument { name, .. } = argument {
+                lowerer.declare(name);
+            }
+        }
+
+        // the last expression of a function is what it returns
+        let (tail, body) = match body.split_last() {
+            Some((tail, body)) => (Some(tail), body),
+            None => (None, body.as_slice()),
+        };
+        lowerer.lower_sequence(body, tail, *location)?;
+        lowerer.emit(Op::Return, *location);
+        if let Some(entry) = lowerer.entry {
+            lowerer.code.insert(
+                0,
+                Instr {
+                    op: Op::Label(entry),
+                    location: *location,
+                },
+            );
+        }
+
+        functions.push(Function {
+            name: name.to_string(),
+            params: arguments.len() as u32,
+            locals: lowerer.max_slots,
+            code: lowerer.code,
+        });
+    }
+
+    Ok(functions)
+}
+
+/// lowers a standalone expression, as typed on the repl, into a function
+/// that returns its value
+pub fn lower_expression(expr: &Expression<'_>) -> Result<Function, LowerError> {
+    let mut lowerer = Lowerer::default();
+    lowerer.scopes.push(vec![]);
+    lowerer.lower_value(expr)?;
+    lowerer.emit(Op::Return, expr.location());
+
+    Ok(Function {
+        name: String::from("<expression>"),
+        params: 0,
+        locals: lowerer.max_slots,
+        code: lowerer.code,
+    })
+}
+
+#[derive(Default)]
+struct Lowerer {
+    code: Vec<Instr>,
+    // every scope holds the names declared in it with their slots, a scope
+    // gives back its slots once it ends
+    scopes: Vec<Vec<(String, u32)>>,
+    next_slot: u32,
+    max_slots: u32,
+    next_label: u32,
+    /// how many arguments the function being lowered takes, which are the
+    /// first slots. `None` outside of a function
+    params: Option<u32>,
+    /// where a tail call jumps back to, at the start of the function. it is
+    /// only placed there when some call uses it
+    entry: Option<Label>,
+}
+
+impl Lowerer {
+    fn emit(&mut self, op: Op, location: Location) {
+        self.code.push(Instr { op, location });
+    }
+
+    fn label(&mut self) -> Label {
+        let label = Label(self.next_label);
+        self.next_label += 1;
+        label
+    }
+
+    fn declare(&mut self, name: &str) -> u32 {
+        let slot = self.next_slot;
+        self.next_slot += 1;
+        self.max_slots = self.max_slots.max(self.next_slot);
+        self.scopes
+            .last_mut()
+            .expect("declarations only happen inside of a scope")
+            .push((name.to_string(), slot));
+        slot
+    }
+
+    fn lookup(&self, name: &str) -> Option<u32> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|(declared, _)| declared == name)
+            .map(|(_, slot)| *slot)
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(vec![]);
+    }
+
+    fn pop_scope(&mut self) {
+        let scope = self.scopes.pop().expect("scopes are balanced");
+        self.next_slot -= scope.len() as u32;
+    }
+
+    /// lowers expressions that leave their value on the stack, pushing a
+    /// unit for the ones that don't have any
+    fn lower_value(&mut self, expr: &Expression<'_>) -> Result<(), LowerError> {
+        if !self.lower_expr(expr)? {
+            self.emit(Op::PushUnit, expr.location());
+        }
+        Ok(())
+    }
+
+    /// leaves the value of `tail` on the stack, or a unit without one,
+    /// discarding the values of every other expression
+    fn lower_sequence(
+        &mut self,
+        expressions: &[Expression<'_>],
+        tail: Option<&Expression<'_>>,
+        location: Location,
+    ) -> Result<(), LowerError> {
+        for expr in expressions {
+            if self.lower_expr(expr)? {
+                self.emit(Op::Pop, expr.location());
+            }
+        }
+
+        match tail {
+            Some(tail) => self.lower_value(tail),
+            None => {
+                self.emit(Op::PushUnit, location);
+                Ok(())
+            }
+        }
+    }
+
+    /// returns whether the expression left a value on the stack
+    fn lower_expr(&mut self, expr: &Expression<'_>) -> Result<bool, LowerError> {
+        let location = expr.location();
+
+        match expr {
+            Expression::Var { name, value, .. } => {
+                // the value is lowered before declaring the variable, so that
+                // `const x = x + 1` refers to an outer `x`
+                self.lower_value(value)?;
+                let slot = self.declare(name);
+                self.emit(Op::StoreLocal(slot), location);
+                Ok(false)
+            }
+            Expression::Assign { ident, value, .. } => {
+                let Expression::Ident { name, .. } = ident.as_ref() else {
+                    return Err(LowerError::InvalidAssignment {
+                        span: ident.location().into(),
+                    });
+                };
+                let Some(slot) = self.lookup(name) else {
+                    return Err(LowerError::UndeclaredAssignment {
+                        name: name.to_string(),
+                        span: ident.location().into(),
+                    });
+                };
+
+                self.lower_value(value)?;
+                self.emit(Op::StoreLocal(slot), location);
+                Ok(false)
+            }
+            // lowered as the assignment it is short for
+            Expression::CompoundAssign { .. } => self.lower_expr(&desugar_compound_assign(expr.clone())),
+            Expression::Return { value, .. } => {
+                self.lower_value(value)?;
+                self.emit(Op::Return, location);
+                Ok(false)
+            }
+            Expression::Bool { value, .. } => {
+                self.emit(Op::PushBool(*value), location);
+                Ok(true)
+            }
+            Expression::UintLiteral { value, .. } => {
+                self.emit(Op::PushUInt(*value), location);
+                Ok(true)
+            }
+            Expression::IntLiteral { value, .. } => {
+                self.emit(Op::PushInt(*value), location);
+                Ok(true)
+            }
+            Expression::FloatLiteral { value, .. } => {
+                self.emit(Op::PushFloat(*value), location);
+                Ok(true)
+            }
+            Expression::StringLiteral { value, .. } => {
+                self.emit(Op::PushString(value.to_string()), location);
+                Ok(true)
+            }
+            // lowered the same way the desugaring pass rewrites it, as a call
+            // taking the literal parts followed by the embedded expressions
+            Expression::Interpolated {
+                literals, expressions, ..
+            } => {
+                for part in literals.iter().chain(expressions) {
+                    self.lower_value(part)?;
+                }
+                let argc = literals.len() + expressions.len();
+                self.emit(Op::Call(FORMAT_FUNCTION.to_string(), argc as u32), location);
+                Ok(true)
+            }
+            // lowered the same way the desugaring pass rewrites it
+            Expression::Await { expr, .. } => {
+                self.lower_value(expr)?;
+                self.emit(Op::Call(AWAIT_FUNCTION.to_string(), 1), location);
+                Ok(true)
+            }
+            Expression::RuntimeTypeOf { expr, .. } => {
+                self.lower_value(expr)?;
+                self.emit(Op::Call(RUNTIME_TYPE_OF_FUNCTION.to_string(), 1), location);
+                Ok(true)
+            }
+            Expression::Array { elements, .. } | Expression::Tuple { elements, .. } => {
+                for element in elements {
+                    self.lower_value(element)?;
+                }
+                let function = match expr {
+                    Expression::Array { .. } => ARRAY_FUNCTION,
+                    _ => TUPLE_FUNCTION,
+                };
+                self.emit(Op::Call(function.to_string(), elements.len() as u32), location);
+                Ok(true)
+            }
+            // the length of a literal is known without building it, while
+            // the one of `const` bindings is left to `fold_constants`
+            Expression::ArrayLen { array, .. } => {
+                match array.as_ref() {
+                    Expression::Array { elements, .. } => self.emit(Op::PushUInt(elements.len() as u64), location),
+                    array => {
+                        self.lower_value(array)?;
+                        self.emit(Op::Call(ARRAY_LEN_FUNCTION.to_string(), 1), location);
+                    }
+                }
+                Ok(true)
+            }
+            Expression::SafeCast { expr, typ, .. } => {
+                self.lower_value(expr)?;
+                self.emit(Op::PushString(typ.to_string()), location);
+                self.emit(Op::Call(SAFE_CAST_FUNCTION.to_string(), 2), location);
+                Ok(true)
+            }
+            Expression::Sizeof { typ, .. } => {
+                let Some(size) = size_of(typ) else {
+                    return Err(LowerError::UnsupportedSizeof {
+                        typ: typ.to_string(),
+                        span: location.into(),
+                    });
+                };
+                self.emit(Op::PushUInt(size), location);
+                Ok(true)
+            }
+            Expression::FieldAccess { target, field, .. } => {
+                self.lower_value(target)?;
+                self.emit(Op::LoadField(field.to_string()), location);
+                Ok(true)
+            }
+            // the elements of a tuple are fields named after their index
+            Expression::TupleAccess { tuple, index, .. } => {
+                self.lower_value(tuple)?;
+                self.emit(Op::LoadField(index.to_string()), location);
+                Ok(true)
+            }
+            Expression::Index { target, index, .. } => {
+                self.lower_value(target)?;
+                self.lower_value(index)?;
+                self.emit(Op::Call(INDEX_FUNCTION.to_string(), 2), location);
+                Ok(true)
+            }
+            Expression::Try { .. } => Err(LowerError::UnsupportedTry { span: location.into() }),
+            Expression::Defer { .. } => Err(LowerError::UnsupportedDefer { span: location.into() }),
+            Expression::Macro { name, .. } => Err(LowerError::UnexpandedMacro {
+                name: name.to_string(),
+                span: location.into(),
+            }),
+            Expression::Lambda { .. } => Err(LowerError::UnsupportedLambda { span: location.into() }),
+            Expression::Yield { .. } => Err(LowerError::UnsupportedYield { span: location.into() }),
+            Expression::DoBlock { .. } => Err(LowerError::UnsupportedDo { span: location.into() }),
+            Expression::Ref { .. } | Expression::Deref { .. } => {
+                Err(LowerError::UnsupportedReference { span: location.into() })
+            }
+            Expression::InlineAsm { .. } => Err(LowerError::UnsupportedInlineAsm { span: location.into() }),
+            Expression::Splat { .. } => Err(LowerError::UnsupportedSplat { span: location.into() }),
+            Expression::Let { name, value, body, .. } => {
+                self.lower_value(value)?;
+                self.push_scope();
+                let slot = self.declare(name);
+                self.emit(Op::StoreLocal(slot), location);
+                self.lower_value(body)?;
+                self.pop_scope();
+                Ok(true)
+            }
+            Expression::Ident { name, .. } => {
+                match self.lookup(name) {
+                    Some(slot) => self.emit(Op::LoadLocal(slot), location),
+                    None => self.emit(Op::LoadGlobal(name.to_string()), location),
+                }
+                Ok(true)
+            }
+            // a path never names a local, so it is always a global
+            Expression::Path { segments, .. } => {
+                self.emit(Op::LoadGlobal(segments.join("::")), location);
+                Ok(true)
+            }
+            // a unit variant is a global just like any other path, and a tuple
+            // variant is built by calling it
+            Expression::EnumVariant {
+                path,
+                kind: VariantKind::Unit,
+                ..
+            } => {
+                self.emit(Op::LoadGlobal(path.join("::")), location);
+                Ok(true)
+            }
+            Expression::EnumVariant {
+                path,
+                kind: VariantKind::Tuple(elements),
+                ..
+            } => {
+                for element in elements {
+                    self.lower_value(element)?;
+                }
+                self.emit(Op::Call(path.join("::"), elements.len() as u32), location);
+                Ok(true)
+            }
+            Expression::EnumVariant { .. } => Err(LowerError::UnsupportedVariant { span: location.into() }),
+            Expression::Block { expressions, tail, .. } => {
+                self.push_scope();
+                self.lower_sequence(expressions, tail.as_deref(), location)?;
+                self.pop_scope();
+                Ok(true)
+            }
+            // the ir has no notion of safety, so the block runs like any other
+            Expression::UnsafeBlock { body, .. } => self.lower_expr(body),
+            // nothing of the block is left to run, only its value
+            Expression::ComptimeBlock { body, .. } => {
+                let op = match ConstExprEvaluator::eval(body)? {
+                    LiteralValue::Int(value) => Op::PushInt(value),
+                    LiteralValue::UInt(value) => Op::PushUInt(value),
+                    LiteralValue::Float(value) => Op::PushFloat(value),
+                    LiteralValue::Bool(value) => Op::PushBool(value),
+                    LiteralValue::Str(value) => Op::PushString(value),
+                };
+                self.emit(op, location);
+                Ok(true)
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                self.lower_if(condition, truthy, falsy, location)?;
+                Ok(true)
+            }
+            Expression::Match { scrutinee, arms, .. } => {
+                self.lower_match(scrutinee, arms, location)?;
+                Ok(true)
+            }
+            // the arguments take the place of the ones the function was called
+            // with, and it starts over rather than growing the stack
+            Expression::FunCall {
+                arguments,
+                tail_call: true,
+                ..
+            } if self.params == Some(arguments.len() as u32) => {
+                for argument in arguments {
+                    self.lower_value(argument)?;
+                }
+                // the last argument is on top of the stack
+                for slot in (0..arguments.len() as u32).rev() {
+                    self.emit(Op::StoreLocal(slot), location);
+                }
+                let entry = match self.entry {
+                    Some(entry) => entry,
+                    None => {
+                        let entry = self.label();
+                        *self.entry.insert(entry)
+                    }
+                };
+                self.emit(Op::Jump(entry), location);
+                Ok(true)
+            }
+            Expression::FunCall { ident, arguments, .. } => {
+                let name = match ident.as_ref() {
+                    Expression::Ident { name, .. } => name.to_string(),
+                    Expression::Path { segments, .. } => segments.join("::"),
+                    _ => {
+                        return Err(LowerError::InvalidCallee {
+                            span: ident.location().into(),
+                        })
+                    }
+                };
+
+                for argument in arguments {
+                    self.lower_value(argument)?;
+                }
+                self.emit(Op::Call(name, arguments.len() as u32), location);
+                Ok(true)
+            }
+            Expression::BinaryOp { operator, lhs, rhs, .. } => {
+                self.lower_binary_op(*operator, lhs, rhs, location)?;
+                Ok(true)
+            }
+            // `!a` is `a == false`
+            Expression::Not { expr, .. } => {
+                self.lower_value(expr)?;
+                self.emit(Op::PushBool(false), location);
+                self.emit(Op::CmpEq, location);
+                Ok(true)
+            }
+            Expression::Elvis { condition, default, .. } => {
+                self.lower_elvis(condition, default, location)?;
+                Ok(true)
+            }
+            Expression::Assert { condition, message, .. } => {
+                self.lower_assert(condition, message.as_deref(), location)?;
+                Ok(false)
+            }
+        }
+    }
+
+    fn lower_if(
+        &mut self,
+        condition: &Expression<'_>,
+        truthy: &Expression<'_>,
+        falsy: &[Expression<'_>],
+        location: Location,
+    ) -> Result<(), LowerError> {
+        let else_label = self.label();
+        let end_label = self.label();
+
+        self.lower_value(condition)?;
+        self.emit(Op::JumpIfFalse(else_label), location);
+        self.lower_value(truthy)?;
+        self.emit(Op::Jump(end_label), location);
+
+        self.emit(Op::Label(else_label), location);
+        match falsy {
+            [] => self.emit(Op::PushUnit, location),
+            // an `else if` is an `if` on its own, which holds the rest of the
+            // chain when it has one
+            [Expression::If {
+                condition,
+                truthy,
+                falsy: nested,
+                location,
+            }, rest @ ..] => {
+                let chain = if nested.is_empty() { rest } else { nested.as_slice() };
+                self.lower_if(condition, truthy, chain, *location)?;
+            }
+            [branch, ..] => self.lower_value(branch)?,
+        }
+        self.emit(Op::Label(end_label), location);
+
+        Ok(())
+    }
+
+    /// the scrutinee is kept in a slot of its own and compared against the
+    /// pattern of every arm in order, running the body of the first one
+    /// that matches. when none does, the value is a unit
+    fn lower_match(
+        &mut self,
+        scrutinee: &Expression<'_>,
+        arms: &[MatchArm<'_>],
+        location: Location,
+    ) -> Result<(), LowerError> {
+        let end = self.label();
+
+        self.lower_value(scrutinee)?;
+        self.push_scope();
+        // just like `?:`, no identifier can be named `match`
+        let slot = self.declare("match");
+        self.emit(Op::StoreLocal(slot), location);
+
+        for arm in arms {
+            let next = self.label();
+            match &arm.pattern {
+                Pattern::Wildcard { .. } => self.lower_value(&arm.body)?,
+                Pattern::Binding { name, .. } => {
+                    self.push_scope();
+                    let binding = self.declare(name);
+                    self.emit(Op::LoadLocal(slot), arm.location);
+                    self.emit(Op::StoreLocal(binding), arm.location);
+                    self.lower_value(&arm.body)?;
+                    self.pop_scope();
+                }
+                Pattern::Literal(literal) => {
+                    self.emit(Op::LoadLocal(slot), arm.location);
+                    self.lower_value(literal)?;
+                    self.emit(Op::CmpEq, arm.location);
+                    self.emit(Op::JumpIfFalse(next), arm.location);
+                    self.lower_value(&arm.body)?;
+                }
+                Pattern::Path { segments: path, .. }
+                | Pattern::Variant {
+                    path,
+                    kind: VariantKind::Unit,
+                    ..
+                } => {
+                    self.emit(Op::LoadLocal(slot), arm.location);
+                    self.emit(Op::LoadGlobal(path.join("::")), arm.location);
+                    self.emit(Op::CmpEq, arm.location);
+                    self.emit(Op::JumpIfFalse(next), arm.location);
+                    self.lower_value(&arm.body)?;
+                }
+                Pattern::Variant { location, .. } => {
+                    return Err(LowerError::UnsupportedVariant {
+                        span: (*location).into(),
+                    })
+                }
+            }
+            self.emit(Op::Jump(end), arm.location);
+            self.emit(Op::Label(next), arm.location);
+        }
+
+        self.emit(Op::PushUnit, location);
+        self.emit(Op::Label(end), location);
+        self.pop_scope();
+
+        Ok(())
+    }
+
+    /// the condition is kept in a slot of its own, as it is both tested and,
+    /// when truthy, the value of the whole expression
+    fn lower_elvis(
+        &mut self,
+        condition: &Expression<'_>,
+        default: &Expression<'_>,
+        location: Location,
+    ) -> Result<(), LowerError> {
+        let falsy = self.label();
+        let end = self.label();
+
+        self.lower_value(condition)?;
+        self.push_scope();
+        // no identifier can be named `?:`, so nothing can refer to the slot
+        let slot = self.declare("?:");
+        self.emit(Op::StoreLocal(slot), location);
+        self.emit(Op::LoadLocal(slot), location);
+        self.emit(Op::JumpIfFalse(falsy), location);
+        self.emit(Op::LoadLocal(slot), location);
+        self.pop_scope();
+        self.emit(Op::Jump(end), location);
+
+        self.emit(Op::Label(falsy), location);
+        self.lower_value(default)?;
+        self.emit(Op::Label(end), location);
+
+        Ok(())
+    }
+
+    /// a condition known while lowering either never fails, and is left out,
+    /// or always does, and isn't tested
+    fn lower_assert(
+        &mut self,
+        condition: &Expression<'_>,
+        message: Option<&Expression<'_>>,
+        location: Location,
+    ) -> Result<(), LowerError> {
+        let end = match eval_const(condition) {
+            Some(ConstValue::Bool(true)) => return Ok(()),
+            Some(ConstValue::Bool(false)) => None,
+            _ => {
+                let failed = self.label();
+                let end = self.label();
+                self.lower_value(condition)?;
+                self.emit(Op::JumpIfFalse(failed), location);
+                self.emit(Op::Jump(end), location);
+                self.emit(Op::Label(failed), location);
+                Some(end)
+            }
+        };
+
+        match message {
+            Some(message) => self.lower_value(message)?,
+            None => {
+                let message = format!(
+                    "assertion failed at {}..{}",
+                    condition.location().start_byte,
+                    condition.location().end_byte
+                );
+                self.emit(Op::PushString(message), location);
+            }
+        }
+        self.emit(Op::Call(ASSERT_FUNCTION.to_string(), 1), location);
+        self.emit(Op::Pop, location);
+        if let Some(end) = end {
+            self.emit(Op::Label(end), location);
+        }
+
+        Ok(())
+    }
+
+    fn lower_binary_op(
+        &mut self,
+        operator: Operator,
+        lhs: &Expression<'_>,
+        rhs: &Expression<'_>,
+        location: Location,
+    ) -> Result<(), LowerError> {
+        let op = match operator {
+            Operator::And | Operator::Or => return self.lower_short_circuit(operator, lhs, rhs, location),
+            Operator::Plus => Op::Add,
+            Operator::Minus => Op::Sub,
+            Operator::Star => Op::Mul,
+            Operator::Slash => Op::Div,
+            Operator::EqualEqual => Op::CmpEq,
+            Operator::NotEqual => Op::CmpNe,
+            operator => {
+                return Err(LowerError::UnsupportedOperator {
+                    operator,
+                    span: location.into(),
+                })
+            }
+        };
+
+        self.lower_value(lhs)?;
+        self.lower_value(rhs)?;
+        self.emit(op, location);
+        Ok(())
+    }
+
+    /// `&&` only evaluates its right side when the left one is true, and `||`
+    /// only when it is false
+    fn lower_short_circuit(
+        &mut self,
+        operator: Operator,
+        lhs: &Expression<'_>,
+        rhs: &Expression<'_>,
+        location: Location,
+    ) -> Result<(), LowerError> {
+        let short_circuit = self.label();
+        let end = self.label();
+
+        self.lower_value(lhs)?;
+        self.emit(Op::JumpIfFalse(short_circuit), location);
+
+        match operator {
+            Operator::And => {
+                self.lower_value(rhs)?;
+                self.emit(Op::Jump(end), location);
+                self.emit(Op::Label(short_circuit), location);
+                self.emit(Op::PushBool(false), location);
+            }
+            _ => {
+                self.emit(Op::PushBool(true), location);
+                self.emit(Op::Jump(end), location);
+                self.emit(Op::Label(short_circuit), location);
+                self.lower_value(rhs)?;
+            }
+        }
+
+        self.emit(Op::Label(end), location);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+    use kura_parser::Parser;
+
+    use super::*;
+    use crate::{dump, dump_functions};
+
+    fn lower_source(source: &str) -> String {
+        let lexer = Lexer::new(source);
+        let statements = match Parser::new(source, lexer).parse() {
+            Ok(statements) => statements,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        match lower(&statements) {
+            Ok(functions) => dump_functions(&functions),
+            Err(e) => panic!("{e:?}"),
+        }
+    }
+
+    fn lower_line(source: &str) -> String {
+        let (expr, _) = Parser::parse_repl_line(source).unwrap();
+        dump(&lower_expression(&expr).unwrap().code)
+    }
+
+    #[test]
+    fn simple_math() {
+        insta::assert_snapshot!(lower_line("1 + 2 * 3 - 4"));
+    }
+
+    #[test]
+    fn interpolated_strings() {
+        insta::assert_snapshot!(lower_line(r#"println("{name} is {age + 1}")"#));
+    }
+
+    #[test]
+    fn array_lengths() {
+        assert_eq!(lower_line("len!([a, b, c])"), "    push_uint 3\n    return\n");
+        assert_eq!(
+            lower_line("len!(f())"),
+            "    call f 0\n    call __array_len__ 1\n    return\n"
+        );
+        assert_eq!(
+            lower_line("[1, 2]"),
+            "    push_uint 1\n    push_uint 2\n    call __array__ 2\n    return\n"
+        );
+    }
+
+    #[test]
+    fn safe_casts_are_checked_at_runtime() {
+        assert_eq!(
+            lower_line("x + 1 try_as u8"),
+            "    load_global x\n    push_uint 1\n    push_string \"u8\"\n    call __try_as__ 2\n    add\n    return\n"
+        );
+    }
+
+    #[test]
+    fn blocks_evaluate_to_their_tail() {
+        assert_eq!(
+            lower_line("{ f(); 1 }"),
+            "    call f 0\n    pop\n    push_uint 1\n    return\n"
+        );
+        assert_eq!(
+            lower_line("{ f(); }"),
+            "    call f 0\n    pop\n    push_unit\n    return\n"
+        );
+    }
+
+    #[test]
+    fn short_circuit_and() {
+        insta::assert_snapshot!(lower_line("left == 1 && right != 2"));
+    }
+
+    #[test]
+    fn if_statement() {
+        let source = r#"
+            if something == another_thing && 1 != 2 {
+                const this_is_a_var = 10 + 3;
+            } else if something == 10 {
+                const this_is_another = 10 + 10;
+            } else {
+                const omg = 1 + 1;
+            }
+        "#;
+
+        insta::assert_snapshot!(lower_line(source));
+    }
+
+    #[test]
+    fn function_declaration() {
+        let source = r#"
+            fun calculate_circumference(diameter: f64) => f64 {
+                const pi = 3.14159265358979323846264338327950288_f32;
+                const radius = diameter / 2.0;
+                const circumference = 2.0 * pi * radius;
+
+                const nesting = {
+                    const something = 10;
+                    var nesting_more = {
+                        return 10 + 3 * 4;
+                    };
+                    return 10 + something;
+                };
+
+                circumference
+            }"#;
+
+        insta::assert_snapshot!(lower_source(source));
+    }
+
+    #[test]
+    fn language_features() {
+        let source = r#"
+            fun some_function_name(argument: TypeOfArg) => ReturnType {
+                const immutable_var = if truthy_val {
+                    return 10;
+                } else if another_truthy == 10 {
+                    return 20 + 3 * 2;
+                } else {
+                    const my_inner_var: f64 = 100;
+                };
+
+                var mutable_value = function_call(immutable_var + 10, immutable_var);
+                mutable_value = 10;
+
+                {
+                    const something = println(10 + 3);
+                    println(10);
+                    something_else();
+                    var something = func_call();
+                }
+
+                mutable_value
+            }
+        "#;
+
+        insta::assert_snapshot!(lower_source(source));
+    }
+
+    #[test]
+    fn slots_are_reused_after_a_scope_ends() {
+        let source = r#"
+            fun scopes(argument: u64) {
+                { const first = 1; const second = 2; }
+                { const third = 3; }
+                const fourth = 4;
+            }
+        "#;
+
+        let lexer = Lexer::new(source);
+        let statements = Parser::new(source, lexer).parse().unwrap();
+        let functions = lower(&statements).unwrap();
+
+        assert_eq!(functions[0].params, 1);
+        assert_eq!(functions[0].locals, 3);
+        let stores = functions[0]
+            .code
+            .iter()
+            .filter_map(|instr| match instr.op {
+                Op::StoreLocal(slot) => Some(slot),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(stores, [1, 2, 1, 1]);
+    }
+
+    #[test]
+    fn compound_assignments_lower_like_assignments() {
+        let compound = "fun f(a: i32) { var b = a; b *= a + 1; }";
+        let expanded = "fun f(a: i32) { var b = a; b = b * (a + 1); }";
+        assert_eq!(lower_source(compound), lower_source(expanded));
+    }
+
+    #[test]
+    fn tail_calls_jump_back_to_the_start() {
+        let source = "fun sum(n: u64, acc: u64) => u64 { sum(n - 1, acc + n); }";
+        // calls only start the function over once they are marked
+        assert!(lower_source(source).contains("call sum 2"));
+
+        let mut statements = Parser::new(source, Lexer::new(source)).parse().unwrap();
+        kura_parser::passes::mark_tail_calls(&mut statements);
+        let function = &lower(&statements).unwrap()[0];
+        assert_eq!(
+            dump(&function.code),
+            "L0:\n    load_local 0\n    push_uint 1\n    sub\n    load_local 1\n    load_local 0\n    add\n    \
+             store_local 1\n    store_local 0\n    jump L0\n    return\n"
+        );
+    }
+
+    #[test]
+    fn elvis_only_evaluates_its_default_when_needed() {
+        insta::assert_snapshot!(lower_line("cached ?: compute(1) ?: 0"));
+    }
+
+    #[test]
+    fn constant_assertions_are_decided_while_lowering() {
+        let source = r#"
+            fun check(a: bool) {
+                assert(a);
+                assert(2 == 2 && true, "never fails");
+                assert(1 == 2, "always fails");
+            }
+        "#;
+        insta::assert_snapshot!(lower_source(source));
+    }
+
+    #[test]
+    fn let_bindings_get_a_slot_for_their_body() {
+        insta::assert_snapshot!(lower_line("let a = 1 + 2 in a * a"));
+    }
+
+    #[test]
+    fn match_arms_are_tried_in_order() {
+        insta::assert_snapshot!(lower_line("match count { 0 => none, Size::Big => big, n => n + 1 }"));
+    }
+}
@@ -1,15 +1,56 @@
+use std::sync::Arc;
+
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+
 use crate::token::Location;
 
-#[derive(Debug, Default)]
+#[derive(Default)]
 pub struct Error {
     location: Location,
     message: String,
+    eof: bool,
+    source_code: Option<Arc<dyn SourceCode>>,
 }
 
 impl Error {
     pub fn with_message(&mut self, message: String) {
         self.message = message;
     }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn location(&self) -> Location {
+        self.location
+    }
+
+    /// marks the error as caused by the source ending before the token or
+    /// construct being lexed was complete
+    pub fn at_eof(mut self) -> Self {
+        self.eof = true;
+        self
+    }
+
+    pub fn is_eof(&self) -> bool {
+        self.eof
+    }
+
+    /// source the error is rendered against when reported as a diagnostic
+    pub fn with_source_code(mut self, source_code: Option<Arc<dyn SourceCode>>) -> Self {
+        self.source_code = source_code;
+        self
+    }
+}
+
+impl std::fmt::Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Error")
+            .field("location", &self.location)
+            .field("message", &self.message)
+            .field("eof", &self.eof)
+            .finish_non_exhaustive()
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -20,11 +61,26 @@ impl std::fmt::Display for Error {
 
 impl std::error::Error for Error {}
 
+impl Diagnostic for Error {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        Some(Box::new("kura::lex_error"))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.source_code.as_deref().map(|source| source as &dyn SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let label = LabeledSpan::new_with_span(Some(String::from("here")), self.location);
+        Some(Box::new(std::iter::once(label)))
+    }
+}
+
 impl From<std::ops::Range<usize>> for Error {
     fn from(value: std::ops::Range<usize>) -> Self {
         Self {
             location: value.into(),
-            message: Default::default(),
+            ..Default::default()
         }
     }
 }
@@ -33,7 +89,7 @@ impl From<Location> for Error {
     fn from(location: Location) -> Self {
         Self {
             location,
-            message: Default::default(),
+            ..Default::default()
         }
     }
 }
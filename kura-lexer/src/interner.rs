@@ -0,0 +1,60 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+
+pub use bumpalo::Bump;
+
+/// every distinct identifier lexed so far, kept once. interning a name gives
+/// back the copy the interner made, so equal names share the same pointer and
+/// can be told apart by address alone, like with `std::ptr::eq`.
+///
+/// names are copied into `arena` the first time they are interned, and live
+/// as long as it does rather than the sources they were lexed from. so an
+/// interner can be shared by lexers of many sources, however long each of
+/// them lives
+#[derive(Debug)]
+pub struct Interner<'arena> {
+    arena: &'arena Bump,
+    names: RefCell<HashSet<&'arena str>>,
+}
+
+impl<'arena> Interner<'arena> {
+    pub fn new(arena: &'arena Bump) -> Self {
+        Self {
+            arena,
+            names: RefCell::default(),
+        }
+    }
+
+    /// the copy of `name` kept in the arena, made when it is the first time
+    /// `name` is interned
+    pub fn intern(&self, name: &str) -> &'arena str {
+        let mut names = self.names.borrow_mut();
+        if let Some(interned) = names.get(name) {
+            return interned;
+        }
+        let interned = &*self.arena.alloc_str(name);
+        names.insert(interned);
+        interned
+    }
+
+    /// how many distinct names were interned
+    pub fn len(&self) -> usize {
+        self.names.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.borrow().is_empty()
+    }
+}
+
+/// what a lexer interns identifiers with. it only needs names to live as long
+/// as the tokens it lexes, which any interner whose arena outlives them does
+pub trait Intern<'lex> {
+    fn intern(&self, name: &str) -> &'lex str;
+}
+
+impl<'lex, 'arena: 'lex> Intern<'lex> for Interner<'arena> {
+    fn intern(&self, name: &str) -> &'lex str {
+        Interner::intern(self, name)
+    }
+}
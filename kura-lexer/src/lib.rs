@@ -1,8 +1,21 @@
 pub mod error;
+pub mod interner;
+pub mod position;
+pub mod source;
+pub mod stream;
 pub mod token;
 
+use std::borrow::Cow;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
 use error::Error;
-use token::{FloatSizes, IntSizes, IntoToken, Kind, Operator, Primitive, Token, UIntSizes};
+use interner::Intern;
+use miette::{NamedSource, SourceCode};
+use position::PositionIndex;
+use self_cell::self_cell;
+use token::{FloatSizes, IntSizes, IntoToken, Kind, Location, Operator, Primitive, Token, UIntSizes, Value};
 
 pub trait TransposeRef<'a, T, E: std::error::Error> {
     fn transpose(self) -> Result<Option<&'a T>, &'a E>;
@@ -20,20 +33,177 @@ impl<'lex> TransposeRef<'lex, Token<'lex>, Error> for Option<&'lex Result<Token<
     }
 }
 
+/// byte-order mark some editors write at the start of utf-8 files. it is
+/// skipped, but still counted in locations, which editors measure from the
+/// very start of the file
+pub const UTF8_BOM: &str = "\u{feff}";
+
+/// the repl mode lets the parser treat the end of the input as the end of
+/// an expression, so a line doesn't need to be terminated by a semicolon
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum LexerMode {
+    #[default]
+    Source,
+    Repl,
+}
+
 pub struct Lexer<'lex> {
     pos: usize,
     source: &'lex str,
     pub complete_source: &'lex str,
     peeked: Option<Result<Token<'lex>, Error>>,
+    preserve_comments: bool,
+    mode: LexerMode,
+    position_index: Option<PositionIndex>,
+    source_code: Option<Arc<dyn SourceCode>>,
+    byte_offset: usize,
+    line_offset: u32,
+    interner: Option<&'lex dyn Intern<'lex>>,
+    /// doc comments skipped since the last token
+    pending_docs: Vec<&'lex str>,
+    /// doc comments written right before the token starting at the byte
+    /// they are kept with
+    docs: Option<(usize, Vec<&'lex str>)>,
+    /// tokens given back instead of the ones lexed from the source
+    replayed: Option<std::vec::IntoIter<Token<'lex>>>,
+}
+
+/// lexes the whole of `source`, stopping at the first error
+pub fn tokenize_all(source: &str) -> Result<Vec<Token<'_>>, Error> {
+    Lexer::new(source).collect()
+}
+
+self_cell!(
+    /// a lexer over the contents of a file, which it owns. tokens borrow
+    /// from the contents, so they are reached through `with_dependent_mut`
+    pub struct FileLexer {
+        owner: Arc<str>,
+        #[not_covariant]
+        dependent: Lexer,
+    }
+);
+
+impl FileLexer {
+    pub fn source(&self) -> &str {
+        self.borrow_owner()
+    }
+}
+
+impl Lexer<'_> {
+    /// lexes the file at `path`, with errors reported under its name
+    pub fn from_file(path: &Path) -> Result<FileLexer, io::Error> {
+        let source: Arc<str> = std::fs::read_to_string(path)?.into();
+        let named = NamedSource::new(path.display().to_string(), Arc::clone(&source));
+        Ok(FileLexer::new(source, |source| {
+            Lexer::new(source).with_source_code(Arc::new(named))
+        }))
+    }
 }
 
 impl<'lex> Lexer<'lex> {
     pub fn new(source: &'lex str) -> Self {
+        let start = bom_len(source);
         Self {
-            pos: 0,
-            source,
+            pos: start,
+            source: &source[start..],
             complete_source: source,
             peeked: None,
+            preserve_comments: false,
+            mode: LexerMode::default(),
+            position_index: None,
+            source_code: None,
+            byte_offset: 0,
+            line_offset: 0,
+            interner: None,
+            pending_docs: vec![],
+            docs: None,
+            replayed: None,
+        }
+    }
+
+    /// lexes `source` as if it started `byte_offset` bytes and `line_offset`
+    /// lines into another file, like code embedded in a string literal, so
+    /// locations of tokens and errors point into that file instead
+    pub fn with_offset(source: &'lex str, byte_offset: usize, line_offset: u32) -> Self {
+        let lexer = Self::new(source);
+        Self {
+            pos: lexer.pos + byte_offset,
+            byte_offset,
+            line_offset,
+            ..lexer
+        }
+    }
+
+    pub fn for_repl(source: &'lex str) -> Self {
+        Self {
+            mode: LexerMode::Repl,
+            ..Self::new(source)
+        }
+    }
+
+    pub fn mode(&self) -> LexerMode {
+        self.mode
+    }
+
+    /// builds a `PositionIndex` for the whole source, so locations of tokens
+    /// can be turned into lines and columns cheaply
+    pub fn with_position_index(mut self) -> Self {
+        let index = PositionIndex::new(self.complete_source).with_offset(self.byte_offset, self.line_offset);
+        self.position_index = Some(index);
+        self
+    }
+
+    /// source errors are reported against, instead of the source being
+    /// lexed. used when the source is made of many files, like with a
+    /// `MultiFileSource`
+    pub fn with_source_code(mut self, source_code: Arc<dyn SourceCode>) -> Self {
+        self.source_code = Some(source_code);
+        self
+    }
+
+    /// interns every identifier into `interner`, so equal identifiers share
+    /// the same pointer, even across lexers of different sources. the names
+    /// of identifiers then point into the interner rather than the source
+    pub fn with_interning(mut self, interner: &'lex dyn Intern<'lex>) -> Self {
+        self.interner = Some(interner);
+        self
+    }
+
+    pub fn position_index(&self) -> Option<&PositionIndex> {
+        self.position_index.as_ref()
+    }
+
+    /// when enabled, comments are emitted as `LineComment` and `BlockComment`
+    /// tokens, and a shebang as `Shebang`, instead of being skipped, which
+    /// tooling like the formatter needs to put them back where they belong
+    pub fn preserve_comments(mut self, preserve: bool) -> Self {
+        self.preserve_comments = preserve;
+        self
+    }
+
+    /// gives back `tokens` instead of lexing the source, which is then only
+    /// read for what tokens don't hold themselves, like the expressions
+    /// interpolated in a string. tokens are expected to be located where
+    /// their text is in the source
+    pub fn replaying(mut self, tokens: Vec<Token<'lex>>) -> Self {
+        self.replayed = Some(tokens.into_iter());
+        self
+    }
+
+    /// lexes only `range` of the complete source, keeping token locations
+    /// relative to the whole of it. used to lex the expressions embedded in
+    /// interpolated strings
+    pub fn sub_lexer(&self, range: std::ops::Range<usize>) -> Lexer<'lex> {
+        let end = range.end - self.byte_offset;
+        let start = (range.start - self.byte_offset)
+            .max(bom_len(self.complete_source))
+            .min(end);
+        Self {
+            pos: start + self.byte_offset,
+            source: &self.complete_source[start..end],
+            source_code: self.source_code.clone(),
+            interner: self.interner,
+            ..Self::with_offset(self.complete_source, self.byte_offset, self.line_offset)
         }
     }
 
@@ -41,6 +211,17 @@ impl<'lex> Lexer<'lex> {
         self.complete_source
     }
 
+    /// bytes every location is shifted by, see `with_offset`
+    pub fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+
+    /// byte right past the last one being lexed. it is the end of the
+    /// complete source, unless this lexes only a range of it
+    pub fn end(&self) -> usize {
+        self.pos + self.source.len()
+    }
+
     pub fn peek(&mut self) -> Option<&Result<Token<'lex>, Error>> {
         if self.peeked.is_none() {
             self.peeked = self.next();
@@ -53,6 +234,32 @@ impl<'lex> Lexer<'lex> {
         self.peek().is_none()
     }
 
+    /// the doc comments `doc_comments` would give, without taking them
+    pub fn peek_doc_comments(&mut self) -> &[&'lex str] {
+        let Some(Ok(token)) = self.peek() else {
+            return &[];
+        };
+        let start = token.location.start_byte;
+        match &self.docs {
+            Some((before, docs)) if *before == start => docs,
+            _ => &[],
+        }
+    }
+
+    /// the doc comments written right before the next token, as written,
+    /// markers included. they are given only once
+    pub fn doc_comments(&mut self) -> Vec<&'lex str> {
+        let Some(Ok(token)) = self.peek() else {
+            return vec![];
+        };
+        let start = token.location.start_byte;
+        self.docs
+            .take()
+            .filter(|(before, _)| *before == start)
+            .map(|(_, docs)| docs)
+            .unwrap_or_default()
+    }
+
     fn make_token<T>(&mut self, tokenizable: T, size: usize) -> Token<'lex>
     where
         T: IntoToken<'lex>,
@@ -64,32 +271,43 @@ impl<'lex> Lexer<'lex> {
 
     pub fn expect(&mut self, expected: Kind<'_>) -> Result<Token<'lex>, Error> {
         let Some(token) = self.next().transpose()? else {
-            let location = self.complete_source.len() - 1..self.complete_source.len();
-            return Err(Error::from(location));
+            let location = self.end().saturating_sub(1)..self.end();
+            let mut error = self.error(location);
+            error.with_message(format!("expected `{expected}`, found the end of the input"));
+            return Err(error.at_eof());
         };
         let kind = &token.kind;
 
         if kind == &expected {
             Ok(token)
         } else {
-            Err(Error::from(token.location))
+            let mut error = self.error(token.location);
+            error.with_message(format!("expected `{expected}`, found `{kind}`"));
+            Err(error)
         }
     }
 
     pub fn expect_one_of(&mut self, expected_list: &[Kind<'_>]) -> Result<Token<'lex>, Error> {
         let token = self.next().transpose()?;
-        let kind = token.as_ref().map(|token| &token.kind);
-        let kind = kind.unwrap_or(&Kind::Eof);
+        let kinds = expected_list
+            .iter()
+            .map(|k| format!("`{k}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
 
-        if expected_list.contains(kind) {
-            Ok(token.unwrap())
-        } else {
-            let kinds = expected_list
-                .iter()
-                .map(|k| k.to_string())
-                .collect::<Vec<_>>()
-                .join(" ");
-            panic!("invalid token kind. expected one of {kinds} but got {kind}");
+        match token {
+            Some(token) if expected_list.contains(&token.kind) => Ok(token),
+            Some(token) => {
+                let mut error = self.error(token.location);
+                error.with_message(format!("expected one of {kinds}, found `{}`", token.kind));
+                Err(error)
+            }
+            None => {
+                let location = self.end().saturating_sub(1)..self.end();
+                let mut error = self.error(location);
+                error.with_message(format!("expected one of {kinds}, found the end of the input"));
+                Err(error.at_eof())
+            }
         }
     }
 }
@@ -98,11 +316,28 @@ impl<'lex> Iterator for Lexer<'lex> {
     type Item = Result<Token<'lex>, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            if let Some(peeked) = self.peeked.take() {
-                return Some(peeked);
+        if let Some(peeked) = self.peeked.take() {
+            return Some(peeked);
+        }
+
+        let token = self.lex_token();
+        // doc comments belong to the token right after them
+        if let Some(Ok(token)) = &token {
+            if !self.pending_docs.is_empty() {
+                self.docs = Some((token.location.start_byte, std::mem::take(&mut self.pending_docs)));
             }
+        }
+        token
+    }
+}
+
+impl<'lex> Lexer<'lex> {
+    fn lex_token(&mut self) -> Option<Result<Token<'lex>, Error>> {
+        if let Some(replayed) = &mut self.replayed {
+            return replayed.next().map(Ok);
+        }
 
+        loop {
             let mut chars = self.source.chars().peekable();
             let c = chars.next()?;
 
@@ -117,48 +352,59 @@ impl<'lex> Iterator for Lexer<'lex> {
                 // DOUBLE TOKENS
                 // ----------------------------------------------------
                 ('+', Some('=')) => Some(Ok(self.make_token(Operator::PlusEqual, 2))),
+                ('+', Some('+')) => Some(Ok(self.make_token(Operator::PlusPlus, 2))),
                 ('=', Some('=')) => Some(Ok(self.make_token(Operator::EqualEqual, 2))),
                 ('=', Some('>')) => Some(Ok(self.make_token(Operator::ThickArrow, 2))),
                 ('*', Some('=')) => Some(Ok(self.make_token(Operator::StarEqual, 2))),
                 ('/', Some('=')) => Some(Ok(self.make_token(Operator::SlashEqual, 2))),
                 ('!', Some('=')) => Some(Ok(self.make_token(Operator::NotEqual, 2))),
                 ('<', Some('=')) => Some(Ok(self.make_token(Operator::LessEqual, 2))),
+                ('<', Some('-')) => Some(Ok(self.make_token(Operator::LeftArrow, 2))),
                 ('>', Some('=')) => Some(Ok(self.make_token(Operator::GreaterEqual, 2))),
                 ('-', Some('=')) => Some(Ok(self.make_token(Operator::MinusEqual, 2))),
+                ('-', Some('-')) => Some(Ok(self.make_token(Operator::MinusMinus, 2))),
+                ('-', Some('>')) => Some(Ok(self.make_token(Operator::ThinArrow, 2))),
                 ('&', Some('&')) => Some(Ok(self.make_token(Operator::And, 2))),
                 ('|', Some('|')) => Some(Ok(self.make_token(Operator::Or, 2))),
+                (':', Some(':')) => Some(Ok(self.make_token(Operator::ColonColon, 2))),
+                ('?', Some(':')) => Some(Ok(self.make_token(Operator::Elvis, 2))),
                 ('-', Some(c)) if c.is_numeric() => Some(self.lex_numerals()),
+                // a shebang is only a shebang on the first line of the source,
+                // and it is skipped up to and including its newline
+                ('#', Some('!')) if self.pos == self.byte_offset + bom_len(self.complete_source) => {
+                    let eol_location = self.source.find('\n').unwrap_or(self.source.len());
+                    if self.preserve_comments {
+                        let line = &self.source[..eol_location];
+                        let token = self.make_token(Kind::Shebang(line.into()), eol_location);
+                        self.advance_by(self.source.len().min(1));
+                        break Some(Ok(token));
+                    }
+                    self.advance_by((eol_location + 1).min(self.source.len()));
+                    continue;
+                }
                 // we ignore a comment until the end of the line
                 ('/', Some('/')) => {
-                    let eol_location = self.source.find(|c| matches!(c, '\n')).unwrap_or(self.source.len());
+                    let eol_location = self.source.find('\n').unwrap_or(self.source.len());
+                    let comment = &self.source[..eol_location];
+                    if self.preserve_comments {
+                        break Some(Ok(self.make_token(Kind::LineComment(comment.into()), eol_location)));
+                    }
+                    if is_doc_comment(comment) {
+                        self.pending_docs.push(comment);
+                    }
                     self.advance_by(eol_location);
                     continue;
                 }
-                // for multiline comments, we ignore until we find the closing pattern
+                // for multiline comments, we ignore until we find the closing
+                // pattern, which has to balance every nested opening one
                 ('/', Some('*')) => {
-                    // we start past the next 2 characters as we know they are `/*`
-                    let mut pos = 2;
-                    let mut open_comments = 1;
-                    loop {
-                        if pos > self.source.len() - 1 {
-                            break;
-                        }
-
-                        let curr = self.source.chars().nth(pos);
-                        let next = self.source.chars().nth(pos + 1);
-
-                        match (curr, next) {
-                            (Some('*'), Some('/')) => open_comments -= 1,
-                            (Some('/'), Some('*')) => open_comments += 1,
-                            _ => (),
-                        }
-
-                        pos += 1;
-
-                        if open_comments == 0 {
-                            pos += 1;
-                            break;
-                        }
+                    let pos = block_comment_end(self.source);
+                    let comment = &self.source[..pos];
+                    if self.preserve_comments {
+                        break Some(Ok(self.make_token(Kind::BlockComment(comment.into()), pos)));
+                    }
+                    if is_doc_comment(comment) {
+                        self.pending_docs.push(comment);
                     }
 
                     self.advance_by(pos);
@@ -187,10 +433,21 @@ impl<'lex> Iterator for Lexer<'lex> {
                 ('<', _) => Some(Ok(self.make_token(Operator::Less, 1))),
                 ('>', _) => Some(Ok(self.make_token(Operator::Greater, 1))),
                 ('-', _) => Some(Ok(self.make_token(Operator::Minus, 1))),
+                ('#', _) => Some(Ok(self.make_token(Operator::Hash, 1))),
+                ('$', _) => Some(Ok(self.make_token(Operator::Dollar, 1))),
+                ('?', _) => Some(Ok(self.make_token(Operator::Question, 1))),
 
+                ('r', Some('"' | '#')) => Some(self.lex_raw_string()),
                 ('a'..='z' | 'A'..='Z' | '_', _) => Some(Ok(self.lex_identifier())),
                 ('0'..='9', _) => Some(self.lex_numerals()),
-                _ => Some(Ok(Kind::Eof.into_token(self.pos, self.pos))),
+                ('"', _) => Some(self.lex_string()),
+                ('\'', Some('a'..='z' | 'A'..='Z' | '_')) => Some(Ok(self.lex_lifetime())),
+                (c, _) => {
+                    let mut error = self.error(self.pos..self.pos + c.len_utf8());
+                    error.with_message(format!("unexpected character `{c}`"));
+                    self.advance_by(c.len_utf8());
+                    Some(Err(error))
+                }
             };
         }
     }
@@ -204,8 +461,14 @@ impl<'lex> Lexer<'lex> {
             .unwrap_or(self.source.len());
 
         let identifier = &self.source[..next_whitespace];
+        let kind = match (Kind::identifier_from(identifier), self.interner) {
+            (Kind::Value(Value::Ident(_)), Some(interner)) => {
+                Kind::Value(Value::Ident(Cow::Borrowed(interner.intern(identifier))))
+            }
+            (kind, _) => kind,
+        };
 
-        self.make_token(Kind::identifier_from(identifier), next_whitespace)
+        self.make_token(kind, next_whitespace)
     }
 
     fn lex_numerals(&mut self) -> Result<Token<'lex>, Error> {
@@ -267,16 +530,14 @@ impl<'lex> Lexer<'lex> {
         let literal = literal.replace('_', "");
 
         // numbers are allowed to potentially have a postfix specifying its size
-        // like `1234usize`
+        // like `1234usize`, which ends at the first character that can't be
+        // part of it, so `f(1u8, 2u8)` is sized as well
         let end_of_postfix = self
             .source
-            .find(|c: char| c.is_whitespace())
+            .find(|c: char| !c.is_ascii_alphanumeric())
             .unwrap_or(self.source.len());
 
-        let mut postfix = &self.source[..end_of_postfix];
-        if postfix.ends_with(';') {
-            postfix = postfix.trim_matches(';');
-        }
+        let postfix = &self.source[..end_of_postfix];
 
         let is_signed = literal.contains('-');
         let is_float = literal.contains('.');
@@ -285,7 +546,7 @@ impl<'lex> Lexer<'lex> {
                 value: match literal.parse() {
                     Ok(numeral) => numeral,
                     Err(_) => {
-                        return Err(Error::from(self.pos - bytes_eaten..self.pos));
+                        return Err(self.error(self.pos - bytes_eaten..self.pos));
                     }
                 },
                 size: IntSizes::try_from(postfix).ok(),
@@ -293,14 +554,14 @@ impl<'lex> Lexer<'lex> {
             (false, false) => Primitive::UInt {
                 value: match literal.parse() {
                     Ok(numeral) => numeral,
-                    Err(_) => return Err(Error::from(self.pos - bytes_eaten..self.pos)),
+                    Err(_) => return Err(self.error(self.pos - bytes_eaten..self.pos)),
                 },
                 size: UIntSizes::try_from(postfix).ok(),
             },
             (true, _) => Primitive::Float {
                 value: match literal.parse() {
                     Ok(numeral) => numeral,
-                    Err(_) => return Err(Error::from(self.pos - bytes_eaten..self.pos)),
+                    Err(_) => return Err(self.error(self.pos - bytes_eaten..self.pos)),
                 },
                 size: FloatSizes::try_from(postfix).ok(),
             },
@@ -321,15 +582,147 @@ impl<'lex> Lexer<'lex> {
         Ok(token.into_token(start_byte, self.pos))
     }
 
+    /// lexes `r"..."` or `r##"..."##`. the string ends at the first quote
+    /// followed by as many `#` as there are after its `r`, so any quote
+    /// followed by fewer of them, like the `"#` of a nested `r#"..."#`, is
+    /// part of the contents. an `r` followed by `#` but not by a quote is
+    /// just a name
+    fn lex_raw_string(&mut self) -> Result<Token<'lex>, Error> {
+        let start_byte = self.pos;
+        let hashes = self.source[1..].bytes().take_while(|&byte| byte == b'#').count();
+        let open = 1 + hashes;
+        if !self.source[open..].starts_with('"') {
+            return Ok(self.lex_identifier());
+        }
+
+        let closing = format!("\"{}", "#".repeat(hashes));
+        let Some(end) = self.source[open + 1..].find(&closing) else {
+            let mut error = self.error(start_byte..start_byte + self.source.len());
+            error.with_message("unterminated raw string literal".into());
+            self.advance_by(self.source.len());
+            return Err(error.at_eof());
+        };
+
+        let contents = &self.source[open + 1..open + 1 + end];
+        let kind = Kind::Value(Value::RawString {
+            contents: contents.into(),
+            hashes,
+        });
+        Ok(self.make_token(kind, open + 1 + end + closing.len()))
+    }
+
+    /// lexes `'a`. the quote is part of the location of the token, but not
+    /// of its name
+    fn lex_lifetime(&mut self) -> Token<'lex> {
+        let end = self.source[1..]
+            .find(|c| !matches!(c, 'a'..='z' | 'A'..='Z' | '_' | '0'..='9'))
+            .map_or(self.source.len(), |end| end + 1);
+
+        let name = &self.source[1..end];
+        self.make_token(Kind::Lifetime(name.into()), end)
+    }
+
+    fn lex_string(&mut self) -> Result<Token<'lex>, Error> {
+        let start_byte = self.pos;
+
+        // we skip the opening quote, and the contents don't include the quotes
+        let Some(end) = string_end(&self.source[1..]) else {
+            let mut error = self.error(start_byte..start_byte + self.source.len());
+            error.with_message("unterminated string literal".into());
+            self.advance_by(self.source.len());
+            return Err(error.at_eof());
+        };
+
+        let contents = &self.source[1..end + 1];
+        Ok(self.make_token(Kind::Value(Value::String(contents.into())), end + 2))
+    }
+
+    fn error(&self, location: impl Into<Location>) -> Error {
+        Error::from(location.into()).with_source_code(self.source_code.clone())
+    }
+
     fn advance_by(&mut self, amount: usize) {
         self.source = &self.source[amount..];
         self.pos += amount;
     }
 }
 
+/// whether `comment` documents what comes after it, which is the case for
+/// `/// line` and `/** block */`, but not for `////` or `/***` rulers, nor
+/// for an empty `/**/`
+pub fn is_doc_comment(comment: &str) -> bool {
+    if let Some(rest) = comment.strip_prefix("///") {
+        return !rest.starts_with('/');
+    }
+    comment
+        .strip_prefix("/**")
+        .is_some_and(|rest| !rest.starts_with(['*', '/']))
+}
+
+/// byte right past the `*/` closing the block comment `source` starts
+/// with, or the end of the source when it is never closed
+fn block_comment_end(source: &str) -> usize {
+    let bytes = source.as_bytes();
+    // we start past the `/*` opening the comment
+    let mut pos = 2;
+    let mut open_comments = 1;
+
+    while pos < bytes.len() {
+        match (bytes[pos], bytes.get(pos + 1)) {
+            (b'*', Some(b'/')) => {
+                open_comments -= 1;
+                pos += 2;
+                if open_comments == 0 {
+                    return pos;
+                }
+            }
+            (b'/', Some(b'*')) => {
+                open_comments += 1;
+                pos += 2;
+            }
+            _ => pos += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+fn bom_len(source: &str) -> usize {
+    if source.starts_with(UTF8_BOM) {
+        UTF8_BOM.len()
+    } else {
+        0
+    }
+}
+
+/// finds the quote closing a string, given everything after its opening
+/// quote. quotes inside of an interpolated expression belong to strings
+/// nested in it, so they don't close the outer string
+fn string_end(source: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut pos = 0;
+
+    while let Some(c) = source[pos..].chars().next() {
+        match c {
+            '"' if depth == 0 => return Some(pos),
+            '"' => pos += string_end(&source[pos + 1..])? + 1,
+            '\\' => pos += source[pos + 1..].chars().next().map_or(0, char::len_utf8),
+            // `{{` is an escaped brace, not an interpolation
+            '{' if depth == 0 && source[pos + 1..].starts_with('{') => pos += 1,
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        pos += c.len_utf8();
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::interner::{Bump, Interner};
 
     fn make_sut(source: &str) -> Lexer<'_> {
         Lexer::new(source)
@@ -391,7 +784,10 @@ mod tests {
 
     #[test]
     fn lexing_builtin_identifiers() {
-        let source = ["var", "const", "match", "if", "else", "fun", "struct", "enum", "return"];
+        let source = [
+            "var", "const", "match", "if", "else", "fun", "struct", "enum", "impl", "for", "let", "in", "unsafe",
+            "return",
+        ];
 
         let source = source.join(" ");
 
@@ -424,4 +820,372 @@ mod tests {
 
         insta::assert_debug_snapshot!(calculate_circumference_function);
     }
+
+    #[test]
+    fn lexing_preserved_comments() {
+        let source = [
+            "// leading comment",
+            "const a = 10; // trailing comment",
+            "/* block /* nested */ comment */",
+            "a",
+        ];
+
+        let source = source.join("\n");
+
+        let mut tokens = vec![];
+        for token in make_sut(&source).preserve_comments(true) {
+            tokens.push(token.unwrap());
+        }
+
+        insta::assert_debug_snapshot!(tokens);
+    }
+
+    #[test]
+    fn lexing_strings() {
+        let source = r#"const a = "hello, {name("}")}!" + "with \"quotes\"";"#;
+
+        let mut tokens = vec![];
+        for token in make_sut(source) {
+            tokens.push(token.unwrap());
+        }
+
+        insta::assert_debug_snapshot!(tokens);
+    }
+
+    #[test]
+    fn unterminated_strings_are_errors() {
+        let source = r#"const a = "hello {"}"#;
+
+        let result = make_sut(source).collect::<Result<Vec<_>, _>>();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn raw_strings_end_at_as_many_hashes_as_they_open_with() {
+        let source = r####"r##"He said "hello" and r#"hi"#"## r"\n{x}" r#x"####;
+        let tokens = tokenize_all(source).unwrap();
+
+        assert_eq!(
+            tokens[0].kind,
+            Kind::Value(Value::RawString {
+                contents: r##"He said "hello" and r#"hi"#"##.into(),
+                hashes: 2,
+            })
+        );
+        assert_eq!(tokens[0].location, Location::new(0, 34));
+        assert_eq!(
+            tokens[1].kind,
+            Kind::Value(Value::RawString {
+                contents: r"\n{x}".into(),
+                hashes: 0,
+            })
+        );
+        // without a quote after the hashes, it's just a name
+        assert_eq!(tokens[2].kind, Kind::Value(Value::Ident("r".into())));
+        assert_eq!(tokens[3].kind, Kind::Op(Operator::Hash));
+
+        assert!(tokenize_all(r###"r##"ends too soon"#"###).is_err());
+    }
+
+    #[test]
+    fn operators_display_as_written() {
+        let operators = [
+            Operator::LeftParen,
+            Operator::RightParen,
+            Operator::LeftBracket,
+            Operator::RightBracket,
+            Operator::LeftBrace,
+            Operator::RightBrace,
+            Operator::Comma,
+            Operator::Dot,
+            Operator::Minus,
+            Operator::MinusEqual,
+            Operator::MinusMinus,
+            Operator::Plus,
+            Operator::PlusEqual,
+            Operator::PlusPlus,
+            Operator::Equal,
+            Operator::ThickArrow,
+            Operator::EqualEqual,
+            Operator::Star,
+            Operator::StarEqual,
+            Operator::Ampersand,
+            Operator::Slash,
+            Operator::SlashEqual,
+            Operator::Colon,
+            Operator::ColonColon,
+            Operator::SemiColon,
+            Operator::Bang,
+            Operator::Less,
+            Operator::LessEqual,
+            Operator::Greater,
+            Operator::GreaterEqual,
+            Operator::NotEqual,
+            Operator::And,
+            Operator::Or,
+            Operator::Hash,
+            Operator::Dollar,
+            Operator::Elvis,
+            Operator::Question,
+            Operator::LeftArrow,
+            Operator::ThinArrow,
+        ];
+
+        // lexing what an operator displays as gives back the same operator
+        for operator in operators {
+            let source = operator.to_string();
+            let token = make_sut(&source).next().unwrap().unwrap();
+            assert_eq!(token.kind, Kind::Op(operator), "{source}");
+            assert_eq!(token.kind.to_string(), source);
+        }
+    }
+
+    #[test]
+    fn kinds_display_as_written() {
+        let source =
+            r##"var const match if else fun struct enum impl for return where 'a name 10 "text {x}" r#"raw"#"##;
+
+        let displayed = make_sut(source)
+            .map(|token| token.unwrap().kind.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(displayed.join(" "), source);
+    }
+
+    #[test]
+    fn kinds_are_named_without_what_they_hold() {
+        let names = tokenize_all(r#"fun name 10 -1 1.5 true "text" +"#)
+            .unwrap()
+            .iter()
+            .map(|token| token.kind.name())
+            .collect::<Vec<_>>();
+
+        assert_eq!(names, ["Fun", "Ident", "UInt", "Int", "Float", "Bool", "String", "Op"]);
+        assert!(tokenize_all("a @ b").is_err());
+    }
+
+    #[test]
+    fn lifetimes_are_lexed_without_their_quote() {
+        let tokens = tokenize_all("&'a_1 T").unwrap();
+
+        assert_eq!(tokens[1].kind, Kind::Lifetime("a_1".into()));
+        assert_eq!(tokens[1].location, Location::new(1, 5));
+        assert!(tokenize_all("' a").is_err());
+    }
+
+    #[test]
+    fn shebang_lines_are_skipped() {
+        let source = "#!/usr/bin/env rmb\nfun main() {}";
+
+        let tokens = make_sut(source).map(|token| token.unwrap()).collect::<Vec<_>>();
+        assert_eq!(tokens[0].kind, Kind::Fun);
+        assert_eq!(tokens[0].location, Location::new(19, 22));
+
+        let mut lexer = make_sut(source).preserve_comments(true);
+        let shebang = lexer.next().unwrap().unwrap();
+        assert_eq!(shebang.kind, Kind::Shebang("#!/usr/bin/env rmb".into()));
+        assert_eq!(shebang.location, Location::new(0, 18));
+        assert_eq!(lexer.next().unwrap().unwrap().location, Location::new(19, 22));
+
+        // only the very first line can be a shebang
+        let mut lexer = make_sut(" #!/usr/bin/env rmb");
+        assert_eq!(lexer.next().unwrap().unwrap().kind, Kind::Op(Operator::Hash));
+        assert!(make_sut("#!/usr/bin/env rmb").next().is_none());
+    }
+
+    #[test]
+    fn byte_order_marks_are_skipped() {
+        let source = "\u{feff}fun main() { \"\u{feff}\"; }";
+
+        let tokens = make_sut(source).map(|token| token.unwrap()).collect::<Vec<_>>();
+        assert_eq!(tokens[0].kind, Kind::Fun);
+        assert_eq!(tokens[0].location, Location::new(3, 6));
+        // only the one starting the source is skipped
+        assert_eq!(tokens[5].kind, Kind::Value(Value::String("\u{feff}".into())));
+
+        let lexer = make_sut(source);
+        let tokens = lexer.sub_lexer(0..source.len()).collect::<Vec<_>>();
+        assert_eq!(tokens[0].as_ref().unwrap().location, Location::new(3, 6));
+
+        let tokens = make_sut("\u{feff}#!/usr/bin/env rmb\nfun").collect::<Vec<_>>();
+        assert_eq!(tokens[0].as_ref().unwrap().location, Location::new(22, 25));
+        assert!(make_sut("\u{feff}").next().is_none());
+    }
+
+    #[test]
+    fn unknown_characters_are_errors() {
+        let tokens = make_sut("a @ § b").collect::<Vec<_>>();
+        assert_eq!(tokens.len(), 4);
+
+        let error = tokens[1].as_ref().unwrap_err();
+        assert_eq!(error.message(), "unexpected character `@`");
+        assert_eq!(error.location(), Location::new(2, 3));
+        assert_eq!(tokens[2].as_ref().unwrap_err().location(), Location::new(4, 6));
+        assert_eq!(tokens[3].as_ref().unwrap().location, Location::new(7, 8));
+    }
+
+    #[test]
+    fn offsets_shift_every_location() {
+        // `a @\nb` embedded 40 bytes and 3 lines into some other file
+        let mut lexer = Lexer::with_offset("a @\nb", 40, 3).with_position_index();
+        let tokens = lexer.by_ref().collect::<Vec<_>>();
+
+        assert_eq!(tokens[0].as_ref().unwrap().location, Location::new(40, 41));
+        assert_eq!(tokens[1].as_ref().unwrap_err().location(), Location::new(42, 43));
+        assert_eq!(tokens[2].as_ref().unwrap().location, Location::new(44, 45));
+        assert_eq!(lexer.end(), 45);
+
+        let index = lexer.position_index().unwrap();
+        assert_eq!(index.line_col(44), (4, 0));
+        assert_eq!(index.line_start(4), Some(44));
+
+        let sub_lexer = lexer.sub_lexer(44..45).collect::<Vec<_>>();
+        assert_eq!(sub_lexer[0].as_ref().unwrap().location, Location::new(44, 45));
+    }
+
+    #[test]
+    fn block_comments_with_multibyte_chars() {
+        let source = "/* ééé /* ü */ */ a /*é";
+
+        let tokens = make_sut(source).map(|token| token.unwrap()).collect::<Vec<_>>();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].location, Location::new(22, 23));
+
+        let comments = make_sut(source)
+            .preserve_comments(true)
+            .map(|token| token.unwrap().location)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            comments,
+            [Location::new(0, 21), Location::new(22, 23), Location::new(24, 28)]
+        );
+    }
+
+    #[test]
+    fn sized_numerals_before_punctuation() {
+        let kinds = make_sut("f(1u8, 2.5f32)")
+            .map(|token| token.unwrap().kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds[2],
+            Kind::Value(Value::Primitive(Primitive::UInt {
+                value: 1,
+                size: Some(UIntSizes::U8)
+            }))
+        );
+        assert_eq!(kinds[3], Kind::Op(Operator::Comma));
+        assert_eq!(
+            kinds[4],
+            Kind::Value(Value::Primitive(Primitive::Float {
+                value: 2.5,
+                size: Some(FloatSizes::F32)
+            }))
+        );
+        assert_eq!(kinds.len(), 6);
+    }
+
+    #[test]
+    fn booleans_are_primitives() {
+        let kinds = make_sut("true false truthy")
+            .map(|token| token.unwrap().kind)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            [
+                Kind::Value(Value::Primitive(Primitive::Bool(true))),
+                Kind::Value(Value::Primitive(Primitive::Bool(false))),
+                Kind::Value(Value::Ident("truthy".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn lexing_a_file() {
+        let path = std::env::temp_dir().join(format!("kura-lexer-{}.rmb", std::process::id()));
+        std::fs::write(&path, "const greeting = \"hi\";\nvar @ = 1;\n").unwrap();
+
+        let mut lexer = Lexer::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(lexer.source().starts_with("const greeting"));
+
+        // the contents outlive the file
+        let rendered = lexer.with_dependent_mut(|_, lexer| {
+            let results = lexer.collect::<Vec<_>>();
+            let tokens = results
+                .iter()
+                .filter_map(|token| token.as_ref().ok())
+                .collect::<Vec<_>>();
+            let kinds = tokens.iter().map(|token| token.kind.to_string()).collect::<Vec<_>>();
+            assert_eq!(kinds[..5], ["const", "greeting", "=", "\"hi\"", ";"]);
+            assert_eq!(tokens[1].location, Location::new(6, 14));
+
+            let error = results.iter().find_map(|token| token.as_ref().err()).unwrap();
+            let mut rendered = String::new();
+            miette::NarratableReportHandler::new()
+                .render_report(&mut rendered, error)
+                .unwrap();
+            rendered
+        });
+        assert!(
+            rendered.contains(&format!("snippet for {}", path.display())),
+            "{rendered}"
+        );
+
+        let missing = Lexer::from_file(&path).err().unwrap();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn replayed_tokens_are_given_back_as_they_are() {
+        // `2.0` is written `2` by its token, which would lex as an integer
+        let float = Token::new(
+            Kind::Value(Value::Primitive(Primitive::Float { value: 2.0, size: None })),
+            Location::new(0, 1),
+        );
+        let tokens = vec![float, Token::new(Kind::Op(Operator::Star), Location::new(2, 3))];
+        let replayed = make_sut("2 *").replaying(tokens.clone()).map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(replayed, tokens);
+    }
+
+    #[test]
+    fn identifiers_are_interned() {
+        fn idents(lexer: Lexer<'_>) -> Vec<&str> {
+            lexer
+                .filter_map(|token| match token.unwrap().kind {
+                    Kind::Value(Value::Ident(Cow::Borrowed(name))) => Some(name),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        let arena = Bump::new();
+        let interner = Interner::new(&arena);
+        let first = idents(Lexer::new("var x = len + x * len;").with_interning(&interner));
+        let second = idents(Lexer::new("fun f(x: i32) { x }").with_interning(&interner));
+        assert_eq!(first, ["x", "len", "x", "len"]);
+        assert_eq!(second, ["f", "x", "i32", "x"]);
+
+        // equal names point at the copy the interner owns, even across sources
+        assert!(std::ptr::eq(first[0], first[2]));
+        assert!(std::ptr::eq(first[1], first[3]));
+        assert!(std::ptr::eq(first[0], second[1]));
+        assert!(std::ptr::eq(first[0], interner.intern("x")));
+        // keywords aren't interned
+        assert_eq!(interner.len(), 4);
+
+        // interned names outlive the sources they were lexed from
+        let name = {
+            let source = String::from("fun short_lived() {}");
+            let name = idents(Lexer::new(&source).with_interning(&interner))[0];
+            interner.intern(name)
+        };
+        assert_eq!(name, "short_lived");
+        assert!(std::ptr::eq(name, interner.intern("short_lived")));
+
+        // without an interner, every name points into its own source
+        let plain = idents(Lexer::new("x + x"));
+        assert!(!std::ptr::eq(plain[0], plain[1]));
+    }
 }
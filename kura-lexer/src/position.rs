@@ -0,0 +1,110 @@
+/// maps byte offsets into `(line, column)` pairs without scanning the source
+/// for every lookup. both line and column are zero based, and columns are
+/// counted in bytes from the start of the line
+#[derive(Debug, Clone)]
+pub struct PositionIndex {
+    // byte position of every `\n` in the source, in order
+    newlines: Vec<usize>,
+    len: usize,
+    byte_offset: usize,
+    line_offset: u32,
+}
+
+impl PositionIndex {
+    pub fn new(source: &str) -> Self {
+        let newlines = source
+            .bytes()
+            .enumerate()
+            .filter_map(|(idx, byte)| (byte == b'\n').then_some(idx))
+            .collect();
+
+        Self {
+            newlines,
+            len: source.len(),
+            byte_offset: 0,
+            line_offset: 0,
+        }
+    }
+
+    /// for a source that starts `byte_offset` bytes and `line_offset` lines
+    /// into another file. offsets are taken and given relative to that file,
+    /// but columns in the first line are still counted from the start of the
+    /// source
+    pub fn with_offset(mut self, byte_offset: usize, line_offset: u32) -> Self {
+        self.byte_offset = byte_offset;
+        self.line_offset = line_offset;
+        self
+    }
+
+    /// offsets past the end of the source are clamped to the end of it. a
+    /// `\n` belongs to the line it terminates
+    pub fn line_col(&self, offset: usize) -> (u32, u32) {
+        let offset = offset.saturating_sub(self.byte_offset).min(self.len);
+        // amount of newlines strictly before `offset` is the line we are in
+        let line = self.newlines.partition_point(|newline| *newline < offset);
+        let line_start = match line {
+            0 => 0,
+            line => self.newlines[line - 1] + 1,
+        };
+
+        (line as u32 + self.line_offset, (offset - line_start) as u32)
+    }
+
+    /// byte offset where `line` starts, if the source has that many lines
+    pub fn line_start(&self, line: u32) -> Option<usize> {
+        let start = match line.checked_sub(self.line_offset)? as usize {
+            0 => Some(0),
+            line => self.newlines.get(line - 1).map(|newline| newline + 1),
+        };
+        start.map(|start| start + self.byte_offset)
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.newlines.len() + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offsets_inside_lines() {
+        let index = PositionIndex::new("const a = 1;\nconst b = 2;\n\nb");
+
+        assert_eq!(index.line_col(0), (0, 0));
+        assert_eq!(index.line_col(6), (0, 6));
+        assert_eq!(index.line_col(13), (1, 0));
+        assert_eq!(index.line_col(19), (1, 6));
+        assert_eq!(index.line_col(26), (2, 0));
+        assert_eq!(index.line_col(27), (3, 0));
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(index.line_start(3), Some(27));
+        assert_eq!(index.line_start(4), None);
+    }
+
+    #[test]
+    fn offset_at_newline() {
+        let index = PositionIndex::new("ab\ncd");
+
+        assert_eq!(index.line_col(2), (0, 2));
+        assert_eq!(index.line_col(3), (1, 0));
+    }
+
+    #[test]
+    fn offset_past_eof() {
+        let index = PositionIndex::new("ab\ncd");
+
+        assert_eq!(index.line_col(5), (1, 2));
+        assert_eq!(index.line_col(500), (1, 2));
+    }
+
+    #[test]
+    fn empty_source() {
+        let index = PositionIndex::new("");
+
+        assert_eq!(index.line_col(0), (0, 0));
+        assert_eq!(index.line_col(10), (0, 0));
+        assert_eq!(index.line_count(), 1);
+    }
+}
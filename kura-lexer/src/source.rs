@@ -0,0 +1,237 @@
+use miette::{MietteError, MietteSpanContents, SourceCode, SourceSpan, SpanContents};
+
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub path: String,
+    pub content: String,
+    /// byte the segment starts at on the concatenated source
+    pub start_offset: usize,
+}
+
+/// many files lexed as if they were a single source. spans are offsets on
+/// the concatenation of every file, and are reported with the name of the
+/// file they fall in and the line within that file
+#[derive(Debug, Clone, Default)]
+pub struct MultiFileSource {
+    pub segments: Vec<Segment>,
+}
+
+impl MultiFileSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// appends a file to the end of the source, returning the offset it
+    /// starts at
+    pub fn push(&mut self, path: impl Into<String>, content: impl Into<String>) -> usize {
+        let start_offset = self
+            .segments
+            .last()
+            .map_or(0, |segment| segment.start_offset + segment.content.len());
+
+        self.segments.push(Segment {
+            path: path.into(),
+            content: content.into(),
+            start_offset,
+        });
+
+        start_offset
+    }
+
+    /// every file concatenated, which is what gets lexed
+    pub fn concatenated(&self) -> String {
+        self.segments.iter().map(|segment| segment.content.as_str()).collect()
+    }
+
+    /// segment the byte at `offset` belongs to. offsets past the end belong to
+    /// the last segment
+    pub fn segment_at(&self, offset: usize) -> Option<&Segment> {
+        let idx = self
+            .segments
+            .partition_point(|segment| segment.start_offset <= offset)
+            .checked_sub(1)?;
+        self.segments.get(idx)
+    }
+}
+
+impl SourceCode for MultiFileSource {
+    fn read_span<'a>(
+        &'a self,
+        span: &SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> Result<Box<dyn SpanContents<'a> + 'a>, MietteError> {
+        let segment = self.segment_at(span.offset()).ok_or(MietteError::OutOfBounds)?;
+
+        // spans crossing into the next file are cut at the end of this one
+        let offset = span.offset() - segment.start_offset;
+        let len = span.len().min(segment.content.len().saturating_sub(offset));
+        let local = SourceSpan::new(offset.into(), len);
+
+        let contents = segment
+            .content
+            .read_span(&local, context_lines_before, context_lines_after)?;
+        let span = SourceSpan::new(
+            (contents.span().offset() + segment.start_offset).into(),
+            contents.span().len(),
+        );
+
+        Ok(Box::new(MietteSpanContents::new_named(
+            segment.path.clone(),
+            contents.data(),
+            span,
+            contents.line(),
+            contents.column(),
+            contents.line_count(),
+        )))
+    }
+}
+
+/// a piece of a larger source that isn't kept in memory, like the one a
+/// `StreamingLexer` reads. spans are offsets on the whole source, and are
+/// reported with the line and column they have on it
+#[derive(Debug, Clone)]
+pub struct SourceSnippet {
+    pub content: String,
+    /// byte the snippet starts at on the whole source
+    pub start_offset: usize,
+    /// zero based line and column the snippet starts at on the whole source
+    pub start_line: usize,
+    pub start_column: usize,
+}
+
+impl SourceCode for SourceSnippet {
+    fn read_span<'a>(
+        &'a self,
+        span: &SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> Result<Box<dyn SpanContents<'a> + 'a>, MietteError> {
+        let offset = span
+            .offset()
+            .checked_sub(self.start_offset)
+            .filter(|offset| *offset <= self.content.len())
+            .ok_or(MietteError::OutOfBounds)?;
+        let len = span.len().min(self.content.len() - offset);
+        let local = SourceSpan::new(offset.into(), len);
+
+        let contents = self
+            .content
+            .read_span(&local, context_lines_before, context_lines_after)?;
+        let span = SourceSpan::new(
+            (contents.span().offset() + self.start_offset).into(),
+            contents.span().len(),
+        );
+        // only the first line of the snippet can start past its beginning
+        let column = match contents.line() {
+            0 => contents.column() + self.start_column,
+            _ => contents.column(),
+        };
+
+        Ok(Box::new(MietteSpanContents::new(
+            contents.data(),
+            span,
+            contents.line() + self.start_line,
+            column,
+            contents.line_count(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use miette::NarratableReportHandler;
+
+    use super::*;
+    use crate::Lexer;
+
+    fn source() -> MultiFileSource {
+        let mut source = MultiFileSource::new();
+        source.push("first.rmb", "const a = 1;\nconst b = 2;\n");
+        source.push("second.rmb", "const c = 3;\nconst d = 4;\n");
+        source
+    }
+
+    #[test]
+    fn spans_are_reported_on_the_file_they_fall_in() {
+        let source = source();
+        let concatenated = source.concatenated();
+
+        let offset = concatenated.find("d = 4").unwrap();
+        let contents = source.read_span(&(offset, 1).into(), 0, 0).unwrap();
+
+        assert_eq!(contents.name(), Some("second.rmb"));
+        assert_eq!((contents.line(), contents.column()), (1, 6));
+        assert_eq!(contents.span().offset(), offset);
+        assert_eq!(std::str::from_utf8(contents.data()).unwrap(), "d");
+    }
+
+    #[test]
+    fn spans_crossing_files_are_cut() {
+        let source = source();
+        let offset = source.concatenated().find("b = 2").unwrap();
+        let contents = source.read_span(&(offset, 100).into(), 0, 0).unwrap();
+
+        assert_eq!(contents.name(), Some("first.rmb"));
+        assert_eq!(contents.line(), 1);
+        assert_eq!(std::str::from_utf8(contents.data()).unwrap(), "b = 2;\n");
+    }
+
+    #[test]
+    fn offsets_start_at_the_end_of_the_previous_file() {
+        let source = source();
+
+        assert_eq!(source.segments[1].start_offset, 26);
+        assert_eq!(source.segment_at(25).unwrap().path, "first.rmb");
+        assert_eq!(source.segment_at(26).unwrap().path, "second.rmb");
+        assert_eq!(source.segment_at(1000).unwrap().path, "second.rmb");
+    }
+
+    #[test]
+    fn snippets_are_reported_with_positions_on_the_whole_source() {
+        let source = "const a = 1;\nconst b = \"c\";\nconst d = 4;\n";
+        let start_offset = source.find("b =").unwrap();
+        let snippet = SourceSnippet {
+            content: source[start_offset..].to_string(),
+            start_offset,
+            start_line: 1,
+            start_column: 6,
+        };
+
+        let offset = source.find("\"c\"").unwrap() + 1;
+        let contents = snippet.read_span(&(offset, 1).into(), 0, 0).unwrap();
+        assert_eq!((contents.line(), contents.column()), (1, 11));
+        assert_eq!(contents.span().offset(), offset);
+
+        let offset = source.find("d =").unwrap();
+        let contents = snippet.read_span(&(offset, 1).into(), 0, 0).unwrap();
+        assert_eq!((contents.line(), contents.column()), (2, 6));
+
+        assert!(snippet.read_span(&(0, 1).into(), 0, 0).is_err());
+    }
+
+    #[test]
+    fn lexer_errors_are_reported_on_their_file() {
+        let mut files = MultiFileSource::new();
+        files.push("fine.rmb", "const a = 1;\n");
+        files.push("broken.rmb", "const b = \"unterminated;\n");
+
+        let concatenated = files.concatenated();
+        let lexer = Lexer::new(&concatenated).with_source_code(Arc::new(files));
+        let error = lexer.filter_map(Result::err).next().unwrap();
+
+        let mut rendered = String::new();
+        NarratableReportHandler::new()
+            .render_report(&mut rendered, &error)
+            .unwrap();
+
+        // lines are counted from the start of the file, not the concatenation
+        assert!(
+            rendered.contains("snippet for broken.rmb starting at line 1"),
+            "{rendered}"
+        );
+        assert!(rendered.contains("label at line 1, columns 11 to 24"), "{rendered}");
+    }
+}
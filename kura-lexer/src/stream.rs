@@ -0,0 +1,455 @@
+use std::io::{ErrorKind, Read};
+use std::sync::Arc;
+
+use miette::SourceCode;
+
+use crate::error::Error;
+use crate::source::SourceSnippet;
+use crate::token::{Kind, Location, Token};
+use crate::{Lexer, LexerMode};
+
+/// how much is read from the reader at once, unless told otherwise
+pub const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// lines of source kept before what is being lexed, to show around errors
+const CONTEXT_LINES: usize = 2;
+/// most bytes kept as context, so sources with very long lines don't make
+/// the buffer grow past what was read
+const MAX_CONTEXT: usize = 4 * 1024;
+
+/// lexes a source read from `reader` as it goes, for sources too large to
+/// be held in memory whole, like machine generated ones. only a window of
+/// the source around what is being lexed is kept, tokens still have
+/// locations relative to the start of the whole source.
+///
+/// as the window moves on, tokens own their contents, and errors come with
+/// a snippet of the source around them to be reported against
+pub struct StreamingLexer<R> {
+    reader: R,
+    chunk_size: usize,
+    /// the window of the source, starting at `buffer_start`
+    buffer: String,
+    buffer_start: usize,
+    /// zero based line and column `buffer` starts at
+    buffer_line: usize,
+    buffer_column: usize,
+    /// how much of `buffer` was already lexed
+    cursor: usize,
+    /// bytes read that don't make up a whole char yet
+    partial: Vec<u8>,
+    exhausted: bool,
+    peeked: Option<Result<Token<'static>, Error>>,
+    preserve_comments: bool,
+    source_code: Option<Arc<dyn SourceCode>>,
+}
+
+impl<R: Read> StreamingLexer<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            buffer: String::new(),
+            buffer_start: 0,
+            buffer_line: 0,
+            buffer_column: 0,
+            cursor: 0,
+            partial: vec![],
+            exhausted: false,
+            peeked: None,
+            preserve_comments: false,
+            source_code: None,
+        }
+    }
+
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// see `Lexer::preserve_comments`
+    pub fn preserve_comments(mut self, preserve: bool) -> Self {
+        self.preserve_comments = preserve;
+        self
+    }
+
+    /// source errors are reported against, instead of a snippet of the
+    /// window they were found in
+    pub fn with_source_code(mut self, source_code: Arc<dyn SourceCode>) -> Self {
+        self.source_code = Some(source_code);
+        self
+    }
+
+    pub fn peek(&mut self) -> Option<&Result<Token<'static>, Error>> {
+        if self.peeked.is_none() {
+            self.peeked = self.next();
+        }
+
+        self.peeked.as_ref()
+    }
+
+    pub fn is_empty(&mut self) -> bool {
+        self.peek().is_none()
+    }
+
+    /// part of the source currently held in memory, mostly useful
+    /// to check how much of the source is being held
+    pub fn window_location(&self) -> Location {
+        Location::new(self.buffer_start, self.buffer_start + self.buffer.len())
+    }
+
+    pub fn expect(&mut self, expected: Kind<'_>) -> Result<Token<'static>, Error> {
+        let Some(token) = self.next().transpose()? else {
+            let end = self.buffer_start + self.buffer.len();
+            let mut error = self.attach_snippet(Error::from(end.saturating_sub(1)..end));
+            error.with_message(format!("expected `{expected}`, found the end of the input"));
+            return Err(error.at_eof());
+        };
+        let kind = &token.kind;
+
+        if kind == &expected {
+            Ok(token)
+        } else {
+            let mut error = self.attach_snippet(Error::from(token.location));
+            error.with_message(format!("expected `{expected}`, found `{kind}`"));
+            Err(error)
+        }
+    }
+
+    fn lex(&mut self) -> Option<Result<Token<'static>, Error>> {
+        loop {
+            let start = self.buffer_start + self.cursor;
+            let (result, end) = {
+                let mut lexer = self.window();
+                let result = lexer.next().map(|result| result.map(Token::into_owned));
+                (result, lexer.pos)
+            };
+
+            // lexing a token can look past its end up to the next whitespace,
+            // like numerals do for their size, so a token is only known to be
+            // complete once the window goes past that
+            let complete = self.exhausted
+                || match &result {
+                    None => false,
+                    Some(Ok(_)) => self.whitespace_after(end),
+                    Some(Err(error)) => !error.is_eof() && self.whitespace_after(end.max(error.location().end_byte)),
+                };
+
+            if complete {
+                self.cursor += end - start;
+                return result.map(|result| result.map_err(|error| self.attach_snippet(error)));
+            }
+
+            if let Err(error) = self.fill() {
+                return Some(Err(error));
+            }
+        }
+    }
+
+    fn whitespace_after(&self, offset: usize) -> bool {
+        let start = (offset - self.buffer_start).min(self.buffer.len());
+        self.buffer[start..].contains(char::is_whitespace)
+    }
+
+    /// a lexer over what wasn't lexed yet, emitting locations on the whole
+    /// source. only at the start of the source it can see a shebang or a
+    /// byte-order mark
+    fn window(&self) -> Lexer<'_> {
+        let lexer = match self.buffer_start + self.cursor {
+            0 => Lexer::new(&self.buffer),
+            start => Lexer {
+                pos: start,
+                source: &self.buffer[self.cursor..],
+                ..Lexer::new(&self.buffer)
+            },
+        };
+
+        Lexer {
+            preserve_comments: self.preserve_comments,
+            mode: LexerMode::Source,
+            source_code: self.source_code.clone(),
+            ..lexer
+        }
+    }
+
+    /// reads the next chunk into the buffer, dropping what was lexed and
+    /// isn't needed as context anymore
+    fn fill(&mut self) -> Result<(), Error> {
+        self.compact();
+
+        let mut chunk = vec![0; self.chunk_size];
+        let read = loop {
+            match self.reader.read(&mut chunk) {
+                Ok(read) => break read,
+                Err(error) if error.kind() == ErrorKind::Interrupted => continue,
+                Err(error) => return Err(self.fail(format!("failed to read the source: {error}"))),
+            }
+        };
+
+        if read == 0 {
+            self.exhausted = true;
+            if !self.partial.is_empty() {
+                return Err(self.fail(String::from("source is not valid utf-8")));
+            }
+            return Ok(());
+        }
+
+        self.partial.extend_from_slice(&chunk[..read]);
+        let valid = match std::str::from_utf8(&self.partial) {
+            Ok(text) => text.len(),
+            // a char split between two chunks is completed by the next one
+            Err(error) if error.error_len().is_none() => error.valid_up_to(),
+            Err(_) => return Err(self.fail(String::from("source is not valid utf-8"))),
+        };
+
+        let text = std::str::from_utf8(&self.partial[..valid]).expect("validated above");
+        self.buffer.push_str(text);
+        self.partial.drain(..valid);
+
+        Ok(())
+    }
+
+    fn compact(&mut self) {
+        let lexed = &self.buffer[..self.cursor];
+        let mut keep = lexed.rfind('\n').map_or(0, |newline| newline + 1);
+        for _ in 0..CONTEXT_LINES {
+            if keep == 0 {
+                break;
+            }
+            keep = lexed[..keep - 1].rfind('\n').map_or(0, |newline| newline + 1);
+        }
+
+        let mut keep = keep.max(self.cursor.saturating_sub(MAX_CONTEXT));
+        while !self.buffer.is_char_boundary(keep) {
+            keep += 1;
+        }
+
+        let dropped = &self.buffer[..keep];
+        match dropped.rfind('\n') {
+            Some(newline) => {
+                self.buffer_line += dropped.matches('\n').count();
+                self.buffer_column = keep - newline - 1;
+            }
+            None => self.buffer_column += keep,
+        }
+
+        self.buffer.drain(..keep);
+        self.buffer_start += keep;
+        self.cursor -= keep;
+    }
+
+    /// stops lexing, as nothing sensible can be read past an io error
+    fn fail(&mut self, message: String) -> Error {
+        self.exhausted = true;
+        self.buffer.truncate(self.cursor);
+        self.partial.clear();
+
+        let end = self.buffer_start + self.buffer.len();
+        let mut error = Error::from(end..end);
+        error.with_message(message);
+        error
+    }
+
+    /// gives `error` the part of the window around it to be reported
+    /// against, unless a source was given for the whole of it
+    fn attach_snippet(&self, error: Error) -> Error {
+        if self.source_code.is_some() {
+            return error.with_source_code(self.source_code.clone());
+        }
+
+        // the lines following the error, that are already in the window
+        let after = error.location().end_byte.saturating_sub(self.buffer_start);
+        let mut end = after.min(self.buffer.len());
+        for _ in 0..=CONTEXT_LINES {
+            end = match self.buffer[end..].find('\n') {
+                Some(newline) => end + newline + 1,
+                None => self.buffer.len(),
+            };
+        }
+
+        let snippet = SourceSnippet {
+            content: self.buffer[..end].to_string(),
+            start_offset: self.buffer_start,
+            start_line: self.buffer_line,
+            start_column: self.buffer_column,
+        };
+        error.with_source_code(Some(Arc::new(snippet)))
+    }
+}
+
+impl<R: Read> Iterator for StreamingLexer<R> {
+    type Item = Result<Token<'static>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(peeked) = self.peeked.take() {
+            return Some(peeked);
+        }
+
+        self.lex()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Write;
+
+    use miette::NarratableReportHandler;
+
+    use super::*;
+
+    /// hands out at most `limit` bytes per read, like a socket or pipe would
+    struct Chunked<'a> {
+        bytes: &'a [u8],
+        limit: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.limit.min(buf.len()).min(self.bytes.len());
+            buf[..len].copy_from_slice(&self.bytes[..len]);
+            self.bytes = &self.bytes[len..];
+            Ok(len)
+        }
+    }
+
+    fn streamed(source: &str, limit: usize) -> StreamingLexer<Chunked<'_>> {
+        let reader = Chunked {
+            bytes: source.as_bytes(),
+            limit,
+        };
+        StreamingLexer::new(reader).with_chunk_size(limit)
+    }
+
+    /// renders tokens and errors alike, as errors can't be compared
+    fn render(tokens: impl Iterator<Item = Result<Token<'static>, Error>>) -> Vec<String> {
+        tokens
+            .map(|token| match token {
+                Ok(token) => format!("{:?} {:?}", token.kind, token.location),
+                Err(error) => format!("error {error}"),
+            })
+            .collect()
+    }
+
+    fn in_memory(source: &str, preserve_comments: bool) -> Vec<String> {
+        let lexer = Lexer::new(source).preserve_comments(preserve_comments);
+        render(lexer.map(|token| token.map(Token::into_owned)))
+    }
+
+    fn generated(functions: usize) -> String {
+        let mut source = String::from("#!/usr/bin/env rmb\n");
+        for idx in 0..functions {
+            writeln!(source, "// function number {idx}, with a ünïcode comment").unwrap();
+            writeln!(source, "fun generated_{idx}(value: i32) => i32 {{").unwrap();
+            writeln!(
+                source,
+                "    /* a block\n       comment */ var total_{idx}: u64 = {idx}u64;"
+            )
+            .unwrap();
+            writeln!(source, "    const label = \"entry {idx} — {{total_{idx}}}\";").unwrap();
+            writeln!(
+                source,
+                "    if value >= -{idx} && value != 1.5f32 {{ total_{idx} += 2; }}"
+            )
+            .unwrap();
+            writeln!(source, "    return value * {idx} / (value - 1);\n}}\n").unwrap();
+        }
+        source
+    }
+
+    #[test]
+    fn large_sources_lex_like_in_memory_ones() {
+        let source = generated(12_000);
+        assert!(source.len() > 2_000_000);
+
+        for preserve_comments in [false, true] {
+            let mut lexer = streamed(&source, 4096).preserve_comments(preserve_comments);
+            let mut largest_window = 0;
+            let tokens = std::iter::from_fn(|| {
+                let token = lexer.next();
+                largest_window = largest_window.max({
+                    let window = lexer.window_location();
+                    window.end_byte - window.start_byte
+                });
+                token
+            });
+
+            assert_eq!(render(tokens), in_memory(&source, preserve_comments));
+            assert!(largest_window < 4096 + MAX_CONTEXT + 1024, "{largest_window}");
+        }
+    }
+
+    #[test]
+    fn tokens_straddling_chunks() {
+        let sources = [
+            generated(3),
+            String::from("\u{feff}fun a() { b += 1; }"),
+            String::from("fun a() { \"unterminated"),
+            String::from("const a = 1 @ 2;"),
+            String::from("fun a() { // trailing comment without a newline"),
+            String::from("/* unterminated /* nested */"),
+            String::from("const a = 1.2.3;\nconst b = 2;"),
+        ];
+
+        for source in &sources {
+            for limit in [1, 2, 3, 7, 64] {
+                for preserve_comments in [false, true] {
+                    let lexer = streamed(source, limit).preserve_comments(preserve_comments);
+                    assert_eq!(
+                        render(lexer),
+                        in_memory(source, preserve_comments),
+                        "{source:?} in chunks of {limit}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn peeking_and_expecting_across_chunks() {
+        let source = "fun identifier_split_across() {}";
+        let mut lexer = streamed(source, 5);
+
+        assert_eq!(lexer.expect(Kind::Fun).unwrap().location, Location::new(0, 3));
+        let peeked = lexer.peek().unwrap().as_ref().unwrap().clone();
+        assert_eq!(peeked.location, Location::new(4, 27));
+        assert_eq!(lexer.next().unwrap().unwrap(), peeked);
+
+        let error = lexer.expect(Kind::Fun).unwrap_err();
+        assert_eq!(error.message(), "expected `fun`, found `(`");
+
+        lexer.by_ref().for_each(drop);
+        assert!(lexer.is_empty());
+        assert!(lexer.expect(Kind::Fun).unwrap_err().is_eof());
+    }
+
+    #[test]
+    fn errors_are_reported_against_a_snippet() {
+        let mut source = generated(200);
+        source.push_str("fun broken() {\n    const a = 1;\n    const b = \"unterminated;\n}\n");
+
+        let error = streamed(&source, 4096).filter_map(Result::err).next().unwrap();
+
+        let full = Lexer::new(&source).filter_map(Result::err).next().unwrap();
+        let full = full.with_source_code(Some(Arc::new(source.clone())));
+
+        // the snippet is rendered just like the whole source would be
+        let render = |error: &Error| {
+            let mut rendered = String::new();
+            NarratableReportHandler::new()
+                .render_report(&mut rendered, error)
+                .unwrap();
+            rendered
+        };
+        assert_eq!(render(&error), render(&full));
+        assert!(render(&error).contains("const a = 1;"));
+    }
+
+    #[test]
+    fn invalid_utf8_is_an_error() {
+        let bytes = b"fun a() { \xff }";
+        let reader = Chunked { bytes, limit: 4 };
+        let tokens = StreamingLexer::new(reader).collect::<Vec<_>>();
+
+        let error = tokens.last().unwrap().as_ref().unwrap_err();
+        assert_eq!(error.message(), "source is not valid utf-8");
+    }
+}
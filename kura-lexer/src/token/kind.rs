@@ -1,12 +1,21 @@
+use std::borrow::Cow;
+
 use super::operator::Operator;
+use super::primitive::Primitive;
 use super::token::{IntoToken, Token};
 use super::value::Value;
-use crate::error::Error;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Kind<'tok> {
     Value(Value<'tok>),
     Op(Operator),
+    LineComment(Cow<'tok, str>),
+    BlockComment(Cow<'tok, str>),
+    /// a `#!/usr/bin/env rmb` line at the very start of a script
+    Shebang(Cow<'tok, str>),
+    /// `'a`, holding the name without its quote
+    Lifetime(Cow<'tok, str>),
 
     Var,
     Const,
@@ -16,7 +25,24 @@ pub enum Kind<'tok> {
     Fun,
     Struct,
     Enum,
+    Impl,
+    For,
+    Let,
+    In,
+    Unsafe,
+    Assert,
+    Mod,
+    Extern,
+    Crate,
+    Use,
+    SelfType,
     Return,
+    Yield,
+    Comptime,
+    Do,
+    Defer,
+    Where,
+    TryAs,
     Eof,
 }
 
@@ -25,6 +51,10 @@ impl std::fmt::Display for Kind<'_> {
         match self {
             Kind::Value(val) => write!(f, "{}", val),
             Kind::Op(op) => write!(f, "{}", op),
+            Kind::LineComment(comment) => write!(f, "{comment}"),
+            Kind::BlockComment(comment) => write!(f, "{comment}"),
+            Kind::Shebang(line) => write!(f, "{line}"),
+            Kind::Lifetime(name) => write!(f, "'{name}"),
             Kind::Var => write!(f, "var"),
             Kind::Const => write!(f, "const"),
             Kind::Match => write!(f, "match"),
@@ -33,45 +63,30 @@ impl std::fmt::Display for Kind<'_> {
             Kind::Fun => write!(f, "fun"),
             Kind::Struct => write!(f, "struct"),
             Kind::Enum => write!(f, "enum"),
+            Kind::Impl => write!(f, "impl"),
+            Kind::For => write!(f, "for"),
+            Kind::Let => write!(f, "let"),
+            Kind::In => write!(f, "in"),
+            Kind::Unsafe => write!(f, "unsafe"),
+            Kind::Assert => write!(f, "assert"),
+            Kind::Mod => write!(f, "mod"),
+            Kind::Extern => write!(f, "extern"),
+            Kind::Crate => write!(f, "crate"),
+            Kind::Use => write!(f, "use"),
+            Kind::SelfType => write!(f, "Self"),
             Kind::Return => write!(f, "return"),
+            Kind::Yield => write!(f, "yield"),
+            Kind::Comptime => write!(f, "comptime"),
+            Kind::Do => write!(f, "do"),
+            Kind::Defer => write!(f, "defer"),
+            Kind::Where => write!(f, "where"),
+            Kind::TryAs => write!(f, "try_as"),
             Kind::Eof => write!(f, "eof"),
         }
     }
 }
 
 impl<'tok> Kind<'tok> {
-    pub fn infix_precedence(&self) -> Result<(u8, u8), Error> {
-        match self {
-            Kind::Op(Operator::Plus) | Kind::Op(Operator::Minus) => Ok((1, 2)),
-            Kind::Op(Operator::Less)
-            | Kind::Op(Operator::LessEqual)
-            | Kind::Op(Operator::EqualEqual)
-            | Kind::Op(Operator::Or)
-            | Kind::Op(Operator::Greater)
-            | Kind::Op(Operator::GreaterEqual) => Ok((3, 4)),
-            Kind::Op(Operator::Star) | Kind::Op(Operator::Slash) => Ok((5, 6)),
-            _ => Err(Error::default()),
-        }
-    }
-
-    pub fn is_binary_op(&self) -> bool {
-        matches!(
-            self,
-            Kind::Op(Operator::Star)
-                | Kind::Op(Operator::Slash)
-                | Kind::Op(Operator::And)
-                | Kind::Op(Operator::Or)
-                | Kind::Op(Operator::Plus)
-                | Kind::Op(Operator::Minus)
-                | Kind::Op(Operator::Greater)
-                | Kind::Op(Operator::EqualEqual)
-                | Kind::Op(Operator::Less)
-                | Kind::Op(Operator::LessEqual)
-                | Kind::Op(Operator::NotEqual)
-                | Kind::Op(Operator::GreaterEqual)
-        )
-    }
-
     pub fn identifier_from(value: &'tok str) -> Kind<'tok> {
         match value {
             "var" => Kind::Var,
@@ -82,8 +97,113 @@ impl<'tok> Kind<'tok> {
             "fun" => Kind::Fun,
             "struct" => Kind::Struct,
             "enum" => Kind::Enum,
+            "impl" => Kind::Impl,
+            "for" => Kind::For,
+            "let" => Kind::Let,
+            "in" => Kind::In,
+            "unsafe" => Kind::Unsafe,
+            "assert" => Kind::Assert,
+            "mod" => Kind::Mod,
+            "extern" => Kind::Extern,
+            "crate" => Kind::Crate,
+            "use" => Kind::Use,
+            "Self" => Kind::SelfType,
             "return" => Kind::Return,
-            _ => Kind::Value(Value::Ident(value)),
+            "yield" => Kind::Yield,
+            "comptime" => Kind::Comptime,
+            "do" => Kind::Do,
+            "defer" => Kind::Defer,
+            "where" => Kind::Where,
+            "try_as" => Kind::TryAs,
+            "true" => Kind::Value(Value::Primitive(Primitive::Bool(true))),
+            "false" => Kind::Value(Value::Primitive(Primitive::Bool(false))),
+            _ => Kind::Value(Value::Ident(Cow::Borrowed(value))),
+        }
+    }
+}
+
+impl Kind<'_> {
+    pub fn into_owned(self) -> Kind<'static> {
+        match self {
+            Kind::Value(value) => Kind::Value(value.into_owned()),
+            Kind::Op(op) => Kind::Op(op),
+            Kind::LineComment(comment) => Kind::LineComment(Cow::Owned(comment.into_owned())),
+            Kind::BlockComment(comment) => Kind::BlockComment(Cow::Owned(comment.into_owned())),
+            Kind::Shebang(line) => Kind::Shebang(Cow::Owned(line.into_owned())),
+            Kind::Lifetime(name) => Kind::Lifetime(Cow::Owned(name.into_owned())),
+            Kind::Var => Kind::Var,
+            Kind::Const => Kind::Const,
+            Kind::Match => Kind::Match,
+            Kind::If => Kind::If,
+            Kind::Else => Kind::Else,
+            Kind::Fun => Kind::Fun,
+            Kind::Struct => Kind::Struct,
+            Kind::Enum => Kind::Enum,
+            Kind::Impl => Kind::Impl,
+            Kind::For => Kind::For,
+            Kind::Let => Kind::Let,
+            Kind::In => Kind::In,
+            Kind::Unsafe => Kind::Unsafe,
+            Kind::Assert => Kind::Assert,
+            Kind::Mod => Kind::Mod,
+            Kind::Extern => Kind::Extern,
+            Kind::Crate => Kind::Crate,
+            Kind::Use => Kind::Use,
+            Kind::SelfType => Kind::SelfType,
+            Kind::Return => Kind::Return,
+            Kind::Yield => Kind::Yield,
+            Kind::Comptime => Kind::Comptime,
+            Kind::Do => Kind::Do,
+            Kind::Defer => Kind::Defer,
+            Kind::Where => Kind::Where,
+            Kind::TryAs => Kind::TryAs,
+            Kind::Eof => Kind::Eof,
+        }
+    }
+
+    /// name of the kind of the token, leaving out what it holds, like
+    /// `Ident` for every identifier
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Value(Value::Primitive(Primitive::Bool(_))) => "Bool",
+            Kind::Value(Value::Primitive(Primitive::UInt { .. })) => "UInt",
+            Kind::Value(Value::Primitive(Primitive::Int { .. })) => "Int",
+            Kind::Value(Value::Primitive(Primitive::Float { .. })) => "Float",
+            Kind::Value(Value::Ident(_)) => "Ident",
+            Kind::Value(Value::String(_)) => "String",
+            Kind::Value(Value::RawString { .. }) => "RawString",
+            Kind::Op(_) => "Op",
+            Kind::LineComment(_) => "LineComment",
+            Kind::BlockComment(_) => "BlockComment",
+            Kind::Shebang(_) => "Shebang",
+            Kind::Lifetime(_) => "Lifetime",
+            Kind::Var => "Var",
+            Kind::Const => "Const",
+            Kind::Match => "Match",
+            Kind::If => "If",
+            Kind::Else => "Else",
+            Kind::Fun => "Fun",
+            Kind::Struct => "Struct",
+            Kind::Enum => "Enum",
+            Kind::Impl => "Impl",
+            Kind::For => "For",
+            Kind::Let => "Let",
+            Kind::In => "In",
+            Kind::Unsafe => "Unsafe",
+            Kind::Assert => "Assert",
+            Kind::Mod => "Mod",
+            Kind::Extern => "Extern",
+            Kind::Crate => "Crate",
+            Kind::Use => "Use",
+            Kind::SelfType => "SelfType",
+            Kind::Return => "Return",
+            Kind::Yield => "Yield",
+            Kind::Comptime => "Comptime",
+            Kind::Do => "Do",
+            Kind::Defer => "Defer",
+            Kind::Where => "Where",
+            Kind::TryAs => "TryAs",
+            Kind::Eof => "Eof",
         }
     }
 }
@@ -6,6 +6,7 @@ use super::{
 };
 
 #[derive(Debug, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Operator {
     LeftParen,
     RightParen,
@@ -17,8 +18,13 @@ pub enum Operator {
     Dot,
     Minus,
     MinusEqual,
+    /// `--`, which is not an operator of the language but is lexed so the
+    /// parser can point at it instead of reading `x--` as `x - -`
+    MinusMinus,
     Plus,
     PlusEqual,
+    /// `++`, lexed for the same reason as `MinusMinus`
+    PlusPlus,
     Equal,
     ThickArrow,
     EqualEqual,
@@ -28,6 +34,7 @@ pub enum Operator {
     Slash,
     SlashEqual,
     Colon,
+    ColonColon,
     SemiColon,
     Bang,
     Less,
@@ -37,6 +44,18 @@ pub enum Operator {
     NotEqual,
     And,
     Or,
+    Hash,
+    Dollar,
+    Elvis,
+    /// `?`, propagating the error of what it follows. `?:` is always
+    /// lexed as `Elvis`, so `a?:b` has to be written `a? : b` for it
+    Question,
+    /// `<-`, binding a name inside of a `do` block. it is always lexed as
+    /// one token, so `a<-1` has to be written `a < -1` to compare
+    LeftArrow,
+    /// `->`, only written before the return type of a bound like
+    /// `Fn(i32) -> i32`
+    ThinArrow,
 }
 
 impl fmt::Display for Operator {
@@ -44,7 +63,9 @@ impl fmt::Display for Operator {
         match self {
             Operator::Minus => write!(f, "-"),
             Operator::MinusEqual => write!(f, "-="),
+            Operator::MinusMinus => write!(f, "--"),
             Operator::Colon => write!(f, ":"),
+            Operator::ColonColon => write!(f, "::"),
             Operator::SemiColon => write!(f, ";"),
             Operator::LeftParen => write!(f, "("),
             Operator::RightParen => write!(f, ")"),
@@ -56,6 +77,7 @@ impl fmt::Display for Operator {
             Operator::Dot => write!(f, "."),
             Operator::Plus => write!(f, "+"),
             Operator::PlusEqual => write!(f, "+="),
+            Operator::PlusPlus => write!(f, "++"),
             Operator::Equal => write!(f, "="),
             Operator::ThickArrow => write!(f, "=>"),
             Operator::EqualEqual => write!(f, "=="),
@@ -72,6 +94,12 @@ impl fmt::Display for Operator {
             Operator::SlashEqual => write!(f, "/="),
             Operator::And => write!(f, "&&"),
             Operator::Or => write!(f, "||"),
+            Operator::Hash => write!(f, "#"),
+            Operator::Dollar => write!(f, "$"),
+            Operator::Elvis => write!(f, "?:"),
+            Operator::Question => write!(f, "?"),
+            Operator::LeftArrow => write!(f, "<-"),
+            Operator::ThinArrow => write!(f, "->"),
         }
     }
 }
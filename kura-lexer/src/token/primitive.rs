@@ -6,7 +6,8 @@ use super::{
     value::Value,
 };
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum FloatSizes {
     F8,
     F16,
@@ -25,7 +26,8 @@ impl fmt::Display for FloatSizes {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum IntSizes {
     I8,
     I16,
@@ -46,7 +48,8 @@ impl fmt::Display for IntSizes {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum UIntSizes {
     U8,
     U16,
@@ -67,6 +70,32 @@ impl fmt::Display for UIntSizes {
     }
 }
 
+impl IntSizes {
+    /// whether `value` can be stored in an integer of this size. pointer
+    /// sized integers are taken to be 64 bits wide
+    pub fn fits(&self, value: i64) -> bool {
+        match self {
+            IntSizes::I8 => i8::try_from(value).is_ok(),
+            IntSizes::I16 => i16::try_from(value).is_ok(),
+            IntSizes::I32 => i32::try_from(value).is_ok(),
+            IntSizes::I64 | IntSizes::Isize => true,
+        }
+    }
+}
+
+impl UIntSizes {
+    /// whether `value` can be stored in an integer of this size. pointer
+    /// sized integers are taken to be 64 bits wide
+    pub fn fits(&self, value: u64) -> bool {
+        match self {
+            UIntSizes::U8 => u8::try_from(value).is_ok(),
+            UIntSizes::U16 => u16::try_from(value).is_ok(),
+            UIntSizes::U32 => u32::try_from(value).is_ok(),
+            UIntSizes::U64 | UIntSizes::Usize => true,
+        }
+    }
+}
+
 impl TryFrom<&str> for IntSizes {
     type Error = ();
 
@@ -111,7 +140,8 @@ impl TryFrom<&str> for UIntSizes {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Primitive {
     Bool(bool),
     UInt {
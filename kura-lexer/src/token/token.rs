@@ -7,7 +7,8 @@ pub trait IntoToken<'tok> {
     fn into_token(self, start_byte: usize, end_byte: usize) -> Token<'tok>;
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Location {
     pub start_byte: usize,
     pub end_byte: usize,
@@ -20,6 +21,15 @@ impl Location {
             end_byte: end,
         }
     }
+
+    /// the same span, moved `delta` bytes forward or backwards, as happens
+    /// to everything after an edit that changes the length of the source
+    pub fn shifted_by(self, delta: isize) -> Self {
+        Self {
+            start_byte: self.start_byte.saturating_add_signed(delta),
+            end_byte: self.end_byte.saturating_add_signed(delta),
+        }
+    }
 }
 
 impl std::fmt::Display for Location {
@@ -34,6 +44,16 @@ impl From<(usize, usize)> for Location {
     }
 }
 
+impl From<Location> for miette::SourceSpan {
+    fn from(location: Location) -> Self {
+        (
+            location.start_byte,
+            location.end_byte.saturating_sub(location.start_byte),
+        )
+            .into()
+    }
+}
+
 impl From<Range<usize>> for Location {
     fn from(range: Range<usize>) -> Self {
         let start = match range.start_bound() {
@@ -59,7 +79,8 @@ impl From<Range<usize>> for Location {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Token<'tok> {
     pub kind: Kind<'tok>,
     pub location: Location,
@@ -69,6 +90,14 @@ impl<'tok> Token<'tok> {
     pub fn new(kind: Kind<'tok>, location: Location) -> Self {
         Self { kind, location }
     }
+
+    /// detaches the token from the source it was lexed from
+    pub fn into_owned(self) -> Token<'static> {
+        Token {
+            kind: self.kind.into_owned(),
+            location: self.location,
+        }
+    }
 }
 
 impl fmt::Display for Token<'_> {
@@ -1,12 +1,21 @@
+use std::borrow::Cow;
 use std::fmt;
 
 use super::primitive::Primitive;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Value<'tok> {
     Primitive(Primitive),
-    Ident(&'tok str),
-    String(&'tok str),
+    Ident(Cow<'tok, str>),
+    String(Cow<'tok, str>),
+    /// `r"..."`, or `r#"..."#` with as many `#` on both sides as it takes
+    /// for the contents not to end it. the contents are taken as they are,
+    /// without escapes or interpolation
+    RawString {
+        contents: Cow<'tok, str>,
+        hashes: usize,
+    },
 }
 
 impl fmt::Display for Value<'_> {
@@ -14,7 +23,26 @@ impl fmt::Display for Value<'_> {
         match self {
             Value::Primitive(p) => write!(f, "{}", p),
             Value::Ident(i) => write!(f, "{i}"),
-            Value::String(s) => write!(f, "{s}"),
+            // contents are kept as written, escapes included
+            Value::String(s) => write!(f, "\"{s}\""),
+            Value::RawString { contents, hashes } => {
+                let hashes = "#".repeat(*hashes);
+                write!(f, "r{hashes}\"{contents}\"{hashes}")
+            }
+        }
+    }
+}
+
+impl Value<'_> {
+    pub fn into_owned(self) -> Value<'static> {
+        match self {
+            Value::Primitive(primitive) => Value::Primitive(primitive),
+            Value::Ident(ident) => Value::Ident(Cow::Owned(ident.into_owned())),
+            Value::String(string) => Value::String(Cow::Owned(string.into_owned())),
+            Value::RawString { contents, hashes } => Value::RawString {
+                contents: Cow::Owned(contents.into_owned()),
+                hashes,
+            },
         }
     }
 }
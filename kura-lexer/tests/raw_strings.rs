@@ -0,0 +1,69 @@
+use kura_lexer::token::{Kind, Value};
+use kura_lexer::{tokenize_all, Lexer};
+use proptest::prelude::*;
+
+/// contents made mostly of quotes and hashes, which are what could end a
+/// raw string too early
+fn contents() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![Just("\""), Just("#"), Just("r"), Just("\\"), Just("{"), Just("a b")],
+        0..24,
+    )
+    .prop_map(|pieces| pieces.concat())
+}
+
+proptest! {
+    /// a raw string opened with enough hashes ends right after its contents,
+    /// whatever they hold
+    #[test]
+    fn raw_strings_keep_their_contents(contents in contents(), extra in 0..3usize) {
+        // contents ending in a quote would merge it with the closing one
+        prop_assume!(!contents.ends_with('"'));
+
+        // the contents can't have a quote followed by as many hashes as the
+        // string opens with, so it takes one more than the longest run
+        let longest = contents
+            .split('"')
+            .skip(1)
+            .map(|after| after.len() - after.trim_start_matches('#').len())
+            .max()
+            .map_or(0, |longest| longest + 1);
+
+        let hashes = longest + extra;
+        let fence = "#".repeat(hashes);
+        let source = format!("r{fence}\"{contents}\"{fence} next");
+        let end_byte = source.len() - " next".len();
+
+        let tokens = tokenize_all(&source).unwrap();
+        prop_assert_eq!(tokens.len(), 2);
+        prop_assert_eq!(
+            &tokens[0].kind,
+            &Kind::Value(Value::RawString {
+                contents: contents.as_str().into(),
+                hashes,
+            })
+        );
+        prop_assert_eq!(tokens[0].location.end_byte, end_byte);
+        prop_assert_eq!(tokens[0].kind.to_string(), &source[..end_byte]);
+    }
+
+    /// a raw string ends at the first quote followed by as many hashes as
+    /// it opened with, and is an error when there is none. whatever comes
+    /// after it is lexed on its own
+    #[test]
+    fn raw_strings_end_at_their_first_fence(contents in contents(), hashes in 0..4usize) {
+        let fence = "#".repeat(hashes);
+        let source = format!("r{fence}\"{contents}");
+        let closing = format!("\"{fence}");
+
+        match Lexer::new(&source).next().unwrap() {
+            Ok(token) => {
+                let Kind::Value(Value::RawString { contents: lexed, .. }) = &token.kind else {
+                    panic!("expected a raw string, found {:?}", token.kind);
+                };
+                prop_assert_eq!(contents.find(&closing), Some(lexed.len()));
+            }
+            Err(_) => prop_assert!(!contents.contains(&closing)),
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use kura_lexer::Lexer;
+use kura_parser::{Parser, MAX_NESTING_DEPTH};
+use kura_support::workloads;
+
+const BYTES: usize = 1024 * 1024;
+
+fn throughput(source: &str) -> Throughput {
+    Throughput::ElementsAndBytes {
+        elements: Lexer::new(source).count() as u64,
+        bytes: source.len() as u64,
+    }
+}
+
+fn lex(c: &mut Criterion) {
+    let mut group = c.benchmark_group("lex");
+
+    for workload in workloads(BYTES, MAX_NESTING_DEPTH / 2) {
+        group.throughput(throughput(&workload.source));
+        group.bench_function(workload.name, |b| {
+            b.iter(|| Lexer::new(&workload.source).count());
+        });
+    }
+
+    group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(20);
+
+    for workload in workloads(BYTES, MAX_NESTING_DEPTH / 2) {
+        group.throughput(throughput(&workload.source));
+        group.bench_function(workload.name, |b| {
+            b.iter(|| Parser::new(&workload.source, Lexer::new(&workload.source)).parse());
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, lex, parse);
+criterion_main!(benches);
@@ -0,0 +1,11 @@
+#![no_main]
+
+use kura_lexer::Lexer;
+use kura_parser::Parser;
+
+libfuzzer_sys::fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        let _ = Parser::new(source, Lexer::new(source)).parse();
+        let _ = Parser::parse_repl_line(source);
+    }
+});
@@ -0,0 +1,134 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use crate::error::ParseError;
+use crate::sexpr::to_sexpr;
+use crate::Expression;
+
+/// hashes and compares an expression by what it is made of, leaving out
+/// where it was written. code that only moved around, or whose spacing
+/// changed, hashes the same
+#[derive(Debug, Clone, Copy)]
+pub struct ExpressionHash<'a, 'ast>(pub &'a Expression<'ast>);
+
+impl Hash for ExpressionHash<'_, '_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // the s-expression of a node is all of it but its locations
+        to_sexpr(self.0).hash(state);
+    }
+}
+
+impl PartialEq for ExpressionHash<'_, '_> {
+    fn eq(&self, other: &Self) -> bool {
+        to_sexpr(self.0) == to_sexpr(other.0)
+    }
+}
+
+impl Eq for ExpressionHash<'_, '_> {}
+
+fn source_hash(source: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// expressions already parsed, by a key like the path of the file they come
+/// from. each one is kept along with a hash of its source, so it is only
+/// reused while the source stays the same
+#[derive(Debug)]
+pub struct ParseCache<K> {
+    entries: HashMap<K, (Expression<'static>, u64)>,
+}
+
+impl<K> Default for ParseCache<K> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq> ParseCache<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// the expression parsed out of `source` under `key`, unless it was
+    /// parsed out of a different source
+    pub fn get(&self, key: &K, source: &str) -> Option<&Expression<'static>> {
+        self.entries
+            .get(key)
+            .filter(|(_, hash)| *hash == source_hash(source))
+            .map(|(expr, _)| expr)
+    }
+
+    pub fn insert(&mut self, key: K, source: &str, expr: Expression<'_>) {
+        self.entries.insert(key, (expr.into_owned(), source_hash(source)));
+    }
+
+    /// the expression cached for `source` under `key`, parsing it with
+    /// `parse` and caching the result when there is none
+    pub fn get_or_parse<'src>(
+        &mut self,
+        key: K,
+        source: &'src str,
+        parse: impl FnOnce(&'src str) -> Result<Expression<'src>, ParseError>,
+    ) -> Result<&Expression<'static>, ParseError> {
+        let hash = source_hash(source);
+        match self.entries.entry(key) {
+            Entry::Occupied(entry) if entry.get().1 == hash => Ok(&entry.into_mut().0),
+            Entry::Occupied(mut entry) => {
+                entry.insert((parse(source)?.into_owned(), hash));
+                Ok(&entry.into_mut().0)
+            }
+            Entry::Vacant(entry) => Ok(&entry.insert((parse(source)?.into_owned(), hash)).0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn hashes_leave_locations_out() {
+        let hash = |source| {
+            let mut hasher = DefaultHasher::new();
+            ExpressionHash(&Parser::parse_repl_line(source).unwrap().0).hash(&mut hasher);
+            hasher.finish()
+        };
+
+        assert_eq!(hash("f(1, a + 2)"), hash("f( 1,\n    a+2 )"));
+        assert_ne!(hash("f(1, a + 2)"), hash("f(1, a - 2)"));
+    }
+
+    #[test]
+    fn cache_hits_skip_parsing() {
+        let parses = Cell::new(0);
+        let parse = |source| {
+            parses.set(parses.get() + 1);
+            Parser::parse_repl_line(source).map(|(expression, _)| expression)
+        };
+
+        let mut cache = ParseCache::new();
+        let first = cache.get_or_parse("main.rmb", "a + 1", parse).unwrap().clone();
+        let second = cache.get_or_parse("main.rmb", "a + 1", parse).unwrap();
+        assert_eq!(parses.get(), 1);
+        assert_eq!(&first, second);
+
+        // a different source under the same key is parsed again
+        cache.get_or_parse("main.rmb", "a + 2", parse).unwrap();
+        assert_eq!(parses.get(), 2);
+        assert!(cache.get(&"main.rmb", "a + 1").is_none());
+        assert!(cache.get(&"main.rmb", "a + 2").is_some());
+
+        cache.insert("lib.rmb", "b", Parser::parse_repl_line("b").unwrap().0);
+        assert!(cache.get(&"lib.rmb", "b").is_some());
+        assert!(cache.get(&"other.rmb", "b").is_none());
+    }
+}
@@ -0,0 +1,404 @@
+use kura_lexer::token::{Location, Operator, Token};
+use kura_lexer::Lexer;
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::expression::{lay_out, parse_expression};
+use crate::inference::Type;
+use crate::{Expression, TypeExpr};
+
+/// name of the built in macro evaluating to the kind of the expression it is
+/// given, like `kind_of!(1 + 2)` to `"BinaryOp"`
+pub const KIND_OF_MACRO: &str = "kind_of";
+
+/// the value of an expression evaluated at compile time
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiteralValue {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl LiteralValue {
+    fn type_name(&self) -> &'static str {
+        match self {
+            LiteralValue::Int(_) => "a signed integer",
+            LiteralValue::UInt(_) => "an unsigned integer",
+            LiteralValue::Float(_) => "a float",
+            LiteralValue::Bool(_) => "a boolean",
+            LiteralValue::Str(_) => "a string",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error, Diagnostic)]
+pub enum EvalError {
+    #[error("this operation overflows")]
+    #[diagnostic(code(kura::const_eval::overflow))]
+    Overflow {
+        #[label("overflows")]
+        span: SourceSpan,
+    },
+    #[error("division by zero")]
+    #[diagnostic(code(kura::const_eval::division_by_zero))]
+    DivisionByZero {
+        #[label("divides by zero")]
+        span: SourceSpan,
+    },
+    #[error("`{operator}` can't take {lhs} and {rhs}")]
+    #[diagnostic(code(kura::const_eval::mismatched_operands))]
+    MismatchedOperands {
+        operator: Operator,
+        lhs: &'static str,
+        rhs: &'static str,
+        #[label("in this operation")]
+        span: SourceSpan,
+    },
+    #[error("`!` can only negate a boolean, found {found}")]
+    #[diagnostic(code(kura::const_eval::not_a_boolean))]
+    NotABoolean {
+        found: &'static str,
+        #[label("negated here")]
+        span: SourceSpan,
+    },
+    #[error("this can't be evaluated at compile time")]
+    #[diagnostic(code(kura::const_eval::not_constant))]
+    NotConstant {
+        #[label("not a constant")]
+        span: SourceSpan,
+    },
+}
+
+/// evaluates trees of literals, `!` and binary operations on them, along
+/// with the lengths of array literals and blocks made only of those. names
+/// and calls could refer to anything, so they are never constant
+pub struct ConstExprEvaluator;
+
+impl ConstExprEvaluator {
+    pub fn eval(expr: &Expression<'_>) -> Result<LiteralValue, EvalError> {
+        match expr {
+            Expression::Bool { value, .. } => Ok(LiteralValue::Bool(*value)),
+            Expression::UintLiteral { value, .. } => Ok(LiteralValue::UInt(*value)),
+            Expression::IntLiteral { value, .. } => Ok(LiteralValue::Int(*value)),
+            Expression::FloatLiteral { value, .. } => Ok(LiteralValue::Float(*value)),
+            Expression::StringLiteral { value, .. } => Ok(LiteralValue::Str(value.to_string())),
+            Expression::ArrayLen { array, .. } => match array.as_ref() {
+                Expression::Array { elements, .. } => Ok(LiteralValue::UInt(elements.len() as u64)),
+                array => Err(EvalError::NotConstant {
+                    span: array.location().into(),
+                }),
+            },
+            Expression::Sizeof { typ, location } => {
+                size_of(typ)
+                    .map(LiteralValue::UInt)
+                    .ok_or_else(|| EvalError::NotConstant {
+                        span: (*location).into(),
+                    })
+            }
+            Expression::Macro { name, tokens, location } if name == KIND_OF_MACRO => kind_of(tokens)
+                .map(|kind| LiteralValue::Str(kind.to_string()))
+                .ok_or_else(|| EvalError::NotConstant {
+                    span: (*location).into(),
+                }),
+            Expression::ComptimeBlock { body, .. } => Self::eval(body),
+            // every expression of a block has to be constant, and its tail is
+            // its value
+            Expression::Block {
+                expressions,
+                tail,
+                location,
+                ..
+            } => {
+                expressions.iter().try_for_each(|expr| Self::eval(expr).map(drop))?;
+                match tail {
+                    Some(tail) => Self::eval(tail),
+                    None => Err(EvalError::NotConstant {
+                        span: (*location).into(),
+                    }),
+                }
+            }
+            Expression::Not { expr, location } => match Self::eval(expr)? {
+                LiteralValue::Bool(value) => Ok(LiteralValue::Bool(!value)),
+                value => Err(EvalError::NotABoolean {
+                    found: value.type_name(),
+                    span: (*location).into(),
+                }),
+            },
+            Expression::BinaryOp {
+                operator: Operator::Minus,
+                lhs,
+                rhs,
+                location,
+                ..
+            } if is_unsuffixed(lhs) && is_unsuffixed(rhs) => Self::subtract_unsuffixed(lhs, rhs, *location),
+            Expression::BinaryOp {
+                operator: operator @ (Operator::And | Operator::Or),
+                lhs,
+                rhs,
+                location,
+                ..
+            } => Self::short_circuit(*operator, lhs, rhs, *location),
+            Expression::BinaryOp {
+                operator,
+                lhs,
+                rhs,
+                location,
+                ..
+            } => Self::apply(*operator, Self::eval(lhs)?, Self::eval(rhs)?, *location),
+            expr => Err(EvalError::NotConstant {
+                span: expr.location().into(),
+            }),
+        }
+    }
+
+    /// the right side of `&&` and `||` is only evaluated when the left one
+    /// doesn't decide the result on its own
+    fn short_circuit(
+        operator: Operator,
+        lhs: &Expression<'_>,
+        rhs: &Expression<'_>,
+        location: Location,
+    ) -> Result<LiteralValue, EvalError> {
+        let decided = operator == Operator::Or;
+        match Self::eval(lhs)? {
+            LiteralValue::Bool(lhs) if lhs == decided => Ok(LiteralValue::Bool(lhs)),
+            LiteralValue::Bool(lhs) => match Self::eval(rhs)? {
+                LiteralValue::Bool(rhs) => Ok(LiteralValue::Bool(rhs)),
+                rhs => Err(mismatched(operator, &LiteralValue::Bool(lhs), &rhs, location)),
+            },
+            lhs => Err(mismatched(operator, &lhs, &Self::eval(rhs)?, location)),
+        }
+    }
+
+    /// integers written without a suffix default to `i64` once their type is
+    /// inferred, so taking a bigger one from a smaller one is negative rather
+    /// than an overflow, as in `1 - 2`
+    fn subtract_unsuffixed(
+        lhs: &Expression<'_>,
+        rhs: &Expression<'_>,
+        location: Location,
+    ) -> Result<LiteralValue, EvalError> {
+        use LiteralValue::*;
+
+        match (Self::eval(lhs)?, Self::eval(rhs)?) {
+            (UInt(lhs), UInt(rhs)) if lhs < rhs => {
+                let overflow = || EvalError::Overflow { span: location.into() };
+                let lhs = i64::try_from(lhs).map_err(|_| overflow())?;
+                let rhs = i64::try_from(rhs).map_err(|_| overflow())?;
+                lhs.checked_sub(rhs).map(Int).ok_or_else(overflow)
+            }
+            (lhs, rhs) => Self::apply(Operator::Minus, lhs, rhs, location),
+        }
+    }
+
+    fn apply(
+        operator: Operator,
+        lhs: LiteralValue,
+        rhs: LiteralValue,
+        location: Location,
+    ) -> Result<LiteralValue, EvalError> {
+        use LiteralValue::*;
+
+        let overflow = || EvalError::Overflow { span: location.into() };
+        let division_by_zero = || EvalError::DivisionByZero { span: location.into() };
+
+        // an unsigned integer next to a signed one is taken as signed
+        let (lhs, rhs) = match (lhs, rhs) {
+            (Int(lhs), UInt(rhs)) => (Int(lhs), Int(i64::try_from(rhs).map_err(|_| overflow())?)),
+            (UInt(lhs), Int(rhs)) => (Int(i64::try_from(lhs).map_err(|_| overflow())?), Int(rhs)),
+            operands => operands,
+        };
+
+        match (operator, &lhs, &rhs) {
+            (Operator::Plus, Int(lhs), Int(rhs)) => lhs.checked_add(*rhs).map(Int).ok_or_else(overflow),
+            (Operator::Minus, Int(lhs), Int(rhs)) => lhs.checked_sub(*rhs).map(Int).ok_or_else(overflow),
+            (Operator::Star, Int(lhs), Int(rhs)) => lhs.checked_mul(*rhs).map(Int).ok_or_else(overflow),
+            (Operator::Slash, Int(_), Int(0)) => Err(division_by_zero()),
+            (Operator::Slash, Int(lhs), Int(rhs)) => lhs.checked_div(*rhs).map(Int).ok_or_else(overflow),
+            (Operator::Plus, UInt(lhs), UInt(rhs)) => lhs.checked_add(*rhs).map(UInt).ok_or_else(overflow),
+            (Operator::Minus, UInt(lhs), UInt(rhs)) => lhs.checked_sub(*rhs).map(UInt).ok_or_else(overflow),
+            (Operator::Star, UInt(lhs), UInt(rhs)) => lhs.checked_mul(*rhs).map(UInt).ok_or_else(overflow),
+            (Operator::Slash, UInt(_), UInt(0)) => Err(division_by_zero()),
+            (Operator::Slash, UInt(lhs), UInt(rhs)) => Ok(UInt(lhs / rhs)),
+            (Operator::Plus, Float(lhs), Float(rhs)) => Ok(Float(lhs + rhs)),
+            (Operator::Minus, Float(lhs), Float(rhs)) => Ok(Float(lhs - rhs)),
+            (Operator::Star, Float(lhs), Float(rhs)) => Ok(Float(lhs * rhs)),
+            (Operator::Slash, Float(lhs), Float(rhs)) => Ok(Float(lhs / rhs)),
+            (Operator::Plus, Str(lhs), Str(rhs)) => Ok(Str(format!("{lhs}{rhs}"))),
+            _ => compare(operator, &lhs, &rhs)
+                .map(Bool)
+                .ok_or_else(|| mismatched(operator, &lhs, &rhs, location)),
+        }
+    }
+}
+
+/// whether `expr` is an integer literal without a suffix, or an operation
+/// on those only
+fn is_unsuffixed(expr: &Expression<'_>) -> bool {
+    match expr {
+        Expression::UintLiteral { size: None, .. } | Expression::IntLiteral { size: None, .. } => true,
+        Expression::BinaryOp { lhs, rhs, .. } => is_unsuffixed(lhs) && is_unsuffixed(rhs),
+        _ => false,
+    }
+}
+
+/// the number of bytes a value of `typ` takes, `None` for anything whose
+/// layout isn't known before types are checked, like structs and tuples
+pub fn size_of(typ: &TypeExpr<'_>) -> Option<u64> {
+    match typ {
+        TypeExpr::Named { name, .. } => Type::from_name(name)?.size(),
+        TypeExpr::Ptr { .. } | TypeExpr::Ref { .. } | TypeExpr::Fn { .. } => Some(8),
+        TypeExpr::Array { element, length, .. } => size_of(element)?.checked_mul(*length),
+        _ => None,
+    }
+}
+
+/// what `kind_of!` evaluates to when given `tokens`, the name of the kind of
+/// expression they are. `None` when they aren't a single expression
+pub fn kind_of(tokens: &[Token<'_>]) -> Option<&'static str> {
+    let (source, tokens) = lay_out(tokens, 0);
+    let mut lexer = Lexer::new(&source).replaying(tokens);
+    let expr = parse_expression(&mut lexer, false).ok()?;
+    lexer.next().is_none().then(|| expr.kind_name())
+}
+
+/// compares two values of the same type, `None` if they can't be compared
+/// or `operator` isn't a comparison
+fn compare(operator: Operator, lhs: &LiteralValue, rhs: &LiteralValue) -> Option<bool> {
+    use std::cmp::Ordering;
+
+    let ordering = match (lhs, rhs) {
+        (LiteralValue::Int(lhs), LiteralValue::Int(rhs)) => lhs.partial_cmp(rhs),
+        (LiteralValue::UInt(lhs), LiteralValue::UInt(rhs)) => lhs.partial_cmp(rhs),
+        // comparing anything to NaN is false, except for `!=`
+        (LiteralValue::Float(lhs), LiteralValue::Float(rhs)) => lhs.partial_cmp(rhs),
+        (LiteralValue::Bool(lhs), LiteralValue::Bool(rhs)) => lhs.partial_cmp(rhs),
+        (LiteralValue::Str(lhs), LiteralValue::Str(rhs)) => lhs.partial_cmp(rhs),
+        _ => return None,
+    };
+
+    match operator {
+        Operator::EqualEqual => Some(ordering == Some(Ordering::Equal)),
+        Operator::NotEqual => Some(ordering != Some(Ordering::Equal)),
+        Operator::Less => Some(ordering == Some(Ordering::Less)),
+        Operator::LessEqual => Some(matches!(ordering, Some(Ordering::Less | Ordering::Equal))),
+        Operator::Greater => Some(ordering == Some(Ordering::Greater)),
+        Operator::GreaterEqual => Some(matches!(ordering, Some(Ordering::Greater | Ordering::Equal))),
+        _ => None,
+    }
+}
+
+fn mismatched(operator: Operator, lhs: &LiteralValue, rhs: &LiteralValue, location: Location) -> EvalError {
+    EvalError::MismatchedOperands {
+        operator,
+        lhs: lhs.type_name(),
+        rhs: rhs.type_name(),
+        span: location.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    fn eval(value: &str) -> Result<LiteralValue, EvalError> {
+        let (expression, _) = Parser::parse_repl_line(value).unwrap();
+        ConstExprEvaluator::eval(&expression)
+    }
+
+    #[test]
+    fn arithmetic_and_comparisons() {
+        assert_eq!(eval("(1 + 2) * 3"), Ok(LiteralValue::UInt(9)));
+        assert_eq!(eval("-7 / 2"), Ok(LiteralValue::Int(-3)));
+        // mixing signed and unsigned integers gives a signed one
+        assert_eq!(eval("-2 + 5"), Ok(LiteralValue::Int(3)));
+        assert_eq!(eval("0.5 * 3.0"), Ok(LiteralValue::Float(1.5)));
+        assert_eq!(eval("\"a\" + \"b\""), Ok(LiteralValue::Str(String::from("ab"))));
+        assert_eq!(eval("(2 * 3) >= 6"), Ok(LiteralValue::Bool(true)));
+        assert_eq!(eval("-1 < 1"), Ok(LiteralValue::Bool(true)));
+        assert_eq!(eval("1.5 != 1.5"), Ok(LiteralValue::Bool(false)));
+        assert_eq!(eval("\"a\" == \"a\""), Ok(LiteralValue::Bool(true)));
+        assert_eq!(eval("len!([1, 2, 3]) * 2"), Ok(LiteralValue::UInt(6)));
+        assert!(matches!(eval("len!(a)"), Err(EvalError::NotConstant { .. })));
+    }
+
+    #[test]
+    fn sizes_of_primitive_types() {
+        assert_eq!(eval("sizeof<i32>() + sizeof<i32>()"), Ok(LiteralValue::UInt(8)));
+        assert_eq!(eval("sizeof<u8>()"), Ok(LiteralValue::UInt(1)));
+        assert_eq!(eval("sizeof<f64>()"), Ok(LiteralValue::UInt(8)));
+        assert_eq!(eval("sizeof<bool>()"), Ok(LiteralValue::UInt(1)));
+        assert_eq!(eval("sizeof<[i16; 4]>()"), Ok(LiteralValue::UInt(8)));
+        assert_eq!(eval("sizeof<*const Point>()"), Ok(LiteralValue::UInt(8)));
+        // the layout of a struct is only known once types are checked
+        assert!(matches!(eval("sizeof<Point>()"), Err(EvalError::NotConstant { .. })));
+    }
+
+    #[test]
+    fn kinds_of_expressions() {
+        assert_eq!(
+            eval("kind_of!(a.b) + kind_of!([1])"),
+            Ok(LiteralValue::Str("FieldAccessArray".into()))
+        );
+        assert!(matches!(eval("kind_of!()"), Err(EvalError::NotConstant { .. })));
+
+        // floats are taken as they were lexed, suffixed or not
+        assert_eq!(eval("kind_of!(2.0)"), Ok(LiteralValue::Str("FloatLiteral".into())));
+        assert_eq!(eval("kind_of!(1.0f32)"), Ok(LiteralValue::Str("FloatLiteral".into())));
+        assert_eq!(eval("kind_of!(t.0.1)"), Ok(LiteralValue::Str("TupleAccess".into())));
+    }
+
+    #[test]
+    fn booleans_are_negated() {
+        assert_eq!(eval("!true"), Ok(LiteralValue::Bool(false)));
+        assert_eq!(eval("!(1 == 2) && !!true"), Ok(LiteralValue::Bool(true)));
+        assert_eq!(eval("!false == true"), Ok(LiteralValue::Bool(true)));
+        assert!(matches!(eval("!a"), Err(EvalError::NotConstant { .. })));
+
+        let Err(error) = eval("!1") else {
+            panic!("expected `!` to only take booleans");
+        };
+        assert_eq!(error.to_string(), "`!` can only negate a boolean, found an unsigned integer");
+    }
+
+    #[test]
+    fn logical_operators_short_circuit() {
+        assert_eq!(eval("false && (1 / 0 == 1)"), Ok(LiteralValue::Bool(false)));
+        assert_eq!(eval("true || anything"), Ok(LiteralValue::Bool(true)));
+        assert_eq!(eval("true && 1 == 2"), Ok(LiteralValue::Bool(false)));
+        assert!(matches!(eval("false || a"), Err(EvalError::NotConstant { .. })));
+    }
+
+    #[test]
+    fn errors_point_at_the_operation() {
+        let source = "1 + (18446744073709551615 * 2)";
+        let Err(EvalError::Overflow { span }) = eval(source) else {
+            panic!("expected an overflow");
+        };
+        assert_eq!(
+            &source[span.offset()..span.offset() + span.len()],
+            "18446744073709551615 * 2"
+        );
+
+        // unsuffixed integers are signed once inferred, suffixed ones aren't
+        assert_eq!(eval("1 - 2"), Ok(LiteralValue::Int(-1)));
+        assert_eq!(eval("(1 + 2) - 10"), Ok(LiteralValue::Int(-7)));
+        assert_eq!(eval("3 - 1"), Ok(LiteralValue::UInt(2)));
+        assert!(matches!(eval("1u8 - 2u8"), Err(EvalError::Overflow { .. })));
+        assert!(matches!(eval("0 - 18446744073709551615"), Err(EvalError::Overflow { .. })));
+        assert!(matches!(
+            eval("-9223372036854775807 - 2"),
+            Err(EvalError::Overflow { .. })
+        ));
+        assert!(matches!(eval("4 / 0"), Err(EvalError::DivisionByZero { .. })));
+        assert!(matches!(eval("f(1)"), Err(EvalError::NotConstant { .. })));
+
+        let Err(error) = eval("1 + 1.0") else {
+            panic!("expected mismatched operands");
+        };
+        assert_eq!(error.to_string(), "`+` can't take an unsigned integer and a float");
+    }
+}
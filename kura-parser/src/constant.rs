@@ -0,0 +1,92 @@
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::const_eval::{ConstExprEvaluator, LiteralValue};
+use crate::Expression;
+
+/// the value of an expression known while parsing
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConstValue {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Error, Diagnostic)]
+#[error("cannot verify constness at parse time")]
+#[diagnostic(
+    code(kura::unverified_const),
+    severity(Warning),
+    help("constants can only be made of literals and operations on them")
+)]
+pub struct UnverifiedConst {
+    #[label("this value")]
+    pub value: SourceSpan,
+}
+
+/// evaluates `expr` if it is made only of literals, binary operations on
+/// them and blocks of those. identifiers and calls could refer to anything, so they are never
+/// constant, and neither are operations that overflow or divide by zero
+pub fn eval_const(expr: &Expression<'_>) -> Option<ConstValue> {
+    match ConstExprEvaluator::eval(expr).ok()? {
+        LiteralValue::Int(value) => Some(ConstValue::Int(value)),
+        LiteralValue::UInt(value) => i64::try_from(value).ok().map(ConstValue::Int),
+        LiteralValue::Float(value) => Some(ConstValue::Float(value)),
+        LiteralValue::Bool(value) => Some(ConstValue::Bool(value)),
+        LiteralValue::Str(value) => Some(ConstValue::Str(value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::warning::DiagnosticWarning;
+    use crate::Parser;
+
+    fn eval(value: &str) -> Option<ConstValue> {
+        let (expression, _) = Parser::parse_repl_line(value).unwrap();
+        eval_const(&expression)
+    }
+
+    #[test]
+    fn literals_and_operations_on_them_are_constant() {
+        assert_eq!(eval("1 + 2 * 3"), Some(ConstValue::Int(7)));
+        assert_eq!(eval("-4 / 2"), Some(ConstValue::Int(-2)));
+        assert_eq!(eval("1.5 * 2.0"), Some(ConstValue::Float(3.0)));
+        assert_eq!(eval("true && 1 == 1"), Some(ConstValue::Bool(true)));
+        assert_eq!(eval("\"a\" + \"b\""), Some(ConstValue::Str(String::from("ab"))));
+        assert_eq!(eval("\"a\" != \"b\""), Some(ConstValue::Bool(true)));
+        assert_eq!(eval("{ 1 }"), Some(ConstValue::Int(1)));
+    }
+
+    #[test]
+    fn anything_else_is_not() {
+        for value in [
+            "a",
+            "1 + a",
+            "f()",
+            "1 / 0",
+            "9223372036854775807 + 1",
+            "1 + 1.0",
+            "1 == true",
+            "{ a }",
+        ] {
+            assert_eq!(eval(value), None, "{value}");
+        }
+    }
+
+    #[test]
+    fn const_bindings_are_verified() {
+        let source = "fun main() { const a = 1 + 2; const b = a * 2; var c = f(); }";
+        let (_, warnings) = Parser::new(source, Lexer::new(source)).parse_with_warnings().unwrap();
+
+        let [DiagnosticWarning::UnverifiedConst(warning)] = warnings.as_slice() else {
+            panic!("expected a single warning, got {warnings:?}");
+        };
+        let span = warning.value;
+        assert_eq!(&source[span.offset()..span.offset() + span.len()], "a * 2");
+    }
+}
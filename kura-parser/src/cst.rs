@@ -0,0 +1,783 @@
+use kura_lexer::token::{Kind, Location};
+use kura_lexer::{Lexer, UTF8_BOM};
+
+use crate::{Expression, GenericParam, MatchArm, ParseError, Parser, Pattern, Statement, TypeExpr, WherePredicate};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+    Shebang,
+    ByteOrderMark,
+    /// bytes the lexer couldn't turn into a token, like an unknown character
+    Skipped,
+}
+
+/// source text that carries no meaning for the parser, but has to be kept
+/// to reproduce the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trivia<'src> {
+    pub kind: TriviaKind,
+    pub text: &'src str,
+    pub location: Location,
+}
+
+/// a token along with the trivia around it. `trailing` trivia is whatever
+/// follows the token on the same line, everything else before the next
+/// token is `leading` trivia of it
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstToken<'src> {
+    pub kind: Kind<'src>,
+    pub text: &'src str,
+    pub location: Location,
+    pub leading: Vec<Trivia<'src>>,
+    pub trailing: Vec<Trivia<'src>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Root,
+    Function,
+    Argument,
+    Struct,
+    Impl,
+    Variable,
+    Attribute,
+    Bool,
+    If,
+    Call,
+    Assign,
+    Ident,
+    Path,
+    Block,
+    Literal,
+    BinaryOp,
+    Elvis,
+    Assert,
+    Return,
+    Yield,
+    String,
+    Macro,
+    InlineAsm,
+    Await,
+    RuntimeTypeOf,
+    Array,
+    ArrayLen,
+    Splat,
+    Sizeof,
+    SafeCast,
+    Tuple,
+    FieldAccess,
+    TupleAccess,
+    Index,
+    Try,
+    Lambda,
+    Let,
+    UnsafeBlock,
+    ComptimeBlock,
+    Defer,
+    Ref,
+    Deref,
+    Not,
+    Match,
+    MatchArm,
+    Pattern,
+    EnumVariant,
+    DoBlock,
+    Type,
+    GenericParam,
+    WherePredicate,
+    MacroRules,
+    Mod,
+    ExternCrate,
+    Use,
+    Static,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CstElement<'src> {
+    Node(CstNode<'src>),
+    Token(CstToken<'src>),
+}
+
+/// a node of the concrete syntax tree, mirroring an ast node. tokens that
+/// belong to the node but to none of its children, like keywords and
+/// punctuation, are kept as its direct children
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstNode<'src> {
+    pub kind: NodeKind,
+    /// location of the ast node this one mirrors
+    pub location: Location,
+    pub children: Vec<CstElement<'src>>,
+}
+
+/// a lossless view of a source: writing out the text of the tree gives the
+/// source back byte for byte. built alongside the ast, which is what most
+/// consumers still want
+#[derive(Debug)]
+pub struct Cst<'src> {
+    pub root: CstNode<'src>,
+    /// every statement parsed before the first syntax error, if any
+    pub statements: Vec<Statement<'src>>,
+    pub error: Option<ParseError>,
+}
+
+impl Trivia<'_> {
+    fn write_text(&self, output: &mut String) {
+        output.push_str(self.text);
+    }
+}
+
+impl CstToken<'_> {
+    fn write_text(&self, output: &mut String) {
+        self.leading.iter().for_each(|trivia| trivia.write_text(output));
+        output.push_str(self.text);
+        self.trailing.iter().for_each(|trivia| trivia.write_text(output));
+    }
+}
+
+impl<'src> CstNode<'src> {
+    pub fn text(&self) -> String {
+        let mut output = String::new();
+        self.write_text(&mut output);
+        output
+    }
+
+    fn write_text(&self, output: &mut String) {
+        for child in &self.children {
+            match child {
+                CstElement::Node(node) => node.write_text(output),
+                CstElement::Token(token) => token.write_text(output),
+            }
+        }
+    }
+
+    /// every token in the node, in source order
+    pub fn tokens(&self) -> Box<dyn Iterator<Item = &CstToken<'src>> + '_> {
+        Box::new(self.children.iter().flat_map(|child| match child {
+            CstElement::Node(node) => node.tokens(),
+            CstElement::Token(token) => Box::new(std::iter::once(token)),
+        }))
+    }
+
+    /// the innermost node mirroring the ast node at `location`
+    pub fn find(&self, location: Location) -> Option<&CstNode<'src>> {
+        let inner = self.children.iter().find_map(|child| match child {
+            CstElement::Node(node) => node.find(location),
+            CstElement::Token(_) => None,
+        });
+
+        match inner {
+            Some(node) => Some(node),
+            None if self.location == location => Some(self),
+            None => None,
+        }
+    }
+}
+
+impl Cst<'_> {
+    pub fn text(&self) -> String {
+        self.root.text()
+    }
+}
+
+/// parses `source` into both its ast and its concrete syntax tree. on a
+/// syntax error, the tree holds the statements parsed before it, followed
+/// by the rest of the tokens as direct children of the root
+pub fn parse_lossless(source: &str) -> Cst<'_> {
+    let mut parser = Parser::new(source, Lexer::new(source));
+    let mut statements = vec![];
+    let mut error = None;
+
+    while !parser.lexer.is_empty() {
+        match parser.parse_statement() {
+            Ok(statement) => statements.push(statement),
+            Err(e) => {
+                error = Some(e);
+                break;
+            }
+        }
+    }
+
+    let root = Shape {
+        kind: NodeKind::Root,
+        location: Location::new(0, source.len()),
+        start: 0,
+        end: usize::MAX,
+        children: statements.iter().map(statement_shape).collect(),
+    };
+
+    let mut tokens = tokenize(source).into_iter().peekable();
+    let root = assemble(root, &mut tokens);
+
+    Cst {
+        root,
+        statements,
+        error,
+    }
+}
+
+enum Item<'src> {
+    Token(Kind<'src>, Location),
+    Trivia(TriviaKind, Location),
+}
+
+/// pushes `item`, along with the whitespace between it and `cursor`
+fn push<'src>(items: &mut Vec<Item<'src>>, item: Item<'src>, cursor: &mut usize) {
+    let location = match &item {
+        Item::Token(_, location) | Item::Trivia(_, location) => *location,
+    };
+    if location.start_byte > *cursor {
+        let gap = Location::new(*cursor, location.start_byte);
+        items.push(Item::Trivia(TriviaKind::Whitespace, gap));
+    }
+    *cursor = location.end_byte;
+    items.push(item);
+}
+
+/// lexes the whole source, comments included, into tokens with their trivia
+/// attached. the last token is always an empty `Eof` holding the trivia at
+/// the end of the source
+fn tokenize(source: &str) -> Vec<CstToken<'_>> {
+    let mut items = vec![];
+    let mut lexer = Lexer::new(source).preserve_comments(true);
+    let mut cursor = 0;
+
+    if source.starts_with(UTF8_BOM) {
+        let bom = Location::new(0, UTF8_BOM.len());
+        push(&mut items, Item::Trivia(TriviaKind::ByteOrderMark, bom), &mut cursor);
+    }
+
+    loop {
+        let skipped = match lexer.next() {
+            None => break,
+            Some(Ok(token)) => match token.kind {
+                Kind::LineComment(_) => {
+                    push(
+                        &mut items,
+                        Item::Trivia(TriviaKind::LineComment, token.location),
+                        &mut cursor,
+                    );
+                    continue;
+                }
+                Kind::BlockComment(_) => {
+                    push(
+                        &mut items,
+                        Item::Trivia(TriviaKind::BlockComment, token.location),
+                        &mut cursor,
+                    );
+                    continue;
+                }
+                Kind::Shebang(_) => {
+                    push(
+                        &mut items,
+                        Item::Trivia(TriviaKind::Shebang, token.location),
+                        &mut cursor,
+                    );
+                    continue;
+                }
+                kind => {
+                    push(&mut items, Item::Token(kind, token.location), &mut cursor);
+                    continue;
+                }
+            },
+            Some(Err(error)) => error.location(),
+        };
+
+        let start = skipped.start_byte.max(cursor);
+        let next_char = source[start..].chars().next().map_or(0, char::len_utf8);
+        let end = skipped.end_byte.max(start + next_char).min(source.len());
+        push(
+            &mut items,
+            Item::Trivia(TriviaKind::Skipped, Location::new(start, end)),
+            &mut cursor,
+        );
+
+        if end >= source.len() {
+            break;
+        }
+        lexer = lexer.sub_lexer(end..source.len()).preserve_comments(true);
+    }
+
+    let end = Location::new(source.len(), source.len());
+    push(&mut items, Item::Token(Kind::Eof, end), &mut cursor);
+
+    attach_trivia(source, items)
+}
+
+fn attach_trivia<'src>(source: &'src str, items: Vec<Item<'src>>) -> Vec<CstToken<'src>> {
+    let text = |location: Location| &source[location.start_byte..location.end_byte];
+    let trivia = |kind, location| Trivia {
+        kind,
+        text: text(location),
+        location,
+    };
+
+    let mut tokens: Vec<CstToken<'src>> = vec![];
+    let mut leading = vec![];
+    // whether we are still on the line of the last token
+    let mut same_line = false;
+
+    for item in items {
+        match item {
+            Item::Token(kind, location) => {
+                tokens.push(CstToken {
+                    kind,
+                    text: text(location),
+                    location,
+                    leading: std::mem::take(&mut leading),
+                    trailing: vec![],
+                });
+                same_line = true;
+            }
+            Item::Trivia(kind, location) if same_line => {
+                let last = tokens.last_mut().expect("trivia on the same line follows a token");
+                let newline = match kind {
+                    TriviaKind::Whitespace => text(location).find('\n'),
+                    _ => None,
+                };
+
+                match newline {
+                    Some(offset) => {
+                        let split = location.start_byte + offset;
+                        if offset > 0 {
+                            let before = Location::new(location.start_byte, split);
+                            last.trailing.push(trivia(kind, before));
+                        }
+                        leading.push(trivia(kind, Location::new(split, location.end_byte)));
+                        same_line = false;
+                    }
+                    None => last.trailing.push(trivia(kind, location)),
+                }
+            }
+            Item::Trivia(kind, location) => leading.push(trivia(kind, location)),
+        }
+    }
+
+    tokens
+}
+
+/// the shape of an ast node, used to lay tokens out into the tree. `start`
+/// and `end` are the bytes the node takes tokens from, which can be wider
+/// than its location, like attributes written before a variable
+struct Shape {
+    kind: NodeKind,
+    location: Location,
+    start: usize,
+    end: usize,
+    children: Vec<Shape>,
+}
+
+impl Shape {
+    fn new(kind: NodeKind, location: Location, children: Vec<Shape>) -> Self {
+        Self {
+            kind,
+            location,
+            start: location.start_byte,
+            end: location.end_byte,
+            children,
+        }
+    }
+
+    fn leaf(kind: NodeKind, location: Location) -> Self {
+        Self::new(kind, location, vec![])
+    }
+}
+
+fn statement_shape(statement: &Statement<'_>) -> Shape {
+    match statement {
+        Statement::FunArgument { arg_type, location, .. } => {
+            Shape::new(NodeKind::Argument, *location, vec![type_shape(arg_type)])
+        }
+        Statement::Fun {
+            generics,
+            arguments,
+            body,
+            return_type,
+            where_clause,
+            attributes,
+            location,
+            ..
+        } => {
+            let children = attributes
+                .iter()
+                .map(|attribute| Shape::leaf(NodeKind::Attribute, attribute.location))
+                .chain(generics.iter().map(generic_shape))
+                .chain(arguments.iter().map(statement_shape))
+                .chain(return_type.iter().map(|typ| type_shape(typ)))
+                .chain(where_clause.iter().map(where_shape))
+                .chain(body.iter().map(expression_shape))
+                .collect();
+            Shape::new(NodeKind::Function, *location, children)
+        }
+        Statement::Struct {
+            generics,
+            fields,
+            attributes,
+            location,
+            ..
+        } => {
+            let children = attributes
+                .iter()
+                .map(|attribute| Shape::leaf(NodeKind::Attribute, attribute.location))
+                .chain(generics.iter().map(generic_shape))
+                .chain(fields.iter().map(statement_shape))
+                .collect();
+            Shape::new(NodeKind::Struct, *location, children)
+        }
+        Statement::Impl {
+            generics,
+            arguments,
+            functions,
+            location,
+            ..
+        } => Shape::new(
+            NodeKind::Impl,
+            *location,
+            generics
+                .iter()
+                .map(generic_shape)
+                .chain(arguments.iter().map(type_shape))
+                .chain(functions.iter().map(statement_shape))
+                .collect(),
+        ),
+        // the rules are raw tokens, kept as direct children of the node
+        Statement::MacroRules { location, .. } => Shape::leaf(NodeKind::MacroRules, *location),
+        Statement::Mod { location, .. } => Shape::leaf(NodeKind::Mod, *location),
+        Statement::Use { location, .. } => Shape::leaf(NodeKind::Use, *location),
+        Statement::ExternCrate {
+            attributes, location, ..
+        } => Shape::new(
+            NodeKind::ExternCrate,
+            *location,
+            attributes
+                .iter()
+                .map(|attribute| Shape::leaf(NodeKind::Attribute, attribute.location))
+                .collect(),
+        ),
+        Statement::Static {
+            typ,
+            value,
+            attributes,
+            location,
+            ..
+        } => {
+            let children = attributes
+                .iter()
+                .map(|attribute| Shape::leaf(NodeKind::Attribute, attribute.location))
+                .chain(typ.iter().map(|typ| type_shape(typ)))
+                .chain([expression_shape(value)])
+                .collect();
+            Shape::new(NodeKind::Static, *location, children)
+        }
+    }
+}
+
+/// a generic parameter, with the traits bounding it as children
+fn generic_shape(param: &GenericParam<'_>) -> Shape {
+    Shape::new(
+        NodeKind::GenericParam,
+        param.location,
+        param.bounds.iter().map(type_shape).collect(),
+    )
+}
+
+/// a where predicate, with the type it bounds followed by its bounds
+fn where_shape(predicate: &WherePredicate<'_>) -> Shape {
+    Shape::new(
+        NodeKind::WherePredicate,
+        predicate.location,
+        std::iter::once(&predicate.bounded)
+            .chain(&predicate.bounds)
+            .map(type_shape)
+            .collect(),
+    )
+}
+
+/// a type and every type nested in it, like the elements of a tuple
+fn type_shape(typ: &TypeExpr<'_>) -> Shape {
+    let children = match typ {
+        TypeExpr::Named { .. } | TypeExpr::Lifetime { .. } | TypeExpr::Never { .. } => vec![],
+        TypeExpr::Fn {
+            parameters, returns, ..
+        }
+        | TypeExpr::FnTrait {
+            parameters, returns, ..
+        } => parameters.iter().chain(returns.as_deref()).map(type_shape).collect(),
+        TypeExpr::Array { element: inner, .. }
+        | TypeExpr::Ptr { pointee: inner, .. }
+        | TypeExpr::ImplTrait { bound: inner, .. }
+        | TypeExpr::Ref { referent: inner, .. } => vec![type_shape(inner)],
+        TypeExpr::Tuple { elements: types, .. } | TypeExpr::Generic { arguments: types, .. } => {
+            types.iter().map(type_shape).collect()
+        }
+    };
+    Shape::new(NodeKind::Type, typ.location(), children)
+}
+
+fn arm_shape(arm: &MatchArm<'_>) -> Shape {
+    Shape::new(
+        NodeKind::MatchArm,
+        arm.location,
+        vec![pattern_shape(&arm.pattern), expression_shape(&arm.body)],
+    )
+}
+
+fn pattern_shape(pattern: &Pattern<'_>) -> Shape {
+    match pattern {
+        Pattern::Literal(literal) => expression_shape(literal),
+        Pattern::Variant { kind, .. } => Shape::new(
+            NodeKind::Pattern,
+            pattern.location(),
+            kind.values().map(pattern_shape).collect(),
+        ),
+        pattern => Shape::new(NodeKind::Pattern, pattern.location(), vec![]),
+    }
+}
+
+fn expression_shape(expr: &Expression<'_>) -> Shape {
+    let location = expr.location();
+    let (kind, children) = match expr {
+        Expression::Var {
+            typ, value, attributes, ..
+        } => {
+            let children = attributes
+                .iter()
+                .map(|attribute| Shape::leaf(NodeKind::Attribute, attribute.location))
+                .chain(typ.iter().map(|typ| type_shape(typ)))
+                .chain([expression_shape(value)])
+                .collect();
+            let mut shape = Shape::new(NodeKind::Variable, location, children);
+            if let Some(attribute) = attributes.first() {
+                shape.start = attribute.location.start_byte;
+            }
+            return shape;
+        }
+        Expression::Assert { condition, message, .. } => {
+            let children = [condition.as_ref()]
+                .into_iter()
+                .chain(message.as_deref())
+                .map(expression_shape)
+                .collect();
+            (NodeKind::Assert, children)
+        }
+        Expression::If {
+            condition,
+            truthy,
+            falsy,
+            ..
+        } => {
+            let children = [condition.as_ref(), truthy.as_ref()]
+                .into_iter()
+                .chain(falsy)
+                .map(expression_shape)
+                .collect();
+            (NodeKind::If, children)
+        }
+        Expression::FunCall { ident, arguments, .. } => {
+            let children = [ident.as_ref()]
+                .into_iter()
+                .chain(arguments)
+                .map(expression_shape)
+                .collect();
+            (NodeKind::Call, children)
+        }
+        Expression::Assign { ident, value, .. } | Expression::CompoundAssign { ident, value, .. } => {
+            (NodeKind::Assign, vec![expression_shape(ident), expression_shape(value)])
+        }
+        Expression::Block { expressions, tail, .. } => (
+            NodeKind::Block,
+            expressions
+                .iter()
+                .chain(tail.as_deref())
+                .map(expression_shape)
+                .collect(),
+        ),
+        Expression::BinaryOp { lhs, rhs, .. } => {
+            (NodeKind::BinaryOp, vec![expression_shape(lhs), expression_shape(rhs)])
+        }
+        Expression::Elvis { condition, default, .. } => (
+            NodeKind::Elvis,
+            vec![expression_shape(condition), expression_shape(default)],
+        ),
+        Expression::Return { value, .. } => (NodeKind::Return, vec![expression_shape(value)]),
+        Expression::Yield { value, .. } => (
+            NodeKind::Yield,
+            value.iter().map(|value| expression_shape(value)).collect(),
+        ),
+        Expression::Await { expr, .. } => (NodeKind::Await, vec![expression_shape(expr)]),
+        Expression::RuntimeTypeOf { expr, .. } => (NodeKind::RuntimeTypeOf, vec![expression_shape(expr)]),
+        Expression::Array { elements, .. } => (NodeKind::Array, elements.iter().map(expression_shape).collect()),
+        Expression::Tuple { elements, .. } => (NodeKind::Tuple, elements.iter().map(expression_shape).collect()),
+        Expression::ArrayLen { array, .. } => (NodeKind::ArrayLen, vec![expression_shape(array)]),
+        Expression::Splat { expr, .. } => (NodeKind::Splat, vec![expression_shape(expr)]),
+        Expression::Sizeof { typ, .. } => (NodeKind::Sizeof, vec![type_shape(typ)]),
+        Expression::SafeCast { expr, typ, .. } => (NodeKind::SafeCast, vec![expression_shape(expr), type_shape(typ)]),
+        Expression::UnsafeBlock { body, .. } => (NodeKind::UnsafeBlock, vec![expression_shape(body)]),
+        Expression::ComptimeBlock { body, .. } => (NodeKind::ComptimeBlock, vec![expression_shape(body)]),
+        Expression::Defer { body, .. } => (NodeKind::Defer, vec![expression_shape(body)]),
+        Expression::Ref { expr, .. } => (NodeKind::Ref, vec![expression_shape(expr)]),
+        Expression::Deref { expr, .. } => (NodeKind::Deref, vec![expression_shape(expr)]),
+        Expression::Not { expr, .. } => (NodeKind::Not, vec![expression_shape(expr)]),
+        Expression::FieldAccess { target, .. } => (NodeKind::FieldAccess, vec![expression_shape(target)]),
+        Expression::TupleAccess { tuple, .. } => (NodeKind::TupleAccess, vec![expression_shape(tuple)]),
+        Expression::Index { target, index, .. } => {
+            (NodeKind::Index, vec![expression_shape(target), expression_shape(index)])
+        }
+        Expression::Try { expr, .. } => (NodeKind::Try, vec![expression_shape(expr)]),
+        Expression::Lambda {
+            parameters,
+            parameter_types,
+            return_type,
+            body,
+            ..
+        } => {
+            let children = parameters
+                .iter()
+                .zip(parameter_types)
+                .flat_map(|(parameter, typ)| [Some(expression_shape(parameter)), typ.as_ref().map(type_shape)])
+                .flatten()
+                .chain(return_type.as_deref().map(type_shape))
+                .chain([expression_shape(body)])
+                .collect();
+            (NodeKind::Lambda, children)
+        }
+        Expression::Let { value, body, .. } => (NodeKind::Let, vec![expression_shape(value), expression_shape(body)]),
+        Expression::Match { scrutinee, arms, .. } => {
+            let children = std::iter::once(expression_shape(scrutinee))
+                .chain(arms.iter().map(arm_shape))
+                .collect();
+            (NodeKind::Match, children)
+        }
+        Expression::EnumVariant { kind, .. } => (NodeKind::EnumVariant, kind.values().map(expression_shape).collect()),
+        Expression::DoBlock { steps, .. } => (
+            NodeKind::DoBlock,
+            steps.iter().map(|step| expression_shape(step.expr())).collect(),
+        ),
+        // what is embedded into a string lives inside of its single token
+        Expression::StringLiteral { .. } | Expression::Interpolated { .. } => (NodeKind::String, vec![]),
+        Expression::Macro { .. } => (NodeKind::Macro, vec![]),
+        Expression::InlineAsm { outputs, inputs, .. } => (
+            NodeKind::InlineAsm,
+            outputs
+                .iter()
+                .chain(inputs)
+                .map(|operand| expression_shape(&operand.expr))
+                .collect(),
+        ),
+        Expression::Bool { .. } => (NodeKind::Bool, vec![]),
+        Expression::Ident { .. } => (NodeKind::Ident, vec![]),
+        Expression::Path { .. } => (NodeKind::Path, vec![]),
+        Expression::UintLiteral { .. } | Expression::FloatLiteral { .. } | Expression::IntLiteral { .. } => {
+            (NodeKind::Literal, vec![])
+        }
+    };
+
+    Shape::new(kind, location, children)
+}
+
+/// lays the tokens inside of `shape` out into a node. every token is taken
+/// exactly once and in order, so the text of the tree is always the source,
+/// no matter how the ast locations line up with the tokens
+fn assemble<'src>(shape: Shape, tokens: &mut std::iter::Peekable<std::vec::IntoIter<CstToken<'src>>>) -> CstNode<'src> {
+    let mut shapes = shape.children;
+    shapes.sort_by_key(|child| child.start);
+    let mut shapes = shapes.into_iter().peekable();
+    let mut children = vec![];
+
+    while let Some(token) = tokens.peek() {
+        let start = token.location.start_byte;
+        if start >= shape.end {
+            break;
+        }
+
+        if let Some(child) = shapes.next_if(|child| start >= child.start) {
+            children.push(CstElement::Node(assemble(child, tokens)));
+            continue;
+        }
+
+        let token = tokens.next().expect("the token was just peeked");
+        children.push(CstElement::Token(token));
+    }
+
+    CstNode {
+        kind: shape.kind,
+        location: shape.location,
+        children,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(node: &CstNode<'_>) -> String {
+        let children = node
+            .children
+            .iter()
+            .map(|child| match child {
+                CstElement::Node(node) => kinds(node),
+                CstElement::Token(token) => token.text.to_string(),
+            })
+            .collect::<Vec<_>>();
+        format!("({:?} {})", node.kind, children.join(" "))
+    }
+
+    #[test]
+    fn trivia_is_split_at_the_end_of_the_line() {
+        let source = "fun main() { // trailing\n    // leading\n    return 1; /* after */ }";
+        let cst = parse_lossless(source);
+
+        let tokens = cst.root.tokens().collect::<Vec<_>>();
+        let brace = tokens.iter().find(|token| token.text == "{").unwrap();
+        let trailing = brace.trailing.iter().map(|trivia| trivia.text).collect::<Vec<_>>();
+        assert_eq!(trailing, [" ", "// trailing"]);
+
+        let keyword = tokens.iter().find(|token| token.text == "return").unwrap();
+        let leading = keyword.leading.iter().map(|trivia| trivia.text).collect::<Vec<_>>();
+        assert_eq!(leading, ["\n    ", "// leading", "\n    "]);
+
+        let semicolon = tokens.iter().find(|token| token.text == ";").unwrap();
+        assert_eq!(semicolon.trailing.len(), 3);
+        assert_eq!(cst.text(), source);
+    }
+
+    #[test]
+    fn nodes_mirror_the_ast() {
+        let source = "fun f(a: i32) { #[allow(shadow)] const b = g(a) + 1; }";
+        let cst = parse_lossless(source);
+
+        assert_eq!(
+            kinds(&cst.root),
+            concat!(
+                "(Root (Function fun f ( (Argument a : (Type i32)) ) { ",
+                "(Variable (Attribute # [ allow ( shadow ) ]) const b = ",
+                "(BinaryOp (Call (Ident g) ( (Ident a) )) + (Literal 1))) ; }) )"
+            )
+        );
+
+        let Statement::Fun { body, .. } = &cst.statements[0] else {
+            panic!("expected a function");
+        };
+        let node = cst.root.find(body[0].location()).unwrap();
+        assert_eq!(node.kind, NodeKind::Variable);
+        assert_eq!(node.text(), "#[allow(shadow)] const b = g(a) + 1");
+    }
+
+    #[test]
+    fn sources_that_dont_parse_are_kept_too() {
+        for source in [
+            "fun main() { const = 1; }",
+            "fun a() {} @ fun",
+            "\"unterminated",
+            "",
+            "  \n",
+        ] {
+            let cst = parse_lossless(source);
+            assert_eq!(cst.text(), source);
+        }
+
+        assert!(parse_lossless("fun main() { const = 1; }").error.is_some());
+    }
+}
@@ -0,0 +1,80 @@
+use kura_lexer::Lexer;
+
+/// the doc comments written right before the next token of `lexer`, in the
+/// order they were written and with their markers, like `/// adds two
+/// numbers` or `/** adds two numbers */`. the ones before any other token
+/// are dropped once it is lexed, so they never reach a later declaration
+pub fn parse_doc_comment<'src>(lexer: &mut Lexer<'src>) -> Vec<&'src str> {
+    lexer.doc_comments()
+}
+
+/// the text of `doc`, without its `///`, `/**` and `*/` markers, nor the
+/// `*` starting the lines of a block comment. every line is trimmed, and
+/// the blank ones around the text of each comment are dropped
+pub fn format_doc<S: AsRef<str>>(doc: &[S]) -> String {
+    doc.iter()
+        .flat_map(|comment| {
+            let comment = comment.as_ref();
+            match comment.strip_prefix("/**") {
+                Some(block) => {
+                    let lines = block
+                        .strip_suffix("*/")
+                        .unwrap_or(block)
+                        .lines()
+                        .map(|line| {
+                            let line = line.trim();
+                            line.strip_prefix('*').unwrap_or(line).trim()
+                        })
+                        .collect::<Vec<_>>();
+                    trim_blank_lines(&lines).to_vec()
+                }
+                None => vec![comment.strip_prefix("///").unwrap_or(comment).trim()],
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+fn trim_blank_lines<'a, 'src>(lines: &'a [&'src str]) -> &'a [&'src str] {
+    let first = lines.iter().position(|line| !line.is_empty()).unwrap_or(lines.len());
+    let last = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(first, |last| last + 1);
+    &lines[first..last]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_doc_comments_are_stripped() {
+        assert_eq!(
+            format_doc(&["/// adds two", "///numbers  ", "///"]),
+            "adds two\nnumbers"
+        );
+        assert_eq!(format_doc::<&str>(&[]), "");
+    }
+
+    #[test]
+    fn block_doc_comments_are_stripped() {
+        assert_eq!(format_doc(&["/** adds two numbers */"]), "adds two numbers");
+        let block = "/**\n     * adds two\n     *   numbers\n     */";
+        assert_eq!(format_doc(&[block, "/// and more"]), "adds two\nnumbers\nand more");
+    }
+
+    #[test]
+    fn doc_comments_are_taken_before_the_next_token() {
+        let source = "/// first\n// plain\n//// not a doc\n/** second */ fun /// dropped\n f";
+        let mut lexer = Lexer::new(source);
+        assert_eq!(parse_doc_comment(&mut lexer), ["/// first", "/** second */"]);
+        assert!(parse_doc_comment(&mut lexer).is_empty());
+        lexer.next();
+        assert_eq!(parse_doc_comment(&mut lexer), ["/// dropped"]);
+        lexer.next();
+        assert!(parse_doc_comment(&mut lexer).is_empty());
+    }
+}
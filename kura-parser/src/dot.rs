@@ -0,0 +1,314 @@
+use kura_lexer::token::Token;
+
+use crate::{DoStep, Expression, Statement, TypeExpr};
+
+/// renders the tree of a program as a graphviz digraph, with one node per
+/// statement or expression
+pub fn statements_to_dot(statements: &[Statement<'_>]) -> String {
+    let mut graph = Graph {
+        output: String::from("digraph ast {\n    node [shape=box];\n"),
+        nodes: 0,
+    };
+
+    let program = graph.node("program");
+    for statement in statements {
+        let child = graph.statement(statement);
+        graph.edge(program, child);
+    }
+
+    graph.output.push_str("}\n");
+    graph.output
+}
+
+struct Graph {
+    output: String,
+    nodes: usize,
+}
+
+impl Graph {
+    fn node(&mut self, label: &str) -> usize {
+        let id = self.nodes;
+        self.nodes += 1;
+        // `{:?}` quotes the label and escapes anything that would break it
+        self.output.push_str(&format!("    n{id} [label={label:?}];\n"));
+        id
+    }
+
+    fn edge(&mut self, from: usize, to: usize) {
+        self.output.push_str(&format!("    n{from} -> n{to};\n"));
+    }
+
+    fn statement(&mut self, statement: &Statement<'_>) -> usize {
+        match statement {
+            Statement::FunArgument { name, arg_type, .. } => {
+                let id = self.node(&format!("arg {name}"));
+                let child = self.typ(arg_type);
+                self.edge(id, child);
+                id
+            }
+            Statement::Fun {
+                name,
+                generics,
+                arguments,
+                body,
+                return_type,
+                ..
+            } => {
+                let id = self.node(&format!("fun {name}{}", angled(generics)));
+                for argument in arguments {
+                    let child = self.statement(argument);
+                    self.edge(id, child);
+                }
+                if let Some(return_type) = return_type {
+                    let child = self.typ(return_type);
+                    self.edge(id, child);
+                }
+                for expr in body {
+                    let child = self.expression(expr);
+                    self.edge(id, child);
+                }
+                id
+            }
+            Statement::Struct {
+                name, generics, fields, ..
+            } => {
+                let id = self.node(&format!("struct {name}{}", angled(generics)));
+                for field in fields {
+                    let child = self.statement(field);
+                    self.edge(id, child);
+                }
+                id
+            }
+            Statement::Impl {
+                generics,
+                trait_name,
+                target,
+                arguments,
+                functions,
+                ..
+            } => {
+                let (generics, arguments) = (angled(generics), angled(arguments));
+                let label = match trait_name {
+                    Some(trait_name) => format!("impl{generics} {trait_name} for {target}{arguments}"),
+                    None => format!("impl{generics} {target}{arguments}"),
+                };
+                let id = self.node(&label);
+                for function in functions {
+                    let child = self.statement(function);
+                    self.edge(id, child);
+                }
+                id
+            }
+            Statement::Mod { name, .. } => self.node(&format!("mod {name}")),
+            Statement::Use { tree, .. } => self.node(&format!("use {tree}")),
+            Statement::ExternCrate { name, alias, .. } => match alias {
+                Some(alias) => self.node(&format!("extern crate {name} as {alias}")),
+                None => self.node(&format!("extern crate {name}")),
+            },
+            Statement::Static {
+                mutable,
+                name,
+                typ,
+                value,
+                ..
+            } => {
+                let keyword = if *mutable { "static var" } else { "static const" };
+                let id = self.node(&format!("{keyword} {name}"));
+                if let Some(typ) = typ {
+                    let child = self.typ(typ);
+                    self.edge(id, child);
+                }
+                let child = self.expression(value);
+                self.edge(id, child);
+                id
+            }
+            Statement::MacroRules { name, rules, .. } => {
+                let id = self.node(&format!("macro_rules! {name}"));
+                for rule in rules {
+                    let tokens = |tokens: &[Token<'_>]| {
+                        tokens
+                            .iter()
+                            .map(|token| token.kind.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    };
+                    let child = self.node(&format!(
+                        "({}) => {{ {} }}",
+                        tokens(&rule.pattern),
+                        tokens(&rule.expansion)
+                    ));
+                    self.edge(id, child);
+                }
+                id
+            }
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression<'_>) -> usize {
+        let (label, children): (String, Vec<&Expression<'_>>) = match expr {
+            Expression::Var {
+                mutable,
+                name,
+                typ,
+                value,
+                ..
+            } => {
+                let keyword = if *mutable { "var" } else { "const" };
+                let id = self.node(&format!("{keyword} {name}"));
+                if let Some(typ) = typ {
+                    let child = self.typ(typ);
+                    self.edge(id, child);
+                }
+                let child = self.expression(value);
+                self.edge(id, child);
+                return id;
+            }
+            Expression::Bool { value, .. } => (value.to_string(), vec![]),
+            Expression::Assert { condition, message, .. } => {
+                let children = [condition.as_ref()].into_iter().chain(message.as_deref()).collect();
+                (String::from("assert"), children)
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                let children = [condition.as_ref(), truthy.as_ref()].into_iter().chain(falsy).collect();
+                (String::from("if"), children)
+            }
+            Expression::FunCall { ident, arguments, .. } => {
+                let children = [ident.as_ref()].into_iter().chain(arguments).collect();
+                (String::from("call"), children)
+            }
+            Expression::Assign { ident, value, .. } => (String::from("="), vec![ident, value]),
+            Expression::CompoundAssign {
+                operator, ident, value, ..
+            } => (format!("{operator}="), vec![ident, value]),
+            Expression::Ident { name, .. } => (name.to_string(), vec![]),
+            Expression::Path { segments, .. } => (segments.join("::"), vec![]),
+            Expression::Block { expressions, tail, .. } => (
+                String::from("block"),
+                expressions.iter().chain(tail.as_deref()).collect(),
+            ),
+            Expression::UintLiteral { value, .. } => (value.to_string(), vec![]),
+            Expression::IntLiteral { value, .. } => (value.to_string(), vec![]),
+            Expression::FloatLiteral { value, .. } => (format!("{value:?}"), vec![]),
+            Expression::BinaryOp { operator, lhs, rhs, .. } => (operator.to_string(), vec![lhs, rhs]),
+            Expression::Elvis { condition, default, .. } => (String::from("?:"), vec![condition, default]),
+            Expression::Return { value, .. } => (String::from("return"), vec![value]),
+            Expression::Yield { value, .. } => (String::from("yield"), value.iter().map(AsRef::as_ref).collect()),
+            Expression::Await { expr, .. } => (String::from("await"), vec![expr]),
+            Expression::RuntimeTypeOf { expr, .. } => (String::from("typeof!"), vec![expr]),
+            Expression::Array { elements, .. } => (String::from("array"), elements.iter().collect()),
+            Expression::Tuple { elements, .. } => (String::from("tuple"), elements.iter().collect()),
+            Expression::ArrayLen { array, .. } => (String::from("len!"), vec![array]),
+            Expression::Splat { expr, .. } => (String::from("splat!"), vec![expr]),
+            Expression::Sizeof { typ, .. } => (format!("sizeof<{typ}>"), vec![]),
+            Expression::SafeCast { expr, typ, .. } => (format!("try_as {typ}"), vec![expr]),
+            Expression::UnsafeBlock { body, .. } => (String::from("unsafe"), vec![body]),
+            Expression::ComptimeBlock { body, .. } => (String::from("comptime"), vec![body]),
+            Expression::Defer { body, .. } => (String::from("defer"), vec![body]),
+            Expression::Ref {
+                mutable: true, expr, ..
+            } => (String::from("&mut"), vec![expr]),
+            Expression::Ref { expr, .. } => (String::from("&"), vec![expr]),
+            Expression::Deref { expr, .. } => (String::from("*"), vec![expr]),
+            Expression::Not { expr, .. } => (String::from("!"), vec![expr]),
+            Expression::FieldAccess { target, field, .. } => (format!(".{field}"), vec![target]),
+            Expression::TupleAccess { tuple, index, .. } => (format!(".{index}"), vec![tuple]),
+            Expression::Index { target, index, .. } => (String::from("[]"), vec![target, index]),
+            Expression::Try { expr, .. } => (String::from("?"), vec![expr]),
+            Expression::Lambda {
+                parameters,
+                parameter_types,
+                return_type,
+                body,
+                ..
+            } => {
+                let id = self.node("lambda");
+                for (parameter, typ) in parameters.iter().zip(parameter_types) {
+                    let child = self.expression(parameter);
+                    self.edge(id, child);
+                    if let Some(typ) = typ {
+                        let annotation = self.typ(typ);
+                        self.edge(child, annotation);
+                    }
+                }
+                if let Some(return_type) = return_type {
+                    let child = self.typ(return_type);
+                    self.edge(id, child);
+                }
+                let child = self.expression(body);
+                self.edge(id, child);
+                return id;
+            }
+            Expression::Let { name, value, body, .. } => (format!("let {name}"), vec![value, body]),
+            Expression::Match { scrutinee, arms, .. } => {
+                let id = self.node("match");
+                let child = self.expression(scrutinee);
+                self.edge(id, child);
+                for arm in arms {
+                    let arm_id = self.node(&format!("{} =>", arm.pattern));
+                    self.edge(id, arm_id);
+                    let child = self.expression(&arm.body);
+                    self.edge(arm_id, child);
+                }
+                return id;
+            }
+            Expression::EnumVariant { path, kind, .. } => (path.join("::"), kind.values().collect()),
+            Expression::DoBlock { steps, .. } => (String::from("do"), steps.iter().map(DoStep::expr).collect()),
+            Expression::StringLiteral { value, .. } => (format!("{value:?}"), vec![]),
+            Expression::Macro { name, .. } => (format!("{name}!"), vec![]),
+            Expression::InlineAsm { outputs, inputs, .. } => (
+                String::from("asm!"),
+                outputs.iter().chain(inputs).map(|operand| &operand.expr).collect(),
+            ),
+            Expression::Interpolated {
+                literals, expressions, ..
+            } => {
+                let children = literals.iter().chain(expressions).collect();
+                (String::from("interpolated"), children)
+            }
+        };
+
+        let id = self.node(&label);
+        for child in children {
+            let child = self.expression(child);
+            self.edge(id, child);
+        }
+        id
+    }
+
+    /// a type is a single node, written as it is in the source
+    fn typ(&mut self, typ: &TypeExpr<'_>) -> usize {
+        self.node(&typ.to_string())
+    }
+}
+
+/// `<T, U: Show>`, or nothing when there is nothing to write in it
+fn angled(items: &[impl std::fmt::Display]) -> String {
+    match items.is_empty() {
+        true => String::new(),
+        false => format!(
+            "<{}>",
+            items.iter().map(|item| item.to_string()).collect::<Vec<_>>().join(", ")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::Parser;
+
+    #[test]
+    fn program_as_dot() {
+        let source = r#"fun greet(name: Name) { print("hi {name}"); }"#;
+        let statements = Parser::new(source, Lexer::new(source)).parse().unwrap();
+
+        insta::assert_snapshot!(statements_to_dot(&statements));
+    }
+}
@@ -0,0 +1,80 @@
+use kura_lexer::token::{Location, Token};
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    #[default]
+    Invalid,
+    /// the source ended before what was being parsed was complete, so more
+    /// input could still make it valid
+    UnexpectedEof,
+}
+
+/// a syntax error, pointing at the part of the source that caused it
+#[derive(Debug, Clone, PartialEq, Error, Diagnostic)]
+#[error("{message}")]
+#[diagnostic(code(kura::syntax_error))]
+pub struct ParseError {
+    pub message: String,
+    #[label("here")]
+    pub span: SourceSpan,
+    pub kind: ErrorKind,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>, location: Location) -> Self {
+        Self {
+            message: message.into(),
+            span: location.into(),
+            kind: ErrorKind::Invalid,
+        }
+    }
+
+    pub fn eof(message: impl Into<String>, location: Location) -> Self {
+        Self {
+            kind: ErrorKind::UnexpectedEof,
+            ..Self::new(message, location)
+        }
+    }
+
+    pub fn invalid(self) -> Self {
+        Self {
+            kind: ErrorKind::Invalid,
+            ..self
+        }
+    }
+
+    /// whether the error would go away by appending more input, like the
+    /// missing closing brace of `if x == 1 {`
+    pub fn is_incomplete(&self) -> bool {
+        self.kind == ErrorKind::UnexpectedEof
+    }
+
+    pub fn unexpected(token: &Token<'_>) -> Self {
+        Self::new(format!("unexpected `{}`", token.kind), token.location)
+    }
+
+    pub fn location(&self) -> Location {
+        Location::new(self.span.offset(), self.span.offset() + self.span.len())
+    }
+}
+
+impl From<&kura_lexer::error::Error> for ParseError {
+    fn from(error: &kura_lexer::error::Error) -> Self {
+        let message = match error.message() {
+            "" => "invalid token",
+            message => message,
+        };
+        match error.is_eof() {
+            true => Self::eof(message, error.location()),
+            false => Self::new(message, error.location()),
+        }
+    }
+}
+
+impl From<kura_lexer::error::Error> for ParseError {
+    fn from(error: kura_lexer::error::Error) -> Self {
+        Self::from(&error)
+    }
+}
@@ -1,60 +1,547 @@
+use std::borrow::Cow;
+use std::ops::{Deref, DerefMut};
+
 use kura_lexer::token::{Kind, Location, Operator, Primitive, Token, Value};
-use kura_lexer::{Lexer, TransposeRef};
+use kura_lexer::{Lexer, LexerMode, TransposeRef};
+
+use crate::constant::{eval_const, UnverifiedConst};
+use crate::error::ParseError;
+use crate::ops;
+use crate::passes::{DEPRECATED_ATTRIBUTE, DEPRECATED_KEYS};
+use crate::scope::ScopeTree;
+use crate::types::parse_type_expression_with;
+use crate::warning::DiagnosticWarning;
+use crate::{
+    AsmOperand, Attribute, DoStep, Expression, Inline, MatchArm, ParserOptions, Pattern, UseTree, VariantField,
+    VariantKind,
+};
+
+/// name of the attribute hinting whether a function should be inlined, like
+/// `#[inline]`, `#[inline(always)]` or `#[inline(never)]`
+pub const INLINE_ATTRIBUTE: &str = "inline";
+
+/// the arguments `#[inline]` accepts, at most one of them
+const INLINE_ARGUMENTS: [&str; 2] = ["always", "never"];
+
+/// how deep expressions can nest inside of each other. deeper input is
+/// rejected instead of overflowing the stack, either while parsing it or
+/// while walking the tree later on
+pub const MAX_NESTING_DEPTH: usize = 128;
+
+/// what the parser keeps track of while going through a program, given to
+/// every function parsing a part of it. a parse started on its own, like
+/// the one of the expansion of a macro, gets a context of its own
+#[derive(Debug)]
+pub(crate) struct ParseContext<'par> {
+    pub(crate) options: ParserOptions,
+    /// how many expressions the one being parsed is nested into
+    depth: usize,
+    /// whether a block after the arguments of a call is a trailing closure,
+    /// and whether braces after an enum variant hold its fields. neither is
+    /// in the condition of an `if`, where the braces are the body instead
+    trailing_closures: bool,
+    /// whether an impl block is being parsed, the only place `Self` names a
+    /// type in
+    inside_impl: bool,
+    /// whether an `unsafe` block is being parsed, the only place inline
+    /// assembly can be written in
+    inside_unsafe: bool,
+    /// where the argument of the call being parsed starts, the only place a
+    /// `splat!` can be written in. it is taken by the `splat!` found there
+    splat_argument: Option<usize>,
+    /// warnings found so far, kept until whoever started the parse takes them
+    warnings: Vec<DiagnosticWarning>,
+    /// errors recovered from so far, only kept when the parser keeps going
+    /// past the errors it can recover from
+    recovered: Option<Vec<ParseError>>,
+    /// scopes opened so far, only tracked for `Parser::parse_with_scopes`
+    scopes: Option<ScopeTree<'par>>,
+}
+
+impl Default for ParseContext<'_> {
+    fn default() -> Self {
+        Self::new(ParserOptions::default())
+    }
+}
+
+impl<'par> ParseContext<'par> {
+    pub(crate) fn new(options: ParserOptions) -> Self {
+        Self {
+            options,
+            depth: 0,
+            trailing_closures: true,
+            inside_impl: false,
+            inside_unsafe: false,
+            splat_argument: None,
+            warnings: vec![],
+            recovered: None,
+            scopes: None,
+        }
+    }
+
+    pub(crate) fn warn(&mut self, warning: impl Into<DiagnosticWarning>) {
+        self.warnings.push(warning.into());
+    }
+
+    /// takes every warning found since the last call
+    pub(crate) fn take_warnings(&mut self) -> Vec<DiagnosticWarning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    /// sets what `field` points at to `value` for as long as the returned
+    /// context lives
+    fn scoped<T: Copy>(&mut self, field: fn(&mut Self) -> &mut T, value: T) -> Scoped<'_, 'par, T> {
+        let previous = std::mem::replace(field(self), value);
+        Scoped {
+            context: self,
+            field,
+            previous,
+        }
+    }
+
+    /// counts one level of nesting for as long as the returned context
+    /// lives, failing instead when that is deeper than `MAX_NESTING_DEPTH`
+    pub(crate) fn nested(&mut self, lexer: &mut Lexer<'_>) -> Result<Scoped<'_, 'par, usize>, ParseError> {
+        if self.depth == MAX_NESTING_DEPTH {
+            let location = match lexer.peek() {
+                Some(Ok(token)) => token.location,
+                _ => end_of_input(lexer),
+            };
+            return Err(ParseError::new(
+                format!("expressions are nested more than {MAX_NESTING_DEPTH} levels deep"),
+                location,
+            ));
+        }
+
+        let depth = self.depth + 1;
+        Ok(self.scoped(|context| &mut context.depth, depth))
+    }
+
+    /// allows or forbids trailing closures for as long as the returned
+    /// context lives
+    fn allow_trailing_closures(&mut self, allowed: bool) -> Scoped<'_, 'par, bool> {
+        self.scoped(|context| &mut context.trailing_closures, allowed)
+    }
+
+    /// marks an `unsafe` block as being parsed for as long as the returned
+    /// context lives
+    fn enter_unsafe(&mut self) -> Scoped<'_, 'par, bool> {
+        self.scoped(|context| &mut context.inside_unsafe, true)
+    }
+
+    /// marks the argument of a call starting at `start_byte` as being parsed
+    /// for as long as the returned context lives
+    fn enter_argument(&mut self, start_byte: usize) -> Scoped<'_, 'par, Option<usize>> {
+        self.scoped(|context| &mut context.splat_argument, Some(start_byte))
+    }
+
+    /// marks whether an impl block is being parsed, giving back whether one
+    /// was before
+    pub(crate) fn set_inside_impl(&mut self, inside: bool) -> bool {
+        std::mem::replace(&mut self.inside_impl, inside)
+    }
+
+    /// marks whether the body of an `unsafe fun` is being parsed, giving
+    /// back whether one was before
+    pub(crate) fn set_inside_unsafe(&mut self, inside: bool) -> bool {
+        std::mem::replace(&mut self.inside_unsafe, inside)
+    }
+
+    pub(crate) fn inside_impl(&self) -> bool {
+        self.inside_impl
+    }
 
-use crate::Expression;
+    /// keeps going past the errors the parser can recover from, collecting
+    /// them instead of failing
+    pub(crate) fn start_recovering(&mut self) {
+        self.recovered = Some(vec![]);
+    }
+
+    /// whether the parser keeps going past the errors it can recover from
+    pub(crate) fn recovering(&self) -> bool {
+        self.recovered.is_some()
+    }
+
+    /// takes every error recovered from, and stops recovering from them
+    pub(crate) fn take_recovered(&mut self) -> Vec<ParseError> {
+        self.recovered.take().unwrap_or_default()
+    }
+
+    /// starts tracking scopes, dropping whatever a previous parse left behind
+    pub(crate) fn track_scopes(&mut self) {
+        self.scopes = Some(ScopeTree::default());
+    }
+
+    /// stops tracking scopes, giving back the ones tracked so far
+    pub(crate) fn take_scopes(&mut self) -> Option<ScopeTree<'par>> {
+        self.scopes.take()
+    }
+
+    /// opens a scope nested into the current one when scopes are tracked
+    pub(crate) fn open_scope(&mut self, start: usize) {
+        if let Some(scopes) = &mut self.scopes {
+            scopes.push(start);
+        }
+    }
+
+    pub(crate) fn close_scope(&mut self, end: usize) {
+        if let Some(scopes) = &mut self.scopes {
+            scopes.pop(end);
+        }
+    }
+
+    fn bind(&mut self, name: Cow<'par, str>, value: &Expression<'par>, location: Location) {
+        if let Some(scopes) = &mut self.scopes {
+            scopes.bind(name, value.clone(), location);
+        }
+    }
+}
+
+/// a context with one of the things it keeps track of changed, which goes
+/// back to what it was once this is dropped
+pub(crate) struct Scoped<'ctx, 'par, T: Copy> {
+    context: &'ctx mut ParseContext<'par>,
+    field: for<'a> fn(&'a mut ParseContext<'par>) -> &'a mut T,
+    previous: T,
+}
+
+impl<'par, T: Copy> Deref for Scoped<'_, 'par, T> {
+    type Target = ParseContext<'par>;
+
+    fn deref(&self) -> &ParseContext<'par> {
+        self.context
+    }
+}
 
-mod precedences {
-    pub const BASE: u8 = 0;
-    pub const SUM: u8 = 3;
-    pub const MUL: u8 = 4;
-    pub const ASSOC: u8 = 5;
-    pub const APPLY: u8 = 6;
+impl<'par, T: Copy> DerefMut for Scoped<'_, 'par, T> {
+    fn deref_mut(&mut self) -> &mut ParseContext<'par> {
+        self.context
+    }
 }
 
-fn get_precedence(operator: Operator) -> u8 {
-    match operator {
-        Operator::Plus | Operator::Minus => precedences::SUM,
-        Operator::Star | Operator::Slash => precedences::MUL,
-        Operator::And => precedences::ASSOC,
-        Operator::LeftParen | Operator::EqualEqual | Operator::NotEqual => precedences::APPLY,
-        _ => precedences::BASE,
+impl<T: Copy> Drop for Scoped<'_, '_, T> {
+    fn drop(&mut self) {
+        *(self.field)(self.context) = self.previous;
     }
 }
 
-pub fn parse_expression<'parser>(lexer: &mut Lexer<'parser>, base_expr: bool) -> Result<Expression<'parser>, String> {
-    match lexer.peek().transpose().map_err(ToString::to_string)? {
+/// expects the `close_kind` matching `open_token`, giving back where it is.
+/// when recovering, reaching the end of the input instead reports the
+/// unmatched opener and gives back the end of the input, so the construct
+/// keeps whatever was parsed before it
+pub(crate) fn recover_from_unclosed_delimiter(
+    lexer: &mut Lexer<'_>,
+    context: &mut ParseContext<'_>,
+    open_kind: Operator,
+    close_kind: Operator,
+    open_token: &Token<'_>,
+) -> Result<Location, ParseError> {
+    let Some(recovered) = context.recovered.as_mut().filter(|_| lexer.is_empty()) else {
+        return Ok(lexer.expect(Kind::Op(close_kind))?.location);
+    };
+
+    let error = ParseError::eof(
+        format!("this `{open_kind}` is never closed, expected a `{close_kind}` before the end of the input"),
+        open_token.location,
+    );
+    recovered.push(error);
+    Ok(end_of_input(lexer))
+}
+
+const SPLAT_OUTSIDE_ARGUMENTS: &str = "`splat!` can only be used as a whole argument of a call";
+
+/// parses a single expression on its own, with the default options
+pub fn parse_expression<'parser>(
+    lexer: &mut Lexer<'parser>,
+    base_expr: bool,
+) -> Result<Expression<'parser>, ParseError> {
+    parse_expression_with(lexer, &mut ParseContext::default(), base_expr)
+}
+
+pub(crate) fn parse_expression_with<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    base_expr: bool,
+) -> Result<Expression<'parser>, ParseError> {
+    match lexer.peek().transpose()? {
         Some(token) => match token.kind {
-            Kind::Var | Kind::Const => parse_variable(lexer),
-            _ => parse_with_precedence(lexer, precedences::BASE, base_expr),
+            Kind::Var | Kind::Const => parse_variable(lexer, context, vec![]),
+            Kind::Op(Operator::Hash) => {
+                let attributes = parse_attributes(lexer)?;
+                match lexer.peek().transpose()? {
+                    Some(token) if matches!(token.kind, Kind::Var | Kind::Const) => {
+                        parse_variable(lexer, context, attributes)
+                    }
+                    Some(token) => Err(ParseError::new(
+                        format!("attributes can only be placed on variables, found `{}`", token.kind),
+                        token.location,
+                    )),
+                    None => Err(ParseError::eof(
+                        "expected a variable after the attributes",
+                        end_of_input(lexer),
+                    )),
+                }
+            }
+            _ => parse_with_precedence(lexer, context, ops::LOWEST, base_expr),
         },
-        None => unreachable!(),
+        None => Err(ParseError::eof("expected an expression", end_of_input(lexer))),
     }
 }
 
-pub fn parse_identifier<'parser>(lexer: &mut Lexer<'parser>) -> Result<(Expression<'parser>, &'parser str), String> {
-    let name_and_loc = lexer
-        .next()
-        .transpose()
-        .map_err(|e| e.to_string())?
-        .map(|token| match token.kind {
-            Kind::Value(Value::Ident(name)) => (name, token.location),
-            _ => ("", token.location),
-        });
+pub fn parse_identifier<'parser>(
+    lexer: &mut Lexer<'parser>,
+) -> Result<(Expression<'parser>, Cow<'parser, str>), ParseError> {
+    match lexer.next().transpose()? {
+        Some(Token {
+            kind: Kind::Value(Value::Ident(name)),
+            location,
+        }) => Ok((
+            Expression::Ident {
+                name: name.clone(),
+                location,
+            },
+            name,
+        )),
+        Some(token) => Err(ParseError::unexpected(&token)),
+        None => Err(ParseError::eof("expected an identifier", end_of_input(lexer))),
+    }
+}
+
+/// parses a name, which can be qualified by the modules it lives in, like
+/// `std::io::write`. a name without any `::` is an `Ident`
+pub fn parse_path<'parser>(lexer: &mut Lexer<'parser>) -> Result<Expression<'parser>, ParseError> {
+    let (ident, name) = parse_identifier(lexer)?;
+    let mut segments = vec![name];
+    let mut location = ident.location();
+
+    while let Some(token) = lexer.peek().transpose()? {
+        if token.kind != Kind::Op(Operator::ColonColon) {
+            break;
+        }
+        lexer.next().transpose()?;
+
+        let (segment, name) = parse_identifier(lexer)?;
+        segments.push(name);
+        location = Location::new(location.start_byte, segment.location().end_byte);
+    }
+
+    match segments.len() {
+        1 => Ok(ident),
+        _ => Ok(Expression::Path { segments, location }),
+    }
+}
+
+/// whether `name` is written like the name of an enum variant, which starts
+/// uppercase like `Red`. a constant like `PI` is written all uppercase
+fn is_variant_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(char::is_uppercase) && (name.len() == 1 || chars.any(char::is_lowercase))
+}
+
+/// whether `segments` name an enum variant rather than a constant or a
+/// function, like `Color::Red`
+fn names_variant(segments: &[Cow<'_, str>]) -> bool {
+    segments.len() > 1 && segments.last().is_some_and(|name| is_variant_name(name))
+}
+
+/// parses the values given to the enum variant named by `path`, like the
+/// `(1.0)` of `Shape::Circle(1.0)` or the `{ w: 1 }` of `Shape::Rect { w: 1 }`.
+/// a variant given neither is a unit variant, like `Color::Red`
+fn parse_enum_variant_expression<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    path: Expression<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let Expression::Path { segments, location } = path else {
+        unreachable!("only paths name variants");
+    };
+
+    let braces = context.trailing_closures;
+    let mut closures = context.allow_trailing_closures(true);
+    let context = &mut *closures;
+    let (kind, close) = parse_variant_kind(
+        lexer,
+        context,
+        braces,
+        |lexer, context| parse_expression_with(lexer, context, false),
+        Expression::location,
+    )?;
+
+    Ok(Expression::EnumVariant {
+        path: segments,
+        kind,
+        location: Location::new(location.start_byte, close.unwrap_or(location).end_byte),
+    })
+}
+
+/// parses the values of a variant with `parse_value`, returning where the
+/// closing delimiter is unless it is a unit variant. fields between braces
+/// are only taken when `braces` is set, as they would be a block otherwise
+fn parse_variant_kind<'parser, T>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    braces: bool,
+    parse_value: fn(&mut Lexer<'parser>, &mut ParseContext<'parser>) -> Result<T, ParseError>,
+    location_of: fn(&T) -> Location,
+) -> Result<(VariantKind<'parser, T>, Option<Location>), ParseError> {
+    match lexer.peek().transpose()?.map(|token| &token.kind) {
+        Some(Kind::Op(Operator::LeftParen)) => {
+            lexer.next().transpose()?;
+            let (elements, close) = parse_delimited(lexer, context, Operator::RightParen, parse_value)?;
+            Ok((VariantKind::Tuple(elements), Some(close)))
+        }
+        Some(Kind::Op(Operator::LeftBrace)) if braces => {
+            lexer.next().transpose()?;
+            let (fields, close) = parse_delimited(lexer, context, Operator::RightBrace, |lexer, context| {
+                let (ident, name) = parse_identifier(lexer)?;
+                lexer.expect(Kind::Op(Operator::Colon))?;
+                let value = parse_value(lexer, context)?;
+                let location = Location::new(ident.location().start_byte, location_of(&value).end_byte);
+                Ok(VariantField { name, value, location })
+            })?;
+            Ok((VariantKind::Struct(fields), Some(close)))
+        }
+        _ => Ok((VariantKind::Unit, None)),
+    }
+}
+
+/// parses items separated by commas up to `close`, whose opening delimiter
+/// was already taken, returning them along with where `close` is. the last
+/// item can be followed by a comma too
+fn parse_delimited<'parser, T>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    close: Operator,
+    mut parse_item: impl FnMut(&mut Lexer<'parser>, &mut ParseContext<'parser>) -> Result<T, ParseError>,
+) -> Result<(Vec<T>, Location), ParseError> {
+    let mut items = vec![];
+
+    loop {
+        if let Some(token) = lexer.peek().transpose()? {
+            if token.kind == Kind::Op(close) {
+                return Ok((items, lexer.expect(Kind::Op(close))?.location));
+            }
+        }
+
+        items.push(parse_item(lexer, context)?);
+        match lexer.next().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::Comma) => continue,
+            Some(token) if token.kind == Kind::Op(close) => return Ok((items, token.location)),
+            Some(token) => return Err(ParseError::unexpected(&token)),
+            None => return Err(ParseError::eof(format!("expected `{close}`"), end_of_input(lexer))),
+        }
+    }
+}
+
+/// parses what a `use` imports: a name, `*`, or a group of trees inside of
+/// braces, each of them optionally behind a path like `std::io::`. a glob
+/// has to be behind one, as there is nothing at the root of the program to
+/// import everything from. `path` is the one the tree is nested in, and
+/// every glob is added to `globs` along with the path it imports from
+pub fn parse_use_tree<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    path: &[Cow<'parser, str>],
+    globs: &mut Vec<(String, Location)>,
+) -> Result<UseTree<'parser>, ParseError> {
+    let mut segments = vec![];
+
+    let tree = loop {
+        match lexer.next().transpose()? {
+            Some(Token {
+                kind: Kind::Value(Value::Ident(name)),
+                ..
+            }) => match lexer.peek().transpose()? {
+                Some(token) if token.kind == Kind::Op(Operator::ColonColon) => {
+                    lexer.next().transpose()?;
+                    segments.push(name);
+                }
+                _ => break UseTree::Leaf(name),
+            },
+            Some(token) if token.kind == Kind::Op(Operator::Star) => match path.is_empty() && segments.is_empty() {
+                false => {
+                    globs.push(([path, &segments].concat().join("::"), token.location));
+                    break UseTree::Glob;
+                }
+                true => {
+                    return Err(ParseError::new(
+                        "cannot import everything from the root of the program, name a module before `*`",
+                        token.location,
+                    ))
+                }
+            },
+            Some(token) if token.kind == Kind::Op(Operator::LeftBrace) => {
+                let mut depth = context.nested(lexer)?;
+                let context = &mut *depth;
+                let nested = [path, &segments].concat();
+                let mut trees = vec![];
+                loop {
+                    if let Some(token) = lexer.peek().transpose()? {
+                        if token.kind == Kind::Op(Operator::RightBrace) {
+                            lexer.next().transpose()?;
+                            break;
+                        }
+                    }
+                    trees.push(parse_use_tree(lexer, context, &nested, globs)?);
+
+                    match lexer.next().transpose()? {
+                        Some(token) if token.kind == Kind::Op(Operator::Comma) => continue,
+                        Some(token) if token.kind == Kind::Op(Operator::RightBrace) => break,
+                        Some(token) => return Err(ParseError::unexpected(&token)),
+                        None => return Err(ParseError::eof("expected `}`", end_of_input(lexer))),
+                    }
+                }
+                break UseTree::Group(trees);
+            }
+            Some(token) => return Err(ParseError::unexpected(&token)),
+            None => return Err(ParseError::eof("expected a name to import", end_of_input(lexer))),
+        }
+    };
 
-    match name_and_loc {
-        Some(("", location)) => Err(location.to_string()),
-        Some((name, location)) => Ok((Expression::Ident { name, location }, name)),
-        None => Err("TODO".into()),
+    // paths are nested from their last segment out, so `a::b::c` is `a`
+    // holding `b` holding `c`
+    Ok(segments.into_iter().rev().fold(tree, |child, segment| UseTree::Path {
+        segment,
+        child: Box::new(child),
+    }))
+}
+
+/// expects the semicolon terminating an expression. on the repl, reaching the
+/// end of the input is as good as finding one
+fn expect_semicolon(lexer: &mut Lexer<'_>) -> Result<Location, ParseError> {
+    if lexer.mode() == LexerMode::Repl && lexer.is_empty() {
+        let offset = lexer.byte_offset();
+        let end = offset + lexer.complete_source[..lexer.end() - offset].trim_end().len();
+        return Ok(Location::new(end, end));
     }
+
+    lexer
+        .expect(Kind::Op(Operator::SemiColon))
+        .map(|token| token.location)
+        .map_err(ParseError::from)
+}
+
+/// location right past the last character being parsed, used for errors
+/// where the input ended before it was supposed to
+pub fn end_of_input(lexer: &Lexer<'_>) -> Location {
+    let end = lexer.end();
+    Location::new(end, end)
 }
 
-fn parse_expr_block<'parser>(lexer: &mut Lexer<'parser>, base_expr: bool) -> Result<Expression<'parser>, String> {
+fn parse_expr_block<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    base_expr: bool,
+) -> Result<Expression<'parser>, ParseError> {
     let mut expressions = vec![];
+    let mut closures = context.allow_trailing_closures(true);
+    let context = &mut *closures;
 
-    let block_start = lexer.expect(Kind::Op(Operator::LeftBrace)).map_err(|e| e.to_string())?;
+    let block_start = lexer.expect(Kind::Op(Operator::LeftBrace))?;
+    context.open_scope(block_start.location.start_byte);
 
     loop {
-        match lexer.peek().transpose().map_err(|e| e.to_string())? {
+        match lexer.peek().transpose()? {
             Some(token) if matches!(token.kind, Kind::Op(Operator::RightBrace)) => {
                 break;
             }
@@ -62,84 +549,231 @@ fn parse_expr_block<'parser>(lexer: &mut Lexer<'parser>, base_expr: bool) -> Res
             _ => (),
         }
 
-        let expr = parse_expression(lexer, base_expr)?;
+        let expr = match parse_expression_with(lexer, context, base_expr) {
+            // the input ended inside of the block, which is never closed
+            Err(error) if error.is_incomplete() && context.recovering() && lexer.is_empty() => break,
+            expr => expr?,
+        };
         expressions.push(expr);
     }
 
-    let block_end = lexer
-        .expect(Kind::Op(Operator::RightBrace))
-        .map_err(|e| e.to_string())?;
+    let block_end =
+        recover_from_unclosed_delimiter(lexer, context, Operator::LeftBrace, Operator::RightBrace, &block_start)?;
+    context.close_scope(block_end.end_byte);
 
-    let location = block_start.location.start_byte..block_end.location.end_byte;
+    // the last expression is the value of the block, unless a semicolon
+    // made a statement out of it
+    let tail = expressions
+        .pop_if(|last| !owns_semicolon(last, base_expr))
+        .map(Box::new);
+
+    let location = block_start.location.start_byte..block_end.end_byte;
     Ok(Expression::Block {
         expressions,
+        tail,
+        reachable: None,
         location: location.into(),
     })
 }
 
-fn parse_variable<'parser>(lexer: &mut Lexer<'parser>) -> Result<Expression<'parser>, String> {
-    let keyword = lexer
-        .expect_one_of(&[Kind::Var, Kind::Const])
-        .map_err(|e| e.to_string())?;
+/// parses every attribute in a row, like `#[allow(shadow)] #[inline]`
+pub fn parse_attributes<'parser>(lexer: &mut Lexer<'parser>) -> Result<Vec<Attribute<'parser>>, ParseError> {
+    let mut attributes = vec![];
+
+    while let Some(token) = lexer.peek().transpose()? {
+        if token.kind != Kind::Op(Operator::Hash) {
+            break;
+        }
+
+        let hash = lexer.expect(Kind::Op(Operator::Hash))?;
+        lexer.expect(Kind::Op(Operator::LeftBracket))?;
+        let (_, name) = parse_identifier(lexer)?;
+
+        let mut arguments = vec![];
+        let mut values = vec![];
+        if let Some(token) = lexer.peek().transpose()? {
+            if token.kind == Kind::Op(Operator::LeftParen) {
+                lexer.next().transpose()?;
+                loop {
+                    let (argument_token, argument) = parse_identifier(lexer)?;
+                    if name == INLINE_ATTRIBUTE && !INLINE_ARGUMENTS.contains(&argument.as_ref()) {
+                        return Err(ParseError::new(
+                            format!("`inline` takes either `always` or `never`, found `{argument}`"),
+                            argument_token.location(),
+                        ));
+                    }
+                    if name == INLINE_ATTRIBUTE && !arguments.is_empty() {
+                        return Err(ParseError::new(
+                            "`inline` takes a single argument",
+                            argument_token.location(),
+                        ));
+                    }
+                    if name == DEPRECATED_ATTRIBUTE && !DEPRECATED_KEYS.contains(&argument.as_ref()) {
+                        return Err(ParseError::new(
+                            format!("`deprecated` only takes `since` and `note`, found `{argument}`"),
+                            argument_token.location(),
+                        ));
+                    }
+                    match lexer.peek().transpose()? {
+                        Some(token) if token.kind == Kind::Op(Operator::Equal) => {
+                            lexer.next().transpose()?;
+                            match lexer.next().transpose()? {
+                                Some(Token {
+                                    kind: Kind::Value(Value::String(value)),
+                                    ..
+                                }) => values.push((argument, value)),
+                                Some(token) => {
+                                    return Err(ParseError::new(
+                                        format!("attribute values must be strings, found `{}`", token.kind),
+                                        token.location,
+                                    ))
+                                }
+                                None => return Err(ParseError::eof("expected a string", end_of_input(lexer))),
+                            }
+                        }
+                        _ if name == DEPRECATED_ATTRIBUTE => {
+                            return Err(ParseError::new(
+                                format!("`deprecated` expects `{argument} = \"...\"`"),
+                                argument_token.location(),
+                            ))
+                        }
+                        _ if !values.is_empty() => {
+                            return Err(ParseError::new(
+                                "attribute arguments must come before the ones with a value",
+                                argument_token.location(),
+                            ))
+                        }
+                        _ => arguments.push(argument),
+                    }
+
+                    match lexer.next().transpose()? {
+                        Some(token) if token.kind == Kind::Op(Operator::Comma) => continue,
+                        Some(token) if token.kind == Kind::Op(Operator::RightParen) => break,
+                        Some(token) => return Err(ParseError::unexpected(&token)),
+                        None => return Err(ParseError::eof("expected `)`", end_of_input(lexer))),
+                    }
+                }
+            }
+        }
+
+        let closing = lexer.expect(Kind::Op(Operator::RightBracket))?;
+        attributes.push(Attribute {
+            name,
+            arguments,
+            values,
+            location: Location::new(hash.location.start_byte, closing.location.end_byte),
+        });
+    }
+
+    Ok(attributes)
+}
+
+/// what the `#[inline(...)]` attributes of a function ask for. when both
+/// `always` and `never` are asked for, the first one is kept and a warning
+/// points at both
+pub(crate) fn parse_inline(context: &mut ParseContext<'_>, attributes: &[Attribute<'_>]) -> Option<Inline> {
+    let mut inline: Option<(Inline, &Attribute<'_>)> = None;
+
+    for attribute in attributes.iter().filter(|attribute| attribute.name == INLINE_ATTRIBUTE) {
+        let Some(argument) = attribute.arguments.first() else {
+            continue;
+        };
+        let always = argument == "always";
+        match inline {
+            None => inline = Some((Inline { always }, attribute)),
+            Some((first, first_attribute)) if first.always != always => {
+                let warning = DiagnosticWarning::ConflictingAttributes {
+                    name: String::from(INLINE_ATTRIBUTE),
+                    first: first_attribute.location.into(),
+                    second: attribute.location.into(),
+                };
+                context.warn(warning);
+            }
+            Some(_) => {}
+        }
+    }
+
+    inline.map(|(inline, _)| inline)
+}
+
+pub(crate) fn parse_variable<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    attributes: Vec<Attribute<'parser>>,
+) -> Result<Expression<'parser>, ParseError> {
+    let keyword = lexer.expect_one_of(&[Kind::Var, Kind::Const])?;
     let mutable = matches!(keyword.kind, Kind::Var);
 
     let (_, name) = parse_identifier(lexer)?;
 
-    let typ = match lexer.peek().transpose().map_err(|e| e.to_string())? {
+    let typ = match lexer.peek().transpose()? {
         Some(token) if matches!(token.kind, Kind::Op(Operator::Colon)) => {
-            lexer.next().transpose().map_err(|e| e.to_string())?;
-            Some(parse_identifier(lexer)?)
+            lexer.next().transpose()?;
+            Some(parse_type_expression_with(lexer, context)?)
         }
         _ => None,
     };
 
-    lexer.expect(Kind::Op(Operator::Equal)).map_err(|e| e.to_string())?;
+    lexer.expect(Kind::Op(Operator::Equal))?;
 
-    let value = match lexer.peek().transpose().map_err(|e| e.to_string())? {
+    let value = match lexer.peek().transpose()? {
         Some(token) => match token.kind {
-            Kind::Op(Operator::LeftBrace) => parse_expr_block(lexer, false)?,
-            _ => parse_expression(lexer, false)?,
+            Kind::Op(Operator::LeftBrace) => parse_expr_block(lexer, context, false)?,
+            _ => parse_expression_with(lexer, context, false)?,
         },
-        _ => unreachable!(),
+        None => return Err(ParseError::eof("expected an expression", end_of_input(lexer))),
     };
 
-    lexer.expect(Kind::Op(Operator::SemiColon)).map_err(|e| e.to_string())?;
+    expect_semicolon(lexer)?;
+
+    if !mutable && eval_const(&value).is_none() {
+        context.warn(UnverifiedConst {
+            value: value.location().into(),
+        });
+    }
 
     let location = Location::new(keyword.location.start_byte, value.location().end_byte);
+    context.bind(name.clone(), &value, location);
     Ok(Expression::Var {
         mutable,
-        typ: typ.map(|(typ, _)| Box::new(typ)),
+        typ: typ.map(Box::new),
         name,
         value: Box::new(value),
+        attributes,
         location,
     })
 }
 
-fn parse_if_expression<'parser>(lexer: &mut Lexer<'parser>) -> Result<Expression<'parser>, String> {
-    let keyword = lexer.expect(Kind::If).map_err(|e| e.to_string())?;
+fn parse_if_expression<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    // every `else if` is nested into the `if` before it
+    let mut depth = context.nested(lexer)?;
+    let context = &mut *depth;
+    let keyword = lexer.expect(Kind::If)?;
 
-    let condition = parse_expression(lexer, false)?;
+    let condition = parse_expression_with(lexer, &mut context.allow_trailing_closures(false), false)?;
 
-    let body = parse_expr_block(lexer, true)?;
+    let body = parse_expr_block(lexer, context, true)?;
 
     let mut falsy_branches = vec![];
 
-    while let Some(keyword) = lexer.peek().transpose().map_err(|e| e.to_string())? {
+    while let Some(keyword) = lexer.peek().transpose()? {
         if keyword.kind == Kind::Else {
             // consume `else`
-            lexer.next().transpose().map_err(|e| e.to_string())?;
+            lexer.next().transpose()?;
 
             if lexer
                 .peek()
-                .transpose()
-                .map_err(|e| e.to_string())?
+                .transpose()?
                 .map(|t| matches!(t.kind, Kind::If))
                 .unwrap_or(false)
             {
-                let else_if = parse_if_expression(lexer)?;
+                let else_if = parse_if_expression(lexer, context)?;
                 falsy_branches.push(else_if);
             } else {
-                let else_block = parse_expr_block(lexer, true)?;
+                let else_block = parse_expr_block(lexer, context, true)?;
                 falsy_branches.push(else_block);
                 break;
             }
@@ -162,188 +796,1528 @@ fn parse_if_expression<'parser>(lexer: &mut Lexer<'parser>) -> Result<Expression
     })
 }
 
-fn parse_value<'parser>(lexer: &mut Lexer<'parser>) -> Result<Expression<'parser>, String> {
-    let Some(Token { kind, .. }) = lexer.peek().transpose().map_err(|e| e.to_string())? else {
-        unreachable!();
-    };
+/// parses `match scrutinee { pattern => body, .. }`. arms are separated by
+/// commas, which a block body can leave out, and the last arm can have one
+fn parse_match<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let mut depth = context.nested(lexer)?;
+    let context = &mut *depth;
+    let keyword = lexer.expect(Kind::Match)?;
 
-    let Kind::Value(value) = kind else {
-        unreachable!();
-    };
+    let scrutinee = parse_expression_with(lexer, &mut context.allow_trailing_closures(false), false)?;
 
-    match value {
-        Value::Primitive(_) => parse_primitive(lexer),
-        Value::Ident(_) => Ok(parse_identifier(lexer)?.0),
-        t => todo!("{t}"),
+    let mut closures = context.allow_trailing_closures(true);
+    let context = &mut *closures;
+    lexer.expect(Kind::Op(Operator::LeftBrace))?;
+    let mut arms = vec![];
+
+    loop {
+        match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::RightBrace) => break,
+            Some(_) => (),
+            None => {
+                return Err(ParseError::eof(
+                    "expected `}` to close the `match`",
+                    end_of_input(lexer),
+                ))
+            }
+        }
+
+        let pattern = parse_pattern(lexer, context)?;
+        lexer.expect(Kind::Op(Operator::ThickArrow))?;
+        let body = parse_expression_with(lexer, context, false)?;
+        let location = Location::new(pattern.location().start_byte, body.location().end_byte);
+        let is_block = matches!(body, Expression::Block { .. });
+        arms.push(MatchArm {
+            pattern,
+            body,
+            location,
+        });
+
+        match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::Comma) => {
+                lexer.next().transpose()?;
+            }
+            Some(token) if token.kind == Kind::Op(Operator::RightBrace) => break,
+            Some(_) if is_block => (),
+            Some(token) => {
+                return Err(ParseError::new(
+                    format!("expected `,` or `}}` after a `match` arm, found `{}`", token.kind),
+                    token.location,
+                ))
+            }
+            None => {
+                return Err(ParseError::eof(
+                    "expected `}` to close the `match`",
+                    end_of_input(lexer),
+                ))
+            }
+        }
+    }
+
+    let close = lexer.expect(Kind::Op(Operator::RightBrace))?;
+    Ok(Expression::Match {
+        scrutinee: Box::new(scrutinee),
+        arms,
+        location: Location::new(keyword.location.start_byte, close.location.end_byte),
+    })
+}
+
+/// parses the pattern of a `match` arm: `_`, a literal, a name to bind, or
+/// a path like `Color::Red`
+fn parse_pattern<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Pattern<'parser>, ParseError> {
+    match lexer.peek().transpose()? {
+        Some(Token {
+            kind: Kind::Value(Value::Primitive(_)),
+            ..
+        }) => Ok(Pattern::Literal(Box::new(parse_primitive(lexer, context)?))),
+        Some(Token {
+            kind: Kind::Value(Value::String(_) | Value::RawString { .. }),
+            ..
+        }) => match parse_string(lexer, context)? {
+            literal @ Expression::StringLiteral { .. } => Ok(Pattern::Literal(Box::new(literal))),
+            other => Err(ParseError::new(
+                "a pattern cannot interpolate expressions into a string",
+                other.location(),
+            )),
+        },
+        Some(Token {
+            kind: Kind::Value(Value::Ident(name)),
+            location,
+        }) if name == "_" => {
+            let location = *location;
+            lexer.next().transpose()?;
+            Ok(Pattern::Wildcard { location })
+        }
+        Some(Token {
+            kind: Kind::Value(Value::Ident(_)),
+            ..
+        }) => {
+            let (path, location) = match parse_path(lexer)? {
+                Expression::Ident { name, location } => (vec![name], location),
+                Expression::Path { segments, location } => (segments, location),
+                _ => unreachable!(),
+            };
+            // nothing is ever called in a pattern, so `Some(x)` is a variant
+            // even without a path in front of it. `None` is still a binding
+            let (kind, close) = match path.last().is_some_and(|name| is_variant_name(name)) {
+                true => parse_variant_kind(lexer, context, true, parse_pattern, Pattern::location)?,
+                false => (VariantKind::Unit, None),
+            };
+            match close {
+                Some(close) => Ok(Pattern::Variant {
+                    path,
+                    kind,
+                    location: Location::new(location.start_byte, close.end_byte),
+                }),
+                None if names_variant(&path) => Ok(Pattern::Variant { path, kind, location }),
+                None if path.len() == 1 => Ok(Pattern::Binding {
+                    name: path.into_iter().next().expect("a path has a segment"),
+                    location,
+                }),
+                None => Ok(Pattern::Path {
+                    segments: path,
+                    location,
+                }),
+            }
+        }
+        Some(token) => Err(ParseError::new(
+            format!("expected a pattern, found `{}`", token.kind),
+            token.location,
+        )),
+        None => Err(ParseError::eof("expected a pattern", end_of_input(lexer))),
     }
 }
 
-fn parse_operation<'parser>(lexer: &mut Lexer<'parser>) -> Result<Expression<'parser>, String> {
-    let Some(Token { kind, .. }) = lexer.peek().transpose().map_err(|e| e.to_string())? else {
+fn parse_value<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let Some(Token { kind, .. }) = lexer.peek().transpose()? else {
         unreachable!();
     };
 
-    let Kind::Op(op) = kind else {
+    let Kind::Value(value) = kind else {
         unreachable!();
     };
 
-    match op {
-        Operator::LeftParen => {
-            lexer.next().transpose().map_err(|e| e.to_string())?;
-            let left = parse_with_precedence(lexer, precedences::BASE, false)?;
-            lexer
-                .expect(Kind::Op(Operator::RightParen))
-                .map_err(|e| e.to_string())?;
-            Ok(left)
-        }
-        Operator::LeftBrace => parse_expr_block(lexer, true),
-        t => todo!("{t:?}"),
+    match value {
+        Value::Primitive(_) => parse_primitive(lexer, context),
+        Value::Ident(_) => parse_path(lexer),
+        Value::String(_) | Value::RawString { .. } => parse_string(lexer, context),
     }
 }
 
-fn parse_fun_call<'parser>(
+/// parses a string literal, splitting it into an `Interpolated` expression
+/// when it embeds expressions between braces, like `"hello {name}"`. a
+/// literal brace is written as `{{` or `}}`. a raw string is always a
+/// plain literal
+fn parse_string<'parser>(
     lexer: &mut Lexer<'parser>,
-    ident: Expression<'parser>,
-    base_expr: bool,
-) -> Result<Expression<'parser>, String> {
-    lexer.expect(Kind::Op(Operator::LeftParen)).map_err(|e| e.to_string())?;
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let (contents, location) = match lexer.next().transpose()? {
+        Some(Token {
+            kind: Kind::Value(Value::String(contents)),
+            location,
+        }) => (contents, location),
+        Some(Token {
+            kind: Kind::Value(Value::RawString { contents, .. }),
+            location,
+        }) => {
+            return Ok(Expression::StringLiteral {
+                value: contents,
+                location,
+            })
+        }
+        _ => unreachable!(),
+    };
 
-    let mut arguments = vec![];
+    // the contents start right after the opening quote
+    let start = location.start_byte + 1;
+    let end = location.end_byte - 1;
 
-    loop {
-        match lexer.peek().transpose().map_err(|e| e.to_string())? {
-            Some(token) if matches!(token.kind, Kind::Op(Operator::RightParen)) => {
-                break;
+    let mut literals = vec![];
+    let mut expressions = vec![];
+    let mut literal = StringPart::default();
+    let mut pos = 0;
+
+    while let Some(c) = contents[pos..].chars().next() {
+        let next = contents[pos + c.len_utf8()..].chars().next();
+
+        match (c, next) {
+            ('\\', escaped) => {
+                let unescaped = match escaped {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some('0') => '\0',
+                    Some('\\') => '\\',
+                    Some('"') => '"',
+                    _ => {
+                        let location = Location::new(start + pos, start + pos + 1 + escaped.map_or(0, char::len_utf8));
+                        return Err(ParseError::new("unknown escape sequence", location));
+                    }
+                };
+                literal.push_escaped(&contents, pos, unescaped);
+                pos += 2;
+                continue;
             }
-            Some(token) if matches!(token.kind, Kind::Op(Operator::Comma)) => {
-                lexer.next().transpose().map_err(|e| e.to_string())?;
+            ('{', Some('{')) | ('}', Some('}')) => {
+                literal.push_escaped(&contents, pos, c);
+                pos += 2;
                 continue;
             }
-            None => break,
-            _ => (),
+            ('{', _) => {
+                literals.push(literal.finish(&contents, pos, start));
+
+                let mut embedded = lexer.sub_lexer(start + pos + 1..end);
+                if let Some(token) = embedded.peek().transpose()? {
+                    if token.kind == Kind::Op(Operator::RightBrace) {
+                        return Err(ParseError::new("expected an expression to interpolate", token.location));
+                    }
+                }
+
+                // the string itself is complete, so reaching the end of it
+                // means the interpolation is malformed rather than unfinished
+                let expression = parse_expression_with(&mut embedded, context, false).map_err(ParseError::invalid)?;
+                let closing = embedded
+                    .expect(Kind::Op(Operator::RightBrace))
+                    .map_err(|e| ParseError::from(e).invalid())?;
+                expressions.push(expression);
+
+                pos = closing.location.end_byte - start;
+                literal = StringPart::starting_at(pos);
+                continue;
+            }
+            ('}', _) => {
+                let location = Location::new(start + pos, start + pos + 1);
+                return Err(ParseError::new(
+                    "unmatched `}` in string, use `}}` for a literal brace",
+                    location,
+                ));
+            }
+            _ => literal.push(c),
         }
 
-        let arg = parse_expression(lexer, false)?;
-
-        arguments.push(arg);
+        pos += c.len_utf8();
     }
 
-    let close_paren = lexer
-        .expect(Kind::Op(Operator::RightParen))
-        .map_err(|e| e.to_string())?;
-
-    if base_expr {
-        lexer.expect(Kind::Op(Operator::SemiColon)).map_err(|e| e.to_string())?;
+    let last = literal.finish(&contents, pos, start);
+    if expressions.is_empty() {
+        return Ok(Expression::StringLiteral {
+            value: match last {
+                Expression::StringLiteral { value, .. } => value,
+                _ => unreachable!(),
+            },
+            location,
+        });
     }
 
-    let location = ident.location().start_byte..close_paren.location.end_byte;
-    let expr = Expression::FunCall {
-        ident: Box::new(ident),
-        location: location.into(),
-        arguments,
-    };
-    Ok(expr)
+    literals.push(last);
+    Ok(Expression::Interpolated {
+        literals,
+        expressions,
+        location,
+    })
 }
 
-fn parse_assign<'parser>(lexer: &mut Lexer<'parser>, left: Expression<'parser>) -> Result<Expression<'parser>, String> {
-    lexer.expect(Kind::Op(Operator::Equal)).map_err(|e| e.to_string())?;
-
-    let value = match lexer.peek().transpose().map_err(|e| e.to_string())? {
-        Some(token) if matches!(token.kind, Kind::Op(Operator::LeftBrace)) => parse_expr_block(lexer, false)?,
-        Some(_) => parse_expression(lexer, false)?,
-        _ => unreachable!(),
-    };
-
-    let closing = lexer.expect(Kind::Op(Operator::SemiColon)).map_err(|e| e.to_string())?;
-
-    let location = left.location().start_byte..closing.location.end_byte;
-
-    Ok(Expression::Assign {
-        ident: Box::new(left),
-        location: location.into(),
-        value: Box::new(value),
-    })
+/// a literal part of a string. parts without escapes borrow from the
+/// source, others hold their unescaped contents
+#[derive(Default)]
+struct StringPart {
+    start: usize,
+    unescaped: Option<String>,
 }
 
-fn parse_with_precedence<'parser>(
-    lexer: &mut Lexer<'parser>,
-    min_precedence: u8,
-    base_expr: bool,
-) -> Result<Expression<'parser>, String> {
-    let mut left = match lexer.peek().transpose().map_err(|e| e.to_string())? {
-        Some(token) => match &token.kind {
-            Kind::Value(_) => parse_value(lexer)?,
-            Kind::Op(_) => parse_operation(lexer)?,
-            Kind::Return => parse_return_expression(lexer)?,
-            Kind::If => parse_if_expression(lexer)?,
-            t => todo!("{t:?}"),
-        },
-        None => todo!(),
-    };
+impl StringPart {
+    fn starting_at(start: usize) -> Self {
+        Self { start, unescaped: None }
+    }
 
-    if let Expression::Ident { .. } = left {
-        match lexer.peek().transpose().map_err(|e| e.to_string())? {
-            Some(token) if matches!(token.kind, Kind::Op(Operator::LeftParen)) => {
-                return parse_fun_call(lexer, left, base_expr);
-            }
-            Some(token) if matches!(token.kind, Kind::Op(Operator::Equal)) => return parse_assign(lexer, left),
-            _ => (),
+    fn push(&mut self, c: char) {
+        if let Some(unescaped) = self.unescaped.as_mut() {
+            unescaped.push(c);
         }
     }
 
-    loop {
-        let Some(next) = lexer.peek().transpose().map_err(|e| e.to_string())? else {
-            return Ok(left);
-        };
+    fn push_escaped(&mut self, contents: &str, pos: usize, c: char) {
+        self.unescaped
+            .get_or_insert_with(|| contents[self.start..pos].to_string())
+            .push(c);
+    }
 
-        let Kind::Op(operator) = next.kind else {
-            return Ok(left);
+    fn finish<'parser>(self, contents: &Cow<'parser, str>, end: usize, offset: usize) -> Expression<'parser> {
+        let value = match (self.unescaped, contents) {
+            (Some(unescaped), _) => Cow::Owned(unescaped),
+            (None, Cow::Borrowed(contents)) => Cow::Borrowed(&contents[self.start..end]),
+            (None, Cow::Owned(contents)) => Cow::Owned(contents[self.start..end].to_string()),
         };
 
-        if !next.kind.is_binary_op() {
-            return Ok(left);
+        Expression::StringLiteral {
+            value,
+            location: Location::new(offset + self.start, offset + end),
         }
+    }
+}
+
+fn parse_operation<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let Some(token) = lexer.peek().transpose()? else {
+        unreachable!();
+    };
+
+    let Kind::Op(op) = token.kind else {
+        unreachable!();
+    };
+
+    match op {
+        Operator::LeftParen => parse_parenthesized(lexer, context),
+        Operator::LeftBrace => parse_expr_block(lexer, context, true),
+        Operator::LeftBracket => parse_array(lexer, context),
+        Operator::Ampersand | Operator::And => parse_reference(lexer, context),
+        Operator::Star => parse_dereference(lexer, context),
+        Operator::Bang => parse_not(lexer, context),
+        Operator::PlusPlus | Operator::MinusMinus => Err(increment_error("prefix", op, None, token.location)),
+        _ => Err(ParseError::unexpected(token)),
+    }
+}
+
+/// the operand of a prefix operator, which takes no infix operator along
+fn parse_prefix_operand<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    operator: Operator,
+) -> Result<Expression<'parser>, ParseError> {
+    let binding_power = ops::prefix(operator).map_or(ops::PREFIX, |info| info.binding_power);
+    parse_with_precedence(lexer, context, binding_power, false)
+}
+
+/// parses `&expr` or `&mut expr`. `&&` is lexed as a logical and, which in
+/// front of an expression can only be two references, as in `&&expr`
+fn parse_reference<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let Some(ampersand) = lexer.next().transpose()? else {
+        unreachable!();
+    };
+
+    let mutable = match lexer.peek().transpose()? {
+        Some(Token {
+            kind: Kind::Value(Value::Ident(name)),
+            ..
+        }) if name == "mut" => {
+            lexer.next().transpose()?;
+            true
+        }
+        _ => false,
+    };
+
+    let expr = parse_prefix_operand(lexer, context, Operator::Ampersand)?;
+    let start_byte = ampersand.location.start_byte;
+    let end_byte = expr.location().end_byte;
+
+    match ampersand.kind {
+        Kind::Op(Operator::And) => {
+            let inner = Expression::Ref {
+                mutable,
+                expr: Box::new(expr),
+                location: Location::new(start_byte + 1, end_byte),
+            };
+            Ok(Expression::Ref {
+                mutable: false,
+                expr: Box::new(inner),
+                location: Location::new(start_byte, end_byte),
+            })
+        }
+        _ => Ok(Expression::Ref {
+            mutable,
+            expr: Box::new(expr),
+            location: Location::new(start_byte, end_byte),
+        }),
+    }
+}
+
+/// parses `*expr`
+fn parse_dereference<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let star = lexer.expect(Kind::Op(Operator::Star))?;
+    let expr = parse_prefix_operand(lexer, context, Operator::Star)?;
+
+    Ok(Expression::Deref {
+        location: Location::new(star.location.start_byte, expr.location().end_byte),
+        expr: Box::new(expr),
+    })
+}
+
+/// parses `!expr`
+fn parse_not<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let bang = lexer.expect(Kind::Op(Operator::Bang))?;
+    let expr = parse_prefix_operand(lexer, context, Operator::Bang)?;
+
+    Ok(Expression::Not {
+        location: Location::new(bang.location.start_byte, expr.location().end_byte),
+        expr: Box::new(expr),
+    })
+}
+
+/// whether `expr` is parsed along with the semicolon after it, which are
+/// the statements along with the calls that are parsed as one
+fn owns_semicolon(expr: &Expression<'_>, base_expr: bool) -> bool {
+    match expr {
+        Expression::Var { .. }
+        | Expression::Assign { .. }
+        | Expression::CompoundAssign { .. }
+        | Expression::Return { .. }
+        | Expression::Yield { .. } => true,
+        _ => base_expr && is_call(expr),
+    }
+}
+
+/// calls are the only expressions that own their semicolon, which they keep
+/// when awaited
+fn is_call(expr: &Expression<'_>) -> bool {
+    match expr {
+        Expression::FunCall { .. }
+        | Expression::Macro { .. }
+        | Expression::Assert { .. }
+        | Expression::RuntimeTypeOf { .. }
+        | Expression::ArrayLen { .. }
+        | Expression::Sizeof { .. }
+        | Expression::InlineAsm { .. } => true,
+        Expression::Await { expr, .. } => is_call(expr),
+        _ => false,
+    }
+}
+
+fn parse_fun_call<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    ident: Expression<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    lexer.expect(Kind::Op(Operator::LeftParen))?;
+
+    let mut closures = context.allow_trailing_closures(true);
+    let mut arguments = vec![];
+
+    loop {
+        let start_byte = match lexer.peek().transpose()? {
+            Some(token) if matches!(token.kind, Kind::Op(Operator::RightParen)) => {
+                break;
+            }
+            Some(token) if matches!(token.kind, Kind::Op(Operator::Comma)) => {
+                lexer.next().transpose()?;
+                continue;
+            }
+            None => break,
+            Some(token) => token.location.start_byte,
+        };
+
+        // a `splat!` starting the argument can still be followed by more of
+        // it, like in `splat!(a) + 1`, which isn't fine either
+        let mut argument = closures.enter_argument(start_byte);
+        let arg = parse_expression_with(lexer, &mut argument, false)?;
+        // the `splat!` starting the argument took it
+        if argument.splat_argument.is_none() && !matches!(arg, Expression::Splat { .. }) {
+            return Err(ParseError::new(SPLAT_OUTSIDE_ARGUMENTS, arg.location()));
+        }
+        drop(argument);
+
+        arguments.push(arg);
+    }
+
+    let close_paren = lexer.expect(Kind::Op(Operator::RightParen))?;
+    drop(closures);
+
+    let mut end = close_paren.location.end_byte;
+    let trailing = lexer.peek().transpose()?.map(|token| token.kind.clone());
+    if context.trailing_closures && trailing == Some(Kind::Op(Operator::LeftBrace)) {
+        let body = parse_expr_block(lexer, context, true)?;
+        end = body.location().end_byte;
+        arguments.push(Expression::Lambda {
+            parameters: vec![],
+            parameter_types: vec![],
+            return_type: None,
+            location: body.location(),
+            body: Box::new(body),
+        });
+    }
+
+    let location = ident.location().start_byte..end;
+    let expr = Expression::FunCall {
+        ident: Box::new(ident),
+        location: location.into(),
+        arguments,
+        tail_call: false,
+    };
+    Ok(expr)
+}
+
+/// parses an anonymous function, like `fun(a, b) { a + b }`. just like the
+/// ones of a function, its parameters and what it returns can be annotated,
+/// as in `fun(a: i32, b) => i32 { a + b }`
+fn parse_lambda<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let keyword = lexer.expect(Kind::Fun)?;
+    lexer.expect(Kind::Op(Operator::LeftParen))?;
+
+    let mut parameters = vec![];
+    let mut parameter_types = vec![];
+    while let Some(token) = lexer.peek().transpose()? {
+        if token.kind == Kind::Op(Operator::RightParen) {
+            break;
+        }
+
+        let (parameter, _) = parse_identifier(lexer)?;
+        parameters.push(parameter);
+        parameter_types.push(match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::Colon) => {
+                lexer.next().transpose()?;
+                Some(parse_type_expression_with(lexer, context)?)
+            }
+            _ => None,
+        });
+
+        match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::Comma) => lexer.next().transpose()?,
+            _ => break,
+        };
+    }
+    lexer.expect(Kind::Op(Operator::RightParen))?;
+
+    let return_type = match lexer.peek().transpose()? {
+        Some(token) if token.kind == Kind::Op(Operator::ThickArrow) => {
+            lexer.next().transpose()?;
+            Some(Box::new(parse_type_expression_with(lexer, context)?))
+        }
+        _ => None,
+    };
+
+    let body = parse_expr_block(lexer, context, true)?;
+
+    let location = Location::new(keyword.location.start_byte, body.location().end_byte);
+    Ok(Expression::Lambda {
+        parameters,
+        parameter_types,
+        return_type,
+        body: Box::new(body),
+        location,
+    })
+}
+
+/// parses `let name = value in body`. the body reaches as far as an
+/// expression can, so `let a = 1 in a + 1` binds `a` over `a + 1`
+fn parse_let<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let keyword = lexer.expect(Kind::Let)?;
+    let (_, name) = parse_identifier(lexer)?;
+    lexer.expect(Kind::Op(Operator::Equal))?;
+    let value = parse_expression_with(lexer, context, false)?;
+    lexer.expect(Kind::In)?;
+    let body = parse_expression_with(lexer, context, false)?;
+
+    let location = Location::new(keyword.location.start_byte, body.location().end_byte);
+    Ok(Expression::Let {
+        name,
+        value: Box::new(value),
+        body: Box::new(body),
+        location,
+    })
+}
+
+/// parses `unsafe { .. }`. the block takes calls without a semicolon when
+/// it is a value rather than a statement, just like `base_expr` says
+fn parse_unsafe<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    base_expr: bool,
+) -> Result<Expression<'parser>, ParseError> {
+    let mut inside = context.enter_unsafe();
+    let context = &mut *inside;
+    let (body, location) = parse_keyword_block(lexer, context, Kind::Unsafe, base_expr)?;
+    Ok(Expression::UnsafeBlock {
+        body: Box::new(body),
+        location,
+    })
+}
+
+/// parses `comptime { .. }`, whose block is parsed just like the one of an
+/// `unsafe`
+fn parse_comptime<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    base_expr: bool,
+) -> Result<Expression<'parser>, ParseError> {
+    let (body, location) = parse_keyword_block(lexer, context, Kind::Comptime, base_expr)?;
+    Ok(Expression::ComptimeBlock {
+        body: Box::new(body),
+        location,
+    })
+}
+
+/// parses `defer { .. }`. a defer runs when the block around it exits, so
+/// one that isn't an expression of a block is warned about
+fn parse_defer<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    base_expr: bool,
+) -> Result<Expression<'parser>, ParseError> {
+    let (body, location) = parse_keyword_block(lexer, context, Kind::Defer, base_expr)?;
+    if !base_expr {
+        context.warn(DiagnosticWarning::DeferOutsideBlock { span: location.into() });
+    }
+    Ok(Expression::Defer {
+        body: Box::new(body),
+        location,
+    })
+}
+
+/// parses `keyword` followed by a block, returning the block along with
+/// where the whole construct is
+fn parse_keyword_block<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    keyword: Kind<'static>,
+    base_expr: bool,
+) -> Result<(Expression<'parser>, Location), ParseError> {
+    let start = lexer.expect(keyword.clone())?.location;
+    expect_brace_after(lexer, &keyword)?;
+
+    let body = parse_expr_block(lexer, context, base_expr)?;
+    let location = Location::new(start.start_byte, body.location().end_byte);
+    Ok((body, location))
+}
+
+/// peeks at the `{` that has to follow `keyword`, without taking it. an
+/// `unsafe` can start an `unsafe fun` too, though not inside of a function
+fn expect_brace_after(lexer: &mut Lexer<'_>, keyword: &Kind<'_>) -> Result<(), ParseError> {
+    let expected = match keyword {
+        Kind::Unsafe => "`{` or `fun`",
+        _ => "`{`",
+    };
+    match lexer.peek().transpose()? {
+        Some(token) if token.kind == Kind::Op(Operator::LeftBrace) => Ok(()),
+        Some(token) if *keyword == Kind::Unsafe && token.kind == Kind::Fun => Err(ParseError::new(
+            "an `unsafe fun` can only be declared outside of a function",
+            token.location,
+        )),
+        Some(token) => Err(ParseError::new(
+            format!("expected {expected} after `{keyword}`, found `{}`", token.kind),
+            token.location,
+        )),
+        None => Err(ParseError::eof(
+            format!("expected {expected} after `{keyword}`"),
+            end_of_input(lexer),
+        )),
+    }
+}
+
+/// parses `do { x <- foo(); bar(x); return x + 1 }`. every step but the
+/// last one is followed by a semicolon, which the last one can have too
+fn parse_do_block<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let keyword = lexer.expect(Kind::Do)?;
+    expect_brace_after(lexer, &Kind::Do)?;
+    lexer.expect(Kind::Op(Operator::LeftBrace))?;
+    let mut closures = context.allow_trailing_closures(true);
+    let context = &mut *closures;
+
+    let mut steps = vec![];
+    loop {
+        match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::RightBrace) => break,
+            None => return Err(ParseError::eof("expected `}`", end_of_input(lexer))),
+            _ => steps.push(parse_do_step(lexer, context)?),
+        }
+
+        match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::SemiColon) => lexer.next().transpose()?,
+            _ => break,
+        };
+    }
+    let block_end = lexer.expect(Kind::Op(Operator::RightBrace))?;
+
+    let location = Location::new(keyword.location.start_byte, block_end.location.end_byte);
+    Ok(Expression::DoBlock { steps, location })
+}
+
+/// parses `name <- expr` or a plain `expr`, leaving the semicolon after it
+/// to the block
+fn parse_do_step<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<DoStep<'parser>, ParseError> {
+    // unlike the statement, the `return` of a do block wraps a value into
+    // the monad, and doesn't own the semicolon after it
+    if let Some(Token { kind: Kind::Return, .. }) = lexer.peek().transpose()? {
+        let keyword = lexer.expect(Kind::Return)?;
+        let value = parse_expression_with(lexer, context, false)?;
+        let location = Location::new(keyword.location.start_byte, value.location().end_byte);
+        return Ok(DoStep::Seq(Expression::Return {
+            value: Box::new(value),
+            location,
+        }));
+    }
+
+    let expr = parse_expression_with(lexer, context, false)?;
+    match lexer.peek().transpose()? {
+        Some(token) if token.kind == Kind::Op(Operator::LeftArrow) => {
+            let Expression::Ident { name, location } = expr else {
+                return Err(ParseError::new("only a name can be bound with `<-`", expr.location()));
+            };
+            lexer.next().transpose()?;
+            let value = parse_expression_with(lexer, context, false)?;
+            let location = Location::new(location.start_byte, value.location().end_byte);
+            Ok(DoStep::Bind {
+                name,
+                expr: value,
+                location,
+            })
+        }
+        _ => Ok(DoStep::Seq(expr)),
+    }
+}
+
+/// parses `assert(condition)` or `assert(condition, message)`, with an
+/// optional comma after the last argument
+fn parse_assert_expression<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let keyword = lexer.expect(Kind::Assert)?;
+    lexer.expect(Kind::Op(Operator::LeftParen))?;
+    let mut closures = context.allow_trailing_closures(true);
+    let context = &mut *closures;
+
+    let is_next = |lexer: &mut Lexer<'parser>, operator| -> Result<bool, ParseError> {
+        Ok(matches!(lexer.peek().transpose()?, Some(token) if token.kind == Kind::Op(operator)))
+    };
+
+    let condition = parse_expression_with(lexer, context, false)?;
+    let mut message = None;
+    if is_next(lexer, Operator::Comma)? {
+        lexer.next().transpose()?;
+        if !is_next(lexer, Operator::RightParen)? {
+            message = Some(Box::new(parse_expression_with(lexer, context, false)?));
+            if is_next(lexer, Operator::Comma)? {
+                lexer.next().transpose()?;
+            }
+        }
+    }
+
+    let close_paren = lexer.expect(Kind::Op(Operator::RightParen))?;
+    Ok(Expression::Assert {
+        condition: Box::new(condition),
+        message,
+        location: Location::new(keyword.location.start_byte, close_paren.location.end_byte),
+    })
+}
+
+/// parses the postfix operators after `expr`: `[index]`, `(arguments)`,
+/// `.field`, `.0`, `.await` and `?`. they bind tighter than any prefix or
+/// infix operator and apply left to right, so `foo()?.await` is
+/// `(foo()?).await` and `a.b(c)[0]` indexes what the method gives back
+fn parse_postfix<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    mut expr: Expression<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    // each operator is parsed on its own, so that only this loop is on the
+    // stack while the arguments of a call nest
+    while let Some(token) = lexer.peek().transpose()? {
+        expr = match token.kind {
+            Kind::Op(Operator::Dot) => {
+                lexer.next().transpose()?;
+                parse_member(lexer, expr)?
+            }
+            Kind::Op(Operator::Question) => {
+                let end = token.location.end_byte;
+                lexer.next().transpose()?;
+                Expression::Try {
+                    location: Location::new(expr.location().start_byte, end),
+                    expr: Box::new(expr),
+                }
+            }
+            Kind::Op(Operator::LeftParen) if !ends_in_braces(&expr) => parse_fun_call(lexer, context, expr)?,
+            Kind::Op(Operator::LeftBracket) if !ends_in_braces(&expr) => parse_index(lexer, context, expr)?,
+            Kind::Op(operator @ (Operator::PlusPlus | Operator::MinusMinus)) => {
+                return Err(increment_error("postfix", operator, Some(&expr), token.location));
+            }
+            _ => break,
+        };
+    }
+
+    Ok(expr)
+}
+
+/// parses what comes after the dot of `expr.`, which is a field, an index
+/// into a tuple or `await`
+fn parse_member<'parser>(
+    lexer: &mut Lexer<'parser>,
+    expr: Expression<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let Some(token) = lexer.next().transpose()? else {
+        return Err(ParseError::eof(
+            "expected field name or integer index",
+            end_of_input(lexer),
+        ));
+    };
+    let location = Location::new(expr.location().start_byte, token.location.end_byte);
+    match token.kind {
+        Kind::Value(Value::Ident(name)) if name == "await" => Ok(Expression::Await {
+            expr: Box::new(expr),
+            location,
+        }),
+        Kind::Value(Value::Ident(name)) => Ok(Expression::FieldAccess {
+            target: Box::new(expr),
+            field: name,
+            location,
+        }),
+        Kind::Value(Value::Primitive(Primitive::UInt { value, size: None })) => {
+            parse_tuple_index(expr, value, token.location)
+        }
+        // `tuple.0.1` is lexed with `0.1` as a float, which is two indices,
+        // and `tuple.0.field` with `0.` as one, followed by the member
+        Kind::Value(Value::Primitive(Primitive::Float { size: None, .. })) => {
+            let offset = lexer.byte_offset();
+            let text = &lexer.source_code()[token.location.start_byte - offset..token.location.end_byte - offset];
+            let indices = text
+                .split_once('.')
+                .filter(|(first, second)| is_tuple_index(first) && (second.is_empty() || is_tuple_index(second)));
+            let Some((first, second)) = indices else {
+                return Err(ParseError::new("expected field name or integer index", token.location));
+            };
+            let start = token.location.start_byte;
+            let first_location = Location::new(start, start + first.len());
+            let expr = parse_tuple_index(expr, first.parse().unwrap_or(u64::MAX), first_location)?;
+            if second.is_empty() {
+                parse_member(lexer, expr)
+            } else {
+                let second_location = Location::new(first_location.end_byte + 1, token.location.end_byte);
+                parse_tuple_index(expr, second.parse().unwrap_or(u64::MAX), second_location)
+            }
+        }
+        _ => Err(ParseError::new("expected field name or integer index", token.location)),
+    }
+}
+
+/// parses `[index]` after `target`
+fn parse_index<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    target: Expression<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    lexer.expect(Kind::Op(Operator::LeftBracket))?;
+    let mut closures = context.allow_trailing_closures(true);
+    let context = &mut *closures;
+    let index = parse_expression_with(lexer, context, false)?;
+    let close = lexer.expect(Kind::Op(Operator::RightBracket))?;
+
+    Ok(Expression::Index {
+        location: Location::new(target.location().start_byte, close.location.end_byte),
+        target: Box::new(target),
+        index: Box::new(index),
+    })
+}
+
+/// whether `expr` ends in braces, like a block or an `if`. parenthesis or
+/// brackets after one start the next expression instead of calling or
+/// indexing it, as in a statement `if a { b } (c, d)`
+fn ends_in_braces(expr: &Expression<'_>) -> bool {
+    matches!(
+        expr,
+        Expression::Block { .. }
+            | Expression::If { .. }
+            | Expression::Match { .. }
+            | Expression::UnsafeBlock { .. }
+            | Expression::ComptimeBlock { .. }
+            | Expression::Defer { .. }
+            | Expression::DoBlock { .. }
+            | Expression::Lambda { .. }
+    )
+}
+
+fn is_tuple_index(index: &str) -> bool {
+    !index.is_empty() && index.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+/// `expr.index`, where `location` is the one of the index. when `expr` is a
+/// tuple literal its length is known, so the index is checked against it
+fn parse_tuple_index<'parser>(
+    expr: Expression<'parser>,
+    index: u64,
+    location: Location,
+) -> Result<Expression<'parser>, ParseError> {
+    let Ok(index) = u32::try_from(index) else {
+        return Err(ParseError::new("tuple index is too large", location));
+    };
+    if let Expression::Tuple { elements, .. } = &expr {
+        if index as usize >= elements.len() {
+            return Err(ParseError::new(
+                format!("no element at index {index} of a tuple of {} elements", elements.len()),
+                location,
+            ));
+        }
+    }
+
+    Ok(Expression::TupleAccess {
+        location: Location::new(expr.location().start_byte, location.end_byte),
+        tuple: Box::new(expr),
+        index,
+    })
+}
+
+/// `++` and `--` are not part of the language, which would leave which
+/// value they give back to guess. the error tells how to write it instead,
+/// using the name being incremented when there is one
+fn increment_error(
+    fixity: &str,
+    operator: Operator,
+    target: Option<&Expression<'_>>,
+    location: Location,
+) -> ParseError {
+    let name = match target {
+        Some(Expression::Ident { name, .. }) => name.to_string(),
+        Some(Expression::Path { segments, .. }) => segments.join("::"),
+        _ => String::from("x"),
+    };
+    let replacement = match operator {
+        Operator::PlusPlus => "+",
+        _ => "-",
+    };
+    ParseError::new(
+        format!("{fixity} `{operator}` is not valid in this language; use `{name} = {name} {replacement} 1` instead"),
+        location,
+    )
+}
+
+/// `expr try_as T`, starting at `try_as`
+fn parse_safe_cast<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    expr: Expression<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    lexer.expect(Kind::TryAs)?;
+    let typ = parse_type_expression_with(lexer, context)?;
+    let location = Location::new(expr.location().start_byte, typ.location().end_byte);
+
+    Ok(Expression::SafeCast {
+        expr: Box::new(expr),
+        typ: Box::new(typ),
+        location,
+    })
+}
+
+/// `sizeof<T>()`, starting at the `<` after `sizeof`
+fn parse_sizeof<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    start: Location,
+) -> Result<Expression<'parser>, ParseError> {
+    lexer.expect(Kind::Op(Operator::Less))?;
+    let typ = parse_type_expression_with(lexer, context)?;
+    lexer.expect(Kind::Op(Operator::Greater))?;
+    lexer.expect(Kind::Op(Operator::LeftParen))?;
+    let close = lexer.expect(Kind::Op(Operator::RightParen))?;
+
+    Ok(Expression::Sizeof {
+        typ: Box::new(typ),
+        location: Location::new(start.start_byte, close.location.end_byte),
+    })
+}
+
+/// parses the arguments of a macro invocation as a token tree, which can be
+/// delimited by parenthesis, brackets or braces, and only has to keep them
+/// balanced inside
+fn parse_macro<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    ident: Expression<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let Expression::Ident { name, location } = ident else {
+        unreachable!();
+    };
+
+    lexer.expect(Kind::Op(Operator::Bang))?;
+    match name.as_ref() {
+        "typeof" => {
+            let (expr, location) = parse_macro_argument(lexer, context, location)?;
+            return Ok(Expression::RuntimeTypeOf { expr, location });
+        }
+        "len" => {
+            let (array, location) = parse_macro_argument(lexer, context, location)?;
+            return Ok(Expression::ArrayLen { array, location });
+        }
+        "splat" => {
+            let allowed = context.splat_argument.take() == Some(location.start_byte);
+            let (expr, location) = parse_macro_argument(lexer, context, location)?;
+            if !allowed {
+                return Err(ParseError::new(SPLAT_OUTSIDE_ARGUMENTS, location));
+            }
+            return Ok(Expression::Splat { expr, location });
+        }
+        "asm" => return parse_inline_assembly(lexer, context, location),
+        _ => (),
+    }
+
+    let Some(open) = lexer.next().transpose()? else {
+        return Err(ParseError::eof(
+            "expected the arguments of the macro",
+            end_of_input(lexer),
+        ));
+    };
+    let Some(close) = closing_delimiter(&open.kind) else {
+        return Err(ParseError::new(
+            format!("expected `(`, `[` or `{{` after `{name}!`, found `{}`", open.kind),
+            open.location,
+        ));
+    };
+
+    let (tokens, closing) = parse_token_tree(lexer, close)?;
+
+    Ok(Expression::Macro {
+        name,
+        tokens,
+        location: Location::new(location.start_byte, closing.end_byte),
+    })
+}
+
+/// parses the arguments of `asm!("template" : outputs : inputs : clobbers)`,
+/// starting at the `(`. the sections after the template are separated by
+/// colons and each of them can be left out, along with every one after it
+fn parse_inline_assembly<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    name: Location,
+) -> Result<Expression<'parser>, ParseError> {
+    lexer.expect(Kind::Op(Operator::LeftParen))?;
+    let mut closures = context.allow_trailing_closures(true);
+    let context = &mut *closures;
+    let (template, _) = parse_asm_string(lexer, "the template of the assembly")?;
+
+    let mut outputs = vec![];
+    let mut inputs = vec![];
+    let mut clobbers = vec![];
+    let mut section = 0;
+    while section < 3 {
+        match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::Colon) => lexer.next().transpose()?,
+            // `::` is lexed as a single token, and leaves the section between
+            // its colons empty
+            Some(token) if token.kind == Kind::Op(Operator::ColonColon) && section < 2 => {
+                section += 1;
+                lexer.next().transpose()?
+            }
+            _ => break,
+        };
+
+        loop {
+            match lexer.peek().transpose()? {
+                Some(token) if matches!(token.kind, Kind::Op(Operator::Colon | Operator::RightParen)) => break,
+                _ => (),
+            }
+            match section {
+                0 => outputs.push(parse_asm_operand(lexer, context)?),
+                1 => inputs.push(parse_asm_operand(lexer, context)?),
+                _ => clobbers.push(parse_asm_string(lexer, "the name of a clobbered register")?.0),
+            }
+            match lexer.peek().transpose()? {
+                Some(token) if token.kind == Kind::Op(Operator::Comma) => lexer.next().transpose()?,
+                _ => break,
+            };
+        }
+        section += 1;
+    }
+
+    let close_paren = lexer.expect(Kind::Op(Operator::RightParen))?;
+    let location = Location::new(name.start_byte, close_paren.location.end_byte);
+    if !context.inside_unsafe {
+        return Err(ParseError::new(
+            "inline assembly can only be used inside of an `unsafe` block",
+            location,
+        ));
+    }
+
+    Ok(Expression::InlineAsm {
+        template,
+        outputs,
+        inputs,
+        clobbers,
+        location,
+    })
+}
+
+/// parses `"constraint"(expr)`, an operand of inline assembly
+fn parse_asm_operand<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<AsmOperand<'parser>, ParseError> {
+    let (constraint, start) = parse_asm_string(lexer, "the constraint of an operand")?;
+    lexer.expect(Kind::Op(Operator::LeftParen))?;
+    let expr = parse_expression_with(lexer, context, false)?;
+    let close_paren = lexer.expect(Kind::Op(Operator::RightParen))?;
+
+    Ok(AsmOperand {
+        constraint,
+        expr,
+        location: Location::new(start.start_byte, close_paren.location.end_byte),
+    })
+}
+
+/// parses a string of inline assembly, kept as written. `what` is what the
+/// string holds, for the error when it is missing
+fn parse_asm_string<'parser>(
+    lexer: &mut Lexer<'parser>,
+    what: &str,
+) -> Result<(Cow<'parser, str>, Location), ParseError> {
+    match lexer.next().transpose()? {
+        Some(Token {
+            kind: Kind::Value(Value::String(contents)),
+            location,
+        }) => Ok((contents, location)),
+        Some(token) => Err(ParseError::new(
+            format!("expected {what} as a string, found `{}`", token.kind),
+            token.location,
+        )),
+        None => Err(ParseError::eof(format!("expected {what}"), end_of_input(lexer))),
+    }
+}
+
+/// parses the parenthesised expression of macros like `typeof!` and `len!`,
+/// which unlike other macros take an expression instead of tokens. `name` is
+/// where the name of the macro is, and with it the macro spans up to the
+/// closing parenthesis
+fn parse_macro_argument<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    name: Location,
+) -> Result<(Box<Expression<'parser>>, Location), ParseError> {
+    lexer.expect(Kind::Op(Operator::LeftParen))?;
+    let mut closures = context.allow_trailing_closures(true);
+    let context = &mut *closures;
+
+    let expr = parse_expression_with(lexer, context, false)?;
+    if let Some(token) = lexer.peek().transpose()? {
+        if token.kind == Kind::Op(Operator::Comma) {
+            lexer.next().transpose()?;
+        }
+    }
+
+    let close_paren = lexer.expect(Kind::Op(Operator::RightParen))?;
+    Ok((
+        Box::new(expr),
+        Location::new(name.start_byte, close_paren.location.end_byte),
+    ))
+}
+
+/// parses an expression inside of parentheses, or a tuple like `()`, `(a,)`
+/// or `(a, b)`. only the comma tells a tuple of one element apart
+fn parse_parenthesized<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let open = lexer.expect(Kind::Op(Operator::LeftParen))?;
+    let mut closures = context.allow_trailing_closures(true);
+    let context = &mut *closures;
+
+    let mut elements = vec![];
+    if !matches!(lexer.peek(), Some(Ok(token)) if token.kind == Kind::Op(Operator::RightParen)) {
+        let first = parse_with_precedence(lexer, context, ops::LOWEST, false)?;
+        match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::Comma) => {
+                lexer.next().transpose()?;
+                elements.push(first);
+            }
+            _ => {
+                lexer.expect(Kind::Op(Operator::RightParen))?;
+                return Ok(first);
+            }
+        }
+    }
+
+    let (rest, close) = parse_delimited(lexer, context, Operator::RightParen, |lexer, context| {
+        parse_expression_with(lexer, context, false)
+    })?;
+    elements.extend(rest);
+    Ok(Expression::Tuple {
+        elements,
+        location: Location::new(open.location.start_byte, close.end_byte),
+    })
+}
+
+/// parses `[a, b, c]`, which may end with a comma
+fn parse_array<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let open = lexer.expect(Kind::Op(Operator::LeftBracket))?;
+    let mut closures = context.allow_trailing_closures(true);
+    let context = &mut *closures;
+    let (elements, close) = parse_delimited(lexer, context, Operator::RightBracket, |lexer, context| {
+        parse_expression_with(lexer, context, false)
+    })?;
+
+    Ok(Expression::Array {
+        elements,
+        location: Location::new(open.location.start_byte, close.end_byte),
+    })
+}
+
+/// takes every token up to `close`, which closes a delimiter already taken,
+/// returning them along with where `close` is. delimiters inside of it must
+/// be balanced, but the tokens are otherwise kept as they are
+pub(crate) fn parse_token_tree<'parser>(
+    lexer: &mut Lexer<'parser>,
+    close: Operator,
+) -> Result<(Vec<Token<'parser>>, Location), ParseError> {
+    let mut tokens = vec![];
+    let mut delimiters = vec![close];
+
+    loop {
+        let Some(token) = lexer.next().transpose()? else {
+            let expected = delimiters.last().expect("there is always an open delimiter");
+            return Err(ParseError::eof(format!("expected `{expected}`"), end_of_input(lexer)));
+        };
+
+        if let Some(close) = closing_delimiter(&token.kind) {
+            delimiters.push(close);
+        } else if let Kind::Op(operator @ (Operator::RightParen | Operator::RightBracket | Operator::RightBrace)) =
+            token.kind
+        {
+            let expected = delimiters.pop().expect("there is always an open delimiter");
+            if operator != expected {
+                return Err(ParseError::new(
+                    format!("expected `{expected}`, found `{operator}`"),
+                    token.location,
+                ));
+            }
+            if delimiters.is_empty() {
+                return Ok((tokens, token.location));
+            }
+        }
+
+        tokens.push(token);
+    }
+}
+
+/// lays `tokens` out one after the other, separated by spaces and starting
+/// at byte `offset`, giving back the text they are then located in. they are
+/// meant to be parsed as they are with `Lexer::replaying`, as the text isn't
+/// always lexed back into the same tokens, like `2.0` being written `2`
+pub(crate) fn lay_out(tokens: &[Token<'_>], offset: usize) -> (String, Vec<Token<'static>>) {
+    let mut text = String::new();
+    let tokens = tokens
+        .iter()
+        .map(|token| {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            let start = offset + text.len();
+            text.push_str(&token.kind.to_string());
+            Token::new(token.kind.clone().into_owned(), Location::new(start, offset + text.len()))
+        })
+        .collect::<Vec<_>>();
+    (text, tokens)
+}
+
+pub(crate) fn closing_delimiter(kind: &Kind<'_>) -> Option<Operator> {
+    match kind {
+        Kind::Op(Operator::LeftParen) => Some(Operator::RightParen),
+        Kind::Op(Operator::LeftBracket) => Some(Operator::RightBracket),
+        Kind::Op(Operator::LeftBrace) => Some(Operator::RightBrace),
+        _ => None,
+    }
+}
+
+/// the operator a compound assignment like `+=` applies
+fn compound_operator(kind: &Kind<'_>) -> Option<Operator> {
+    match kind {
+        Kind::Op(Operator::PlusEqual) => Some(Operator::Plus),
+        Kind::Op(Operator::MinusEqual) => Some(Operator::Minus),
+        Kind::Op(Operator::StarEqual) => Some(Operator::Star),
+        Kind::Op(Operator::SlashEqual) => Some(Operator::Slash),
+        _ => None,
+    }
+}
+
+/// parses `= value;`, or a compound assignment like `+= value;`
+fn parse_assign<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    left: Expression<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let equal = lexer.expect_one_of(&[
+        Kind::Op(Operator::Equal),
+        Kind::Op(Operator::PlusEqual),
+        Kind::Op(Operator::MinusEqual),
+        Kind::Op(Operator::StarEqual),
+        Kind::Op(Operator::SlashEqual),
+    ])?;
+
+    let value = match lexer.peek().transpose()? {
+        Some(token) if matches!(token.kind, Kind::Op(Operator::LeftBrace)) => parse_expr_block(lexer, context, false)?,
+        Some(_) => parse_expression_with(lexer, context, false)?,
+        None => return Err(ParseError::eof("expected an expression", end_of_input(lexer))),
+    };
+
+    let closing = expect_semicolon(lexer)?;
+
+    let location = left.location().start_byte..closing.end_byte;
+
+    match compound_operator(&equal.kind) {
+        Some(operator) => Ok(Expression::CompoundAssign {
+            operator,
+            ident: Box::new(left),
+            value: Box::new(value),
+            location: location.into(),
+        }),
+        None => Ok(Expression::Assign {
+            ident: Box::new(left),
+            location: location.into(),
+            value: Box::new(value),
+        }),
+    }
+}
+
+fn parse_with_precedence<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    min_precedence: u8,
+    base_expr: bool,
+) -> Result<Expression<'parser>, ParseError> {
+    let mut depth = context.nested(lexer)?;
+    let context = &mut *depth;
+
+    let mut left = match lexer.peek().transpose()? {
+        Some(token) => match &token.kind {
+            Kind::Value(_) => parse_value(lexer, context)?,
+            Kind::Op(_) => parse_operation(lexer, context)?,
+            // a return owns its semicolon, so nothing after it is part of it
+            Kind::Return => return parse_return_expression(lexer, context),
+            Kind::Yield => return parse_yield_expression(lexer, context),
+            Kind::If => parse_if_expression(lexer, context)?,
+            Kind::Match => parse_match(lexer, context)?,
+            Kind::Fun => parse_lambda(lexer, context)?,
+            Kind::Let => parse_let(lexer, context)?,
+            Kind::Unsafe => parse_unsafe(lexer, context, base_expr)?,
+            Kind::Comptime => parse_comptime(lexer, context, base_expr)?,
+            Kind::Defer => parse_defer(lexer, context, base_expr)?,
+            Kind::Do => parse_do_block(lexer, context)?,
+            Kind::Assert => parse_assert_expression(lexer, context)?,
+            _ => return Err(ParseError::unexpected(token)),
+        },
+        None => return Err(ParseError::eof("expected an expression", end_of_input(lexer))),
+    };
+
+    if matches!(&left, Expression::Path { segments, .. } if names_variant(segments)) {
+        left = parse_enum_variant_expression(lexer, context, left)?;
+    }
+
+    // a path can name a function, but neither a macro nor a variable
+    if let Expression::Ident { .. } | Expression::Path { .. } = left {
+        let is_ident = matches!(left, Expression::Ident { .. });
+        match lexer.peek().transpose()? {
+            Some(token) if matches!(token.kind, Kind::Op(Operator::LeftParen)) => {
+                left = parse_fun_call(lexer, context, left)?;
+            }
+            Some(token) if is_ident && matches!(token.kind, Kind::Op(Operator::Bang)) => {
+                left = parse_macro(lexer, context, left)?;
+            }
+            Some(token)
+                if matches!(&left, Expression::Ident { name, .. } if name == "sizeof")
+                    && token.kind == Kind::Op(Operator::Less) =>
+            {
+                left = parse_sizeof(lexer, context, left.location())?;
+            }
+            // the operand of a prefix operator is never what gets assigned
+            Some(token)
+                if is_ident
+                    && min_precedence < ops::PREFIX
+                    && (token.kind == Kind::Op(Operator::Equal) || compound_operator(&token.kind).is_some()) =>
+            {
+                return parse_assign(lexer, context, left)
+            }
+            _ => (),
+        }
+    }
+
+    left = parse_postfix(lexer, context, left)?;
+
+    while let Some(next) = lexer.peek().transpose()? {
+        if next.kind == Kind::TryAs {
+            if ops::CAST <= min_precedence {
+                break;
+            }
+            left = parse_safe_cast(lexer, context, left)?;
+            continue;
+        }
+
+        let Kind::Op(operator) = next.kind else {
+            break;
+        };
+
+        let Some(info) = ops::infix(operator) else {
+            break;
+        };
 
-        let precedence = get_precedence(operator);
-
-        if precedence <= min_precedence {
+        if info.binding_power <= min_precedence {
             break;
         }
 
-        let Some(_) = lexer.next().transpose().map_err(|e| e.to_string())? else {
+        let Some(_) = lexer.next().transpose()? else {
             unreachable!();
         };
 
-        let right = parse_with_precedence(lexer, precedence, false)?;
+        // the right operand takes operators of the same binding power too
+        // when they group from the right
+        let precedence = match info.associativity {
+            ops::Associativity::Left => info.binding_power,
+            ops::Associativity::Right => info.binding_power - 1,
+        };
+        let right = parse_with_precedence(lexer, context, precedence, false)?;
 
         let location = Location::new(left.location().start_byte, right.location().end_byte);
-        left = Expression::BinaryOp {
-            lhs: Box::new(left),
-            operator,
-            rhs: Box::new(right),
-            location,
+        left = match operator {
+            Operator::Elvis => Expression::Elvis {
+                condition: Box::new(left),
+                default: Box::new(right),
+                location,
+            },
+            _ => Expression::BinaryOp {
+                lhs: Box::new(left),
+                operator,
+                rhs: Box::new(right),
+                typ: None,
+                location,
+            },
         };
     }
 
+    // a call on its own is a statement, which owns its semicolon
+    if base_expr && is_call(&left) {
+        expect_semicolon(lexer)?;
+    }
+
     Ok(left)
 }
 
-fn parse_return_expression<'parser>(lexer: &mut Lexer<'parser>) -> Result<Expression<'parser>, String> {
-    let keyword = lexer.expect(Kind::Return).map_err(|e| e.to_string())?;
-
-    let value = parse_expression(lexer, false)?;
+/// parses `return value;`. several values, like `return a, b;`, are
+/// returned as a tuple, just like `return (a, b);`
+fn parse_return_expression<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let keyword = lexer.expect(Kind::Return)?;
+
+    let mut value = parse_expression_with(lexer, context, false)?;
+    if matches!(lexer.peek(), Some(Ok(token)) if token.kind == Kind::Op(Operator::Comma)) {
+        let start = value.location().start_byte;
+        let mut elements = vec![value];
+        while matches!(lexer.peek(), Some(Ok(token)) if token.kind == Kind::Op(Operator::Comma)) {
+            lexer.next().transpose()?;
+            elements.push(parse_expression_with(lexer, context, false)?);
+        }
+        let end = elements.last().map_or(start, |element| element.location().end_byte);
+        value = Expression::Tuple {
+            elements,
+            location: Location::new(start, end),
+        };
+    }
 
-    let ending_semi = lexer.expect(Kind::Op(Operator::SemiColon)).map_err(|e| e.to_string())?;
+    let ending_semi = expect_semicolon(lexer)?;
 
-    let location = keyword.location.start_byte..ending_semi.location.end_byte;
+    let location = keyword.location.start_byte..ending_semi.end_byte;
     Ok(Expression::Return {
         value: Box::new(value),
         location: location.into(),
     })
 }
 
-fn parse_primitive<'parser>(lexer: &mut Lexer<'parser>) -> Result<Expression<'parser>, String> {
-    let (primitive, location) = match lexer.next().transpose().map_err(|e| e.to_string())? {
+/// parses `yield value;` or a bare `yield;`
+fn parse_yield_expression<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let keyword = lexer.expect(Kind::Yield)?;
+
+    let value = match lexer.peek().transpose()? {
+        Some(token) if token.kind == Kind::Op(Operator::SemiColon) => None,
+        _ => Some(Box::new(parse_expression_with(lexer, context, false)?)),
+    };
+
+    let ending_semi = expect_semicolon(lexer)?;
+
+    let location = keyword.location.start_byte..ending_semi.end_byte;
+    Ok(Expression::Yield {
+        value,
+        location: location.into(),
+    })
+}
+
+fn parse_primitive<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Expression<'parser>, ParseError> {
+    let (primitive, location) = match lexer.next().transpose()? {
         Some(Token {
             kind: Kind::Value(Value::Primitive(primitive)),
             location,
@@ -352,10 +2326,30 @@ fn parse_primitive<'parser>(lexer: &mut Lexer<'parser>) -> Result<Expression<'pa
         _ => unreachable!(),
     };
 
+    // an explicit suffix always wins over the default size
+    let options = context.options.clone();
+    let out_of_range =
+        |size: &dyn std::fmt::Display| ParseError::new(format!("literal out of range for `{size}`"), location);
     match primitive {
-        Primitive::Int { value, size } => Ok(Expression::IntLiteral { value, size, location }),
-        Primitive::UInt { value, size } => Ok(Expression::UintLiteral { value, size, location }),
-        Primitive::Float { value, size } => Ok(Expression::FloatLiteral { value, size, location }),
+        Primitive::Int { value, size } => {
+            let size = size.or(options.default_int_size);
+            match size {
+                Some(size) if !size.fits(value) => Err(out_of_range(&size)),
+                size => Ok(Expression::IntLiteral { value, size, location }),
+            }
+        }
+        Primitive::UInt { value, size } => {
+            let size = size.or(options.default_uint_size);
+            match size {
+                Some(size) if !size.fits(value) => Err(out_of_range(&size)),
+                size => Ok(Expression::UintLiteral { value, size, location }),
+            }
+        }
+        Primitive::Float { value, size } => Ok(Expression::FloatLiteral {
+            value,
+            size: size.or(options.default_float_size),
+            location,
+        }),
         Primitive::Bool(value) => Ok(Expression::Bool { value, location }),
     }
 }
@@ -363,6 +2357,7 @@ fn parse_primitive<'parser>(lexer: &mut Lexer<'parser>) -> Result<Expression<'pa
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::sexpr::to_sexpr;
     use crate::Parser;
 
     fn make_sut(source: &str) -> Parser<'_> {
@@ -449,4 +2444,849 @@ mod tests {
 
         insta::assert_debug_snapshot!(let_if_ast);
     }
+
+    #[test]
+    fn string_literals() {
+        let source = r#"const greeting = "hello, \"world\"\n";"#;
+
+        let mut parser = make_sut(source);
+        let string_ast = match parse_expression(&mut parser.lexer, true) {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        insta::assert_debug_snapshot!(string_ast);
+    }
+
+    #[test]
+    fn raw_strings_are_plain_literals() {
+        let source = r###"r##"no {interpolation} or \escapes, "quoted" r#"nested"#"##"###;
+
+        let expr = parse_expression(&mut Lexer::new(source), false).unwrap();
+        assert_eq!(
+            expr,
+            Expression::StringLiteral {
+                value: Cow::Borrowed(r##"no {interpolation} or \escapes, "quoted" r#"nested"#"##),
+                location: Location::new(0, source.len()),
+            }
+        );
+    }
+
+    #[test]
+    fn interpolated_strings() {
+        let source = r#"const greeting = "{{hello}}, {name}! you are {age + 1} {unit("year")}";"#;
+
+        let mut parser = make_sut(source);
+        let interpolated_ast = match parse_expression(&mut parser.lexer, true) {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        insta::assert_debug_snapshot!(interpolated_ast);
+    }
+
+    #[test]
+    fn malformed_interpolations() {
+        for source in [r#""empty {}";"#, r#""unclosed {name";"#, r#""stray } brace";"#] {
+            let mut parser = make_sut(source);
+            assert!(parse_expression(&mut parser.lexer, true).is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn macro_invocations() {
+        let source = r#"println!("hello, {}", name);"#;
+
+        let mut parser = make_sut(source);
+        let macro_ast = match parse_expression(&mut parser.lexer, true) {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        insta::assert_debug_snapshot!(macro_ast);
+    }
+
+    #[test]
+    fn macro_token_trees_are_balanced() {
+        let source = r#"{ vec![(1, [2]), { 3 }]; const a = matches!{ x }; }"#;
+        let mut parser = make_sut(source);
+        let Expression::Block { expressions, .. } = parse_expression(&mut parser.lexer, true).unwrap() else {
+            panic!("expected a block");
+        };
+
+        let Expression::Macro { name, tokens, .. } = &expressions[0] else {
+            panic!("expected a macro");
+        };
+        assert_eq!(name, "vec");
+        let tokens = tokens.iter().map(ToString::to_string).collect::<Vec<_>>();
+        assert_eq!(tokens.join(" "), "( 1 , [ 2 ] ) , { 3 }");
+
+        for source in ["print!(1, (2];", "print!(1, (2)", "print! 1;"] {
+            let mut parser = make_sut(source);
+            let error = parse_expression(&mut parser.lexer, true).unwrap_err();
+            assert_eq!(error.is_incomplete(), source == "print!(1, (2)", "{source}");
+        }
+    }
+
+    #[test]
+    fn paths_as_function_names() {
+        let source = "std::io::write(buf);";
+
+        let mut parser = make_sut(source);
+        let path_ast = match parse_expression(&mut parser.lexer, true) {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        insta::assert_debug_snapshot!(path_ast);
+    }
+
+    #[test]
+    fn paths_as_values() {
+        let source = "{ const a = math::PI * 2; const b = c; std::io::flush().await; }";
+        let mut parser = make_sut(source);
+        let Expression::Block { expressions, .. } = parse_expression(&mut parser.lexer, true).unwrap() else {
+            panic!("expected a block");
+        };
+
+        let Expression::Var { value, .. } = &expressions[0] else {
+            panic!("expected a variable");
+        };
+        let Expression::BinaryOp { lhs, .. } = value.as_ref() else {
+            panic!("expected a binary operation");
+        };
+        assert!(matches!(lhs.as_ref(), Expression::Path { segments, .. } if segments == &["math", "PI"]));
+
+        // a single name is still an identifier
+        let Expression::Var { value, .. } = &expressions[1] else {
+            panic!("expected a variable");
+        };
+        assert!(matches!(value.as_ref(), Expression::Ident { .. }));
+        assert!(matches!(&expressions[2], Expression::Await { .. }));
+
+        for body in ["std::;", "std::1;", "std::io!(1);", "std::io = 1;"] {
+            let source = format!("fun main() {{ {body} }}");
+            assert!(make_sut(&source).parse().is_err(), "{body}");
+        }
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+
+        let source = nested(MAX_NESTING_DEPTH - 1);
+        assert!(parse_expression(&mut make_sut(&source).lexer, true).is_ok());
+
+        let source = nested(MAX_NESTING_DEPTH + 1);
+        let error = parse_expression(&mut make_sut(&source).lexer, true).unwrap_err();
+        assert!(error.message.contains("levels deep"), "{error:?}");
+
+        // the depth is released after an error, so the context can be used
+        // to parse something else right after
+        let mut context = ParseContext::default();
+        let source = nested(MAX_NESTING_DEPTH + 1);
+        assert!(parse_expression_with(&mut Lexer::new(&source), &mut context, true).is_err());
+        assert_eq!(context.depth, 0);
+        let source = nested(MAX_NESTING_DEPTH - 1);
+        assert!(parse_expression_with(&mut Lexer::new(&source), &mut context, true).is_ok());
+    }
+
+    #[test]
+    fn blocks_end_in_their_tail() {
+        let parse = |source: &'static str| {
+            let mut parser = make_sut(source);
+            to_sexpr(&parse_expression(&mut parser.lexer, true).unwrap())
+        };
+
+        assert_eq!(
+            parse("{ f(); x + 1 }"),
+            "(block (call (ident f)) (tail (binary-op + (ident x) (uint-literal 1))))"
+        );
+        // a semicolon makes a statement out of the last expression
+        assert_eq!(parse("{ f(); }"), "(block (call (ident f)))");
+        assert_eq!(parse("{ var a = 1; }"), "(block (var a (uint-literal 1)))");
+        assert_eq!(parse("{}"), "(block)");
+        // calls only take a semicolon at the base of a block
+        assert_eq!(parse("const a = { f() };"), "(const a (block (tail (call (ident f)))))");
+    }
+
+    #[test]
+    fn unsafe_blocks() {
+        let source = "unsafe { ptr_deref(x) }";
+        let mut parser = make_sut(source);
+        let expr = parse_expression(&mut parser.lexer, false).unwrap();
+        assert_eq!(
+            to_sexpr(&expr),
+            "(unsafe (block (tail (call (ident ptr_deref) (ident x)))))"
+        );
+
+        let Expression::UnsafeBlock { body, location } = &expr else {
+            panic!("expected an unsafe block");
+        };
+        assert_eq!(*location, Location::new(0, source.len()));
+        let Expression::Block { expressions, tail, .. } = body.as_ref() else {
+            panic!("expected a block");
+        };
+        assert!(expressions.is_empty());
+        assert!(matches!(tail.as_deref(), Some(Expression::FunCall { .. })));
+
+        // as a statement, the calls inside of it are statements too
+        let mut parser = make_sut("unsafe { ptr_deref(x); } var a = 1;");
+        let expr = parse_expression(&mut parser.lexer, true).unwrap();
+        assert_eq!(to_sexpr(&expr), "(unsafe (block (call (ident ptr_deref) (ident x))))");
+
+        let mut parser = make_sut("unsafe ptr_deref(x)");
+        let error = parse_expression(&mut parser.lexer, false).unwrap_err();
+        assert_eq!(error.message, "expected `{` or `fun` after `unsafe`, found `ptr_deref`");
+        assert_eq!(error.location(), Location::new(7, 16));
+
+        let mut parser = make_sut("unsafe fun f() {}");
+        let error = parse_expression(&mut parser.lexer, true).unwrap_err();
+        assert_eq!(error.message, "an `unsafe fun` can only be declared outside of a function");
+
+        let mut parser = make_sut("unsafe");
+        assert!(parse_expression(&mut parser.lexer, false).unwrap_err().is_incomplete());
+    }
+
+    #[test]
+    fn safe_casts() {
+        let source = "x try_as u8";
+        let expr = parse_expression(&mut make_sut(source).lexer, false).unwrap();
+        assert_eq!(to_sexpr(&expr), "(try-as (ident x) (type-name u8))");
+        assert_eq!(expr.location(), Location::new(0, source.len()));
+
+        // it takes the operand of arithmetic, but not a reference
+        let expr = parse_expression(&mut make_sut("a + &b try_as u8 * 2").lexer, false).unwrap();
+        assert_eq!(
+            to_sexpr(&expr),
+            "(binary-op + (ident a) (binary-op * (try-as (ref (ident b)) (type-name u8)) (uint-literal 2)))"
+        );
+
+        let mut parser = make_sut("x try_as");
+        assert!(parse_expression(&mut parser.lexer, false).unwrap_err().is_incomplete());
+    }
+
+    #[test]
+    fn inline_assembly() {
+        let source = r#"unsafe { asm!("mov rax, 0" : "=r"(out) : "r"(a + 1), "r"(b) : "rax"); }"#;
+        let expr = parse_expression(&mut make_sut(source).lexer, true).unwrap();
+        assert_eq!(
+            to_sexpr(&expr),
+            r#"(unsafe (block (asm "mov rax, 0" (out "=r" (ident out)) (in "r" (binary-op + (ident a) (uint-literal 1))) (in "r" (ident b)) (clobber "rax"))))"#
+        );
+
+        // every section after the template can be left out
+        let expr = parse_expression(&mut make_sut(r#"unsafe { asm!("nop" :: "r"(x)); }"#).lexer, true).unwrap();
+        assert_eq!(to_sexpr(&expr), r#"(unsafe (block (asm "nop" (in "r" (ident x)))))"#);
+
+        let source = r#"asm!("nop")"#;
+        let error = parse_expression(&mut make_sut(source).lexer, false).unwrap_err();
+        assert_eq!(
+            error.message,
+            "inline assembly can only be used inside of an `unsafe` block"
+        );
+        assert_eq!(error.location(), Location::new(0, source.len()));
+
+        let mut parser = make_sut(r#"unsafe { asm!("nop" : "=r"("#);
+        assert!(parse_expression(&mut parser.lexer, false).unwrap_err().is_incomplete());
+    }
+
+    #[test]
+    fn splats() {
+        let source = "sum(1, splat!(rest), splat!(f(splat!(more))))";
+        let expr = parse_expression(&mut make_sut(source).lexer, false).unwrap();
+        assert_eq!(
+            to_sexpr(&expr),
+            "(call (ident sum) (uint-literal 1) (splat (ident rest)) (splat (call (ident f) (splat (ident more)))))"
+        );
+        let Expression::FunCall { arguments, .. } = &expr else {
+            panic!("expected a call");
+        };
+        assert_eq!(arguments[1].location(), Location::new(7, 19));
+
+        let misplaced = |source: &str| {
+            let error = parse_expression(&mut make_sut(source).lexer, false).unwrap_err();
+            assert_eq!(error.message, "`splat!` can only be used as a whole argument of a call");
+            let location = error.location();
+            source[location.start_byte..location.end_byte].to_string()
+        };
+        assert_eq!(misplaced("splat!(a)"), "splat!(a)");
+        assert_eq!(misplaced("f(x + splat!(a))"), "splat!(a)");
+        assert_eq!(misplaced("f([splat!(a)])"), "splat!(a)");
+        assert_eq!(misplaced("f(splat!(splat!(a)))"), "splat!(a)");
+        assert_eq!(misplaced("f(splat!(a) + 1)"), "splat!(a) + 1");
+        assert_eq!(misplaced("f(fun() { splat!(a) })"), "splat!(a)");
+    }
+
+    #[test]
+    fn comptime_blocks() {
+        let source = "comptime { SIZE * 2 }";
+        let mut parser = make_sut(source);
+        let expr = parse_expression(&mut parser.lexer, false).unwrap();
+        assert_eq!(
+            to_sexpr(&expr),
+            "(comptime (block (tail (binary-op * (ident SIZE) (uint-literal 2)))))"
+        );
+        assert_eq!(expr.location(), Location::new(0, source.len()));
+
+        let mut parser = make_sut("comptime 4");
+        let error = parse_expression(&mut parser.lexer, false).unwrap_err();
+        assert_eq!(error.message, "expected `{` after `comptime`, found `4`");
+    }
+
+    #[test]
+    fn do_blocks() {
+        let source = "do { x <- foo(); log(x); return x + 1 }";
+        let mut parser = make_sut(source);
+        let expr = parse_expression(&mut parser.lexer, false).unwrap();
+        assert_eq!(
+            to_sexpr(&expr),
+            "(do (bind x (call (ident foo))) (call (ident log) (ident x)) \
+             (return (binary-op + (ident x) (uint-literal 1))))"
+        );
+        assert_eq!(expr.location(), Location::new(0, source.len()));
+
+        let Expression::DoBlock { steps, .. } = &expr else {
+            panic!("expected a do block");
+        };
+        assert_eq!(steps[0].location(), Location::new(5, 15));
+
+        // the last step can have a semicolon too
+        let mut parser = make_sut("do { foo(); }");
+        let expr = parse_expression(&mut parser.lexer, false).unwrap();
+        assert_eq!(to_sexpr(&expr), "(do (call (ident foo)))");
+
+        let mut parser = make_sut("do { x + 1 <- foo() }");
+        let error = parse_expression(&mut parser.lexer, false).unwrap_err();
+        assert_eq!(error.message, "only a name can be bound with `<-`");
+        assert_eq!(error.location(), Location::new(5, 10));
+
+        let mut parser = make_sut("do { a b }");
+        assert!(parse_expression(&mut parser.lexer, false).is_err());
+    }
+
+    #[test]
+    fn tuples_and_their_elements() {
+        let parse = |source: &'static str| {
+            let mut parser = make_sut(source);
+            parse_expression(&mut parser.lexer, false).map(|expr| to_sexpr(&expr))
+        };
+
+        assert_eq!(parse("()").unwrap(), "(tuple)");
+        assert_eq!(parse("(a)").unwrap(), "(ident a)");
+        assert_eq!(parse("(a,)").unwrap(), "(tuple (ident a))");
+        assert_eq!(
+            parse("(1, b + 2,)").unwrap(),
+            "(tuple (uint-literal 1) (binary-op + (ident b) (uint-literal 2)))"
+        );
+        assert_eq!(parse("t.0").unwrap(), "(tuple-access (ident t) 0)");
+        assert_eq!(
+            parse("(a, (b, c)).1.0").unwrap(),
+            "(tuple-access (tuple-access (tuple (ident a) (tuple (ident b) (ident c))) 1) 0)"
+        );
+        assert_eq!(
+            parse("t.1.field.2").unwrap(),
+            "(tuple-access (field-access (tuple-access (ident t) 1) field) 2)"
+        );
+
+        let mut parser = make_sut("t.12.3");
+        let expr = parse_expression(&mut parser.lexer, false).unwrap();
+        assert_eq!(expr.location(), Location::new(0, 6));
+        let Expression::TupleAccess { tuple, index: 3, .. } = &expr else {
+            panic!("expected a tuple access");
+        };
+        assert_eq!(tuple.location(), Location::new(0, 4));
+
+        let error = parse("(a, b).2").unwrap_err();
+        assert_eq!(error.message, "no element at index 2 of a tuple of 2 elements");
+        assert_eq!(error.location(), Location::new(7, 8));
+
+        // the first of two indices lexed as a float
+        let error = parse("(a, b).5.0").unwrap_err();
+        assert_eq!(error.location(), Location::new(7, 8));
+
+        for source in ["t.\"0\"", "t.1.5f32", "t.0u8"] {
+            assert_eq!(
+                parse(source).unwrap_err().message,
+                "expected field name or integer index",
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn references_and_dereferences() {
+        let parse = |source: &'static str| {
+            let mut parser = make_sut(source);
+            match parse_expression(&mut parser.lexer, false) {
+                Ok(expr) => expr,
+                Err(e) => panic!("{source}: {e:?}"),
+            }
+        };
+
+        assert_eq!(
+            to_sexpr(&parse("&a + *b * 2")),
+            "(binary-op + (ref (ident a)) (binary-op * (deref (ident b)) (uint-literal 2)))"
+        );
+        assert_eq!(
+            to_sexpr(&parse("&mut point.x")),
+            "(ref-mut (field-access (ident point) x))"
+        );
+        assert_eq!(to_sexpr(&parse("*f(a)")), "(deref (call (ident f) (ident a)))");
+        assert_eq!(to_sexpr(&parse("**a")), "(deref (deref (ident a)))");
+
+        // `&&` is two references in front of an expression, and a logical
+        // and between two of them
+        let refs = parse("&&mut a");
+        assert_eq!(to_sexpr(&refs), "(ref (ref-mut (ident a)))");
+        let Expression::Ref { expr, location, .. } = &refs else {
+            panic!("expected a reference");
+        };
+        assert_eq!(*location, Location::new(0, 7));
+        assert_eq!(expr.location(), Location::new(1, 7));
+
+        assert_eq!(
+            to_sexpr(&parse("a && &&b")),
+            "(binary-op && (ident a) (ref (ref (ident b))))"
+        );
+        assert_eq!(
+            to_sexpr(&parse("&a&&*b")),
+            "(binary-op && (ref (ident a)) (deref (ident b)))"
+        );
+
+        // the operand of a prefix operator is never assigned to
+        let mut parser = make_sut("{ *a = 1 }");
+        let error = parse_expression(&mut parser.lexer, true).unwrap_err();
+        assert_eq!(error.location(), Location::new(5, 6));
+    }
+
+    #[test]
+    fn negations() {
+        let parse = |source: &'static str| {
+            let mut parser = make_sut(source);
+            match parse_expression(&mut parser.lexer, false) {
+                Ok(expr) => to_sexpr(&expr),
+                Err(e) => panic!("{source}: {e:?}"),
+            }
+        };
+
+        assert_eq!(parse("!true"), "(not (bool true))");
+        assert_eq!(
+            parse("!a && !!b.c"),
+            "(binary-op && (not (ident a)) (not (not (field-access (ident b) c))))"
+        );
+        assert_eq!(parse("!f(a) == b"), "(binary-op == (not (call (ident f) (ident a))) (ident b))");
+        // `!=` is still lexed as one operator
+        assert_eq!(parse("a != b"), "(binary-op != (ident a) (ident b))");
+    }
+
+    #[test]
+    fn assertions() {
+        let parse = |source: &'static str| {
+            let mut parser = make_sut(source);
+            parse_expression(&mut parser.lexer, true)
+        };
+        let sexpr = |source| match parse(source) {
+            Ok(expr) => to_sexpr(&expr),
+            Err(e) => panic!("{source}: {e:?}"),
+        };
+
+        assert_eq!(
+            sexpr("assert(a == 1);"),
+            "(assert (binary-op == (ident a) (uint-literal 1)))"
+        );
+        assert_eq!(
+            sexpr(r#"assert(a, "a is {a}",);"#),
+            r#"(assert (ident a) (interpolated (string-literal "a is ") (ident a) (string-literal "")))"#
+        );
+        assert_eq!(sexpr("assert(a,);"), "(assert (ident a))");
+        assert_eq!(parse("assert(a);").unwrap().location(), Location::new(0, 9));
+
+        // like a call, an assertion on its own owns its semicolon
+        assert!(parse("assert(a)").unwrap_err().is_incomplete());
+        for source in ["assert();", "assert(a, b, c);", "assert a;", "assert(a b);"] {
+            assert!(parse(source).is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn runtime_type_of() {
+        let parse = |source: &'static str| {
+            let mut parser = make_sut(source);
+            parse_expression(&mut parser.lexer, true)
+        };
+
+        let expr = parse("const t = typeof!(a + 1);").unwrap();
+        assert_eq!(
+            to_sexpr(&expr),
+            "(const t (typeof (binary-op + (ident a) (uint-literal 1))))"
+        );
+        let Expression::Var { value, .. } = expr else {
+            panic!("expected a variable");
+        };
+        assert_eq!(value.location(), Location::new(10, 24));
+
+        // other macros still take tokens
+        assert!(matches!(parse("type_of!(a);"), Ok(Expression::Macro { .. })));
+        assert!(parse("typeof!(a)").unwrap_err().is_incomplete());
+        for source in ["typeof!();", "typeof![a];", "typeof!(a, b);"] {
+            assert!(parse(source).is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn arrays_and_their_lengths() {
+        let parse = |source: &'static str| {
+            let mut parser = make_sut(source);
+            parse_expression(&mut parser.lexer, true)
+        };
+
+        let expr = parse("const n = len!([1, [a], f(b),]);").unwrap();
+        assert_eq!(
+            to_sexpr(&expr),
+            "(const n (len (array (uint-literal 1) (array (ident a)) (call (ident f) (ident b)))))"
+        );
+        let Expression::Var { value, .. } = expr else {
+            panic!("expected a variable");
+        };
+        assert_eq!(value.location(), Location::new(10, 31));
+        assert_eq!(to_sexpr(&parse("[];").unwrap()), "(array)");
+
+        assert!(parse("[1, 2").unwrap_err().is_incomplete());
+        for source in ["[1 2];", "[,];", "len!();", "len!(a, b);", "len![a];"] {
+            assert!(parse(source).is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn elvis_falls_back_to_its_default() {
+        let parse = |source: &'static str| {
+            let mut parser = make_sut(source);
+            parse_expression(&mut parser.lexer, false)
+        };
+        let sexpr = |source| match parse(source) {
+            Ok(expr) => to_sexpr(&expr),
+            Err(e) => panic!("{source}: {e:?}"),
+        };
+
+        // groups from the left, after every other operator
+        assert_eq!(sexpr("a ?: b ?: c"), "(elvis (elvis (ident a) (ident b)) (ident c))");
+        assert_eq!(
+            sexpr("a || b ?: c + 1"),
+            "(elvis (binary-op || (ident a) (ident b)) (binary-op + (ident c) (uint-literal 1)))"
+        );
+        assert_eq!(sexpr("a?:b"), "(elvis (ident a) (ident b))");
+        assert_eq!(parse("a ?: b").unwrap().location(), Location::new(0, 6));
+
+        // only `?` glued to a `:` is the operator, there's no ternary for
+        // anything in between them to belong to. a `?` on its own is a try
+        assert!(Parser::parse_repl_line("a ? : b").is_err());
+        assert!(Parser::parse_repl_line("a ? b : c").is_err());
+        assert!(parse("a ?: ").unwrap_err().is_incomplete());
+    }
+
+    #[test]
+    fn operators_bind_as_the_table_says() {
+        let parse = |source: &'static str| {
+            let mut parser = make_sut(source);
+            match parse_expression(&mut parser.lexer, false) {
+                Ok(expr) => to_sexpr(&expr),
+                Err(e) => panic!("{source}: {e:?}"),
+            }
+        };
+
+        assert_eq!(
+            parse("a < b || c >= 2"),
+            "(binary-op || (binary-op < (ident a) (ident b)) (binary-op >= (ident c) (uint-literal 2)))"
+        );
+        assert_eq!(
+            parse("a - b - c"),
+            "(binary-op - (binary-op - (ident a) (ident b)) (ident c))"
+        );
+        // `||` < `&&` < comparison < sum < product
+        assert_eq!(
+            parse("a * b && c"),
+            "(binary-op && (binary-op * (ident a) (ident b)) (ident c))"
+        );
+        assert_eq!(
+            parse("x < y + 1"),
+            "(binary-op < (ident x) (binary-op + (ident y) (uint-literal 1)))"
+        );
+        assert_eq!(
+            parse("x || y && z"),
+            "(binary-op || (ident x) (binary-op && (ident y) (ident z)))"
+        );
+    }
+
+    #[test]
+    fn lambdas_and_trailing_closures() {
+        let parse = |source: &'static str| {
+            let mut parser = make_sut(source);
+            match parse_expression(&mut parser.lexer, false) {
+                Ok(expr) => expr,
+                Err(e) => panic!("{source}: {e:?}"),
+            }
+        };
+
+        let trailing = parse("map(list) { x * 2 }");
+        let explicit = parse("map(list, fun() { x * 2 })");
+        assert_eq!(to_sexpr(&trailing), to_sexpr(&explicit));
+        assert_eq!(
+            to_sexpr(&trailing),
+            "(call (ident map) (ident list) (lambda (params) (block (tail (binary-op * (ident x) (uint-literal 2))))))"
+        );
+
+        let Expression::FunCall {
+            arguments, location, ..
+        } = &trailing
+        else {
+            panic!("expected a call");
+        };
+        assert_eq!(arguments.len(), 2);
+        assert!(matches!(arguments[1], Expression::Lambda { .. }));
+        assert_eq!(*location, Location::new(0, 19));
+
+        let lambda = parse("fun(a, b,) { a + b }");
+        assert_eq!(
+            to_sexpr(&lambda),
+            "(lambda (params (ident a) (ident b)) (block (tail (binary-op + (ident a) (ident b)))))"
+        );
+        assert_eq!(
+            to_sexpr(&parse("each() { 1 }")),
+            "(call (ident each) (lambda (params) (block (tail (uint-literal 1)))))"
+        );
+    }
+
+    #[test]
+    fn annotated_lambdas() {
+        let parse = |source: &'static str| match parse_expression(&mut make_sut(source).lexer, false) {
+            Ok(expr) => to_sexpr(&expr),
+            Err(e) => panic!("{source}: {e:?}"),
+        };
+
+        let cases = [
+            ("fun(a) { a }", "(lambda (params (ident a)) (block (tail (ident a))))"),
+            (
+                "fun(a: i32, b) { a }",
+                "(lambda (params (arg (ident a) (type-name i32)) (ident b)) (block (tail (ident a))))",
+            ),
+            (
+                "fun(a) => i32 { a }",
+                "(lambda (params (ident a)) (returns (type-name i32)) (block (tail (ident a))))",
+            ),
+            (
+                "fun(a: i32) => i32 { a }",
+                "(lambda (params (arg (ident a) (type-name i32))) (returns (type-name i32)) (block (tail (ident a))))",
+            ),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(parse(source), expected, "{source}");
+        }
+
+        for source in ["fun(a:) { a }", "fun(a) => { a }", "fun(a: i32 b) { a }"] {
+            assert!(
+                parse_expression(&mut make_sut(source).lexer, false).is_err(),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn if_conditions_have_no_trailing_closures() {
+        let source = "if check(a) { 1 } else { 2 }";
+        let mut parser = make_sut(source);
+        let expr = parse_expression(&mut parser.lexer, true).unwrap();
+        assert_eq!(
+            to_sexpr(&expr),
+            "(if (call (ident check) (ident a)) (block (tail (uint-literal 1))) (block (tail (uint-literal 2))))"
+        );
+
+        // but they are back inside of the arguments and blocks of the condition
+        let source = "if any(list, all(l) { x }) { 1 }";
+        let mut parser = make_sut(source);
+        let Expression::If { condition, .. } = parse_expression(&mut parser.lexer, true).unwrap() else {
+            panic!("expected an if");
+        };
+        assert!(to_sexpr(&condition).contains("(call (ident all) (ident l) (lambda"));
+
+        // and the body of the if is parsed like anywhere else
+        let source = "{ if a { run(a) { b }; } run(c) { d }; }";
+        let mut parser = make_sut(source);
+        assert_eq!(
+            to_sexpr(&parse_expression(&mut parser.lexer, true).unwrap())
+                .matches("(lambda")
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn let_expressions() {
+        let sexpr = |source: &str| {
+            let mut parser = make_sut(source);
+            match parse_expression(&mut parser.lexer, false) {
+                Ok(expr) => to_sexpr(&expr),
+                Err(e) => panic!("{source}: {e:?}"),
+            }
+        };
+
+        assert_eq!(
+            sexpr("let a = 1 + 2 in a * b"),
+            "(let a (binary-op + (uint-literal 1) (uint-literal 2)) (binary-op * (ident a) (ident b)))"
+        );
+        assert_eq!(
+            sexpr("let a = 1 in let b = a in f(a, b)"),
+            "(let a (uint-literal 1) (let b (ident a) (call (ident f) (ident a) (ident b))))"
+        );
+        // the body takes everything after it, unless it is wrapped
+        assert_eq!(
+            sexpr("1 + let a = 2 in a + 3"),
+            "(binary-op + (uint-literal 1) (let a (uint-literal 2) (binary-op + (ident a) (uint-literal 3))))"
+        );
+        assert_eq!(
+            sexpr("(let a = 2 in a) + 3"),
+            "(binary-op + (let a (uint-literal 2) (ident a)) (uint-literal 3))"
+        );
+
+        let mut parser = make_sut("let a = 1 a");
+        let error = parse_expression(&mut parser.lexer, false).unwrap_err();
+        assert!(error.to_string().contains("in"), "{error}");
+    }
+
+    #[test]
+    fn yields_with_and_without_a_value() {
+        let sexpr = |source: &str| match parse_expression(&mut make_sut(source).lexer, true) {
+            Ok(expr) => to_sexpr(&expr),
+            Err(e) => panic!("{source}: {e:?}"),
+        };
+
+        assert_eq!(
+            sexpr("yield 1 + 2;"),
+            "(yield (binary-op + (uint-literal 1) (uint-literal 2)))"
+        );
+        assert_eq!(sexpr("yield;"), "(yield)");
+
+        let mut parser = make_sut("yield 1 + 2; x");
+        let expr = parse_expression(&mut parser.lexer, true).unwrap();
+        assert_eq!(expr.location(), Location::new(0, 12));
+
+        for source in ["yield 1", "yield", "yield yield;"] {
+            assert!(parse_expression(&mut make_sut(source).lexer, true).is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn increments_point_at_the_operator() {
+        let error = |source: &str| parse_expression(&mut make_sut(source).lexer, true).unwrap_err();
+
+        let postfix = error("items.len++;");
+        assert_eq!(
+            postfix.message,
+            "postfix `++` is not valid in this language; use `x = x + 1` instead"
+        );
+        assert_eq!(postfix.location(), Location::new(9, 11));
+
+        let prefix = error("--count;");
+        assert_eq!(
+            prefix.message,
+            "prefix `--` is not valid in this language; use `x = x - 1` instead"
+        );
+        assert_eq!(prefix.location(), Location::new(0, 2));
+
+        // a space keeps them apart, as two operators
+        let mut parser = make_sut("a - -1");
+        let expr = parse_expression(&mut parser.lexer, false).unwrap();
+        assert_eq!(to_sexpr(&expr), "(binary-op - (ident a) (int-literal -1))");
+    }
+
+    #[test]
+    fn postfix_operators_stack_left_to_right() {
+        let parse = |source: &'static str| match parse_expression(&mut make_sut(source).lexer, false) {
+            Ok(expr) => to_sexpr(&expr),
+            Err(e) => panic!("{source}: {e:?}"),
+        };
+
+        let cases = [
+            ("foo()?.await", "(await (try (call (ident foo))))"),
+            ("foo().await?", "(try (await (call (ident foo))))"),
+            (
+                "a.b(c)[0]",
+                "(index (call (field-access (ident a) b) (ident c)) (uint-literal 0))",
+            ),
+            ("a[0].b?", "(try (field-access (index (ident a) (uint-literal 0)) b))"),
+            ("a[0][1]", "(index (index (ident a) (uint-literal 0)) (uint-literal 1))"),
+            ("f(a)(b)", "(call (call (ident f) (ident a)) (ident b))"),
+            ("x?.0.1", "(tuple-access (tuple-access (try (ident x)) 0) 1)"),
+            ("a.0?.b", "(field-access (try (tuple-access (ident a) 0)) b)"),
+            ("a??", "(try (try (ident a)))"),
+            (
+                "items[i + 1]?.await",
+                "(await (try (index (ident items) (binary-op + (ident i) (uint-literal 1)))))",
+            ),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(parse(source), expected, "{source}");
+        }
+    }
+
+    #[test]
+    fn postfix_operators_bind_tighter_than_prefix_and_infix_ones() {
+        let parse = |source: &'static str| match parse_expression(&mut make_sut(source).lexer, false) {
+            Ok(expr) => to_sexpr(&expr),
+            Err(e) => panic!("{source}: {e:?}"),
+        };
+
+        let cases = [
+            ("&a?", "(ref (try (ident a)))"),
+            ("*a.b[0]", "(deref (index (field-access (ident a) b) (uint-literal 0)))"),
+            ("&mut f()?", "(ref-mut (try (call (ident f))))"),
+            ("a + b?", "(binary-op + (ident a) (try (ident b)))"),
+            (
+                "a[0] * b.c",
+                "(binary-op * (index (ident a) (uint-literal 0)) (field-access (ident b) c))",
+            ),
+            (
+                "(a + b)[0]",
+                "(index (binary-op + (ident a) (ident b)) (uint-literal 0))",
+            ),
+            ("(&a)?", "(try (ref (ident a)))"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(parse(source), expected, "{source}");
+        }
+    }
+
+    #[test]
+    fn postfix_operators_span_their_whole_chain() {
+        let expr = parse_expression(&mut make_sut("foo()?.await").lexer, false).unwrap();
+        let Expression::Await { expr: inner, location } = &expr else {
+            panic!("expected an await, found {expr:?}");
+        };
+        assert_eq!(*location, Location::new(0, 12));
+        assert_eq!(inner.location(), Location::new(0, 6));
+
+        let expr = parse_expression(&mut make_sut("a[i]").lexer, false).unwrap();
+        assert_eq!(expr.location(), Location::new(0, 4));
+    }
+
+    #[test]
+    fn blocks_are_not_called_nor_indexed() {
+        // a statement ending in braces is over, and what follows starts the
+        // next one
+        for source in ["if c { a } else { b }\n[0]", "{ a }\n(b)", "match x { _ => 1 }\n[1]"] {
+            let mut parser = make_sut(source);
+            let expr = parse_expression(&mut parser.lexer, true).unwrap();
+            assert!(
+                !matches!(expr, Expression::Index { .. } | Expression::FunCall { .. }),
+                "{source}"
+            );
+            assert!(parse_expression(&mut parser.lexer, true).is_ok(), "{source}");
+        }
+
+        // but a `?`, or a dot, still applies to them
+        assert_eq!(
+            to_sexpr(&parse_expression(&mut make_sut("{ a }?").lexer, false).unwrap()),
+            "(try (block (tail (ident a))))"
+        );
+    }
 }
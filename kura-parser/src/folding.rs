@@ -0,0 +1,347 @@
+use kura_lexer::position::PositionIndex;
+use kura_lexer::token::{Kind, Location, Operator, Token};
+use kura_lexer::Lexer;
+
+use crate::{Expression, Statement};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum FoldKind {
+    /// a block, a body or an argument list
+    Region,
+    Comment,
+}
+
+/// a region an editor can collapse, shaped like the `FoldingRange` of the
+/// language server protocol. lines are zero based, and `end_line` is the
+/// line of the closing delimiter, which stays visible when folded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct FoldRange {
+    pub start_line: u32,
+    pub end_line: u32,
+    pub kind: FoldKind,
+}
+
+/// the foldable regions of `expr`, which is part of `source`: every block,
+/// which includes the branches of an `if`, every argument list spanning
+/// more than one line and every multi-line block comment.
+///
+/// ranges are sorted by their start, outer ones first. they never cross,
+/// though a range may end on the line the next one starts, as in `} else {`
+pub fn folding_ranges(expr: &Expression<'_>, source: &str) -> Vec<FoldRange> {
+    let mut folder = Folder::new(source);
+    folder.comments(expr.location());
+    folder.expression(expr);
+    folder.finish()
+}
+
+/// same as `folding_ranges`, for a whole file. function, struct and impl
+/// bodies fold too
+pub fn file_folding_ranges(statements: &[Statement<'_>], source: &str) -> Vec<FoldRange> {
+    let mut folder = Folder::new(source);
+    folder.comments(Location::new(0, source.len()));
+    statements.iter().for_each(|statement| folder.statement(statement));
+    folder.finish()
+}
+
+struct Folder<'src> {
+    index: PositionIndex,
+    /// every token of the source, comments included, used to find the
+    /// delimiters the tree doesn't keep the location of
+    tokens: Vec<Token<'src>>,
+    ranges: Vec<FoldRange>,
+}
+
+impl<'src> Folder<'src> {
+    fn new(source: &'src str) -> Self {
+        Self {
+            index: PositionIndex::new(source),
+            tokens: Lexer::new(source)
+                .preserve_comments(true)
+                .filter_map(Result::ok)
+                .collect(),
+            ranges: vec![],
+        }
+    }
+
+    fn finish(mut self) -> Vec<FoldRange> {
+        self.ranges
+            .sort_by(|a, b| a.start_line.cmp(&b.start_line).then(b.end_line.cmp(&a.end_line)));
+        self.ranges.dedup();
+        self.ranges
+    }
+
+    /// folds from the line of `start_byte` to the line of the last byte
+    /// before `end_byte`, if they aren't the same
+    fn fold(&mut self, start_byte: usize, end_byte: usize, kind: FoldKind) {
+        let (start_line, _) = self.index.line_col(start_byte);
+        let (end_line, _) = self.index.line_col(end_byte.saturating_sub(1));
+        if start_line < end_line {
+            self.ranges.push(FoldRange {
+                start_line,
+                end_line,
+                kind,
+            });
+        }
+    }
+
+    /// index of the first token at or after `start_byte` that is `kind`,
+    /// without going past `end_byte`
+    fn find(&self, start_byte: usize, end_byte: usize, kind: Kind<'_>) -> Option<usize> {
+        let idx = self
+            .tokens
+            .partition_point(|token| token.location.start_byte < start_byte);
+        self.tokens[idx..]
+            .iter()
+            .take_while(|token| token.location.start_byte < end_byte)
+            .position(|token| token.kind == kind)
+            .map(|position| idx + position)
+    }
+
+    /// index of the `)` closing the `(` at `open`
+    fn closing_paren(&self, open: usize) -> Option<usize> {
+        let mut depth = 0;
+        for (idx, token) in self.tokens.iter().enumerate().skip(open) {
+            match token.kind {
+                Kind::Op(Operator::LeftParen) => depth += 1,
+                Kind::Op(Operator::RightParen) if depth == 1 => return Some(idx),
+                Kind::Op(Operator::RightParen) => depth -= 1,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    fn comments(&mut self, within: Location) {
+        let comments = self
+            .tokens
+            .iter()
+            .filter(|token| matches!(token.kind, Kind::BlockComment(_)))
+            .filter(|token| {
+                token.location.start_byte >= within.start_byte && token.location.end_byte <= within.end_byte
+            })
+            .map(|token| token.location)
+            .collect::<Vec<_>>();
+        for location in comments {
+            self.fold(location.start_byte, location.end_byte, FoldKind::Comment);
+        }
+    }
+
+    /// folds the body of an item from its `{` to the end of the item
+    fn body(&mut self, location: Location) {
+        if let Some(brace) = self.find(location.start_byte, location.end_byte, Kind::Op(Operator::LeftBrace)) {
+            let start = self.tokens[brace].location.start_byte;
+            self.fold(start, location.end_byte, FoldKind::Region);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement<'_>) {
+        match statement {
+            Statement::Fun { body, location, .. } => {
+                self.body(*location);
+                body.iter().for_each(|expr| self.expression(expr));
+            }
+            Statement::Struct { location, .. } | Statement::MacroRules { location, .. } => self.body(*location),
+            Statement::Mod { .. } | Statement::ExternCrate { .. } | Statement::Use { .. } => {}
+            Statement::Static { value, .. } => self.expression(value),
+            Statement::Impl {
+                functions, location, ..
+            } => {
+                self.body(*location);
+                functions.iter().for_each(|function| self.statement(function));
+            }
+            Statement::FunArgument { .. } => {}
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression<'_>) {
+        match expr {
+            Expression::Block {
+                expressions,
+                tail,
+                location,
+                ..
+            } => {
+                self.fold(location.start_byte, location.end_byte, FoldKind::Region);
+                expressions
+                    .iter()
+                    .chain(tail.as_deref())
+                    .for_each(|expr| self.expression(expr));
+            }
+            Expression::Assert { condition, message, .. } => {
+                self.expression(condition);
+                message.iter().for_each(|message| self.expression(message));
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                self.expression(condition);
+                self.expression(truthy);
+                falsy.iter().for_each(|branch| self.expression(branch));
+            }
+            Expression::FunCall {
+                ident,
+                arguments,
+                location,
+                ..
+            } => {
+                self.expression(ident);
+                let paren = Kind::Op(Operator::LeftParen);
+                if let Some(open) = self.find(ident.location().end_byte, location.end_byte, paren) {
+                    if let Some(close) = self.closing_paren(open) {
+                        let (start, end) = (self.tokens[open].location, self.tokens[close].location);
+                        self.fold(start.start_byte, end.end_byte, FoldKind::Region);
+                    }
+                }
+                arguments.iter().for_each(|arg| self.expression(arg));
+            }
+            Expression::Match {
+                scrutinee,
+                arms,
+                location,
+            } => {
+                self.expression(scrutinee);
+                let brace = Kind::Op(Operator::LeftBrace);
+                if let Some(open) = self.find(scrutinee.location().end_byte, location.end_byte, brace) {
+                    self.fold(
+                        self.tokens[open].location.start_byte,
+                        location.end_byte,
+                        FoldKind::Region,
+                    );
+                }
+                arms.iter().for_each(|arm| self.expression(&arm.body));
+            }
+            Expression::Yield { value, .. } => value.iter().for_each(|value| self.expression(value)),
+            Expression::EnumVariant { kind, .. } => kind.values().for_each(|value| self.expression(value)),
+            Expression::DoBlock { steps, .. } => steps.iter().for_each(|step| self.expression(step.expr())),
+            Expression::Var { value, .. }
+            | Expression::Assign { value, .. }
+            | Expression::CompoundAssign { value, .. }
+            | Expression::Return { value, .. }
+            | Expression::Await { expr: value, .. }
+            | Expression::RuntimeTypeOf { expr: value, .. }
+            | Expression::SafeCast { expr: value, .. }
+            | Expression::ArrayLen { array: value, .. }
+            | Expression::Splat { expr: value, .. }
+            | Expression::UnsafeBlock { body: value, .. }
+            | Expression::ComptimeBlock { body: value, .. }
+            | Expression::Defer { body: value, .. }
+            | Expression::Ref { expr: value, .. }
+            | Expression::Deref { expr: value, .. }
+            | Expression::Not { expr: value, .. }
+            | Expression::FieldAccess { target: value, .. }
+            | Expression::TupleAccess { tuple: value, .. }
+            | Expression::Try { expr: value, .. }
+            | Expression::Lambda { body: value, .. } => self.expression(value),
+            Expression::BinaryOp { lhs, rhs, .. }
+            | Expression::Index {
+                target: lhs,
+                index: rhs,
+                ..
+            }
+            | Expression::Elvis {
+                condition: lhs,
+                default: rhs,
+                ..
+            }
+            | Expression::Let {
+                value: lhs, body: rhs, ..
+            } => {
+                self.expression(lhs);
+                self.expression(rhs);
+            }
+            Expression::InlineAsm { outputs, inputs, .. } => outputs
+                .iter()
+                .chain(inputs.iter())
+                .for_each(|operand| self.expression(&operand.expr)),
+            Expression::Interpolated { expressions, .. }
+            | Expression::Array {
+                elements: expressions, ..
+            }
+            | Expression::Tuple {
+                elements: expressions, ..
+            } => {
+                expressions.iter().for_each(|expr| self.expression(expr));
+            }
+            Expression::Bool { .. }
+            | Expression::Sizeof { .. }
+            | Expression::Macro { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::Ident { .. }
+            | Expression::Path { .. }
+            | Expression::UintLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::IntLiteral { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parser;
+
+    fn lines(ranges: &[FoldRange]) -> Vec<(u32, u32, FoldKind)> {
+        ranges
+            .iter()
+            .map(|range| (range.start_line, range.end_line, range.kind))
+            .collect()
+    }
+
+    #[test]
+    fn nested_regions_fold_to_their_closing_line() {
+        let source = r#"{
+    var a = {
+        1
+    };
+    /* a comment
+       over two lines */
+    if a == 1 {
+        call(a, {
+            2
+        });
+    } else if a == 2 { 3 } else {
+        run(
+            a,
+            b,
+        ) { 4 };
+    }
+    { /* one line */ }
+}"#;
+
+        let mut lexer = Lexer::new(source);
+        let expr = crate::expression::parse_expression(&mut lexer, true).unwrap();
+
+        use FoldKind::*;
+        assert_eq!(
+            lines(&folding_ranges(&expr, source)),
+            [
+                (0, 17, Region),
+                (1, 3, Region),
+                (4, 5, Comment),
+                (6, 10, Region),
+                (7, 9, Region),
+                (10, 15, Region),
+                (11, 14, Region),
+            ]
+        );
+    }
+
+    #[test]
+    fn item_bodies_fold() {
+        let source = "#[derive(Debug)]\nstruct Point {\n    x: i32,\n}\n\
+                      impl Point {\n    fun zero() => Point {\n        return make(\n            0);\n    }\n}\n\
+                      fun main() {}\n";
+        let statements = Parser::new(source, Lexer::new(source)).parse().unwrap();
+
+        use FoldKind::*;
+        assert_eq!(
+            lines(&file_folding_ranges(&statements, source)),
+            [(1, 3, Region), (4, 9, Region), (5, 8, Region), (6, 7, Region)]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt;
+use std::panic::{catch_unwind, UnwindSafe};
+
+use crate::Statement;
+
+/// builds the closure running a test function, given its declaration. how
+/// the function gets to run, be it interpreted or compiled, is up to the
+/// runner; the harness only calls the closure and looks at how it ended
+pub trait TestRunner<'ast> {
+    type Test: FnOnce() -> Result<(), String> + UnwindSafe;
+
+    fn prepare(&mut self, function: &Statement<'ast>) -> Self::Test;
+}
+
+impl<'ast, F, T> TestRunner<'ast> for F
+where
+    F: FnMut(&Statement<'ast>) -> T,
+    T: FnOnce() -> Result<(), String> + UnwindSafe,
+{
+    type Test = T;
+
+    fn prepare(&mut self, function: &Statement<'ast>) -> T {
+        self(function)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Passed,
+    /// the test returned an error, or didn't panic like its
+    /// `#[should_panic]` said it would
+    Failed(String),
+    /// the test panicked without being marked `#[should_panic]`
+    Panicked(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    pub outcome: Outcome,
+}
+
+/// how every test of a harness ended, in the order they were declared
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TestReport {
+    pub results: Vec<TestResult>,
+}
+
+impl TestReport {
+    pub fn passed(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.outcome == Outcome::Passed)
+            .count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed() == 0
+    }
+}
+
+impl fmt::Display for TestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for TestResult { name, outcome } in &self.results {
+            match outcome {
+                Outcome::Passed => writeln!(f, "test {name} ... ok")?,
+                Outcome::Failed(message) => writeln!(f, "test {name} ... FAILED: {message}")?,
+                Outcome::Panicked(message) => writeln!(f, "test {name} ... panicked: {message}")?,
+            }
+        }
+        write!(f, "test result: {} passed; {} failed", self.passed(), self.failed())
+    }
+}
+
+/// runs test functions, like the ones from `collect_test_functions`, one
+/// after the other. a test passes when it returns `Ok`, or when it panics
+/// and is marked `#[should_panic]`, with the panic message containing its
+/// `expected` value if it has one
+pub struct TestHarness<'a, 'ast> {
+    tests: Vec<&'a Statement<'ast>>,
+}
+
+impl<'a, 'ast> TestHarness<'a, 'ast> {
+    pub fn new(tests: Vec<&'a Statement<'ast>>) -> Self {
+        Self { tests }
+    }
+
+    /// runs every test through the closure `runner` prepares for it.
+    /// statements that aren't functions are skipped
+    pub fn run(&self, mut runner: impl TestRunner<'ast>) -> TestReport {
+        let results = self
+            .tests
+            .iter()
+            .filter_map(|function| {
+                let Statement::Fun { name, attributes, .. } = function else {
+                    return None;
+                };
+                let should_panic = attributes.iter().find(|attribute| attribute.name == "should_panic");
+                let test = runner.prepare(function);
+
+                let outcome = match (catch_unwind(test), should_panic) {
+                    (Ok(Ok(())), None) => Outcome::Passed,
+                    (Ok(Ok(())), Some(_)) => Outcome::Failed("expected a panic".into()),
+                    (Ok(Err(message)), _) => Outcome::Failed(message),
+                    (Err(payload), None) => Outcome::Panicked(panic_message(payload.as_ref())),
+                    (Err(payload), Some(attribute)) => {
+                        let message = panic_message(payload.as_ref());
+                        match attribute.value("expected") {
+                            Some(expected) if !message.contains(expected) => Outcome::Failed(format!(
+                                "panicked with `{message}`, which doesn't contain `{expected}`"
+                            )),
+                            _ => Outcome::Passed,
+                        }
+                    }
+                };
+                Some(TestResult {
+                    name: name.to_string(),
+                    outcome,
+                })
+            })
+            .collect();
+
+        TestReport { results }
+    }
+}
+
+/// the message given to `panic!`, which is a `&str` or a `String` unless
+/// the panic was raised with some other value
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    match payload.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => payload
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "the panic has no message".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::{collect_test_functions, Parser};
+
+    #[test]
+    fn tests_pass_fail_and_panic() {
+        let source = r#"
+            #[test] fun passes() {}
+            #[test] fun fails() {}
+            #[test] fun panics() {}
+            #[test] #[should_panic] fun should_panic() {}
+            #[test] #[should_panic(expected = "overflow")] fun overflows() {}
+            #[test] #[should_panic(expected = "overflow")] fun underflows() {}
+            #[test] #[should_panic] fun doesnt_panic() {}
+            fun helper() {}
+        "#;
+        let statements = Parser::new(source, Lexer::new(source)).parse().unwrap();
+        let harness = TestHarness::new(collect_test_functions(&statements));
+
+        let report = harness.run(|function: &Statement<'_>| {
+            let Statement::Fun { name, .. } = function else { unreachable!() };
+            let name = name.to_string();
+            move || match name.as_str() {
+                "passes" | "doesnt_panic" => Ok(()),
+                "fails" => Err("1 != 2".to_string()),
+                "overflows" => panic!("attempt to add with overflow"),
+                "underflows" => panic!("attempt to subtract with underflow"),
+                _ => panic!("{name} panicked"),
+            }
+        });
+
+        let outcomes = report
+            .results
+            .iter()
+            .map(|result| (result.name.as_str(), result.outcome.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            [
+                ("passes", Outcome::Passed),
+                ("fails", Outcome::Failed("1 != 2".into())),
+                ("panics", Outcome::Panicked("panics panicked".into())),
+                ("should_panic", Outcome::Passed),
+                ("overflows", Outcome::Passed),
+                (
+                    "underflows",
+                    Outcome::Failed(
+                        "panicked with `attempt to subtract with underflow`, which doesn't contain `overflow`".into()
+                    )
+                ),
+                ("doesnt_panic", Outcome::Failed("expected a panic".into())),
+            ]
+        );
+        assert!(!report.is_success());
+        assert!(report
+            .to_string()
+            .ends_with("test doesnt_panic ... FAILED: expected a panic\ntest result: 3 passed; 4 failed"));
+    }
+}
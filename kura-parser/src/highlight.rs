@@ -0,0 +1,915 @@
+use std::collections::HashSet;
+
+use kura_lexer::token::{Kind, Location, Value};
+use kura_lexer::Lexer;
+
+use crate::resolve::SymbolTable;
+use crate::{DoStep, Expression, GenericParam, Parser, Pattern, Statement, TypeExpr, UseTree, VariantKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SemanticKind {
+    Keyword,
+    VariableDeclaration,
+    VariableUse,
+    FunctionName,
+    Parameter,
+    Property,
+    Type,
+    Namespace,
+    Literal,
+    Operator,
+    Comment,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct SemanticToken {
+    pub range: Location,
+    pub kind: SemanticKind,
+}
+
+/// classifies every token of `source` for highlighting. tokens are first
+/// classified by the lexer alone, then identifiers are upgraded using the
+/// syntax tree of every function that parsed successfully, so whatever comes
+/// after a syntax error keeps its lexical classification.
+///
+/// tokens are sorted and never overlap. they cover every byte but
+/// whitespace, except for the parts the lexer can't make sense of, like an
+/// unknown character or an unterminated string, which are skipped
+pub fn semantic_tokens(source: &str) -> Vec<SemanticToken> {
+    let mut parser = Parser::new(source, Lexer::new(source));
+    let mut statements = vec![];
+    while !parser.lexer.is_empty() {
+        match parser.parse_statement() {
+            Ok(statement) => statements.push(statement),
+            Err(_) => break,
+        }
+    }
+
+    let mut classifier = Classifier {
+        tokens: lex(source),
+        parameters: HashSet::new(),
+        symbols: SymbolTable::resolve_statements(&statements),
+    };
+    statements.iter().for_each(|statement| classifier.statement(statement));
+
+    classifier.tokens
+}
+
+fn lex(source: &str) -> Vec<SemanticToken> {
+    let mut tokens = vec![];
+    let mut lexer = Lexer::new(source).preserve_comments(true);
+
+    loop {
+        // where lexing picks up again after something that isn't a token
+        let skipped = match lexer.next() {
+            None => break,
+            Some(Ok(token)) => {
+                tokens.push(SemanticToken {
+                    range: token.location,
+                    kind: lexical_kind(&token.kind),
+                });
+                continue;
+            }
+            Some(Err(error)) => error.location(),
+        };
+
+        let next_char = source[skipped.start_byte..].chars().next().map_or(1, char::len_utf8);
+        let resume = skipped.end_byte.max(skipped.start_byte + next_char);
+        if resume >= source.len() {
+            break;
+        }
+        lexer = lexer.sub_lexer(resume..source.len()).preserve_comments(true);
+    }
+
+    tokens
+}
+
+fn lexical_kind(kind: &Kind<'_>) -> SemanticKind {
+    match kind {
+        Kind::Value(Value::Ident(name)) => match name.as_ref() {
+            "await" => SemanticKind::Keyword,
+            _ => SemanticKind::VariableUse,
+        },
+        Kind::Value(_) => SemanticKind::Literal,
+        Kind::Op(_) => SemanticKind::Operator,
+        Kind::LineComment(_) | Kind::BlockComment(_) | Kind::Shebang(_) => SemanticKind::Comment,
+        Kind::Lifetime(_) => SemanticKind::Type,
+        Kind::Var
+        | Kind::Const
+        | Kind::Match
+        | Kind::If
+        | Kind::Else
+        | Kind::Fun
+        | Kind::Struct
+        | Kind::Enum
+        | Kind::Impl
+        | Kind::For
+        | Kind::Let
+        | Kind::In
+        | Kind::Unsafe
+        | Kind::Assert
+        | Kind::Mod
+        | Kind::Extern
+        | Kind::Crate
+        | Kind::Use
+        | Kind::SelfType
+        | Kind::Return
+        | Kind::Yield
+        | Kind::Comptime
+        | Kind::Do
+        | Kind::Defer
+        | Kind::Where
+        | Kind::TryAs
+        | Kind::Eof => SemanticKind::Keyword,
+    }
+}
+
+struct Classifier<'ast> {
+    tokens: Vec<SemanticToken>,
+    /// where every function argument is declared, so that uses of them can
+    /// be told apart from uses of variables
+    parameters: HashSet<usize>,
+    symbols: SymbolTable<'ast>,
+}
+
+impl Classifier<'_> {
+    /// reclassifies the identifier starting at `start_byte`
+    fn classify(&mut self, start_byte: usize, kind: SemanticKind) {
+        if let Ok(idx) = self
+            .tokens
+            .binary_search_by_key(&start_byte, |token| token.range.start_byte)
+        {
+            if self.tokens[idx].kind == SemanticKind::VariableUse {
+                self.tokens[idx].kind = kind;
+            }
+        }
+    }
+
+    /// reclassifies the first identifier after `start_byte`, which is how
+    /// names are found after the `fun`, `var` and `const` keywords
+    fn classify_name_after(&mut self, start_byte: usize, kind: SemanticKind) {
+        let idx = self
+            .tokens
+            .partition_point(|token| token.range.start_byte <= start_byte);
+        let name = self.tokens[idx..]
+            .iter()
+            .find(|token| token.kind != SemanticKind::Comment)
+            .map(|token| token.range.start_byte);
+
+        if let Some(name) = name {
+            self.classify(name, kind);
+        }
+    }
+
+    /// classifies every segment of the path at `location` as a module,
+    /// except for the last one, which is the item it names
+    fn classify_path(&mut self, location: Location, kind: SemanticKind) {
+        let first = self
+            .tokens
+            .partition_point(|token| token.range.start_byte < location.start_byte);
+        let last = self
+            .tokens
+            .partition_point(|token| token.range.end_byte <= location.end_byte);
+
+        let segments = self.tokens[first..last]
+            .iter()
+            .filter(|token| token.kind == SemanticKind::VariableUse)
+            .map(|token| token.range.start_byte)
+            .collect::<Vec<_>>();
+
+        if let Some((item, modules)) = segments.split_last() {
+            modules
+                .iter()
+                .for_each(|module| self.classify(*module, SemanticKind::Namespace));
+            self.classify(*item, kind);
+        }
+    }
+
+    /// classifies the path of the enum variant at `location`, which spans the
+    /// first `segments` names and the `::` between them, along with the
+    /// names given to its values
+    fn classify_variant<T>(&mut self, segments: usize, kind: &VariantKind<'_, T>, location: Location) {
+        let first = self
+            .tokens
+            .partition_point(|token| token.range.start_byte < location.start_byte);
+        if let Some(last) = self.tokens.get(first + segments * 2 - 2) {
+            let path = Location {
+                end_byte: last.range.end_byte,
+                ..location
+            };
+            self.classify_path(path, SemanticKind::VariableUse);
+        }
+
+        if let VariantKind::Struct(fields) = kind {
+            fields
+                .iter()
+                .for_each(|field| self.classify(field.location.start_byte, SemanticKind::Property));
+        }
+    }
+
+    /// classifies generic parameters and the traits bounding them as types
+    fn classify_generics(&mut self, generics: &[GenericParam<'_>]) {
+        for param in generics {
+            self.classify(param.location.start_byte, SemanticKind::Type);
+            param.bounds.iter().for_each(|bound| self.classify_type(bound));
+        }
+    }
+
+    /// classifies every name in a type, along with the `mut` of a pointer or
+    /// of a reference
+    fn classify_type(&mut self, typ: &TypeExpr<'_>) {
+        match typ {
+            TypeExpr::Named { location, .. } => self.classify_path(*location, SemanticKind::Type),
+            TypeExpr::Fn {
+                parameters, returns, ..
+            } => parameters
+                .iter()
+                .chain(returns.as_deref())
+                .for_each(|typ| self.classify_type(typ)),
+            TypeExpr::Array { element, .. } => self.classify_type(element),
+            TypeExpr::Tuple { elements, .. } => elements.iter().for_each(|typ| self.classify_type(typ)),
+            TypeExpr::Ptr {
+                mutable,
+                pointee,
+                location,
+            } => {
+                if *mutable {
+                    self.classify_name_after(location.start_byte, SemanticKind::Keyword);
+                }
+                self.classify_type(pointee);
+            }
+            TypeExpr::Generic {
+                arguments, location, ..
+            } => {
+                // once the arguments are classified, the only names left are
+                // those of the path of the type itself
+                arguments.iter().for_each(|typ| self.classify_type(typ));
+                self.classify_path(*location, SemanticKind::Type);
+            }
+            TypeExpr::ImplTrait { bound, .. } => self.classify_type(bound),
+            TypeExpr::Ref { mutable, referent, .. } => {
+                // the `mut` of a reference comes after its lifetime, if it
+                // has one, so it is found from the referent backwards
+                if *mutable {
+                    let idx = self
+                        .tokens
+                        .partition_point(|token| token.range.start_byte < referent.location().start_byte);
+                    let name = self.tokens[..idx]
+                        .iter()
+                        .rev()
+                        .find(|token| token.kind != SemanticKind::Comment)
+                        .map(|token| token.range.start_byte);
+                    if let Some(name) = name {
+                        self.classify(name, SemanticKind::Keyword);
+                    }
+                }
+                self.classify_type(referent);
+            }
+            TypeExpr::FnTrait {
+                parameters,
+                returns,
+                location,
+                ..
+            } => {
+                parameters
+                    .iter()
+                    .chain(returns.as_deref())
+                    .for_each(|typ| self.classify_type(typ));
+                self.classify_path(*location, SemanticKind::Type);
+            }
+            TypeExpr::Lifetime { .. } | TypeExpr::Never { .. } => {}
+        }
+    }
+
+    fn classify_use(&mut self, location: Location) {
+        let is_parameter = self
+            .symbols
+            .resolution(location)
+            .is_some_and(|id| self.parameters.contains(&self.symbols.binding(id).location.start_byte));
+
+        if is_parameter {
+            self.classify(location.start_byte, SemanticKind::Parameter);
+        }
+    }
+
+    fn statement(&mut self, statement: &Statement<'_>) {
+        match statement {
+            Statement::FunArgument { arg_type, location, .. } => {
+                self.parameters.insert(location.start_byte);
+                self.classify(location.start_byte, SemanticKind::Parameter);
+                self.classify_type(arg_type);
+            }
+            Statement::Fun {
+                generics,
+                arguments,
+                body,
+                return_type,
+                where_clause,
+                location,
+                ..
+            } => {
+                self.classify_name_after(location.start_byte, SemanticKind::FunctionName);
+                self.classify_generics(generics);
+                arguments.iter().for_each(|argument| self.statement(argument));
+                if let Some(return_type) = return_type {
+                    self.classify_type(return_type);
+                }
+                for predicate in where_clause {
+                    self.classify_type(&predicate.bounded);
+                    predicate.bounds.iter().for_each(|bound| self.classify_type(bound));
+                }
+                body.iter().for_each(|expr| self.expression(expr));
+            }
+            Statement::Struct {
+                generics,
+                fields,
+                location,
+                ..
+            } => {
+                self.classify_name_after(location.start_byte, SemanticKind::Type);
+                self.classify_generics(generics);
+                for field in fields {
+                    if let Statement::FunArgument { arg_type, location, .. } = field {
+                        self.classify(location.start_byte, SemanticKind::Property);
+                        self.classify_type(arg_type);
+                    }
+                }
+            }
+            Statement::Impl {
+                generics,
+                arguments,
+                functions,
+                location,
+                ..
+            } => {
+                // the trait and the type it is for are every name past the
+                // generic parameters, up to the brace or the arguments
+                let start = generics
+                    .last()
+                    .map_or(location.start_byte + 1, |param| param.location.end_byte);
+                let names = self
+                    .tokens
+                    .iter()
+                    .skip_while(|token| token.range.start_byte < start || token.kind == SemanticKind::Operator)
+                    .take_while(|token| token.kind != SemanticKind::Operator)
+                    .map(|token| token.range.start_byte)
+                    .collect::<Vec<_>>();
+                names
+                    .into_iter()
+                    .for_each(|name| self.classify(name, SemanticKind::Type));
+                self.classify_generics(generics);
+                arguments.iter().for_each(|argument| self.classify_type(argument));
+                functions.iter().for_each(|function| self.statement(function));
+            }
+            Statement::Mod { location, .. } => self.classify_name_after(location.start_byte, SemanticKind::Namespace),
+            Statement::Static {
+                typ,
+                value,
+                attributes,
+                location,
+                ..
+            } => {
+                // the name comes right after the `var` or `const` keyword,
+                // the first token past the attributes
+                let start = attributes
+                    .last()
+                    .map_or(location.start_byte, |attribute| attribute.location.end_byte);
+                let keyword = self.tokens.iter().find(|token| token.range.start_byte >= start);
+                if let Some(keyword) = keyword.map(|token| token.range.start_byte) {
+                    self.classify_name_after(keyword, SemanticKind::VariableDeclaration);
+                }
+                if let Some(typ) = typ {
+                    self.classify_type(typ);
+                }
+                self.expression(value);
+            }
+            Statement::ExternCrate {
+                attributes, location, ..
+            } => {
+                // past the attributes, the only identifiers are the name of
+                // the crate and, when it is renamed, `as` and the alias
+                let start = attributes
+                    .last()
+                    .map_or(location.start_byte, |attribute| attribute.location.end_byte);
+                let names = self
+                    .tokens
+                    .iter()
+                    .filter(|token| token.range.start_byte >= start && token.range.end_byte <= location.end_byte)
+                    .filter(|token| token.kind == SemanticKind::VariableUse)
+                    .map(|token| token.range.start_byte)
+                    .collect::<Vec<_>>();
+                for (idx, name) in names.into_iter().enumerate() {
+                    let kind = match idx {
+                        1 => SemanticKind::Keyword,
+                        _ => SemanticKind::Namespace,
+                    };
+                    self.classify(name, kind);
+                }
+            }
+            Statement::Use {
+                tree,
+                attributes,
+                location,
+            } => {
+                // names come in the order the tree is written in past the
+                // attributes, and every one followed by `::` is a module
+                let mut modules = vec![];
+                use_tree_names(tree, &mut modules);
+                let start = attributes
+                    .last()
+                    .map_or(location.start_byte, |attribute| attribute.location.end_byte);
+                let names = self
+                    .tokens
+                    .iter()
+                    .filter(|token| token.range.start_byte >= start && token.range.end_byte <= location.end_byte)
+                    .filter(|token| token.kind == SemanticKind::VariableUse)
+                    .map(|token| token.range.start_byte)
+                    .zip(modules)
+                    .filter(|(_, module)| *module)
+                    .collect::<Vec<_>>();
+                names
+                    .into_iter()
+                    .for_each(|(name, _)| self.classify(name, SemanticKind::Namespace));
+            }
+            Statement::MacroRules { location, .. } => {
+                self.classify(location.start_byte, SemanticKind::Keyword);
+                // the name comes after the `!` of `macro_rules!`
+                let bang = self
+                    .tokens
+                    .iter()
+                    .find(|token| token.range.start_byte > location.start_byte && token.kind == SemanticKind::Operator)
+                    .map(|token| token.range.start_byte);
+                if let Some(bang) = bang {
+                    self.classify_name_after(bang, SemanticKind::FunctionName);
+                }
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern<'_>) {
+        match pattern {
+            Pattern::Binding { location, .. } => self.classify(location.start_byte, SemanticKind::VariableDeclaration),
+            Pattern::Path { location, .. } => self.classify_path(*location, SemanticKind::VariableUse),
+            Pattern::Variant { path, kind, location } => {
+                self.classify_variant(path.len(), kind, *location);
+                kind.values().for_each(|value| self.pattern(value));
+            }
+            Pattern::Literal(literal) => self.expression(literal),
+            Pattern::Wildcard { .. } => {}
+        }
+    }
+
+    fn expression(&mut self, expr: &Expression<'_>) {
+        match expr {
+            Expression::Var {
+                typ, value, location, ..
+            } => {
+                self.classify_name_after(location.start_byte, SemanticKind::VariableDeclaration);
+                if let Some(typ) = typ {
+                    self.classify_type(typ);
+                }
+                self.expression(value);
+            }
+            Expression::Ident { location, .. } => self.classify_use(*location),
+            // every segment but the last is a module
+            Expression::Path { location, .. } => self.classify_path(*location, SemanticKind::VariableUse),
+            Expression::FunCall { ident, arguments, .. } => {
+                match ident.as_ref() {
+                    Expression::Ident { location, .. } => {
+                        self.classify(location.start_byte, SemanticKind::FunctionName)
+                    }
+                    Expression::Path { location, .. } => self.classify_path(*location, SemanticKind::FunctionName),
+                    callee => self.expression(callee),
+                }
+                arguments.iter().for_each(|argument| self.expression(argument));
+            }
+            Expression::Macro { tokens, location, .. } => {
+                self.classify(location.start_byte, SemanticKind::FunctionName);
+                tokens
+                    .iter()
+                    .filter(|token| matches!(token.kind, Kind::Value(Value::Ident(_))))
+                    .for_each(|token| self.classify_use(token.location));
+            }
+            Expression::InlineAsm {
+                outputs,
+                inputs,
+                location,
+                ..
+            } => {
+                self.classify(location.start_byte, SemanticKind::FunctionName);
+                outputs
+                    .iter()
+                    .chain(inputs)
+                    .for_each(|operand| self.expression(&operand.expr));
+            }
+            Expression::RuntimeTypeOf { expr, location }
+            | Expression::ArrayLen { array: expr, location }
+            | Expression::Splat { expr, location } => {
+                self.classify(location.start_byte, SemanticKind::FunctionName);
+                self.expression(expr);
+            }
+            Expression::SafeCast { expr, typ, .. } => {
+                self.expression(expr);
+                self.classify_type(typ);
+            }
+            Expression::Sizeof { typ, location } => {
+                self.classify(location.start_byte, SemanticKind::FunctionName);
+                self.classify_type(typ);
+            }
+            Expression::Array { elements, .. } | Expression::Tuple { elements, .. } => {
+                elements.iter().for_each(|element| self.expression(element))
+            }
+            Expression::TupleAccess { tuple, .. } | Expression::Try { expr: tuple, .. } => self.expression(tuple),
+            Expression::Assert { condition, message, .. } => {
+                self.expression(condition);
+                message.iter().for_each(|message| self.expression(message));
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                self.expression(condition);
+                self.expression(truthy);
+                falsy.iter().for_each(|branch| self.expression(branch));
+            }
+            Expression::Assign { ident, value, .. } | Expression::CompoundAssign { ident, value, .. } => {
+                self.expression(ident);
+                self.expression(value);
+            }
+            Expression::Block { expressions, tail, .. } => expressions
+                .iter()
+                .chain(tail.as_deref())
+                .for_each(|expr| self.expression(expr)),
+            Expression::BinaryOp { lhs, rhs, .. }
+            | Expression::Index {
+                target: lhs,
+                index: rhs,
+                ..
+            }
+            | Expression::Elvis {
+                condition: lhs,
+                default: rhs,
+                ..
+            } => {
+                self.expression(lhs);
+                self.expression(rhs);
+            }
+            Expression::Yield { value, .. } => value.iter().for_each(|value| self.expression(value)),
+            Expression::Return { value, .. }
+            | Expression::Await { expr: value, .. }
+            | Expression::UnsafeBlock { body: value, .. }
+            | Expression::ComptimeBlock { body: value, .. }
+            | Expression::Defer { body: value, .. }
+            | Expression::Deref { expr: value, .. }
+            | Expression::Not { expr: value, .. } => self.expression(value),
+            Expression::Ref {
+                mutable,
+                expr,
+                location,
+            } => {
+                if *mutable {
+                    self.classify_name_after(location.start_byte, SemanticKind::Keyword);
+                }
+                self.expression(expr);
+            }
+            Expression::FieldAccess {
+                target,
+                field,
+                location,
+            } => {
+                self.expression(target);
+                self.classify(location.end_byte - field.len(), SemanticKind::Property);
+            }
+            Expression::Lambda {
+                parameters,
+                parameter_types,
+                return_type,
+                body,
+                ..
+            } => {
+                for (parameter, typ) in parameters.iter().zip(parameter_types) {
+                    let location = parameter.location();
+                    self.parameters.insert(location.start_byte);
+                    self.classify(location.start_byte, SemanticKind::Parameter);
+                    if let Some(typ) = typ {
+                        self.classify_type(typ);
+                    }
+                }
+                if let Some(return_type) = return_type {
+                    self.classify_type(return_type);
+                }
+                self.expression(body);
+            }
+            Expression::Let {
+                value, body, location, ..
+            } => {
+                self.classify_name_after(location.start_byte, SemanticKind::VariableDeclaration);
+                self.expression(value);
+                self.expression(body);
+            }
+            Expression::Match { scrutinee, arms, .. } => {
+                self.expression(scrutinee);
+                for arm in arms {
+                    self.pattern(&arm.pattern);
+                    self.expression(&arm.body);
+                }
+            }
+            Expression::EnumVariant { path, kind, location } => {
+                self.classify_variant(path.len(), kind, *location);
+                kind.values().for_each(|value| self.expression(value));
+            }
+            Expression::DoBlock { steps, .. } => {
+                for step in steps {
+                    if let DoStep::Bind { location, .. } = step {
+                        self.classify(location.start_byte, SemanticKind::VariableDeclaration);
+                    }
+                    self.expression(step.expr());
+                }
+            }
+            // the whole string is a single literal token, what is embedded in
+            // it is highlighted as part of the string
+            Expression::Interpolated { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::Bool { .. }
+            | Expression::UintLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::IntLiteral { .. } => {}
+        }
+    }
+}
+
+/// whether each name in `tree` is a module, in the order they are written
+fn use_tree_names(tree: &UseTree<'_>, modules: &mut Vec<bool>) {
+    match tree {
+        UseTree::Leaf(_) => modules.push(false),
+        UseTree::Path { child, .. } => {
+            modules.push(true);
+            use_tree_names(child, modules);
+        }
+        UseTree::Group(trees) => trees.iter().for_each(|tree| use_tree_names(tree, modules)),
+        UseTree::Glob => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(source: &str) -> String {
+        semantic_tokens(source)
+            .iter()
+            .map(|token| {
+                let text = &source[token.range.start_byte..token.range.end_byte];
+                format!("{text:<20} {:?}\n", token.kind)
+            })
+            .collect()
+    }
+
+    fn assert_sorted_and_disjoint(source: &str) {
+        let tokens = semantic_tokens(source);
+        for pair in tokens.windows(2) {
+            assert!(pair[0].range.end_byte <= pair[1].range.start_byte, "{pair:?}");
+        }
+    }
+
+    #[test]
+    fn every_construct_is_classified() {
+        let source = r#"
+            // adds things up
+            fun add(a: i32, b: i32) => i32 {
+                var total: i32 = a + b * 2;
+                /* reassigned */
+                total = total - 1;
+                const ok = true;
+                if ok == false {
+                    print("{total} is {a}");
+                } else {
+                    log!("{}", total);
+                }
+                const f = 1.5f32;
+                const n = fetch(b).await;
+                return { total };
+            }
+        "#;
+
+        assert_sorted_and_disjoint(source);
+        insta::assert_snapshot!(render(source));
+    }
+
+    #[test]
+    fn code_after_a_syntax_error_is_classified_lexically() {
+        let source = "fun ok(a: i32) { return a; } fun broken(b: i32) { const c = ; b }";
+
+        assert_sorted_and_disjoint(source);
+        insta::assert_snapshot!(render(source));
+    }
+
+    #[test]
+    fn unknown_regions_are_skipped() {
+        let source = "fun main() { const a = 1 @ 2; \"unterminated }";
+        let covered = semantic_tokens(source)
+            .iter()
+            .map(|token| &source[token.range.start_byte..token.range.end_byte])
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            covered,
+            ["fun", "main", "(", ")", "{", "const", "a", "=", "1", "2", ";"]
+        );
+        assert_sorted_and_disjoint(source);
+    }
+
+    #[test]
+    fn path_segments_are_namespaces() {
+        let source = "fun main() { std::io::write(math::PI); }";
+        let kinds = semantic_tokens(source)
+            .iter()
+            .filter(|token| token.kind != SemanticKind::Operator)
+            .map(|token| (&source[token.range.start_byte..token.range.end_byte], token.kind))
+            .collect::<Vec<_>>();
+
+        use SemanticKind::*;
+        assert_eq!(
+            kinds,
+            [
+                ("fun", Keyword),
+                ("main", FunctionName),
+                ("std", Namespace),
+                ("io", Namespace),
+                ("write", FunctionName),
+                ("math", Namespace),
+                ("PI", VariableUse),
+            ]
+        );
+    }
+
+    #[test]
+    fn enum_variants_name_their_fields() {
+        let source = "fun main() { const a = Shape::Rect { w: w, h: 1 }; match a { Shape::Circle(r) => r } }";
+        let kinds = semantic_tokens(source)
+            .iter()
+            .filter(|token| !matches!(token.kind, SemanticKind::Operator | SemanticKind::Keyword))
+            .map(|token| (&source[token.range.start_byte..token.range.end_byte], token.kind))
+            .collect::<Vec<_>>();
+
+        use SemanticKind::*;
+        assert_eq!(
+            kinds,
+            [
+                ("main", FunctionName),
+                ("a", VariableDeclaration),
+                ("Shape", Namespace),
+                ("Rect", VariableUse),
+                ("w", Property),
+                ("w", VariableUse),
+                ("h", Property),
+                ("1", Literal),
+                ("a", VariableUse),
+                ("Shape", Namespace),
+                ("Circle", VariableUse),
+                ("r", VariableDeclaration),
+                ("r", VariableUse),
+            ]
+        );
+    }
+
+    #[test]
+    fn use_paths_are_namespaces() {
+        let source = "use std::{io::*, fmt::Display, mem};";
+        let kinds = semantic_tokens(source)
+            .iter()
+            .filter(|token| token.kind != SemanticKind::Operator)
+            .map(|token| (&source[token.range.start_byte..token.range.end_byte], token.kind))
+            .collect::<Vec<_>>();
+
+        use SemanticKind::*;
+        assert_eq!(
+            kinds,
+            [
+                ("use", Keyword),
+                ("std", Namespace),
+                ("io", Namespace),
+                ("fmt", Namespace),
+                ("Display", VariableUse),
+                ("mem", VariableUse),
+            ]
+        );
+    }
+
+    #[test]
+    fn extern_crates_are_namespaces() {
+        let source = "#[macro_use] extern crate log as logging; extern crate serde;";
+        let kinds = semantic_tokens(source)
+            .iter()
+            .filter(|token| token.kind != SemanticKind::Operator)
+            .map(|token| (&source[token.range.start_byte..token.range.end_byte], token.kind))
+            .collect::<Vec<_>>();
+
+        use SemanticKind::*;
+        assert_eq!(
+            kinds,
+            [
+                ("macro_use", VariableUse),
+                ("extern", Keyword),
+                ("crate", Keyword),
+                ("log", Namespace),
+                ("as", Keyword),
+                ("logging", Namespace),
+                ("extern", Keyword),
+                ("crate", Keyword),
+                ("serde", Namespace),
+            ]
+        );
+    }
+
+    #[test]
+    fn generic_parameters_are_types() {
+        let source = "impl<T: Show> Show for Stack<T> { fun f(s: Self) {} }";
+        let kinds = semantic_tokens(source)
+            .iter()
+            .filter(|token| token.kind != SemanticKind::Operator)
+            .map(|token| (&source[token.range.start_byte..token.range.end_byte], token.kind))
+            .collect::<Vec<_>>();
+
+        use SemanticKind::*;
+        assert_eq!(
+            kinds,
+            [
+                ("impl", Keyword),
+                ("T", Type),
+                ("Show", Type),
+                ("Show", Type),
+                ("for", Keyword),
+                ("Stack", Type),
+                ("T", Type),
+                ("fun", Keyword),
+                ("f", FunctionName),
+                ("s", Parameter),
+                ("Self", Keyword),
+            ]
+        );
+    }
+
+    #[test]
+    fn structs_fields_and_impls() {
+        let source = "struct P { x: i32 } impl Show for P { fun f(p: P) { p.x } }";
+        let kinds = semantic_tokens(source)
+            .iter()
+            .filter(|token| token.kind != SemanticKind::Operator)
+            .map(|token| (&source[token.range.start_byte..token.range.end_byte], token.kind))
+            .collect::<Vec<_>>();
+
+        use SemanticKind::*;
+        assert_eq!(
+            kinds,
+            [
+                ("struct", Keyword),
+                ("P", Type),
+                ("x", Property),
+                ("i32", Type),
+                ("impl", Keyword),
+                ("Show", Type),
+                ("for", Keyword),
+                ("P", Type),
+                ("fun", Keyword),
+                ("f", FunctionName),
+                ("p", Parameter),
+                ("P", Type),
+                ("p", Parameter),
+                ("x", Property),
+            ]
+        );
+    }
+
+    #[test]
+    fn types_and_references() {
+        let source = "fun f(p: *mut Vec<io::File>) { const r = &mut *p; }";
+        let kinds = semantic_tokens(source)
+            .iter()
+            .filter(|token| token.kind != SemanticKind::Operator)
+            .map(|token| (&source[token.range.start_byte..token.range.end_byte], token.kind))
+            .collect::<Vec<_>>();
+
+        use SemanticKind::*;
+        assert_eq!(
+            kinds,
+            [
+                ("fun", Keyword),
+                ("f", FunctionName),
+                ("p", Parameter),
+                ("mut", Keyword),
+                ("Vec", Type),
+                ("io", Namespace),
+                ("File", Type),
+                ("const", Keyword),
+                ("r", VariableDeclaration),
+                ("mut", Keyword),
+                ("p", Parameter),
+            ]
+        );
+    }
+}
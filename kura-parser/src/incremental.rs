@@ -0,0 +1,885 @@
+use std::cell::Cell;
+use std::mem::discriminant;
+use std::ops::Range;
+
+use kura_lexer::token::Location;
+use kura_lexer::{Lexer, TransposeRef};
+
+use crate::expression::parse_expression;
+use crate::passes::map_children;
+use crate::{DoStep, Expression, GenericParam, ParseError, Parser, Pattern, Statement, TypeExpr, VariantKind};
+
+/// replaces the bytes in `range` of the source with `new_text`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub new_text: String,
+}
+
+/// a parsed source that can be cheaply reparsed after an edit, as editors do
+/// on every keystroke.
+///
+/// reparsing works at the level of top level statements. the ones before
+/// the edit are kept as they are, parsing resumes right after them, and as
+/// soon as it reaches the start of a statement that came after the edit the
+/// rest of the old tree is reused, shifted by how much the edit grew or
+/// shrunk the source. this gives the same tree a full parse would, as a
+/// statement only depends on the source from where it starts
+#[derive(Debug, Clone)]
+pub struct ParseResult {
+    source: String,
+    result: Result<Vec<Statement<'static>>, ParseError>,
+    /// how many statements were taken from the previous parse
+    reused: usize,
+}
+
+impl ParseResult {
+    pub fn new(source: impl Into<String>) -> Self {
+        let source = source.into();
+        let result = Parser::new(&source, Lexer::new(&source))
+            .parse()
+            .map(|statements| statements.into_iter().map(Statement::into_owned).collect());
+
+        Self {
+            source,
+            result,
+            reused: 0,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn result(&self) -> &Result<Vec<Statement<'static>>, ParseError> {
+        &self.result
+    }
+
+    /// how many top level statements the last reparse took from the
+    /// previous tree instead of parsing them again
+    pub fn reused_statements(&self) -> usize {
+        self.reused
+    }
+
+    /// applies `edit` to the source and parses it again, reusing what the
+    /// edit didn't touch. a source that had syntax errors is parsed from
+    /// scratch.
+    ///
+    /// panics if the range of the edit is out of bounds or doesn't fall on
+    /// char boundaries, just like `String::replace_range`
+    pub fn reparse(&self, edit: TextEdit) -> ParseResult {
+        let mut source = self.source.clone();
+        source.replace_range(edit.range.clone(), &edit.new_text);
+
+        let Ok(old) = &self.result else {
+            return Self::new(source);
+        };
+
+        let delta = edit.new_text.len() as isize - edit.range.len() as isize;
+
+        // a statement ending right where the edit starts could be extended by
+        // it, like `}` followed by an inserted `}`, so it has to be reparsed
+        let prefix = old
+            .iter()
+            .take_while(|statement| statement.location().end_byte < edit.range.start)
+            .count();
+        let suffix = old
+            .iter()
+            .position(|statement| statement.location().start_byte >= edit.range.end)
+            .unwrap_or(old.len())
+            .max(prefix);
+
+        let resume = match prefix {
+            0 => 0,
+            _ => old[prefix - 1].location().end_byte,
+        };
+
+        let mut statements = old[..prefix].to_vec();
+        let mut reused = prefix;
+
+        let result = {
+            let lexer = Lexer::new(&source).sub_lexer(resume..source.len());
+            let mut parser = Parser::new(&source, lexer);
+
+            loop {
+                let next_start = match parser.lexer.peek() {
+                    None => break Ok(()),
+                    Some(Ok(token)) => Some(token.location.start_byte),
+                    Some(Err(_)) => None,
+                };
+
+                // doc comments aren't part of the location of a statement, so
+                // an edit to them alone leaves its start where it was
+                let docs = parser.lexer.peek_doc_comments();
+                let unchanged = next_start.and_then(|start| {
+                    old[suffix..].iter().position(|statement| {
+                        statement.location().shifted_by(delta).start_byte == start
+                            && statement.doc().iter().map(AsRef::as_ref).eq(docs.iter().copied())
+                    })
+                });
+
+                if let Some(idx) = unchanged {
+                    let rest = &old[suffix + idx..];
+                    reused += rest.len();
+                    statements.extend(rest.iter().cloned().map(|mut statement| {
+                        shift_statement(&mut statement, Shift { from: 0, delta });
+                        statement
+                    }));
+                    break Ok(());
+                }
+
+                match parser.parse_statement() {
+                    Ok(statement) => statements.push(statement.into_owned()),
+                    Err(error) => break Err(error),
+                }
+            }
+        };
+
+        Self {
+            source,
+            result: result.map(|_| statements),
+            reused,
+        }
+    }
+}
+
+impl Expression<'_> {
+    /// patches the literal or identifier spanning exactly the range of
+    /// `edit` without parsing the expression again, which is enough for
+    /// edits like changing a number or renaming a variable. everything after
+    /// the edit is shifted, and everything around it grows or shrinks.
+    ///
+    /// the new text has to be a single node of the same kind as the one it
+    /// replaces, like a string for a string. when it isn't, or when no such
+    /// node spans the range, like for an edit crossing the boundary of a
+    /// node, `None` is returned and the source has to be reparsed
+    pub fn apply_edit(&self, edit: &TextEdit) -> Option<Expression<'static>> {
+        let replacement = parse_leaf(&edit.new_text, edit.range.clone())?;
+        let replaced = Cell::new(false);
+        let mut expr = replace_leaf(self.clone().into_owned(), &replacement, &replaced);
+        if !replaced.get() {
+            return None;
+        }
+
+        // the replacement still spans the old text, which ends where the
+        // edit does, so it grows or shrinks along with its parents
+        let delta = edit.new_text.len() as isize - edit.range.len() as isize;
+        shift_expression(
+            &mut expr,
+            Shift {
+                from: edit.range.end,
+                delta,
+            },
+        );
+        Some(expr)
+    }
+}
+
+/// parses `text` when all of it is a single literal or identifier, located
+/// at `range`, the text it replaces
+fn parse_leaf(text: &str, range: Range<usize>) -> Option<Expression<'static>> {
+    let mut lexer = Lexer::new(text);
+    let mut expr = parse_expression(&mut lexer, false).ok()?.into_owned();
+    if lexer.peek().transpose().ok()?.is_some() || expr.location() != Location::new(0, text.len()) {
+        return None;
+    }
+
+    match &mut expr {
+        Expression::Ident { location, .. }
+        | Expression::Bool { location, .. }
+        | Expression::UintLiteral { location, .. }
+        | Expression::IntLiteral { location, .. }
+        | Expression::FloatLiteral { location, .. }
+        | Expression::StringLiteral { location, .. } => *location = range.into(),
+        _ => return None,
+    }
+    Some(expr)
+}
+
+/// replaces the first node located where `replacement` is and of the same
+/// kind with it
+fn replace_leaf<'ast>(
+    expr: Expression<'ast>,
+    replacement: &Expression<'ast>,
+    replaced: &Cell<bool>,
+) -> Expression<'ast> {
+    if replaced.get() {
+        return expr;
+    }
+    if discriminant(&expr) == discriminant(replacement) && expr.location() == replacement.location() {
+        replaced.set(true);
+        return replacement.clone();
+    }
+
+    match expr {
+        // the literals of an interpolated string are written without their
+        // quotes, so no literal can replace them
+        Expression::Interpolated {
+            literals,
+            expressions,
+            location,
+        } => Expression::Interpolated {
+            literals,
+            expressions: expressions
+                .into_iter()
+                .map(|expr| replace_leaf(expr, replacement, replaced))
+                .collect(),
+            location,
+        },
+        expr => map_children(expr, |child| replace_leaf(child, replacement, replaced)),
+    }
+}
+
+/// how an edit moves the locations of a tree. those starting at or after
+/// `from` move by `delta`, and those only ending after it, which surround
+/// the edit, grow by it
+#[derive(Debug, Clone, Copy)]
+struct Shift {
+    from: usize,
+    delta: isize,
+}
+
+impl Shift {
+    fn apply(self, location: &mut Location) {
+        if location.start_byte >= self.from {
+            location.start_byte = location.start_byte.saturating_add_signed(self.delta);
+        }
+        if location.end_byte >= self.from {
+            location.end_byte = location.end_byte.saturating_add_signed(self.delta);
+        }
+    }
+}
+
+fn shift_statement(statement: &mut Statement<'_>, shift: Shift) {
+    match statement {
+        Statement::FunArgument { arg_type, location, .. } => {
+            shift_type(arg_type, shift);
+            shift.apply(location);
+        }
+        Statement::Fun {
+            generics,
+            arguments,
+            body,
+            return_type,
+            where_clause,
+            attributes,
+            location,
+            ..
+        } => {
+            shift_generics(generics, shift);
+            for predicate in where_clause {
+                predicate
+                    .lifetimes
+                    .iter_mut()
+                    .for_each(|lifetime| shift.apply(&mut lifetime.location));
+                shift_type(&mut predicate.bounded, shift);
+                predicate.bounds.iter_mut().for_each(|bound| shift_type(bound, shift));
+                shift.apply(&mut predicate.location);
+            }
+            arguments
+                .iter_mut()
+                .for_each(|argument| shift_statement(argument, shift));
+            body.iter_mut().for_each(|expr| shift_expression(expr, shift));
+            if let Some(return_type) = return_type {
+                shift_type(return_type, shift);
+            }
+            attributes
+                .iter_mut()
+                .for_each(|attribute| shift.apply(&mut attribute.location));
+            shift.apply(location);
+        }
+        Statement::Struct {
+            generics,
+            fields,
+            attributes,
+            location,
+            ..
+        } => {
+            shift_generics(generics, shift);
+            fields.iter_mut().for_each(|field| shift_statement(field, shift));
+            attributes
+                .iter_mut()
+                .for_each(|attribute| shift.apply(&mut attribute.location));
+            shift.apply(location);
+        }
+        Statement::Impl {
+            generics,
+            arguments,
+            functions,
+            location,
+            ..
+        } => {
+            shift_generics(generics, shift);
+            arguments.iter_mut().for_each(|argument| shift_type(argument, shift));
+            functions
+                .iter_mut()
+                .for_each(|function| shift_statement(function, shift));
+            shift.apply(location);
+        }
+        Statement::Mod { location, .. } | Statement::Use { location, .. } => shift.apply(location),
+        Statement::Static {
+            typ,
+            value,
+            attributes,
+            location,
+            ..
+        } => {
+            if let Some(typ) = typ {
+                shift_type(typ, shift);
+            }
+            shift_expression(value, shift);
+            attributes
+                .iter_mut()
+                .for_each(|attribute| shift.apply(&mut attribute.location));
+            shift.apply(location);
+        }
+        Statement::ExternCrate {
+            attributes, location, ..
+        } => {
+            attributes
+                .iter_mut()
+                .for_each(|attribute| shift.apply(&mut attribute.location));
+            shift.apply(location);
+        }
+        Statement::MacroRules { rules, location, .. } => {
+            for rule in rules {
+                rule.pattern
+                    .iter_mut()
+                    .chain(&mut rule.expansion)
+                    .for_each(|token| shift.apply(&mut token.location));
+                shift.apply(&mut rule.location);
+            }
+            shift.apply(location);
+        }
+    }
+}
+
+fn shift_generics(generics: &mut [GenericParam<'_>], shift: Shift) {
+    for param in generics {
+        param.bounds.iter_mut().for_each(|bound| shift_type(bound, shift));
+        shift.apply(&mut param.location);
+    }
+}
+
+fn shift_type(typ: &mut TypeExpr<'_>, shift: Shift) {
+    match typ {
+        TypeExpr::Named { location, .. } | TypeExpr::Lifetime { location, .. } | TypeExpr::Never { location } => {
+            shift.apply(location)
+        }
+        TypeExpr::Fn {
+            parameters,
+            returns,
+            location,
+        }
+        | TypeExpr::FnTrait {
+            parameters,
+            returns,
+            location,
+            ..
+        } => {
+            parameters.iter_mut().for_each(|typ| shift_type(typ, shift));
+            if let Some(returns) = returns {
+                shift_type(returns, shift);
+            }
+            shift.apply(location);
+        }
+        TypeExpr::Array {
+            element: inner,
+            location,
+            ..
+        }
+        | TypeExpr::Ptr {
+            pointee: inner,
+            location,
+            ..
+        }
+        | TypeExpr::ImplTrait { bound: inner, location }
+        | TypeExpr::Ref {
+            referent: inner,
+            location,
+            ..
+        } => {
+            shift_type(inner, shift);
+            shift.apply(location);
+        }
+        TypeExpr::Tuple {
+            elements: types,
+            location,
+        }
+        | TypeExpr::Generic {
+            arguments: types,
+            location,
+            ..
+        } => {
+            types.iter_mut().for_each(|typ| shift_type(typ, shift));
+            shift.apply(location);
+        }
+    }
+}
+
+fn shift_pattern(pattern: &mut Pattern<'_>, shift: Shift) {
+    match pattern {
+        Pattern::Literal(literal) => shift_expression(literal, shift),
+        Pattern::Variant { kind, location, .. } => {
+            shift_variant(kind, shift, shift_pattern);
+            shift.apply(location);
+        }
+        Pattern::Wildcard { location } | Pattern::Binding { location, .. } | Pattern::Path { location, .. } => {
+            shift.apply(location)
+        }
+    }
+}
+
+fn shift_variant<T>(kind: &mut VariantKind<'_, T>, shift: Shift, shift_value: fn(&mut T, Shift)) {
+    if let VariantKind::Struct(fields) = kind {
+        fields.iter_mut().for_each(|field| shift.apply(&mut field.location));
+    }
+    kind.values_mut().for_each(|value| shift_value(value, shift));
+}
+
+fn shift_expression(expr: &mut Expression<'_>, shift: Shift) {
+    match expr {
+        Expression::EnumVariant { kind, location, .. } => {
+            shift_variant(kind, shift, shift_expression);
+            shift.apply(location);
+        }
+        Expression::DoBlock { steps, location } => {
+            for step in steps {
+                if let DoStep::Bind { location, .. } = step {
+                    shift.apply(location);
+                }
+                shift_expression(step.expr_mut(), shift);
+            }
+            shift.apply(location);
+        }
+        Expression::Var {
+            typ,
+            value,
+            attributes,
+            location,
+            ..
+        } => {
+            if let Some(typ) = typ {
+                shift_type(typ, shift);
+            }
+            shift_expression(value, shift);
+            attributes
+                .iter_mut()
+                .for_each(|attribute| shift.apply(&mut attribute.location));
+            shift.apply(location);
+        }
+        Expression::Assert {
+            condition,
+            message,
+            location,
+        } => {
+            shift_expression(condition, shift);
+            message.iter_mut().for_each(|message| shift_expression(message, shift));
+            shift.apply(location);
+        }
+        Expression::If {
+            condition,
+            truthy,
+            falsy,
+            location,
+        } => {
+            shift_expression(condition, shift);
+            shift_expression(truthy, shift);
+            falsy.iter_mut().for_each(|branch| shift_expression(branch, shift));
+            shift.apply(location);
+        }
+        Expression::Match {
+            scrutinee,
+            arms,
+            location,
+        } => {
+            shift_expression(scrutinee, shift);
+            for arm in arms {
+                shift_pattern(&mut arm.pattern, shift);
+                shift_expression(&mut arm.body, shift);
+                shift.apply(&mut arm.location);
+            }
+            shift.apply(location);
+        }
+        Expression::FunCall {
+            ident,
+            arguments,
+            location,
+            ..
+        } => {
+            shift_expression(ident, shift);
+            arguments
+                .iter_mut()
+                .for_each(|argument| shift_expression(argument, shift));
+            shift.apply(location);
+        }
+        Expression::Assign { ident, value, location }
+        | Expression::CompoundAssign {
+            ident, value, location, ..
+        } => {
+            shift_expression(ident, shift);
+            shift_expression(value, shift);
+            shift.apply(location);
+        }
+        Expression::Block {
+            expressions,
+            tail,
+            location,
+            ..
+        } => {
+            expressions
+                .iter_mut()
+                .chain(tail.as_deref_mut())
+                .for_each(|expr| shift_expression(expr, shift));
+            shift.apply(location);
+        }
+        Expression::BinaryOp { lhs, rhs, location, .. }
+        | Expression::Index {
+            target: lhs,
+            index: rhs,
+            location,
+        }
+        | Expression::Elvis {
+            condition: lhs,
+            default: rhs,
+            location,
+        } => {
+            shift_expression(lhs, shift);
+            shift_expression(rhs, shift);
+            shift.apply(location);
+        }
+        Expression::Lambda {
+            parameters,
+            parameter_types,
+            return_type,
+            body,
+            location,
+        } => {
+            parameters
+                .iter_mut()
+                .for_each(|parameter| shift_expression(parameter, shift));
+            parameter_types
+                .iter_mut()
+                .flatten()
+                .for_each(|typ| shift_type(typ, shift));
+            if let Some(typ) = return_type {
+                shift_type(typ, shift);
+            }
+            shift_expression(body, shift);
+            shift.apply(location);
+        }
+        Expression::Let {
+            value, body, location, ..
+        } => {
+            shift_expression(value, shift);
+            shift_expression(body, shift);
+            shift.apply(location);
+        }
+        Expression::Yield { value, location } => {
+            value.iter_mut().for_each(|value| shift_expression(value, shift));
+            shift.apply(location);
+        }
+        Expression::Sizeof { typ, location } => {
+            shift_type(typ, shift);
+            shift.apply(location);
+        }
+        Expression::InlineAsm {
+            outputs,
+            inputs,
+            location,
+            ..
+        } => {
+            for operand in outputs.iter_mut().chain(inputs) {
+                shift_expression(&mut operand.expr, shift);
+                shift.apply(&mut operand.location);
+            }
+            shift.apply(location);
+        }
+        Expression::SafeCast { expr, typ, location } => {
+            shift_expression(expr, shift);
+            shift_type(typ, shift);
+            shift.apply(location);
+        }
+        Expression::Return { value, location }
+        | Expression::Await { expr: value, location }
+        | Expression::RuntimeTypeOf { expr: value, location }
+        | Expression::ArrayLen { array: value, location }
+        | Expression::Splat { expr: value, location }
+        | Expression::UnsafeBlock { body: value, location }
+        | Expression::ComptimeBlock { body: value, location }
+        | Expression::Defer { body: value, location }
+        | Expression::Ref {
+            expr: value, location, ..
+        }
+        | Expression::Deref { expr: value, location }
+        | Expression::Not { expr: value, location }
+        | Expression::FieldAccess {
+            target: value,
+            location,
+            ..
+        }
+        | Expression::TupleAccess {
+            tuple: value, location, ..
+        }
+        | Expression::Try { expr: value, location } => {
+            shift_expression(value, shift);
+            shift.apply(location);
+        }
+        Expression::Interpolated {
+            literals,
+            expressions,
+            location,
+        } => {
+            literals
+                .iter_mut()
+                .chain(expressions.iter_mut())
+                .for_each(|expr| shift_expression(expr, shift));
+            shift.apply(location);
+        }
+        Expression::Array { elements, location } | Expression::Tuple { elements, location } => {
+            elements.iter_mut().for_each(|element| shift_expression(element, shift));
+            shift.apply(location);
+        }
+        Expression::Macro { tokens, location, .. } => {
+            tokens.iter_mut().for_each(|token| shift.apply(&mut token.location));
+            shift.apply(location);
+        }
+        Expression::Bool { location, .. }
+        | Expression::Ident { location, .. }
+        | Expression::Path { location, .. }
+        | Expression::UintLiteral { location, .. }
+        | Expression::FloatLiteral { location, .. }
+        | Expression::IntLiteral { location, .. }
+        | Expression::StringLiteral { location, .. } => shift.apply(location),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES: &[&str] = &[
+        r#"
+fun add(a: i32, b: i32) => i32 {
+    return a + b;
+}
+
+// the entry point
+fun main() {
+    var total = add(1, 2);
+    total = total * 2;
+    print("total is {total}");
+}
+
+fun pick(flag: bool) => u8 {
+    if flag == true { return 1u8; } else { return 2u8; }
+}
+"#,
+        r#"
+fun fetch() { const value = load().await; log!("{}", value); }
+fun nested() { { const a = 1; { const b = a; } } }
+/* a block comment */ fun last(x: f32) => f32 { #[allow(shadow)] const y: f32 = x; return y; }
+"#,
+        r#"
+/// adds two
+/// numbers
+fun add(a: i32, b: i32) => i32 { return a + b; }
+
+/** a point */
+#[derive(Debug)]
+struct Point { x: i32, y: i32 }
+
+/// the entry point
+fun main() { print("{add(1, 2)}"); }
+"#,
+    ];
+
+    const SNIPPETS: &[&str] = &[
+        "",
+        "",
+        "x",
+        "1",
+        "}",
+        "{",
+        ";",
+        "(",
+        ")",
+        "\"",
+        "/*",
+        "*/",
+        "//",
+        "\n",
+        " ",
+        "fun f() {}",
+        "const a = 1;",
+        "return 2;",
+        "fun",
+        "+",
+        "==",
+        "#[allow(shadow)]",
+        "/// doc\n",
+        "/** doc */",
+        "print(\"{a}\");",
+    ];
+
+    /// xorshift, which is plenty for picking edits and keeps runs reproducible
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as usize
+        }
+    }
+
+    fn random_edit(source: &str, random: &mut Random) -> TextEdit {
+        let start = random.below(source.len() + 1);
+        let end = (start + random.below(12)).min(source.len());
+        TextEdit {
+            range: start..end,
+            new_text: SNIPPETS[random.below(SNIPPETS.len())].to_string(),
+        }
+    }
+
+    #[test]
+    fn random_edits_match_a_full_reparse() {
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+
+        for fixture in FIXTURES {
+            let pristine = ParseResult::new(*fixture);
+            assert!(pristine.result().is_ok(), "{:?}", pristine.result());
+
+            let mut current = pristine.clone();
+            let mut reused = 0;
+
+            for _ in 0..400 {
+                // going back to the fixture every so often keeps most edits
+                // applying to sources that parse, where there is a tree to reuse
+                if random.below(3) == 0 {
+                    current = pristine.clone();
+                }
+
+                let edit = random_edit(current.source(), &mut random);
+                let incremental = current.reparse(edit.clone());
+                let full = ParseResult::new(incremental.source());
+
+                assert_eq!(
+                    incremental.result(),
+                    full.result(),
+                    "editing {:?} with {edit:?}",
+                    current.source()
+                );
+
+                reused += incremental.reused_statements();
+                current = incremental;
+            }
+
+            assert!(reused > 0);
+        }
+    }
+
+    #[test]
+    fn statements_around_the_edit_are_reused() {
+        let source = "fun a() { one(); }\nfun b() { two(); }\nfun c() { three(); }\n";
+        let parsed = ParseResult::new(source);
+
+        let start = source.find("two").unwrap();
+        let edited = parsed.reparse(TextEdit {
+            range: start..start + 3,
+            new_text: String::from("second_call"),
+        });
+
+        assert_eq!(edited.reused_statements(), 2);
+        assert_eq!(edited.result(), ParseResult::new(edited.source()).result());
+
+        let Ok(statements) = edited.result() else {
+            panic!("expected the edit to parse");
+        };
+        let location = statements[2].location();
+        assert_eq!(
+            &edited.source()[location.start_byte..location.end_byte],
+            "fun c() { three(); }"
+        );
+    }
+
+    #[test]
+    fn edits_to_doc_comments_are_reparsed() {
+        let parsed = ParseResult::new("/// a\nfun a() {}\n");
+
+        for (range, new_text) in [(4..5, "b"), (0..6, ""), (0..0, "/// c\n")] {
+            let edited = parsed.reparse(edit(range, new_text));
+            assert_eq!(edited.result(), ParseResult::new(edited.source()).result());
+        }
+
+        let edited = parsed.reparse(edit(4..5, "b"));
+        let Ok(statements) = edited.result() else {
+            panic!("expected the edit to parse");
+        };
+        assert_eq!(statements[0].doc(), ["/// b"]);
+    }
+
+    #[test]
+    fn sources_with_errors_are_parsed_from_scratch() {
+        let parsed = ParseResult::new("fun a() { const = 1; }");
+        assert!(parsed.result().is_err());
+
+        let edited = parsed.reparse(TextEdit {
+            range: 16..16,
+            new_text: String::from("x "),
+        });
+        assert_eq!(edited.source(), "fun a() { const x = 1; }");
+        assert!(edited.result().is_ok());
+        assert_eq!(edited.reused_statements(), 0);
+    }
+
+    fn parse_expr(source: &str) -> Expression<'static> {
+        match parse_expression(&mut Lexer::new(source), false) {
+            Ok(expr) => expr.into_owned(),
+            Err(e) => panic!("{source}: {e:?}"),
+        }
+    }
+
+    fn edit(range: Range<usize>, new_text: &str) -> TextEdit {
+        TextEdit {
+            range,
+            new_text: String::from(new_text),
+        }
+    }
+
+    #[test]
+    fn edits_to_leaves_match_a_full_parse() {
+        let source = r#"f(total, 10, "a") + [1.5, x][0]"#;
+        let expr = parse_expr(source);
+
+        for (range, new_text) in [
+            (2..7, "count"),
+            (9..11, "2"),
+            (13..16, r#""hello""#),
+            (21..24, "10.25"),
+            (26..27, "flag"),
+        ] {
+            let mut edited = String::from(source);
+            edited.replace_range(range.clone(), new_text);
+
+            let patched = expr.apply_edit(&edit(range, new_text));
+            assert_eq!(patched, Some(parse_expr(&edited)), "{edited}");
+        }
+    }
+
+    #[test]
+    fn edits_that_arent_a_leaf_are_rejected() {
+        let expr = parse_expr("f(total, 10) + 1");
+
+        // crossing the boundary of a node
+        assert_eq!(expr.apply_edit(&edit(2..9, "count")), None);
+        // not spanning a whole node
+        assert_eq!(expr.apply_edit(&edit(3..5, "o")), None);
+        assert_eq!(expr.apply_edit(&edit(12..13, "-")), None);
+        // not a single node, or not one of the same kind
+        assert_eq!(expr.apply_edit(&edit(9..11, "10 + 1")), None);
+        assert_eq!(expr.apply_edit(&edit(2..7, "1")), None);
+        assert_eq!(expr.apply_edit(&edit(2..7, "fun")), None);
+    }
+}
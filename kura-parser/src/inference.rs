@@ -0,0 +1,1224 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::rc::Rc;
+
+use kura_lexer::token::{FloatSizes, IntSizes, Location, Operator, UIntSizes};
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::const_eval::KIND_OF_MACRO;
+use crate::passes::is_diverging;
+use crate::{DoStep, Expression, Pattern, Statement, TypeExpr};
+
+/// the type of a value, as far as inference can tell
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Type {
+    Int(IntSizes),
+    UInt(UIntSizes),
+    Float(FloatSizes),
+    Bool,
+    Str,
+    Unit,
+    /// `!`, the type of expressions that never produce a value
+    Never,
+}
+
+impl Type {
+    /// the type a name like `i32` or `bool` refers to, if it is built in
+    pub fn from_name(name: &str) -> Option<Type> {
+        match name {
+            "bool" => Some(Type::Bool),
+            "String" => Some(Type::Str),
+            _ => IntSizes::try_from(name)
+                .map(Type::Int)
+                .or_else(|_| UIntSizes::try_from(name).map(Type::UInt))
+                .or_else(|_| FloatSizes::try_from(name).map(Type::Float))
+                .ok(),
+        }
+    }
+
+    /// the type a type expression like `i32` names, when inference knows it
+    pub fn from_type_expr(typ: &TypeExpr<'_>) -> Option<Type> {
+        match typ {
+            TypeExpr::Named { name, .. } => Type::from_name(name),
+            TypeExpr::Tuple { elements, .. } if elements.is_empty() => Some(Type::Unit),
+            TypeExpr::Never { .. } => Some(Type::Never),
+            _ => None,
+        }
+    }
+
+    /// the type expression the type is written as, located at `location`
+    pub fn to_type_expr(&self, location: Location) -> TypeExpr<'static> {
+        match self {
+            Type::Unit => TypeExpr::Tuple {
+                elements: vec![],
+                location,
+            },
+            Type::Never => TypeExpr::Never { location },
+            named => TypeExpr::Named {
+                name: Cow::Owned(named.to_string()),
+                location,
+            },
+        }
+    }
+
+    /// how many bits the type takes, for numeric types. pointer sized ones
+    /// are as wide as the widest fixed size
+    fn bits(&self) -> Option<u8> {
+        Some(match self {
+            Type::Int(IntSizes::I8) | Type::UInt(UIntSizes::U8) | Type::Float(FloatSizes::F8) => 8,
+            Type::Int(IntSizes::I16) | Type::UInt(UIntSizes::U16) | Type::Float(FloatSizes::F16) => 16,
+            Type::Int(IntSizes::I32) | Type::UInt(UIntSizes::U32) | Type::Float(FloatSizes::F32) => 32,
+            Type::Int(_) | Type::UInt(_) | Type::Float(_) => 64,
+            Type::Bool | Type::Str | Type::Unit | Type::Never => return None,
+        })
+    }
+
+    /// whether converting any value of `other` into this type always
+    /// succeeds, which is the case when it is the same type or a wider one
+    /// of the same family. unsigned integers also fit in wider signed ones
+    pub fn holds_every_value_of(&self, other: &Type) -> bool {
+        let wider = |than: u8| self.bits().is_some_and(|bits| bits > than);
+        match (self, other) {
+            _ if self == other => true,
+            (Type::Int(_), Type::Int(_)) | (Type::UInt(_), Type::UInt(_)) | (Type::Float(_), Type::Float(_)) => {
+                self.bits() >= other.bits()
+            }
+            (Type::Int(_), Type::UInt(_)) => other.bits().is_some_and(wider),
+            _ => false,
+        }
+    }
+
+    /// how many bytes a value of the type takes, when that is known without
+    /// knowing the layout of anything else
+    pub fn size(&self) -> Option<u64> {
+        match self {
+            Type::Bool => Some(1),
+            Type::Unit | Type::Never => Some(0),
+            Type::Str => None,
+            numeric => numeric.bits().map(|bits| u64::from(bits) / 8),
+        }
+    }
+}
+
+/// the type an operation on `lhs` and `rhs` results in, promoted like C
+/// does: numbers of the same family give the wider of them, as in `i8 + i32`
+/// being an `i32`, and integers mixed with floats give the float. mixing
+/// signed and unsigned integers gives the signed one only when it is wider,
+/// as it then holds every value of the other. anything other than numbers
+/// only combines with itself
+pub fn promote(lhs: &Type, rhs: &Type) -> Option<Type> {
+    let (Some(lhs_bits), Some(rhs_bits)) = (lhs.bits(), rhs.bits()) else {
+        return (lhs == rhs).then(|| lhs.clone());
+    };
+    let typ = match (lhs, rhs) {
+        (Type::Float(_), Type::Int(_) | Type::UInt(_)) => lhs,
+        (Type::Int(_) | Type::UInt(_), Type::Float(_)) => rhs,
+        (Type::Int(_), Type::UInt(_)) if lhs_bits > rhs_bits => lhs,
+        (Type::Int(_), Type::UInt(_)) => rhs,
+        (Type::UInt(_), Type::Int(_)) if rhs_bits > lhs_bits => rhs,
+        (Type::UInt(_), Type::Int(_)) => lhs,
+        _ if rhs_bits > lhs_bits => rhs,
+        _ => lhs,
+    };
+    Some(typ.clone())
+}
+
+/// whether one of two types is a signed integer and the other an unsigned one
+fn mixes_signs(lhs: &Type, rhs: &Type) -> bool {
+    matches!(
+        (lhs, rhs),
+        (Type::Int(_), Type::UInt(_)) | (Type::UInt(_), Type::Int(_))
+    )
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Type::Int(size) => write!(f, "{size}"),
+            Type::UInt(size) => write!(f, "{size}"),
+            Type::Float(size) => write!(f, "{size}"),
+            Type::Bool => write!(f, "bool"),
+            Type::Str => write!(f, "String"),
+            Type::Unit => write!(f, "()"),
+            Type::Never => write!(f, "!"),
+        }
+    }
+}
+
+/// types of the names defined outside of what is being inferred, like
+/// globals of the host running the program. environments nest, with the
+/// bindings of a child hiding the ones of its parents. children share the
+/// bindings of their parents, so cloning and extending are cheap.
+///
+/// bindings are `Type`s rather than `TypeExpr`s. a type expression is what
+/// was written, with a location and maybe a name inference knows nothing
+/// of, while types are equal whenever they are the same type. callers
+/// holding type expressions convert them with `extend_annotated` and
+/// `lookup_annotation`
+#[derive(Debug, Clone, Default)]
+pub struct TypeEnv {
+    parent: Option<Rc<TypeEnv>>,
+    bindings: Rc<HashMap<String, Type>>,
+}
+
+impl TypeEnv {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, typ: Type) {
+        Rc::make_mut(&mut self.bindings).insert(name.into(), typ);
+    }
+
+    /// the type `name` is bound to here, or in the closest parent binding it
+    pub fn lookup(&self, name: &str) -> Option<&Type> {
+        let mut env = Some(self);
+        while let Some(current) = env {
+            match current.bindings.get(name) {
+                Some(typ) => return Some(typ),
+                None => env = current.parent.as_deref(),
+            }
+        }
+        None
+    }
+
+    /// a child of this environment binding `name` to `typ`, leaving this one
+    /// untouched
+    pub fn extend(&self, name: impl Into<String>, typ: Type) -> TypeEnv {
+        TypeEnv {
+            parent: Some(Rc::new(self.clone())),
+            bindings: Rc::new(HashMap::from([(name.into(), typ)])),
+        }
+    }
+
+    /// like `extend`, binding `name` to the type `typ` is written as. fails
+    /// when inference doesn't know that type
+    pub fn extend_annotated(&self, name: impl Into<String>, typ: &TypeExpr<'_>) -> Result<TypeEnv, TypeError> {
+        Ok(self.extend(name, known(typ)?))
+    }
+
+    /// like `lookup`, giving the type back written as a type expression
+    /// located at `location`
+    pub fn lookup_annotation(&self, name: &str, location: Location) -> Option<TypeExpr<'static>> {
+        self.lookup(name).map(|typ| typ.to_type_expr(location))
+    }
+}
+
+/// the type `typ` is written as, or an error when inference doesn't know it
+fn known(typ: &TypeExpr<'_>) -> Result<Type, TypeError> {
+    Type::from_type_expr(typ).ok_or_else(|| TypeError::Unknown {
+        typ: typ.to_string(),
+        span: typ.location().into(),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Error, Diagnostic)]
+pub enum TypeError {
+    #[error("mismatched types: expected `{expected}`, found `{found}`")]
+    #[diagnostic(code(kura::type_mismatch))]
+    Mismatch {
+        expected: Type,
+        found: Type,
+        #[label("expected `{expected}` here")]
+        span: SourceSpan,
+    },
+    /// a signed and an unsigned integer in one operation. it is only a
+    /// warning, as the operation is still promoted to `promoted`
+    #[error("`{lhs}` and `{rhs}` differ in signedness")]
+    #[diagnostic(
+        code(kura::sign_mismatch),
+        severity(Warning),
+        help("convert one of the operands so both have the same sign")
+    )]
+    SignMismatch {
+        lhs: Type,
+        rhs: Type,
+        promoted: Type,
+        #[label("promoted to `{promoted}`")]
+        span: SourceSpan,
+    },
+    /// a type expression naming a type inference knows nothing of, like a
+    /// struct
+    #[error("`{typ}` is not a type inference knows")]
+    #[diagnostic(code(kura::unknown_type))]
+    Unknown {
+        typ: String,
+        #[label("written here")]
+        span: SourceSpan,
+    },
+}
+
+/// the type `expected` and `found` agree on, which for types without
+/// anything left to infer in them is only when they are the same.
+/// `location` is where `found` comes from, reported when they don't agree
+pub fn unify(expected: Type, found: Type, location: Location) -> Result<Type, TypeError> {
+    // there is never a value of `!`, so it stands for whatever is expected
+    match expected == found || found == Type::Never {
+        true => Ok(expected),
+        false => Err(TypeError::Mismatch {
+            expected,
+            found,
+            span: location.into(),
+        }),
+    }
+}
+
+/// like `unify`, for types written as type expressions. the type they agree
+/// on is located where `found` is
+pub fn unify_annotations(expected: &TypeExpr<'_>, found: &TypeExpr<'_>) -> Result<TypeExpr<'static>, TypeError> {
+    let location = found.location();
+    unify(known(expected)?, known(found)?, location).map(|typ| typ.to_type_expr(location))
+}
+
+/// gives a size to every numeric literal written without one, and checks
+/// that the types of values agree with where they are used.
+///
+/// a literal takes the type its context expects, like the annotation of the
+/// variable it is assigned to, the argument it is passed as or the other
+/// side of an operation. without any, integers are `i64` and floats are
+/// `f64`. operations on different numbers are [`promote`]d, and every one of
+/// them is annotated with the type of its result. names whose type can't be
+/// told, like fields, are left unchecked.
+///
+/// when nothing fails, the warnings found along the way are returned
+pub fn infer_types(ast: &mut [Statement<'_>], env: &TypeEnv) -> Result<Vec<TypeError>, Vec<TypeError>> {
+    let mut inferrer = Inferrer::new(env);
+    inferrer.signatures(ast);
+    ast.iter_mut().for_each(|statement| inferrer.statement(statement));
+
+    match inferrer.errors.is_empty() {
+        true => Ok(inferrer.warnings),
+        false => Err(inferrer.errors),
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Signature {
+    arguments: Vec<Option<Type>>,
+    returns: Type,
+}
+
+struct Inferrer<'env> {
+    env: &'env TypeEnv,
+    functions: HashMap<String, Signature>,
+    /// variables in scope, innermost last. a variable whose type is unknown
+    /// still hides the ones with the same name around it
+    scopes: Vec<HashMap<String, Option<Type>>>,
+    /// what the function being inferred returns, if inference knows the
+    /// type it is declared to return
+    returns: Option<Type>,
+    errors: Vec<TypeError>,
+    warnings: Vec<TypeError>,
+}
+
+/// adds the type of another branch to `typ`, the one of the branches before
+/// it, telling whether they agree. a branch that diverges never gives a
+/// value, so it agrees with anything and takes the type of the others
+fn join(typ: &mut Option<Type>, found: Option<Type>) -> bool {
+    match (&typ, found) {
+        (_, Some(Type::Never)) => true,
+        (Some(Type::Never), found) => {
+            *typ = found;
+            true
+        }
+        (typ, found) => **typ == found,
+    }
+}
+
+/// whether `expr` is only run for its effects when it ends the body of a
+/// function. calls at the base of a body always end in a semicolon
+fn is_statement(expr: &Expression<'_>) -> bool {
+    match expr {
+        Expression::Await { expr, .. } => is_statement(expr),
+        Expression::Var { .. }
+        | Expression::Assign { .. }
+        | Expression::CompoundAssign { .. }
+        | Expression::Return { .. }
+        | Expression::Yield { .. }
+        | Expression::Defer { .. }
+        | Expression::FunCall { .. }
+        | Expression::Macro { .. }
+        | Expression::InlineAsm { .. } => true,
+        _ => false,
+    }
+}
+
+impl<'env> Inferrer<'env> {
+    fn new(env: &'env TypeEnv) -> Self {
+        Self {
+            env,
+            functions: HashMap::new(),
+            scopes: vec![],
+            returns: None,
+            errors: vec![],
+            warnings: vec![],
+        }
+    }
+
+    /// collects the signature of every function first, so calls can be
+    /// checked no matter the order functions are defined in. methods are
+    /// called through what they are called on, which isn't checked
+    fn signatures(&mut self, statements: &[Statement<'_>]) {
+        for statement in statements {
+            match statement {
+                Statement::Fun {
+                    name,
+                    arguments,
+                    return_type,
+                    ..
+                } => {
+                    let arguments = arguments
+                        .iter()
+                        .map(|argument| match argument {
+                            Statement::FunArgument { arg_type, .. } => Type::from_type_expr(arg_type),
+                            _ => None,
+                        })
+                        .collect();
+                    let returns = return_type.as_deref().map_or(Some(Type::Unit), Type::from_type_expr);
+                    // functions returning a type inference doesn't know about
+                    // are left out, as calling them could return anything
+                    if let Some(returns) = returns {
+                        self.functions
+                            .insert(name.to_string(), Signature { arguments, returns });
+                    }
+                }
+                Statement::Impl { .. }
+                | Statement::FunArgument { .. }
+                | Statement::Struct { .. }
+                | Statement::MacroRules { .. }
+                | Statement::Mod { .. }
+                | Statement::ExternCrate { .. }
+                | Statement::Use { .. }
+                | Statement::Static { .. } => {}
+            }
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<Type> {
+        match self.scopes.iter().rev().find_map(|scope| scope.get(name)) {
+            Some(typ) => typ.clone(),
+            None => self.env.lookup(name).cloned(),
+        }
+    }
+
+    fn declare(&mut self, name: &str, typ: Option<Type>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.to_string(), typ);
+        }
+    }
+
+    /// reports `found` if it isn't what was `expected`. unknown types agree
+    /// with anything
+    fn check(&mut self, expected: Option<&Type>, found: Option<&Type>, location: Location) {
+        if let (Some(expected), Some(found)) = (expected, found) {
+            if let Err(error) = unify(expected.clone(), found.clone(), location) {
+                self.errors.push(error);
+            }
+        }
+    }
+
+    fn statement(&mut self, statement: &mut Statement<'_>) {
+        match statement {
+            Statement::Fun {
+                arguments,
+                body,
+                return_type,
+                ..
+            } => {
+                self.scopes.push(HashMap::new());
+                for argument in arguments.iter() {
+                    if let Statement::FunArgument { name, arg_type, .. } = argument {
+                        self.declare(name, Type::from_type_expr(arg_type));
+                    }
+                }
+                self.returns = return_type.as_deref().map_or(Some(Type::Unit), Type::from_type_expr);
+                let returns = self.returns.clone();
+                // the body of a function is a list rather than a block, so
+                // its tail is told apart here
+                let (body, tail) = match body.last().is_some_and(|last| !is_statement(last)) {
+                    true => body.split_last_mut().map(|(tail, body)| (body, Some(tail))).unwrap(),
+                    false => (body.as_mut_slice(), None),
+                };
+                self.block(body, tail, returns.as_ref(), true);
+                self.scopes.pop();
+            }
+            Statement::Impl { functions, .. } => functions.iter_mut().for_each(|function| self.statement(function)),
+            // globals are checked against what they are annotated with, but
+            // aren't known inside of functions
+            Statement::Static { typ, value, .. } => {
+                let annotated = typ.as_deref().and_then(Type::from_type_expr);
+                let found = self.infer(value, annotated.as_ref());
+                self.check(annotated.as_ref(), found.as_ref(), value.location());
+            }
+            Statement::FunArgument { .. }
+            | Statement::Struct { .. }
+            | Statement::MacroRules { .. }
+            | Statement::Mod { .. }
+            | Statement::ExternCrate { .. }
+            | Statement::Use { .. } => {}
+        }
+    }
+
+    /// infers every expression of a block in its own scope. the tail is the
+    /// value of the block, and is what `hint` applies to. a block without
+    /// one is a `()`, unless it diverges before reaching its end
+    fn block(
+        &mut self,
+        expressions: &mut [Expression<'_>],
+        tail: Option<&mut Expression<'_>>,
+        hint: Option<&Type>,
+        check_tail: bool,
+    ) -> Option<Type> {
+        self.scopes.push(HashMap::new());
+        expressions.iter_mut().for_each(|expr| _ = self.infer(expr, None));
+        let typ = match tail {
+            Some(tail) => {
+                let typ = self.infer(tail, hint);
+                if check_tail {
+                    self.check(hint, typ.as_ref(), tail.location());
+                }
+                typ
+            }
+            None if expressions.iter().any(is_diverging) => Some(Type::Never),
+            None => Some(Type::Unit),
+        };
+        self.scopes.pop();
+        typ
+    }
+
+    /// the type `expr` has without giving its literals a size, used to tell
+    /// what the literals around it should be
+    fn natural(&self, expr: &Expression<'_>) -> Option<Type> {
+        match expr {
+            Expression::IntLiteral { size, .. } => size.clone().map(Type::Int),
+            Expression::UintLiteral { size, .. } => size.clone().map(Type::UInt),
+            Expression::FloatLiteral { size, .. } => size.clone().map(Type::Float),
+            Expression::Ident { name, .. } => self.lookup(name),
+            Expression::ArrayLen { .. } | Expression::Sizeof { .. } => Some(Type::UInt(UIntSizes::Usize)),
+            Expression::BinaryOp { operator, lhs, rhs, .. } if is_arithmetic(*operator) => {
+                match (self.natural(lhs), self.natural(rhs)) {
+                    (Some(lhs), Some(rhs)) => promote(&lhs, &rhs).or(Some(lhs)),
+                    (lhs, rhs) => lhs.or(rhs),
+                }
+            }
+            Expression::FunCall { ident, .. } => match ident.as_ref() {
+                Expression::Ident { name, .. } if self.lookup(name).is_none() => self
+                    .functions
+                    .get(name.as_ref())
+                    .map(|signature| signature.returns.clone()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// checks that `pattern` can match a value of type `scrutinee`, adding
+    /// the names it binds to `bindings`. the values of a variant are of
+    /// types that aren't known
+    fn pattern(
+        &mut self,
+        pattern: &mut Pattern<'_>,
+        scrutinee: Option<&Type>,
+        bindings: &mut HashMap<String, Option<Type>>,
+    ) {
+        match pattern {
+            Pattern::Literal(literal) => {
+                let found = self.infer(literal, scrutinee);
+                self.check(scrutinee, found.as_ref(), literal.location());
+            }
+            Pattern::Binding { name, .. } => _ = bindings.insert(name.to_string(), scrutinee.cloned()),
+            Pattern::Variant { kind, .. } => kind.values_mut().for_each(|value| self.pattern(value, None, bindings)),
+            Pattern::Wildcard { .. } | Pattern::Path { .. } => {}
+        }
+    }
+
+    fn infer(&mut self, expr: &mut Expression<'_>, hint: Option<&Type>) -> Option<Type> {
+        match expr {
+            Expression::IntLiteral { size, .. } => {
+                let size = size.get_or_insert_with(|| match hint {
+                    Some(Type::Int(size)) => size.clone(),
+                    _ => IntSizes::I64,
+                });
+                Some(Type::Int(size.clone()))
+            }
+            Expression::UintLiteral { size: Some(size), .. } => Some(Type::UInt(size.clone())),
+            Expression::UintLiteral {
+                value,
+                size: None,
+                location,
+            } => {
+                let (value, location) = (*value, *location);
+                match (hint, i64::try_from(value)) {
+                    (Some(Type::UInt(size)), _) => {
+                        *expr = Expression::UintLiteral {
+                            value,
+                            size: Some(size.clone()),
+                            location,
+                        };
+                        Some(Type::UInt(size.clone()))
+                    }
+                    // literals are unsigned until they are negated, so they
+                    // become signed when that is what is expected of them
+                    (hint, Ok(value)) => {
+                        let size = match hint {
+                            Some(Type::Int(size)) => size.clone(),
+                            _ => IntSizes::I64,
+                        };
+                        *expr = Expression::IntLiteral {
+                            value,
+                            size: Some(size.clone()),
+                            location,
+                        };
+                        Some(Type::Int(size))
+                    }
+                    // too big for any signed integer
+                    (_, Err(_)) => {
+                        *expr = Expression::UintLiteral {
+                            value,
+                            size: Some(UIntSizes::U64),
+                            location,
+                        };
+                        Some(Type::UInt(UIntSizes::U64))
+                    }
+                }
+            }
+            Expression::FloatLiteral { size, .. } => {
+                let size = size.get_or_insert_with(|| match hint {
+                    Some(Type::Float(size)) => size.clone(),
+                    _ => FloatSizes::F64,
+                });
+                Some(Type::Float(size.clone()))
+            }
+            Expression::Bool { .. } => Some(Type::Bool),
+            Expression::StringLiteral { .. } => Some(Type::Str),
+            Expression::Interpolated { expressions, .. } => {
+                expressions.iter_mut().for_each(|expr| _ = self.infer(expr, None));
+                Some(Type::Str)
+            }
+            Expression::Ident { name, .. } => self.lookup(name),
+            Expression::Var { name, typ, value, .. } => {
+                let annotated = typ.as_deref().and_then(Type::from_type_expr);
+                let found = self.infer(value, annotated.as_ref());
+                self.check(annotated.as_ref(), found.as_ref(), value.location());
+                let name = name.to_string();
+                self.declare(&name, annotated.or(found));
+                Some(Type::Unit)
+            }
+            Expression::Assign { ident, value, .. } | Expression::CompoundAssign { ident, value, .. } => {
+                let target = self.infer(ident, None);
+                let found = self.infer(value, target.as_ref());
+                self.check(target.as_ref(), found.as_ref(), value.location());
+                Some(Type::Unit)
+            }
+            Expression::Return { value, .. } => {
+                let returns = self.returns.clone();
+                let found = self.infer(value, returns.as_ref());
+                self.check(returns.as_ref(), found.as_ref(), value.location());
+                Some(Type::Never)
+            }
+            // what a generator yields isn't tied to what its function returns
+            Expression::Yield { value, .. } => {
+                if let Some(value) = value {
+                    _ = self.infer(value, None);
+                }
+                None
+            }
+            // the enum a variant belongs to isn't a type yet
+            Expression::EnumVariant { kind, .. } => {
+                kind.values_mut().for_each(|value| _ = self.infer(value, None));
+                None
+            }
+            Expression::Block { expressions, tail, .. } => self.block(expressions, tail.as_deref_mut(), hint, false),
+            Expression::UnsafeBlock { body, .. } | Expression::ComptimeBlock { body, .. } => self.infer(body, hint),
+            // the value of the body is dropped, as it runs on its own later
+            Expression::Defer { body, .. } => {
+                _ = self.infer(body, None);
+                Some(Type::Unit)
+            }
+            Expression::Assert { condition, message, .. } => {
+                let found = self.infer(condition, Some(&Type::Bool));
+                self.check(Some(&Type::Bool), found.as_ref(), condition.location());
+                if let Some(message) = message {
+                    _ = self.infer(message, None);
+                }
+                Some(Type::Unit)
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                let found = self.infer(condition, Some(&Type::Bool));
+                self.check(Some(&Type::Bool), found.as_ref(), condition.location());
+                let mut typ = self.infer(truthy, hint);
+                if falsy.is_empty() {
+                    return Some(Type::Unit);
+                }
+                let mut same = true;
+                for branch in falsy.iter_mut() {
+                    let found = self.infer(branch, typ.as_ref().filter(|typ| **typ != Type::Never).or(hint));
+                    same &= join(&mut typ, found);
+                }
+                typ.filter(|_| same)
+            }
+            Expression::FunCall { ident, arguments, .. } => {
+                let signature = match ident.as_ref() {
+                    // a variable with the same name hides the function
+                    Expression::Ident { name, .. } if self.lookup(name).is_none() => {
+                        self.functions.get(name.as_ref()).cloned()
+                    }
+                    _ => None,
+                };
+                _ = self.infer(ident, None);
+                for (idx, argument) in arguments.iter_mut().enumerate() {
+                    let expected = signature
+                        .as_ref()
+                        .and_then(|signature| signature.arguments.get(idx).cloned().flatten());
+                    let found = self.infer(argument, expected.as_ref());
+                    self.check(expected.as_ref(), found.as_ref(), argument.location());
+                }
+                signature.map(|signature| signature.returns)
+            }
+            Expression::BinaryOp {
+                operator,
+                lhs,
+                rhs,
+                typ,
+                location,
+            } => {
+                *typ = self.binary(*operator, lhs, rhs, *location, hint);
+                typ.clone()
+            }
+            // what an index gives back depends on what is indexed, which
+            // inference doesn't know the type of yet
+            Expression::Index { target, index, .. } => {
+                _ = self.infer(target, None);
+                _ = self.infer(index, None);
+                None
+            }
+            // the default stands in for the condition, so both are the same
+            Expression::Elvis { condition, default, .. } => {
+                let typ = self.infer(condition, hint);
+                let found = self.infer(default, typ.as_ref().or(hint));
+                self.check(typ.as_ref(), found.as_ref(), default.location());
+                typ
+            }
+            Expression::Match { scrutinee, arms, .. } => {
+                let scrutinee = self.infer(scrutinee, None);
+                let mut typ = None;
+                let mut same = true;
+                for (idx, arm) in arms.iter_mut().enumerate() {
+                    let mut bindings = HashMap::new();
+                    self.pattern(&mut arm.pattern, scrutinee.as_ref(), &mut bindings);
+                    self.scopes.push(bindings);
+                    let found = self.infer(&mut arm.body, typ.as_ref().filter(|typ| **typ != Type::Never).or(hint));
+                    self.scopes.pop();
+                    match idx {
+                        0 => typ = found,
+                        _ => same &= join(&mut typ, found),
+                    }
+                }
+                typ.filter(|_| same)
+            }
+            // the values of the steps are wrapped into a monad inference knows
+            // nothing about, and so is what the names are bound to. a
+            // `return` wraps the value of the block rather than returning
+            // from the function around it
+            Expression::DoBlock { steps, .. } => {
+                let returns = self.returns.take();
+                self.scopes.push(HashMap::new());
+                for step in steps {
+                    _ = self.infer(step.expr_mut(), None);
+                    if let DoStep::Bind { name, .. } = step {
+                        if let Some(scope) = self.scopes.last_mut() {
+                            scope.insert(name.to_string(), None);
+                        }
+                    }
+                }
+                self.scopes.pop();
+                self.returns = returns;
+                None
+            }
+            Expression::Let { name, value, body, .. } => {
+                let typ = self.infer(value, None);
+                self.scopes.push(HashMap::from([(name.to_string(), typ)]));
+                let typ = self.infer(body, hint);
+                self.scopes.pop();
+                typ
+            }
+            // annotated parameters and return types are checked just like the
+            // ones of a function, and anything else is left to inference
+            Expression::Lambda {
+                parameters,
+                parameter_types,
+                return_type,
+                body,
+                ..
+            } => {
+                let parameters = parameters
+                    .iter()
+                    .zip(parameter_types.iter())
+                    .filter_map(|(parameter, typ)| match parameter {
+                        Expression::Ident { name, .. } => Some((name.to_string(), typ.as_ref().and_then(Type::from_type_expr))),
+                        _ => None,
+                    })
+                    .collect();
+                // a lambda returns on its own, not from the function around it
+                let annotated = return_type.as_deref().and_then(Type::from_type_expr);
+                let returns = std::mem::replace(&mut self.returns, annotated.clone());
+                self.scopes.push(parameters);
+                match body.as_mut() {
+                    Expression::Block { expressions, tail, .. } => {
+                        _ = self.block(expressions, tail.as_deref_mut(), annotated.as_ref(), true);
+                    }
+                    body => _ = self.infer(body, annotated.as_ref()),
+                }
+                self.scopes.pop();
+                self.returns = returns;
+                None
+            }
+            // a `TypeTag`, which is a struct inference knows nothing about
+            Expression::RuntimeTypeOf { expr, .. } => {
+                _ = self.infer(expr, None);
+                None
+            }
+            // an `Option`, which inference knows nothing about either
+            Expression::SafeCast { expr, .. } => {
+                _ = self.infer(expr, None);
+                None
+            }
+            // there are no array or tuple types inference knows about yet,
+            // but the lengths of arrays are always a `usize`
+            Expression::Array { elements, .. } | Expression::Tuple { elements, .. } => {
+                elements.iter_mut().for_each(|element| _ = self.infer(element, None));
+                None
+            }
+            Expression::ArrayLen { array, .. } => {
+                _ = self.infer(array, None);
+                Some(Type::UInt(UIntSizes::Usize))
+            }
+            Expression::Sizeof { .. } => Some(Type::UInt(UIntSizes::Usize)),
+            // it stands for many values, none of which is known
+            Expression::Splat { expr, .. } => {
+                _ = self.infer(expr, None);
+                None
+            }
+            // references have no type inference knows about yet
+            Expression::Await { expr: value, .. }
+            | Expression::FieldAccess { target: value, .. }
+            | Expression::TupleAccess { tuple: value, .. }
+            | Expression::Try { expr: value, .. }
+            | Expression::Ref { expr: value, .. }
+            | Expression::Deref { expr: value, .. } => {
+                _ = self.infer(value, None);
+                None
+            }
+            Expression::Not { expr, .. } => {
+                let found = self.infer(expr, Some(&Type::Bool));
+                self.check(Some(&Type::Bool), found.as_ref(), expr.location());
+                Some(Type::Bool)
+            }
+            Expression::InlineAsm { outputs, inputs, .. } => {
+                outputs
+                    .iter_mut()
+                    .chain(inputs)
+                    .for_each(|operand| _ = self.infer(&mut operand.expr, None));
+                None
+            }
+            Expression::Macro { name, .. } if name == KIND_OF_MACRO => Some(Type::Str),
+            Expression::Path { .. } | Expression::Macro { .. } => None,
+        }
+    }
+
+    fn binary<'ast>(
+        &mut self,
+        operator: Operator,
+        lhs: &mut Expression<'ast>,
+        rhs: &mut Expression<'ast>,
+        location: Location,
+        hint: Option<&Type>,
+    ) -> Option<Type> {
+        if matches!(operator, Operator::And | Operator::Or) {
+            for operand in [lhs, rhs] {
+                let found = self.infer(operand, Some(&Type::Bool));
+                self.check(Some(&Type::Bool), found.as_ref(), operand.location());
+            }
+            return Some(Type::Bool);
+        }
+
+        // literals take the type of the other side, like the `1` of `a + 1`,
+        // and the one expected of the whole operation if neither has one
+        let operands = self.natural(lhs).or_else(|| self.natural(rhs));
+        let operands = operands.or_else(|| hint.filter(|_| is_arithmetic(operator)).cloned());
+        let lhs_type = self.infer(lhs, operands.as_ref());
+        let rhs_type = self.infer(rhs, operands.as_ref());
+
+        let typ = match (lhs_type, rhs_type) {
+            (Some(lhs_type), Some(rhs_type)) => match promote(&lhs_type, &rhs_type) {
+                Some(typ) => {
+                    if mixes_signs(&lhs_type, &rhs_type) {
+                        self.warnings.push(TypeError::SignMismatch {
+                            lhs: lhs_type,
+                            rhs: rhs_type,
+                            promoted: typ.clone(),
+                            span: location.into(),
+                        });
+                    }
+                    Some(typ)
+                }
+                None => {
+                    self.check(Some(&lhs_type), Some(&rhs_type), rhs.location());
+                    None
+                }
+            },
+            _ => None,
+        };
+
+        match is_arithmetic(operator) {
+            true => typ,
+            false => Some(Type::Bool),
+        }
+    }
+}
+
+fn is_arithmetic(operator: Operator) -> bool {
+    matches!(
+        operator,
+        Operator::Plus | Operator::Minus | Operator::Star | Operator::Slash
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+    use miette::{NarratableReportHandler, Report};
+
+    use super::*;
+    use crate::Parser;
+
+    type Inferred = (Vec<Statement<'static>>, Result<Vec<TypeError>, Vec<TypeError>>);
+
+    fn infer(source: &'static str, env: &TypeEnv) -> Inferred {
+        let mut statements = Parser::new(source, Lexer::new(source)).parse().unwrap();
+        let result = infer_types(&mut statements, env);
+        (statements, result)
+    }
+
+    /// the source of every numeric literal, with the type it was given
+    fn literals(source: &str, expr: &Expression<'_>, output: &mut Vec<String>) {
+        let location = expr.location();
+        let text = &source[location.start_byte..location.end_byte];
+        match expr {
+            Expression::IntLiteral { size, .. } => output.push(format!("{text}: {}", size.as_ref().unwrap())),
+            Expression::UintLiteral { size, .. } => output.push(format!("{text}: {}", size.as_ref().unwrap())),
+            Expression::FloatLiteral { size, .. } => output.push(format!("{text}: {}", size.as_ref().unwrap())),
+            Expression::Var { value, .. } | Expression::Return { value, .. } | Expression::Assign { value, .. } => {
+                literals(source, value, output)
+            }
+            Expression::BinaryOp { lhs, rhs, .. }
+            | Expression::Index {
+                target: lhs,
+                index: rhs,
+                ..
+            }
+            | Expression::Elvis {
+                condition: lhs,
+                default: rhs,
+                ..
+            } => {
+                literals(source, lhs, output);
+                literals(source, rhs, output);
+            }
+            Expression::FunCall { arguments, .. } => {
+                arguments.iter().for_each(|arg| literals(source, arg, output));
+            }
+            Expression::Block { expressions, tail, .. } => {
+                expressions
+                    .iter()
+                    .chain(tail.as_deref())
+                    .for_each(|expr| literals(source, expr, output));
+            }
+            _ => {}
+        }
+    }
+
+    fn typed_literals(source: &'static str) -> Vec<String> {
+        let (statements, result) = infer(source, &TypeEnv::new());
+        assert_eq!(result, Ok(vec![]));
+
+        let mut output = vec![];
+        for statement in statements.iter().flat_map(Statement::functions) {
+            if let Statement::Fun { body, .. } = statement {
+                body.iter().for_each(|expr| literals(source, expr, &mut output));
+            }
+        }
+        output
+    }
+
+    #[test]
+    fn literals_default_without_a_context() {
+        assert_eq!(
+            typed_literals("fun main() { var a = 1; var b = 1.5; var c = -2; var d = 18446744073709551615; }"),
+            ["1: i64", "1.5: f64", "-2: i64", "18446744073709551615: u64"]
+        );
+    }
+
+    #[test]
+    fn literals_take_the_type_of_their_context() {
+        let source = r#"
+            fun scale(x: i32, by: f32) => u8 {
+                var a: u16 = 1;
+                var b = x + 2;
+                var c = by * 0.5;
+                var d: i8 = { 3 };
+                scale(4, 5.0);
+                return 6;
+            }
+        "#;
+        assert_eq!(
+            typed_literals(source),
+            ["1: u16", "2: i32", "0.5: f32", "3: i8", "4: i32", "5.0: f32", "6: u8"]
+        );
+    }
+
+    #[test]
+    fn operations_are_as_wide_as_their_widest_operand() {
+        let env = TypeEnv::new();
+        let (_, result) = infer("fun f(a: i8, b: i64) => i64 { return a + b; }", &env);
+        assert_eq!(result, Ok(vec![]));
+
+        let (_, result) = infer("fun f(a: i8, b: i16) => i8 { return a * b; }", &env);
+        assert_eq!(
+            result.unwrap_err()[0].to_string(),
+            "mismatched types: expected `i8`, found `i16`"
+        );
+
+        let (_, result) = infer(
+            "fun f(a: u8, b: u32) { var c: u32 = a + b; var d: bool = c == 1; }",
+            &env,
+        );
+        assert_eq!(result, Ok(vec![]));
+    }
+
+    #[test]
+    fn numbers_are_promoted_like_in_c() {
+        let (i8, i32, u8, u32, u64, f32, f64) = (
+            Type::Int(IntSizes::I8),
+            Type::Int(IntSizes::I32),
+            Type::UInt(UIntSizes::U8),
+            Type::UInt(UIntSizes::U32),
+            Type::UInt(UIntSizes::U64),
+            Type::Float(FloatSizes::F32),
+            Type::Float(FloatSizes::F64),
+        );
+        assert_eq!(promote(&i8, &i32), Some(i32.clone()));
+        assert_eq!(promote(&u64, &u8), Some(u64.clone()));
+        assert_eq!(promote(&f64, &f32), Some(f64.clone()));
+        assert_eq!(promote(&i32, &f32), Some(f32.clone()));
+        assert_eq!(promote(&u64, &f32), Some(f32));
+        assert_eq!(promote(&u8, &i32), Some(i32.clone()));
+        assert_eq!(promote(&i32, &u32), Some(u32.clone()));
+        assert_eq!(promote(&i8, &u64), Some(u64));
+        assert_eq!(promote(&Type::Bool, &Type::Bool), Some(Type::Bool));
+        assert_eq!(promote(&Type::Bool, &i32), None);
+    }
+
+    #[test]
+    fn mixing_signs_is_a_warning() {
+        let source = "fun f(a: u8, b: i32, c: i64) { var d = a + b; var e: i64 = b + c; }";
+        let (statements, result) = infer(source, &TypeEnv::new());
+        let warnings = result.unwrap();
+        assert_eq!(
+            warnings.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["`u8` and `i32` differ in signedness"]
+        );
+        assert!(matches!(
+            &warnings[0],
+            TypeError::SignMismatch {
+                promoted: Type::Int(IntSizes::I32),
+                ..
+            }
+        ));
+
+        let Statement::Fun { body, .. } = &statements[0] else {
+            panic!("expected a function");
+        };
+        let types = body
+            .iter()
+            .filter_map(|expr| match expr {
+                Expression::Var { value, .. } => match value.as_ref() {
+                    Expression::BinaryOp { typ, .. } => typ.clone(),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(types, [Type::Int(IntSizes::I32), Type::Int(IntSizes::I64)]);
+    }
+
+    #[test]
+    fn environments_nest() {
+        let mut globals = TypeEnv::new();
+        globals.insert("limit", Type::UInt(UIntSizes::U32));
+        globals.insert("name", Type::Str);
+
+        let env = globals.extend("limit", Type::Bool).extend("ready", Type::Bool);
+        let root = env.parent.as_ref().and_then(|parent| parent.parent.as_ref()).unwrap();
+        assert!(Rc::ptr_eq(&root.bindings, &globals.bindings));
+        assert_eq!(env.lookup("limit"), Some(&Type::Bool));
+        assert_eq!(env.lookup("name"), Some(&Type::Str));
+        assert_eq!(env.lookup("ready"), Some(&Type::Bool));
+        assert_eq!(env.lookup("missing"), None);
+        // extending leaves the parent untouched
+        assert_eq!(globals.lookup("limit"), Some(&Type::UInt(UIntSizes::U32)));
+        assert_eq!(globals.lookup("ready"), None);
+
+        let (_, result) = infer("fun main() { var a: bool = limit; var b: String = name; }", &env);
+        assert_eq!(result, Ok(vec![]));
+    }
+
+    #[test]
+    fn lambdas_are_checked_against_their_annotations() {
+        let mismatches = |source: &'static str| match infer(source, &TypeEnv::new()).1 {
+            Ok(warnings) => {
+                assert_eq!(warnings, vec![], "{source}");
+                vec![]
+            }
+            Err(errors) => errors.iter().map(ToString::to_string).collect(),
+        };
+
+        // without annotations, the types of the parameters are left unknown
+        assert!(mismatches("fun main() { const f = fun(x) { var y: bool = x; 1 }; }").is_empty());
+        assert_eq!(
+            mismatches("fun main() { const f = fun(x: i32) { var y: bool = x; }; }"),
+            ["mismatched types: expected `bool`, found `i32`"]
+        );
+        assert_eq!(
+            mismatches("fun main() { const f = fun(x) => bool { 1u8 }; }"),
+            ["mismatched types: expected `bool`, found `u8`"]
+        );
+        assert!(mismatches("fun main() { const f = fun(x: i32) => i32 { if x > 0 { return x; } x + 1 }; }").is_empty());
+        assert_eq!(
+            mismatches("fun main() => u8 { const f = fun(x: i32) => i32 { return true; }; 1 }"),
+            ["mismatched types: expected `i32`, found `bool`"]
+        );
+    }
+
+    #[test]
+    fn diverging_branches_take_the_type_of_the_others() {
+        let mismatches = |source: &'static str| match infer(source, &TypeEnv::new()).1 {
+            Ok(_) => vec![],
+            Err(errors) => errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        };
+
+        assert!(mismatches("fun f(cond: bool) => i32 { if cond { return 0; } else { 1 } }").is_empty());
+        assert!(mismatches("fun f(cond: bool) => u8 { var a: i32 = if cond { return 0; } else { 1 }; 2 }").is_empty());
+        assert!(
+            mismatches(r#"fun f(n: i32) { var a: bool = match n { 0 => panic!("zero"), _ => true }; }"#).is_empty()
+        );
+        assert_eq!(
+            mismatches(r#"fun f(cond: bool) { var a: bool = if cond { 1 } else { panic!("no"); }; }"#),
+            ["mismatched types: expected `bool`, found `i64`"]
+        );
+        // a function returning `!` can only end by diverging
+        assert!(mismatches(r#"fun fail() => ! { panic!("failed"); }"#).is_empty());
+        assert_eq!(
+            mismatches("fun fail() => ! { 1 }"),
+            ["mismatched types: expected `!`, found `i64`"]
+        );
+
+        let source = "fun f(cond: bool) => i32 { if cond { return 0; } else { 1 } }";
+        let (mut statements, _) = infer(source, &TypeEnv::new());
+        let Statement::Fun { body, .. } = &mut statements[0] else {
+            panic!("expected a function");
+        };
+        let env = TypeEnv::new();
+        let mut inferrer = Inferrer::new(&env);
+        let i32 = Type::Int(IntSizes::I32);
+        assert_eq!(inferrer.infer(&mut body[0], Some(&i32)), Some(i32));
+    }
+
+    #[test]
+    fn only_equal_types_unify() {
+        let location = Location::new(3, 5);
+        assert_eq!(unify(Type::Bool, Type::Bool, location), Ok(Type::Bool));
+
+        let error = unify(Type::Int(IntSizes::I32), Type::Int(IntSizes::I64), location).unwrap_err();
+        assert_eq!(error.to_string(), "mismatched types: expected `i32`, found `i64`");
+        assert!(matches!(error, TypeError::Mismatch { span, .. } if span == location.into()));
+    }
+
+    #[test]
+    fn type_expressions_convert_at_the_boundary() {
+        let named = |name: &'static str, location: Location| TypeExpr::Named {
+            name: Cow::Borrowed(name),
+            location,
+        };
+
+        let env = TypeEnv::new()
+            .extend_annotated("limit", &named("u32", Location::new(0, 3)))
+            .unwrap();
+        assert_eq!(env.lookup("limit"), Some(&Type::UInt(UIntSizes::U32)));
+        assert_eq!(
+            env.lookup_annotation("limit", Location::new(8, 13)),
+            Some(named("u32", Location::new(8, 13)))
+        );
+
+        let unit = TypeExpr::Tuple {
+            elements: vec![],
+            location: Location::new(4, 6),
+        };
+        assert_eq!(unify_annotations(&unit, &unit), Ok(unit.clone()));
+        let error = unify_annotations(&named("bool", Location::new(0, 4)), &unit).unwrap_err();
+        assert_eq!(error.to_string(), "mismatched types: expected `bool`, found `()`");
+
+        // types inference knows nothing of can't be bound nor unified
+        let error = TypeEnv::new()
+            .extend_annotated("point", &named("Point", Location::new(0, 5)))
+            .unwrap_err();
+        assert_eq!(error.to_string(), "`Point` is not a type inference knows");
+        assert!(unify_annotations(&named("Point", Location::new(0, 5)), &unit).is_err());
+    }
+
+    #[test]
+    fn conflicts_are_reported() {
+        let mut env = TypeEnv::new();
+        env.insert("limit", Type::UInt(UIntSizes::U32));
+
+        let source = r#"fun main(a: i32, b: f64) {
+    var c: i32 = a + b;
+    var d: u32 = limit + 1;
+    var e: i32 = limit;
+    if a { var f: bool = 1; }
+}"#;
+        let (_, result) = infer(source, &env);
+        let errors = result.unwrap_err();
+        let found = errors
+            .iter()
+            .map(|error| match error {
+                TypeError::Mismatch { expected, found, .. } => format!("{expected} {found}"),
+                TypeError::SignMismatch { .. } | TypeError::Unknown { .. } => panic!("{error:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(found, ["i32 f64", "i32 u32", "bool i32", "bool i64"]);
+
+        let mut rendered = String::new();
+        let report = Report::new(errors[0].clone()).with_source_code(source);
+        NarratableReportHandler::new()
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+        assert!(
+            rendered.contains("mismatched types: expected `i32`, found `f64`"),
+            "{rendered}"
+        );
+        assert!(rendered.contains("diagnostic code: kura::type_mismatch"), "{rendered}");
+        assert!(
+            rendered.contains("label at line 2, columns 18 to 22: expected `i32` here"),
+            "{rendered}"
+        );
+    }
+}
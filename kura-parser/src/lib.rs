@@ -1,33 +1,408 @@
+pub mod cache;
+pub mod const_eval;
+pub mod constant;
+pub mod cst;
+pub mod doc;
+pub mod dot;
+pub mod error;
 mod expression;
+pub mod folding;
+pub mod harness;
+pub mod highlight;
+pub mod incremental;
+pub mod inference;
+pub mod ops;
+pub mod passes;
+pub mod resolve;
+pub mod scope;
+pub mod session;
+pub mod sexpr;
+pub mod symbols;
+mod types;
+pub mod warning;
 
-use kura_lexer::token::{FloatSizes, IntSizes, Kind, Location, Operator, Token, UIntSizes};
+use std::borrow::Cow;
+
+use kura_lexer::token::{FloatSizes, IntSizes, Kind, Location, Operator, Token, UIntSizes, Value};
 use kura_lexer::{Lexer, TransposeRef};
 
-use crate::expression::{parse_expression, parse_identifier};
+use crate::doc::parse_doc_comment;
+pub use crate::error::{ErrorKind, ParseError};
+pub use crate::expression::MAX_NESTING_DEPTH;
+use crate::expression::{
+    closing_delimiter, end_of_input, parse_attributes, parse_expression_with, parse_identifier, parse_inline,
+    parse_token_tree, parse_use_tree, parse_variable, recover_from_unclosed_delimiter, ParseContext,
+};
+use crate::inference::Type;
+use crate::scope::ScopeTree;
+pub use crate::types::parse_type_expression;
+use crate::types::{parse_generic_params, parse_named, parse_type_expression_with, parse_where_clause};
+use crate::warning::DiagnosticWarning;
+
+/// argument of `#[allow(...)]` that silences the warning about the globs of
+/// a `use`
+const ALLOW_GLOB_IMPORT: &str = "glob_import";
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Statement<'ast> {
     FunArgument {
-        name: &'ast str,
-        arg_type: Box<Expression<'ast>>,
+        name: Cow<'ast, str>,
+        arg_type: Box<TypeExpr<'ast>>,
         location: Location,
     },
     Fun {
-        name: &'ast str,
+        name: Cow<'ast, str>,
+        /// `<T, U: Bound>`, written after the name
+        generics: Vec<GenericParam<'ast>>,
         arguments: Vec<Statement<'ast>>,
         body: Vec<Expression<'ast>>,
-        return_type: Option<Box<Expression<'ast>>>,
+        return_type: Option<Box<TypeExpr<'ast>>>,
+        /// `where T: Bound, for<'a> U: Fn(&'a i32)`, written after the
+        /// return type
+        where_clause: Vec<WherePredicate<'ast>>,
+        /// attributes written before the `fun` keyword, like `#[test]`. the
+        /// location of the function starts at them
+        attributes: Vec<Attribute<'ast>>,
+        /// set by `#[inline(always)]` or `#[inline(never)]`. a bare
+        /// `#[inline]` is only a hint and leaves it unset
+        inline: Option<Inline>,
+        /// written as `unsafe fun`, so its whole body is allowed to do what
+        /// an `unsafe { .. }` block is
+        is_unsafe: bool,
+        /// doc comments written before it, with their markers. they are
+        /// not part of its location
+        doc: Vec<Cow<'ast, str>>,
+        location: Location,
+    },
+    /// `struct Name { field: Type }`. fields are written just like function
+    /// arguments, so they are `FunArgument`s too
+    Struct {
+        name: Cow<'ast, str>,
+        /// `<T, U: Bound>`, written after the name
+        generics: Vec<GenericParam<'ast>>,
+        fields: Vec<Statement<'ast>>,
+        /// attributes written before the `struct` keyword, like
+        /// `#[derive(Debug)]`. unlike on variables, the location of the struct
+        /// starts at them
+        attributes: Vec<Attribute<'ast>>,
+        /// doc comments written before it, with their markers
+        doc: Vec<Cow<'ast, str>>,
+        location: Location,
+    },
+    /// `impl Name { .. }`, or `impl Trait for Name { .. }`, holding the
+    /// functions declared inside of it. `Self` refers to the target in them
+    Impl {
+        /// `<T, U: Bound>`, written right after `impl`
+        generics: Vec<GenericParam<'ast>>,
+        trait_name: Option<Cow<'ast, str>>,
+        target: Cow<'ast, str>,
+        /// generic arguments given to the target, like the `T` of `Stack<T>`
+        arguments: Vec<TypeExpr<'ast>>,
+        functions: Vec<Statement<'ast>>,
+        location: Location,
+    },
+    /// `macro_rules! name { (pattern) => { expansion }; .. }`, a macro
+    /// defined by the rules it is expanded with
+    MacroRules {
+        name: Cow<'ast, str>,
+        rules: Vec<MacroRule<'ast>>,
+        location: Location,
+    },
+    /// `mod name;`, declaring a module that lives in another file
+    Mod { name: Cow<'ast, str>, location: Location },
+    /// `extern crate name;` or `extern crate name as alias;`, declaring a
+    /// dependency of the program. `extern crate self as alias;` names the
+    /// crate being compiled, and is told apart by `is_self`
+    ExternCrate {
+        name: Cow<'ast, str>,
+        alias: Option<Cow<'ast, str>>,
+        is_self: bool,
+        /// every attribute written before it is kept, as they are meant for
+        /// whatever resolves the dependency
+        attributes: Vec<Attribute<'ast>>,
+        location: Location,
+    },
+    /// `use path::to::{item, other::*};`, bringing the items named by
+    /// `tree` into scope. its location starts at the attributes written
+    /// before it
+    Use {
+        tree: UseTree<'ast>,
+        attributes: Vec<Attribute<'ast>>,
+        location: Location,
+    },
+    /// a global, declared with `var` or `const` outside of any function.
+    /// its location starts at the attributes written before it
+    Static {
+        mutable: bool,
+        name: Cow<'ast, str>,
+        typ: Option<Box<TypeExpr<'ast>>>,
+        value: Box<Expression<'ast>>,
+        attributes: Vec<Attribute<'ast>>,
         location: Location,
     },
 }
 
-#[derive(Debug)]
+/// what a `use` imports, as written. `a::{b::{c, d}, e}` is a path through
+/// `a` into a group of the path through `b` into `c` and `d`, and of `e`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum UseTree<'ast> {
+    /// the item a path ends at
+    Leaf(Cow<'ast, str>),
+    /// `segment::child`
+    Path {
+        segment: Cow<'ast, str>,
+        child: Box<UseTree<'ast>>,
+    },
+    /// `{a, b::c}`, every tree inside of the braces
+    Group(Vec<UseTree<'ast>>),
+    /// `*`, every item of the path it ends
+    Glob,
+}
+
+impl UseTree<'_> {
+    pub fn into_owned(self) -> UseTree<'static> {
+        match self {
+            UseTree::Leaf(name) => UseTree::Leaf(Cow::Owned(name.into_owned())),
+            UseTree::Path { segment, child } => UseTree::Path {
+                segment: Cow::Owned(segment.into_owned()),
+                child: Box::new(child.into_owned()),
+            },
+            UseTree::Group(trees) => UseTree::Group(trees.into_iter().map(UseTree::into_owned).collect()),
+            UseTree::Glob => UseTree::Glob,
+        }
+    }
+}
+
+impl std::fmt::Display for UseTree<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            UseTree::Leaf(name) => write!(f, "{name}"),
+            UseTree::Path { segment, child } => write!(f, "{segment}::{child}"),
+            UseTree::Group(trees) => {
+                let trees = trees.iter().map(|tree| tree.to_string()).collect::<Vec<_>>();
+                write!(f, "{{{}}}", trees.join(", "))
+            }
+            UseTree::Glob => write!(f, "*"),
+        }
+    }
+}
+
+/// a rule of `macro_rules!`. both sides are kept as the raw tokens inside
+/// of their delimiters, as they only get a meaning once the macro is
+/// expanded
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MacroRule<'ast> {
+    pub pattern: Vec<Token<'ast>>,
+    pub expansion: Vec<Token<'ast>>,
+    pub location: Location,
+}
+
+impl MacroRule<'_> {
+    pub fn into_owned(self) -> MacroRule<'static> {
+        MacroRule {
+            pattern: self.pattern.into_iter().map(Token::into_owned).collect(),
+            expansion: self.expansion.into_iter().map(Token::into_owned).collect(),
+            location: self.location,
+        }
+    }
+}
+
+/// a generic parameter like the `T: Show + Debug` of `impl<T: Show + Debug>`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct GenericParam<'ast> {
+    pub name: Cow<'ast, str>,
+    /// traits the type given to it has to implement
+    pub bounds: Vec<TypeExpr<'ast>>,
+    pub location: Location,
+}
+
+impl GenericParam<'_> {
+    pub fn into_owned(self) -> GenericParam<'static> {
+        GenericParam {
+            name: Cow::Owned(self.name.into_owned()),
+            bounds: self.bounds.into_iter().map(TypeExpr::into_owned).collect(),
+            location: self.location,
+        }
+    }
+}
+
+/// a lifetime parameter like the `'a` of `for<'a>`. the name is kept
+/// without its quote
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LifetimeParam<'ast> {
+    pub name: Cow<'ast, str>,
+    pub location: Location,
+}
+
+impl LifetimeParam<'_> {
+    pub fn into_owned(self) -> LifetimeParam<'static> {
+        LifetimeParam {
+            name: Cow::Owned(self.name.into_owned()),
+            location: self.location,
+        }
+    }
+}
+
+/// the lifetime of a reference type, `Elided` when none is written as in
+/// `&T`, for it to be inferred
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Lifetime<'ast> {
+    Elided,
+    /// the name of the lifetime, without its quote
+    Named(Cow<'ast, str>),
+}
+
+impl Lifetime<'_> {
+    pub fn name(&self) -> Option<&str> {
+        match self {
+            Lifetime::Elided => None,
+            Lifetime::Named(name) => Some(name),
+        }
+    }
+
+    pub fn into_owned(self) -> Lifetime<'static> {
+        match self {
+            Lifetime::Elided => Lifetime::Elided,
+            Lifetime::Named(name) => Lifetime::Named(Cow::Owned(name.into_owned())),
+        }
+    }
+}
+
+/// a predicate of a where clause, like `T: Show + Debug`. one written as
+/// `for<'a> T: Trait<'a>` has to hold for every lifetime it introduces,
+/// which nothing checks to be used by its bounds
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct WherePredicate<'ast> {
+    /// the lifetimes of the `for<..>` before it, if any
+    pub lifetimes: Vec<LifetimeParam<'ast>>,
+    /// the type on the left of the colon
+    pub bounded: TypeExpr<'ast>,
+    /// traits the bounded type has to implement
+    pub bounds: Vec<TypeExpr<'ast>>,
+    pub location: Location,
+}
+
+impl WherePredicate<'_> {
+    pub fn into_owned(self) -> WherePredicate<'static> {
+        WherePredicate {
+            lifetimes: self.lifetimes.into_iter().map(LifetimeParam::into_owned).collect(),
+            bounded: self.bounded.into_owned(),
+            bounds: self.bounds.into_iter().map(TypeExpr::into_owned).collect(),
+            location: self.location,
+        }
+    }
+}
+
+impl std::fmt::Display for WherePredicate<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.lifetimes.is_empty() {
+            let lifetimes = self
+                .lifetimes
+                .iter()
+                .map(|lifetime| format!("'{}", lifetime.name))
+                .collect::<Vec<_>>();
+            write!(f, "for<{}> ", lifetimes.join(", "))?;
+        }
+        let bounds = self.bounds.iter().map(|bound| bound.to_string()).collect::<Vec<_>>();
+        write!(f, "{}: {}", self.bounded, bounds.join(" + "))
+    }
+}
+
+impl std::fmt::Display for GenericParam<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.bounds.is_empty() {
+            let bounds = self.bounds.iter().map(|bound| bound.to_string()).collect::<Vec<_>>();
+            write!(f, ": {}", bounds.join(" + "))?;
+        }
+        Ok(())
+    }
+}
+
+/// an attribute like `#[allow(shadow)]`, attached to the item after it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Attribute<'ast> {
+    pub name: Cow<'ast, str>,
+    pub arguments: Vec<Cow<'ast, str>>,
+    /// arguments like `expected = "message"`, holding the contents of the
+    /// string. they are written after the plain ones
+    pub values: Vec<(Cow<'ast, str>, Cow<'ast, str>)>,
+    pub location: Location,
+}
+
+impl Attribute<'_> {
+    pub fn into_owned(self) -> Attribute<'static> {
+        Attribute {
+            name: Cow::Owned(self.name.into_owned()),
+            arguments: self
+                .arguments
+                .into_iter()
+                .map(|argument| Cow::Owned(argument.into_owned()))
+                .collect(),
+            values: self
+                .values
+                .into_iter()
+                .map(|(key, value)| (Cow::Owned(key.into_owned()), Cow::Owned(value.into_owned())))
+                .collect(),
+            location: self.location,
+        }
+    }
+
+    /// the string given to `key`, like `message` for `expected = "message"`
+    pub fn value(&self, key: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .find_map(|(name, value)| (name == key).then_some(value.as_ref()))
+    }
+
+    /// whether this is `#[allow(lint)]`
+    pub fn allows(&self, lint: &str) -> bool {
+        self.name == "allow" && self.arguments.iter().any(|argument| argument == lint)
+    }
+}
+
+/// whether `#[inline(always)]` or `#[inline(never)]` was asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Inline {
+    pub always: bool,
+}
+
+impl std::fmt::Display for Attribute<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#[{}", self.name)?;
+        let arguments = self
+            .arguments
+            .iter()
+            .map(|argument| argument.to_string())
+            .chain(self.values.iter().map(|(key, value)| format!("{key} = \"{value}\"")))
+            .collect::<Vec<_>>();
+        if !arguments.is_empty() {
+            write!(f, "({})", arguments.join(", "))?;
+        }
+        write!(f, "]")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Expression<'ast> {
     Var {
         mutable: bool,
-        name: &'ast str,
-        typ: Option<Box<Expression<'ast>>>,
+        name: Cow<'ast, str>,
+        typ: Option<Box<TypeExpr<'ast>>>,
         value: Box<Expression<'ast>>,
+        /// attributes written before the `var` or `const` keyword, which the
+        /// location doesn't include
+        attributes: Vec<Attribute<'ast>>,
         location: Location,
     },
     Bool {
@@ -48,18 +423,43 @@ pub enum Expression<'ast> {
         ident: Box<Expression<'ast>>,
         location: Location,
         arguments: Vec<Expression<'ast>>,
+        /// whether this is a call of a function to itself whose value the
+        /// function returns, so it can reuse its frame. `mark_tail_calls`
+        /// tells it, it is always `false` out of the parser
+        tail_call: bool,
     },
     Assign {
         ident: Box<Expression<'ast>>,
         location: Location,
         value: Box<Expression<'ast>>,
     },
+    /// `a += b`, and the same for `-=`, `*=` and `/=`. `operator` is the one
+    /// applied, so `Plus` for `+=`. it is sugar for `a = a + b`, which is
+    /// what `passes::normalize` turns it into
+    CompoundAssign {
+        operator: Operator,
+        ident: Box<Expression<'ast>>,
+        value: Box<Expression<'ast>>,
+        location: Location,
+    },
     Ident {
-        name: &'ast str,
+        name: Cow<'ast, str>,
+        location: Location,
+    },
+    /// a name qualified by the modules it lives in, like `std::io::write`.
+    /// there are always at least two segments, a single name is an `Ident`
+    Path {
+        segments: Vec<Cow<'ast, str>>,
         location: Location,
     },
     Block {
         expressions: Vec<Expression<'ast>>,
+        /// the last expression when it isn't followed by a semicolon, which
+        /// is what the block evaluates to, like `x + 1` in `{ f(); x + 1 }`
+        tail: Option<Box<Expression<'ast>>>,
+        /// whether the block can ever run, which is only known after
+        /// `passes::analyze_reachability` runs over it
+        reachable: Option<bool>,
         location: Location,
     },
     UintLiteral {
@@ -81,156 +481,1955 @@ pub enum Expression<'ast> {
         operator: Operator,
         lhs: Box<Expression<'ast>>,
         rhs: Box<Expression<'ast>>,
+        /// the type of the result, once inference has told it
+        typ: Option<Type>,
+        location: Location,
+    },
+    /// `assert(condition, message)`, where the message is optional
+    Assert {
+        condition: Box<Expression<'ast>>,
+        message: Option<Box<Expression<'ast>>>,
+        location: Location,
+    },
+    /// `condition ?: default`, which is `condition` when it is truthy and
+    /// `default` otherwise
+    Elvis {
+        condition: Box<Expression<'ast>>,
+        default: Box<Expression<'ast>>,
         location: Location,
     },
     Return {
         value: Box<Expression<'ast>>,
         location: Location,
     },
+    /// `yield value;`, or `yield;` with no value, handing a value out of a
+    /// generator. like a return, it owns its semicolon
+    Yield {
+        value: Option<Box<Expression<'ast>>>,
+        location: Location,
+    },
+    StringLiteral {
+        value: Cow<'ast, str>,
+        location: Location,
+    },
+    /// a string with expressions embedded into it, like `"hello {name}"`.
+    /// `literals` are the `StringLiteral` parts around the expressions, so
+    /// there is always one more of them than there are expressions
+    Interpolated {
+        literals: Vec<Expression<'ast>>,
+        expressions: Vec<Expression<'ast>>,
+        location: Location,
+    },
+    /// a macro invocation like `println!("{}", name)`. the arguments are kept
+    /// as the raw tokens between the delimiters, as they are only given a
+    /// meaning once the macro is expanded
+    Macro {
+        name: Cow<'ast, str>,
+        tokens: Vec<Token<'ast>>,
+        location: Location,
+    },
+    /// `typeof!(expr)`, a value describing the type `expr` has at runtime.
+    /// it lowers to a call to the built-in `__runtime_type_of__`, which
+    /// gives back the `TypeTag` struct of the standard library
+    RuntimeTypeOf {
+        expr: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `[a, b, c]`, whose length is the number of elements written
+    Array {
+        elements: Vec<Expression<'ast>>,
+        location: Location,
+    },
+    /// `(a, b)`. a tuple of one element needs a trailing comma, as in `(a,)`,
+    /// to tell it apart from parentheses around an expression
+    Tuple {
+        elements: Vec<Expression<'ast>>,
+        location: Location,
+    },
+    /// `len!(array)`, the number of elements of `array`. it is folded into a
+    /// literal by `fold_constants` when the length is known at compile
+    /// time, and lowers to a call to the built-in `__array_len__` otherwise
+    ArrayLen {
+        array: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `splat!(values)`, an argument of a call standing for every element of
+    /// `values`, for variadic functions. the code generator expands it into
+    /// as many arguments as there are elements
+    Splat {
+        expr: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `asm!("mov {0}, 1" : "=r"(out) : "r"(input) : "rax")`, assembly
+    /// run in place along with the values it writes to, the values it reads
+    /// and the registers it clobbers. the template and clobbers are kept as
+    /// written, and it can only be used inside of an `unsafe` block
+    InlineAsm {
+        template: Cow<'ast, str>,
+        outputs: Vec<AsmOperand<'ast>>,
+        inputs: Vec<AsmOperand<'ast>>,
+        clobbers: Vec<Cow<'ast, str>>,
+        location: Location,
+    },
+    /// `expr try_as T`, converting `expr` into an `Option<T>` that is `None`
+    /// when its value doesn't fit in `T`
+    SafeCast {
+        expr: Box<Expression<'ast>>,
+        typ: Box<TypeExpr<'ast>>,
+        location: Location,
+    },
+    /// `sizeof<T>()`, the number of bytes a value of `T` takes. it folds
+    /// into a literal when the size of `T` is known at compile time
+    Sizeof {
+        typ: Box<TypeExpr<'ast>>,
+        location: Location,
+    },
+    /// `expr.await`, suspending until the awaited value is ready
+    Await {
+        expr: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `expr.field`
+    FieldAccess {
+        target: Box<Expression<'ast>>,
+        field: Cow<'ast, str>,
+        location: Location,
+    },
+    /// `tuple.0`, the element of `tuple` at `index`
+    TupleAccess {
+        tuple: Box<Expression<'ast>>,
+        index: u32,
+        location: Location,
+    },
+    /// `target[index]`
+    Index {
+        target: Box<Expression<'ast>>,
+        index: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `expr?`, giving back the error of `expr` from the function it is in
+    /// when there is one
+    Try {
+        expr: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// an anonymous function like `fun(x) { x * 2 }`. the parameters are
+    /// `Ident`s, each with the type it is annotated with, if any, as in
+    /// `fun(x: i32) => i32 { x * 2 }`. a block right after the arguments of
+    /// a call is a lambda without parameters too, passed as the last
+    /// argument, like `map(list) { x * 2 }`
+    Lambda {
+        parameters: Vec<Expression<'ast>>,
+        parameter_types: Vec<Option<TypeExpr<'ast>>>,
+        return_type: Option<Box<TypeExpr<'ast>>>,
+        body: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `let name = value in body`, binding `name` only while evaluating
+    /// `body`, which is what the whole expression evaluates to
+    Let {
+        name: Cow<'ast, str>,
+        value: Box<Expression<'ast>>,
+        body: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `unsafe { .. }`, a block allowed to do what the compiler can't
+    /// prove to be safe
+    UnsafeBlock {
+        body: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `&expr` or `&mut expr`, a reference to the value of `expr`
+    Ref {
+        mutable: bool,
+        expr: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `*expr`, the value `expr` refers to
+    Deref {
+        expr: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `!expr`, the negation of the boolean `expr`
+    Not {
+        expr: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `match scrutinee { pattern => body, .. }`, evaluating to the body of
+    /// the first arm whose pattern matches. `passes::check_exhaustiveness`
+    /// tells whether every value is matched by some arm
+    Match {
+        scrutinee: Box<Expression<'ast>>,
+        arms: Vec<MatchArm<'ast>>,
+        location: Location,
+    },
+    /// `comptime { .. }`, a block whose value must be known at compile time.
+    /// `passes::check_comptime` rejects the ones that can't be evaluated
+    ComptimeBlock {
+        body: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `defer { .. }`, a block that runs when the block it is written in
+    /// exits, however it does, rather than where it is written. running it
+    /// is up to the backend. the defers of a block run in the reverse order
+    /// they were written in, so what was set up last is torn down first
+    Defer {
+        body: Box<Expression<'ast>>,
+        location: Location,
+    },
+    /// `Color::Red`, `Shape::Circle(1.0)` or `Shape::Rect { w: 1, h: 2 }`,
+    /// a value of an enum. the path always ends in an uppercase name
+    EnumVariant {
+        path: Vec<Cow<'ast, str>>,
+        kind: VariantKind<'ast, Expression<'ast>>,
+        location: Location,
+    },
+    /// `do { x <- foo(); return x + 1 }`, a sequence of monadic steps. the
+    /// names bound by a step are visible to the ones after it
+    DoBlock {
+        steps: Vec<DoStep<'ast>>,
+        location: Location,
+    },
 }
 
-impl Expression<'_> {
-    fn location(&self) -> Location {
+/// a step of a `do` block, which is separated from the next one by a
+/// semicolon
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum DoStep<'ast> {
+    /// `name <- expr`, binding `name` to the value wrapped by `expr`
+    Bind {
+        name: Cow<'ast, str>,
+        expr: Expression<'ast>,
+        location: Location,
+    },
+    /// `expr`, whose value is sequenced without being bound
+    Seq(Expression<'ast>),
+}
+
+impl<'ast> DoStep<'ast> {
+    /// the expression the step evaluates
+    pub fn expr(&self) -> &Expression<'ast> {
         match self {
-            Expression::Var { location, .. } => *location,
-            Expression::If { location, .. } => *location,
-            Expression::Ident { location, .. } => *location,
-            Expression::Bool { location, .. } => *location,
-            Expression::Block { location, .. } => *location,
-            Expression::FunCall { location, .. } => *location,
-            Expression::Assign { location, .. } => *location,
-            Expression::UintLiteral { location, .. } => *location,
-            Expression::Return { location, .. } => *location,
-            Expression::FloatLiteral { location, .. } => *location,
-            Expression::IntLiteral { location, .. } => *location,
-            Expression::BinaryOp { location, .. } => *location,
+            DoStep::Bind { expr, .. } | DoStep::Seq(expr) => expr,
         }
     }
-}
-
-pub struct Parser<'par> {
-    pub source: &'par str,
-    pub lexer: Lexer<'par>,
-}
 
-impl<'par> Parser<'par> {
-    pub fn new(source: &'par str, lexer: Lexer<'par>) -> Self {
-        Self { source, lexer }
+    pub fn expr_mut(&mut self) -> &mut Expression<'ast> {
+        match self {
+            DoStep::Bind { expr, .. } | DoStep::Seq(expr) => expr,
+        }
     }
 
-    pub fn parse(mut self) -> Result<Vec<Statement<'par>>, String> {
-        let mut statements = vec![];
-
-        while !self.lexer.is_empty() {
-            let statement = self.parse_statement()?;
-            statements.push(statement);
+    /// applies `f` to the expression of the step, keeping the name it binds
+    pub fn map(self, f: impl FnOnce(Expression<'ast>) -> Expression<'ast>) -> DoStep<'ast> {
+        match self {
+            DoStep::Bind { name, expr, location } => DoStep::Bind {
+                name,
+                expr: f(expr),
+                location,
+            },
+            DoStep::Seq(expr) => DoStep::Seq(f(expr)),
         }
+    }
 
-        Ok(statements)
+    pub fn location(&self) -> Location {
+        match self {
+            DoStep::Bind { location, .. } => *location,
+            DoStep::Seq(expr) => expr.location(),
+        }
     }
 
-    fn parse_statement(&mut self) -> Result<Statement<'par>, String> {
-        match self.lexer.peek().transpose().map_err(|e| e.to_string())? {
-            Some(token) => match &token.kind {
-                Kind::Fun => self.parse_function(),
-                t => todo!("{t:?}"),
+    pub fn into_owned(self) -> DoStep<'static> {
+        match self {
+            DoStep::Bind { name, expr, location } => DoStep::Bind {
+                name: Cow::Owned(name.into_owned()),
+                expr: expr.into_owned(),
+                location,
             },
-            _ => todo!(),
+            DoStep::Seq(expr) => DoStep::Seq(expr.into_owned()),
         }
     }
+}
 
-    fn parse_function_args(&mut self, _: &Token<'_>) -> Result<Vec<Statement<'par>>, String> {
-        let mut arguments = vec![];
+/// the values given to an enum variant, either when constructing it or when
+/// matching on it, so `T` is an `Expression` or a `Pattern`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum VariantKind<'ast, T> {
+    /// `Color::Red`
+    Unit,
+    /// `Shape::Circle(1.0)`
+    Tuple(Vec<T>),
+    /// `Shape::Rect { w: 1, h: 2 }`
+    Struct(Vec<VariantField<'ast, T>>),
+}
 
-        loop {
-            let (arg_name_expr, arg_name) = parse_identifier(&mut self.lexer)?;
-            self.lexer
-                .expect(Kind::Op(Operator::Colon))
-                .map_err(|e| e.to_string())?;
-            let (arg_type, _) = parse_identifier(&mut self.lexer)?;
+/// a named value of a struct variant, like the `w: 1` of `Shape::Rect { w: 1 }`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct VariantField<'ast, T> {
+    pub name: Cow<'ast, str>,
+    pub value: T,
+    pub location: Location,
+}
 
-            arguments.push(Statement::FunArgument {
-                name: arg_name,
-                location: Location::new(arg_name_expr.location().start_byte, arg_type.location().end_byte),
-                arg_type: Box::new(arg_type),
-            });
+impl<'ast, T> VariantKind<'ast, T> {
+    /// every value of the variant, in the order they are written
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        let (elements, fields) = match self {
+            VariantKind::Unit => (&[][..], &[][..]),
+            VariantKind::Tuple(elements) => (elements.as_slice(), &[][..]),
+            VariantKind::Struct(fields) => (&[][..], fields.as_slice()),
+        };
+        elements.iter().chain(fields.iter().map(|field| &field.value))
+    }
 
-            match self.lexer.peek().transpose().map_err(|e| e.to_string())? {
-                Some(token) => match token.kind {
-                    Kind::Op(Operator::RightParen) => break,
-                    Kind::Op(Operator::Comma) => (),
-                    _ => return Err(token.location.to_string())?,
-                },
-                None => break,
-            }
-        }
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> + use<'_, 'ast, T> {
+        let (elements, fields) = match self {
+            VariantKind::Unit => (&mut [][..], &mut [][..]),
+            VariantKind::Tuple(elements) => (elements.as_mut_slice(), &mut [][..]),
+            VariantKind::Struct(fields) => (&mut [][..], fields.as_mut_slice()),
+        };
+        elements
+            .iter_mut()
+            .chain(fields.iter_mut().map(|field| &mut field.value))
+    }
 
-        Ok(arguments)
+    /// applies `f` to every value of the variant, keeping the names of its
+    /// fields
+    pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> VariantKind<'ast, U> {
+        match self {
+            VariantKind::Unit => VariantKind::Unit,
+            VariantKind::Tuple(elements) => VariantKind::Tuple(elements.into_iter().map(f).collect()),
+            VariantKind::Struct(fields) => VariantKind::Struct(
+                fields
+                    .into_iter()
+                    .map(|field| VariantField {
+                        name: field.name,
+                        value: f(field.value),
+                        location: field.location,
+                    })
+                    .collect(),
+            ),
+        }
     }
 
-    fn parse_function(&mut self) -> Result<Statement<'par>, String> {
-        let keyword = self.lexer.expect(Kind::Fun).map_err(|e| e.to_string())?;
-        let (_, fun_name) = parse_identifier(&mut self.lexer)?;
-        self.lexer
-            .expect(Kind::Op(Operator::LeftParen))
-            .map_err(|e| e.to_string())?;
+    pub fn into_owned_with<U>(self, mut into_owned: impl FnMut(T) -> U) -> VariantKind<'static, U> {
+        match self {
+            VariantKind::Unit => VariantKind::Unit,
+            VariantKind::Tuple(elements) => VariantKind::Tuple(elements.into_iter().map(into_owned).collect()),
+            VariantKind::Struct(fields) => VariantKind::Struct(
+                fields
+                    .into_iter()
+                    .map(|field| VariantField {
+                        name: Cow::Owned(field.name.into_owned()),
+                        value: into_owned(field.value),
+                        location: field.location,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
 
-        let mut arguments = vec![];
-        if let Some(next) = self.lexer.peek().transpose().map_err(|e| e.to_string())? {
-            if !matches!(next.kind, Kind::Op(Operator::RightParen)) {
-                arguments = self.parse_function_args(&keyword)?;
+impl<T: std::fmt::Display> std::fmt::Display for VariantKind<'_, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VariantKind::Unit => Ok(()),
+            VariantKind::Tuple(elements) => {
+                let elements = elements.iter().map(ToString::to_string).collect::<Vec<_>>();
+                write!(f, "({})", elements.join(", "))
+            }
+            VariantKind::Struct(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|field| format!("{}: {}", field.name, field.value))
+                    .collect::<Vec<_>>();
+                write!(f, " {{ {} }}", fields.join(", "))
             }
         }
+    }
+}
 
-        // after parsing argument list we need to consume the closing parenthesis
-        self.lexer
-            .expect(Kind::Op(Operator::RightParen))
-            .map_err(|e| e.to_string())?;
-
-        // after the argument list of a function, there can be an optional return type annotation
-        // => <TYPE> {
-        // before the left brace, but its fine to be ommited
-        let has_return = match self.lexer.peek().transpose().map_err(|e| e.to_string())? {
-            Some(token) => matches!(token.kind, Kind::Op(Operator::ThickArrow)),
-            _ => false,
-        };
+/// `"=r"(out)`, a value given to or taken out of inline assembly, along with
+/// the constraint telling where the assembly finds it. the constraint is
+/// kept as written
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct AsmOperand<'ast> {
+    pub constraint: Cow<'ast, str>,
+    pub expr: Expression<'ast>,
+    pub location: Location,
+}
 
-        let return_type = if has_return {
-            self.lexer.next().transpose().map_err(|e| e.to_string())?;
-            let (identifier, _) = parse_identifier(&mut self.lexer)?;
-            Some(Box::new(identifier))
-        } else {
-            None
-        };
+impl AsmOperand<'_> {
+    pub fn into_owned(self) -> AsmOperand<'static> {
+        AsmOperand {
+            constraint: Cow::Owned(self.constraint.into_owned()),
+            expr: self.expr.into_owned(),
+            location: self.location,
+        }
+    }
+}
 
-        self.lexer
-            .expect(Kind::Op(Operator::LeftBrace))
-            .map_err(|e| e.to_string())?;
+/// an arm of a `match`, like `0 => "none"`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MatchArm<'ast> {
+    pub pattern: Pattern<'ast>,
+    pub body: Expression<'ast>,
+    pub location: Location,
+}
 
-        let mut body = vec![];
+impl MatchArm<'_> {
+    pub fn into_owned(self) -> MatchArm<'static> {
+        MatchArm {
+            pattern: self.pattern.into_owned(),
+            body: self.body.into_owned(),
+            location: self.location,
+        }
+    }
+}
 
-        while let Some(token) = self.lexer.peek().transpose().map_err(|e| e.to_string())? {
-            if let Kind::Op(Operator::RightBrace) = token.kind {
-                break;
+/// what the value matched by a `match` is compared against
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Pattern<'ast> {
+    /// `_`, matching anything
+    Wildcard { location: Location },
+    /// a literal, matching the values equal to it. it is always a `Bool`,
+    /// a `StringLiteral`, or a number literal
+    Literal(Box<Expression<'ast>>),
+    /// a name, matching anything and binding it over the body of the arm
+    Binding { name: Cow<'ast, str>, location: Location },
+    /// `MAX_SIZE` or `config::LIMIT`, matching the constant it names
+    Path {
+        segments: Vec<Cow<'ast, str>>,
+        location: Location,
+    },
+    /// `Color::Red` or `Shape::Circle(r)`, matching a variant of an enum
+    /// whose values match the patterns given to it
+    Variant {
+        path: Vec<Cow<'ast, str>>,
+        kind: VariantKind<'ast, Pattern<'ast>>,
+        location: Location,
+    },
+}
+
+impl<'ast> Pattern<'ast> {
+    pub fn location(&self) -> Location {
+        match self {
+            Pattern::Literal(literal) => literal.location(),
+            Pattern::Wildcard { location }
+            | Pattern::Binding { location, .. }
+            | Pattern::Path { location, .. }
+            | Pattern::Variant { location, .. } => *location,
+        }
+    }
+
+    /// every name bound by the pattern, including the ones nested in the
+    /// values of a variant
+    pub fn bindings(&self) -> Vec<(&Cow<'ast, str>, Location)> {
+        match self {
+            Pattern::Binding { name, location } => vec![(name, *location)],
+            Pattern::Variant { kind, .. } => kind.values().flat_map(Pattern::bindings).collect(),
+            Pattern::Wildcard { .. } | Pattern::Literal(_) | Pattern::Path { .. } => vec![],
+        }
+    }
+
+    /// whether the pattern matches every value, like `_` or a binding
+    pub fn is_irrefutable(&self) -> bool {
+        matches!(self, Pattern::Wildcard { .. } | Pattern::Binding { .. })
+    }
+
+    pub fn into_owned(self) -> Pattern<'static> {
+        match self {
+            Pattern::Wildcard { location } => Pattern::Wildcard { location },
+            Pattern::Literal(literal) => Pattern::Literal(Box::new(literal.into_owned())),
+            Pattern::Binding { name, location } => Pattern::Binding {
+                name: Cow::Owned(name.into_owned()),
+                location,
+            },
+            Pattern::Path { segments, location } => Pattern::Path {
+                segments: segments
+                    .into_iter()
+                    .map(|segment| Cow::Owned(segment.into_owned()))
+                    .collect(),
+                location,
+            },
+            Pattern::Variant { path, kind, location } => Pattern::Variant {
+                path: path
+                    .into_iter()
+                    .map(|segment| Cow::Owned(segment.into_owned()))
+                    .collect(),
+                kind: kind.into_owned_with(Pattern::into_owned),
+                location,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Pattern<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Wildcard { .. } => write!(f, "_"),
+            Pattern::Literal(literal) => match literal.as_ref() {
+                Expression::Bool { value, .. } => write!(f, "{value}"),
+                Expression::StringLiteral { value, .. } => write!(f, "\"{value}\""),
+                Expression::IntLiteral { value, size, .. } => {
+                    write!(
+                        f,
+                        "{value}{}",
+                        size.as_ref().map(ToString::to_string).unwrap_or_default()
+                    )
+                }
+                Expression::UintLiteral { value, size, .. } => {
+                    write!(
+                        f,
+                        "{value}{}",
+                        size.as_ref().map(ToString::to_string).unwrap_or_default()
+                    )
+                }
+                Expression::FloatLiteral { value, size, .. } => {
+                    write!(
+                        f,
+                        "{value:?}{}",
+                        size.as_ref().map(ToString::to_string).unwrap_or_default()
+                    )
+                }
+                _ => unreachable!("patterns only hold literals"),
+            },
+            Pattern::Binding { name, .. } => write!(f, "{name}"),
+            Pattern::Path { segments, .. } => write!(f, "{}", segments.join("::")),
+            Pattern::Variant { path, kind, .. } => write!(f, "{}{kind}", path.join("::")),
+        }
+    }
+}
+
+/// a type written in an annotation, like the type of an argument
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum TypeExpr<'ast> {
+    /// a type named on its own, like `i32` or `std::io::File`
+    Named { name: Cow<'ast, str>, location: Location },
+    /// `fun(i32, bool) => bool`. without a `=>` it returns nothing
+    Fn {
+        parameters: Vec<TypeExpr<'ast>>,
+        returns: Option<Box<TypeExpr<'ast>>>,
+        location: Location,
+    },
+    /// `[i32; 10]`
+    Array {
+        element: Box<TypeExpr<'ast>>,
+        length: u64,
+        location: Location,
+    },
+    /// `(i32, f64)`. `()` is the empty tuple, and `(i32,)` has a single
+    /// element, as `(i32)` is just `i32`
+    Tuple {
+        elements: Vec<TypeExpr<'ast>>,
+        location: Location,
+    },
+    /// `*const i32` or `*mut i32`
+    Ptr {
+        mutable: bool,
+        pointee: Box<TypeExpr<'ast>>,
+        location: Location,
+    },
+    /// `Option<T>`
+    Generic {
+        name: Cow<'ast, str>,
+        arguments: Vec<TypeExpr<'ast>>,
+        location: Location,
+    },
+    /// `impl Trait`, some type implementing `Trait`
+    ImplTrait {
+        bound: Box<TypeExpr<'ast>>,
+        location: Location,
+    },
+    /// `&T`, `&mut T`, `&'a T` or `&'a mut T`
+    Ref {
+        mutable: bool,
+        lifetime: Lifetime<'ast>,
+        referent: Box<TypeExpr<'ast>>,
+        location: Location,
+    },
+    /// `Fn(&'a i32) -> &'a i32`, a trait bound written like the signature
+    /// of a function. only bounds can be written like this
+    FnTrait {
+        name: Cow<'ast, str>,
+        parameters: Vec<TypeExpr<'ast>>,
+        returns: Option<Box<TypeExpr<'ast>>>,
+        location: Location,
+    },
+    /// `'a`, given as a generic argument like in `Trait<'a>`. the name is
+    /// kept without its quote
+    Lifetime { name: Cow<'ast, str>, location: Location },
+    /// `!`, the type of expressions that never produce a value, like a
+    /// `return`
+    Never { location: Location },
+}
+
+impl TypeExpr<'_> {
+    pub fn location(&self) -> Location {
+        match self {
+            TypeExpr::Named { location, .. }
+            | TypeExpr::Fn { location, .. }
+            | TypeExpr::Array { location, .. }
+            | TypeExpr::Tuple { location, .. }
+            | TypeExpr::Ptr { location, .. }
+            | TypeExpr::Generic { location, .. }
+            | TypeExpr::ImplTrait { location, .. }
+            | TypeExpr::Ref { location, .. }
+            | TypeExpr::FnTrait { location, .. }
+            | TypeExpr::Lifetime { location, .. }
+            | TypeExpr::Never { location } => *location,
+        }
+    }
+
+    pub fn into_owned(self) -> TypeExpr<'static> {
+        let owned = |types: Vec<TypeExpr<'_>>| types.into_iter().map(TypeExpr::into_owned).collect();
+        match self {
+            TypeExpr::Named { name, location } => TypeExpr::Named {
+                name: Cow::Owned(name.into_owned()),
+                location,
+            },
+            TypeExpr::Fn {
+                parameters,
+                returns,
+                location,
+            } => TypeExpr::Fn {
+                parameters: owned(parameters),
+                returns: returns.map(|returns| Box::new(returns.into_owned())),
+                location,
+            },
+            TypeExpr::Array {
+                element,
+                length,
+                location,
+            } => TypeExpr::Array {
+                element: Box::new(element.into_owned()),
+                length,
+                location,
+            },
+            TypeExpr::Tuple { elements, location } => TypeExpr::Tuple {
+                elements: owned(elements),
+                location,
+            },
+            TypeExpr::Ptr {
+                mutable,
+                pointee,
+                location,
+            } => TypeExpr::Ptr {
+                mutable,
+                pointee: Box::new(pointee.into_owned()),
+                location,
+            },
+            TypeExpr::Generic {
+                name,
+                arguments,
+                location,
+            } => TypeExpr::Generic {
+                name: Cow::Owned(name.into_owned()),
+                arguments: owned(arguments),
+                location,
+            },
+            TypeExpr::ImplTrait { bound, location } => TypeExpr::ImplTrait {
+                bound: Box::new(bound.into_owned()),
+                location,
+            },
+            TypeExpr::Ref {
+                mutable,
+                lifetime,
+                referent,
+                location,
+            } => TypeExpr::Ref {
+                mutable,
+                lifetime: lifetime.into_owned(),
+                referent: Box::new(referent.into_owned()),
+                location,
+            },
+            TypeExpr::FnTrait {
+                name,
+                parameters,
+                returns,
+                location,
+            } => TypeExpr::FnTrait {
+                name: Cow::Owned(name.into_owned()),
+                parameters: owned(parameters),
+                returns: returns.map(|returns| Box::new(returns.into_owned())),
+                location,
+            },
+            TypeExpr::Lifetime { name, location } => TypeExpr::Lifetime {
+                name: Cow::Owned(name.into_owned()),
+                location,
+            },
+            TypeExpr::Never { location } => TypeExpr::Never { location },
+        }
+    }
+}
+
+/// writes the type the way it is written in source, which parses back into
+/// the same type
+impl std::fmt::Display for TypeExpr<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let list = |f: &mut std::fmt::Formatter<'_>, types: &[TypeExpr<'_>]| {
+            for (idx, typ) in types.iter().enumerate() {
+                if idx > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{typ}")?;
             }
-            body.push(parse_expression(&mut self.lexer, true)?);
+            Ok(())
+        };
+
+        match self {
+            TypeExpr::Named { name, .. } => write!(f, "{name}"),
+            TypeExpr::Fn {
+                parameters, returns, ..
+            } => {
+                write!(f, "fun(")?;
+                list(f, parameters)?;
+                write!(f, ")")?;
+                match returns {
+                    Some(returns) => write!(f, " => {returns}"),
+                    None => Ok(()),
+                }
+            }
+            TypeExpr::Array { element, length, .. } => write!(f, "[{element}; {length}]"),
+            TypeExpr::Tuple { elements, .. } => {
+                write!(f, "(")?;
+                list(f, elements)?;
+                match elements.len() {
+                    1 => write!(f, ",)"),
+                    _ => write!(f, ")"),
+                }
+            }
+            TypeExpr::Ptr { mutable, pointee, .. } => match mutable {
+                true => write!(f, "*mut {pointee}"),
+                false => write!(f, "*const {pointee}"),
+            },
+            TypeExpr::Generic { name, arguments, .. } => {
+                write!(f, "{name}<")?;
+                list(f, arguments)?;
+                write!(f, ">")
+            }
+            TypeExpr::ImplTrait { bound, .. } => write!(f, "impl {bound}"),
+            TypeExpr::Ref {
+                mutable,
+                lifetime,
+                referent,
+                ..
+            } => {
+                write!(f, "&")?;
+                if let Some(lifetime) = lifetime.name() {
+                    write!(f, "'{lifetime} ")?;
+                }
+                match mutable {
+                    true => write!(f, "mut {referent}"),
+                    false => write!(f, "{referent}"),
+                }
+            }
+            TypeExpr::FnTrait {
+                name,
+                parameters,
+                returns,
+                ..
+            } => {
+                write!(f, "{name}(")?;
+                list(f, parameters)?;
+                write!(f, ")")?;
+                match returns {
+                    Some(returns) => write!(f, " -> {returns}"),
+                    None => Ok(()),
+                }
+            }
+            TypeExpr::Lifetime { name, .. } => write!(f, "'{name}"),
+            TypeExpr::Never { .. } => write!(f, "!"),
+        }
+    }
+}
+
+impl<'ast> Statement<'ast> {
+    pub fn location(&self) -> Location {
+        match self {
+            Statement::FunArgument { location, .. } => *location,
+            Statement::Fun { location, .. } => *location,
+            Statement::Struct { location, .. } => *location,
+            Statement::Impl { location, .. } => *location,
+            Statement::MacroRules { location, .. } => *location,
+            Statement::Mod { location, .. } => *location,
+            Statement::ExternCrate { location, .. } => *location,
+            Statement::Use { location, .. } => *location,
+            Statement::Static { location, .. } => *location,
+        }
+    }
+
+    /// the doc comments written before the statement, which only functions
+    /// and structs keep
+    pub fn doc(&self) -> &[Cow<'ast, str>] {
+        match self {
+            Statement::Fun { doc, .. } | Statement::Struct { doc, .. } => doc,
+            Statement::FunArgument { .. }
+            | Statement::Impl { .. }
+            | Statement::MacroRules { .. }
+            | Statement::Mod { .. }
+            | Statement::ExternCrate { .. }
+            | Statement::Use { .. }
+            | Statement::Static { .. } => &[],
+        }
+    }
+
+    /// the functions declared by this statement, which is itself for a
+    /// function and every method for an impl block
+    pub fn functions(&self) -> &[Self] {
+        match self {
+            Statement::Fun { .. } => std::slice::from_ref(self),
+            Statement::Impl { functions, .. } => functions,
+            Statement::FunArgument { .. }
+            | Statement::Struct { .. }
+            | Statement::MacroRules { .. }
+            | Statement::Mod { .. }
+            | Statement::ExternCrate { .. }
+            | Statement::Use { .. }
+            | Statement::Static { .. } => &[],
+        }
+    }
+
+    /// whether this is a function returning several values, like
+    /// `fun f() => (i32, i32) { return 1, 2; }`
+    pub fn returns_multiple(&self) -> bool {
+        matches!(self, Statement::Fun { return_type: Some(typ), .. }
+            if matches!(typ.as_ref(), TypeExpr::Tuple { elements, .. } if elements.len() > 1))
+    }
+}
+
+/// every function marked `#[test]`, in the order they were declared, to be
+/// run by a `harness::TestHarness`
+pub fn collect_test_functions<'a, 'ast>(statements: &'a [Statement<'ast>]) -> Vec<&'a Statement<'ast>> {
+    statements
+        .iter()
+        .flat_map(Statement::functions)
+        .filter(|function| {
+            matches!(function, Statement::Fun { attributes, .. }
+                if attributes.iter().any(|attribute| attribute.name == "test"))
+        })
+        .collect()
+}
+
+impl Statement<'_> {
+    /// detaches the statement from the source it was parsed from, so it can
+    /// outlive it
+    pub fn into_owned(self) -> Statement<'static> {
+        match self {
+            Statement::FunArgument {
+                name,
+                arg_type,
+                location,
+            } => Statement::FunArgument {
+                name: Cow::Owned(name.into_owned()),
+                arg_type: Box::new(arg_type.into_owned()),
+                location,
+            },
+            Statement::Fun {
+                name,
+                generics,
+                arguments,
+                body,
+                return_type,
+                where_clause,
+                attributes,
+                inline,
+                is_unsafe,
+                doc,
+                location,
+            } => Statement::Fun {
+                name: Cow::Owned(name.into_owned()),
+                generics: generics.into_iter().map(GenericParam::into_owned).collect(),
+                arguments: arguments.into_iter().map(Statement::into_owned).collect(),
+                body: body.into_iter().map(Expression::into_owned).collect(),
+                return_type: return_type.map(|typ| Box::new(typ.into_owned())),
+                where_clause: where_clause.into_iter().map(WherePredicate::into_owned).collect(),
+                attributes: attributes.into_iter().map(Attribute::into_owned).collect(),
+                inline,
+                is_unsafe,
+                doc: doc.into_iter().map(|line| Cow::Owned(line.into_owned())).collect(),
+                location,
+            },
+            Statement::Struct {
+                name,
+                generics,
+                fields,
+                attributes,
+                doc,
+                location,
+            } => Statement::Struct {
+                name: Cow::Owned(name.into_owned()),
+                generics: generics.into_iter().map(GenericParam::into_owned).collect(),
+                fields: fields.into_iter().map(Statement::into_owned).collect(),
+                attributes: attributes.into_iter().map(Attribute::into_owned).collect(),
+                doc: doc.into_iter().map(|line| Cow::Owned(line.into_owned())).collect(),
+                location,
+            },
+            Statement::Impl {
+                generics,
+                trait_name,
+                target,
+                arguments,
+                functions,
+                location,
+            } => Statement::Impl {
+                generics: generics.into_iter().map(GenericParam::into_owned).collect(),
+                trait_name: trait_name.map(|name| Cow::Owned(name.into_owned())),
+                target: Cow::Owned(target.into_owned()),
+                arguments: arguments.into_iter().map(TypeExpr::into_owned).collect(),
+                functions: functions.into_iter().map(Statement::into_owned).collect(),
+                location,
+            },
+            Statement::MacroRules { name, rules, location } => Statement::MacroRules {
+                name: Cow::Owned(name.into_owned()),
+                rules: rules.into_iter().map(MacroRule::into_owned).collect(),
+                location,
+            },
+            Statement::Mod { name, location } => Statement::Mod {
+                name: Cow::Owned(name.into_owned()),
+                location,
+            },
+            Statement::ExternCrate {
+                name,
+                alias,
+                is_self,
+                attributes,
+                location,
+            } => Statement::ExternCrate {
+                name: Cow::Owned(name.into_owned()),
+                alias: alias.map(|alias| Cow::Owned(alias.into_owned())),
+                is_self,
+                attributes: attributes.into_iter().map(Attribute::into_owned).collect(),
+                location,
+            },
+            Statement::Use {
+                tree,
+                attributes,
+                location,
+            } => Statement::Use {
+                tree: tree.into_owned(),
+                attributes: attributes.into_iter().map(Attribute::into_owned).collect(),
+                location,
+            },
+            Statement::Static {
+                mutable,
+                name,
+                typ,
+                value,
+                attributes,
+                location,
+            } => Statement::Static {
+                mutable,
+                name: Cow::Owned(name.into_owned()),
+                typ: typ.map(|typ| Box::new(typ.into_owned())),
+                value: Box::new(value.into_owned()),
+                attributes: attributes.into_iter().map(Attribute::into_owned).collect(),
+                location,
+            },
+        }
+    }
+}
+
+impl Expression<'_> {
+    /// detaches the expression from the source it was parsed from, so it can
+    /// outlive it
+    pub fn into_owned(self) -> Expression<'static> {
+        match self {
+            Expression::Var {
+                mutable,
+                name,
+                typ,
+                value,
+                attributes,
+                location,
+            } => Expression::Var {
+                mutable,
+                name: Cow::Owned(name.into_owned()),
+                typ: typ.map(|typ| Box::new(typ.into_owned())),
+                value: Box::new(value.into_owned()),
+                attributes: attributes.into_iter().map(Attribute::into_owned).collect(),
+                location,
+            },
+            Expression::Bool { value, location } => Expression::Bool { value, location },
+            Expression::If {
+                condition,
+                location,
+                truthy,
+                falsy,
+            } => Expression::If {
+                condition: Box::new(condition.into_owned()),
+                location,
+                truthy: Box::new(truthy.into_owned()),
+                falsy: falsy.into_iter().map(Expression::into_owned).collect(),
+            },
+            Expression::FunCall {
+                ident,
+                location,
+                arguments,
+                tail_call,
+            } => Expression::FunCall {
+                ident: Box::new(ident.into_owned()),
+                location,
+                arguments: arguments.into_iter().map(Expression::into_owned).collect(),
+                tail_call,
+            },
+            Expression::Assign { ident, location, value } => Expression::Assign {
+                ident: Box::new(ident.into_owned()),
+                location,
+                value: Box::new(value.into_owned()),
+            },
+            Expression::CompoundAssign {
+                operator,
+                ident,
+                value,
+                location,
+            } => Expression::CompoundAssign {
+                operator,
+                ident: Box::new(ident.into_owned()),
+                value: Box::new(value.into_owned()),
+                location,
+            },
+            Expression::Ident { name, location } => Expression::Ident {
+                name: Cow::Owned(name.into_owned()),
+                location,
+            },
+            Expression::Path { segments, location } => Expression::Path {
+                segments: segments
+                    .into_iter()
+                    .map(|segment| Cow::Owned(segment.into_owned()))
+                    .collect(),
+                location,
+            },
+            Expression::Block {
+                expressions,
+                tail,
+                reachable,
+                location,
+            } => Expression::Block {
+                expressions: expressions.into_iter().map(Expression::into_owned).collect(),
+                tail: tail.map(|tail| Box::new(tail.into_owned())),
+                reachable,
+                location,
+            },
+            Expression::UintLiteral { value, size, location } => Expression::UintLiteral { value, size, location },
+            Expression::FloatLiteral { value, size, location } => Expression::FloatLiteral { value, size, location },
+            Expression::IntLiteral { value, size, location } => Expression::IntLiteral { value, size, location },
+            Expression::BinaryOp {
+                operator,
+                lhs,
+                rhs,
+                typ,
+                location,
+            } => Expression::BinaryOp {
+                operator,
+                lhs: Box::new(lhs.into_owned()),
+                rhs: Box::new(rhs.into_owned()),
+                typ,
+                location,
+            },
+            Expression::Assert {
+                condition,
+                message,
+                location,
+            } => Expression::Assert {
+                condition: Box::new(condition.into_owned()),
+                message: message.map(|message| Box::new(message.into_owned())),
+                location,
+            },
+            Expression::Elvis {
+                condition,
+                default,
+                location,
+            } => Expression::Elvis {
+                condition: Box::new(condition.into_owned()),
+                default: Box::new(default.into_owned()),
+                location,
+            },
+            Expression::Return { value, location } => Expression::Return {
+                value: Box::new(value.into_owned()),
+                location,
+            },
+            Expression::Yield { value, location } => Expression::Yield {
+                value: value.map(|value| Box::new(value.into_owned())),
+                location,
+            },
+            Expression::StringLiteral { value, location } => Expression::StringLiteral {
+                value: Cow::Owned(value.into_owned()),
+                location,
+            },
+            Expression::Interpolated {
+                literals,
+                expressions,
+                location,
+            } => Expression::Interpolated {
+                literals: literals.into_iter().map(Expression::into_owned).collect(),
+                expressions: expressions.into_iter().map(Expression::into_owned).collect(),
+                location,
+            },
+            Expression::Macro { name, tokens, location } => Expression::Macro {
+                name: Cow::Owned(name.into_owned()),
+                tokens: tokens.into_iter().map(Token::into_owned).collect(),
+                location,
+            },
+            Expression::RuntimeTypeOf { expr, location } => Expression::RuntimeTypeOf {
+                expr: Box::new(expr.into_owned()),
+                location,
+            },
+            Expression::Array { elements, location } => Expression::Array {
+                elements: elements.into_iter().map(Expression::into_owned).collect(),
+                location,
+            },
+            Expression::Tuple { elements, location } => Expression::Tuple {
+                elements: elements.into_iter().map(Expression::into_owned).collect(),
+                location,
+            },
+            Expression::ArrayLen { array, location } => Expression::ArrayLen {
+                array: Box::new(array.into_owned()),
+                location,
+            },
+            Expression::Splat { expr, location } => Expression::Splat {
+                expr: Box::new(expr.into_owned()),
+                location,
+            },
+            Expression::InlineAsm {
+                template,
+                outputs,
+                inputs,
+                clobbers,
+                location,
+            } => Expression::InlineAsm {
+                template: Cow::Owned(template.into_owned()),
+                outputs: outputs.into_iter().map(AsmOperand::into_owned).collect(),
+                inputs: inputs.into_iter().map(AsmOperand::into_owned).collect(),
+                clobbers: clobbers
+                    .into_iter()
+                    .map(|clobber| Cow::Owned(clobber.into_owned()))
+                    .collect(),
+                location,
+            },
+            Expression::SafeCast { expr, typ, location } => Expression::SafeCast {
+                expr: Box::new(expr.into_owned()),
+                typ: Box::new(typ.into_owned()),
+                location,
+            },
+            Expression::Sizeof { typ, location } => Expression::Sizeof {
+                typ: Box::new(typ.into_owned()),
+                location,
+            },
+            Expression::Await { expr, location } => Expression::Await {
+                expr: Box::new(expr.into_owned()),
+                location,
+            },
+            Expression::FieldAccess {
+                target,
+                field,
+                location,
+            } => Expression::FieldAccess {
+                target: Box::new(target.into_owned()),
+                field: Cow::Owned(field.into_owned()),
+                location,
+            },
+            Expression::TupleAccess { tuple, index, location } => Expression::TupleAccess {
+                tuple: Box::new(tuple.into_owned()),
+                index,
+                location,
+            },
+            Expression::Index {
+                target,
+                index,
+                location,
+            } => Expression::Index {
+                target: Box::new(target.into_owned()),
+                index: Box::new(index.into_owned()),
+                location,
+            },
+            Expression::Try { expr, location } => Expression::Try {
+                expr: Box::new(expr.into_owned()),
+                location,
+            },
+            Expression::Lambda {
+                parameters,
+                parameter_types,
+                return_type,
+                body,
+                location,
+            } => Expression::Lambda {
+                parameters: parameters.into_iter().map(Expression::into_owned).collect(),
+                parameter_types: parameter_types
+                    .into_iter()
+                    .map(|typ| typ.map(TypeExpr::into_owned))
+                    .collect(),
+                return_type: return_type.map(|typ| Box::new(typ.into_owned())),
+                body: Box::new(body.into_owned()),
+                location,
+            },
+            Expression::Let {
+                name,
+                value,
+                body,
+                location,
+            } => Expression::Let {
+                name: Cow::Owned(name.into_owned()),
+                value: Box::new(value.into_owned()),
+                body: Box::new(body.into_owned()),
+                location,
+            },
+            Expression::UnsafeBlock { body, location } => Expression::UnsafeBlock {
+                body: Box::new(body.into_owned()),
+                location,
+            },
+            Expression::Ref {
+                mutable,
+                expr,
+                location,
+            } => Expression::Ref {
+                mutable,
+                expr: Box::new(expr.into_owned()),
+                location,
+            },
+            Expression::Deref { expr, location } => Expression::Deref {
+                expr: Box::new(expr.into_owned()),
+                location,
+            },
+            Expression::Not { expr, location } => Expression::Not {
+                expr: Box::new(expr.into_owned()),
+                location,
+            },
+            Expression::Match {
+                scrutinee,
+                arms,
+                location,
+            } => Expression::Match {
+                scrutinee: Box::new(scrutinee.into_owned()),
+                arms: arms.into_iter().map(MatchArm::into_owned).collect(),
+                location,
+            },
+            Expression::ComptimeBlock { body, location } => Expression::ComptimeBlock {
+                body: Box::new(body.into_owned()),
+                location,
+            },
+            Expression::Defer { body, location } => Expression::Defer {
+                body: Box::new(body.into_owned()),
+                location,
+            },
+            Expression::EnumVariant { path, kind, location } => Expression::EnumVariant {
+                path: path
+                    .into_iter()
+                    .map(|segment| Cow::Owned(segment.into_owned()))
+                    .collect(),
+                kind: kind.into_owned_with(Expression::into_owned),
+                location,
+            },
+            Expression::DoBlock { steps, location } => Expression::DoBlock {
+                steps: steps.into_iter().map(DoStep::into_owned).collect(),
+                location,
+            },
         }
+    }
+
+    pub fn location(&self) -> Location {
+        match self {
+            Expression::Var { location, .. } => *location,
+            Expression::If { location, .. } => *location,
+            Expression::Ident { location, .. } => *location,
+            Expression::Path { location, .. } => *location,
+            Expression::Bool { location, .. } => *location,
+            Expression::Block { location, .. } => *location,
+            Expression::FunCall { location, .. } => *location,
+            Expression::Assign { location, .. } => *location,
+            Expression::CompoundAssign { location, .. } => *location,
+            Expression::UintLiteral { location, .. } => *location,
+            Expression::Return { location, .. } => *location,
+            Expression::Yield { location, .. } => *location,
+            Expression::FloatLiteral { location, .. } => *location,
+            Expression::IntLiteral { location, .. } => *location,
+            Expression::BinaryOp { location, .. } => *location,
+            Expression::Elvis { location, .. } => *location,
+            Expression::Assert { location, .. } => *location,
+            Expression::StringLiteral { location, .. } => *location,
+            Expression::Interpolated { location, .. } => *location,
+            Expression::Macro { location, .. } => *location,
+            Expression::RuntimeTypeOf { location, .. } => *location,
+            Expression::Array { location, .. } => *location,
+            Expression::ArrayLen { location, .. } => *location,
+            Expression::Splat { location, .. } => *location,
+            Expression::Sizeof { location, .. } => *location,
+            Expression::SafeCast { location, .. } => *location,
+            Expression::InlineAsm { location, .. } => *location,
+            Expression::Await { location, .. } => *location,
+            Expression::FieldAccess { location, .. } => *location,
+            Expression::TupleAccess { location, .. } => *location,
+            Expression::Index { location, .. } => *location,
+            Expression::Try { location, .. } => *location,
+            Expression::Tuple { location, .. } => *location,
+            Expression::Lambda { location, .. } => *location,
+            Expression::Let { location, .. } => *location,
+            Expression::UnsafeBlock { location, .. } => *location,
+            Expression::Ref { location, .. } => *location,
+            Expression::Deref { location, .. } => *location,
+            Expression::Not { location, .. } => *location,
+            Expression::Match { location, .. } => *location,
+            Expression::EnumVariant { location, .. } => *location,
+            Expression::ComptimeBlock { location, .. } => *location,
+            Expression::Defer { location, .. } => *location,
+            Expression::DoBlock { location, .. } => *location,
+        }
+    }
+
+    /// name of the variant of the expression, which is what `kind_of!`
+    /// evaluates to
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            Expression::Var { .. } => "Var",
+            Expression::If { .. } => "If",
+            Expression::Ident { .. } => "Ident",
+            Expression::Path { .. } => "Path",
+            Expression::Bool { .. } => "Bool",
+            Expression::Block { .. } => "Block",
+            Expression::FunCall { .. } => "FunCall",
+            Expression::Assign { .. } => "Assign",
+            Expression::CompoundAssign { .. } => "CompoundAssign",
+            Expression::UintLiteral { .. } => "UintLiteral",
+            Expression::Return { .. } => "Return",
+            Expression::Yield { .. } => "Yield",
+            Expression::FloatLiteral { .. } => "FloatLiteral",
+            Expression::IntLiteral { .. } => "IntLiteral",
+            Expression::BinaryOp { .. } => "BinaryOp",
+            Expression::Elvis { .. } => "Elvis",
+            Expression::Assert { .. } => "Assert",
+            Expression::StringLiteral { .. } => "StringLiteral",
+            Expression::Interpolated { .. } => "Interpolated",
+            Expression::Macro { .. } => "Macro",
+            Expression::RuntimeTypeOf { .. } => "RuntimeTypeOf",
+            Expression::Array { .. } => "Array",
+            Expression::ArrayLen { .. } => "ArrayLen",
+            Expression::Splat { .. } => "Splat",
+            Expression::Sizeof { .. } => "Sizeof",
+            Expression::SafeCast { .. } => "SafeCast",
+            Expression::InlineAsm { .. } => "InlineAsm",
+            Expression::Await { .. } => "Await",
+            Expression::FieldAccess { .. } => "FieldAccess",
+            Expression::TupleAccess { .. } => "TupleAccess",
+            Expression::Index { .. } => "Index",
+            Expression::Try { .. } => "Try",
+            Expression::Tuple { .. } => "Tuple",
+            Expression::Lambda { .. } => "Lambda",
+            Expression::Let { .. } => "Let",
+            Expression::UnsafeBlock { .. } => "UnsafeBlock",
+            Expression::Ref { .. } => "Ref",
+            Expression::Deref { .. } => "Deref",
+            Expression::Not { .. } => "Not",
+            Expression::Match { .. } => "Match",
+            Expression::EnumVariant { .. } => "EnumVariant",
+            Expression::ComptimeBlock { .. } => "ComptimeBlock",
+            Expression::Defer { .. } => "Defer",
+            Expression::DoBlock { .. } => "DoBlock",
+        }
+    }
+}
+
+/// settings changing how a program is parsed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParserOptions {
+    /// size given to negative integer literals written without a suffix.
+    /// when `None` they are left unsized, for type inference to decide
+    pub default_int_size: Option<IntSizes>,
+    /// size given to other integer literals written without a suffix
+    pub default_uint_size: Option<UIntSizes>,
+    /// size given to float literals written without a suffix
+    pub default_float_size: Option<FloatSizes>,
+}
+
+pub struct Parser<'par> {
+    pub source: &'par str,
+    pub lexer: Lexer<'par>,
+    context: ParseContext<'par>,
+}
+
+impl<'par> Parser<'par> {
+    pub fn new(source: &'par str, lexer: Lexer<'par>) -> Self {
+        Self::new_with_options(source, lexer, ParserOptions::default())
+    }
+
+    pub fn new_with_options(source: &'par str, lexer: Lexer<'par>, options: ParserOptions) -> Self {
+        Self {
+            source,
+            lexer,
+            context: ParseContext::new(options),
+        }
+    }
+
+    /// parses a single expression typed on the repl, where the trailing
+    /// semicolon is optional, along with the warnings found in it
+    pub fn parse_repl_line(source: &'par str) -> Result<(Expression<'par>, Vec<DiagnosticWarning>), ParseError> {
+        let mut lexer = Lexer::for_repl(source);
+        let mut context = ParseContext::default();
+        let expression = parse_expression_with(&mut lexer, &mut context, true)?;
+
+        // a semicolon after an expression that doesn't need one is fine too
+        if let Some(token) = lexer.peek().transpose()? {
+            if token.kind == Kind::Op(Operator::SemiColon) {
+                lexer.next();
+            }
+        }
+
+        match lexer.next().transpose()? {
+            Some(token) => Err(ParseError::unexpected(&token)),
+            None => Ok((expression, context.take_warnings())),
+        }
+    }
+
+    /// parses the whole program. the warnings found while parsing it stay on
+    /// the parser until `take_warnings` is called
+    pub fn parse(&mut self) -> Result<Vec<Statement<'par>>, ParseError> {
+        let mut statements = vec![];
+
+        while !self.lexer.is_empty() {
+            let statement = self.parse_statement()?;
+            statements.push(statement);
+        }
+
+        Ok(statements)
+    }
+
+    /// parses the whole program, also returning the warnings found while
+    /// parsing it, like `const` bindings that can't be verified
+    pub fn parse_with_warnings(mut self) -> Result<(Vec<Statement<'par>>, Vec<DiagnosticWarning>), ParseError> {
+        let statements = self.parse()?;
+        Ok((statements, self.take_warnings()))
+    }
+
+    /// takes every warning found since the last call, including the ones of
+    /// a parse that failed halfway through
+    pub fn take_warnings(&mut self) -> Vec<DiagnosticWarning> {
+        self.context.take_warnings()
+    }
+
+    /// parses the whole program, recovering from the errors it can, like a
+    /// block that is never closed. those are given back along with the tree,
+    /// which holds whatever could be parsed around them. the warnings stay
+    /// on the parser, as with `parse`
+    pub fn parse_recovering(&mut self) -> Result<(Vec<Statement<'par>>, Vec<ParseError>), ParseError> {
+        self.context.start_recovering();
+        let statements = self.parse();
+        let recovered = self.context.take_recovered();
+        Ok((statements?, recovered))
+    }
+
+    /// parses the whole program, also returning how its blocks nest and
+    /// what each of them declares
+    pub fn parse_with_scopes(mut self) -> Result<(Vec<Statement<'par>>, ScopeTree<'par>), ParseError> {
+        let end = self.lexer.end();
+        self.context.track_scopes();
+        let statements = self.parse();
+        let mut scopes = self.context.take_scopes().unwrap_or_default();
+        scopes.finish(end);
+        Ok((statements?, scopes))
+    }
+
+    fn parse_statement(&mut self) -> Result<Statement<'par>, ParseError> {
+        let doc = parse_doc_comment(&mut self.lexer);
+        match self.lexer.peek().transpose()? {
+            Some(token) => match &token.kind {
+                Kind::Fun | Kind::Unsafe => self.parse_function(vec![], doc),
+                Kind::Struct => self.parse_struct(vec![], doc),
+                Kind::Impl => self.parse_impl(),
+                Kind::Mod => self.parse_mod(),
+                Kind::Extern => self.parse_extern_crate(vec![]),
+                Kind::Use => self.parse_use_statement(vec![]),
+                Kind::Var | Kind::Const => self.parse_static(vec![]),
+                Kind::Value(Value::Ident(name)) if name == "macro_rules" => self.parse_macro_rules(),
+                Kind::Op(Operator::Hash) => {
+                    let attributes = parse_attributes(&mut self.lexer)?;
+                    match self.lexer.peek().transpose()? {
+                        Some(token) if token.kind == Kind::Struct => self.parse_struct(attributes, doc),
+                        Some(token) if matches!(token.kind, Kind::Fun | Kind::Unsafe) => {
+                            self.parse_function(attributes, doc)
+                        }
+                        Some(token) if token.kind == Kind::Extern => self.parse_extern_crate(attributes),
+                        Some(token) if token.kind == Kind::Use => self.parse_use_statement(attributes),
+                        Some(token) if matches!(token.kind, Kind::Var | Kind::Const) => self.parse_static(attributes),
+                        Some(token) => Err(ParseError::new(
+                            format!(
+                                "attributes can only be placed on structs, functions, globals, `extern crate` and `use`, found `{}`",
+                                token.kind
+                            ),
+                            token.location,
+                        )),
+                        None => Err(ParseError::eof(
+                            "expected a struct, a function, a global, an `extern crate` or a `use` after the attributes",
+                            end_of_input(&self.lexer),
+                        )),
+                    }
+                }
+                Kind::Defer => Err(ParseError::new(
+                    "`defer` can only be used inside of a block",
+                    token.location,
+                )),
+                _ => Err(ParseError::unexpected(token)),
+            },
+            None => Err(ParseError::eof("expected a statement", end_of_input(&self.lexer))),
+        }
+    }
+
+    fn parse_function_args(&mut self, _: &Token<'_>) -> Result<Vec<Statement<'par>>, ParseError> {
+        let mut arguments = vec![];
+
+        loop {
+            let (arg_name_expr, arg_name) = parse_identifier(&mut self.lexer)?;
+            self.lexer.expect(Kind::Op(Operator::Colon))?;
+            let arg_type = parse_type_expression_with(&mut self.lexer, &mut self.context)?;
+
+            arguments.push(Statement::FunArgument {
+                name: arg_name,
+                location: Location::new(arg_name_expr.location().start_byte, arg_type.location().end_byte),
+                arg_type: Box::new(arg_type),
+            });
+
+            match self.lexer.peek().transpose()? {
+                Some(token) => match token.kind {
+                    Kind::Op(Operator::RightParen) => break,
+                    Kind::Op(Operator::Comma) => (),
+                    _ => return Err(ParseError::unexpected(token)),
+                },
+                None => break,
+            }
+
+            // consume the comma separating this argument from the next one
+            self.lexer.next().transpose()?;
+        }
+
+        Ok(arguments)
+    }
+
+    fn parse_function(
+        &mut self,
+        attributes: Vec<Attribute<'par>>,
+        doc: Vec<&'par str>,
+    ) -> Result<Statement<'par>, ParseError> {
+        let unsafe_keyword = self.parse_unsafe_keyword()?;
+        let keyword = self.lexer.expect(Kind::Fun)?;
+        let (_, fun_name) = parse_identifier(&mut self.lexer)?;
+        let generics = self.parse_optional_generics()?;
+        self.lexer.expect(Kind::Op(Operator::LeftParen))?;
+
+        let mut arguments = vec![];
+        if let Some(next) = self.lexer.peek().transpose()? {
+            if !matches!(next.kind, Kind::Op(Operator::RightParen)) {
+                arguments = self.parse_function_args(&keyword)?;
+            }
+        }
+
+        // after parsing argument list we need to consume the closing parenthesis
+        self.lexer.expect(Kind::Op(Operator::RightParen))?;
+
+        // after the argument list of a function, there can be an optional return type annotation
+        // => <TYPE> {
+        // before the left brace, but its fine to be ommited
+        let has_return = match self.lexer.peek().transpose()? {
+            Some(token) => matches!(token.kind, Kind::Op(Operator::ThickArrow)),
+            _ => false,
+        };
+
+        let return_type = if has_return {
+            self.lexer.next().transpose()?;
+            Some(Box::new(parse_type_expression_with(
+                &mut self.lexer,
+                &mut self.context,
+            )?))
+        } else {
+            None
+        };
+
+        let where_clause = match self.lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Where => parse_where_clause(&mut self.lexer, &mut self.context)?,
+            _ => vec![],
+        };
+
+        let opening_brace = self.lexer.expect(Kind::Op(Operator::LeftBrace))?;
+        self.context.open_scope(opening_brace.location.start_byte);
+
+        let inside_unsafe = self.context.set_inside_unsafe(unsafe_keyword.is_some());
+        let body = self.parse_function_body();
+        self.context.set_inside_unsafe(inside_unsafe);
+        let body = body?;
 
         // consume the closing brace of the function
-        let closing_brace = self
-            .lexer
-            .expect(Kind::Op(Operator::RightBrace))
-            .map_err(|e| e.to_string())?;
+        let closing_brace = recover_from_unclosed_delimiter(
+            &mut self.lexer,
+            &mut self.context,
+            Operator::LeftBrace,
+            Operator::RightBrace,
+            &opening_brace,
+        )?;
+        self.context.close_scope(closing_brace.end_byte);
 
-        let location = keyword.location.start_byte..closing_brace.location.end_byte;
+        let start = attributes
+            .first()
+            .map(|attribute| attribute.location)
+            .or(unsafe_keyword.as_ref().map(|keyword| keyword.location))
+            .map_or(keyword.location.start_byte, |location| location.start_byte);
+        let location = start..closing_brace.end_byte;
         Ok(Statement::Fun {
             name: fun_name,
+            generics,
             arguments,
             body,
             return_type,
+            where_clause,
+            inline: parse_inline(&mut self.context, &attributes),
+            is_unsafe: unsafe_keyword.is_some(),
+            attributes,
+            doc: doc.into_iter().map(Cow::Borrowed).collect(),
             location: location.into(),
         })
     }
+
+    /// takes the `unsafe` written before a function, if any. outside of a
+    /// function, only another function can follow it
+    fn parse_unsafe_keyword(&mut self) -> Result<Option<Token<'par>>, ParseError> {
+        if !matches!(self.lexer.peek(), Some(Ok(token)) if token.kind == Kind::Unsafe) {
+            return Ok(None);
+        }
+        let keyword = self.lexer.expect(Kind::Unsafe)?;
+        match self.lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Fun => Ok(Some(keyword)),
+            Some(token) if token.kind == Kind::Op(Operator::LeftBrace) => Err(ParseError::new(
+                "an `unsafe { .. }` block can only be written inside of a function",
+                keyword.location,
+            )),
+            Some(token) => Err(ParseError::new(
+                format!("expected `{{` or `fun` after `unsafe`, found `{}`", token.kind),
+                token.location,
+            )),
+            None => Err(ParseError::eof(
+                "expected `{` or `fun` after `unsafe`",
+                end_of_input(&self.lexer),
+            )),
+        }
+    }
+
+    /// parses the expressions of a function body, up to its closing brace
+    fn parse_function_body(&mut self) -> Result<Vec<Expression<'par>>, ParseError> {
+        let mut body = vec![];
+
+        while let Some(token) = self.lexer.peek().transpose()? {
+            if let Kind::Op(Operator::RightBrace) = token.kind {
+                break;
+            }
+            match parse_expression_with(&mut self.lexer, &mut self.context, true) {
+                Err(error) if error.is_incomplete() && self.context.recovering() && self.lexer.is_empty() => break,
+                expr => body.push(expr?),
+            }
+        }
+
+        Ok(body)
+    }
+
+    fn parse_struct(
+        &mut self,
+        attributes: Vec<Attribute<'par>>,
+        doc: Vec<&'par str>,
+    ) -> Result<Statement<'par>, ParseError> {
+        let keyword = self.lexer.expect(Kind::Struct)?;
+        let (_, name) = parse_identifier(&mut self.lexer)?;
+        let generics = self.parse_optional_generics()?;
+        self.lexer.expect(Kind::Op(Operator::LeftBrace))?;
+
+        let mut fields = vec![];
+        loop {
+            match self.lexer.peek().transpose()? {
+                Some(token) if token.kind == Kind::Op(Operator::RightBrace) => break,
+                None => return Err(ParseError::eof("expected `}`", end_of_input(&self.lexer))),
+                _ => (),
+            }
+
+            let (field_name_expr, field_name) = parse_identifier(&mut self.lexer)?;
+            self.lexer.expect(Kind::Op(Operator::Colon))?;
+            let field_type = parse_type_expression_with(&mut self.lexer, &mut self.context)?;
+
+            fields.push(Statement::FunArgument {
+                name: field_name,
+                location: Location::new(field_name_expr.location().start_byte, field_type.location().end_byte),
+                arg_type: Box::new(field_type),
+            });
+
+            // the comma after the last field is optional
+            match self.lexer.peek().transpose()? {
+                Some(token) if token.kind == Kind::Op(Operator::Comma) => {
+                    self.lexer.next().transpose()?;
+                }
+                Some(token) if token.kind == Kind::Op(Operator::RightBrace) => break,
+                Some(token) => return Err(ParseError::unexpected(token)),
+                None => return Err(ParseError::eof("expected `}`", end_of_input(&self.lexer))),
+            }
+        }
+
+        let closing_brace = self.lexer.expect(Kind::Op(Operator::RightBrace))?;
+
+        let start = attributes
+            .first()
+            .map_or(keyword.location.start_byte, |attribute| attribute.location.start_byte);
+        Ok(Statement::Struct {
+            name,
+            generics,
+            fields,
+            attributes,
+            doc: doc.into_iter().map(Cow::Borrowed).collect(),
+            location: Location::new(start, closing_brace.location.end_byte),
+        })
+    }
+
+    fn parse_mod(&mut self) -> Result<Statement<'par>, ParseError> {
+        let keyword = self.lexer.expect(Kind::Mod)?;
+        let (_, name) = parse_identifier(&mut self.lexer)?;
+        let semicolon = self.lexer.expect(Kind::Op(Operator::SemiColon))?;
+
+        Ok(Statement::Mod {
+            name,
+            location: Location::new(keyword.location.start_byte, semicolon.location.end_byte),
+        })
+    }
+
+    /// parses `extern crate name;`, optionally renamed with `as alias`.
+    /// `self` can only be named when it is given an alias
+    fn parse_extern_crate(&mut self, attributes: Vec<Attribute<'par>>) -> Result<Statement<'par>, ParseError> {
+        let keyword = self.lexer.expect(Kind::Extern)?;
+        self.lexer.expect(Kind::Crate)?;
+        let (name_expr, name) = parse_identifier(&mut self.lexer)?;
+
+        let alias = match self.lexer.peek().transpose()? {
+            Some(Token {
+                kind: Kind::Value(Value::Ident(word)),
+                ..
+            }) if word == "as" => {
+                self.lexer.next().transpose()?;
+                Some(parse_identifier(&mut self.lexer)?.1)
+            }
+            _ => None,
+        };
+
+        let is_self = name == "self";
+        if is_self && alias.is_none() {
+            return Err(ParseError::new(
+                "`extern crate self` must be given a name with `as`",
+                name_expr.location(),
+            ));
+        }
+
+        let semicolon = self.lexer.expect(Kind::Op(Operator::SemiColon))?;
+        let start = attributes
+            .first()
+            .map_or(keyword.location.start_byte, |attribute| attribute.location.start_byte);
+        Ok(Statement::ExternCrate {
+            name,
+            alias,
+            is_self,
+            attributes,
+            location: Location::new(start, semicolon.location.end_byte),
+        })
+    }
+
+    /// parses `var name = value;` or `const name = value;` outside of any
+    /// function, just like a variable inside of one
+    fn parse_static(&mut self, attributes: Vec<Attribute<'par>>) -> Result<Statement<'par>, ParseError> {
+        let Expression::Var {
+            mutable,
+            name,
+            typ,
+            value,
+            attributes,
+            location,
+        } = parse_variable(&mut self.lexer, &mut self.context, attributes)?
+        else {
+            unreachable!("parsing a variable gives a variable");
+        };
+
+        let start = attributes
+            .first()
+            .map_or(location.start_byte, |attribute| attribute.location.start_byte);
+        Ok(Statement::Static {
+            mutable,
+            name,
+            typ,
+            value,
+            attributes,
+            location: Location::new(start, location.end_byte),
+        })
+    }
+
+    /// parses `use path::to::item;`, warning about every glob in it unless
+    /// it is `#[allow(glob_import)]`
+    fn parse_use_statement(&mut self, attributes: Vec<Attribute<'par>>) -> Result<Statement<'par>, ParseError> {
+        let keyword = self.lexer.expect(Kind::Use)?;
+        let mut globs = vec![];
+        let tree = parse_use_tree(&mut self.lexer, &mut self.context, &[], &mut globs)?;
+        let semicolon = self.lexer.expect(Kind::Op(Operator::SemiColon))?;
+
+        if !attributes.iter().any(|attribute| attribute.allows(ALLOW_GLOB_IMPORT)) {
+            globs
+                .into_iter()
+                .map(|(path, location)| DiagnosticWarning::GlobImport {
+                    path,
+                    span: location.into(),
+                })
+                .for_each(|warning| self.context.warn(warning));
+        }
+
+        let start = attributes
+            .first()
+            .map_or(keyword.location.start_byte, |attribute| attribute.location.start_byte);
+        Ok(Statement::Use {
+            tree,
+            attributes,
+            location: Location::new(start, semicolon.location.end_byte),
+        })
+    }
+
+    /// parses the `<T, U: Bound>` after the name of an item, if there is one
+    fn parse_optional_generics(&mut self) -> Result<Vec<GenericParam<'par>>, ParseError> {
+        match self.lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::Less) => {
+                parse_generic_params(&mut self.lexer, &mut self.context)
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// parses `impl<T: Bound> Trait for Target<T> { .. }`, where both the
+    /// generic parameters and the trait are optional
+    fn parse_impl(&mut self) -> Result<Statement<'par>, ParseError> {
+        let keyword = self.lexer.expect(Kind::Impl)?;
+        let generics = self.parse_optional_generics()?;
+        let name = parse_named(&mut self.lexer, &mut self.context)?;
+
+        let (trait_name, target) = match self.lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::For => {
+                let TypeExpr::Named { name: trait_name, .. } = name else {
+                    return Err(ParseError::new("traits cannot take generic arguments", name.location()));
+                };
+                self.lexer.next().transpose()?;
+                (Some(trait_name), parse_named(&mut self.lexer, &mut self.context)?)
+            }
+            _ => (None, name),
+        };
+        let (target, arguments) = match target {
+            TypeExpr::Generic { name, arguments, .. } => (name, arguments),
+            TypeExpr::Named { name, .. } => (name, vec![]),
+            _ => unreachable!("`parse_named` only parses names"),
+        };
+
+        self.lexer.expect(Kind::Op(Operator::LeftBrace))?;
+        let inside_impl = self.context.set_inside_impl(true);
+        let functions = self.parse_impl_functions();
+        self.context.set_inside_impl(inside_impl);
+        let functions = functions?;
+
+        let closing_brace = self.lexer.expect(Kind::Op(Operator::RightBrace))?;
+
+        Ok(Statement::Impl {
+            generics,
+            trait_name,
+            target,
+            arguments,
+            functions,
+            location: Location::new(keyword.location.start_byte, closing_brace.location.end_byte),
+        })
+    }
+
+    /// parses the functions of an impl block, up to its closing brace
+    fn parse_impl_functions(&mut self) -> Result<Vec<Statement<'par>>, ParseError> {
+        let mut functions = vec![];
+        loop {
+            let doc = parse_doc_comment(&mut self.lexer);
+            match self.lexer.peek().transpose()? {
+                Some(token) if token.kind == Kind::Op(Operator::RightBrace) => return Ok(functions),
+                Some(token) if matches!(token.kind, Kind::Fun | Kind::Unsafe) => {
+                    functions.push(self.parse_function(vec![], doc)?)
+                }
+                Some(token) if token.kind == Kind::Op(Operator::Hash) => {
+                    let attributes = parse_attributes(&mut self.lexer)?;
+                    functions.push(self.parse_function(attributes, doc)?);
+                }
+                Some(token) => return Err(ParseError::unexpected(token)),
+                None => return Err(ParseError::eof("expected `}`", end_of_input(&self.lexer))),
+            }
+        }
+    }
+
+    /// parses `macro_rules! name { .. }`. `macro_rules` is only a keyword
+    /// where a statement starts, so it can still name anything else
+    fn parse_macro_rules(&mut self) -> Result<Statement<'par>, ParseError> {
+        let (keyword, _) = parse_identifier(&mut self.lexer)?;
+        self.lexer.expect(Kind::Op(Operator::Bang))?;
+        let (_, name) = parse_identifier(&mut self.lexer)?;
+        self.lexer.expect(Kind::Op(Operator::LeftBrace))?;
+
+        let mut rules = vec![];
+        loop {
+            match self.lexer.peek().transpose()? {
+                Some(token) if token.kind == Kind::Op(Operator::RightBrace) => break,
+                None => return Err(ParseError::eof("expected `}`", end_of_input(&self.lexer))),
+                _ => (),
+            }
+
+            let (pattern, pattern_location) = self.parse_rule_side("pattern")?;
+            self.lexer.expect(Kind::Op(Operator::ThickArrow))?;
+            let (expansion, expansion_location) = self.parse_rule_side("expansion")?;
+            rules.push(MacroRule {
+                pattern,
+                expansion,
+                location: Location::new(pattern_location.start_byte, expansion_location.end_byte),
+            });
+
+            // the semicolon after the last rule is optional
+            match self.lexer.peek().transpose()? {
+                Some(token) if token.kind == Kind::Op(Operator::SemiColon) => {
+                    self.lexer.next().transpose()?;
+                }
+                Some(token) if token.kind == Kind::Op(Operator::RightBrace) => break,
+                Some(token) => return Err(ParseError::unexpected(token)),
+                None => return Err(ParseError::eof("expected `}`", end_of_input(&self.lexer))),
+            }
+        }
+
+        let closing_brace = self.lexer.expect(Kind::Op(Operator::RightBrace))?;
+
+        Ok(Statement::MacroRules {
+            name,
+            rules,
+            location: Location::new(keyword.location().start_byte, closing_brace.location.end_byte),
+        })
+    }
+
+    /// the tokens of either side of a macro rule, inside of any delimiter
+    fn parse_rule_side(&mut self, side: &str) -> Result<(Vec<Token<'par>>, Location), ParseError> {
+        let Some(open) = self.lexer.next().transpose()? else {
+            return Err(ParseError::eof(
+                format!("expected the {side} of a macro rule"),
+                end_of_input(&self.lexer),
+            ));
+        };
+        let Some(close) = closing_delimiter(&open.kind) else {
+            return Err(ParseError::new(
+                format!(
+                    "expected `(`, `[` or `{{` around the {side} of a macro rule, found `{}`",
+                    open.kind
+                ),
+                open.location,
+            ));
+        };
+
+        let (tokens, closing) = parse_token_tree(&mut self.lexer, close)?;
+        Ok((tokens, Location::new(open.location.start_byte, closing.end_byte)))
+    }
 }
 
 #[cfg(test)]
@@ -312,4 +2511,796 @@ mod tests {
 
         insta::assert_debug_snapshot!(ast);
     }
+
+    #[test]
+    fn owned_ast_outlives_source() {
+        let ast = {
+            let source = String::from("fun double(value: u64) => u64 { const result = value * 2; result }");
+            let statements = make_sut(&source).parse().unwrap();
+            statements.into_iter().map(Statement::into_owned).collect::<Vec<_>>()
+        };
+
+        insta::assert_debug_snapshot!(ast);
+    }
+
+    #[test]
+    fn repl_lines_without_semicolons() {
+        let lines = [
+            "1 + 2",
+            "const answer = 40 + 2",
+            "var answer = 42;",
+            "return answer",
+            "println(answer)",
+        ];
+
+        let ast = lines
+            .iter()
+            .map(|line| match Parser::parse_repl_line(line) {
+                Ok((expr, _)) => expr,
+                Err(e) => panic!("{line}: {e:?}"),
+            })
+            .collect::<Vec<_>>();
+
+        insta::assert_debug_snapshot!(ast);
+    }
+
+    #[test]
+    fn source_mode_still_requires_semicolons() {
+        let source = "fun main() { const answer = 42 }";
+
+        assert!(make_sut(source).parse().is_err());
+    }
+
+    #[test]
+    fn syntax_errors_point_at_the_offending_token() {
+        let source = "fun main() { const answer = 42 }";
+        let error = make_sut(source).parse().unwrap_err();
+
+        assert_eq!(error.message, "expected `;`, found `}`");
+        assert_eq!(error.location(), Location::new(31, 32));
+
+        let error = make_sut("fun main() { const answer = ").parse().unwrap_err();
+        assert_eq!(error.message, "expected an expression");
+    }
+
+    #[test]
+    fn incomplete_input_is_told_apart_from_invalid_input() {
+        let incomplete = [
+            "fun main() {",
+            "fun main(a: i32",
+            "fun main() { if x == 1 {",
+            r#"fun main() { "open"#,
+        ];
+        for source in incomplete {
+            assert!(make_sut(source).parse().unwrap_err().is_incomplete(), "{source}");
+        }
+
+        let invalid = [
+            "fun main() { const = 1; }",
+            r#"fun main() { "{a +}"; }"#,
+            "fun main() }",
+        ];
+        for source in invalid {
+            assert!(!make_sut(source).parse().unwrap_err().is_incomplete(), "{source}");
+        }
+    }
+
+    #[test]
+    fn structs_and_impls() {
+        let source = r#"
+            #[derive(Debug)]
+            struct Point {
+                x: f64,
+                y: f64,
+            }
+
+            impl Point {
+                fun length(self: Point) => f64 {
+                    self.x * self.x + self.y * self.y
+                }
+            }
+
+            impl Display for Point {}
+        "#;
+
+        let ast = match make_sut(source).parse() {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+        assert_eq!(ast[0].location().start_byte, source.find("#[derive").unwrap());
+
+        insta::assert_snapshot!(sexpr::statements_to_sexpr(&ast));
+    }
+
+    #[test]
+    fn generic_structs_and_impls() {
+        let source = r#"
+            struct Stack<T> {
+                items: [T; 8],
+                len: usize,
+            }
+
+            impl<T> Stack<T> {
+                fun push(self: Self, item: T) {
+                    assert(self.len < 8, "the stack is full");
+                }
+
+                fun pop(self: Self) => T {
+                    self.items
+                }
+            }
+
+            impl<T: Show + Debug, U> Show for Pair<T, U> {}
+        "#;
+
+        let ast = match make_sut(source).parse() {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+        let Statement::Impl { generics, .. } = &ast[2] else {
+            panic!("expected an impl, found {:?}", ast[2]);
+        };
+        assert_eq!(
+            generics[0].location,
+            Location::new(source.find("T: Show").unwrap(), source.find(", U").unwrap())
+        );
+
+        insta::assert_snapshot!(sexpr::statements_to_sexpr(&ast));
+    }
+
+    #[test]
+    fn malformed_structs_and_impls() {
+        let invalid = [
+            "struct A { a }",
+            "struct A { a: i32 b: i32 }",
+            "#[derive(Debug)] impl A {}",
+            "#[should_panic(expected = 1)] fun a() {}",
+            "#[should_panic(expected = \"a\", b)] fun a() {}",
+            "impl A { const a = 1; }",
+            "impl A for {}",
+            "impl<T> Show<T> for A {}",
+            "impl<T U> A {}",
+            "impl<T: > A {}",
+            "struct A<T> { a: Self }",
+            "fun a(b: Self) {}",
+        ];
+        for source in invalid {
+            assert!(!make_sut(source).parse().unwrap_err().is_incomplete(), "{source}");
+        }
+
+        for source in ["struct A { a: i32,", "impl A {", "#[derive(Debug)]"] {
+            assert!(make_sut(source).parse().unwrap_err().is_incomplete(), "{source}");
+        }
+    }
+
+    #[test]
+    fn use_trees() {
+        let source = "use a::{b::{c, d}, e};\nuse std::io::*;\nuse a::{self, b::*,};\nuse {x, y};";
+        let statements = make_sut(source).parse().unwrap();
+
+        assert_eq!(
+            sexpr::statements_to_sexpr(&statements),
+            "(use (path a (group (path b (group c d)) e)))\n\
+             (use (path std (path io *)))\n\
+             (use (path a (group self (path b *))))\n\
+             (use (group x y))\n"
+        );
+        assert_eq!(
+            statements
+                .iter()
+                .filter_map(|statement| match statement {
+                    Statement::Use { tree, .. } => Some(tree.to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>(),
+            ["a::{b::{c, d}, e}", "std::io::*", "a::{self, b::*}", "{x, y}"]
+        );
+        assert_eq!(statements[1].location(), Location::new(23, 38));
+
+        for source in [
+            "use *;",
+            "use {a, *};",
+            "use;",
+            "use a::;",
+            "use a::{b",
+            "use a::{b c};",
+            "use a::*::b;",
+            "use a",
+        ] {
+            assert!(make_sut(source).parse().is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn glob_imports_are_warned_about() {
+        let source = "use foo::*;
+use foo::{bar::*, baz};
+#[allow(glob_import)]
+use quux::*;";
+        let (statements, warnings) = make_sut(source).parse_with_warnings().unwrap();
+
+        assert_eq!(
+            sexpr::statements_to_sexpr(&statements),
+            "(use (path foo *))\n\
+             (use (path foo (group (path bar *) baz)))\n\
+             (use (path quux *) (attribute allow glob_import))\n"
+        );
+        assert_eq!(statements[2].location(), Location::new(36, source.len()));
+
+        let globs = warnings
+            .iter()
+            .map(|warning| match warning {
+                DiagnosticWarning::GlobImport { path, span } => (path.as_str(), span.offset()),
+                warning => panic!("expected a glob import, got {warning:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(globs, [("foo", 9), ("foo::bar", 27)]);
+        assert_eq!(warnings[1].to_string(), "glob import from `foo::bar`");
+    }
+
+    #[test]
+    fn warnings_stay_on_the_parser() {
+        let mut parser = make_sut("use foo::*;\nfun f( {}");
+        assert!(parser.parse().is_err());
+        assert_eq!(parser.take_warnings().len(), 1);
+        assert!(parser.take_warnings().is_empty());
+
+        // a parse of its own doesn't see the warnings of the one before
+        let (_, warnings) = make_sut("use foo;").parse_with_warnings().unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn match_expressions() {
+        let source = "fun main() {\n    match count {\n        0 => \"none\",\n        true => { one(); }\n        Color::Red => red,\n        n => n,\n        _ => 1.5,\n    }\n}";
+        let statements = make_sut(source).parse().unwrap();
+
+        assert_eq!(
+            sexpr::statements_to_sexpr(&statements),
+            "(fun main (args) (match (ident count) \
+             (arm (uint-literal 0) (string-literal \"none\")) \
+             (arm (bool true) (block (call (ident one)))) \
+             (arm (variant Color::Red) (ident red)) \
+             (arm (binding n) (ident n)) \
+             (arm _ (float-literal 1.5))))\n"
+        );
+
+        let Statement::Fun { body, .. } = &statements[0] else {
+            unreachable!();
+        };
+        let Expression::Match { arms, location, .. } = &body[0] else {
+            unreachable!();
+        };
+        let patterns = arms.iter().map(|arm| arm.pattern.to_string()).collect::<Vec<_>>();
+        assert_eq!(patterns, ["0", "true", "Color::Red", "n", "_"]);
+        assert_eq!(
+            &source[location.start_byte..location.end_byte],
+            &source[17..source.len() - 2]
+        );
+
+        for source in [
+            "fun main() { match x { 1 => 2 3 => 4 } }",
+            "fun main() { match x { 1 2 } }",
+            "fun main() { match x { \"{x}\" => 1 } }",
+            "fun main() { match x { (1) => 1 } }",
+            "fun main() { match x { 1 => 1",
+            "fun main() { match { 1 => 1 } }",
+        ] {
+            assert!(make_sut(source).parse().is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn enum_variants() {
+        let source = "fun main() {\n    \
+                      const a = Color::Red;\n    \
+                      const b = Shape::Circle(1.5, math::PI);\n    \
+                      const c = Shape::Rect { w: 1, h: 2, };\n    \
+                      if a == Color::Red { a }\n    \
+                      match b { Shape::Circle(r, _) => r, Shape::Rect { w: w, h: 2 } => w, Some(x) => x, None => 0 }\n}";
+        let statements = make_sut(source).parse().unwrap();
+
+        assert_eq!(
+            sexpr::statements_to_sexpr(&statements),
+            "(fun main (args) \
+             (const a (variant Color::Red)) \
+             (const b (variant Shape::Circle (float-literal 1.5) (path math::PI))) \
+             (const c (variant Shape::Rect (field w (uint-literal 1)) (field h (uint-literal 2)))) \
+             (if (binary-op == (ident a) (variant Color::Red)) (block (tail (ident a)))) \
+             (match (ident b) \
+             (arm (variant Shape::Circle (binding r) _) (ident r)) \
+             (arm (variant Shape::Rect (field w (binding w)) (field h (uint-literal 2))) (ident w)) \
+             (arm (variant Some (binding x)) (ident x)) \
+             (arm (binding None) (uint-literal 0))))\n"
+        );
+
+        let Statement::Fun { body, .. } = &statements[0] else {
+            unreachable!();
+        };
+        let Expression::Var { value, .. } = &body[2] else {
+            unreachable!();
+        };
+        let location = value.location();
+        assert_eq!(
+            &source[location.start_byte..location.end_byte],
+            "Shape::Rect { w: 1, h: 2, }"
+        );
+
+        for source in [
+            "fun main() { const a = Shape::Rect { w }; }",
+            "fun main() { const a = Shape::Circle(1 2); }",
+            "fun main() { match a { Shape::Rect { 1: w } => w } }",
+        ] {
+            assert!(make_sut(source).parse().is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn extern_crates() {
+        let source = "extern crate serde;\n#[macro_use] #[cfg(feature)]\nextern crate log as logging;\nextern crate self as root;";
+        let statements = make_sut(source).parse().unwrap();
+
+        assert_eq!(
+            sexpr::statements_to_sexpr(&statements),
+            "(extern-crate serde)\n\
+             (extern-crate log (as logging) (attribute macro_use) (attribute cfg feature))\n\
+             (extern-crate self (as root))\n"
+        );
+        assert_eq!(
+            statements[1].location().start_byte,
+            source.find("#[macro_use]").unwrap()
+        );
+        assert!(matches!(&statements[0], Statement::ExternCrate { is_self: false, .. }));
+        assert!(matches!(&statements[2], Statement::ExternCrate { is_self: true, .. }));
+
+        for source in [
+            "extern crate self;",
+            "extern crate;",
+            "extern crate a as;",
+            "extern crate a",
+            "extern a;",
+            "crate a;",
+        ] {
+            assert!(make_sut(source).parse().is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_functions() {
+        let source = r#"
+            fun helper() {}
+
+            #[test]
+            fun adds() { assert(ready); }
+
+            #[test]
+            #[should_panic(expected = "overflow")]
+            fun overflows() { add(255, 1); }
+        "#;
+
+        let ast = match make_sut(source).parse() {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+        assert_eq!(ast[1].location().start_byte, source.find("#[test]").unwrap());
+
+        let tests = collect_test_functions(&ast);
+        let names = tests
+            .iter()
+            .filter_map(|test| match test {
+                Statement::Fun { name, .. } => Some(name.as_ref()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["adds", "overflows"]);
+
+        let Statement::Fun { attributes, .. } = tests[1] else {
+            panic!("expected a function");
+        };
+        assert_eq!(attributes[1].value("expected"), Some("overflow"));
+        assert_eq!(attributes[1].to_string(), r#"#[should_panic(expected = "overflow")]"#);
+        assert_eq!(
+            sexpr::statements_to_sexpr(&ast[2..]),
+            "(fun overflows (attribute test) (attribute should_panic (expected \"overflow\")) (args) \
+             (call (ident add) (uint-literal 255) (uint-literal 1)))\n"
+        );
+    }
+
+    #[test]
+    fn doc_comments_are_attached_to_declarations() {
+        let source = r#"
+            /// adds two
+            /// numbers
+            #[test]
+            fun add() {}
+
+            /** a point */
+            // not a doc comment
+            //// nor this one
+            struct Point { x: i32 }
+
+            fun undocumented() {}
+
+            impl Point {
+                /// the origin
+                fun origin() {}
+            }
+        "#;
+
+        let ast = match make_sut(source).parse() {
+            Ok(ast) => ast,
+            Err(e) => panic!("{e:?}"),
+        };
+        fn docs<'a>(statement: &'a Statement<'_>) -> Vec<&'a str> {
+            let doc = match statement {
+                Statement::Fun { doc, .. } | Statement::Struct { doc, .. } => doc,
+                Statement::Impl { functions, .. } => match &functions[0] {
+                    Statement::Fun { doc, .. } => doc,
+                    _ => panic!("expected a function"),
+                },
+                _ => panic!("expected a declaration"),
+            };
+            doc.iter().map(AsRef::as_ref).collect()
+        }
+        assert_eq!(docs(&ast[0]), ["/// adds two", "/// numbers"]);
+        assert_eq!(docs(&ast[1]), ["/** a point */"]);
+        assert!(docs(&ast[2]).is_empty());
+        assert_eq!(docs(&ast[3]), ["/// the origin"]);
+
+        assert_eq!(ast[0].location().start_byte, source.find("#[test]").unwrap());
+        assert_eq!(doc::format_doc(&docs(&ast[0])), "adds two\nnumbers");
+    }
+
+    #[test]
+    fn deprecated_attributes() {
+        let source = r#"#[deprecated(since = "0.2", note = "use bar")] fun foo() {}"#;
+        let ast = match make_sut(source).parse() {
+            Ok(ast) => ast,
+            Err(e) => panic!("{e:?}"),
+        };
+        let Statement::Fun { attributes, .. } = &ast[0] else {
+            panic!("expected a function");
+        };
+        assert_eq!(attributes[0].value("since"), Some("0.2"));
+        assert_eq!(attributes[0].value("note"), Some("use bar"));
+
+        for source in [
+            "#[deprecated(reason = \"old\")] fun foo() {}",
+            "#[deprecated(since)] fun foo() {}",
+        ] {
+            assert!(make_sut(source).parse().is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn defers() {
+        let source = "fun f() { var file = open(); defer { close(file); } defer { log(1); } work(file); }";
+        let (ast, warnings) = match make_sut(source).parse_with_warnings() {
+            Ok(parsed) => parsed,
+            Err(e) => panic!("{e:?}"),
+        };
+        assert_eq!(
+            sexpr::statements_to_sexpr(&ast),
+            "(fun f (args) (var file (call (ident open))) (defer (block (call (ident close) (ident file)))) \
+             (defer (block (call (ident log) (uint-literal 1)))) (call (ident work) (ident file)))\n"
+        );
+        assert!(warnings.is_empty());
+
+        let source = "fun f() { var a = defer { 1 }; }";
+        let (_, warnings) = make_sut(source).parse_with_warnings().unwrap();
+        let [DiagnosticWarning::DeferOutsideBlock { span }] = warnings.as_slice() else {
+            panic!("expected a single warning, got {warnings:?}");
+        };
+        assert_eq!(&source[span.offset()..span.offset() + span.len()], "defer { 1 }");
+
+        let error = make_sut("defer { close(file); }").parse().unwrap_err();
+        assert_eq!(error.message, "`defer` can only be used inside of a block");
+    }
+
+    #[test]
+    fn unsafe_outside_of_a_function() {
+        let source = "unsafe fun f() { asm!(\"nop\"); } impl P { #[inline] unsafe fun g() {} }";
+        let ast = match make_sut(source).parse() {
+            Ok(ast) => ast,
+            Err(e) => panic!("{e:?}"),
+        };
+        let Statement::Fun { is_unsafe, location, .. } = &ast[0] else {
+            panic!("expected a function");
+        };
+        assert!(is_unsafe);
+        assert_eq!(*location, Location::new(0, 31));
+        let Statement::Impl { functions, .. } = &ast[1] else {
+            panic!("expected an impl");
+        };
+        assert!(matches!(functions[0], Statement::Fun { is_unsafe: true, .. }));
+
+        let error = make_sut("unsafe { f(); }").parse().unwrap_err();
+        assert_eq!(
+            error.message,
+            "an `unsafe { .. }` block can only be written inside of a function"
+        );
+        assert_eq!(error.location(), Location::new(0, 6));
+
+        let error = make_sut("unsafe var x = 1;").parse().unwrap_err();
+        assert_eq!(error.message, "expected `{` or `fun` after `unsafe`, found `var`");
+        assert_eq!(error.location(), Location::new(7, 10));
+    }
+
+    #[test]
+    fn where_clauses() {
+        let source = "fun apply<T>(f: T) => i32 where for<'a> T: Fn(&'a i32) -> &'a i32, U: Show + Trait<'b>, { 1 }";
+        let ast = match make_sut(source).parse() {
+            Ok(ast) => ast,
+            Err(e) => panic!("{e:?}"),
+        };
+        let Statement::Fun { where_clause, .. } = &ast[0] else {
+            panic!("expected a function");
+        };
+        assert_eq!(
+            sexpr::statements_to_sexpr(&ast),
+            "(fun apply (generics T) (args (arg f (type-name T))) (returns (type-name i32)) \
+             (where ((for 'a) (type-name T) (fn-trait Fn (params (ref-type 'a (type-name i32))) \
+             (returns (ref-type 'a (type-name i32))))) ((type-name U) (type-name Show) \
+             (generic-type Trait (lifetime b)))) (uint-literal 1))\n"
+        );
+
+        let higher_ranked = &where_clause[0];
+        assert_eq!(higher_ranked.lifetimes.len(), 1);
+        assert_eq!(higher_ranked.lifetimes[0].name, "a");
+        let location = higher_ranked.location;
+        assert_eq!(
+            &source[location.start_byte..location.end_byte],
+            "for<'a> T: Fn(&'a i32) -> &'a i32"
+        );
+        assert_eq!(higher_ranked.to_string(), "for<'a> T: Fn(&'a i32) -> &'a i32");
+
+        assert!(make_sut("fun f() where {}").parse().is_err());
+        assert!(make_sut("fun f() where for<> T: Show {}").parse().is_err());
+        assert!(make_sut("fun f() where for<T> T: Show {}").parse().is_err());
+    }
+
+    #[test]
+    fn inline_attributes() {
+        let source = "#[inline(always)] fun hot() {}\n#[inline] fun warm() {}\n#[inline(never)] fun cold() {}";
+        let (ast, warnings) = match make_sut(source).parse_with_warnings() {
+            Ok(parsed) => parsed,
+            Err(e) => panic!("{e:?}"),
+        };
+        let inlines = ast
+            .iter()
+            .map(|function| match function {
+                Statement::Fun { inline, .. } => *inline,
+                _ => panic!("expected a function"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            inlines,
+            [Some(Inline { always: true }), None, Some(Inline { always: false })]
+        );
+        assert!(warnings.is_empty());
+
+        let source = "#[inline(never)]\n#[inline(always)]\nfun both() {}";
+        let (ast, warnings) = match make_sut(source).parse_with_warnings() {
+            Ok(parsed) => parsed,
+            Err(e) => panic!("{e:?}"),
+        };
+        let Statement::Fun { inline, .. } = &ast[0] else {
+            panic!("expected a function");
+        };
+        assert_eq!(*inline, Some(Inline { always: false }));
+        let [DiagnosticWarning::ConflictingAttributes { first, second, .. }] = warnings.as_slice() else {
+            panic!("expected a single warning, got {warnings:?}");
+        };
+        assert_eq!(
+            &source[first.offset()..first.offset() + first.len()],
+            "#[inline(never)]"
+        );
+        assert_eq!(
+            &source[second.offset()..second.offset() + second.len()],
+            "#[inline(always)]"
+        );
+
+        for source in [
+            "#[inline(sometimes)] fun foo() {}",
+            "#[inline(always, never)] fun foo() {}",
+        ] {
+            assert!(make_sut(source).parse().is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn returning_several_values() {
+        let implicit = "fun pair() => (i32, i32) { return 1, 2; }";
+        let explicit = "fun pair() => (i32, i32) { return (1, 2); }";
+        let parse = |source| match make_sut(source).parse() {
+            Ok(ast) => ast,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        let ast = parse(implicit);
+        assert!(ast[0].returns_multiple());
+        assert_eq!(
+            sexpr::statements_to_sexpr(&ast),
+            sexpr::statements_to_sexpr(&parse(explicit))
+        );
+        assert!(!parse("fun single() => (i32,) { return (1,); }")[0].returns_multiple());
+        assert!(!parse("fun none() => i32 { return 1; }")[0].returns_multiple());
+
+        assert!(make_sut("fun pair() => (i32, i32) { return 1, ; }").parse().is_err());
+    }
+
+    #[test]
+    fn globals() {
+        let source = "var counter: u64 = 0;\n#[allow(mutable_global)]\nconst LIMIT = 1 + 2;\nfun main() {}";
+        let ast = match make_sut(source).parse() {
+            Ok(ast) => ast,
+            Err(e) => panic!("{e:?}"),
+        };
+        assert_eq!(
+            sexpr::statements_to_sexpr(&ast[..2]),
+            "(static-var counter (type (type-name u64)) (uint-literal 0))\n\
+             (static-const LIMIT (attribute allow mutable_global) (binary-op + (uint-literal 1) (uint-literal 2)))\n"
+        );
+        assert_eq!(
+            &source[ast[1].location().start_byte..ast[1].location().end_byte],
+            "#[allow(mutable_global)]\nconst LIMIT = 1 + 2"
+        );
+
+        assert!(make_sut("var counter = 0").parse().is_err());
+    }
+
+    #[test]
+    fn unclosed_blocks_are_recovered_from() {
+        let source = "fun main() {\n    const a = 1;\n    if a == 1 {\n        print(a);\n";
+        let (ast, errors) = match make_sut(source).parse_recovering() {
+            Ok(result) => result,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        assert_eq!(
+            sexpr::statements_to_sexpr(&ast),
+            "(fun main (args) (const a (uint-literal 1)) \
+             (if (binary-op == (ident a) (uint-literal 1)) (block (call (ident print) (ident a)))))\n"
+        );
+        assert_eq!(ast[0].location(), Location::new(0, source.len()));
+
+        // the innermost opener is the first one found unclosed
+        let openers = errors
+            .iter()
+            .map(|error| error.location().start_byte)
+            .collect::<Vec<_>>();
+        assert_eq!(openers, [source.rfind('{').unwrap(), source.find('{').unwrap()]);
+        assert_eq!(
+            errors[0].message,
+            "this `{` is never closed, expected a `}` before the end of the input"
+        );
+        assert!(errors.iter().all(ParseError::is_incomplete));
+
+        // what was being parsed when the input ended is left out
+        let (ast, errors) = make_sut("fun main() { const a = 1; print(a")
+            .parse_recovering()
+            .unwrap();
+        assert_eq!(
+            sexpr::statements_to_sexpr(&ast),
+            "(fun main (args) (const a (uint-literal 1)))\n"
+        );
+        assert_eq!(errors.len(), 1);
+
+        // errors anywhere else are not recovered from, and without recovery
+        // an unclosed block fails the parse like before
+        assert!(make_sut("fun main() { const = 1; ").parse_recovering().is_err());
+        assert!(make_sut(source).parse().unwrap_err().is_incomplete());
+
+        // a failed parse stops recovering, keeping no errors around
+        let mut parser = make_sut("fun f() { const = 1; }");
+        assert!(parser.parse_recovering().is_err());
+        assert!(!parser.context.recovering());
+    }
+
+    #[test]
+    fn module_declarations() {
+        let source = "mod lexer;\nmod  parser ;";
+        let statements = make_sut(source).parse().unwrap();
+
+        assert_eq!(sexpr::statements_to_sexpr(&statements), "(mod lexer)\n(mod parser)\n");
+        assert_eq!(statements[1].location(), Location::new(11, source.len()));
+        for source in ["mod;", "mod a", "mod a::b;", "mod a {}"] {
+            assert!(make_sut(source).parse().is_err(), "{source}");
+        }
+    }
+
+    #[test]
+    fn embedded_code_reports_locations_in_its_host() {
+        // taken out of a file where it starts at byte 30
+        let source = "fun f() { print(\"{a}\"); 1 +; }";
+        let error = Parser::new(source, Lexer::with_offset(source, 30, 2))
+            .parse()
+            .unwrap_err();
+
+        assert_eq!(error.location(), Location::new(57, 58));
+    }
+
+    #[test]
+    fn macro_rules_definitions() {
+        let source = "macro_rules! square { ($x:expr) => { $x * $x }; () => [0] }";
+        let ast = match make_sut(source).parse() {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        assert_eq!(ast[0].location(), Location::new(0, source.len()));
+        assert_eq!(
+            sexpr::statements_to_sexpr(&ast),
+            "(macro-rules square (rule (pattern $ x : expr) (expansion $ x * $ x)) (rule (pattern) (expansion 0)))\n"
+        );
+
+        let invalid = [
+            "macro_rules! square { x => { x } }",
+            "macro_rules! square { (x) { x } }",
+            "macro_rules! square { (x) => { x } (y) => { y } }",
+            "macro_rules! { (x) => { x } }",
+            "macro_rules! square { (x) => { x ] }",
+        ];
+        for source in invalid {
+            assert!(!make_sut(source).parse().unwrap_err().is_incomplete(), "{source}");
+        }
+
+        for source in [
+            "macro_rules! square {",
+            "macro_rules! square { (x) =>",
+            "macro_rules! square { (x) => { x",
+        ] {
+            assert!(make_sut(source).parse().unwrap_err().is_incomplete(), "{source}");
+        }
+    }
+
+    fn parse_with(source: &str, options: ParserOptions) -> Result<Vec<Statement<'_>>, ParseError> {
+        Parser::new_with_options(source, Lexer::new(source), options).parse()
+    }
+
+    #[test]
+    fn default_literal_sizes() {
+        let source = "fun main() { var a = -42; var b = 42; var c = 1.5; var d = 7u8; var e = -1i64; }";
+        let options = ParserOptions {
+            default_int_size: Some(IntSizes::I32),
+            default_uint_size: Some(UIntSizes::U32),
+            default_float_size: Some(FloatSizes::F32),
+        };
+
+        let ast = parse_with(source, options).unwrap();
+        insta::assert_debug_snapshot!(ast);
+
+        // without options, they are left for type inference to decide
+        let Statement::Fun { body, .. } = &parse_with(source, ParserOptions::default()).unwrap()[0] else {
+            panic!("expected a function");
+        };
+        let Expression::Var { value, .. } = &body[0] else {
+            panic!("expected a variable");
+        };
+        assert!(matches!(value.as_ref(), Expression::IntLiteral { size: None, .. }));
+    }
+
+    #[test]
+    fn literals_out_of_range_for_the_default_size() {
+        let source = "fun main() { const x = 70000; }";
+        let sixteen = ParserOptions {
+            default_int_size: Some(IntSizes::I16),
+            default_uint_size: Some(UIntSizes::U16),
+            ..Default::default()
+        };
+        let error = parse_with(source, sixteen).unwrap_err();
+        assert_eq!(error.message, "literal out of range for `u16`");
+        assert_eq!(error.location(), Location::new(23, 28));
+
+        let thirty_two = ParserOptions {
+            default_int_size: Some(IntSizes::I32),
+            default_uint_size: Some(UIntSizes::U32),
+            ..Default::default()
+        };
+        assert!(parse_with(source, thirty_two).is_ok());
+
+        // an explicit suffix is checked against its own size
+        let error = parse_with("fun main() { const x = 300u8; }", ParserOptions::default()).unwrap_err();
+        assert_eq!(error.message, "literal out of range for `u8`");
+    }
 }
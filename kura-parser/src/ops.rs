@@ -0,0 +1,213 @@
+use kura_lexer::token::Operator;
+
+/// the binding power expressions start being parsed at, lower than that of
+/// every operator
+pub const LOWEST: u8 = 0;
+/// `a ?: b` falls back to `b` after every other operation in `a` is done
+pub const ELVIS: u8 = 1;
+/// `a || b && c` is `a || (b && c)`
+pub const OR: u8 = 2;
+pub const AND: u8 = 3;
+/// `x < y + 1` compares with the sum, and `a == b && c` joins the comparison
+pub const COMPARISON: u8 = 4;
+pub const SUM: u8 = 5;
+pub const PRODUCT: u8 = 6;
+/// `a + b try_as T` only converts `b`, while `&a try_as T` converts the
+/// reference
+pub const CAST: u8 = 7;
+/// references, dereferences and `!` take their operand before any infix
+/// operator
+pub const PREFIX: u8 = 8;
+/// calls, indexing, field accesses, `?` and macro invocations bind tighter
+/// than anything
+pub const POSTFIX: u8 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Associativity {
+    /// `a - b - c` is `(a - b) - c`
+    Left,
+    /// operators of the same binding power group from the right
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fixity {
+    /// written before its operand, like the `&` of a reference. negative
+    /// numbers are lexed as literals, so `-` is never prefix
+    Prefix,
+    /// written between its operands
+    Infix,
+    /// written after its operand, like the `(` of a call
+    Postfix,
+}
+
+/// how the parser treats an operator inside of an expression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OpInfo {
+    pub operator: Operator,
+    /// operators with a higher binding power take their operands first
+    pub binding_power: u8,
+    pub associativity: Associativity,
+    pub fixity: Fixity,
+    pub symbol: &'static str,
+}
+
+const fn op(
+    operator: Operator,
+    binding_power: u8,
+    associativity: Associativity,
+    fixity: Fixity,
+    symbol: &'static str,
+) -> OpInfo {
+    OpInfo {
+        operator,
+        binding_power,
+        associativity,
+        fixity,
+        symbol,
+    }
+}
+
+/// every operator that can be part of an expression. this is the table the
+/// parser reads, so tooling deciding where parenthesis go agrees with it
+pub const OPERATORS: &[OpInfo] = {
+    use Associativity::*;
+    use Fixity::*;
+
+    &[
+        op(Operator::Elvis, ELVIS, Left, Infix, "?:"),
+        op(Operator::Plus, SUM, Left, Infix, "+"),
+        op(Operator::Minus, SUM, Left, Infix, "-"),
+        op(Operator::Star, PRODUCT, Left, Infix, "*"),
+        op(Operator::Slash, PRODUCT, Left, Infix, "/"),
+        op(Operator::And, AND, Left, Infix, "&&"),
+        op(Operator::Or, OR, Left, Infix, "||"),
+        op(Operator::EqualEqual, COMPARISON, Left, Infix, "=="),
+        op(Operator::NotEqual, COMPARISON, Left, Infix, "!="),
+        op(Operator::Less, COMPARISON, Left, Infix, "<"),
+        op(Operator::LessEqual, COMPARISON, Left, Infix, "<="),
+        op(Operator::Greater, COMPARISON, Left, Infix, ">"),
+        op(Operator::GreaterEqual, COMPARISON, Left, Infix, ">="),
+        op(Operator::Ampersand, PREFIX, Right, Prefix, "&"),
+        op(Operator::Star, PREFIX, Right, Prefix, "*"),
+        op(Operator::Bang, PREFIX, Right, Prefix, "!"),
+        op(Operator::LeftParen, POSTFIX, Left, Postfix, "("),
+        op(Operator::LeftBracket, POSTFIX, Left, Postfix, "["),
+        op(Operator::Dot, POSTFIX, Left, Postfix, "."),
+        op(Operator::Bang, POSTFIX, Left, Postfix, "!"),
+        op(Operator::Question, POSTFIX, Left, Postfix, "?"),
+    ]
+};
+
+/// operators that only ever delimit or separate the parts of a construct,
+/// and never combine expressions on their own
+pub const PUNCTUATION: &[Operator] = &[
+    Operator::RightParen,
+    Operator::RightBracket,
+    Operator::LeftBrace,
+    Operator::RightBrace,
+    Operator::Comma,
+    Operator::Colon,
+    Operator::ColonColon,
+    Operator::SemiColon,
+    Operator::ThickArrow,
+    // only binds names inside of `do` blocks
+    Operator::LeftArrow,
+    // only written before the return type of a bound
+    Operator::ThinArrow,
+    Operator::Hash,
+    // only ever written inside of the token trees of macros
+    Operator::Dollar,
+    // not operators of the language, rejected as soon as they are found
+    Operator::PlusPlus,
+    Operator::MinusMinus,
+    // assignments are statements, parsed on their own rather than as an
+    // operation with a binding power
+    Operator::Equal,
+    Operator::PlusEqual,
+    Operator::MinusEqual,
+    Operator::StarEqual,
+    Operator::SlashEqual,
+];
+
+/// infix operators whose operands can be grouped either way, so `a + (b + c)`
+/// is the same as `(a + b) + c`. that only holds for integers and booleans,
+/// floats round differently depending on the grouping
+pub const ASSOCIATIVE: &[Operator] = &[Operator::Plus, Operator::Star, Operator::And, Operator::Or];
+
+/// the entry of `operator` written the way `fixity` says. an operator can
+/// have one entry of each fixity, as `*` multiplies and dereferences
+pub fn lookup(operator: Operator, fixity: Fixity) -> Option<&'static OpInfo> {
+    OPERATORS
+        .iter()
+        .find(|info| info.operator == operator && info.fixity == fixity)
+}
+
+/// the entry of `operator` if it is written between two expressions
+pub fn infix(operator: Operator) -> Option<&'static OpInfo> {
+    lookup(operator, Fixity::Infix)
+}
+
+/// the entry of `operator` if it is written before an expression
+pub fn prefix(operator: Operator) -> Option<&'static OpInfo> {
+    lookup(operator, Fixity::Prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::token::Kind;
+    use kura_lexer::Lexer;
+
+    use super::*;
+
+    /// every operator the lexer produces out of one or two ascii characters,
+    /// which is as long as any operator is
+    fn lexed_operators() -> Vec<Operator> {
+        let printable = (b'!'..=b'~').map(char::from).collect::<Vec<_>>();
+        let mut operators: Vec<Operator> = vec![];
+        for first in &printable {
+            for second in std::iter::once(None).chain(printable.iter().map(Some)) {
+                let source = format!("{first}{}", second.map(char::to_string).unwrap_or_default());
+                for token in Lexer::new(&source).filter_map(Result::ok) {
+                    if let Kind::Op(operator) = token.kind {
+                        if !operators.contains(&operator) {
+                            operators.push(operator);
+                        }
+                    }
+                }
+            }
+        }
+        operators
+    }
+
+    #[test]
+    fn every_operator_has_a_decision() {
+        let operators = lexed_operators();
+        assert!(operators.len() >= OPERATORS.len());
+
+        for operator in operators {
+            let entries = OPERATORS.iter().filter(|info| info.operator == operator).count();
+            let punctuation = PUNCTUATION.contains(&operator);
+            assert!(
+                (entries > 0) != punctuation,
+                "`{operator}` must have an entry in `OPERATORS` or be listed in `PUNCTUATION`"
+            );
+
+            for fixity in [Fixity::Prefix, Fixity::Infix, Fixity::Postfix] {
+                let same_fixity = OPERATORS
+                    .iter()
+                    .filter(|info| info.operator == operator && info.fixity == fixity)
+                    .count();
+                assert!(same_fixity <= 1, "`{operator}` has more than one {fixity:?} entry");
+            }
+        }
+    }
+
+    #[test]
+    fn symbols_are_how_operators_are_written() {
+        for info in OPERATORS {
+            assert_eq!(info.symbol, info.operator.to_string());
+            assert!(info.binding_power > LOWEST);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+mod associativity;
+mod comptime;
+mod deprecated;
+mod derive;
+mod desugar;
+mod exhaustiveness;
+mod fold;
+mod format_args;
+mod generators;
+mod global_state;
+mod lints;
+mod macro_expand;
+mod magic_numbers;
+mod reachability;
+mod reify;
+mod shadowing;
+mod tail_calls;
+mod unreachable;
+mod unused;
+
+pub use associativity::normalize_associativity;
+pub use comptime::check_comptime;
+pub use deprecated::{lint_deprecated, Deprecated, DEPRECATED_ATTRIBUTE, DEPRECATED_KEYS};
+pub use derive::{derive_expand, DERIVABLE_TRAITS};
+pub(crate) use desugar::map_children;
+pub use desugar::{
+    desugar_await, desugar_compound_assign, desugar_interpolation, normalize, AWAIT_FUNCTION, FORMAT_FUNCTION,
+};
+pub use exhaustiveness::{check_exhaustiveness, NonExhaustiveMatch, UnprovableMatch};
+pub use fold::{fold_constants, fold_constants_statements};
+pub use format_args::{lint_format_args, validate_format_args, FORMAT_MACROS};
+pub use generators::{lint_yield_outside_generator, YieldOutsideGenerator, GENERATOR_ATTRIBUTE};
+pub use global_state::{analyze_global_state, MutableGlobal};
+pub use lints::lint_all;
+pub use macro_expand::{expand_macros, MacroEnv, MacroError, MAX_EXPANSION_DEPTH};
+pub use magic_numbers::{lint_magic_numbers, MagicNumber};
+pub use reachability::{analyze_reachability, analyze_reachability_statements};
+pub use reify::reify_generics;
+pub use shadowing::lint_shadowing;
+pub use tail_calls::mark_tail_calls;
+pub use unreachable::{is_diverging, unreachable_code, unreachable_code_statements, PANIC_MACRO};
+pub use unused::{lint_unused, lint_unused_statements};
@@ -0,0 +1,156 @@
+use kura_lexer::token::{Location, Operator};
+
+use super::desugar::map_children;
+use crate::ops;
+use crate::Expression;
+
+/// regroups every chain of the same associative operator to the left, so
+/// `a + (b + c)` becomes `(a + b) + c` and both are written the same way.
+/// only the operators in `ops::ASSOCIATIVE` are regrouped, and the operands
+/// keep their order.
+///
+/// regrouping floats changes how they round, so a chain with a float
+/// literal in it is left alone. the pass has no types though, so a chain of
+/// names holding floats is still regrouped, it must only run where every
+/// operand is known not to be a float
+pub fn normalize_associativity(expr: Expression<'_>) -> Expression<'_> {
+    match map_children(expr, normalize_associativity) {
+        Expression::BinaryOp {
+            operator,
+            lhs,
+            rhs,
+            location,
+            ..
+        } if ops::ASSOCIATIVE.contains(&operator) && !has_float_operand(operator, &lhs, &rhs) => {
+            // the right operand was already regrouped, so its own chain leans
+            // left and its operands can be appended one by one
+            let mut operands = vec![];
+            flatten(operator, *rhs, &mut operands);
+            let last = operands.pop().expect("a chain has an operand");
+            let lhs = operands.into_iter().fold(*lhs, |lhs, rhs| Expression::BinaryOp {
+                operator,
+                location: Location::new(lhs.location().start_byte, rhs.location().end_byte),
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+                typ: None,
+            });
+            // the whole operation still spans what it did before
+            Expression::BinaryOp {
+                operator,
+                lhs: Box::new(lhs),
+                rhs: Box::new(last),
+                typ: None,
+                location,
+            }
+        }
+        expr => expr,
+    }
+}
+
+/// pushes the operands of the chain of `operator` that `expr` is, from left
+/// to right. anything else is a single operand
+fn flatten<'ast>(operator: Operator, expr: Expression<'ast>, operands: &mut Vec<Expression<'ast>>) {
+    match expr {
+        Expression::BinaryOp {
+            operator: inner,
+            lhs,
+            rhs,
+            ..
+        } if inner == operator => {
+            flatten(operator, *lhs, operands);
+            flatten(operator, *rhs, operands);
+        }
+        operand => operands.push(operand),
+    }
+}
+
+fn has_float_operand(operator: Operator, lhs: &Expression<'_>, rhs: &Expression<'_>) -> bool {
+    [lhs, rhs].into_iter().any(|operand| match operand {
+        Expression::FloatLiteral { .. } => true,
+        Expression::BinaryOp {
+            operator: inner,
+            lhs,
+            rhs,
+            ..
+        } if *inner == operator => has_float_operand(operator, lhs, rhs),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::expression::parse_expression;
+    use crate::sexpr::to_sexpr;
+
+    fn normalize(source: &str) -> String {
+        let mut lexer = Lexer::new(source);
+        let expr = match parse_expression(&mut lexer, false) {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+        to_sexpr(&normalize_associativity(expr))
+    }
+
+    #[test]
+    fn chains_lean_left() {
+        let left = normalize("(a + b) + c");
+        assert_eq!(left, "(binary-op + (binary-op + (ident a) (ident b)) (ident c))");
+        assert_eq!(normalize("a + (b + c)"), left);
+        assert_eq!(normalize("a + b + c"), left);
+        assert_eq!(
+            normalize("(a * b) * (c * d)"),
+            "(binary-op * (binary-op * (binary-op * (ident a) (ident b)) (ident c)) (ident d))"
+        );
+        assert_eq!(
+            normalize("a && (b && (c || (d || e)))"),
+            "(binary-op && (binary-op && (ident a) (ident b)) \
+             (binary-op || (binary-op || (ident c) (ident d)) (ident e)))"
+        );
+    }
+
+    #[test]
+    fn only_associative_operators_are_regrouped() {
+        assert_eq!(
+            normalize("a - (b - c)"),
+            "(binary-op - (ident a) (binary-op - (ident b) (ident c)))"
+        );
+        assert_eq!(
+            normalize("a + (b * c)"),
+            "(binary-op + (ident a) (binary-op * (ident b) (ident c)))"
+        );
+        // the chain inside of the call is regrouped on its own
+        assert_eq!(
+            normalize("f(a * (b * c)) + d"),
+            "(binary-op + (call (ident f) (binary-op * (binary-op * (ident a) (ident b)) (ident c))) (ident d))"
+        );
+    }
+
+    #[test]
+    fn floats_keep_their_grouping() {
+        assert_eq!(
+            normalize("a + (b + 1.5)"),
+            "(binary-op + (ident a) (binary-op + (ident b) (float-literal 1.5)))"
+        );
+        assert_eq!(
+            normalize("0.5 * (b * c)"),
+            "(binary-op * (float-literal 0.5) (binary-op * (ident b) (ident c)))"
+        );
+    }
+
+    #[test]
+    fn locations_cover_the_regrouped_operands() {
+        let source = "a + (b + c)";
+        let mut lexer = Lexer::new(source);
+        let expr = parse_expression(&mut lexer, false).unwrap();
+        let before = expr.location();
+        let Expression::BinaryOp { lhs, location, .. } = normalize_associativity(expr) else {
+            panic!("expected a binary operation");
+        };
+        assert_eq!(location, before);
+        let location = lhs.location();
+        assert_eq!(&source[location.start_byte..location.end_byte], "a + (b");
+    }
+}
@@ -0,0 +1,86 @@
+use miette::Report;
+
+use super::desugar::for_each_child;
+use crate::const_eval::ConstExprEvaluator;
+use crate::{Expression, Statement};
+
+/// evaluates every `comptime` block of the program, in functions and in the
+/// values of globals, reporting the ones whose value can't be known at
+/// compile time as errors. a block nested inside of another is evaluated
+/// along with it, so it is never reported on its own
+pub fn check_comptime(statements: &[Statement<'_>]) -> Vec<Report> {
+    let mut reports = vec![];
+
+    for statement in statements {
+        if let Statement::Static { value, .. } = statement {
+            visit(value, &mut reports);
+        }
+    }
+
+    for statement in statements.iter().flat_map(Statement::functions) {
+        if let Statement::Fun { body, .. } = statement {
+            body.iter().for_each(|expr| visit(expr, &mut reports));
+        }
+    }
+
+    reports
+}
+
+fn visit(expr: &Expression<'_>, reports: &mut Vec<Report>) {
+    match expr {
+        Expression::ComptimeBlock { body, .. } => {
+            if let Err(error) = ConstExprEvaluator::eval(body) {
+                reports.push(Report::new(error));
+            }
+        }
+        expr => for_each_child(expr, |child| visit(child, reports)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::Parser;
+
+    fn check(source: &str) -> Vec<&str> {
+        let statements = match Parser::new(source, Lexer::new(source)).parse() {
+            Ok(statements) => statements,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        check_comptime(&statements)
+            .iter()
+            .map(|report| {
+                let label = report.labels().and_then(|mut labels| labels.next()).unwrap();
+                &source[label.offset()..label.offset() + label.len()]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn constant_blocks_are_accepted() {
+        let source = "fun main() { const a = comptime { 1 + 2 * 3 }; const b = comptime { len!([1, 2]) + 1 }; }";
+        assert!(check(source).is_empty());
+    }
+
+    #[test]
+    fn anything_unknown_is_an_error() {
+        let source = "fun main() {\n\
+                      const a = comptime { count + 1 };\n\
+                      const b = comptime { 1 / 0 };\n\
+                      const c = comptime {};\n\
+                      if ok { f(comptime { comptime { g() } }); }\n\
+                      }";
+        assert_eq!(check(source), ["count", "1 / 0", "{}", "g()"]);
+    }
+
+    #[test]
+    fn globals_are_checked_too() {
+        let source = "const X = comptime { f() };\n\
+                      var Y = comptime { 2 * 8 };\n\
+                      fun main() { X }";
+        assert_eq!(check(source), ["f()"]);
+    }
+}
@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use miette::{Diagnostic, Report, SourceSpan};
+use thiserror::Error;
+
+use crate::resolve::SymbolTable;
+use crate::{Attribute, Expression, Statement};
+
+/// name of the attribute marking a function as deprecated, like
+/// `#[deprecated(since = "0.2", note = "use `bar` instead")] fun foo() {}`
+pub const DEPRECATED_ATTRIBUTE: &str = "deprecated";
+
+/// the only keys `#[deprecated]` accepts, both optional
+pub const DEPRECATED_KEYS: [&str; 2] = ["since", "note"];
+
+#[derive(Debug, Error, Diagnostic)]
+#[error(
+    "`{name}` is deprecated{}",
+    .since.as_ref().map(|since| format!(" since {since}")).unwrap_or_default()
+)]
+#[diagnostic(code(kura::deprecated), severity(Warning))]
+pub struct Deprecated {
+    pub name: String,
+    pub since: Option<String>,
+    #[help]
+    pub note: Option<String>,
+    #[label("called here")]
+    pub call_site: SourceSpan,
+}
+
+/// reports every call to a function marked `#[deprecated]`. only calls
+/// through a bare name are checked, and a local binding with the same name
+/// as the function hides it, so calling that binding is not reported
+pub fn lint_deprecated(statements: &[Statement<'_>], symbols: &SymbolTable<'_>) -> Vec<Report> {
+    let deprecated = statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Fun { name, attributes, .. } => attributes
+                .iter()
+                .find(|attribute| attribute.name == DEPRECATED_ATTRIBUTE)
+                .map(|attribute| (name.as_ref(), attribute)),
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut reports = vec![];
+    if deprecated.is_empty() {
+        return reports;
+    }
+
+    let linter = Linter { deprecated, symbols };
+    for statement in statements.iter().flat_map(Statement::functions) {
+        if let Statement::Fun { body, .. } = statement {
+            body.iter().for_each(|expr| linter.visit(expr, &mut reports));
+        }
+    }
+
+    reports
+}
+
+struct Linter<'a, 'ast> {
+    deprecated: HashMap<&'a str, &'a Attribute<'ast>>,
+    symbols: &'a SymbolTable<'ast>,
+}
+
+impl Linter<'_, '_> {
+    fn visit(&self, expr: &Expression<'_>, reports: &mut Vec<Report>) {
+        match expr {
+            Expression::FunCall {
+                ident,
+                arguments,
+                location,
+                ..
+            } => {
+                if let Expression::Ident {
+                    name,
+                    location: name_location,
+                } = ident.as_ref()
+                {
+                    let attribute = self.deprecated.get(name.as_ref());
+                    if let Some(attribute) = attribute.filter(|_| self.symbols.resolution(*name_location).is_none()) {
+                        reports.push(Report::new(Deprecated {
+                            name: name.to_string(),
+                            since: attribute.value("since").map(str::to_string),
+                            note: attribute.value("note").map(str::to_string),
+                            call_site: (*location).into(),
+                        }));
+                    }
+                }
+                self.visit(ident, reports);
+                arguments.iter().for_each(|arg| self.visit(arg, reports));
+            }
+            Expression::Block { expressions, tail, .. } => expressions
+                .iter()
+                .chain(tail.as_deref())
+                .for_each(|expr| self.visit(expr, reports)),
+            Expression::InlineAsm { outputs, inputs, .. } => outputs
+                .iter()
+                .chain(inputs.iter())
+                .for_each(|operand| self.visit(&operand.expr, reports)),
+            Expression::Interpolated { expressions, .. }
+            | Expression::Array {
+                elements: expressions, ..
+            }
+            | Expression::Tuple {
+                elements: expressions, ..
+            } => expressions.iter().for_each(|expr| self.visit(expr, reports)),
+            Expression::Assert { condition, message, .. } => {
+                self.visit(condition, reports);
+                message.iter().for_each(|message| self.visit(message, reports));
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                self.visit(condition, reports);
+                self.visit(truthy, reports);
+                falsy.iter().for_each(|branch| self.visit(branch, reports));
+            }
+            Expression::Match { scrutinee, arms, .. } => {
+                self.visit(scrutinee, reports);
+                arms.iter().for_each(|arm| self.visit(&arm.body, reports));
+            }
+            Expression::EnumVariant { kind, .. } => kind.values().for_each(|value| self.visit(value, reports)),
+            Expression::DoBlock { steps, .. } => steps.iter().for_each(|step| self.visit(step.expr(), reports)),
+            Expression::Yield { value, .. } => value.iter().for_each(|value| self.visit(value, reports)),
+            Expression::Lambda { body: value, .. }
+            | Expression::Var { value, .. }
+            | Expression::Return { value, .. }
+            | Expression::Await { expr: value, .. }
+            | Expression::RuntimeTypeOf { expr: value, .. }
+            | Expression::SafeCast { expr: value, .. }
+            | Expression::ArrayLen { array: value, .. }
+            | Expression::Splat { expr: value, .. }
+            | Expression::UnsafeBlock { body: value, .. }
+            | Expression::ComptimeBlock { body: value, .. }
+            | Expression::Defer { body: value, .. }
+            | Expression::Ref { expr: value, .. }
+            | Expression::Deref { expr: value, .. }
+            | Expression::Not { expr: value, .. }
+            | Expression::FieldAccess { target: value, .. }
+            | Expression::TupleAccess { tuple: value, .. }
+            | Expression::Try { expr: value, .. } => self.visit(value, reports),
+            Expression::Assign {
+                ident: lhs, value: rhs, ..
+            }
+            | Expression::CompoundAssign {
+                ident: lhs, value: rhs, ..
+            }
+            | Expression::BinaryOp { lhs, rhs, .. }
+            | Expression::Index {
+                target: lhs,
+                index: rhs,
+                ..
+            }
+            | Expression::Elvis {
+                condition: lhs,
+                default: rhs,
+                ..
+            }
+            | Expression::Let {
+                value: lhs, body: rhs, ..
+            } => {
+                self.visit(lhs, reports);
+                self.visit(rhs, reports);
+            }
+            Expression::Bool { .. }
+            | Expression::Sizeof { .. }
+            | Expression::Macro { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::Ident { .. }
+            | Expression::Path { .. }
+            | Expression::UintLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::IntLiteral { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::Parser;
+
+    fn lint(source: &str) -> Vec<String> {
+        let statements = match Parser::new(source, Lexer::new(source)).parse() {
+            Ok(statements) => statements,
+            Err(e) => panic!("{e:?}"),
+        };
+        let symbols = SymbolTable::resolve_statements(&statements);
+
+        lint_deprecated(&statements, &symbols)
+            .iter()
+            .map(|report| {
+                let deprecated = report.downcast_ref::<Deprecated>().unwrap();
+                let span = deprecated.call_site;
+                format!(
+                    "{report}: {} ({:?})",
+                    &source[span.offset()..span.offset() + span.len()],
+                    deprecated.note
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn calls_to_deprecated_functions() {
+        let source = "#[deprecated(since = \"0.2\", note = \"use `bar` instead\")]\nfun foo() {}\n\
+                      #[deprecated]\nfun old() {}\n\
+                      fun bar() {}\n\
+                      fun main() { foo(); bar(); const f = fun() { old(); }; }";
+        assert_eq!(
+            lint(source),
+            [
+                "`foo` is deprecated since 0.2: foo() (Some(\"use `bar` instead\"))",
+                "`old` is deprecated: old() (None)",
+            ]
+        );
+    }
+
+    #[test]
+    fn local_bindings_hide_deprecated_functions() {
+        let source = "#[deprecated]\nfun foo() {}\nfun main() { const foo = bar; foo(); }";
+        assert!(lint(source).is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+use std::borrow::Cow;
+
+use kura_lexer::token::Location;
+
+use crate::{Attribute, Expression, GenericParam, Statement, TypeExpr};
+
+/// traits `#[derive(..)]` knows how to implement, in the order their impls
+/// are emitted
+pub const DERIVABLE_TRAITS: &[&str] = &["Debug", "Display"];
+
+/// expands the `#[derive(..)]` attributes of a struct, returning it followed
+/// by an impl block for every trait it derives. any other statement is
+/// returned as is, and so are derives of traits that are not in
+/// `DERIVABLE_TRAITS`.
+///
+/// `Debug` writes the name of the struct and every field with its value,
+/// like `Point { x: 1, y: 2 }`, and `Display` calls `Debug::fmt`. every
+/// generated node is located at the derive attribute asking for it
+pub fn derive_expand(statement: Statement<'_>) -> Vec<Statement<'_>> {
+    let Statement::Struct {
+        name,
+        generics,
+        fields,
+        attributes,
+        ..
+    } = &statement
+    else {
+        return vec![statement];
+    };
+
+    let mut impls = vec![];
+    for trait_name in DERIVABLE_TRAITS {
+        let Some(derive) = attributes.iter().find(|attribute| derives(attribute, trait_name)) else {
+            continue;
+        };
+
+        let body = match *trait_name {
+            "Debug" => debug_body(name, fields, derive.location),
+            _ => display_body(derive.location),
+        };
+        impls.push(fmt_impl(trait_name, name, generics, body, derive.location));
+    }
+
+    let mut statements = vec![statement];
+    statements.append(&mut impls);
+    statements
+}
+
+fn derives(attribute: &Attribute<'_>, trait_name: &str) -> bool {
+    attribute.name == "derive" && attribute.arguments.iter().any(|argument| argument == trait_name)
+}
+
+/// `impl Trait for Name { fun fmt(self: Name) => String { body } }`. a
+/// generic struct gets a generic impl, like `impl<T> Trait for Name<T>`
+fn fmt_impl<'ast>(
+    trait_name: &'static str,
+    target: &Cow<'ast, str>,
+    generics: &[GenericParam<'ast>],
+    body: Expression<'ast>,
+    location: Location,
+) -> Statement<'ast> {
+    let arguments = generics
+        .iter()
+        .map(|param| TypeExpr::Named {
+            name: param.name.clone(),
+            location,
+        })
+        .collect::<Vec<_>>();
+    let receiver_type = match arguments.is_empty() {
+        true => TypeExpr::Named {
+            name: target.clone(),
+            location,
+        },
+        false => TypeExpr::Generic {
+            name: target.clone(),
+            arguments: arguments.clone(),
+            location,
+        },
+    };
+    let receiver = Statement::FunArgument {
+        name: Cow::Borrowed("self"),
+        arg_type: Box::new(receiver_type),
+        location,
+    };
+
+    let function = Statement::Fun {
+        name: Cow::Borrowed("fmt"),
+        generics: vec![],
+        arguments: vec![receiver],
+        body: vec![body],
+        return_type: Some(Box::new(TypeExpr::Named {
+            name: Cow::Borrowed("String"),
+            location,
+        })),
+        where_clause: vec![],
+        attributes: vec![],
+        inline: None,
+        is_unsafe: false,
+        doc: vec![],
+        location,
+    };
+
+    Statement::Impl {
+        generics: generics.to_vec(),
+        trait_name: Some(Cow::Borrowed(trait_name)),
+        target: target.clone(),
+        arguments,
+        functions: vec![function],
+        location,
+    }
+}
+
+/// `"Name { a: {self.a}, b: {self.b} }"`, or just `"Name"` without fields
+fn debug_body<'ast>(name: &str, fields: &[Statement<'ast>], location: Location) -> Expression<'ast> {
+    let names = fields
+        .iter()
+        .filter_map(|field| match field {
+            Statement::FunArgument { name, .. } => Some(name.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    if names.is_empty() {
+        return string(name.to_string(), location);
+    }
+
+    let mut literals = vec![];
+    let mut expressions = vec![];
+    for (idx, field) in names.into_iter().enumerate() {
+        let separator = if idx == 0 { format!("{name} {{ ") } else { String::from(", ") };
+        literals.push(string(format!("{separator}{field}: "), location));
+        expressions.push(Expression::FieldAccess {
+            target: Box::new(Expression::Ident {
+                name: Cow::Borrowed("self"),
+                location,
+            }),
+            field,
+            location,
+        });
+    }
+    literals.push(string(String::from(" }"), location));
+
+    Expression::Interpolated {
+        literals,
+        expressions,
+        location,
+    }
+}
+
+/// `Debug::fmt(self)`
+fn display_body<'ast>(location: Location) -> Expression<'ast> {
+    Expression::FunCall {
+        ident: Box::new(Expression::Path {
+            segments: vec![Cow::Borrowed("Debug"), Cow::Borrowed("fmt")],
+            location,
+        }),
+        location,
+        arguments: vec![Expression::Ident {
+            name: Cow::Borrowed("self"),
+            location,
+        }],
+        tail_call: false,
+    }
+}
+
+fn string<'ast>(value: String, location: Location) -> Expression<'ast> {
+    Expression::StringLiteral {
+        value: Cow::Owned(value),
+        location,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::sexpr::statements_to_sexpr;
+    use crate::Parser;
+
+    fn expand(source: &str) -> String {
+        let statements = match Parser::new(source, Lexer::new(source)).parse() {
+            Ok(statements) => statements,
+            Err(e) => panic!("{e:?}"),
+        };
+        let expanded = statements.into_iter().flat_map(derive_expand).collect::<Vec<_>>();
+        statements_to_sexpr(&expanded)
+    }
+
+    #[test]
+    fn debug_and_display_are_derived() {
+        let source = "#[derive(Display, Debug)]\nstruct Point { x: i32, y: i32 }";
+
+        insta::assert_snapshot!(expand(source));
+    }
+
+    #[test]
+    fn structs_without_fields_print_their_name() {
+        let output = expand("#[derive(Debug)] struct Unit {}");
+        assert!(output.contains(r#"(string-literal "Unit")"#), "{output}");
+    }
+
+    #[test]
+    fn anything_else_is_left_alone() {
+        let source = "#[derive(Clone)] #[allow(shadow)] struct A { a: i32 } fun main() {} impl A {}";
+
+        let statements = Parser::new(source, Lexer::new(source)).parse().unwrap();
+        let expanded = statements
+            .clone()
+            .into_iter()
+            .flat_map(derive_expand)
+            .collect::<Vec<_>>();
+        assert_eq!(expanded, statements);
+    }
+
+    #[test]
+    fn generated_impls_point_at_the_derive() {
+        let source = "struct A { a: i32 }\n#[derive(Debug)]\nstruct B { b: i32 }";
+
+        let statements = Parser::new(source, Lexer::new(source)).parse().unwrap();
+        let expanded = statements.into_iter().flat_map(derive_expand).collect::<Vec<_>>();
+        assert_eq!(expanded.len(), 3);
+
+        let location = expanded[2].location();
+        assert_eq!(&source[location.start_byte..location.end_byte], "#[derive(Debug)]");
+    }
+}
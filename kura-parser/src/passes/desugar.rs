@@ -0,0 +1,596 @@
+use std::borrow::Cow;
+
+use kura_lexer::token::Location;
+
+use crate::{AsmOperand, Expression, MatchArm};
+
+/// name of the built-in function interpolated strings are turned into a
+/// call to
+pub const FORMAT_FUNCTION: &str = "__format__";
+
+/// name of the built-in function awaited expressions are turned into a call
+/// to
+pub const AWAIT_FUNCTION: &str = "__await__";
+
+/// replaces every `Interpolated` string with a call to `__format__`, taking
+/// the literal parts of the string first, followed by the embedded
+/// expressions. `"a {b} c"` becomes `__format__("a ", " c", b)`
+pub fn desugar_interpolation(expr: Expression<'_>) -> Expression<'_> {
+    match expr {
+        Expression::Interpolated {
+            literals,
+            expressions,
+            location,
+        } => Expression::FunCall {
+            ident: Box::new(Expression::Ident {
+                name: Cow::Borrowed(FORMAT_FUNCTION),
+                location,
+            }),
+            location,
+            arguments: literals
+                .into_iter()
+                .chain(expressions.into_iter().map(desugar_interpolation))
+                .collect(),
+            tail_call: false,
+        },
+        expr => map_children(expr, desugar_interpolation),
+    }
+}
+
+/// replaces every `expr.await` with a call to `__await__`, taking the
+/// awaited expression as its only argument
+pub fn desugar_await(expr: Expression<'_>) -> Expression<'_> {
+    match expr {
+        Expression::Await { expr, location } => Expression::FunCall {
+            ident: Box::new(Expression::Ident {
+                name: Cow::Borrowed(AWAIT_FUNCTION),
+                location,
+            }),
+            location,
+            arguments: vec![desugar_await(*expr)],
+            tail_call: false,
+        },
+        expr => map_children(expr, desugar_await),
+    }
+}
+
+/// replaces every compound assignment with the assignment it is short for,
+/// `a += b` becomes `a = a + b`
+pub fn desugar_compound_assign(expr: Expression<'_>) -> Expression<'_> {
+    match expr {
+        Expression::CompoundAssign {
+            operator,
+            ident,
+            value,
+            location,
+        } => {
+            let value = desugar_compound_assign(*value);
+            let operation = Location::new(ident.location().start_byte, value.location().end_byte);
+            Expression::Assign {
+                ident: ident.clone(),
+                location,
+                value: Box::new(Expression::BinaryOp {
+                    operator,
+                    lhs: ident,
+                    rhs: Box::new(value),
+                    typ: None,
+                    location: operation,
+                }),
+            }
+        }
+        expr => map_children(expr, desugar_compound_assign),
+    }
+}
+
+/// rewrites every piece of syntactic sugar in `expr` into the core forms it
+/// stands for, so later stages never see an `Interpolated`, an `Await` or a
+/// `CompoundAssign`
+pub fn normalize(expr: Expression<'_>) -> Expression<'_> {
+    desugar_await(desugar_interpolation(desugar_compound_assign(expr)))
+}
+
+/// rebuilds `expr` with `pass` applied to each of its direct children, so a
+/// pass only has to handle the nodes it rewrites
+pub(crate) fn map_children<'ast>(
+    expr: Expression<'ast>,
+    pass: impl Fn(Expression<'ast>) -> Expression<'ast> + Copy,
+) -> Expression<'ast> {
+    let boxed = |expr: Box<Expression<'ast>>| Box::new(pass(*expr));
+
+    match expr {
+        Expression::Var {
+            mutable,
+            name,
+            typ,
+            value,
+            attributes,
+            location,
+        } => Expression::Var {
+            mutable,
+            name,
+            typ,
+            value: boxed(value),
+            attributes,
+            location,
+        },
+        Expression::Assert {
+            condition,
+            message,
+            location,
+        } => Expression::Assert {
+            condition: boxed(condition),
+            message: message.map(boxed),
+            location,
+        },
+        Expression::If {
+            condition,
+            location,
+            truthy,
+            falsy,
+        } => Expression::If {
+            condition: boxed(condition),
+            location,
+            truthy: boxed(truthy),
+            falsy: falsy.into_iter().map(pass).collect(),
+        },
+        Expression::Match {
+            scrutinee,
+            arms,
+            location,
+        } => Expression::Match {
+            scrutinee: boxed(scrutinee),
+            arms: arms
+                .into_iter()
+                .map(|arm| MatchArm {
+                    body: pass(arm.body),
+                    ..arm
+                })
+                .collect(),
+            location,
+        },
+        Expression::FunCall {
+            ident,
+            location,
+            arguments,
+            tail_call,
+        } => Expression::FunCall {
+            ident: boxed(ident),
+            location,
+            arguments: arguments.into_iter().map(pass).collect(),
+            tail_call,
+        },
+        Expression::Assign { ident, location, value } => Expression::Assign {
+            ident: boxed(ident),
+            location,
+            value: boxed(value),
+        },
+        Expression::CompoundAssign {
+            operator,
+            ident,
+            value,
+            location,
+        } => Expression::CompoundAssign {
+            operator,
+            ident: boxed(ident),
+            value: boxed(value),
+            location,
+        },
+        Expression::Block {
+            expressions,
+            tail,
+            reachable,
+            location,
+        } => Expression::Block {
+            expressions: expressions.into_iter().map(pass).collect(),
+            tail: tail.map(boxed),
+            reachable,
+            location,
+        },
+        Expression::BinaryOp {
+            operator,
+            lhs,
+            rhs,
+            typ,
+            location,
+        } => Expression::BinaryOp {
+            operator,
+            lhs: boxed(lhs),
+            rhs: boxed(rhs),
+            typ,
+            location,
+        },
+        Expression::Elvis {
+            condition,
+            default,
+            location,
+        } => Expression::Elvis {
+            condition: boxed(condition),
+            default: boxed(default),
+            location,
+        },
+        Expression::Return { value, location } => Expression::Return {
+            value: boxed(value),
+            location,
+        },
+        Expression::Yield { value, location } => Expression::Yield {
+            value: value.map(boxed),
+            location,
+        },
+        Expression::EnumVariant { path, kind, location } => Expression::EnumVariant {
+            path,
+            kind: kind.map(pass),
+            location,
+        },
+        Expression::RuntimeTypeOf { expr, location } => Expression::RuntimeTypeOf {
+            expr: boxed(expr),
+            location,
+        },
+        Expression::SafeCast { expr, typ, location } => Expression::SafeCast {
+            expr: boxed(expr),
+            typ,
+            location,
+        },
+        Expression::Array { elements, location } => Expression::Array {
+            elements: elements.into_iter().map(pass).collect(),
+            location,
+        },
+        Expression::Tuple { elements, location } => Expression::Tuple {
+            elements: elements.into_iter().map(pass).collect(),
+            location,
+        },
+        Expression::ArrayLen { array, location } => Expression::ArrayLen {
+            array: boxed(array),
+            location,
+        },
+        Expression::Splat { expr, location } => Expression::Splat {
+            expr: boxed(expr),
+            location,
+        },
+        Expression::Await { expr, location } => Expression::Await {
+            expr: boxed(expr),
+            location,
+        },
+        Expression::UnsafeBlock { body, location } => Expression::UnsafeBlock {
+            body: boxed(body),
+            location,
+        },
+        Expression::ComptimeBlock { body, location } => Expression::ComptimeBlock {
+            body: boxed(body),
+            location,
+        },
+        Expression::Defer { body, location } => Expression::Defer {
+            body: boxed(body),
+            location,
+        },
+        Expression::DoBlock { steps, location } => Expression::DoBlock {
+            steps: steps.into_iter().map(|step| step.map(pass)).collect(),
+            location,
+        },
+        Expression::Ref {
+            mutable,
+            expr,
+            location,
+        } => Expression::Ref {
+            mutable,
+            expr: boxed(expr),
+            location,
+        },
+        Expression::Deref { expr, location } => Expression::Deref {
+            expr: boxed(expr),
+            location,
+        },
+        Expression::Not { expr, location } => Expression::Not {
+            expr: boxed(expr),
+            location,
+        },
+        Expression::FieldAccess {
+            target,
+            field,
+            location,
+        } => Expression::FieldAccess {
+            target: boxed(target),
+            field,
+            location,
+        },
+        Expression::TupleAccess { tuple, index, location } => Expression::TupleAccess {
+            tuple: boxed(tuple),
+            index,
+            location,
+        },
+        Expression::Index {
+            target,
+            index,
+            location,
+        } => Expression::Index {
+            target: boxed(target),
+            index: boxed(index),
+            location,
+        },
+        Expression::Try { expr, location } => Expression::Try {
+            expr: boxed(expr),
+            location,
+        },
+        Expression::Lambda {
+            parameters,
+            parameter_types,
+            return_type,
+            body,
+            location,
+        } => Expression::Lambda {
+            parameters,
+            parameter_types,
+            return_type,
+            body: boxed(body),
+            location,
+        },
+        Expression::Let {
+            name,
+            value,
+            body,
+            location,
+        } => Expression::Let {
+            name,
+            value: boxed(value),
+            body: boxed(body),
+            location,
+        },
+        Expression::Interpolated {
+            literals,
+            expressions,
+            location,
+        } => Expression::Interpolated {
+            literals,
+            expressions: expressions.into_iter().map(pass).collect(),
+            location,
+        },
+        Expression::InlineAsm {
+            template,
+            outputs,
+            inputs,
+            clobbers,
+            location,
+        } => {
+            let operand = |operand: AsmOperand<'ast>| AsmOperand {
+                expr: pass(operand.expr),
+                ..operand
+            };
+            Expression::InlineAsm {
+                template,
+                outputs: outputs.into_iter().map(operand).collect(),
+                inputs: inputs.into_iter().map(operand).collect(),
+                clobbers,
+                location,
+            }
+        }
+        Expression::Bool { .. }
+        | Expression::Sizeof { .. }
+        | Expression::Ident { .. }
+        | Expression::Path { .. }
+        | Expression::UintLiteral { .. }
+        | Expression::FloatLiteral { .. }
+        | Expression::IntLiteral { .. }
+        | Expression::Macro { .. }
+        | Expression::StringLiteral { .. } => expr,
+    }
+}
+
+/// calls `f` with each of the direct children of `expr`, so a pass that only
+/// reads the tree only has to handle the nodes it looks at
+pub(crate) fn for_each_child<'a, 'ast>(expr: &'a Expression<'ast>, mut f: impl FnMut(&'a Expression<'ast>)) {
+    match expr {
+        Expression::Block { expressions, tail, .. } => expressions.iter().chain(tail.as_deref()).for_each(f),
+        Expression::InlineAsm { outputs, inputs, .. } => outputs
+            .iter()
+            .chain(inputs.iter())
+            .for_each(|operand| f(&operand.expr)),
+        Expression::Interpolated { expressions, .. }
+        | Expression::Array {
+            elements: expressions, ..
+        }
+        | Expression::Tuple {
+            elements: expressions, ..
+        } => expressions.iter().for_each(f),
+        Expression::Assert { condition, message, .. } => {
+            f(condition);
+            message.iter().for_each(|message| f(message));
+        }
+        Expression::If {
+            condition,
+            truthy,
+            falsy,
+            ..
+        } => {
+            f(condition);
+            f(truthy);
+            falsy.iter().for_each(f);
+        }
+        Expression::Match { scrutinee, arms, .. } => {
+            f(scrutinee);
+            arms.iter().for_each(|arm| f(&arm.body));
+        }
+        Expression::EnumVariant { kind, .. } => kind.values().for_each(f),
+        Expression::DoBlock { steps, .. } => steps.iter().for_each(|step| f(step.expr())),
+        Expression::FunCall { ident, arguments, .. } => {
+            f(ident);
+            arguments.iter().for_each(f);
+        }
+        Expression::Yield { value, .. } => value.iter().for_each(|value| f(value)),
+        Expression::Var { value, .. }
+        | Expression::Return { value, .. }
+        | Expression::Await { expr: value, .. }
+        | Expression::RuntimeTypeOf { expr: value, .. }
+        | Expression::SafeCast { expr: value, .. }
+        | Expression::ArrayLen { array: value, .. }
+        | Expression::Splat { expr: value, .. }
+        | Expression::UnsafeBlock { body: value, .. }
+        | Expression::ComptimeBlock { body: value, .. }
+        | Expression::Defer { body: value, .. }
+        | Expression::Ref { expr: value, .. }
+        | Expression::Deref { expr: value, .. }
+        | Expression::Not { expr: value, .. }
+        | Expression::FieldAccess { target: value, .. }
+        | Expression::TupleAccess { tuple: value, .. }
+        | Expression::Try { expr: value, .. }
+        | Expression::Lambda { body: value, .. } => f(value),
+        Expression::Assign {
+            ident: lhs, value: rhs, ..
+        }
+        | Expression::CompoundAssign {
+            ident: lhs, value: rhs, ..
+        }
+        | Expression::BinaryOp { lhs, rhs, .. }
+        | Expression::Index {
+            target: lhs,
+            index: rhs,
+            ..
+        }
+        | Expression::Elvis {
+            condition: lhs,
+            default: rhs,
+            ..
+        }
+        | Expression::Let {
+            value: lhs, body: rhs, ..
+        } => {
+            f(lhs);
+            f(rhs);
+        }
+        Expression::Bool { .. }
+        | Expression::Sizeof { .. }
+        | Expression::Macro { .. }
+        | Expression::StringLiteral { .. }
+        | Expression::Ident { .. }
+        | Expression::Path { .. }
+        | Expression::UintLiteral { .. }
+        | Expression::FloatLiteral { .. }
+        | Expression::IntLiteral { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::expression::parse_expression;
+    use crate::sexpr::to_sexpr;
+
+    fn desugar(source: &str) -> Expression<'_> {
+        let mut lexer = Lexer::new(source);
+        let expr = match parse_expression(&mut lexer, true) {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+        desugar_interpolation(expr)
+    }
+
+    /// renders calls as `name(args)`, strings quoted and anything else by
+    /// its name, which is enough to compare argument lists
+    fn shape(expr: &Expression<'_>) -> String {
+        match expr {
+            Expression::FunCall { ident, arguments, .. } => {
+                let arguments = arguments.iter().map(shape).collect::<Vec<_>>();
+                format!("{}({})", shape(ident), arguments.join(", "))
+            }
+            Expression::StringLiteral { value, .. } => format!("{value:?}"),
+            Expression::Ident { name, .. } => name.to_string(),
+            Expression::Var { value, .. } => shape(value),
+            Expression::BinaryOp { lhs, rhs, .. } => format!("{} op {}", shape(lhs), shape(rhs)),
+            Expression::Block { expressions, tail, .. } => {
+                let expressions = expressions.iter().chain(tail.as_deref()).map(shape).collect::<Vec<_>>();
+                format!("{{ {} }}", expressions.join("; "))
+            }
+            Expression::Interpolated { .. } => panic!("interpolation was not desugared"),
+            Expression::Await { .. } => panic!("await was not desugared"),
+            other => format!("{other:?}"),
+        }
+    }
+
+    #[test]
+    fn interpolation_becomes_a_format_call() {
+        let expr = desugar(r#"const a = "hello {name}, {greeting}!";"#);
+
+        assert_eq!(shape(&expr), r#"__format__("hello ", ", ", "!", name, greeting)"#);
+    }
+
+    #[test]
+    fn nested_interpolations() {
+        let expr = desugar(r#"const a = "outer {wrap("inner {value}")} and {"{deep}" == other}";"#);
+
+        assert_eq!(
+            shape(&expr),
+            concat!(
+                r#"__format__("outer ", " and ", "", "#,
+                r#"wrap(__format__("inner ", "", value)), "#,
+                r#"__format__("", "", deep) op other)"#,
+            )
+        );
+    }
+
+    #[test]
+    fn plain_strings_are_untouched() {
+        let expr = desugar(r#"const a = "no {{interpolation}} here";"#);
+        assert_eq!(shape(&expr), r#""no {interpolation} here""#);
+    }
+
+    fn desugar_awaits(source: &str) -> Expression<'_> {
+        let mut lexer = Lexer::new(source);
+        let expr = match parse_expression(&mut lexer, true) {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+        desugar_await(expr)
+    }
+
+    #[test]
+    fn await_becomes_an_await_call() {
+        let expr = desugar_awaits("const a = foo().await;");
+        assert_eq!(shape(&expr), "__await__(foo())");
+
+        let expr = desugar_awaits("const a = (bar() + baz()).await;");
+        assert_eq!(shape(&expr), "__await__(bar() op baz())");
+    }
+
+    #[test]
+    fn nested_awaits() {
+        let expr = desugar_awaits("const a = outer(inner().await, x.await).await.await;");
+        assert_eq!(
+            shape(&expr),
+            "__await__(__await__(outer(__await__(inner()), __await__(x))))"
+        );
+
+        let expr = desugar_awaits("{ fetch().await; const b = { wait(c.await) }; }");
+        assert_eq!(shape(&expr), "{ __await__(fetch()); { wait(__await__(c)) } }");
+    }
+
+    #[test]
+    fn compound_assignments_become_assignments() {
+        let source = "{ a += 1; b -= c * 2; d /= { e *= 2; e }; }";
+        let mut lexer = Lexer::new(source);
+        let expr = parse_expression(&mut lexer, true).unwrap();
+
+        assert_eq!(
+            to_sexpr(&desugar_compound_assign(expr)),
+            concat!(
+                "(block (assign (ident a) (binary-op + (ident a) (uint-literal 1))) ",
+                "(assign (ident b) (binary-op - (ident b) (binary-op * (ident c) (uint-literal 2)))) ",
+                "(assign (ident d) (binary-op / (ident d) ",
+                "(block (assign (ident e) (binary-op * (ident e) (uint-literal 2))) (tail (ident e))))))",
+            )
+        );
+    }
+
+    #[test]
+    fn normalized_trees_only_have_core_forms() {
+        let source = r#"{
+            var total = 0;
+            total += fetch("{total} so far").await;
+            total *= { var step = 2; step -= 1; step };
+        }"#;
+        let mut lexer = Lexer::new(source);
+        let expr = parse_expression(&mut lexer, true).unwrap();
+
+        insta::assert_snapshot!("before_normalizing", to_sexpr(&expr));
+        insta::assert_snapshot!("after_normalizing", to_sexpr(&normalize(expr)));
+    }
+}
@@ -0,0 +1,206 @@
+use kura_lexer::token::{FloatSizes, IntSizes, UIntSizes};
+use miette::{Diagnostic, Report, SourceSpan};
+use thiserror::Error;
+
+use crate::inference::{Type, TypeEnv};
+use crate::{Expression, MatchArm, Pattern};
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("`match` doesn't cover every value, missing {}", .missing.join(", "))]
+#[diagnostic(
+    code(kura::non_exhaustive_match),
+    severity(Warning),
+    help("add an arm for each missing pattern, or a `_` arm matching everything else")
+)]
+pub struct NonExhaustiveMatch {
+    /// every pattern that isn't covered, as it would be written
+    pub missing: Vec<String>,
+    #[label("some values are not matched")]
+    pub span: SourceSpan,
+}
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("can't prove that every value of `{typ}` is matched")]
+#[diagnostic(
+    code(kura::unprovable_match),
+    severity(Warning),
+    help("add a `_` arm to match every value the other arms don't")
+)]
+pub struct UnprovableMatch {
+    pub typ: String,
+    #[label("this `match` might not match its value")]
+    pub span: SourceSpan,
+}
+
+/// what a `match` is known to be matching on
+enum Scrutinee {
+    Type(Type),
+    /// a type whose values are named by paths, like an enum
+    Named(String),
+    Unknown,
+}
+
+/// checks that some arm of `match_expr` matches every value of its
+/// scrutinee, taking the type of names from `type_info`. only booleans can
+/// be proven exhaustive without a `_` or a binding arm, so any other type
+/// is reported as unprovable instead. anything other than a `match` is
+/// always exhaustive
+pub fn check_exhaustiveness(match_expr: &Expression<'_>, type_info: &TypeEnv) -> Result<(), Vec<Report>> {
+    let Expression::Match {
+        scrutinee,
+        arms,
+        location,
+    } = match_expr
+    else {
+        return Ok(());
+    };
+
+    if arms.iter().any(|arm| arm.pattern.is_irrefutable()) {
+        return Ok(());
+    }
+
+    let span = SourceSpan::from(*location);
+    let unprovable = |typ: String| Err(vec![Report::new(UnprovableMatch { typ, span })]);
+
+    match scrutinee_type(scrutinee, arms, type_info) {
+        Scrutinee::Type(Type::Bool) => {
+            let missing = [true, false]
+                .into_iter()
+                .filter(|value| {
+                    !arms.iter().any(|arm| match &arm.pattern {
+                        Pattern::Literal(literal) => {
+                            matches!(literal.as_ref(), Expression::Bool { value: b, .. } if b == value)
+                        }
+                        _ => false,
+                    })
+                })
+                .map(|value| format!("`{value}`"))
+                .collect::<Vec<_>>();
+
+            match missing.is_empty() {
+                true => Ok(()),
+                false => Err(vec![Report::new(NonExhaustiveMatch { missing, span })]),
+            }
+        }
+        Scrutinee::Type(typ) => unprovable(typ.to_string()),
+        // the variants of an enum are never known, as there is nowhere to
+        // declare them yet
+        Scrutinee::Named(name) => unprovable(name),
+        // every arm has a pattern telling the type, so there are none
+        Scrutinee::Unknown => Err(vec![Report::new(NonExhaustiveMatch {
+            missing: vec![String::from("`_`")],
+            span,
+        })]),
+    }
+}
+
+/// the type of the scrutinee, as told by `type_info` when it is a name, or
+/// by the patterns matched against it otherwise
+fn scrutinee_type(scrutinee: &Expression<'_>, arms: &[MatchArm<'_>], type_info: &TypeEnv) -> Scrutinee {
+    let declared = match scrutinee {
+        Expression::Ident { name, .. } => type_info.lookup(name).cloned(),
+        literal => literal_type(literal),
+    };
+    if let Some(typ) = declared {
+        return Scrutinee::Type(typ);
+    }
+
+    arms.iter()
+        .find_map(|arm| match &arm.pattern {
+            Pattern::Literal(literal) => literal_type(literal).map(Scrutinee::Type),
+            Pattern::Variant { path, .. } => {
+                let (_, enumeration) = path.split_last()?;
+                Some(Scrutinee::Named(enumeration.join("::")))
+            }
+            Pattern::Path { .. } => None,
+            Pattern::Wildcard { .. } | Pattern::Binding { .. } => None,
+        })
+        .unwrap_or(Scrutinee::Unknown)
+}
+
+fn literal_type(literal: &Expression<'_>) -> Option<Type> {
+    match literal {
+        Expression::Bool { .. } => Some(Type::Bool),
+        Expression::StringLiteral { .. } | Expression::Interpolated { .. } => Some(Type::Str),
+        Expression::IntLiteral { size, .. } => Some(Type::Int(size.clone().unwrap_or(IntSizes::I64))),
+        Expression::UintLiteral { size, .. } => Some(Type::UInt(size.clone().unwrap_or(UIntSizes::U64))),
+        Expression::FloatLiteral { size, .. } => Some(Type::Float(size.clone().unwrap_or(FloatSizes::F64))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::expression::parse_expression;
+
+    fn check(source: &str, type_info: &TypeEnv) -> Result<(), Vec<String>> {
+        let mut lexer = Lexer::new(source);
+        let expr = match parse_expression(&mut lexer, false) {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+        check_exhaustiveness(&expr, type_info).map_err(|reports| reports.iter().map(ToString::to_string).collect())
+    }
+
+    #[test]
+    fn booleans_need_both_values() {
+        let mut type_info = TypeEnv::new();
+        type_info.insert("flag", Type::Bool);
+
+        assert_eq!(check("match flag { true => 1, false => 0 }", &type_info), Ok(()));
+        assert_eq!(
+            check("match flag { true => 1 }", &type_info),
+            Err(vec![String::from("`match` doesn't cover every value, missing `false`")])
+        );
+        assert_eq!(
+            check("match flag {}", &type_info),
+            Err(vec![String::from(
+                "`match` doesn't cover every value, missing `true`, `false`"
+            )])
+        );
+    }
+
+    #[test]
+    fn wildcards_and_bindings_match_everything() {
+        let type_info = TypeEnv::new();
+        assert_eq!(
+            check("match count { 0 => \"none\", _ => \"some\" }", &type_info),
+            Ok(())
+        );
+        assert_eq!(check("match count { 0 => 0, n => n - 1 }", &type_info), Ok(()));
+    }
+
+    #[test]
+    fn numbers_and_paths_are_never_proven() {
+        let mut type_info = TypeEnv::new();
+        type_info.insert("count", Type::UInt(UIntSizes::U8));
+
+        assert_eq!(
+            check("match count { 0 => \"none\", 1 => \"one\" }", &type_info),
+            Err(vec![String::from("can't prove that every value of `u8` is matched")])
+        );
+        // without knowing the name, the type comes from the patterns
+        assert_eq!(
+            check("match name { \"ada\" => 1 }", &type_info),
+            Err(vec![String::from(
+                "can't prove that every value of `String` is matched"
+            )])
+        );
+        assert_eq!(
+            check("match color { Color::Red => 1, Color::Blue => 2 }", &type_info),
+            Err(vec![String::from("can't prove that every value of `Color` is matched")])
+        );
+    }
+
+    #[test]
+    fn only_matches_are_checked() {
+        assert_eq!(check("if flag { 1 }", &TypeEnv::new()), Ok(()));
+        assert_eq!(
+            check("match value {}", &TypeEnv::new()),
+            Err(vec![String::from("`match` doesn't cover every value, missing `_`")])
+        );
+    }
+}
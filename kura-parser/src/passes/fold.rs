@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+
+use std::borrow::Cow;
+
+use kura_lexer::token::UIntSizes;
+
+use crate::const_eval::{kind_of, KIND_OF_MACRO};
+use crate::inference::Type;
+use crate::resolve::{BindingId, SymbolTable};
+use crate::{Expression, Statement, TypeExpr};
+
+/// replaces every `len!` whose length is known at compile time with the
+/// `usize` literal it evaluates to. that is the case for array literals and
+/// for `const` bindings holding one, while anything else is left to be
+/// computed at runtime. a `try_as` that can never fail, like converting an
+/// `i32` into an `i64`, is replaced with `Some` of its value, and every
+/// `kind_of!` with the name of the kind of its argument
+pub fn fold_constants(expr: &mut Expression<'_>) {
+    let symbols = SymbolTable::resolve(expr);
+    Folder::new(&symbols).visit(expr);
+}
+
+/// same as `fold_constants`, for the body of every function in a program
+pub fn fold_constants_statements(statements: &mut [Statement<'_>]) {
+    let symbols = SymbolTable::resolve_statements(statements);
+    let mut folder = Folder::new(&symbols);
+
+    for statement in statements {
+        match statement {
+            Statement::Fun { body, .. } => body.iter_mut().for_each(|expr| folder.visit(expr)),
+            Statement::Impl { functions, .. } => functions
+                .iter_mut()
+                .filter_map(|function| match function {
+                    Statement::Fun { body, .. } => Some(body),
+                    _ => None,
+                })
+                .for_each(|body| body.iter_mut().for_each(|expr| folder.visit(expr))),
+            Statement::Static { value, .. } => folder.visit(value),
+            Statement::FunArgument { .. }
+            | Statement::Struct { .. }
+            | Statement::MacroRules { .. }
+            | Statement::Mod { .. }
+            | Statement::ExternCrate { .. }
+            | Statement::Use { .. } => {}
+        }
+    }
+}
+
+struct Folder<'sym, 'ast> {
+    symbols: &'sym SymbolTable<'ast>,
+    /// length of the array every `const` binding holding one was declared
+    /// with. a binding is always declared before it is used, so it is known
+    /// by the time a `len!` refers to it
+    lengths: HashMap<BindingId, usize>,
+    /// type of every binding declared with one of the built in types
+    types: HashMap<BindingId, Type>,
+}
+
+impl<'sym, 'ast> Folder<'sym, 'ast> {
+    fn new(symbols: &'sym SymbolTable<'ast>) -> Self {
+        Self {
+            symbols,
+            lengths: HashMap::default(),
+            types: HashMap::default(),
+        }
+    }
+
+    /// the type of `expr` when it is a literal or a binding whose type was
+    /// written down
+    fn typ(&self, expr: &Expression<'_>) -> Option<Type> {
+        match expr {
+            Expression::IntLiteral { size, .. } => size.clone().map(Type::Int),
+            Expression::UintLiteral { size, .. } => size.clone().map(Type::UInt),
+            Expression::FloatLiteral { size, .. } => size.clone().map(Type::Float),
+            Expression::Bool { .. } => Some(Type::Bool),
+            Expression::Ident { location, .. } => self
+                .symbols
+                .resolution(*location)
+                .and_then(|id| self.types.get(&id))
+                .cloned(),
+            _ => None,
+        }
+    }
+
+    fn length(&self, array: &Expression<'_>) -> Option<usize> {
+        match array {
+            Expression::Array { elements, .. } => Some(elements.len()),
+            Expression::Ident { location, .. } => self
+                .symbols
+                .resolution(*location)
+                .and_then(|id| self.lengths.get(&id))
+                .copied(),
+            _ => None,
+        }
+    }
+
+    fn visit(&mut self, expr: &mut Expression<'_>) {
+        match expr {
+            Expression::ArrayLen { array, location } => {
+                self.visit(array);
+                let location = *location;
+                if let Some(length) = self.length(array) {
+                    *expr = Expression::UintLiteral {
+                        value: length as u64,
+                        size: Some(UIntSizes::Usize),
+                        location,
+                    };
+                }
+            }
+            Expression::SafeCast {
+                expr: value,
+                typ,
+                location,
+            } => {
+                self.visit(value);
+                let target = match typ.as_ref() {
+                    TypeExpr::Named { name, .. } => Type::from_name(name),
+                    _ => None,
+                };
+                let infallible = target
+                    .zip(self.typ(value))
+                    .is_some_and(|(target, source)| target.holds_every_value_of(&source));
+                if infallible {
+                    let location = *location;
+                    let value = std::mem::replace(value.as_mut(), Expression::Bool { value: false, location });
+                    *expr = Expression::FunCall {
+                        ident: Box::new(Expression::Ident {
+                            name: Cow::Borrowed("Some"),
+                            location,
+                        }),
+                        location,
+                        arguments: vec![value],
+                        tail_call: false,
+                    };
+                }
+            }
+            Expression::Macro { name, tokens, location } if name == KIND_OF_MACRO => {
+                if let Some(kind) = kind_of(tokens) {
+                    *expr = Expression::StringLiteral {
+                        value: Cow::Borrowed(kind),
+                        location: *location,
+                    };
+                }
+            }
+            Expression::Var {
+                mutable,
+                typ,
+                value,
+                location,
+                ..
+            } => {
+                self.visit(value);
+                let declaration = self.symbols.declaration(*location);
+                if let (false, Some(id), Some(length)) = (*mutable, declaration, self.length(value)) {
+                    self.lengths.insert(id, length);
+                }
+                let declared = match typ.as_deref() {
+                    Some(TypeExpr::Named { name, .. }) => Type::from_name(name),
+                    _ => None,
+                };
+                if let (Some(id), Some(declared)) = (declaration, declared) {
+                    self.types.insert(id, declared);
+                }
+            }
+            Expression::Assert { condition, message, .. } => {
+                self.visit(condition);
+                message.iter_mut().for_each(|message| self.visit(message));
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                self.visit(condition);
+                self.visit(truthy);
+                falsy.iter_mut().for_each(|branch| self.visit(branch));
+            }
+            Expression::Match { scrutinee, arms, .. } => {
+                self.visit(scrutinee);
+                arms.iter_mut().for_each(|arm| self.visit(&mut arm.body));
+            }
+            Expression::Yield { value, .. } => value.iter_mut().for_each(|value| self.visit(value)),
+            Expression::EnumVariant { kind, .. } => kind.values_mut().for_each(|value| self.visit(value)),
+            Expression::DoBlock { steps, .. } => steps.iter_mut().for_each(|step| self.visit(step.expr_mut())),
+            Expression::Assign { value, .. }
+            | Expression::CompoundAssign { value, .. }
+            | Expression::Return { value, .. }
+            | Expression::Await { expr: value, .. }
+            | Expression::RuntimeTypeOf { expr: value, .. }
+            | Expression::Splat { expr: value, .. }
+            | Expression::UnsafeBlock { body: value, .. }
+            | Expression::ComptimeBlock { body: value, .. }
+            | Expression::Defer { body: value, .. }
+            | Expression::Ref { expr: value, .. }
+            | Expression::Deref { expr: value, .. }
+            | Expression::Not { expr: value, .. }
+            | Expression::FieldAccess { target: value, .. }
+            | Expression::TupleAccess { tuple: value, .. }
+            | Expression::Try { expr: value, .. }
+            | Expression::Lambda { body: value, .. } => self.visit(value),
+            Expression::FunCall { ident, arguments, .. } => {
+                self.visit(ident);
+                arguments.iter_mut().for_each(|argument| self.visit(argument));
+            }
+            Expression::BinaryOp { lhs, rhs, .. }
+            | Expression::Index {
+                target: lhs,
+                index: rhs,
+                ..
+            }
+            | Expression::Elvis {
+                condition: lhs,
+                default: rhs,
+                ..
+            }
+            | Expression::Let {
+                value: lhs, body: rhs, ..
+            } => {
+                self.visit(lhs);
+                self.visit(rhs);
+            }
+            Expression::Block { expressions, tail, .. } => expressions
+                .iter_mut()
+                .chain(tail.as_deref_mut())
+                .for_each(|expr| self.visit(expr)),
+            Expression::InlineAsm { outputs, inputs, .. } => outputs
+                .iter_mut()
+                .chain(inputs.iter_mut())
+                .for_each(|operand| self.visit(&mut operand.expr)),
+            Expression::Interpolated { expressions, .. }
+            | Expression::Array {
+                elements: expressions, ..
+            }
+            | Expression::Tuple {
+                elements: expressions, ..
+            } => expressions.iter_mut().for_each(|expr| self.visit(expr)),
+            Expression::Bool { .. }
+            | Expression::Sizeof { .. }
+            | Expression::Macro { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::Ident { .. }
+            | Expression::Path { .. }
+            | Expression::UintLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::IntLiteral { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::expression::parse_expression;
+    use crate::sexpr::{statements_to_sexpr, to_sexpr};
+    use crate::Parser;
+
+    fn fold(source: &str) -> String {
+        let mut lexer = Lexer::new(source);
+        let mut expr = match parse_expression(&mut lexer, true) {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+        fold_constants(&mut expr);
+        to_sexpr(&expr)
+    }
+
+    #[test]
+    fn lengths_of_array_literals_are_folded() {
+        assert_eq!(fold("const N = len!([1, 2, 3]);"), "(const N (uint-literal 3 usize))");
+        assert_eq!(fold("const N = len!([]);"), "(const N (uint-literal 0 usize))");
+        assert_eq!(fold("const N = len!(f());"), "(const N (len (call (ident f))))");
+    }
+
+    #[test]
+    fn lengths_of_const_bindings_are_folded() {
+        let source = "fun main() {
+            const a = [1, 2];
+            var b = [1, 2, 3];
+            const c = a;
+            print(len!(a), len!(b), len!(c));
+        }";
+        let mut ast = Parser::new(source, Lexer::new(source)).parse().unwrap();
+        fold_constants_statements(&mut ast);
+
+        let sexpr = statements_to_sexpr(&ast);
+        assert!(
+            sexpr.contains("(call (ident print) (uint-literal 2 usize) (len (ident b)) (uint-literal 2 usize))"),
+            "{sexpr}"
+        );
+    }
+
+    #[test]
+    fn kinds_of_expressions_are_folded() {
+        assert_eq!(
+            fold("const x = kind_of!(1 + 2);"),
+            r#"(const x (string-literal "BinaryOp"))"#
+        );
+        assert_eq!(
+            fold("const x = kind_of!(-1);"),
+            r#"(const x (string-literal "IntLiteral"))"#
+        );
+        assert_eq!(
+            fold("const x = kind_of!(f(a));"),
+            r#"(const x (string-literal "FunCall"))"#
+        );
+        // what isn't a single expression is left as it is
+        assert_eq!(fold("const x = kind_of!(1 2);"), "(const x (macro kind_of 1 2))");
+    }
+
+    #[test]
+    fn casts_that_cant_fail_are_folded() {
+        assert_eq!(
+            fold("const x = -1i32 try_as i32;"),
+            "(const x (call (ident Some) (int-literal -1 i32)))"
+        );
+        assert_eq!(
+            fold("const x = 7u8 try_as i16;"),
+            "(const x (call (ident Some) (uint-literal 7 u8)))"
+        );
+        assert_eq!(
+            fold("const x = -7i64 try_as i32;"),
+            "(const x (try-as (int-literal -7 i64) (type-name i32)))"
+        );
+        assert_eq!(
+            fold("const x = 7u8 try_as i8;"),
+            "(const x (try-as (uint-literal 7 u8) (type-name i8)))"
+        );
+        assert_eq!(
+            fold("const x = a try_as u8;"),
+            "(const x (try-as (ident a) (type-name u8)))"
+        );
+
+        let source = "fun main() {
+            const a: u16 = f();
+            var b: i64 = g();
+            print(a try_as u32, b try_as u8);
+        }";
+        let mut ast = Parser::new(source, Lexer::new(source)).parse().unwrap();
+        fold_constants_statements(&mut ast);
+
+        let sexpr = statements_to_sexpr(&ast);
+        assert!(
+            sexpr.contains("(call (ident print) (call (ident Some) (ident a)) (try-as (ident b) (type-name u8)))"),
+            "{sexpr}"
+        );
+    }
+}
@@ -0,0 +1,258 @@
+use std::collections::HashSet;
+
+use kura_lexer::token::{Kind, Location, Operator, Token, Value};
+use miette::Report;
+
+use super::desugar::for_each_child;
+use crate::expression::closing_delimiter;
+use crate::warning::DiagnosticWarning;
+use crate::{Expression, Statement};
+
+/// macros taking a format string followed by the arguments it refers to,
+/// like `println!("{} is {age}", name, age)`
+pub const FORMAT_MACROS: &[&str] = &["format", "print", "println", "eprint", "eprintln", "panic"];
+
+/// checks the format string of every invocation of one of `FORMAT_MACROS`
+/// against the arguments it is given. invocations not starting with a
+/// string literal are left alone
+pub fn lint_format_args(statements: &[Statement<'_>]) -> Vec<Report> {
+    let mut reports = vec![];
+
+    for statement in statements.iter().flat_map(Statement::functions) {
+        if let Statement::Fun { body, .. } = statement {
+            body.iter().for_each(|expr| visit(expr, &mut reports));
+        }
+    }
+
+    reports
+}
+
+/// the warnings about `template`, the format string of an invocation, given
+/// the tokens of each argument after it. a `{}` takes the next positional
+/// argument and a `{0}` the one at that index, and the positional arguments
+/// must match in number the most either of them takes. a `{name}` takes
+/// either an argument written `name = value` or one that is just `name`,
+/// which then isn't positional
+pub fn validate_format_args(template: &Token<'_>, arguments: &[&[Token<'_>]]) -> Vec<DiagnosticWarning> {
+    let Kind::Value(Value::String(contents)) = &template.kind else {
+        return vec![];
+    };
+    // the contents start right after the opening quote
+    let placeholders = placeholders(contents, template.location.start_byte + 1);
+
+    let named = placeholders
+        .iter()
+        .filter_map(|(placeholder, _)| match placeholder {
+            Placeholder::Named(name) => Some(*name),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+    let mut declared = HashSet::new();
+    let mut positional = 0;
+    for argument in arguments {
+        match argument {
+            [Token {
+                kind: Kind::Value(Value::Ident(name)),
+                ..
+            }, Token {
+                kind: Kind::Op(Operator::Equal),
+                ..
+            }, ..] => _ = declared.insert(name.as_ref()),
+            [Token {
+                kind: Kind::Value(Value::Ident(name)),
+                ..
+            }] if named.contains(name.as_ref()) => _ = declared.insert(name.as_ref()),
+            _ => positional += 1,
+        }
+    }
+
+    let mut warnings = vec![];
+    let next = placeholders
+        .iter()
+        .filter(|(placeholder, _)| *placeholder == Placeholder::Next)
+        .count();
+    let indexed = placeholders
+        .iter()
+        .filter_map(|(placeholder, _)| match placeholder {
+            Placeholder::Index(index) => Some(index + 1),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0);
+    let expected = next.max(indexed);
+    if expected != positional {
+        warnings.push(DiagnosticWarning::FormatArgCountMismatch {
+            placeholders: expected,
+            arguments: positional,
+            span: template.location.into(),
+        });
+    }
+    for (placeholder, location) in &placeholders {
+        match placeholder {
+            Placeholder::Named(name) if !declared.contains(name) => {
+                warnings.push(DiagnosticWarning::UnknownFormatArgument {
+                    name: name.to_string(),
+                    span: (*location).into(),
+                })
+            }
+            _ => (),
+        }
+    }
+    warnings
+}
+
+/// which argument a placeholder of a format string takes
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Placeholder<'a> {
+    /// `{}`, the positional argument after the one the last `{}` took
+    Next,
+    /// `{0}`, the positional argument at that index
+    Index(usize),
+    /// `{name}`
+    Named(&'a str),
+}
+
+/// every placeholder of `contents`, along with where it is. `{{` and `}}`
+/// are literal braces, and whatever follows a `:` in a placeholder is how
+/// its argument is formatted
+fn placeholders(contents: &str, start: usize) -> Vec<(Placeholder<'_>, Location)> {
+    let mut placeholders = vec![];
+    let mut pos = 0;
+
+    while let Some(offset) = contents[pos..].find(['{', '}']) {
+        pos += offset;
+        let rest = &contents[pos..];
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            pos += 2;
+            continue;
+        }
+        if rest.starts_with('}') {
+            pos += 1;
+            continue;
+        }
+        let Some(length) = rest.find('}') else {
+            break;
+        };
+        let argument = rest[1..length].split(':').next().unwrap_or_default().trim();
+        let location = Location::new(start + pos, start + pos + length + 1);
+        let placeholder = match argument {
+            "" => Some(Placeholder::Next),
+            index if index.bytes().all(|byte| byte.is_ascii_digit()) => index.parse().ok().map(Placeholder::Index),
+            name if is_identifier(name) => Some(Placeholder::Named(name)),
+            _ => None,
+        };
+        placeholders.extend(placeholder.map(|placeholder| (placeholder, location)));
+        pos += length + 1;
+    }
+
+    placeholders
+}
+
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// the tokens of each argument, split at the commas outside of any
+/// delimiter. a trailing comma doesn't start another argument
+fn split_arguments<'a, 'tok>(tokens: &'a [Token<'tok>]) -> Vec<&'a [Token<'tok>]> {
+    let mut arguments = vec![];
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (idx, token) in tokens.iter().enumerate() {
+        match token.kind {
+            Kind::Op(Operator::Comma) if depth == 0 => {
+                arguments.push(&tokens[start..idx]);
+                start = idx + 1;
+            }
+            Kind::Op(Operator::RightParen | Operator::RightBracket | Operator::RightBrace) => {
+                depth = depth.saturating_sub(1)
+            }
+            ref kind if closing_delimiter(kind).is_some() => depth += 1,
+            _ => (),
+        }
+    }
+    if start < tokens.len() {
+        arguments.push(&tokens[start..]);
+    }
+    arguments
+}
+
+fn visit(expr: &Expression<'_>, reports: &mut Vec<Report>) {
+    match expr {
+        Expression::Macro { name, tokens, .. } if FORMAT_MACROS.contains(&name.as_ref()) => {
+            let arguments = split_arguments(tokens);
+            if let Some(([template], arguments)) = arguments.split_first() {
+                validate_format_args(template, arguments)
+                    .into_iter()
+                    .for_each(|warning| reports.push(Report::new(warning)));
+            }
+        }
+        expr => for_each_child(expr, |child| visit(child, reports)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::Parser;
+
+    fn lint(source: &str) -> Vec<String> {
+        let statements = match Parser::new(source, Lexer::new(source)).parse() {
+            Ok(statements) => statements,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        lint_format_args(&statements)
+            .iter()
+            .map(|report| report.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn arguments_match_the_placeholders() {
+        let source = r#"fun main() {
+            println!("{} is {} years old", name, age + 1);
+            println!("{0} {0} and {1:?}, {}", a, b);
+            println!("{name} is {:>3} years old, {{literally}}", f(a, b), name);
+            print!("{greeting}, {}!", greeting = "hi", "you",);
+            format!(template, a);
+            other!("{}");
+        }"#;
+        assert!(lint(source).is_empty());
+    }
+
+    #[test]
+    fn mismatched_arguments_are_warned_about() {
+        let source = r#"fun main() {
+            println!("{} {}", a);
+            const s = format!("{}", a, b);
+            panic!("{missing} {}", x);
+            println!("{0} {0}", a, b);
+            println!("{2}", a);
+        }"#;
+        assert_eq!(
+            lint(source),
+            [
+                "the format string takes 2 arguments but 1 was given",
+                "the format string takes 1 argument but 2 were given",
+                "there is no argument named `missing`",
+                "the format string takes 1 argument but 2 were given",
+                "the format string takes 3 arguments but 1 was given",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_names_point_at_their_placeholder() {
+        let source = r#"fun main() { println!("hi {who:?}"); }"#;
+        let statements = Parser::new(source, Lexer::new(source)).parse().unwrap();
+        let report = &lint_format_args(&statements)[0];
+        let Some(DiagnosticWarning::UnknownFormatArgument { span, .. }) = report.downcast_ref() else {
+            panic!("expected an unknown argument");
+        };
+        assert_eq!(&source[span.offset()..span.offset() + span.len()], "{who:?}");
+    }
+}
@@ -0,0 +1,174 @@
+use miette::{Diagnostic, Report, SourceSpan};
+use thiserror::Error;
+
+use crate::{Expression, Statement};
+
+/// name of the attribute marking a function as a generator, like
+/// `#[gen] fun numbers() { yield 1; }`
+pub const GENERATOR_ATTRIBUTE: &str = "gen";
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("`yield` outside of a generator")]
+#[diagnostic(
+    code(kura::yield_outside_generator),
+    severity(Warning),
+    help("mark the function with `#[gen]` to make it a generator")
+)]
+pub struct YieldOutsideGenerator {
+    #[label("yields here")]
+    pub span: SourceSpan,
+}
+
+/// reports every `yield` that isn't directly inside of a generator. the
+/// body of a lambda is a function of its own, so a `yield` in it is
+/// reported even when the lambda is inside of a generator
+pub fn lint_yield_outside_generator(statements: &[Statement<'_>]) -> Vec<Report> {
+    let mut reports = vec![];
+
+    for statement in statements.iter().flat_map(Statement::functions) {
+        if let Statement::Fun { body, attributes, .. } = statement {
+            let generator = attributes.iter().any(|attribute| attribute.name == GENERATOR_ATTRIBUTE);
+            body.iter().for_each(|expr| visit(expr, generator, &mut reports));
+        }
+    }
+
+    reports
+}
+
+fn visit(expr: &Expression<'_>, generator: bool, reports: &mut Vec<Report>) {
+    match expr {
+        Expression::Yield { value, location } => {
+            if !generator {
+                reports.push(Report::new(YieldOutsideGenerator {
+                    span: (*location).into(),
+                }));
+            }
+            value.iter().for_each(|value| visit(value, generator, reports));
+        }
+        Expression::Lambda { body, .. } => visit(body, false, reports),
+        Expression::Block { expressions, tail, .. } => expressions
+            .iter()
+            .chain(tail.as_deref())
+            .for_each(|expr| visit(expr, generator, reports)),
+        Expression::InlineAsm { outputs, inputs, .. } => outputs
+            .iter()
+            .chain(inputs.iter())
+            .for_each(|operand| visit(&operand.expr, generator, reports)),
+        Expression::Interpolated { expressions, .. }
+        | Expression::Array {
+            elements: expressions, ..
+        }
+        | Expression::Tuple {
+            elements: expressions, ..
+        } => expressions.iter().for_each(|expr| visit(expr, generator, reports)),
+        Expression::Assert { condition, message, .. } => {
+            visit(condition, generator, reports);
+            message.iter().for_each(|message| visit(message, generator, reports));
+        }
+        Expression::If {
+            condition,
+            truthy,
+            falsy,
+            ..
+        } => {
+            visit(condition, generator, reports);
+            visit(truthy, generator, reports);
+            falsy.iter().for_each(|branch| visit(branch, generator, reports));
+        }
+        Expression::Match { scrutinee, arms, .. } => {
+            visit(scrutinee, generator, reports);
+            arms.iter().for_each(|arm| visit(&arm.body, generator, reports));
+        }
+        Expression::EnumVariant { kind, .. } => kind.values().for_each(|value| visit(value, generator, reports)),
+        Expression::DoBlock { steps, .. } => steps.iter().for_each(|step| visit(step.expr(), generator, reports)),
+        Expression::FunCall { ident, arguments, .. } => {
+            visit(ident, generator, reports);
+            arguments.iter().for_each(|arg| visit(arg, generator, reports));
+        }
+        Expression::Var { value, .. }
+        | Expression::Return { value, .. }
+        | Expression::Await { expr: value, .. }
+        | Expression::RuntimeTypeOf { expr: value, .. }
+        | Expression::SafeCast { expr: value, .. }
+        | Expression::ArrayLen { array: value, .. }
+        | Expression::Splat { expr: value, .. }
+        | Expression::UnsafeBlock { body: value, .. }
+        | Expression::ComptimeBlock { body: value, .. }
+        | Expression::Defer { body: value, .. }
+        | Expression::Ref { expr: value, .. }
+        | Expression::Deref { expr: value, .. }
+        | Expression::Not { expr: value, .. }
+        | Expression::FieldAccess { target: value, .. }
+        | Expression::TupleAccess { tuple: value, .. }
+        | Expression::Try { expr: value, .. } => visit(value, generator, reports),
+        Expression::Assign {
+            ident: lhs, value: rhs, ..
+        }
+        | Expression::CompoundAssign {
+            ident: lhs, value: rhs, ..
+        }
+        | Expression::BinaryOp { lhs, rhs, .. }
+        | Expression::Index {
+            target: lhs,
+            index: rhs,
+            ..
+        }
+        | Expression::Elvis {
+            condition: lhs,
+            default: rhs,
+            ..
+        }
+        | Expression::Let {
+            value: lhs, body: rhs, ..
+        } => {
+            visit(lhs, generator, reports);
+            visit(rhs, generator, reports);
+        }
+        Expression::Bool { .. }
+        | Expression::Sizeof { .. }
+        | Expression::Macro { .. }
+        | Expression::StringLiteral { .. }
+        | Expression::Ident { .. }
+        | Expression::Path { .. }
+        | Expression::UintLiteral { .. }
+        | Expression::FloatLiteral { .. }
+        | Expression::IntLiteral { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::Parser;
+
+    fn lint(source: &str) -> Vec<&str> {
+        let statements = match Parser::new(source, Lexer::new(source)).parse() {
+            Ok(statements) => statements,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        lint_yield_outside_generator(&statements)
+            .iter()
+            .map(|report| {
+                let span = report.downcast_ref::<YieldOutsideGenerator>().unwrap().span;
+                &source[span.offset()..span.offset() + span.len()]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn generators_can_yield() {
+        let source = "#[gen]\nfun numbers() { yield 1; if done { yield; } }";
+        assert!(lint(source).is_empty());
+    }
+
+    #[test]
+    fn yields_outside_of_generators() {
+        let source = "fun numbers() { yield 1; }\n\
+                      impl Counter { fun next() { yield 2; } }\n\
+                      #[gen]\nfun nested() { const f = fun() { yield 3; }; yield 4; }";
+        assert_eq!(lint(source), ["yield 1;", "yield 2;", "yield 3;"]);
+    }
+}
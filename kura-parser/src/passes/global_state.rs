@@ -0,0 +1,80 @@
+use miette::{Diagnostic, Report, SourceSpan};
+use thiserror::Error;
+
+use crate::Statement;
+
+/// argument of `#[allow(...)]` that silences this lint on a global
+const ALLOW_MUTABLE_GLOBAL: &str = "mutable_global";
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("`{name}` is mutable global state")]
+#[diagnostic(
+    code(kura::mutable_global),
+    severity(Warning),
+    help("wrap it in a `Mutex` and declare it with `const` instead, or add `#[allow(mutable_global)]` to it")
+)]
+pub struct MutableGlobal {
+    pub name: String,
+    #[label("declared with `var` here")]
+    pub global: SourceSpan,
+}
+
+/// reports every global declared with `var`, as anything can change it from
+/// anywhere
+pub fn analyze_global_state(statements: &[Statement<'_>]) -> Vec<Report> {
+    statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Static {
+                mutable: true,
+                name,
+                attributes,
+                location,
+                ..
+            } if !attributes
+                .iter()
+                .any(|attribute| attribute.allows(ALLOW_MUTABLE_GLOBAL)) =>
+            {
+                Some(Report::new(MutableGlobal {
+                    name: name.to_string(),
+                    global: (*location).into(),
+                }))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::Parser;
+
+    fn analyze(source: &str) -> Vec<String> {
+        let statements = match Parser::new(source, Lexer::new(source)).parse() {
+            Ok(statements) => statements,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        analyze_global_state(&statements)
+            .iter()
+            .map(|report| {
+                let span = report.downcast_ref::<MutableGlobal>().unwrap().global;
+                format!("{report}: {}", &source[span.offset()..span.offset() + span.len()])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mutable_globals() {
+        let source = "var counter: u64 = 0;\nconst LIMIT = 10;\n\
+                      #[allow(mutable_global)]\nvar cache = 0;\n\
+                      fun main() { var local = 1; }";
+        assert_eq!(
+            analyze(source),
+            ["`counter` is mutable global state: var counter: u64 = 0"]
+        );
+    }
+}
@@ -0,0 +1,25 @@
+use miette::Report;
+
+use super::{
+    analyze_global_state, lint_deprecated, lint_format_args, lint_magic_numbers, lint_shadowing,
+    lint_unused_statements, lint_yield_outside_generator, unreachable_code_statements,
+};
+use crate::resolve::SymbolTable;
+use crate::Statement;
+
+/// the warnings of every lint over `statements`, in the order they are
+/// reported. the lints that are given symbols share one table, resolved here
+pub fn lint_all(statements: &[Statement<'_>]) -> Vec<Report> {
+    let symbols = SymbolTable::resolve_statements(statements);
+
+    unreachable_code_statements(statements)
+        .into_iter()
+        .chain(lint_unused_statements(statements, &symbols))
+        .chain(lint_shadowing(statements, &symbols))
+        .chain(lint_yield_outside_generator(statements))
+        .chain(lint_format_args(statements))
+        .chain(lint_magic_numbers(statements))
+        .chain(lint_deprecated(statements, &symbols))
+        .chain(analyze_global_state(statements))
+        .collect()
+}
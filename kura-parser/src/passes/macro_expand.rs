@@ -0,0 +1,466 @@
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+
+use kura_lexer::token::{Kind, Location, Operator, Token, Value};
+use kura_lexer::Lexer;
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use super::desugar::map_children;
+use crate::expression::{closing_delimiter, lay_out, parse_expression};
+use crate::{parse_type_expression, Expression, MacroRule, ParseError, Statement};
+
+/// how many expansions deep a macro can be expanded from another one,
+/// unless the environment says otherwise
+pub const MAX_EXPANSION_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Error, Diagnostic)]
+pub enum MacroError {
+    #[error("no rule of `{name}!` matches these arguments")]
+    #[diagnostic(code(kura::macro_expand::no_matching_rule))]
+    NoMatchingRule {
+        name: String,
+        #[label("in this invocation")]
+        span: SourceSpan,
+    },
+    #[error("`{name}!` expands to invalid code: {message}")]
+    #[diagnostic(code(kura::macro_expand::invalid_expansion))]
+    InvalidExpansion {
+        name: String,
+        message: String,
+        #[label("expanded here")]
+        span: SourceSpan,
+    },
+    #[error("`{name}!` is expanded more than {limit} levels deep")]
+    #[diagnostic(
+        code(kura::macro_expand::recursion_limit),
+        help("a rule of the macro probably expands to itself forever")
+    )]
+    RecursionLimit {
+        name: String,
+        limit: usize,
+        #[label("while expanding this")]
+        span: SourceSpan,
+    },
+}
+
+/// the `macro_rules!` macros that can be expanded, by name
+#[derive(Debug, Clone)]
+pub struct MacroEnv<'ast> {
+    macros: HashMap<String, Vec<MacroRule<'ast>>>,
+    max_depth: usize,
+}
+
+impl Default for MacroEnv<'_> {
+    fn default() -> Self {
+        Self {
+            macros: HashMap::new(),
+            max_depth: MAX_EXPANSION_DEPTH,
+        }
+    }
+}
+
+impl<'ast> MacroEnv<'ast> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// every macro defined by `statements`. a later definition of a name
+    /// replaces the earlier ones
+    pub fn from_statements(statements: &[Statement<'ast>]) -> Self {
+        let mut env = Self::new();
+        for statement in statements {
+            if let Statement::MacroRules { name, rules, .. } = statement {
+                env.define(name.to_string(), rules.clone());
+            }
+        }
+        env
+    }
+
+    pub fn define(&mut self, name: String, rules: Vec<MacroRule<'ast>>) {
+        self.macros.insert(name, rules);
+    }
+
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+}
+
+/// replaces every invocation of a macro of `env` in `expr` by what it
+/// expands to, which can invoke other macros, or the same one again. other
+/// macros, like the built-in ones, are left as they are.
+///
+/// the first rule whose pattern matches the arguments is expanded. patterns
+/// match tokens as written, besides `$name:ident`, `$name:expr` and
+/// `$name:ty`, which take an identifier, an expression or a type, and are
+/// substituted for `$name` in the expansion. the expansion is then parsed as
+/// an expression, located where the macro was invoked.
+///
+/// names declared by the expansion itself, with `var`, `const` or `let`, are
+/// suffixed by a number unique to the expansion, like `tmp__3`, so they
+/// don't capture nor shadow the names of the arguments
+pub fn expand_macros<'ast>(expr: Expression<'ast>, env: &MacroEnv<'_>) -> Result<Expression<'ast>, MacroError> {
+    let expander = Expander {
+        env,
+        error: RefCell::new(None),
+        expansions: Cell::new(0),
+    };
+    let expr = expander.expand(expr, 0);
+    match expander.error.into_inner() {
+        Some(error) => Err(error),
+        None => Ok(expr),
+    }
+}
+
+struct Expander<'env, 'ast> {
+    env: &'env MacroEnv<'ast>,
+    /// the first error found, after which nothing else is expanded
+    error: RefCell<Option<MacroError>>,
+    /// how many expansions were made, to give each one its own names
+    expansions: Cell<usize>,
+}
+
+impl<'ast> Expander<'_, '_> {
+    fn expand(&self, expr: Expression<'ast>, depth: usize) -> Expression<'ast> {
+        if self.error.borrow().is_some() {
+            return expr;
+        }
+
+        match expr {
+            Expression::Macro {
+                ref name,
+                ref tokens,
+                location,
+            } if self.env.macros.contains_key(name.as_ref()) => {
+                let expanded = match depth < self.env.max_depth {
+                    true => self.expand_once(name, tokens, location),
+                    false => Err(MacroError::RecursionLimit {
+                        name: name.to_string(),
+                        limit: self.env.max_depth,
+                        span: location.into(),
+                    }),
+                };
+                match expanded {
+                    Ok(expanded) => self.expand(expanded, depth + 1),
+                    Err(error) => {
+                        *self.error.borrow_mut() = Some(error);
+                        expr
+                    }
+                }
+            }
+            expr => map_children(expr, |child| self.expand(child, depth)),
+        }
+    }
+
+    fn expand_once(
+        &self,
+        name: &str,
+        arguments: &[Token<'_>],
+        location: Location,
+    ) -> Result<Expression<'ast>, MacroError> {
+        let rules = &self.env.macros[name];
+        let invalid = |message: String| MacroError::InvalidExpansion {
+            name: name.to_string(),
+            message,
+            span: location.into(),
+        };
+
+        let Some((rule, bindings)) = rules
+            .iter()
+            .find_map(|rule| Some((rule, match_pattern(&parse_pattern(&rule.pattern)?, arguments)?)))
+        else {
+            return Err(MacroError::NoMatchingRule {
+                name: name.to_string(),
+                span: location.into(),
+            });
+        };
+
+        self.expansions.set(self.expansions.get() + 1);
+        let expansion = substitute(&rule.expansion, &bindings, self.expansions.get()).map_err(invalid)?;
+
+        // the expansion is parsed as if it was written where the macro is
+        // invoked, so whatever points into it points at the invocation
+        let (source, tokens) = lay_out(&expansion, location.start_byte);
+        let mut lexer = Lexer::with_offset(&source, location.start_byte, 0).replaying(tokens);
+        let expr = parse_expression(&mut lexer, false).map_err(|error| invalid(error.message))?;
+        if let Some(token) = lexer
+            .next()
+            .transpose()
+            .map_err(|error| invalid(ParseError::from(error).message))?
+        {
+            return Err(invalid(format!("unexpected `{}` after the expression", token.kind)));
+        }
+        Ok(expr.into_owned())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Fragment {
+    Ident,
+    Expr,
+    Ty,
+}
+
+enum Matcher<'a, 'tok> {
+    Token(&'a Kind<'tok>),
+    Variable(&'a str, Fragment),
+}
+
+/// the matchers of a pattern, or `None` when it uses a fragment other than
+/// `ident`, `expr` or `ty`, which can't match anything
+fn parse_pattern<'a, 'tok>(pattern: &'a [Token<'tok>]) -> Option<Vec<Matcher<'a, 'tok>>> {
+    let mut matchers = vec![];
+    let mut tokens = pattern.iter();
+    while let Some(token) = tokens.next() {
+        if token.kind != Kind::Op(Operator::Dollar) {
+            matchers.push(Matcher::Token(&token.kind));
+            continue;
+        }
+
+        let (Some(name), Some(colon), Some(fragment)) = (tokens.next(), tokens.next(), tokens.next()) else {
+            return None;
+        };
+        let (Kind::Value(Value::Ident(name)), Kind::Op(Operator::Colon), Kind::Value(Value::Ident(fragment))) =
+            (&name.kind, &colon.kind, &fragment.kind)
+        else {
+            return None;
+        };
+        let fragment = match fragment.as_ref() {
+            "ident" => Fragment::Ident,
+            "expr" => Fragment::Expr,
+            "ty" => Fragment::Ty,
+            _ => return None,
+        };
+        matchers.push(Matcher::Variable(name, fragment));
+    }
+    Some(matchers)
+}
+
+/// the tokens each variable of `matchers` takes from `arguments`, when all
+/// of them match. expressions and types take every token up to the one
+/// following them in the pattern, outside of any delimiter
+fn match_pattern<'a, 'tok>(
+    matchers: &[Matcher<'a, '_>],
+    arguments: &[Token<'tok>],
+) -> Option<HashMap<&'a str, Vec<Token<'tok>>>> {
+    let mut bindings = HashMap::new();
+    let mut rest = arguments;
+
+    for (idx, matcher) in matchers.iter().enumerate() {
+        match matcher {
+            Matcher::Token(kind) => {
+                let (first, others) = rest.split_first()?;
+                if first.kind != **kind {
+                    return None;
+                }
+                rest = others;
+            }
+            Matcher::Variable(name, Fragment::Ident) => {
+                let (first, others) = rest.split_first()?;
+                if !matches!(first.kind, Kind::Value(Value::Ident(_))) {
+                    return None;
+                }
+                bindings.insert(*name, vec![first.clone()]);
+                rest = others;
+            }
+            Matcher::Variable(name, fragment) => {
+                let end = match matchers.get(idx + 1) {
+                    Some(Matcher::Token(until)) => position_outside_delimiters(rest, until)?,
+                    _ => rest.len(),
+                };
+                let (taken, others) = rest.split_at(end);
+                if taken.is_empty() || !parses_as(taken, *fragment) {
+                    return None;
+                }
+                // expressions are parenthesized so they keep their meaning
+                // whatever surrounds them once substituted
+                let bound = match fragment {
+                    Fragment::Expr if taken.len() > 1 => {
+                        let paren = |operator, location| Token::new(Kind::Op(operator), location);
+                        let mut bound = vec![paren(Operator::LeftParen, taken[0].location)];
+                        bound.extend_from_slice(taken);
+                        bound.push(paren(Operator::RightParen, taken[taken.len() - 1].location));
+                        bound
+                    }
+                    _ => taken.to_vec(),
+                };
+                bindings.insert(*name, bound);
+                rest = others;
+            }
+        }
+    }
+
+    rest.is_empty().then_some(bindings)
+}
+
+/// where the first `kind` outside of any delimiter is among `tokens`
+fn position_outside_delimiters(tokens: &[Token<'_>], kind: &Kind<'_>) -> Option<usize> {
+    let mut depth = 0usize;
+    for (idx, token) in tokens.iter().enumerate() {
+        if depth == 0 && token.kind == *kind {
+            return Some(idx);
+        }
+        if closing_delimiter(&token.kind).is_some() {
+            depth += 1;
+        } else if let Kind::Op(Operator::RightParen | Operator::RightBracket | Operator::RightBrace) = token.kind {
+            depth = depth.checked_sub(1)?;
+        }
+    }
+    None
+}
+
+/// whether the whole of `tokens` is a single `fragment`
+fn parses_as(tokens: &[Token<'_>], fragment: Fragment) -> bool {
+    let (text, tokens) = lay_out(tokens, 0);
+    let mut lexer = Lexer::new(&text).replaying(tokens);
+    let parsed = match fragment {
+        Fragment::Ident => matches!(
+            lexer.next(),
+            Some(Ok(Token {
+                kind: Kind::Value(Value::Ident(_)),
+                ..
+            }))
+        ),
+        Fragment::Expr => parse_expression(&mut lexer, false).is_ok(),
+        Fragment::Ty => parse_type_expression(&mut lexer).is_ok(),
+    };
+    parsed && lexer.next().is_none()
+}
+
+/// the tokens of `expansion`, with every `$name` replaced by what it bound,
+/// and every name the expansion declares suffixed by `expansion_id`
+fn substitute(
+    expansion: &[Token<'_>],
+    bindings: &HashMap<&str, Vec<Token<'_>>>,
+    expansion_id: usize,
+) -> Result<Vec<Token<'static>>, String> {
+    let declared = expansion
+        .windows(2)
+        .filter_map(|pair| match (&pair[0].kind, &pair[1].kind) {
+            (Kind::Var | Kind::Const | Kind::Let, Kind::Value(Value::Ident(name))) => Some(name.as_ref()),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    let mut output = vec![];
+    let mut tokens = expansion.iter();
+    let mut after_dot = false;
+    while let Some(token) = tokens.next() {
+        match &token.kind {
+            Kind::Op(Operator::Dollar) => {
+                let Some(Kind::Value(Value::Ident(name))) = tokens.next().map(|token| &token.kind) else {
+                    return Err("expected the name of a variable after `$`".into());
+                };
+                let Some(bound) = bindings.get(name.as_ref()) else {
+                    return Err(format!("`${name}` isn't bound by the pattern"));
+                };
+                output.extend(bound.iter().cloned().map(Token::into_owned));
+            }
+            Kind::Value(Value::Ident(name)) if !after_dot && declared.contains(name.as_ref()) => {
+                let renamed = Value::Ident(Cow::Owned(format!("{name}__{expansion_id}")));
+                output.push(Token::new(Kind::Value(renamed), token.location));
+            }
+            _ => output.push(token.clone().into_owned()),
+        }
+        after_dot = token.kind == Kind::Op(Operator::Dot);
+    }
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sexpr::to_sexpr;
+    use crate::Parser;
+
+    const MACROS: &str = "
+        macro_rules! square { ($x:expr) => { $x * $x } }
+        macro_rules! double { ($x:expr) => { { var t = $x; t + t } } }
+        macro_rules! declare { ($name:ident: $t:ty = $value:expr) => { var $name: $t = $value; } }
+        macro_rules! sum {
+            () => { 0 };
+            ($x:expr) => { $x };
+            ($x:expr, $y:expr) => { $x + sum!($y) }
+        }
+        macro_rules! forever { () => { forever!() } }
+    ";
+
+    fn expand(source: &str) -> Result<String, MacroError> {
+        let statements = Parser::new(MACROS, Lexer::new(MACROS)).parse().unwrap();
+        let env = MacroEnv::from_statements(&statements);
+        let expr = parse_expression(&mut Lexer::new(source), false).unwrap();
+        expand_macros(expr, &env).map(|expr| to_sexpr(&expr))
+    }
+
+    fn parse(source: &str) -> String {
+        to_sexpr(&parse_expression(&mut Lexer::new(source), false).unwrap())
+    }
+
+    #[test]
+    fn expressions_are_substituted_as_a_whole() {
+        assert_eq!(expand("square!(1 + 2)"), Ok(parse("(1 + 2) * (1 + 2)")));
+        assert_eq!(expand("square!(a)"), Ok(parse("a * a")));
+        assert_eq!(
+            expand("declare!(bytes: [u8; 2] = [1, 2])"),
+            Ok(parse("var bytes: [u8; 2] = [1, 2];"))
+        );
+    }
+
+    #[test]
+    fn literals_are_substituted_as_they_were_lexed() {
+        assert_eq!(expand("square!(2.0)"), Ok(parse("2.0 * 2.0")));
+        assert_eq!(expand("square!(1.5f32 + 1.0)"), Ok(parse("(1.5f32 + 1.0) * (1.5f32 + 1.0)")));
+        assert_eq!(expand("square!(t.0.1)"), Ok(parse("t.0.1 * t.0.1")));
+        assert_eq!(expand("square!(\"{2.0}\")"), Ok(parse("\"{2.0}\" * \"{2.0}\"")));
+    }
+
+    #[test]
+    fn expansions_are_expanded_again() {
+        assert_eq!(expand("sum!(1, sum!(2, 3))"), Ok(parse("1 + (2 + 3)")));
+        assert_eq!(expand("foo(square!(sum!()))"), Ok(parse("foo(0 * 0)")));
+        assert!(matches!(
+            expand("forever!()"),
+            Err(MacroError::RecursionLimit {
+                limit: MAX_EXPANSION_DEPTH,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn names_declared_by_an_expansion_dont_capture_arguments() {
+        assert_eq!(
+            expand("double!(t) + double!(t)"),
+            Ok(parse("{ var t__1 = t; t__1 + t__1 } + { var t__2 = t; t__2 + t__2 }"))
+        );
+    }
+
+    #[test]
+    fn invocations_are_matched_against_every_rule() {
+        assert_eq!(expand("println!(square!(2))"), Ok(parse("println!(square!(2))")));
+        assert!(matches!(expand("square!()"), Err(MacroError::NoMatchingRule { .. })));
+        assert!(matches!(
+            expand("sum!(1, 2, 3)"),
+            Err(MacroError::NoMatchingRule { .. })
+        ));
+        assert!(matches!(
+            expand("declare!(1: i32 = 1)"),
+            Err(MacroError::NoMatchingRule { .. })
+        ));
+    }
+
+    #[test]
+    fn expansions_are_located_at_their_invocation() {
+        let statements = Parser::new(MACROS, Lexer::new(MACROS)).parse().unwrap();
+        let source = "foo(square!(a))";
+        let expr = parse_expression(&mut Lexer::new(source), false).unwrap();
+        let Expression::FunCall { arguments, .. } =
+            expand_macros(expr, &MacroEnv::from_statements(&statements)).unwrap()
+        else {
+            panic!("expected a call");
+        };
+        assert_eq!(arguments[0].location().start_byte, source.find("square").unwrap());
+    }
+}
@@ -0,0 +1,139 @@
+use miette::{Diagnostic, Report, SourceSpan};
+use thiserror::Error;
+
+use super::desugar::for_each_child;
+use crate::{Attribute, Expression, Statement};
+
+/// argument of `#[allow(...)]` that silences this lint on an item
+const ALLOW_MAGIC_NUMBERS: &str = "magic_numbers";
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("magic number `{value}`")]
+#[diagnostic(
+    code(kura::magic_number),
+    severity(Warning),
+    help("give it a name with a `const`, or add `#[allow(magic_numbers)]` to the item it is in")
+)]
+pub struct MagicNumber {
+    pub value: String,
+    #[label("used here")]
+    pub span: SourceSpan,
+}
+
+/// reports every number written as a literal in the body of a function or
+/// as the value of a `var` global, which says nothing of what it stands for.
+/// the value of a `const` names the number already, and so do the lengths
+/// of array types and the patterns of a `match`, which are never visited.
+/// `0`, `1` and `-1` mean what they are, so they are never reported
+pub fn lint_magic_numbers(statements: &[Statement<'_>]) -> Vec<Report> {
+    let mut reports = vec![];
+
+    for statement in statements {
+        match statement {
+            Statement::Static {
+                mutable: true,
+                value,
+                attributes,
+                ..
+            } if !allowed(attributes) => visit(value, &mut reports),
+            statement => {
+                for function in statement.functions() {
+                    if let Statement::Fun { body, attributes, .. } = function {
+                        if !allowed(attributes) {
+                            body.iter().for_each(|expr| visit(expr, &mut reports));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    reports
+}
+
+fn allowed(attributes: &[Attribute<'_>]) -> bool {
+    attributes.iter().any(|attribute| attribute.allows(ALLOW_MAGIC_NUMBERS))
+}
+
+/// the value of `expr` as written, when it is a number other than `0`, `1`
+/// and `-1`
+fn magic_number(expr: &Expression<'_>) -> Option<String> {
+    match expr {
+        Expression::UintLiteral { value, .. } => (*value > 1).then(|| value.to_string()),
+        Expression::IntLiteral { value, .. } => (!(-1..=1).contains(value)).then(|| value.to_string()),
+        Expression::FloatLiteral { value, .. } => (![0.0, 1.0, -1.0].contains(value)).then(|| format!("{value:?}")),
+        _ => None,
+    }
+}
+
+fn visit(expr: &Expression<'_>, reports: &mut Vec<Report>) {
+    if let Some(value) = magic_number(expr) {
+        reports.push(Report::new(MagicNumber {
+            value,
+            span: expr.location().into(),
+        }));
+        return;
+    }
+
+    match expr {
+        // a `const` is the name of its value
+        Expression::Var { mutable: false, .. } => {}
+        Expression::Var { attributes, .. } if allowed(attributes) => {}
+        expr => for_each_child(expr, |child| visit(child, reports)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::Parser;
+
+    fn lint(source: &str) -> Vec<String> {
+        let statements = match Parser::new(source, Lexer::new(source)).parse() {
+            Ok(statements) => statements,
+            Err(e) => panic!("{e:?}"),
+        };
+
+        lint_magic_numbers(&statements)
+            .iter()
+            .map(|report| {
+                let span = report.downcast_ref::<MagicNumber>().unwrap().span;
+                format!("{report}: {}", &source[span.offset()..span.offset() + span.len()])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn numbers_without_a_name() {
+        let source = "var retries = 3;\n\
+                      fun main(values: [i32; 16]) {\n\
+                          const LIMIT = 60 * 60;\n\
+                          var elapsed = 0;\n\
+                          elapsed += 1;\n\
+                          match elapsed { 42 => stop(), _ => wait(elapsed * 1.5) }\n\
+                          if elapsed > -7 { return -1; }\n\
+                      }";
+        assert_eq!(
+            lint(source),
+            [
+                "magic number `3`: 3",
+                "magic number `1.5`: 1.5",
+                "magic number `-7`: -7",
+            ]
+        );
+    }
+
+    #[test]
+    fn allowed_on_the_item() {
+        let source = "#[allow(magic_numbers)]\nvar retries = 3;\n\
+                      #[allow(magic_numbers)]\nfun main() { wait(100); }\n\
+                      fun other() {\n\
+                          #[allow(magic_numbers)]\n\
+                          var timeout = 30;\n\
+                          wait(timeout * 2);\n\
+                      }";
+        assert_eq!(lint(source), ["magic number `2`: 2"]);
+    }
+}
@@ -0,0 +1,225 @@
+use crate::passes::is_diverging;
+use crate::{Expression, Statement};
+
+/// marks every block inside of `block`, itself included, with whether it can
+/// ever run. a block is unreachable when it comes after an expression that
+/// always returns on the same block, or when it is inside of one that is
+/// unreachable already
+pub fn analyze_reachability(block: &mut Expression<'_>) {
+    visit(block, true);
+}
+
+/// same as `analyze_reachability`, for the body of every function in a
+/// program
+pub fn analyze_reachability_statements(statements: &mut [Statement<'_>]) {
+    for statement in statements {
+        match statement {
+            Statement::Fun { body, .. } => visit_block(body, true),
+            Statement::Impl { functions, .. } => analyze_reachability_statements(functions),
+            Statement::Static { value, .. } => visit(value, true),
+            Statement::FunArgument { .. }
+            | Statement::Struct { .. }
+            | Statement::MacroRules { .. }
+            | Statement::Mod { .. }
+            | Statement::ExternCrate { .. }
+            | Statement::Use { .. } => {}
+        }
+    }
+}
+
+fn visit(expr: &mut Expression<'_>, reachable: bool) {
+    match expr {
+        Expression::Block {
+            expressions,
+            tail,
+            reachable: mark,
+            ..
+        } => {
+            *mark = Some(reachable);
+            visit_block(expressions, reachable);
+            let reachable = reachable && !expressions.iter().any(is_diverging);
+            tail.iter_mut().for_each(|tail| visit(tail, reachable));
+        }
+        Expression::Assert { condition, message, .. } => {
+            visit(condition, reachable);
+            message.iter_mut().for_each(|message| visit(message, reachable));
+        }
+        Expression::If {
+            condition,
+            truthy,
+            falsy,
+            ..
+        } => {
+            visit(condition, reachable);
+            visit(truthy, reachable);
+            falsy.iter_mut().for_each(|branch| visit(branch, reachable));
+        }
+        Expression::Match { scrutinee, arms, .. } => {
+            visit(scrutinee, reachable);
+            arms.iter_mut().for_each(|arm| visit(&mut arm.body, reachable));
+        }
+        Expression::Yield { value, .. } => value.iter_mut().for_each(|value| visit(value, reachable)),
+        Expression::EnumVariant { kind, .. } => kind.values_mut().for_each(|value| visit(value, reachable)),
+        Expression::DoBlock { steps, .. } => steps.iter_mut().for_each(|step| visit(step.expr_mut(), reachable)),
+        Expression::Var { value, .. }
+        | Expression::Assign { value, .. }
+        | Expression::CompoundAssign { value, .. }
+        | Expression::Return { value, .. }
+        | Expression::Await { expr: value, .. }
+        | Expression::RuntimeTypeOf { expr: value, .. }
+        | Expression::SafeCast { expr: value, .. }
+        | Expression::ArrayLen { array: value, .. }
+        | Expression::Splat { expr: value, .. }
+        | Expression::UnsafeBlock { body: value, .. }
+        | Expression::ComptimeBlock { body: value, .. }
+        | Expression::Defer { body: value, .. }
+        | Expression::Ref { expr: value, .. }
+        | Expression::Deref { expr: value, .. }
+        | Expression::Not { expr: value, .. }
+        | Expression::FieldAccess { target: value, .. }
+        | Expression::TupleAccess { tuple: value, .. }
+        | Expression::Try { expr: value, .. } => visit(value, reachable),
+        // a lambda runs whenever it is called, so its body can be reached
+        // even when the lambda is defined after a return
+        Expression::Lambda { body, .. } => visit(body, true),
+        Expression::FunCall { ident, arguments, .. } => {
+            visit(ident, reachable);
+            arguments.iter_mut().for_each(|arg| visit(arg, reachable));
+        }
+        Expression::BinaryOp { lhs, rhs, .. }
+        | Expression::Index {
+            target: lhs,
+            index: rhs,
+            ..
+        }
+        | Expression::Elvis {
+            condition: lhs,
+            default: rhs,
+            ..
+        }
+        | Expression::Let {
+            value: lhs, body: rhs, ..
+        } => {
+            visit(lhs, reachable);
+            visit(rhs, reachable);
+        }
+        Expression::InlineAsm { outputs, inputs, .. } => outputs
+            .iter_mut()
+            .chain(inputs.iter_mut())
+            .for_each(|operand| visit(&mut operand.expr, reachable)),
+        Expression::Interpolated { expressions, .. }
+        | Expression::Array {
+            elements: expressions, ..
+        }
+        | Expression::Tuple {
+            elements: expressions, ..
+        } => {
+            expressions.iter_mut().for_each(|expr| visit(expr, reachable));
+        }
+        Expression::Bool { .. }
+        | Expression::Sizeof { .. }
+        | Expression::Macro { .. }
+        | Expression::StringLiteral { .. }
+        | Expression::Ident { .. }
+        | Expression::Path { .. }
+        | Expression::UintLiteral { .. }
+        | Expression::FloatLiteral { .. }
+        | Expression::IntLiteral { .. } => {}
+    }
+}
+
+fn visit_block(expressions: &mut [Expression<'_>], mut reachable: bool) {
+    for expr in expressions {
+        visit(expr, reachable);
+        reachable &= !is_diverging(expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::expression::parse_expression;
+    use crate::Parser;
+
+    fn collect(expr: &Expression<'_>, marks: &mut Vec<Option<bool>>) {
+        match expr {
+            Expression::Block {
+                expressions,
+                tail,
+                reachable,
+                ..
+            } => {
+                marks.push(*reachable);
+                expressions
+                    .iter()
+                    .chain(tail.as_deref())
+                    .for_each(|expr| collect(expr, marks));
+            }
+            Expression::If { truthy, falsy, .. } => {
+                collect(truthy, marks);
+                falsy.iter().for_each(|branch| collect(branch, marks));
+            }
+            Expression::Var { value, .. } | Expression::Return { value, .. } => collect(value, marks),
+            _ => {}
+        }
+    }
+
+    fn marks(source: &str) -> Vec<Option<bool>> {
+        let mut lexer = Lexer::new(source);
+        let mut expr = match parse_expression(&mut lexer, true) {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+        analyze_reachability(&mut expr);
+
+        let mut marks = vec![];
+        collect(&expr, &mut marks);
+        marks
+    }
+
+    #[test]
+    fn blocks_after_a_return_are_unreachable() {
+        let source = r#"{
+            { const a = 1; }
+            return 1;
+            if a == 1 { { a } } else { b }
+        }"#;
+
+        assert_eq!(
+            marks(source),
+            [Some(true), Some(true), Some(false), Some(false), Some(false)]
+        );
+    }
+
+    #[test]
+    fn ifs_terminate_when_every_branch_does() {
+        let source = r#"{
+            if a == 1 { return 1; } else { return 2; }
+            var b = { 1 };
+        }"#;
+        assert_eq!(marks(source), [Some(true), Some(true), Some(true), Some(false)]);
+
+        let source = r#"{
+            if a == 1 { return 1; }
+            var b = { 1 };
+        }"#;
+        assert_eq!(marks(source), [Some(true), Some(true), Some(true)]);
+    }
+
+    #[test]
+    fn function_bodies_are_analyzed() {
+        let source = "fun main() { return 1; var a = { 2 }; } impl A { fun b() { var c = { 3 }; } }";
+        let mut statements = Parser::new(source, Lexer::new(source)).parse().unwrap();
+        analyze_reachability_statements(&mut statements);
+
+        let mut marks = vec![];
+        for statement in statements.iter().flat_map(Statement::functions) {
+            if let Statement::Fun { body, .. } = statement {
+                body.iter().for_each(|expr| collect(expr, &mut marks));
+            }
+        }
+        assert_eq!(marks, [Some(false), Some(true)]);
+    }
+}
@@ -0,0 +1,365 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use crate::{Expression, Statement, TypeExpr};
+
+/// specializes generic functions for the type arguments they are used with,
+/// like the ones collected from the calls a type checker went through. each
+/// instantiation names a function declared at the top of `statements`, and
+/// gives a type for every one of its generic parameters, in order.
+///
+/// every instantiation becomes a copy of its function without generic
+/// parameters, where each of them is replaced by its type, and whose name
+/// has the types appended to it, like `foo_i32_bool` for `foo<i32, bool>`.
+/// the same instantiation is only specialized once, and the ones naming a
+/// function that doesn't exist, or with the wrong number of types, are
+/// skipped
+pub fn reify_generics<'ast>(
+    statements: &[Statement<'ast>],
+    instantiations: &[(&str, Vec<TypeExpr<'ast>>)],
+) -> Vec<Statement<'ast>> {
+    let functions = statements
+        .iter()
+        .filter_map(|statement| match statement {
+            Statement::Fun { name, generics, .. } if !generics.is_empty() => Some((name.as_ref(), statement)),
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut reified = HashSet::new();
+    let mut specialized = vec![];
+    for (name, arguments) in instantiations {
+        let Some(Statement::Fun { generics, .. }) = functions.get(name) else {
+            continue;
+        };
+        if generics.len() != arguments.len() {
+            continue;
+        }
+
+        let mangled = mangle(name, arguments);
+        if !reified.insert(mangled.clone()) {
+            continue;
+        }
+
+        let substitutions = generics
+            .iter()
+            .map(|param| param.name.as_ref())
+            .zip(arguments)
+            .collect();
+        specialized.push(specialize(functions[name], mangled, &Reifier { substitutions }));
+    }
+
+    specialized
+}
+
+/// `name` followed by every type argument, joined by underscores
+fn mangle(name: &str, arguments: &[TypeExpr<'_>]) -> String {
+    std::iter::once(name.to_string())
+        .chain(arguments.iter().map(mangle_type))
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// a type written with only the characters a name can have, like
+/// `Option_i32` for `Option<i32>` or `ptr_mut_u8` for `*mut u8`
+fn mangle_type(typ: &TypeExpr<'_>) -> String {
+    let list = |types: &[TypeExpr<'_>]| types.iter().map(mangle_type).collect::<Vec<_>>().join("_");
+    match typ {
+        TypeExpr::Named { name, .. } => name.replace("::", "_"),
+        TypeExpr::Generic { name, arguments, .. } => format!("{}_{}", name.replace("::", "_"), list(arguments)),
+        TypeExpr::Fn {
+            parameters, returns, ..
+        } => match returns {
+            Some(returns) => format!("fn_{}_returns_{}", list(parameters), mangle_type(returns)),
+            None => format!("fn_{}", list(parameters)),
+        },
+        TypeExpr::Array { element, length, .. } => format!("array_{}_{length}", mangle_type(element)),
+        TypeExpr::Tuple { elements, .. } => format!("tuple_{}", list(elements)),
+        TypeExpr::Ptr { mutable, pointee, .. } => match mutable {
+            true => format!("ptr_mut_{}", mangle_type(pointee)),
+            false => format!("ptr_const_{}", mangle_type(pointee)),
+        },
+        TypeExpr::ImplTrait { bound, .. } => format!("impl_{}", mangle_type(bound)),
+        TypeExpr::Ref {
+            mutable,
+            lifetime,
+            referent,
+            ..
+        } => {
+            let lifetime = lifetime.name().map(|name| format!("{name}_")).unwrap_or_default();
+            match mutable {
+                true => format!("ref_{lifetime}mut_{}", mangle_type(referent)),
+                false => format!("ref_{lifetime}{}", mangle_type(referent)),
+            }
+        }
+        TypeExpr::FnTrait {
+            name,
+            parameters,
+            returns,
+            ..
+        } => match returns {
+            Some(returns) => format!("{name}_{}_returns_{}", list(parameters), mangle_type(returns)),
+            None => format!("{name}_{}", list(parameters)),
+        },
+        TypeExpr::Lifetime { name, .. } => format!("lifetime_{name}"),
+        TypeExpr::Never { .. } => "never".to_string(),
+    }
+}
+
+/// a copy of `function` named `name`, with every generic parameter replaced
+/// by the type `reifier` substitutes for it
+fn specialize<'ast>(function: &Statement<'ast>, name: String, reifier: &Reifier<'_, 'ast>) -> Statement<'ast> {
+    let mut function = function.clone();
+    if let Statement::Fun {
+        name: fun_name,
+        generics,
+        arguments,
+        body,
+        return_type,
+        where_clause,
+        ..
+    } = &mut function
+    {
+        *fun_name = Cow::Owned(name);
+        generics.clear();
+        where_clause.clear();
+        for argument in arguments {
+            if let Statement::FunArgument { arg_type, .. } = argument {
+                reifier.substitute(arg_type);
+            }
+        }
+        if let Some(return_type) = return_type {
+            reifier.substitute(return_type);
+        }
+        body.iter_mut().for_each(|expr| reifier.visit(expr));
+    }
+    function
+}
+
+struct Reifier<'a, 'ast> {
+    /// the type each generic parameter is replaced by
+    substitutions: HashMap<&'a str, &'a TypeExpr<'ast>>,
+}
+
+impl<'ast> Reifier<'_, 'ast> {
+    fn substitute(&self, typ: &mut TypeExpr<'ast>) {
+        match typ {
+            TypeExpr::Named { name, .. } => {
+                if let Some(concrete) = self.substitutions.get(name.as_ref()) {
+                    *typ = (*concrete).clone();
+                }
+            }
+            TypeExpr::Fn {
+                parameters, returns, ..
+            }
+            | TypeExpr::FnTrait {
+                parameters, returns, ..
+            } => parameters
+                .iter_mut()
+                .chain(returns.as_deref_mut())
+                .for_each(|typ| self.substitute(typ)),
+            TypeExpr::Tuple { elements: types, .. } | TypeExpr::Generic { arguments: types, .. } => {
+                types.iter_mut().for_each(|typ| self.substitute(typ))
+            }
+            TypeExpr::Array { element: inner, .. }
+            | TypeExpr::Ptr { pointee: inner, .. }
+            | TypeExpr::ImplTrait { bound: inner, .. }
+            | TypeExpr::Ref { referent: inner, .. } => self.substitute(inner),
+            TypeExpr::Lifetime { .. } | TypeExpr::Never { .. } => {}
+        }
+    }
+
+    fn visit(&self, expr: &mut Expression<'ast>) {
+        match expr {
+            Expression::Var { typ, value, .. } => {
+                if let Some(typ) = typ {
+                    self.substitute(typ);
+                }
+                self.visit(value);
+            }
+            Expression::Sizeof { typ, .. } => self.substitute(typ),
+            Expression::InlineAsm { outputs, inputs, .. } => outputs
+                .iter_mut()
+                .chain(inputs)
+                .for_each(|operand| self.visit(&mut operand.expr)),
+            Expression::SafeCast { expr, typ, .. } => {
+                self.visit(expr);
+                self.substitute(typ);
+            }
+            Expression::Lambda {
+                parameter_types,
+                return_type,
+                body,
+                ..
+            } => {
+                parameter_types
+                    .iter_mut()
+                    .flatten()
+                    .for_each(|typ| self.substitute(typ));
+                if let Some(return_type) = return_type {
+                    self.substitute(return_type);
+                }
+                self.visit(body);
+            }
+            Expression::Assert { condition, message, .. } => {
+                self.visit(condition);
+                message.iter_mut().for_each(|message| self.visit(message));
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                self.visit(condition);
+                self.visit(truthy);
+                falsy.iter_mut().for_each(|branch| self.visit(branch));
+            }
+            Expression::Match { scrutinee, arms, .. } => {
+                self.visit(scrutinee);
+                arms.iter_mut().for_each(|arm| self.visit(&mut arm.body));
+            }
+            Expression::Yield { value, .. } => value.iter_mut().for_each(|value| self.visit(value)),
+            Expression::EnumVariant { kind, .. } => kind.values_mut().for_each(|value| self.visit(value)),
+            Expression::DoBlock { steps, .. } => steps.iter_mut().for_each(|step| self.visit(step.expr_mut())),
+            Expression::Assign { value, .. }
+            | Expression::CompoundAssign { value, .. }
+            | Expression::Return { value, .. }
+            | Expression::Await { expr: value, .. }
+            | Expression::RuntimeTypeOf { expr: value, .. }
+            | Expression::ArrayLen { array: value, .. }
+            | Expression::Splat { expr: value, .. }
+            | Expression::UnsafeBlock { body: value, .. }
+            | Expression::ComptimeBlock { body: value, .. }
+            | Expression::Defer { body: value, .. }
+            | Expression::Ref { expr: value, .. }
+            | Expression::Deref { expr: value, .. }
+            | Expression::Not { expr: value, .. }
+            | Expression::FieldAccess { target: value, .. }
+            | Expression::TupleAccess { tuple: value, .. }
+            | Expression::Try { expr: value, .. } => self.visit(value),
+            Expression::FunCall { ident, arguments, .. } => {
+                self.visit(ident);
+                arguments.iter_mut().for_each(|argument| self.visit(argument));
+            }
+            Expression::BinaryOp { lhs, rhs, .. }
+            | Expression::Index {
+                target: lhs,
+                index: rhs,
+                ..
+            }
+            | Expression::Elvis {
+                condition: lhs,
+                default: rhs,
+                ..
+            }
+            | Expression::Let {
+                value: lhs, body: rhs, ..
+            } => {
+                self.visit(lhs);
+                self.visit(rhs);
+            }
+            Expression::Block { expressions, tail, .. } => expressions
+                .iter_mut()
+                .chain(tail.as_deref_mut())
+                .for_each(|expr| self.visit(expr)),
+            Expression::Interpolated { expressions, .. }
+            | Expression::Array {
+                elements: expressions, ..
+            }
+            | Expression::Tuple {
+                elements: expressions, ..
+            } => expressions.iter_mut().for_each(|expr| self.visit(expr)),
+            Expression::Bool { .. }
+            | Expression::Macro { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::Ident { .. }
+            | Expression::Path { .. }
+            | Expression::UintLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::IntLiteral { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::sexpr::statements_to_sexpr;
+    use crate::types::parse_type_expression;
+    use crate::Parser;
+
+    fn parse(source: &str) -> Vec<Statement<'_>> {
+        match Parser::new(source, Lexer::new(source)).parse() {
+            Ok(statements) => statements,
+            Err(e) => panic!("{e:?}"),
+        }
+    }
+
+    /// the instantiations written like `foo<i32, bool>`
+    fn instantiations(written: &[&'static str]) -> Vec<(&'static str, Vec<TypeExpr<'static>>)> {
+        written
+            .iter()
+            .map(|written| {
+                let (name, arguments) = written.split_once('<').unwrap();
+                let arguments = arguments.strip_suffix('>').unwrap();
+                let mut lexer = Lexer::new(arguments);
+                let mut types = vec![parse_type_expression(&mut lexer).unwrap()];
+                while lexer.next().is_some() {
+                    types.push(parse_type_expression(&mut lexer).unwrap());
+                }
+                (name, types)
+            })
+            .collect()
+    }
+
+    fn reify(source: &str, written: &[&'static str]) -> String {
+        statements_to_sexpr(&reify_generics(&parse(source), &instantiations(written)))
+    }
+
+    #[test]
+    fn generic_parameters_are_substituted() {
+        let source = "fun pair<A, B: Show>(a: A, b: B) => (A, B) {
+            var first: A = a;
+            const f = fun(x: Option<B>) => B { x.value };
+            (first, b)
+        }";
+        let expected = "fun pair_i32_bool(a: i32, b: bool) => (i32, bool) {
+            var first: i32 = a;
+            const f = fun(x: Option<bool>) => bool { x.value };
+            (first, b)
+        }";
+        assert_eq!(
+            reify(source, &["pair<i32, bool>"]),
+            statements_to_sexpr(&parse(expected))
+        );
+    }
+
+    #[test]
+    fn every_instantiation_is_a_copy() {
+        let source = "fun id<T>(x: T) => T { x } fun main() { id(1); }";
+        let specialized = reify_generics(
+            &parse(source),
+            &instantiations(&["id<i32>", "id<*mut [u8; 4]>", "id<i32>", "id<Vec<String>>"]),
+        );
+        let names = specialized
+            .iter()
+            .map(|function| match function {
+                Statement::Fun { name, generics, .. } => {
+                    assert!(generics.is_empty());
+                    name.to_string()
+                }
+                _ => panic!("expected a function, found {function:?}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["id_i32", "id_ptr_mut_array_u8_4", "id_Vec_String"]);
+    }
+
+    #[test]
+    fn instantiations_that_dont_fit_are_skipped() {
+        let source = "fun id<T>(x: T) => T { x } fun main() {}";
+        assert_eq!(reify(source, &["main<i32>", "missing<i32>", "id<i32, bool>"]), "");
+    }
+}
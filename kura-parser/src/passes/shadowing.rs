@@ -0,0 +1,309 @@
+use kura_lexer::token::Location;
+use miette::{Diagnostic, Report, SourceSpan};
+use thiserror::Error;
+
+use crate::resolve::{BindingId, SymbolTable};
+use crate::{DoStep, Expression, Statement};
+
+/// argument of `#[allow(...)]` that silences this lint on a binding
+const ALLOW_SHADOW: &str = "shadow";
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("`{name}` shadows a binding from an enclosing scope")]
+#[diagnostic(
+    code(kura::shadowed_variable),
+    severity(Warning),
+    help("if this is intentional, add `#[allow(shadow)]` to the inner binding")
+)]
+pub struct ShadowedVariable {
+    pub name: String,
+    #[label("`{name}` is first declared here")]
+    pub original: SourceSpan,
+    #[label("and shadowed here")]
+    pub shadowing: SourceSpan,
+}
+
+/// reports variables declared with a name that is already bound in an
+/// enclosing scope, function arguments included. declaring the same name
+/// twice in one scope is not reported, as that is how a value is rebound
+pub fn lint_shadowing(statements: &[Statement<'_>], symbols: &SymbolTable<'_>) -> Vec<Report> {
+    let mut linter = Linter {
+        symbols,
+        scopes: vec![],
+        reports: vec![],
+    };
+
+    for statement in statements.iter().flat_map(Statement::functions) {
+        if let Statement::Fun { arguments, body, .. } = statement {
+            let arguments = arguments
+                .iter()
+                .filter_map(|argument| symbols.declaration(argument.location()))
+                .collect();
+
+            linter.scopes.push(arguments);
+            linter.block(body, None);
+            linter.scopes.pop();
+        }
+    }
+
+    linter.reports
+}
+
+struct Linter<'sym, 'ast> {
+    symbols: &'sym SymbolTable<'ast>,
+    scopes: Vec<Vec<BindingId>>,
+    reports: Vec<Report>,
+}
+
+impl Linter<'_, '_> {
+    fn block(&mut self, expressions: &[Expression<'_>], tail: Option<&Expression<'_>>) {
+        self.scopes.push(vec![]);
+        expressions.iter().chain(tail).for_each(|expr| self.visit(expr));
+        self.scopes.pop();
+    }
+
+    /// binding named `name` in any scope but the innermost one
+    fn enclosing(&self, name: &str) -> Option<BindingId> {
+        let (_, enclosing) = self.scopes.split_last()?;
+        enclosing
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|id| self.symbols.binding(**id).name == name)
+            .copied()
+    }
+
+    /// adds the binding declared at `location` to the innermost scope,
+    /// reporting it if it shadows another one
+    fn declare(&mut self, location: Location, allowed: bool) {
+        let Some(id) = self.symbols.declaration(location) else {
+            return;
+        };
+        let binding = self.symbols.binding(id);
+
+        if let Some(original) = self.enclosing(&binding.name).filter(|_| !allowed) {
+            self.reports.push(Report::new(ShadowedVariable {
+                name: binding.name.to_string(),
+                original: self.symbols.binding(original).location.into(),
+                shadowing: binding.location.into(),
+            }));
+        }
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(id);
+        }
+    }
+
+    fn visit(&mut self, expr: &Expression<'_>) {
+        match expr {
+            Expression::Var {
+                value,
+                attributes,
+                location,
+                ..
+            } => {
+                self.visit(value);
+                let allowed = attributes.iter().any(|attribute| attribute.allows(ALLOW_SHADOW));
+                self.declare(*location, allowed);
+            }
+            Expression::Let {
+                value, body, location, ..
+            } => {
+                self.visit(value);
+                self.scopes.push(vec![]);
+                self.declare(*location, false);
+                self.visit(body);
+                self.scopes.pop();
+            }
+            Expression::Match { scrutinee, arms, .. } => {
+                self.visit(scrutinee);
+                for arm in arms {
+                    self.scopes.push(vec![]);
+                    for (_, location) in arm.pattern.bindings() {
+                        self.declare(location, false);
+                    }
+                    self.visit(&arm.body);
+                    self.scopes.pop();
+                }
+            }
+            Expression::Block { expressions, tail, .. } => self.block(expressions, tail.as_deref()),
+            Expression::Assert { condition, message, .. } => {
+                self.visit(condition);
+                message.iter().for_each(|message| self.visit(message));
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                self.visit(condition);
+                self.visit(truthy);
+                falsy.iter().for_each(|branch| self.visit(branch));
+            }
+            Expression::FunCall { ident, arguments, .. } => {
+                self.visit(ident);
+                arguments.iter().for_each(|argument| self.visit(argument));
+            }
+            Expression::Assign { ident, value, .. } | Expression::CompoundAssign { ident, value, .. } => {
+                self.visit(value);
+                self.visit(ident);
+            }
+            Expression::BinaryOp { lhs, rhs, .. }
+            | Expression::Index {
+                target: lhs,
+                index: rhs,
+                ..
+            }
+            | Expression::Elvis {
+                condition: lhs,
+                default: rhs,
+                ..
+            } => {
+                self.visit(lhs);
+                self.visit(rhs);
+            }
+            Expression::Yield { value, .. } => value.iter().for_each(|value| self.visit(value)),
+            Expression::EnumVariant { kind, .. } => kind.values().for_each(|value| self.visit(value)),
+            Expression::DoBlock { steps, .. } => {
+                self.scopes.push(vec![]);
+                for step in steps {
+                    self.visit(step.expr());
+                    if let DoStep::Bind { location, .. } = step {
+                        self.declare(*location, false);
+                    }
+                }
+                self.scopes.pop();
+            }
+            Expression::Return { value, .. }
+            | Expression::Await { expr: value, .. }
+            | Expression::RuntimeTypeOf { expr: value, .. }
+            | Expression::SafeCast { expr: value, .. }
+            | Expression::ArrayLen { array: value, .. }
+            | Expression::Splat { expr: value, .. }
+            | Expression::UnsafeBlock { body: value, .. }
+            | Expression::ComptimeBlock { body: value, .. }
+            | Expression::Defer { body: value, .. }
+            | Expression::Ref { expr: value, .. }
+            | Expression::Deref { expr: value, .. }
+            | Expression::Not { expr: value, .. }
+            | Expression::FieldAccess { target: value, .. }
+            | Expression::TupleAccess { tuple: value, .. }
+            | Expression::Try { expr: value, .. }
+            | Expression::Lambda { body: value, .. } => self.visit(value),
+            Expression::InlineAsm { outputs, inputs, .. } => outputs
+                .iter()
+                .chain(inputs.iter())
+                .for_each(|operand| self.visit(&operand.expr)),
+            Expression::Interpolated { expressions, .. }
+            | Expression::Array {
+                elements: expressions, ..
+            }
+            | Expression::Tuple {
+                elements: expressions, ..
+            } => expressions.iter().for_each(|expr| self.visit(expr)),
+            Expression::Bool { .. }
+            | Expression::Sizeof { .. }
+            | Expression::Ident { .. }
+            | Expression::Path { .. }
+            | Expression::Macro { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::UintLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::IntLiteral { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::Parser;
+
+    fn lint(source: &str) -> Vec<(String, &str, &str)> {
+        let statements = match Parser::new(source, Lexer::new(source)).parse() {
+            Ok(statements) => statements,
+            Err(e) => panic!("{e:?}"),
+        };
+        let symbols = SymbolTable::resolve_statements(&statements);
+
+        let span = |span: SourceSpan| &source[span.offset()..span.offset() + span.len()];
+        lint_shadowing(&statements, &symbols)
+            .iter()
+            .map(|report| {
+                let shadowed = report.downcast_ref::<ShadowedVariable>().unwrap();
+                (shadowed.name.clone(), span(shadowed.original), span(shadowed.shadowing))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nested_blocks_reusing_a_name() {
+        let source = r#"fun main() {
+            const value = 1;
+            {
+                const value = 2;
+                {
+                    var value = 3;
+                    print(value);
+                }
+            }
+            const other = { const value = 4; value };
+        }"#;
+
+        assert_eq!(
+            lint(source),
+            [
+                ("value".to_string(), "const value = 1", "const value = 2"),
+                ("value".to_string(), "const value = 2", "var value = 3"),
+                ("value".to_string(), "const value = 1", "const value = 4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn arguments_and_branches_are_enclosing_scopes() {
+        let source = r#"fun main(count: i32) {
+            const count = 1;
+            if count == 1 {
+                const count = 2;
+            }
+        }"#;
+
+        let reports = lint(source);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].1, "count: i32");
+        assert_eq!(reports[1].1, "const count = 1");
+    }
+
+    #[test]
+    fn rebinding_in_the_same_scope_and_siblings_are_fine() {
+        let source = r#"fun main() {
+            const value = 1;
+            const value = value + 1;
+            { const inner = 1; }
+            { const inner = 2; }
+        }"#;
+
+        assert!(lint(source).is_empty());
+    }
+
+    #[test]
+    fn allow_shadow_suppresses_the_warning() {
+        let source = r#"fun main() {
+            const value = 1;
+            {
+                #[allow(shadow)]
+                const value = 2;
+                #[allow(unused, shadow)] const value = 3;
+                #[allow(unused)]
+                const value = 4;
+            }
+        }"#;
+
+        let reports = lint(source);
+        assert_eq!(reports, [("value".to_string(), "const value = 1", "const value = 4")]);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::resolve::SymbolTable;
+use crate::{Expression, Statement};
+
+/// sets `tail_call` on every call a function makes to itself in tail
+/// position, where the value of the call is what the function returns. that
+/// is the last expression of its body, the value of a `return`, and from
+/// there on the tail of a block and every branch of an `if` or a `match`.
+///
+/// only calls through the bare name of the function are marked, and not
+/// when a variable with the same name hides it. methods are called through
+/// what they are called on, so none of their calls are marked. the body of a lambda is a
+/// function of its own, so nothing in it is a tail call of the one around it
+pub fn mark_tail_calls(statements: &mut [Statement<'_>]) {
+    let symbols = SymbolTable::resolve_statements(statements);
+
+    for statement in statements {
+        if let Statement::Fun { name, body, .. } = statement {
+            let marker = Marker {
+                name,
+                symbols: &symbols,
+            };
+            if let Some((tail, body)) = body.split_last_mut() {
+                body.iter_mut().for_each(|expr| marker.visit(expr));
+                marker.tail(tail);
+            }
+        }
+    }
+}
+
+struct Marker<'a, 'ast> {
+    name: &'a str,
+    symbols: &'a SymbolTable<'ast>,
+}
+
+impl Marker<'_, '_> {
+    /// marks `expr` when it is a call to the function, as its value is the
+    /// one the function returns
+    fn tail(&self, expr: &mut Expression<'_>) {
+        match expr {
+            Expression::FunCall {
+                ident,
+                arguments,
+                tail_call,
+                ..
+            } => {
+                arguments.iter_mut().for_each(|argument| self.visit(argument));
+                *tail_call = matches!(
+                    ident.as_ref(),
+                    Expression::Ident { name, location }
+                        if name == self.name && self.symbols.resolution(*location).is_none()
+                );
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                self.visit(condition);
+                self.tail(truthy);
+                falsy.iter_mut().for_each(|branch| self.tail(branch));
+            }
+            Expression::Match { scrutinee, arms, .. } => {
+                self.visit(scrutinee);
+                arms.iter_mut().for_each(|arm| self.tail(&mut arm.body));
+            }
+            Expression::Block { expressions, tail, .. } => {
+                expressions.iter_mut().for_each(|expr| self.visit(expr));
+                tail.iter_mut().for_each(|tail| self.tail(tail));
+            }
+            Expression::Let { value, body, .. } => {
+                self.visit(value);
+                self.tail(body);
+            }
+            Expression::UnsafeBlock { body, .. } => self.tail(body),
+            expr => self.visit(expr),
+        }
+    }
+
+    /// looks for a `return` in `expr`, whose value is in tail position no
+    /// matter where it is
+    fn visit(&self, expr: &mut Expression<'_>) {
+        match expr {
+            Expression::Return { value, .. } => self.tail(value),
+            Expression::Assert { condition, message, .. } => {
+                self.visit(condition);
+                message.iter_mut().for_each(|message| self.visit(message));
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                self.visit(condition);
+                self.visit(truthy);
+                falsy.iter_mut().for_each(|branch| self.visit(branch));
+            }
+            Expression::Match { scrutinee, arms, .. } => {
+                self.visit(scrutinee);
+                arms.iter_mut().for_each(|arm| self.visit(&mut arm.body));
+            }
+            Expression::Yield { value, .. } => value.iter_mut().for_each(|value| self.visit(value)),
+            Expression::EnumVariant { kind, .. } => kind.values_mut().for_each(|value| self.visit(value)),
+            Expression::DoBlock { steps, .. } => steps.iter_mut().for_each(|step| self.visit(step.expr_mut())),
+            Expression::Var { value, .. }
+            | Expression::Assign { value, .. }
+            | Expression::CompoundAssign { value, .. }
+            | Expression::Await { expr: value, .. }
+            | Expression::RuntimeTypeOf { expr: value, .. }
+            | Expression::SafeCast { expr: value, .. }
+            | Expression::ArrayLen { array: value, .. }
+            | Expression::Splat { expr: value, .. }
+            | Expression::UnsafeBlock { body: value, .. }
+            | Expression::ComptimeBlock { body: value, .. }
+            | Expression::Defer { body: value, .. }
+            | Expression::Ref { expr: value, .. }
+            | Expression::Deref { expr: value, .. }
+            | Expression::Not { expr: value, .. }
+            | Expression::FieldAccess { target: value, .. }
+            | Expression::TupleAccess { tuple: value, .. }
+            | Expression::Try { expr: value, .. } => self.visit(value),
+            Expression::FunCall { ident, arguments, .. } => {
+                self.visit(ident);
+                arguments.iter_mut().for_each(|argument| self.visit(argument));
+            }
+            Expression::BinaryOp { lhs, rhs, .. }
+            | Expression::Index {
+                target: lhs,
+                index: rhs,
+                ..
+            }
+            | Expression::Elvis {
+                condition: lhs,
+                default: rhs,
+                ..
+            }
+            | Expression::Let {
+                value: lhs, body: rhs, ..
+            } => {
+                self.visit(lhs);
+                self.visit(rhs);
+            }
+            Expression::Block { expressions, tail, .. } => expressions
+                .iter_mut()
+                .chain(tail.as_deref_mut())
+                .for_each(|expr| self.visit(expr)),
+            Expression::InlineAsm { outputs, inputs, .. } => outputs
+                .iter_mut()
+                .chain(inputs.iter_mut())
+                .for_each(|operand| self.visit(&mut operand.expr)),
+            Expression::Interpolated { expressions, .. }
+            | Expression::Array {
+                elements: expressions, ..
+            }
+            | Expression::Tuple {
+                elements: expressions, ..
+            } => expressions.iter_mut().for_each(|expr| self.visit(expr)),
+            Expression::Lambda { .. }
+            | Expression::Bool { .. }
+            | Expression::Sizeof { .. }
+            | Expression::Macro { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::Ident { .. }
+            | Expression::Path { .. }
+            | Expression::UintLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::IntLiteral { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::sexpr::statements_to_sexpr;
+    use crate::Parser;
+
+    fn mark(source: &str) -> String {
+        let mut statements = match Parser::new(source, Lexer::new(source)).parse() {
+            Ok(statements) => statements,
+            Err(e) => panic!("{e:?}"),
+        };
+        mark_tail_calls(&mut statements);
+        statements_to_sexpr(&statements)
+    }
+
+    #[test]
+    fn recursive_calls_in_tail_position_are_marked() {
+        let source = "fun sum(n: u64, acc: u64) => u64 {
+            if n == 0 { return acc; }
+            sum(n - 1, acc + n);
+        }";
+        assert_eq!(
+            mark(source),
+            "(fun sum (args (arg n (type-name u64)) (arg acc (type-name u64))) (returns (type-name u64)) \
+             (if (binary-op == (ident n) (uint-literal 0)) (block (return (ident acc)))) \
+             (tail-call (ident sum) (binary-op - (ident n) (uint-literal 1)) (binary-op + (ident acc) (ident n))))\n"
+        );
+
+        let source = "fun count(n: u64) => u64 {
+            match n { 0 => count(1), _ => { log(n); return count(n - 1); } }
+        }";
+        assert_eq!(mark(source).matches("(tail-call (ident count)").count(), 2);
+    }
+
+    #[test]
+    fn other_calls_are_left_alone() {
+        let source = "fun sum(n: u64) => u64 {
+            const f = fun() { return sum(1); };
+            sum(n);
+            return sum(n) + 1;
+        }";
+        let marked = mark(source);
+        assert!(!marked.contains("tail-call"), "{marked}");
+
+        // only the outer call is in tail position
+        assert_eq!(
+            mark("fun sum(n: u64) => u64 { sum(sum(n)); }")
+                .matches("tail-call")
+                .count(),
+            1
+        );
+
+        let source = "impl Counter { fun count(n: u64) => u64 { return count(n); } }";
+        assert!(!mark(source).contains("tail-call"));
+
+        // the argument hides the function
+        assert!(!mark("fun apply(apply: u64) => u64 { apply(1); }").contains("tail-call"));
+    }
+}
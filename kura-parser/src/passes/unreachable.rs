@@ -0,0 +1,286 @@
+use miette::{Diagnostic, Report, SourceSpan};
+use thiserror::Error;
+
+use crate::{Expression, Statement};
+
+/// name of the macro aborting the program, which never returns
+pub const PANIC_MACRO: &str = "panic";
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("unreachable code")]
+#[diagnostic(code(kura::unreachable_code), severity(Warning))]
+pub struct UnreachableCode {
+    #[label("this is never reached")]
+    pub unreachable: SourceSpan,
+    #[label("{reason}")]
+    pub cause: SourceSpan,
+    pub reason: &'static str,
+}
+
+/// reports every expression that can never run because something before it
+/// on the same block always returns
+pub fn unreachable_code(expr: &Expression<'_>) -> Vec<Report> {
+    let mut reports = vec![];
+    visit(expr, &mut reports);
+    reports
+}
+
+/// same as `unreachable_code`, for the body of every function in a program
+pub fn unreachable_code_statements(statements: &[Statement<'_>]) -> Vec<Report> {
+    let mut reports = vec![];
+
+    for statement in statements.iter().flat_map(Statement::functions) {
+        if let Statement::Fun { body, .. } = statement {
+            visit_block(body, None, &mut reports);
+        }
+    }
+
+    reports
+}
+
+fn visit(expr: &Expression<'_>, reports: &mut Vec<Report>) {
+    match expr {
+        Expression::Block { expressions, tail, .. } => visit_block(expressions, tail.as_deref(), reports),
+        Expression::Assert { condition, message, .. } => {
+            visit(condition, reports);
+            message.iter().for_each(|message| visit(message, reports));
+        }
+        Expression::If {
+            condition,
+            truthy,
+            falsy,
+            ..
+        } => {
+            visit(condition, reports);
+            visit(truthy, reports);
+            falsy.iter().for_each(|branch| visit(branch, reports));
+        }
+        Expression::Match { scrutinee, arms, .. } => {
+            visit(scrutinee, reports);
+            arms.iter().for_each(|arm| visit(&arm.body, reports));
+        }
+        Expression::Yield { value, .. } => value.iter().for_each(|value| visit(value, reports)),
+        Expression::EnumVariant { kind, .. } => kind.values().for_each(|value| visit(value, reports)),
+        Expression::DoBlock { steps, .. } => steps.iter().for_each(|step| visit(step.expr(), reports)),
+        Expression::Var { value, .. }
+        | Expression::Assign { value, .. }
+        | Expression::CompoundAssign { value, .. }
+        | Expression::Return { value, .. }
+        | Expression::Await { expr: value, .. }
+        | Expression::RuntimeTypeOf { expr: value, .. }
+        | Expression::SafeCast { expr: value, .. }
+        | Expression::ArrayLen { array: value, .. }
+        | Expression::Splat { expr: value, .. }
+        | Expression::UnsafeBlock { body: value, .. }
+        | Expression::ComptimeBlock { body: value, .. }
+        | Expression::Defer { body: value, .. }
+        | Expression::Ref { expr: value, .. }
+        | Expression::Deref { expr: value, .. }
+        | Expression::Not { expr: value, .. }
+        | Expression::FieldAccess { target: value, .. }
+        | Expression::TupleAccess { tuple: value, .. }
+        | Expression::Try { expr: value, .. }
+        | Expression::Lambda { body: value, .. } => visit(value, reports),
+        Expression::FunCall { arguments, .. } => arguments.iter().for_each(|arg| visit(arg, reports)),
+        Expression::BinaryOp { lhs, rhs, .. }
+        | Expression::Index {
+            target: lhs,
+            index: rhs,
+            ..
+        }
+        | Expression::Elvis {
+            condition: lhs,
+            default: rhs,
+            ..
+        }
+        | Expression::Let {
+            value: lhs, body: rhs, ..
+        } => {
+            visit(lhs, reports);
+            visit(rhs, reports);
+        }
+        Expression::InlineAsm { outputs, inputs, .. } => outputs
+            .iter()
+            .chain(inputs.iter())
+            .for_each(|operand| visit(&operand.expr, reports)),
+        Expression::Interpolated { expressions, .. }
+        | Expression::Array {
+            elements: expressions, ..
+        }
+        | Expression::Tuple {
+            elements: expressions, ..
+        } => expressions.iter().for_each(|expr| visit(expr, reports)),
+        Expression::Bool { .. }
+        | Expression::Sizeof { .. }
+        | Expression::Macro { .. }
+        | Expression::StringLiteral { .. }
+        | Expression::Ident { .. }
+        | Expression::Path { .. }
+        | Expression::UintLiteral { .. }
+        | Expression::FloatLiteral { .. }
+        | Expression::IntLiteral { .. } => {}
+    }
+}
+
+fn visit_block(expressions: &[Expression<'_>], tail: Option<&Expression<'_>>, reports: &mut Vec<Report>) {
+    let mut expressions = expressions.iter().chain(tail).peekable();
+    while let Some(expr) = expressions.next() {
+        visit(expr, reports);
+
+        if !is_diverging(expr) {
+            continue;
+        }
+
+        // we only report the first unreachable expression, everything after
+        // it is unreachable for the same reason
+        if let Some(unreachable) = expressions.peek() {
+            let reason = match expr {
+                Expression::Return { .. } => "returns here",
+                Expression::Macro { .. } => "panics here",
+                _ => "every branch of this returns",
+            };
+
+            reports.push(Report::new(UnreachableCode {
+                unreachable: unreachable.location().into(),
+                cause: expr.location().into(),
+                reason,
+            }));
+        }
+
+        break;
+    }
+}
+
+/// whether `expr` never produces a value, skipping the rest of the
+/// enclosing block, which makes it of type `!`. that is the case of a
+/// `return` and of `panic!()`. an `if` only diverges when every branch of it
+/// does, which requires an `else`, and a `match` when every arm does. a
+/// block only diverges as one of those branches, so a `return` nested in
+/// blocks is reported once, on the block it is in
+pub fn is_diverging(expr: &Expression<'_>) -> bool {
+    match expr {
+        Expression::Return { .. } => true,
+        Expression::Macro { name, .. } => name == PANIC_MACRO,
+        Expression::If { truthy, falsy, .. } => {
+            branch_diverges(truthy) && !falsy.is_empty() && falsy.iter().all(branch_diverges)
+        }
+        Expression::Match { arms, .. } => !arms.is_empty() && arms.iter().all(|arm| branch_diverges(&arm.body)),
+        _ => false,
+    }
+}
+
+fn branch_diverges(branch: &Expression<'_>) -> bool {
+    match branch {
+        Expression::Block { expressions, tail, .. } => expressions.iter().chain(tail.as_deref()).any(is_diverging),
+        branch => is_diverging(branch),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::expression::parse_expression;
+
+    fn check(source: &str) -> Vec<Report> {
+        let mut lexer = Lexer::new(source);
+        let expr = match parse_expression(&mut lexer, true) {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+        unreachable_code(&expr)
+    }
+
+    fn spans(report: &Report) -> (SourceSpan, SourceSpan) {
+        let diagnostic = report.downcast_ref::<UnreachableCode>().unwrap();
+        (diagnostic.unreachable, diagnostic.cause)
+    }
+
+    #[test]
+    fn return_in_a_single_branch() {
+        let source = r#"{
+            if value == 1 {
+                return 1;
+            }
+            const other = 2;
+            other
+        }"#;
+
+        assert!(check(source).is_empty());
+    }
+
+    #[test]
+    fn return_in_every_branch() {
+        let source = r#"{
+            if value == 1 {
+                return 1;
+            } else if value == 2 {
+                return 2;
+            } else {
+                return 3;
+            }
+            const other = 2;
+            other
+        }"#;
+
+        let reports = check(source);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].severity(), Some(miette::Severity::Warning));
+
+        let (unreachable, cause) = spans(&reports[0]);
+        let unreachable = &source[unreachable.offset()..unreachable.offset() + unreachable.len()];
+        let cause = &source[cause.offset()..cause.offset() + cause.len()];
+
+        assert_eq!(unreachable, "const other = 2");
+        assert!(cause.starts_with("if value == 1"));
+    }
+
+    #[test]
+    fn panics_diverge() {
+        let source = r#"{
+            match value {
+                0 => panic!("zero"),
+                _ => { return 1; },
+            }
+            other
+        }"#;
+
+        let reports = check(source);
+        assert_eq!(reports.len(), 1);
+        let (unreachable, _) = spans(&reports[0]);
+        assert_eq!(
+            &source[unreachable.offset()..unreachable.offset() + unreachable.len()],
+            "other"
+        );
+
+        let source = r#"{ panic!("stop"); other }"#;
+        let reports = check(source);
+        let diagnostic = reports[0].downcast_ref::<UnreachableCode>().unwrap();
+        assert_eq!(diagnostic.reason, "panics here");
+    }
+
+    #[test]
+    fn return_nested_two_blocks_deep() {
+        let source = r#"{
+            {
+                {
+                    return 1;
+                    const dead = 2;
+                }
+            }
+            const alive = 2;
+            alive
+        }"#;
+
+        let reports = check(source);
+        assert_eq!(reports.len(), 1);
+
+        let (unreachable, cause) = spans(&reports[0]);
+        let unreachable = &source[unreachable.offset()..unreachable.offset() + unreachable.len()];
+        let cause = &source[cause.offset()..cause.offset() + cause.len()];
+
+        assert_eq!(unreachable, "const dead = 2");
+        assert_eq!(cause, "return 1;");
+    }
+}
@@ -0,0 +1,289 @@
+use miette::{Diagnostic, Report, SourceSpan};
+use thiserror::Error;
+
+use crate::resolve::{BindingId, SymbolTable};
+use crate::{DoStep, Expression, Statement};
+
+#[derive(Debug, Error, Diagnostic)]
+#[error("variable `{name}` is {usage}")]
+#[diagnostic(
+    code(kura::unused_variable),
+    severity(Warning),
+    help("if this is intentional, prefix it with an underscore: `_{name}`")
+)]
+pub struct UnusedVariable {
+    pub name: String,
+    pub usage: &'static str,
+    #[label("declared here")]
+    pub declaration: SourceSpan,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct Usage {
+    reads: usize,
+    writes: usize,
+}
+
+/// reports variables that are never read, either because they are never
+/// used at all or because they are only ever assigned to. names starting
+/// with an underscore are never reported
+pub fn lint_unused(expr: &Expression<'_>, symbols: &SymbolTable<'_>) -> Vec<Report> {
+    let mut usages = vec![Usage::default(); symbols.bindings.len()];
+    let mut declared = vec![];
+    visit(expr, symbols, &mut usages, &mut declared);
+    report(declared, &usages, symbols)
+}
+
+/// same as `lint_unused`, for the body of every function in a program
+pub fn lint_unused_statements(statements: &[Statement<'_>], symbols: &SymbolTable<'_>) -> Vec<Report> {
+    let mut usages = vec![Usage::default(); symbols.bindings.len()];
+    let mut declared = vec![];
+
+    for statement in statements.iter().flat_map(Statement::functions) {
+        if let Statement::Fun { body, .. } = statement {
+            body.iter()
+                .for_each(|expr| visit(expr, symbols, &mut usages, &mut declared));
+        }
+    }
+
+    report(declared, &usages, symbols)
+}
+
+fn report(declared: Vec<BindingId>, usages: &[Usage], symbols: &SymbolTable<'_>) -> Vec<Report> {
+    declared
+        .into_iter()
+        .filter_map(|id| {
+            let binding = symbols.binding(id);
+            let usage = usages[id.0];
+
+            if usage.reads > 0 || binding.name.starts_with('_') {
+                return None;
+            }
+
+            Some(Report::new(UnusedVariable {
+                name: binding.name.to_string(),
+                usage: match usage.writes {
+                    0 => "never used",
+                    _ => "assigned but never read",
+                },
+                declaration: binding.location.into(),
+            }))
+        })
+        .collect()
+}
+
+fn visit(expr: &Expression<'_>, symbols: &SymbolTable<'_>, usages: &mut [Usage], declared: &mut Vec<BindingId>) {
+    match expr {
+        Expression::Var { value, location, .. } => {
+            visit(value, symbols, usages, declared);
+            declared.extend(symbols.declaration(*location));
+        }
+        Expression::Let {
+            value, body, location, ..
+        } => {
+            visit(value, symbols, usages, declared);
+            declared.extend(symbols.declaration(*location));
+            visit(body, symbols, usages, declared);
+        }
+        Expression::Match { scrutinee, arms, .. } => {
+            visit(scrutinee, symbols, usages, declared);
+            for arm in arms {
+                for (_, location) in arm.pattern.bindings() {
+                    declared.extend(symbols.declaration(location));
+                }
+                visit(&arm.body, symbols, usages, declared);
+            }
+        }
+        Expression::Ident { location, .. } => {
+            if let Some(id) = symbols.resolution(*location) {
+                usages[id.0].reads += 1;
+            }
+        }
+        // like in rust, `a += 1` doesn't count as reading `a`
+        Expression::Assign { ident, value, .. } | Expression::CompoundAssign { ident, value, .. } => {
+            visit(value, symbols, usages, declared);
+            match ident.as_ref() {
+                Expression::Ident { location, .. } => {
+                    if let Some(id) = symbols.resolution(*location) {
+                        usages[id.0].writes += 1;
+                    }
+                }
+                target => visit(target, symbols, usages, declared),
+            }
+        }
+        Expression::Block { expressions, tail, .. } => expressions
+            .iter()
+            .chain(tail.as_deref())
+            .for_each(|expr| visit(expr, symbols, usages, declared)),
+        Expression::Assert { condition, message, .. } => {
+            visit(condition, symbols, usages, declared);
+            message
+                .iter()
+                .for_each(|message| visit(message, symbols, usages, declared));
+        }
+        Expression::If {
+            condition,
+            truthy,
+            falsy,
+            ..
+        } => {
+            visit(condition, symbols, usages, declared);
+            visit(truthy, symbols, usages, declared);
+            falsy.iter().for_each(|branch| visit(branch, symbols, usages, declared));
+        }
+        Expression::FunCall { ident, arguments, .. } => {
+            visit(ident, symbols, usages, declared);
+            arguments
+                .iter()
+                .for_each(|argument| visit(argument, symbols, usages, declared));
+        }
+        Expression::BinaryOp { lhs, rhs, .. }
+        | Expression::Index {
+            target: lhs,
+            index: rhs,
+            ..
+        }
+        | Expression::Elvis {
+            condition: lhs,
+            default: rhs,
+            ..
+        } => {
+            visit(lhs, symbols, usages, declared);
+            visit(rhs, symbols, usages, declared);
+        }
+        Expression::Yield { value, .. } => value.iter().for_each(|value| visit(value, symbols, usages, declared)),
+        Expression::EnumVariant { kind, .. } => kind.values().for_each(|value| visit(value, symbols, usages, declared)),
+        Expression::DoBlock { steps, .. } => {
+            for step in steps {
+                visit(step.expr(), symbols, usages, declared);
+                if let DoStep::Bind { location, .. } = step {
+                    declared.extend(symbols.declaration(*location));
+                }
+            }
+        }
+        Expression::Return { value, .. }
+        | Expression::Await { expr: value, .. }
+        | Expression::RuntimeTypeOf { expr: value, .. }
+        | Expression::SafeCast { expr: value, .. }
+        | Expression::ArrayLen { array: value, .. }
+        | Expression::Splat { expr: value, .. }
+        | Expression::UnsafeBlock { body: value, .. }
+        | Expression::ComptimeBlock { body: value, .. }
+        | Expression::Defer { body: value, .. }
+        | Expression::Ref { expr: value, .. }
+        | Expression::Deref { expr: value, .. }
+        | Expression::Not { expr: value, .. }
+        | Expression::FieldAccess { target: value, .. }
+        | Expression::TupleAccess { tuple: value, .. }
+        | Expression::Try { expr: value, .. }
+        | Expression::Lambda { body: value, .. } => visit(value, symbols, usages, declared),
+        Expression::InlineAsm { outputs, inputs, .. } => outputs
+            .iter()
+            .chain(inputs.iter())
+            .for_each(|operand| visit(&operand.expr, symbols, usages, declared)),
+        Expression::Interpolated { expressions, .. }
+        | Expression::Array {
+            elements: expressions, ..
+        }
+        | Expression::Tuple {
+            elements: expressions, ..
+        } => expressions
+            .iter()
+            .for_each(|expr| visit(expr, symbols, usages, declared)),
+        Expression::Macro { tokens, .. } => tokens
+            .iter()
+            .filter_map(|token| symbols.resolution(token.location))
+            .for_each(|id| usages[id.0].reads += 1),
+        Expression::Bool { .. }
+        | Expression::Sizeof { .. }
+        | Expression::Path { .. }
+        | Expression::StringLiteral { .. }
+        | Expression::UintLiteral { .. }
+        | Expression::FloatLiteral { .. }
+        | Expression::IntLiteral { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::expression::parse_expression;
+
+    fn lint(source: &str) -> Vec<(String, &'static str)> {
+        let mut lexer = Lexer::new(source);
+        let expr = match parse_expression(&mut lexer, true) {
+            Ok(expr) => expr,
+            Err(e) => panic!("{e:?}"),
+        };
+        let symbols = SymbolTable::resolve(&expr);
+
+        lint_unused(&expr, &symbols)
+            .iter()
+            .map(|report| {
+                let unused = report.downcast_ref::<UnusedVariable>().unwrap();
+                (unused.name.clone(), unused.usage)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn unused_and_write_only_variables() {
+        let source = r#"{
+            var unused = compute();
+            var written = 1;
+            written = 2;
+            const read = 3;
+            println(read);
+        }"#;
+
+        assert_eq!(
+            lint(source),
+            [
+                ("unused".to_string(), "never used"),
+                ("written".to_string(), "assigned but never read"),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_the_shadow_is_read() {
+        let source = r#"{
+            const value = 1;
+            {
+                const value = 2;
+                println(value);
+            }
+        }"#;
+
+        let reports = lint(source);
+        assert_eq!(reports, [("value".to_string(), "never used")]);
+    }
+
+    #[test]
+    fn reads_in_dead_branches_count() {
+        let source = r#"{
+            const value = 1;
+            const condition = 2;
+            if 1 == 2 {
+                if condition == 2 {
+                    return value;
+                }
+            }
+        }"#;
+
+        assert!(lint(source).is_empty());
+    }
+
+    #[test]
+    fn underscore_names_are_ignored() {
+        let source = r#"{
+            const _unused = 1;
+            var _written = 2;
+            _written = 3;
+        }"#;
+
+        assert!(lint(source).is_empty());
+    }
+}
@@ -0,0 +1,324 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use kura_lexer::token::{Kind, Location, Value};
+
+use crate::{DoStep, Expression, Statement};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BindingId(pub usize);
+
+#[derive(Debug)]
+pub struct Binding<'ast> {
+    pub name: Cow<'ast, str>,
+    pub mutable: bool,
+    /// location of the whole declaration
+    pub location: Location,
+    /// how many scopes deep the binding was declared, function arguments
+    /// live on depth 0
+    pub depth: usize,
+}
+
+/// result of resolving every identifier to the binding it refers to. nodes
+/// are keyed by the byte they start at, as no two identifiers or
+/// declarations can start at the same byte
+#[derive(Debug, Default)]
+pub struct SymbolTable<'ast> {
+    pub bindings: Vec<Binding<'ast>>,
+    declarations: HashMap<usize, BindingId>,
+    resolutions: HashMap<usize, BindingId>,
+}
+
+impl<'ast> SymbolTable<'ast> {
+    pub fn resolve(expr: &Expression<'ast>) -> Self {
+        let mut resolver = Resolver::default();
+        resolver.resolve(expr);
+        resolver.table
+    }
+
+    pub fn resolve_statements(statements: &[Statement<'ast>]) -> Self {
+        let mut resolver = Resolver::default();
+        statements
+            .iter()
+            .for_each(|statement| resolver.resolve_statement(statement));
+        resolver.table
+    }
+
+    pub fn binding(&self, id: BindingId) -> &Binding<'ast> {
+        &self.bindings[id.0]
+    }
+
+    /// binding introduced by the declaration at `location`, either a variable
+    /// or a function argument
+    pub fn declaration(&self, location: Location) -> Option<BindingId> {
+        self.declarations.get(&location.start_byte).copied()
+    }
+
+    /// binding that the identifier at `location` refers to, `None` for names
+    /// that are not declared anywhere in scope, like functions
+    pub fn resolution(&self, location: Location) -> Option<BindingId> {
+        self.resolutions.get(&location.start_byte).copied()
+    }
+}
+
+#[derive(Default)]
+struct Resolver<'ast> {
+    table: SymbolTable<'ast>,
+    scopes: Vec<Vec<BindingId>>,
+}
+
+impl<'ast> Resolver<'ast> {
+    fn declare(&mut self, name: Cow<'ast, str>, mutable: bool, location: Location) {
+        let id = BindingId(self.table.bindings.len());
+        self.table.bindings.push(Binding {
+            name,
+            mutable,
+            location,
+            depth: self.scopes.len().saturating_sub(1),
+        });
+        self.table.declarations.insert(location.start_byte, id);
+
+        match self.scopes.last_mut() {
+            Some(scope) => scope.push(id),
+            None => self.scopes.push(vec![id]),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<BindingId> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|id| self.table.binding(**id).name == name)
+            .copied()
+    }
+
+    fn resolve_statement(&mut self, statement: &Statement<'ast>) {
+        match statement {
+            Statement::FunArgument { name, location, .. } => self.declare(name.clone(), false, *location),
+            Statement::Fun { arguments, body, .. } => {
+                self.scopes.push(vec![]);
+                arguments.iter().for_each(|argument| self.resolve_statement(argument));
+                body.iter().for_each(|expr| self.resolve(expr));
+                self.scopes.pop();
+            }
+            // fields are not bindings, they are only reached through a value
+            Statement::Struct { .. } => {}
+            Statement::Impl { functions, .. } => functions.iter().for_each(|function| self.resolve_statement(function)),
+            // the names in a macro only mean something once it is expanded
+            Statement::MacroRules { .. } => {}
+            // what a module declares is only known once its file is parsed
+            Statement::Mod { .. } => {}
+            // and so is what a dependency declares, once it is resolved
+            Statement::ExternCrate { .. } => {}
+            // what is imported is looked up where it is declared
+            Statement::Use { .. } => {}
+            // globals are looked up by name wherever they are used, just like
+            // functions, so they aren't bindings
+            Statement::Static { value, .. } => self.resolve(value),
+        }
+    }
+
+    fn resolve(&mut self, expr: &Expression<'ast>) {
+        match expr {
+            Expression::Var {
+                mutable,
+                name,
+                value,
+                location,
+                ..
+            } => {
+                // the value is resolved before the binding exists, so that
+                // `const x = x + 1` refers to an outer `x`
+                self.resolve(value);
+                self.declare(name.clone(), *mutable, *location);
+            }
+            Expression::Ident { name, location } => {
+                if let Some(id) = self.lookup(name) {
+                    self.table.resolutions.insert(location.start_byte, id);
+                }
+            }
+            Expression::Block { expressions, tail, .. } => {
+                self.scopes.push(vec![]);
+                expressions
+                    .iter()
+                    .chain(tail.as_deref())
+                    .for_each(|expr| self.resolve(expr));
+                self.scopes.pop();
+            }
+            Expression::Assert { condition, message, .. } => {
+                self.resolve(condition);
+                message.iter().for_each(|message| self.resolve(message));
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                self.resolve(condition);
+                self.resolve(truthy);
+                falsy.iter().for_each(|branch| self.resolve(branch));
+            }
+            Expression::FunCall { ident, arguments, .. } => {
+                self.resolve(ident);
+                arguments.iter().for_each(|argument| self.resolve(argument));
+            }
+            Expression::Assign { ident, value, .. } | Expression::CompoundAssign { ident, value, .. } => {
+                self.resolve(value);
+                self.resolve(ident);
+            }
+            Expression::BinaryOp { lhs, rhs, .. }
+            | Expression::Index {
+                target: lhs,
+                index: rhs,
+                ..
+            }
+            | Expression::Elvis {
+                condition: lhs,
+                default: rhs,
+                ..
+            } => {
+                self.resolve(lhs);
+                self.resolve(rhs);
+            }
+            Expression::Yield { value, .. } => value.iter().for_each(|value| self.resolve(value)),
+            Expression::EnumVariant { kind, .. } => kind.values().for_each(|value| self.resolve(value)),
+            Expression::DoBlock { steps, .. } => {
+                self.scopes.push(vec![]);
+                for step in steps {
+                    self.resolve(step.expr());
+                    if let DoStep::Bind { name, location, .. } = step {
+                        self.declare(name.clone(), false, *location);
+                    }
+                }
+                self.scopes.pop();
+            }
+            Expression::Return { value, .. }
+            | Expression::Await { expr: value, .. }
+            | Expression::RuntimeTypeOf { expr: value, .. }
+            | Expression::SafeCast { expr: value, .. }
+            | Expression::ArrayLen { array: value, .. }
+            | Expression::Splat { expr: value, .. }
+            | Expression::UnsafeBlock { body: value, .. }
+            | Expression::ComptimeBlock { body: value, .. }
+            | Expression::Defer { body: value, .. }
+            | Expression::Ref { expr: value, .. }
+            | Expression::Deref { expr: value, .. }
+            | Expression::Not { expr: value, .. }
+            | Expression::FieldAccess { target: value, .. }
+            | Expression::TupleAccess { tuple: value, .. }
+            | Expression::Try { expr: value, .. } => self.resolve(value),
+            Expression::InlineAsm { outputs, inputs, .. } => outputs
+                .iter()
+                .chain(inputs.iter())
+                .for_each(|operand| self.resolve(&operand.expr)),
+            Expression::Interpolated { expressions, .. }
+            | Expression::Array {
+                elements: expressions, ..
+            }
+            | Expression::Tuple {
+                elements: expressions, ..
+            } => expressions.iter().for_each(|expr| self.resolve(expr)),
+            Expression::Lambda { parameters, body, .. } => {
+                self.scopes.push(vec![]);
+                for parameter in parameters {
+                    if let Expression::Ident { name, location } = parameter {
+                        self.declare(name.clone(), false, *location);
+                    }
+                }
+                self.resolve(body);
+                self.scopes.pop();
+            }
+            Expression::Match { scrutinee, arms, .. } => {
+                self.resolve(scrutinee);
+                for arm in arms {
+                    self.scopes.push(vec![]);
+                    for (name, location) in arm.pattern.bindings() {
+                        self.declare(name.clone(), false, location);
+                    }
+                    self.resolve(&arm.body);
+                    self.scopes.pop();
+                }
+            }
+            Expression::Let {
+                name,
+                value,
+                body,
+                location,
+            } => {
+                self.resolve(value);
+                self.scopes.push(vec![]);
+                self.declare(name.clone(), false, *location);
+                self.resolve(body);
+                self.scopes.pop();
+            }
+            Expression::Macro { tokens, .. } => {
+                // the arguments of a macro are not expressions yet, but any
+                // identifier among them is likely a reference to a binding
+                for token in tokens {
+                    if let Kind::Value(Value::Ident(name)) = &token.kind {
+                        if let Some(id) = self.lookup(name) {
+                            self.table.resolutions.insert(token.location.start_byte, id);
+                        }
+                    }
+                }
+            }
+            // paths name items in other modules, never local bindings
+            Expression::Bool { .. }
+            | Expression::Sizeof { .. }
+            | Expression::Path { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::UintLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::IntLiteral { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::expression::parse_expression;
+
+    #[test]
+    fn resolves_to_the_innermost_binding() {
+        let source = "{ const x = 1; { const x = x + 1; x } x }";
+        let mut lexer = Lexer::new(source);
+        let expr = parse_expression(&mut lexer, true).unwrap();
+        let table = SymbolTable::resolve(&expr);
+
+        let uses = source.match_indices('x').map(|(idx, _)| idx).collect::<Vec<_>>();
+        let resolved = uses
+            .iter()
+            .map(|start| table.resolution(Location::new(*start, *start + 1)))
+            .collect::<Vec<_>>();
+
+        // declarations are not uses, the inner value reads the outer `x`
+        assert_eq!(
+            resolved,
+            [None, None, Some(BindingId(0)), Some(BindingId(1)), Some(BindingId(0))]
+        );
+        assert_eq!(table.binding(BindingId(1)).depth, 1);
+    }
+
+    #[test]
+    fn let_bindings_only_reach_their_body() {
+        let source = "{ const x = 1; (let x = x + 1 in x * 2) + x }";
+        let mut lexer = Lexer::new(source);
+        let expr = parse_expression(&mut lexer, true).unwrap();
+        let table = SymbolTable::resolve(&expr);
+
+        let resolved = source
+            .match_indices('x')
+            .map(|(idx, _)| table.resolution(Location::new(idx, idx + 1)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            resolved,
+            [None, None, Some(BindingId(0)), Some(BindingId(1)), Some(BindingId(0))]
+        );
+    }
+}
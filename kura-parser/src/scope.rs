@@ -0,0 +1,150 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use kura_lexer::token::Location;
+
+use crate::Expression;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ScopeId(pub usize);
+
+#[derive(Debug)]
+pub struct Scope<'ast> {
+    /// `None` only for the root scope, which spans the whole program
+    pub parent: Option<ScopeId>,
+    pub location: Location,
+    /// value and location of the declaration of every binding introduced
+    /// right in this scope, not in the ones nested into it
+    pub bindings: HashMap<Cow<'ast, str>, (Expression<'ast>, Location)>,
+}
+
+/// how blocks nest into each other, and what each of them declares. built by
+/// `Parser::parse_with_scopes` as blocks are opened and closed, and only
+/// queried afterwards
+#[derive(Debug)]
+pub struct ScopeTree<'ast> {
+    scopes: Vec<Scope<'ast>>,
+    current: ScopeId,
+}
+
+impl Default for ScopeTree<'_> {
+    fn default() -> Self {
+        Self {
+            scopes: vec![Scope {
+                parent: None,
+                location: Location::default(),
+                bindings: HashMap::default(),
+            }],
+            current: ScopeId(0),
+        }
+    }
+}
+
+impl<'ast> ScopeTree<'ast> {
+    pub fn root(&self) -> ScopeId {
+        ScopeId(0)
+    }
+
+    pub fn scope(&self, id: ScopeId) -> &Scope<'ast> {
+        &self.scopes[id.0]
+    }
+
+    pub fn scopes(&self) -> impl Iterator<Item = (ScopeId, &Scope<'ast>)> {
+        self.scopes.iter().enumerate().map(|(id, scope)| (ScopeId(id), scope))
+    }
+
+    /// innermost scope containing `offset`. scopes are pushed in the order
+    /// they open, so the last one containing it is the innermost
+    pub fn scope_at(&self, offset: usize) -> ScopeId {
+        self.scopes()
+            .filter(|(_, scope)| (scope.location.start_byte..scope.location.end_byte).contains(&offset))
+            .last()
+            .map_or(self.root(), |(id, _)| id)
+    }
+
+    /// binding `name` refers to when used at `offset`, looking through every
+    /// scope around it from the innermost out. bindings declared after
+    /// `offset` aren't visible yet
+    pub fn lookup(&self, name: &str, offset: usize) -> Option<&(Expression<'ast>, Location)> {
+        let mut id = Some(self.scope_at(offset));
+
+        while let Some(scope) = id.map(|id| self.scope(id)) {
+            match scope.bindings.get(name) {
+                Some(binding) if binding.1.start_byte < offset => return Some(binding),
+                _ => id = scope.parent,
+            }
+        }
+
+        None
+    }
+
+    pub(crate) fn push(&mut self, start: usize) {
+        self.scopes.push(Scope {
+            parent: Some(self.current),
+            location: Location::new(start, start),
+            bindings: HashMap::default(),
+        });
+        self.current = ScopeId(self.scopes.len() - 1);
+    }
+
+    pub(crate) fn pop(&mut self, end: usize) {
+        let scope = &mut self.scopes[self.current.0];
+        scope.location.end_byte = end;
+        self.current = scope.parent.unwrap_or(self.current);
+    }
+
+    pub(crate) fn bind(&mut self, name: Cow<'ast, str>, value: Expression<'ast>, location: Location) {
+        self.scopes[self.current.0].bindings.insert(name, (value, location));
+    }
+
+    pub(crate) fn finish(&mut self, end: usize) {
+        self.scopes[0].location = Location::new(0, end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::sexpr::to_sexpr;
+    use crate::Parser;
+
+    #[test]
+    fn blocks_nest_into_scopes() {
+        let source =
+            "fun main() {\n    const a = 1;\n    if a == 1 {\n        var b = a;\n        const a = 2;\n    }\n}";
+        let (_, scopes) = Parser::new(source, Lexer::new(source)).parse_with_scopes().unwrap();
+
+        let body = scopes.scope_at(source.find("const").unwrap());
+        let block = scopes.scope_at(source.find("var").unwrap());
+        assert_eq!(scopes.scope(block).parent, Some(body));
+        assert_eq!(scopes.scope(body).parent, Some(scopes.root()));
+        assert_eq!(scopes.scope(scopes.root()).location, Location::new(0, source.len()));
+        assert_eq!(scopes.scope(body).bindings.len(), 1);
+        assert_eq!(scopes.scope(block).bindings.len(), 2);
+
+        // the inner `a` shadows the outer one only after it is declared
+        let value = |offset| scopes.lookup("a", offset).map(|(value, _)| to_sexpr(value));
+        assert_eq!(
+            value(source.find("= a").unwrap()),
+            Some(String::from("(uint-literal 1)"))
+        );
+        assert_eq!(
+            value(source.rfind("    }").unwrap()),
+            Some(String::from("(uint-literal 2)"))
+        );
+        assert_eq!(
+            value(source.rfind('}').unwrap()),
+            Some(String::from("(uint-literal 1)"))
+        );
+        assert!(scopes.lookup("b", source.rfind('}').unwrap()).is_none());
+
+        // bindings borrow their names from the source instead of copying them
+        assert!(scopes
+            .scope(body)
+            .bindings
+            .keys()
+            .all(|name| matches!(name, Cow::Borrowed(_))));
+    }
+}
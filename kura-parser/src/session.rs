@@ -0,0 +1,223 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use kura_lexer::source::MultiFileSource;
+use kura_lexer::token::Location;
+use kura_lexer::{Lexer, UTF8_BOM};
+use miette::{MietteError, SourceCode, SourceSpan, SpanContents};
+
+use crate::Parser;
+
+/// a file added to a `Session`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(pub u32);
+
+/// every file of a program, parsed into a single space of locations. each
+/// file gets a range of bytes of its own, right after the previous one, so
+/// a `Location` also tells the file it is in, and a diagnostic can point at
+/// many files at once when reported against the session
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    files: Arc<MultiFileSource>,
+    /// every file, one after the other, which is what locations index into
+    source: String,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_file(&mut self, name: impl Into<String>, content: impl Into<String>) -> FileId {
+        let content = content.into();
+        self.source.push_str(&content);
+        Arc::make_mut(&mut self.files).push(name, content);
+        FileId(self.files.segments.len() as u32 - 1)
+    }
+
+    pub fn file_name(&self, file: FileId) -> &str {
+        &self.files.segments[file.0 as usize].path
+    }
+
+    pub fn file_source(&self, file: FileId) -> &str {
+        &self.source[self.range(file)]
+    }
+
+    /// bytes `file` takes in the locations of the session
+    pub fn range(&self, file: FileId) -> Range<usize> {
+        let segment = &self.files.segments[file.0 as usize];
+        segment.start_offset..segment.start_offset + segment.content.len()
+    }
+
+    /// file `location` points into, if it belongs to this session
+    pub fn file_of(&self, location: Location) -> Option<FileId> {
+        let idx = self
+            .files
+            .segments
+            .partition_point(|segment| segment.start_offset <= location.start_byte)
+            .checked_sub(1)?;
+        let file = FileId(idx as u32);
+        // a location right past the end of a file, like the end of input,
+        // still belongs to it
+        (location.end_byte <= self.range(file).end).then_some(file)
+    }
+
+    /// `location` relative to the start of its own file, as editors count it
+    pub fn local(&self, location: Location) -> Option<(FileId, Location)> {
+        let file = self.file_of(location)?;
+        let start = self.range(file).start;
+        Some((
+            file,
+            Location::new(location.start_byte - start, location.end_byte - start),
+        ))
+    }
+
+    /// a lexer over `file` alone, whose errors are reported on the session
+    pub fn lexer(&self, file: FileId) -> Lexer<'_> {
+        let range = self.range(file);
+        // each file can start with its own byte-order mark
+        let start = match self.file_source(file).starts_with(UTF8_BOM) {
+            true => range.start + UTF8_BOM.len(),
+            false => range.start,
+        };
+
+        Lexer::new(&self.source)
+            .with_source_code(self.files.clone())
+            .sub_lexer(start..range.end)
+    }
+}
+
+impl SourceCode for Session {
+    fn read_span<'a>(
+        &'a self,
+        span: &SourceSpan,
+        context_lines_before: usize,
+        context_lines_after: usize,
+    ) -> Result<Box<dyn SpanContents<'a> + 'a>, MietteError> {
+        // unlike a `MultiFileSource`, spans crossing into another file are
+        // refused rather than cut. reporters try to merge labels that are
+        // close by lines, which would otherwise merge labels of different
+        // files into a single snippet
+        let location = Location::new(span.offset(), span.offset() + span.len());
+        if self.file_of(location).is_none() {
+            return Err(MietteError::OutOfBounds);
+        }
+        self.files.read_span(span, context_lines_before, context_lines_after)
+    }
+}
+
+impl<'par> Parser<'par> {
+    /// parses `file` of the session, with locations in the space of the
+    /// whole session
+    pub fn for_file(session: &'par Session, file: FileId) -> Self {
+        Parser::new(&session.source, session.lexer(file))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use miette::{Diagnostic, NarratableReportHandler, Report};
+    use thiserror::Error;
+
+    use super::*;
+    use crate::Statement;
+
+    #[derive(Debug, Error, Diagnostic)]
+    #[error("`{name}` is defined twice")]
+    struct Redefinition {
+        name: String,
+        #[label("first defined here")]
+        first: SourceSpan,
+        #[label("defined again here")]
+        second: SourceSpan,
+    }
+
+    fn render(report: &Report) -> String {
+        let mut rendered = String::new();
+        NarratableReportHandler::new()
+            .render_report(&mut rendered, report.as_ref())
+            .unwrap();
+        rendered
+    }
+
+    fn session() -> (Session, FileId, FileId) {
+        let mut session = Session::new();
+        let a = session.add_file("a.rmb", "fun helper() {}\n\nfun main() { var a = 1; }\n");
+        let b = session.add_file("b.rmb", "// the same name again\nfun helper() { var b = 2; }\n");
+        (session, a, b)
+    }
+
+    #[test]
+    fn diagnostics_point_into_many_files() {
+        let (session, a, b) = session();
+        let first = Parser::for_file(&session, a).parse().unwrap();
+        let second = Parser::for_file(&session, b).parse().unwrap();
+
+        let Statement::Fun { location: first, .. } = &first[0] else {
+            panic!("expected a function");
+        };
+        let Statement::Fun { location: second, .. } = &second[0] else {
+            panic!("expected a function");
+        };
+
+        let report = Report::new(Redefinition {
+            name: String::from("helper"),
+            first: (*first).into(),
+            second: (*second).into(),
+        })
+        .with_source_code(session.clone());
+        let rendered = render(&report);
+
+        // each label is shown on the lines of its own file
+        let (a, b) = rendered.split_once("Begin snippet for b.rmb").unwrap();
+        assert!(a.contains("snippet for a.rmb starting at line 1"), "{rendered}");
+        assert!(
+            a.contains("label at line 1, columns 1 to 15: first defined here"),
+            "{rendered}"
+        );
+        assert!(b.contains("snippet line 2: fun helper() { var b = 2; }"), "{rendered}");
+        assert!(
+            b.contains("label at line 2, columns 1 to 27: defined again here"),
+            "{rendered}"
+        );
+    }
+
+    #[test]
+    fn locations_of_a_file_stay_in_it() {
+        let (session, a, b) = session();
+        let statements = Parser::for_file(&session, b).parse().unwrap();
+
+        let Statement::Fun { location, .. } = &statements[0] else {
+            panic!("expected a function");
+        };
+        assert_eq!(session.file_of(*location), Some(b));
+        assert!(session.range(b).contains(&location.start_byte));
+        assert_eq!(
+            &session.source[location.start_byte..location.end_byte],
+            "fun helper() { var b = 2; }"
+        );
+
+        let (file, local) = session.local(*location).unwrap();
+        assert_eq!(file, b);
+        assert_eq!(
+            &session.file_source(b)[local.start_byte..local.end_byte],
+            "fun helper() { var b = 2; }"
+        );
+        assert_eq!(session.file_of(Location::new(0, 3)), Some(a));
+    }
+
+    #[test]
+    fn errors_at_the_end_of_a_file_stay_in_it() {
+        let mut session = Session::new();
+        let broken = session.add_file("broken.rmb", "fun main() { var a = 1;");
+        session.add_file("fine.rmb", "fun other() {}\n");
+
+        let error = Parser::for_file(&session, broken).parse().unwrap_err();
+        let location = Location::new(error.span.offset(), error.span.offset() + error.span.len());
+        assert_eq!(location.end_byte, session.range(broken).end);
+        assert_eq!(session.file_of(location), Some(broken));
+
+        let rendered = render(&Report::new(error).with_source_code(session));
+        assert!(rendered.contains("snippet for broken.rmb"), "{rendered}");
+    }
+}
@@ -0,0 +1,806 @@
+use kura_lexer::token::{Location, Token};
+
+use crate::{
+    Attribute, DoStep, Expression, GenericParam, Pattern, Statement, TypeExpr, UseTree, VariantKind, WherePredicate,
+};
+
+/// renders an expression as an s-expression, like
+/// `(binary-op + (uint-literal 1) (uint-literal 2))`
+pub fn to_sexpr(expr: &Expression<'_>) -> String {
+    SexprPrinter::default().expression(expr)
+}
+
+/// renders every statement of a program as an s-expression, one per line
+pub fn statements_to_sexpr(statements: &[Statement<'_>]) -> String {
+    SexprPrinter::default().statements(statements)
+}
+
+/// renders trees as s-expressions. locations are left out unless
+/// `with_locations` is set, which writes them right after the name of
+/// every node, like `(ident@4..8 name)`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SexprPrinter {
+    pub with_locations: bool,
+}
+
+impl SexprPrinter {
+    pub fn expression(&self, expr: &Expression<'_>) -> String {
+        let mut output = String::new();
+        self.write_expression(&mut output, expr);
+        output
+    }
+
+    pub fn statements(&self, statements: &[Statement<'_>]) -> String {
+        let mut output = String::new();
+
+        for statement in statements {
+            self.write_statement(&mut output, statement);
+            output.push('\n');
+        }
+
+        output
+    }
+
+    /// writes `location` after the name of the node written from `start`
+    fn locate(&self, output: &mut String, start: usize, location: Location) {
+        if !self.with_locations {
+            return;
+        }
+
+        let name_end = output[start..].find([' ', ')']).map_or(output.len(), |end| start + end);
+        output.insert_str(name_end, &format!("@{}..{}", location.start_byte, location.end_byte));
+    }
+
+    fn write_statement(&self, output: &mut String, statement: &Statement<'_>) {
+        let start = output.len();
+        match statement {
+            Statement::FunArgument { name, arg_type, .. } => {
+                output.push_str(&format!("(arg {name} "));
+                self.write_type(output, arg_type);
+                output.push(')');
+            }
+            Statement::Fun {
+                name,
+                generics,
+                arguments,
+                body,
+                return_type,
+                where_clause,
+                attributes,
+                is_unsafe,
+                ..
+            } => {
+                let keyword = if *is_unsafe { "unsafe-fun" } else { "fun" };
+                output.push_str(&format!("({keyword} {name}"));
+                self.write_generics(output, generics);
+                self.write_attributes(output, attributes);
+                output.push_str(" (args");
+                for argument in arguments {
+                    output.push(' ');
+                    self.write_statement(output, argument);
+                }
+                output.push(')');
+
+                if let Some(return_type) = return_type {
+                    output.push_str(" (returns ");
+                    self.write_type(output, return_type);
+                    output.push(')');
+                }
+                self.write_where_clause(output, where_clause);
+
+                self.write_list(output, body);
+                output.push(')');
+            }
+            Statement::Struct {
+                name,
+                generics,
+                fields,
+                attributes,
+                ..
+            } => {
+                output.push_str(&format!("(struct {name}"));
+                self.write_generics(output, generics);
+                self.write_attributes(output, attributes);
+                for field in fields {
+                    if let Statement::FunArgument { name, arg_type, .. } = field {
+                        output.push(' ');
+                        let start = output.len();
+                        output.push_str(&format!("(field {name} "));
+                        self.write_type(output, arg_type);
+                        output.push(')');
+                        self.locate(output, start, field.location());
+                    }
+                }
+                output.push(')');
+            }
+            Statement::Impl {
+                generics,
+                trait_name,
+                target,
+                arguments,
+                functions,
+                ..
+            } => {
+                output.push_str(&format!("(impl {target}"));
+                if !arguments.is_empty() {
+                    output.push_str(" (arguments");
+                    self.write_types(output, arguments);
+                    output.push(')');
+                }
+                if let Some(trait_name) = trait_name {
+                    output.push_str(&format!(" (trait {trait_name})"));
+                }
+                self.write_generics(output, generics);
+                for function in functions {
+                    output.push(' ');
+                    self.write_statement(output, function);
+                }
+                output.push(')');
+            }
+            Statement::Mod { name, .. } => output.push_str(&format!("(mod {name})")),
+            Statement::Use { tree, attributes, .. } => {
+                output.push_str("(use ");
+                self.write_use_tree(output, tree);
+                self.write_attributes(output, attributes);
+                output.push(')');
+            }
+            Statement::ExternCrate {
+                name,
+                alias,
+                attributes,
+                ..
+            } => {
+                output.push_str(&format!("(extern-crate {name}"));
+                if let Some(alias) = alias {
+                    output.push_str(&format!(" (as {alias})"));
+                }
+                self.write_attributes(output, attributes);
+                output.push(')');
+            }
+            Statement::Static {
+                mutable,
+                name,
+                typ,
+                value,
+                attributes,
+                ..
+            } => {
+                let keyword = if *mutable { "var" } else { "const" };
+                output.push_str(&format!("(static-{keyword} {name}"));
+                self.write_attributes(output, attributes);
+                if let Some(typ) = typ {
+                    output.push_str(" (type ");
+                    self.write_type(output, typ);
+                    output.push(')');
+                }
+                output.push(' ');
+                self.write_expression(output, value);
+                output.push(')');
+            }
+            Statement::MacroRules { name, rules, .. } => {
+                output.push_str(&format!("(macro-rules {name}"));
+                for rule in rules {
+                    output.push_str(" (rule (pattern");
+                    self.write_tokens(output, &rule.pattern);
+                    output.push_str(") (expansion");
+                    self.write_tokens(output, &rule.expansion);
+                    output.push_str("))");
+                }
+                output.push(')');
+            }
+        }
+        self.locate(output, start, statement.location());
+    }
+
+    fn write_tokens(&self, output: &mut String, tokens: &[Token<'_>]) {
+        tokens
+            .iter()
+            .for_each(|token| output.push_str(&format!(" {}", token.kind)));
+    }
+
+    fn write_use_tree(&self, output: &mut String, tree: &UseTree<'_>) {
+        match tree {
+            UseTree::Leaf(name) => output.push_str(name),
+            UseTree::Path { segment, child } => {
+                output.push_str(&format!("(path {segment} "));
+                self.write_use_tree(output, child);
+                output.push(')');
+            }
+            UseTree::Group(trees) => {
+                output.push_str("(group");
+                for tree in trees {
+                    output.push(' ');
+                    self.write_use_tree(output, tree);
+                }
+                output.push(')');
+            }
+            UseTree::Glob => output.push('*'),
+        }
+    }
+
+    fn write_generics(&self, output: &mut String, generics: &[GenericParam<'_>]) {
+        if generics.is_empty() {
+            return;
+        }
+        output.push_str(" (generics");
+        for param in generics {
+            match param.bounds.is_empty() {
+                true => output.push_str(&format!(" {}", param.name)),
+                false => {
+                    output.push_str(&format!(" ({}", param.name));
+                    self.write_types(output, &param.bounds);
+                    output.push(')');
+                }
+            }
+        }
+        output.push(')');
+    }
+
+    fn write_where_clause(&self, output: &mut String, where_clause: &[WherePredicate<'_>]) {
+        if where_clause.is_empty() {
+            return;
+        }
+        output.push_str(" (where");
+        for predicate in where_clause {
+            output.push_str(" (");
+            if !predicate.lifetimes.is_empty() {
+                output.push_str("(for");
+                for lifetime in &predicate.lifetimes {
+                    output.push_str(&format!(" '{}", lifetime.name));
+                }
+                output.push_str(") ");
+            }
+            self.write_type(output, &predicate.bounded);
+            self.write_types(output, &predicate.bounds);
+            output.push(')');
+        }
+        output.push(')');
+    }
+
+    fn write_attributes(&self, output: &mut String, attributes: &[Attribute<'_>]) {
+        for attribute in attributes {
+            output.push_str(&format!(" (attribute {}", attribute.name));
+            attribute
+                .arguments
+                .iter()
+                .for_each(|argument| output.push_str(&format!(" {argument}")));
+            attribute
+                .values
+                .iter()
+                .for_each(|(key, value)| output.push_str(&format!(" ({key} \"{value}\")")));
+            output.push(')');
+        }
+    }
+
+    fn write_type(&self, output: &mut String, typ: &TypeExpr<'_>) {
+        let start = output.len();
+        match typ {
+            TypeExpr::Named { name, .. } => output.push_str(&format!("(type-name {name})")),
+            TypeExpr::Fn {
+                parameters, returns, ..
+            } => {
+                output.push_str("(fn-type (params");
+                self.write_types(output, parameters);
+                output.push(')');
+                if let Some(returns) = returns {
+                    output.push_str(" (returns ");
+                    self.write_type(output, returns);
+                    output.push(')');
+                }
+                output.push(')');
+            }
+            TypeExpr::Array { element, length, .. } => {
+                output.push_str("(array-type ");
+                self.write_type(output, element);
+                output.push_str(&format!(" {length})"));
+            }
+            TypeExpr::Tuple { elements, .. } => {
+                output.push_str("(tuple-type");
+                self.write_types(output, elements);
+                output.push(')');
+            }
+            TypeExpr::Ptr { mutable, pointee, .. } => {
+                let mutability = if *mutable { "mut" } else { "const" };
+                output.push_str(&format!("(ptr-type {mutability} "));
+                self.write_type(output, pointee);
+                output.push(')');
+            }
+            TypeExpr::Generic { name, arguments, .. } => {
+                output.push_str(&format!("(generic-type {name}"));
+                self.write_types(output, arguments);
+                output.push(')');
+            }
+            TypeExpr::ImplTrait { bound, .. } => {
+                output.push_str("(impl-type ");
+                self.write_type(output, bound);
+                output.push(')');
+            }
+            TypeExpr::Ref {
+                mutable,
+                lifetime,
+                referent,
+                ..
+            } => {
+                output.push_str("(ref-type ");
+                if let Some(lifetime) = lifetime.name() {
+                    output.push_str(&format!("'{lifetime} "));
+                }
+                if *mutable {
+                    output.push_str("mut ");
+                }
+                self.write_type(output, referent);
+                output.push(')');
+            }
+            TypeExpr::FnTrait {
+                name,
+                parameters,
+                returns,
+                ..
+            } => {
+                output.push_str(&format!("(fn-trait {name} (params"));
+                self.write_types(output, parameters);
+                output.push(')');
+                if let Some(returns) = returns {
+                    output.push_str(" (returns ");
+                    self.write_type(output, returns);
+                    output.push(')');
+                }
+                output.push(')');
+            }
+            TypeExpr::Lifetime { name, .. } => output.push_str(&format!("(lifetime {name})")),
+            TypeExpr::Never { .. } => output.push_str("(never-type)"),
+        }
+        self.locate(output, start, typ.location());
+    }
+
+    fn write_types(&self, output: &mut String, types: &[TypeExpr<'_>]) {
+        for typ in types {
+            output.push(' ');
+            self.write_type(output, typ);
+        }
+    }
+
+    fn write_expression(&self, output: &mut String, expr: &Expression<'_>) {
+        let start = output.len();
+        match expr {
+            Expression::Var {
+                mutable,
+                name,
+                typ,
+                value,
+                attributes,
+                ..
+            } => {
+                let keyword = if *mutable { "var" } else { "const" };
+                output.push_str(&format!("({keyword} {name}"));
+                self.write_attributes(output, attributes);
+                if let Some(typ) = typ {
+                    output.push_str(" (type ");
+                    self.write_type(output, typ);
+                    output.push(')');
+                }
+                output.push(' ');
+                self.write_expression(output, value);
+                output.push(')');
+            }
+            Expression::Bool { value, .. } => output.push_str(&format!("(bool {value})")),
+            Expression::Assert { condition, message, .. } => {
+                output.push_str("(assert ");
+                self.write_expression(output, condition);
+                if let Some(message) = message {
+                    output.push(' ');
+                    self.write_expression(output, message);
+                }
+                output.push(')');
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                output.push_str("(if ");
+                self.write_expression(output, condition);
+                output.push(' ');
+                self.write_expression(output, truthy);
+                self.write_list(output, falsy);
+                output.push(')');
+            }
+            Expression::FunCall {
+                ident,
+                arguments,
+                tail_call,
+                ..
+            } => {
+                output.push_str(if *tail_call { "(tail-call " } else { "(call " });
+                self.write_expression(output, ident);
+                self.write_list(output, arguments);
+                output.push(')');
+            }
+            Expression::Assign { ident, value, .. } => {
+                output.push_str("(assign ");
+                self.write_expression(output, ident);
+                output.push(' ');
+                self.write_expression(output, value);
+                output.push(')');
+            }
+            Expression::CompoundAssign {
+                operator, ident, value, ..
+            } => {
+                output.push_str(&format!("(compound-assign {operator} "));
+                self.write_expression(output, ident);
+                output.push(' ');
+                self.write_expression(output, value);
+                output.push(')');
+            }
+            Expression::Ident { name, .. } => output.push_str(&format!("(ident {name})")),
+            Expression::Path { segments, .. } => output.push_str(&format!("(path {})", segments.join("::"))),
+            Expression::Block { expressions, tail, .. } => {
+                output.push_str("(block");
+                self.write_list(output, expressions);
+                if let Some(tail) = tail {
+                    output.push_str(" (tail ");
+                    self.write_expression(output, tail);
+                    output.push(')');
+                }
+                output.push(')');
+            }
+            Expression::UintLiteral { value, size, .. } => self.write_literal(output, "uint-literal", value, size),
+            Expression::IntLiteral { value, size, .. } => self.write_literal(output, "int-literal", value, size),
+            Expression::FloatLiteral { value, size, .. } => self.write_literal(output, "float-literal", value, size),
+            Expression::BinaryOp { operator, lhs, rhs, .. } => {
+                output.push_str(&format!("(binary-op {operator} "));
+                self.write_expression(output, lhs);
+                output.push(' ');
+                self.write_expression(output, rhs);
+                output.push(')');
+            }
+            Expression::Elvis { condition, default, .. } => {
+                output.push_str("(elvis ");
+                self.write_expression(output, condition);
+                output.push(' ');
+                self.write_expression(output, default);
+                output.push(')');
+            }
+            Expression::RuntimeTypeOf { expr, .. } => {
+                output.push_str("(typeof ");
+                self.write_expression(output, expr);
+                output.push(')');
+            }
+            Expression::SafeCast { expr, typ, .. } => {
+                output.push_str("(try-as ");
+                self.write_expression(output, expr);
+                output.push(' ');
+                self.write_type(output, typ);
+                output.push(')');
+            }
+            Expression::Array { elements, .. } => {
+                output.push_str("(array");
+                self.write_list(output, elements);
+                output.push(')');
+            }
+            Expression::Tuple { elements, .. } => {
+                output.push_str("(tuple");
+                self.write_list(output, elements);
+                output.push(')');
+            }
+            Expression::ArrayLen { array, .. } => {
+                output.push_str("(len ");
+                self.write_expression(output, array);
+                output.push(')');
+            }
+            Expression::Splat { expr, .. } => {
+                output.push_str("(splat ");
+                self.write_expression(output, expr);
+                output.push(')');
+            }
+            Expression::Sizeof { typ, .. } => {
+                output.push_str("(sizeof ");
+                self.write_type(output, typ);
+                output.push(')');
+            }
+            Expression::Await { expr, .. } => {
+                output.push_str("(await ");
+                self.write_expression(output, expr);
+                output.push(')');
+            }
+            Expression::UnsafeBlock { body, .. } => {
+                output.push_str("(unsafe ");
+                self.write_expression(output, body);
+                output.push(')');
+            }
+            Expression::ComptimeBlock { body, .. } => {
+                output.push_str("(comptime ");
+                self.write_expression(output, body);
+                output.push(')');
+            }
+            Expression::Defer { body, .. } => {
+                output.push_str("(defer ");
+                self.write_expression(output, body);
+                output.push(')');
+            }
+            Expression::Ref { mutable, expr, .. } => {
+                output.push_str(if *mutable { "(ref-mut " } else { "(ref " });
+                self.write_expression(output, expr);
+                output.push(')');
+            }
+            Expression::Deref { expr, .. } => {
+                output.push_str("(deref ");
+                self.write_expression(output, expr);
+                output.push(')');
+            }
+            Expression::Not { expr, .. } => {
+                output.push_str("(not ");
+                self.write_expression(output, expr);
+                output.push(')');
+            }
+            Expression::FieldAccess { target, field, .. } => {
+                output.push_str("(field-access ");
+                self.write_expression(output, target);
+                output.push_str(&format!(" {field})"));
+            }
+            Expression::TupleAccess { tuple, index, .. } => {
+                output.push_str("(tuple-access ");
+                self.write_expression(output, tuple);
+                output.push_str(&format!(" {index})"));
+            }
+            Expression::Index { target, index, .. } => {
+                output.push_str("(index ");
+                self.write_expression(output, target);
+                output.push(' ');
+                self.write_expression(output, index);
+                output.push(')');
+            }
+            Expression::Try { expr, .. } => {
+                output.push_str("(try ");
+                self.write_expression(output, expr);
+                output.push(')');
+            }
+            Expression::Lambda {
+                parameters,
+                parameter_types,
+                return_type,
+                body,
+                ..
+            } => {
+                output.push_str("(lambda (params");
+                for (parameter, typ) in parameters.iter().zip(parameter_types) {
+                    output.push(' ');
+                    match typ {
+                        Some(typ) => {
+                            output.push_str("(arg ");
+                            self.write_expression(output, parameter);
+                            output.push(' ');
+                            self.write_type(output, typ);
+                            output.push(')');
+                        }
+                        None => self.write_expression(output, parameter),
+                    }
+                }
+                output.push(')');
+                if let Some(return_type) = return_type {
+                    output.push_str(" (returns ");
+                    self.write_type(output, return_type);
+                    output.push(')');
+                }
+                output.push(' ');
+                self.write_expression(output, body);
+                output.push(')');
+            }
+            Expression::Match { scrutinee, arms, .. } => {
+                output.push_str("(match ");
+                self.write_expression(output, scrutinee);
+                for arm in arms {
+                    output.push_str(" (arm ");
+                    self.write_pattern(output, &arm.pattern);
+                    output.push(' ');
+                    self.write_expression(output, &arm.body);
+                    output.push(')');
+                }
+                output.push(')');
+            }
+            Expression::EnumVariant { path, kind, .. } => {
+                self.write_variant(output, &path.join("::"), kind, Self::write_expression)
+            }
+            Expression::DoBlock { steps, .. } => {
+                output.push_str("(do");
+                for step in steps {
+                    output.push(' ');
+                    match step {
+                        DoStep::Bind { name, expr, .. } => {
+                            output.push_str(&format!("(bind {name} "));
+                            self.write_expression(output, expr);
+                            output.push(')');
+                        }
+                        DoStep::Seq(expr) => self.write_expression(output, expr),
+                    }
+                }
+                output.push(')');
+            }
+            Expression::Let { name, value, body, .. } => {
+                output.push_str(&format!("(let {name} "));
+                self.write_expression(output, value);
+                output.push(' ');
+                self.write_expression(output, body);
+                output.push(')');
+            }
+            Expression::Return { value, .. } => {
+                output.push_str("(return ");
+                self.write_expression(output, value);
+                output.push(')');
+            }
+            Expression::Yield { value, .. } => {
+                output.push_str("(yield");
+                if let Some(value) = value {
+                    output.push(' ');
+                    self.write_expression(output, value);
+                }
+                output.push(')');
+            }
+            Expression::StringLiteral { value, .. } => output.push_str(&format!("(string-literal {value:?})")),
+            Expression::InlineAsm {
+                template,
+                outputs,
+                inputs,
+                clobbers,
+                ..
+            } => {
+                output.push_str(&format!("(asm {template:?}"));
+                for (direction, operand) in outputs
+                    .iter()
+                    .map(|operand| ("out", operand))
+                    .chain(inputs.iter().map(|operand| ("in", operand)))
+                {
+                    output.push_str(&format!(" ({direction} {:?} ", operand.constraint));
+                    self.write_expression(output, &operand.expr);
+                    output.push(')');
+                }
+                for clobber in clobbers {
+                    output.push_str(&format!(" (clobber {clobber:?})"));
+                }
+                output.push(')');
+            }
+            Expression::Macro { name, tokens, .. } => {
+                output.push_str(&format!("(macro {name}"));
+                self.write_tokens(output, tokens);
+                output.push(')');
+            }
+            Expression::Interpolated {
+                literals, expressions, ..
+            } => {
+                // parts are written in the order they appear on the string
+                output.push_str("(interpolated");
+                for (idx, literal) in literals.iter().enumerate() {
+                    output.push(' ');
+                    self.write_expression(output, literal);
+                    if let Some(expr) = expressions.get(idx) {
+                        output.push(' ');
+                        self.write_expression(output, expr);
+                    }
+                }
+                output.push(')');
+            }
+        }
+        self.locate(output, start, expr.location());
+    }
+
+    fn write_pattern(&self, output: &mut String, pattern: &Pattern<'_>) {
+        match pattern {
+            Pattern::Wildcard { .. } => output.push('_'),
+            Pattern::Literal(literal) => self.write_expression(output, literal),
+            Pattern::Binding { name, .. } => output.push_str(&format!("(binding {name})")),
+            Pattern::Path { segments, .. } => output.push_str(&format!("(path {})", segments.join("::"))),
+            Pattern::Variant { path, kind, .. } => {
+                self.write_variant(output, &path.join("::"), kind, Self::write_pattern)
+            }
+        }
+    }
+
+    /// writes `(variant Shape::Circle 1.0)`, or `(variant Shape::Rect (field w 1))`
+    /// for a struct variant
+    fn write_variant<T>(
+        &self,
+        output: &mut String,
+        path: &str,
+        kind: &VariantKind<'_, T>,
+        write: fn(&Self, &mut String, &T),
+    ) {
+        output.push_str(&format!("(variant {path}"));
+        match kind {
+            VariantKind::Unit => {}
+            VariantKind::Tuple(elements) => {
+                for element in elements {
+                    output.push(' ');
+                    write(self, output, element);
+                }
+            }
+            VariantKind::Struct(fields) => {
+                for field in fields {
+                    output.push_str(&format!(" (field {} ", field.name));
+                    write(self, output, &field.value);
+                    output.push(')');
+                }
+            }
+        }
+        output.push(')');
+    }
+
+    fn write_list(&self, output: &mut String, expressions: &[Expression<'_>]) {
+        for expr in expressions {
+            output.push(' ');
+            self.write_expression(output, expr);
+        }
+    }
+
+    fn write_literal(
+        &self,
+        output: &mut String,
+        name: &str,
+        value: &impl std::fmt::Debug,
+        size: &Option<impl std::fmt::Display>,
+    ) {
+        output.push_str(&format!("({name} {value:?}"));
+        if let Some(size) = size {
+            output.push_str(&format!(" {size}"));
+        }
+        output.push(')');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kura_lexer::Lexer;
+
+    use super::*;
+    use crate::expression::parse_expression;
+    use crate::Parser;
+
+    #[test]
+    fn expressions_as_sexpr() {
+        let source = r#"{
+            const total: u64 = 1 + 2 * 3u8;
+            if total == 7 { print("{total}!"); } else { return -1.5; }
+        }"#;
+
+        let mut lexer = Lexer::new(source);
+        let expr = parse_expression(&mut lexer, true).unwrap();
+
+        assert_eq!(
+            to_sexpr(&expr),
+            concat!(
+                "(block ",
+                "(const total (type (type-name u64)) ",
+                "(binary-op + (uint-literal 1) (binary-op * (uint-literal 2) (uint-literal 3 u8)))) ",
+                "(tail (if (binary-op == (ident total) (uint-literal 7)) ",
+                r#"(block (call (ident print) (interpolated (string-literal "") (ident total) (string-literal "!")))) "#,
+                "(block (return (float-literal -1.5))))))",
+            )
+        );
+    }
+
+    #[test]
+    fn statements_as_sexpr() {
+        let source = "fun add(a: i32, b: Vec<*mut i32>) => (i32, [u8; 2]) { a + b }";
+        let statements = Parser::new(source, Lexer::new(source)).parse().unwrap();
+
+        assert_eq!(
+            statements_to_sexpr(&statements),
+            "(fun add (args (arg a (type-name i32)) (arg b (generic-type Vec (ptr-type mut (type-name i32))))) \
+             (returns (tuple-type (type-name i32) (array-type (type-name u8) 2))) \
+             (binary-op + (ident a) (ident b)))\n"
+        );
+    }
+
+    #[test]
+    fn locations_are_written_when_asked_for() {
+        let source = "struct P { x: u8 }\nfun f() { x + 1 }";
+        let statements = Parser::new(source, Lexer::new(source)).parse().unwrap();
+        let printer = SexprPrinter { with_locations: true };
+
+        assert_eq!(
+            printer.statements(&statements),
+            "(struct@0..18 P (field@11..16 x (type-name@14..16 u8)))\n\
+             (fun@19..36 f (args) (binary-op@29..34 + (ident@29..30 x) (uint-literal@33..34 1)))\n"
+        );
+    }
+}
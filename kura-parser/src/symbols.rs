@@ -0,0 +1,449 @@
+use kura_lexer::token::{Kind, Location, Operator, Token, Value};
+use kura_lexer::Lexer;
+
+use crate::{DoStep, Expression, Parser, Pattern, Statement};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum SymbolKind {
+    Variable,
+    Constant,
+    Function,
+    Struct,
+    Field,
+    Impl,
+    Macro,
+    Module,
+}
+
+/// a declaration in the outline of a file, shaped like the `DocumentSymbol`
+/// of the language server protocol
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// the whole declaration
+    pub range: Location,
+    /// just the name being declared
+    pub selection_range: Location,
+    /// declarations made inside of this one
+    pub children: Vec<Symbol>,
+}
+
+/// the variables declared by `expr`, which is part of `source`.
+///
+/// blocks and branches don't show up in the outline, what is declared
+/// inside of them is flattened into the closest declaration around them, so
+/// a variable declared in an `if` is a sibling of the ones before the `if`.
+/// only the value of a declaration nests, as in `var a = { var b = 1; b }`
+pub fn document_symbols(source: &str, expr: &Expression<'_>) -> Vec<Symbol> {
+    let collector = Collector::new(source);
+    let mut symbols = vec![];
+    collector.expression(expr, &mut symbols);
+    symbols
+}
+
+/// the outline of a whole file: its functions, structs and impls, with the
+/// variables declared in each function. a syntax error stops the outline
+/// at the item it is in, every item before it is still there
+pub fn file_symbols(source: &str) -> Vec<Symbol> {
+    let mut parser = Parser::new(source, Lexer::new(source));
+    let mut statements = vec![];
+    while !parser.lexer.is_empty() {
+        match parser.parse_statement() {
+            Ok(statement) => statements.push(statement),
+            Err(_) => break,
+        }
+    }
+
+    let collector = Collector::new(source);
+    let mut symbols = vec![];
+    statements
+        .iter()
+        .for_each(|statement| collector.statement(statement, &mut symbols));
+    symbols
+}
+
+struct Collector<'src> {
+    /// every token of the source, without comments, used to find the name
+    /// of each declaration
+    tokens: Vec<Token<'src>>,
+}
+
+impl<'src> Collector<'src> {
+    fn new(source: &'src str) -> Self {
+        Self {
+            tokens: Lexer::new(source).filter_map(Result::ok).collect(),
+        }
+    }
+
+    /// the identifier right after the first `keyword` at or after `start_byte`
+    fn name_after(&self, start_byte: usize, keyword: Kind<'_>) -> Option<Location> {
+        let idx = self
+            .tokens
+            .partition_point(|token| token.location.start_byte < start_byte);
+        let keyword = idx + self.tokens[idx..].iter().position(|token| token.kind == keyword)?;
+
+        match self.tokens.get(keyword + 1) {
+            Some(token) if matches!(token.kind, Kind::Value(Value::Ident(_))) => Some(token.location),
+            _ => None,
+        }
+    }
+
+    fn push(
+        &self,
+        symbols: &mut Vec<Symbol>,
+        name: &str,
+        kind: SymbolKind,
+        range: Location,
+        selection_range: Option<Location>,
+        children: Vec<Symbol>,
+    ) {
+        symbols.push(Symbol {
+            name: name.to_string(),
+            kind,
+            range,
+            // the name is always there on a tree that parsed, but an
+            // outline is still better than nothing if it isn't
+            selection_range: selection_range.unwrap_or(range),
+            children,
+        });
+    }
+
+    fn statement(&self, statement: &Statement<'_>, symbols: &mut Vec<Symbol>) {
+        match statement {
+            Statement::FunArgument { name, location, .. } => {
+                let name_location = Location::new(location.start_byte, location.start_byte + name.len());
+                self.push(symbols, name, SymbolKind::Field, *location, Some(name_location), vec![]);
+            }
+            Statement::Fun {
+                name, body, location, ..
+            } => {
+                let mut children = vec![];
+                body.iter().for_each(|expr| self.expression(expr, &mut children));
+                let selection = self.name_after(location.start_byte, Kind::Fun);
+                self.push(symbols, name, SymbolKind::Function, *location, selection, children);
+            }
+            Statement::Struct {
+                name, fields, location, ..
+            } => {
+                let mut children = vec![];
+                fields.iter().for_each(|field| self.statement(field, &mut children));
+                let selection = self.name_after(location.start_byte, Kind::Struct);
+                self.push(symbols, name, SymbolKind::Struct, *location, selection, children);
+            }
+            Statement::Impl {
+                generics,
+                trait_name,
+                target,
+                functions,
+                location,
+                ..
+            } => {
+                let mut children = vec![];
+                functions
+                    .iter()
+                    .for_each(|function| self.statement(function, &mut children));
+                // the first `>` past the last parameter closes them, as any
+                // other is inside of its bounds
+                let selection = match (trait_name, generics.last()) {
+                    (Some(_), _) => self.name_after(location.start_byte, Kind::For),
+                    (None, Some(param)) => self.name_after(param.location.end_byte, Kind::Op(Operator::Greater)),
+                    (None, None) => self.name_after(location.start_byte, Kind::Impl),
+                };
+                self.push(symbols, target, SymbolKind::Impl, *location, selection, children);
+            }
+            Statement::MacroRules { name, location, .. } => {
+                let selection = self.name_after(location.start_byte, Kind::Op(Operator::Bang));
+                self.push(symbols, name, SymbolKind::Macro, *location, selection, vec![]);
+            }
+            Statement::Mod { name, location } => {
+                let selection = self.name_after(location.start_byte, Kind::Mod);
+                self.push(symbols, name, SymbolKind::Module, *location, selection, vec![]);
+            }
+            // imports declare nothing of their own
+            Statement::Use { .. } => {}
+            Statement::Static {
+                mutable,
+                name,
+                value,
+                location,
+                ..
+            } => {
+                let (keyword, kind) = match mutable {
+                    true => (Kind::Var, SymbolKind::Variable),
+                    false => (Kind::Const, SymbolKind::Constant),
+                };
+                let mut children = vec![];
+                self.expression(value, &mut children);
+                let selection = self.name_after(location.start_byte, keyword);
+                self.push(symbols, name, kind, *location, selection, children);
+            }
+            // a renamed dependency is only known by its alias
+            Statement::ExternCrate {
+                name, alias, location, ..
+            } => {
+                let (name, selection) = match alias {
+                    Some(alias) => (alias, self.name_after(location.start_byte, Kind::identifier_from("as"))),
+                    None => (name, self.name_after(location.start_byte, Kind::Crate)),
+                };
+                self.push(symbols, name, SymbolKind::Module, *location, selection, vec![]);
+            }
+        }
+    }
+
+    fn expression(&self, expr: &Expression<'_>, symbols: &mut Vec<Symbol>) {
+        match expr {
+            Expression::Var {
+                mutable,
+                name,
+                value,
+                location,
+                ..
+            } => {
+                let (keyword, kind) = match mutable {
+                    true => (Kind::Var, SymbolKind::Variable),
+                    false => (Kind::Const, SymbolKind::Constant),
+                };
+                let mut children = vec![];
+                self.expression(value, &mut children);
+                let selection = self.name_after(location.start_byte, keyword);
+                self.push(symbols, name, kind, *location, selection, children);
+            }
+            Expression::Block { expressions, tail, .. } => {
+                expressions
+                    .iter()
+                    .chain(tail.as_deref())
+                    .for_each(|expr| self.expression(expr, symbols));
+            }
+            Expression::Assert { condition, message, .. } => {
+                self.expression(condition, symbols);
+                message.iter().for_each(|message| self.expression(message, symbols));
+            }
+            Expression::If {
+                condition,
+                truthy,
+                falsy,
+                ..
+            } => {
+                self.expression(condition, symbols);
+                self.expression(truthy, symbols);
+                falsy.iter().for_each(|branch| self.expression(branch, symbols));
+            }
+            Expression::Match { scrutinee, arms, .. } => {
+                self.expression(scrutinee, symbols);
+                for arm in arms {
+                    match &arm.pattern {
+                        Pattern::Binding { name, location } => {
+                            let mut children = vec![];
+                            self.expression(&arm.body, &mut children);
+                            self.push(
+                                symbols,
+                                name,
+                                SymbolKind::Constant,
+                                arm.location,
+                                Some(*location),
+                                children,
+                            );
+                        }
+                        pattern => {
+                            for (name, location) in pattern.bindings() {
+                                self.push(symbols, name, SymbolKind::Constant, location, None, vec![]);
+                            }
+                            self.expression(&arm.body, symbols)
+                        }
+                    }
+                }
+            }
+            Expression::Yield { value, .. } => value.iter().for_each(|value| self.expression(value, symbols)),
+            Expression::EnumVariant { kind, .. } => kind.values().for_each(|value| self.expression(value, symbols)),
+            Expression::DoBlock { steps, .. } => {
+                for step in steps {
+                    match step {
+                        DoStep::Bind { name, expr, location } => {
+                            let mut children = vec![];
+                            self.expression(expr, &mut children);
+                            let selection = Location::new(location.start_byte, location.start_byte + name.len());
+                            self.push(
+                                symbols,
+                                name,
+                                SymbolKind::Constant,
+                                *location,
+                                Some(selection),
+                                children,
+                            );
+                        }
+                        DoStep::Seq(expr) => self.expression(expr, symbols),
+                    }
+                }
+            }
+            Expression::Assign { value, .. }
+            | Expression::CompoundAssign { value, .. }
+            | Expression::Return { value, .. }
+            | Expression::Await { expr: value, .. }
+            | Expression::RuntimeTypeOf { expr: value, .. }
+            | Expression::SafeCast { expr: value, .. }
+            | Expression::ArrayLen { array: value, .. }
+            | Expression::Splat { expr: value, .. }
+            | Expression::UnsafeBlock { body: value, .. }
+            | Expression::ComptimeBlock { body: value, .. }
+            | Expression::Defer { body: value, .. }
+            | Expression::Ref { expr: value, .. }
+            | Expression::Deref { expr: value, .. }
+            | Expression::Not { expr: value, .. }
+            | Expression::FieldAccess { target: value, .. }
+            | Expression::TupleAccess { tuple: value, .. }
+            | Expression::Try { expr: value, .. }
+            | Expression::Lambda { body: value, .. } => self.expression(value, symbols),
+            Expression::FunCall { ident, arguments, .. } => {
+                self.expression(ident, symbols);
+                arguments.iter().for_each(|arg| self.expression(arg, symbols));
+            }
+            Expression::BinaryOp { lhs, rhs, .. }
+            | Expression::Index {
+                target: lhs,
+                index: rhs,
+                ..
+            }
+            | Expression::Elvis {
+                condition: lhs,
+                default: rhs,
+                ..
+            } => {
+                self.expression(lhs, symbols);
+                self.expression(rhs, symbols);
+            }
+            Expression::Let {
+                name,
+                value,
+                body,
+                location,
+            } => {
+                let mut children = vec![];
+                self.expression(value, &mut children);
+                let selection = self.name_after(location.start_byte, Kind::Let);
+                self.push(symbols, name, SymbolKind::Constant, *location, selection, children);
+                self.expression(body, symbols);
+            }
+            Expression::InlineAsm { outputs, inputs, .. } => outputs
+                .iter()
+                .chain(inputs.iter())
+                .for_each(|operand| self.expression(&operand.expr, symbols)),
+            Expression::Interpolated { expressions, .. }
+            | Expression::Array {
+                elements: expressions, ..
+            }
+            | Expression::Tuple {
+                elements: expressions, ..
+            } => {
+                expressions.iter().for_each(|expr| self.expression(expr, symbols));
+            }
+            Expression::Bool { .. }
+            | Expression::Sizeof { .. }
+            | Expression::Macro { .. }
+            | Expression::StringLiteral { .. }
+            | Expression::Ident { .. }
+            | Expression::Path { .. }
+            | Expression::UintLiteral { .. }
+            | Expression::FloatLiteral { .. }
+            | Expression::IntLiteral { .. } => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `kind name @ selection` for every symbol, children indented under it
+    fn outline(source: &str, symbols: &[Symbol], depth: usize, output: &mut String) {
+        for symbol in symbols {
+            let range = &source[symbol.range.start_byte..symbol.range.end_byte];
+            let selection = &source[symbol.selection_range.start_byte..symbol.selection_range.end_byte];
+            let first_line = range.lines().next().unwrap_or_default();
+            output.push_str(&format!(
+                "{}{:?} {} @ {selection:?} in {first_line:?}\n",
+                "  ".repeat(depth),
+                symbol.kind,
+                symbol.name
+            ));
+            outline(source, &symbol.children, depth + 1, output);
+        }
+    }
+
+    fn render(source: &str, symbols: &[Symbol]) -> String {
+        let mut output = String::new();
+        outline(source, symbols, 0, &mut output);
+        output
+    }
+
+    #[test]
+    fn nested_blocks_and_shadowed_names() {
+        let source = r#"{
+            var a = 1;
+            {
+                const a = 2;
+                if a == 2 {
+                    var /* name */ b = {
+                        var c = 3;
+                        c
+                    };
+                } else {
+                    var a = 4;
+                }
+            }
+            a
+        }"#;
+
+        let mut lexer = Lexer::new(source);
+        let expr = crate::expression::parse_expression(&mut lexer, true).unwrap();
+        let symbols = document_symbols(source, &expr);
+
+        assert_eq!(
+            render(source, &symbols),
+            [
+                "Variable a @ \"a\" in \"var a = 1\"",
+                "Constant a @ \"a\" in \"const a = 2\"",
+                "Variable b @ \"b\" in \"var /* name */ b = {\"",
+                "  Variable c @ \"c\" in \"var c = 3\"",
+                "Variable a @ \"a\" in \"var a = 4\"",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn items_of_a_file() {
+        let source = "#[derive(Debug)]\nstruct Point { x: i32, y: i32 }\n\
+                      impl Debug for Point { fun fmt(self: Point) => String { const s = \"p\"; return s; } }\n\
+                      fun main() { var p = 1; }";
+
+        assert_eq!(
+            render(source, &file_symbols(source)),
+            [
+                "Struct Point @ \"Point\" in \"#[derive(Debug)]\"",
+                "  Field x @ \"x\" in \"x: i32\"",
+                "  Field y @ \"y\" in \"y: i32\"",
+                "Impl Point @ \"Point\" in \"impl Debug for Point { fun fmt(self: Point) => String { const s = \\\"p\\\"; return s; } }\"",
+                "  Function fmt @ \"fmt\" in \"fun fmt(self: Point) => String { const s = \\\"p\\\"; return s; }\"",
+                "    Constant s @ \"s\" in \"const s = \\\"p\\\"\"",
+                "Function main @ \"main\" in \"fun main() { var p = 1; }\"",
+                "  Variable p @ \"p\" in \"var p = 1\"",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn items_before_a_syntax_error_are_kept() {
+        let source = "fun first() { var a = 1; }\nfun second() { var b = ; }\nfun third() {}";
+        let symbols = file_symbols(source);
+
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "first");
+        assert_eq!(symbols[0].children[0].name, "a");
+    }
+}
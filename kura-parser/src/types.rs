@@ -0,0 +1,575 @@
+use std::borrow::Cow;
+
+use kura_lexer::token::{Kind, Location, Operator, Primitive, Token, Value};
+use kura_lexer::{Lexer, TransposeRef};
+
+use crate::error::ParseError;
+use crate::expression::{end_of_input, parse_identifier, ParseContext};
+use crate::{GenericParam, Lifetime, LifetimeParam, TypeExpr, WherePredicate};
+
+/// parses a single type on its own, with the default options
+pub fn parse_type_expression<'parser>(lexer: &mut Lexer<'parser>) -> Result<TypeExpr<'parser>, ParseError> {
+    parse_type_expression_with(lexer, &mut ParseContext::default())
+}
+
+/// parses the type of an annotation. besides a name, a type can be a
+/// function, an array, a tuple, a pointer, a reference, `impl Trait`, `!`,
+/// or take generic arguments, none of which an expression can be
+pub(crate) fn parse_type_expression_with<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<TypeExpr<'parser>, ParseError> {
+    let mut depth = context.nested(lexer)?;
+    let context = &mut *depth;
+
+    let (kind, location) = match lexer.peek().transpose()? {
+        Some(token) => (token.kind.clone(), token.location),
+        None => return Err(ParseError::eof("expected a type", end_of_input(lexer))),
+    };
+
+    match kind {
+        Kind::Value(Value::Ident(_)) => parse_named(lexer, context),
+        Kind::SelfType if context.inside_impl() => {
+            lexer.next().transpose()?;
+            Ok(TypeExpr::Named {
+                name: Cow::Borrowed("Self"),
+                location,
+            })
+        }
+        Kind::SelfType => Err(ParseError::new(
+            "`Self` can only be used inside of an impl block",
+            location,
+        )),
+        Kind::Fun => parse_fn(lexer, context),
+        Kind::Op(Operator::LeftBracket) => parse_array(lexer, context),
+        Kind::Op(Operator::LeftParen) => parse_tuple(lexer, context),
+        Kind::Op(Operator::Star) => parse_pointer(lexer, context),
+        Kind::Op(Operator::Ampersand | Operator::And) => parse_reference(lexer, context),
+        Kind::Lifetime(_) => Ok(TypeExpr::Lifetime {
+            name: parse_lifetime(lexer)?,
+            location,
+        }),
+        Kind::Op(Operator::Bang) => {
+            lexer.next().transpose()?;
+            Ok(TypeExpr::Never { location })
+        }
+        Kind::Impl => {
+            lexer.next().transpose()?;
+            let bound = parse_type_expression_with(lexer, context)?;
+            Ok(TypeExpr::ImplTrait {
+                location: Location::new(location.start_byte, bound.location().end_byte),
+                bound: Box::new(bound),
+            })
+        }
+        kind => Err(ParseError::new(format!("expected a type, found `{kind}`"), location)),
+    }
+}
+
+/// parses types separated by commas up to `close`, which is consumed. a
+/// comma after the last type is fine, and is reported back, as it tells a
+/// tuple of a single element from a type in parenthesis
+fn parse_list<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    close: Operator,
+) -> Result<(Vec<TypeExpr<'parser>>, bool, Token<'parser>), ParseError> {
+    let mut types = vec![];
+    let mut trailing_comma = false;
+
+    loop {
+        match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(close) => break,
+            _ => types.push(parse_type_expression_with(lexer, context)?),
+        }
+
+        trailing_comma = false;
+        match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::Comma) => {
+                lexer.next().transpose()?;
+                trailing_comma = true;
+            }
+            Some(token) if token.kind == Kind::Op(close) => break,
+            Some(token) => return Err(ParseError::unexpected(token)),
+            None => break,
+        }
+    }
+
+    let close = lexer.expect(Kind::Op(close))?;
+    Ok((types, trailing_comma, close))
+}
+
+/// parses `name`, `std::io::File` or `Option<T>`
+pub(crate) fn parse_named<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<TypeExpr<'parser>, ParseError> {
+    let (ident, mut name) = parse_identifier(lexer)?;
+    let mut location = ident.location();
+
+    while let Some(token) = lexer.peek().transpose()? {
+        if token.kind != Kind::Op(Operator::ColonColon) {
+            break;
+        }
+        lexer.next().transpose()?;
+
+        let (segment, segment_name) = parse_identifier(lexer)?;
+        name = Cow::Owned(format!("{name}::{segment_name}"));
+        location.end_byte = segment.location().end_byte;
+    }
+
+    match lexer.peek().transpose()? {
+        Some(token) if token.kind == Kind::Op(Operator::Less) => {
+            lexer.next().transpose()?;
+            let (arguments, _, close) = parse_list(lexer, context, Operator::Greater)?;
+            Ok(TypeExpr::Generic {
+                name,
+                arguments,
+                location: Location::new(location.start_byte, close.location.end_byte),
+            })
+        }
+        _ => Ok(TypeExpr::Named { name, location }),
+    }
+}
+
+/// parses `<T, U: Bound + Other>`, the generic parameters of an item
+pub(crate) fn parse_generic_params<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Vec<GenericParam<'parser>>, ParseError> {
+    lexer.expect(Kind::Op(Operator::Less))?;
+    let mut params = vec![];
+
+    loop {
+        if let Some(token) = lexer.peek().transpose()? {
+            if token.kind == Kind::Op(Operator::Greater) {
+                break;
+            }
+        }
+
+        let (ident, name) = parse_identifier(lexer)?;
+        let mut location = ident.location();
+        let mut bounds = vec![];
+        if let Some(token) = lexer.peek().transpose()? {
+            if token.kind == Kind::Op(Operator::Colon) {
+                lexer.next().transpose()?;
+                bounds = parse_bounds(lexer, context)?;
+                location.end_byte = bounds
+                    .last()
+                    .map_or(location.end_byte, |bound| bound.location().end_byte);
+            }
+        }
+        params.push(GenericParam { name, bounds, location });
+
+        match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::Comma) => {
+                lexer.next().transpose()?;
+            }
+            Some(token) if token.kind == Kind::Op(Operator::Greater) => break,
+            Some(token) => return Err(ParseError::unexpected(token)),
+            None => break,
+        }
+    }
+
+    lexer.expect(Kind::Op(Operator::Greater))?;
+    Ok(params)
+}
+
+/// parses `Show + Debug`, the bounds after the colon of a generic parameter
+/// or of a where predicate
+fn parse_bounds<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Vec<TypeExpr<'parser>>, ParseError> {
+    let mut bounds = vec![];
+    loop {
+        bounds.push(parse_bound(lexer, context)?);
+        match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::Plus) => lexer.next().transpose()?,
+            _ => break,
+        };
+    }
+    Ok(bounds)
+}
+
+/// parses a single bound, which is any type, or a trait written like the
+/// signature of a function, as in `Fn(&'a i32) -> &'a i32`
+fn parse_bound<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<TypeExpr<'parser>, ParseError> {
+    let bound = parse_type_expression_with(lexer, context)?;
+    let TypeExpr::Named { name, location } = bound else {
+        return Ok(bound);
+    };
+    match lexer.peek().transpose()? {
+        Some(token) if token.kind == Kind::Op(Operator::LeftParen) => lexer.next().transpose()?,
+        _ => return Ok(TypeExpr::Named { name, location }),
+    };
+
+    let (parameters, _, close) = parse_list(lexer, context, Operator::RightParen)?;
+    let returns = match lexer.peek().transpose()? {
+        Some(token) if token.kind == Kind::Op(Operator::ThinArrow) => {
+            lexer.next().transpose()?;
+            Some(Box::new(parse_type_expression_with(lexer, context)?))
+        }
+        _ => None,
+    };
+
+    let end_byte = returns
+        .as_ref()
+        .map_or(close.location.end_byte, |returns| returns.location().end_byte);
+    Ok(TypeExpr::FnTrait {
+        name,
+        parameters,
+        returns,
+        location: Location::new(location.start_byte, end_byte),
+    })
+}
+
+/// parses `where T: Bound, for<'a> U: Fn(&'a i32)`, up to the brace that
+/// follows it, which is not consumed. a comma after the last predicate is
+/// fine
+pub(crate) fn parse_where_clause<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<Vec<WherePredicate<'parser>>, ParseError> {
+    lexer.expect(Kind::Where)?;
+    let mut predicates = vec![];
+
+    loop {
+        let start = match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::LeftBrace) && !predicates.is_empty() => break,
+            Some(token) => token.location.start_byte,
+            None => return Err(ParseError::eof("expected a where predicate", end_of_input(lexer))),
+        };
+
+        let lifetimes = match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::For => parse_for_lifetimes(lexer)?,
+            _ => vec![],
+        };
+        let bounded = parse_type_expression_with(lexer, context)?;
+        lexer.expect(Kind::Op(Operator::Colon))?;
+        let bounds = parse_bounds(lexer, context)?;
+
+        let end_byte = bounds
+            .last()
+            .map_or(bounded.location().end_byte, |bound| bound.location().end_byte);
+        predicates.push(WherePredicate {
+            lifetimes,
+            bounded,
+            bounds,
+            location: Location::new(start, end_byte),
+        });
+
+        match lexer.peek().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::Comma) => {
+                lexer.next().transpose()?;
+            }
+            _ => break,
+        }
+    }
+
+    Ok(predicates)
+}
+
+/// parses the `for<'a, 'b>` of a higher-ranked where predicate
+fn parse_for_lifetimes<'parser>(lexer: &mut Lexer<'parser>) -> Result<Vec<LifetimeParam<'parser>>, ParseError> {
+    lexer.expect(Kind::For)?;
+    lexer.expect(Kind::Op(Operator::Less))?;
+    let mut lifetimes = vec![];
+
+    loop {
+        match lexer.next().transpose()? {
+            Some(Token {
+                kind: Kind::Lifetime(name),
+                location,
+            }) => lifetimes.push(LifetimeParam { name, location }),
+            Some(token) if token.kind == Kind::Op(Operator::Greater) && !lifetimes.is_empty() => break,
+            Some(token) => {
+                return Err(ParseError::new(
+                    format!("expected a lifetime, found `{}`", token.kind),
+                    token.location,
+                ))
+            }
+            None => return Err(ParseError::eof("expected a lifetime", end_of_input(lexer))),
+        }
+
+        match lexer.next().transpose()? {
+            Some(token) if token.kind == Kind::Op(Operator::Comma) => {}
+            Some(token) if token.kind == Kind::Op(Operator::Greater) => break,
+            Some(token) => return Err(ParseError::unexpected(&token)),
+            None => return Err(ParseError::eof("expected `>`", end_of_input(lexer))),
+        }
+    }
+
+    Ok(lifetimes)
+}
+
+/// parses `fun(i32, bool) => bool`
+fn parse_fn<'parser>(lexer: &mut Lexer<'parser>, context: &mut ParseContext<'parser>) -> Result<TypeExpr<'parser>, ParseError> {
+    let keyword = lexer.expect(Kind::Fun)?;
+    lexer.expect(Kind::Op(Operator::LeftParen))?;
+    let (parameters, _, close) = parse_list(lexer, context, Operator::RightParen)?;
+
+    let returns = match lexer.peek().transpose()? {
+        Some(token) if token.kind == Kind::Op(Operator::ThickArrow) => {
+            lexer.next().transpose()?;
+            Some(Box::new(parse_type_expression_with(lexer, context)?))
+        }
+        _ => None,
+    };
+
+    let end_byte = returns
+        .as_ref()
+        .map_or(close.location.end_byte, |returns| returns.location().end_byte);
+    Ok(TypeExpr::Fn {
+        parameters,
+        returns,
+        location: Location::new(keyword.location.start_byte, end_byte),
+    })
+}
+
+/// parses `[i32; 10]`
+fn parse_array<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<TypeExpr<'parser>, ParseError> {
+    let open = lexer.expect(Kind::Op(Operator::LeftBracket))?;
+    let element = parse_type_expression_with(lexer, context)?;
+    lexer.expect(Kind::Op(Operator::SemiColon))?;
+
+    let length = match lexer.next().transpose()? {
+        Some(Token {
+            kind: Kind::Value(Value::Primitive(Primitive::UInt { value, .. })),
+            ..
+        }) => value,
+        Some(token) => {
+            return Err(ParseError::new(
+                format!("expected the length of the array, found `{}`", token.kind),
+                token.location,
+            ))
+        }
+        None => return Err(ParseError::eof("expected the length of the array", end_of_input(lexer))),
+    };
+
+    let close = lexer.expect(Kind::Op(Operator::RightBracket))?;
+    Ok(TypeExpr::Array {
+        element: Box::new(element),
+        length,
+        location: Location::new(open.location.start_byte, close.location.end_byte),
+    })
+}
+
+/// parses `(i32, f64)`, or a type in parenthesis
+fn parse_tuple<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<TypeExpr<'parser>, ParseError> {
+    let open = lexer.expect(Kind::Op(Operator::LeftParen))?;
+    let (mut elements, trailing_comma, close) = parse_list(lexer, context, Operator::RightParen)?;
+
+    match (elements.len(), trailing_comma) {
+        (1, false) => Ok(elements.remove(0)),
+        _ => Ok(TypeExpr::Tuple {
+            elements,
+            location: Location::new(open.location.start_byte, close.location.end_byte),
+        }),
+    }
+}
+
+/// parses `*const i32` or `*mut i32`
+fn parse_pointer<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<TypeExpr<'parser>, ParseError> {
+    let star = lexer.expect(Kind::Op(Operator::Star))?;
+    let mutable = match lexer.next().transpose()? {
+        Some(token) if token.kind == Kind::Const => false,
+        Some(Token {
+            kind: Kind::Value(Value::Ident(name)),
+            ..
+        }) if name == "mut" => true,
+        Some(token) => {
+            return Err(ParseError::new(
+                format!("expected `const` or `mut` after `*`, found `{}`", token.kind),
+                token.location,
+            ))
+        }
+        None => {
+            return Err(ParseError::eof(
+                "expected `const` or `mut` after `*`",
+                end_of_input(lexer),
+            ))
+        }
+    };
+
+    let pointee = parse_type_expression_with(lexer, context)?;
+    Ok(TypeExpr::Ptr {
+        mutable,
+        location: Location::new(star.location.start_byte, pointee.location().end_byte),
+        pointee: Box::new(pointee),
+    })
+}
+
+/// parses `'a`, giving back its name without the quote
+fn parse_lifetime<'parser>(lexer: &mut Lexer<'parser>) -> Result<Cow<'parser, str>, ParseError> {
+    match lexer.next().transpose()? {
+        Some(Token {
+            kind: Kind::Lifetime(name),
+            ..
+        }) => Ok(name),
+        Some(token) => Err(ParseError::new(
+            format!("expected a lifetime, found `{}`", token.kind),
+            token.location,
+        )),
+        None => Err(ParseError::eof("expected a lifetime", end_of_input(lexer))),
+    }
+}
+
+/// parses `&T`, `&mut T`, `&'a T` or `&'a mut T`. `&&T` is lexed as a
+/// single `&&`, and is a reference to a reference
+fn parse_reference<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+) -> Result<TypeExpr<'parser>, ParseError> {
+    match lexer.next().transpose()? {
+        Some(token) if token.kind == Kind::Op(Operator::And) => {
+            let start_byte = token.location.start_byte;
+            let referent = parse_referent(lexer, context, start_byte + 1)?;
+            Ok(TypeExpr::Ref {
+                mutable: false,
+                lifetime: Lifetime::Elided,
+                location: Location::new(start_byte, referent.location().end_byte),
+                referent: Box::new(referent),
+            })
+        }
+        Some(token) => parse_referent(lexer, context, token.location.start_byte),
+        None => Err(ParseError::eof("expected a reference", end_of_input(lexer))),
+    }
+}
+
+/// parses what follows the `&` of a reference starting at `start_byte`
+fn parse_referent<'parser>(
+    lexer: &mut Lexer<'parser>,
+    context: &mut ParseContext<'parser>,
+    start_byte: usize,
+) -> Result<TypeExpr<'parser>, ParseError> {
+    let lifetime = match lexer.peek().transpose()? {
+        Some(token) if matches!(token.kind, Kind::Lifetime(_)) => Lifetime::Named(parse_lifetime(lexer)?),
+        _ => Lifetime::Elided,
+    };
+    let mutable = match lexer.peek().transpose()? {
+        Some(Token {
+            kind: Kind::Value(Value::Ident(name)),
+            ..
+        }) if name == "mut" => {
+            lexer.next().transpose()?;
+            true
+        }
+        _ => false,
+    };
+
+    let referent = parse_type_expression_with(lexer, context)?;
+    Ok(TypeExpr::Ref {
+        mutable,
+        lifetime,
+        location: Location::new(start_byte, referent.location().end_byte),
+        referent: Box::new(referent),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> TypeExpr<'_> {
+        let mut lexer = Lexer::new(source);
+        match parse_type_expression(&mut lexer) {
+            Ok(typ) => typ,
+            Err(e) => panic!("{source}: {e:?}"),
+        }
+    }
+
+    #[test]
+    fn every_kind_of_type() {
+        let types = [
+            "i32",
+            "std::io::File",
+            "fun(i32, bool) => bool",
+            "fun()",
+            "[i32; 10]",
+            "(i32, f64)",
+            "()",
+            "(i32,)",
+            "*mut i32",
+            "*const [u8; 4]",
+            "Option<T>",
+            "Result<Vec<i32>, fun() => ()>",
+            "impl Iterator<i32>",
+            "&i32",
+            "&mut [u8; 4]",
+            "&'a T",
+            "&'a mut Option<&'b T>",
+            "&&i32",
+            "!",
+            "fun(i32) => !",
+        ];
+        for source in types {
+            let typ = parse(source);
+            assert_eq!(typ.to_string(), source);
+            assert_eq!(typ.location(), Location::new(0, source.len()), "{source}");
+        }
+
+        // parenthesis only group, a tuple of one element needs a comma
+        assert_eq!(
+            parse("(i32)"),
+            TypeExpr::Named {
+                name: Cow::Borrowed("i32"),
+                location: Location::new(1, 4),
+            }
+        );
+        insta::assert_debug_snapshot!(parse("fun(*mut u8, [i32; 2]) => Option<(i32, bool)>"));
+    }
+
+    #[test]
+    fn lifetimes_of_references() {
+        let lifetime = |source| match parse(source) {
+            TypeExpr::Ref { mutable, lifetime, .. } => (mutable, lifetime),
+            typ => panic!("expected a reference, found {typ:?}"),
+        };
+        assert_eq!(lifetime("&T"), (false, Lifetime::Elided));
+        assert_eq!(lifetime("&mut T"), (true, Lifetime::Elided));
+        assert_eq!(lifetime("&'a T"), (false, Lifetime::Named(Cow::Borrowed("a"))));
+        assert_eq!(
+            lifetime("&'static mut T"),
+            (true, Lifetime::Named(Cow::Borrowed("static")))
+        );
+        // the inner reference of `&&T` has no lifetime either
+        let TypeExpr::Ref { referent, .. } = parse("&&T") else {
+            panic!("expected a reference");
+        };
+        assert!(matches!(
+            *referent,
+            TypeExpr::Ref {
+                lifetime: Lifetime::Elided,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn malformed_types() {
+        let error = |source: &str| {
+            let mut lexer = Lexer::new(source);
+            parse_type_expression(&mut lexer).unwrap_err()
+        };
+
+        assert_eq!(
+            error("*i32").message,
+            "expected `const` or `mut` after `*`, found `i32`"
+        );
+        assert_eq!(error("[i32; n]").message, "expected the length of the array, found `n`");
+        assert_eq!(error("1").message, "expected a type, found `1`");
+        assert!(error("Option<i32").is_incomplete());
+    }
+}
@@ -0,0 +1,84 @@
+use miette::{Diagnostic, SourceSpan};
+use thiserror::Error;
+
+use crate::constant::UnverifiedConst;
+
+/// a warning found while parsing, which doesn't stop the parse
+#[derive(Debug, Clone, PartialEq, Error, Diagnostic)]
+pub enum DiagnosticWarning {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    UnverifiedConst(#[from] UnverifiedConst),
+    /// two attributes asking for opposite things, like `#[inline(always)]`
+    /// and `#[inline(never)]` on the same function
+    #[error("conflicting `{name}` attributes")]
+    #[diagnostic(
+        code(kura::conflicting_attributes),
+        severity(Warning),
+        help("only the first one is kept")
+    )]
+    ConflictingAttributes {
+        name: String,
+        #[label("first here")]
+        first: SourceSpan,
+        #[label("conflicts with this one")]
+        second: SourceSpan,
+    },
+    /// a `defer` used as a value, like `var a = defer { .. }`, rather than
+    /// as an expression of a block, which it would run at the end of
+    #[error("`defer` outside of a block")]
+    #[diagnostic(
+        code(kura::defer_outside_block),
+        severity(Warning),
+        help("write it as an expression of the block it should run at the end of")
+    )]
+    DeferOutsideBlock {
+        #[label("deferred here")]
+        span: SourceSpan,
+    },
+    /// `use path::*`, which brings names into scope without saying which
+    #[error("glob import from `{path}`")]
+    #[diagnostic(
+        code(kura::glob_import),
+        severity(Warning),
+        help("name the items to import, or add `#[allow(glob_import)]` to the `use`")
+    )]
+    GlobImport {
+        path: String,
+        #[label("imports everything from `{path}`")]
+        span: SourceSpan,
+    },
+    /// a format string taking more or fewer positional arguments than it is
+    /// given, like `format!("{} {}", a)`
+    #[error(
+        "the format string takes {placeholders} {} but {arguments} {} given",
+        plural(*.placeholders, "argument", "arguments"),
+        plural(*.arguments, "was", "were")
+    )]
+    #[diagnostic(code(kura::format_arg_count_mismatch), severity(Warning))]
+    FormatArgCountMismatch {
+        placeholders: usize,
+        arguments: usize,
+        #[label("in this format string")]
+        span: SourceSpan,
+    },
+    /// a `{name}` of a format string naming none of its arguments
+    #[error("there is no argument named `{name}`")]
+    #[diagnostic(
+        code(kura::unknown_format_argument),
+        severity(Warning),
+        help("pass it as an argument, like `{name}` or `{name} = value`")
+    )]
+    UnknownFormatArgument {
+        name: String,
+        #[label("used here")]
+        span: SourceSpan,
+    },
+}
+
+fn plural(count: usize, one: &'static str, many: &'static str) -> &'static str {
+    match count {
+        1 => one,
+        _ => many,
+    }
+}
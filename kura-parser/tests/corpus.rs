@@ -0,0 +1,62 @@
+use std::path::Path;
+
+use kura_lexer::Lexer;
+use kura_parser::sexpr::statements_to_sexpr;
+use kura_parser::Parser;
+use miette::{NamedSource, NarratableReportHandler, Report};
+
+// every file in `corpus/ok` must parse, and every file in `corpus/err` must
+// fail to. the tree of the first and the rendered error of the second are
+// snapshotted under `corpus/snapshots`, named after the file they come
+// from. run with `INSTA_UPDATE=always` to accept changes to them
+#[test]
+fn corpus() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut checked = 0;
+
+    for (directory, should_parse) in [("ok", true), ("err", false)] {
+        let mut paths = std::fs::read_dir(corpus.join(directory))
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "rmb"))
+            .collect::<Vec<_>>();
+        paths.sort();
+
+        for path in paths {
+            let name = format!("{directory}/{}", path.file_stem().unwrap().to_string_lossy());
+            let source = std::fs::read_to_string(&path).unwrap();
+
+            let output = match (Parser::new(&source, Lexer::new(&source)).parse(), should_parse) {
+                (Ok(statements), true) => statements_to_sexpr(&statements),
+                (Err(error), false) => render(&name, &source, Report::new(error)),
+                (Ok(_), false) => panic!("{name} should not parse"),
+                (Err(error), true) => panic!(
+                    "{name} should parse, but: {}",
+                    render(&name, &source, Report::new(error))
+                ),
+            };
+
+            insta::with_settings!({
+                snapshot_path => corpus.join("snapshots"),
+                prepend_module_to_snapshot => false,
+                input_file => &path,
+                omit_expression => true,
+            }, {
+                insta::assert_snapshot!(name.replace('/', "__"), output);
+            });
+
+            checked += 1;
+        }
+    }
+
+    assert!(checked > 0, "no fixtures found in {}", corpus.display());
+}
+
+fn render(name: &str, source: &str, report: Report) -> String {
+    let report = report.with_source_code(NamedSource::new(name, source.to_string()));
+    let mut rendered = String::new();
+    NarratableReportHandler::new()
+        .render_report(&mut rendered, report.as_ref())
+        .unwrap();
+    rendered
+}
@@ -0,0 +1,144 @@
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use kura_lexer::Lexer;
+use kura_parser::Parser;
+use proptest::prelude::*;
+
+/// pieces of the language glued together at random, which gets much deeper
+/// into the parser than random bytes do
+const FRAGMENTS: &[&str] = &[
+    "fun",
+    "var",
+    "const",
+    "if",
+    "else",
+    "return",
+    "match",
+    "struct",
+    "enum",
+    "true",
+    "main",
+    "a",
+    "b",
+    "std",
+    "::",
+    "(",
+    ")",
+    "[",
+    "]",
+    "{",
+    "}",
+    ",",
+    ".",
+    "await",
+    ";",
+    ":",
+    "=",
+    "==",
+    "!=",
+    "=>",
+    "+",
+    "-",
+    "*",
+    "/",
+    "+=",
+    "&&",
+    "||",
+    "!",
+    "<",
+    ">=",
+    "#",
+    "#!",
+    "1",
+    "-2",
+    "3.5",
+    "4u8",
+    "1.2.3",
+    "99999999999999999999",
+    "\"",
+    "\"s\"",
+    "\"{a}\"",
+    "\"{\"",
+    "r\"",
+    "r##\"",
+    "\"#",
+    "\\",
+    "//",
+    "/*",
+    "*/",
+    "\n",
+    " ",
+    "é",
+    "@",
+    "\u{feff}",
+];
+
+/// parses `source` every way the parser can be entered, failing if that
+/// panics or takes too long, which is how a loop that doesn't advance shows
+fn parse_everything(source: String) {
+    let (sender, receiver) = mpsc::channel();
+    let description = format!("{source:?}");
+
+    let worker = thread::spawn(move || {
+        let _ = Parser::new(&source, Lexer::new(&source)).parse();
+        let _ = Parser::parse_repl_line(&source);
+        let _ = sender.send(());
+    });
+
+    match receiver.recv_timeout(Duration::from_secs(5)) {
+        Ok(()) => worker.join().expect("the worker finished"),
+        Err(mpsc::RecvTimeoutError::Disconnected) => panic!("parsing {description} panicked"),
+        Err(mpsc::RecvTimeoutError::Timeout) => panic!("parsing {description} never finished"),
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn arbitrary_bytes_never_panic(bytes in prop::collection::vec(any::<u8>(), 0..256)) {
+        parse_everything(String::from_utf8_lossy(&bytes).into_owned());
+    }
+
+    #[test]
+    fn arbitrary_strings_never_panic(source in any::<String>()) {
+        parse_everything(source);
+    }
+
+    #[test]
+    fn token_soup_never_panics(
+        fragments in prop::collection::vec(prop::sample::select(FRAGMENTS), 0..64),
+        separator in prop::sample::select(&["", " ", "\n"][..]),
+    ) {
+        parse_everything(fragments.join(separator));
+    }
+
+    #[test]
+    fn truncated_programs_never_panic(seed in any::<u64>(), cut in 0.0..1.0f64) {
+        let mut source = kura_support::program(seed, 512);
+        let mut end = (source.len() as f64 * cut) as usize;
+        while !source.is_char_boundary(end) {
+            end -= 1;
+        }
+        source.truncate(end);
+        parse_everything(source);
+    }
+}
+
+// inputs that made the parser panic, overflow its stack or loop forever
+#[test]
+fn fuzz_regressions() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fuzz_regressions");
+    let mut checked = 0;
+
+    for entry in std::fs::read_dir(directory).unwrap() {
+        let bytes = std::fs::read(entry.unwrap().path()).unwrap();
+        parse_everything(String::from_utf8_lossy(&bytes).into_owned());
+        checked += 1;
+    }
+
+    assert!(checked > 0, "no regressions found");
+}
@@ -0,0 +1,35 @@
+use std::path::Path;
+
+use kura_parser::cst::parse_lossless;
+
+// the concrete syntax tree of every fixture in the workspace must give the
+// fixture back byte for byte, including the ones that don't parse
+#[test]
+fn fixtures_round_trip() {
+    let workspace = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let directories = [
+        "kura-parser/tests/fixtures",
+        "kura-parser/tests/corpus/ok",
+        "kura-parser/tests/corpus/err",
+        "kura-fmt/tests/fixtures",
+        "kura-cli/tests/fixtures",
+    ];
+    let mut checked = 0;
+
+    for directory in directories {
+        for entry in std::fs::read_dir(workspace.join(directory)).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|extension| extension != "rmb") {
+                continue;
+            }
+
+            let source = std::fs::read_to_string(&path).unwrap();
+            let cst = parse_lossless(&source);
+            assert_eq!(cst.text(), source, "{} did not round trip", path.display());
+
+            checked += 1;
+        }
+    }
+
+    assert!(checked > 0, "no fixtures found");
+}
@@ -0,0 +1,32 @@
+use kura_lexer::Lexer;
+use kura_parser::{Parser, MAX_NESTING_DEPTH};
+use kura_support::workloads;
+
+// the inputs of `benches/parse.rs`, kept small so they run on every test run
+// and a change to the language can't leave the benchmarks measuring errors
+#[test]
+fn benchmark_inputs_parse_as_expected() {
+    for workload in workloads(16 * 1024, MAX_NESTING_DEPTH / 2) {
+        assert!(workload.source.len() >= 16 * 1024, "{} is too small", workload.name);
+        assert!(
+            Lexer::new(&workload.source).all(|token| token.is_ok()),
+            "{} doesn't lex",
+            workload.name
+        );
+
+        let parsed = Parser::new(&workload.source, Lexer::new(&workload.source)).parse();
+        match (parsed, workload.valid) {
+            (Ok(statements), true) => assert!(!statements.is_empty(), "{} is empty", workload.name),
+            (Err(error), false) => {
+                let offset = error.span.offset();
+                assert!(
+                    offset > workload.source.len() - 64,
+                    "{} failed at {offset}, not near its end",
+                    workload.name
+                );
+            }
+            (Ok(_), false) => panic!("{} should not parse", workload.name),
+            (Err(error), true) => panic!("{} should parse: {error:?}", workload.name),
+        }
+    }
+}
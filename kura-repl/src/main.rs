@@ -10,7 +10,7 @@ fn main() -> Result<(), std::io::Error> {
             break Ok(());
         }
         let lexer = Lexer::new(&command);
-        let parser = Parser::new(&command, lexer);
+        let mut parser = Parser::new(&command, lexer);
         let statements = parser.parse().unwrap();
         let tc = Typer::new(statements);
         let typed_statements = tc.type_check().unwrap();
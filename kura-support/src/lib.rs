@@ -0,0 +1,162 @@
+use std::fmt::Write;
+
+const NAMES: &[&str] = &["total", "count", "value", "left", "right", "acc", "step", "limit_of"];
+
+const COMMENTS: &[&str] = &[
+    "// keeps the running total",
+    "// TODO: handle negative values",
+    "/* not reached on the first call */",
+    "// see `step` for the other half",
+];
+
+/// a xorshift generator, so the same seed always builds the same input
+/// without every user pulling in a crate for randomness
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // a state of zero would stay zero forever
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, bound: usize) -> usize {
+        (self.next() % bound as u64) as usize
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len())]
+    }
+}
+
+/// an input the benchmarks measure, and the smoke test checks
+#[derive(Debug, Clone)]
+pub struct Workload {
+    pub name: &'static str,
+    pub source: String,
+    /// whether parsing the source should succeed
+    pub valid: bool,
+}
+
+/// every input the benchmarks run, each of roughly `bytes` bytes, with
+/// expressions nested `depth` levels deep in the pathological one
+pub fn workloads(bytes: usize, depth: usize) -> Vec<Workload> {
+    vec![
+        Workload {
+            name: "program",
+            source: program(0x5eed, bytes),
+            valid: true,
+        },
+        Workload {
+            name: "nested",
+            source: nested(depth, bytes),
+            valid: true,
+        },
+        Workload {
+            name: "comments",
+            source: mostly_comments(0x5eed, bytes),
+            valid: true,
+        },
+        Workload {
+            name: "error_at_end",
+            source: error_at_end(0x5eed, bytes),
+            valid: false,
+        },
+    ]
+}
+
+/// a valid program of at least `bytes` bytes, made of functions using
+/// most of the language. the same seed always gives the same program
+pub fn program(seed: u64, bytes: usize) -> String {
+    let mut rng = Rng::new(seed);
+    let mut source = String::with_capacity(bytes + 512);
+
+    let mut idx = 0;
+    while source.len() < bytes {
+        function(&mut rng, idx, &mut source);
+        idx += 1;
+    }
+
+    source
+}
+
+fn function(rng: &mut Rng, idx: usize, source: &mut String) {
+    let name = rng.pick(NAMES);
+    let callee = rng.pick(NAMES);
+    let comment = rng.pick(COMMENTS);
+    let (n, m) = (rng.below(10_000), rng.below(100));
+
+    if rng.below(4) == 0 {
+        let _ = writeln!(source, "#[derive(Debug)]\nstruct {name}_{idx} {{ a: i32, b: f64 }}\n");
+    }
+
+    let _ = writeln!(
+        source,
+        r#"fun {name}_{idx}(a: i32, b: i32) => i32 {{
+    {comment}
+    const limit = {n} * 2 + 1;
+    var {name} = a + b * {m};
+    if {name} == limit {{
+        {name} = {name} - 1;
+    }} else if {name} != 0 {{
+        {name} = {callee}({name}, "{name} is {{{name}}}").await;
+    }} else {{
+        print!("{{}}", {name});
+    }}
+    return {name};
+}}
+"#
+    );
+}
+
+/// functions holding expressions nested `depth` levels deep, like
+/// `((1 + 1) + 1)`, until there are at least `bytes` bytes of them
+pub fn nested(depth: usize, bytes: usize) -> String {
+    let mut source = String::with_capacity(bytes + 512);
+
+    let mut idx = 0;
+    while source.len() < bytes {
+        let _ = writeln!(
+            source,
+            "fun nested_{idx}() {{\n    var a = {}1{};\n}}\n",
+            "(".repeat(depth),
+            " + 1)".repeat(depth)
+        );
+        idx += 1;
+    }
+
+    source
+}
+
+/// a program that is mostly comments and blank lines, with a small
+/// function every now and then
+pub fn mostly_comments(seed: u64, bytes: usize) -> String {
+    let mut rng = Rng::new(seed);
+    let mut source = String::with_capacity(bytes + 512);
+
+    let mut idx = 0;
+    while source.len() < bytes {
+        for _ in 0..20 {
+            source.push_str(rng.pick(COMMENTS));
+            source.push_str(rng.pick(&["\n", "\n\n", "\n    \n"]));
+        }
+        let _ = writeln!(source, "/*\n * a block\n * comment\n */\nfun commented_{idx}() {{}}\n");
+        idx += 1;
+    }
+
+    source
+}
+
+/// a valid program of at least `bytes` bytes, with a syntax error in the
+/// last function
+pub fn error_at_end(seed: u64, bytes: usize) -> String {
+    let mut source = program(seed, bytes);
+    source.push_str("fun broken(a: i32) {\n    var a = ;\n}\n");
+    source
+}
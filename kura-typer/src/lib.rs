@@ -15,6 +15,13 @@ impl<'typer> Typer<'typer> {
             match statement {
                 Statement::Fun { .. } => {}
                 Statement::FunArgument { .. } => {}
+                Statement::Struct { .. } => {}
+                Statement::Impl { .. } => {}
+                Statement::MacroRules { .. }
+                | Statement::Mod { .. }
+                | Statement::ExternCrate { .. }
+                | Statement::Use { .. }
+                | Statement::Static { .. } => {}
             }
         }
 
@@ -0,0 +1,317 @@
+use std::panic::{catch_unwind, UnwindSafe};
+
+use kura_lexer::token::{Kind, Primitive, Value};
+use kura_lexer::Lexer;
+use kura_parser::passes::{check_comptime, lint_all};
+use kura_parser::{Parser, Statement};
+use miette::Report;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// result of parsing a source file. the ast is only present when the source
+/// is free of syntax errors, warnings are reported alongside it
+#[derive(Debug, Serialize)]
+pub struct Analysis<'src> {
+    pub ast: Option<Vec<Statement<'src>>>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    pub message: String,
+    pub code: Option<String>,
+    pub severity: Severity,
+    pub labels: Vec<Label>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Advice,
+}
+
+/// a span of the source the diagnostic points at, in bytes
+#[derive(Debug, Serialize)]
+pub struct Label {
+    pub message: Option<String>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// a token classified for syntax highlighting, in bytes
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct HighlightToken {
+    pub kind: HighlightKind,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HighlightKind {
+    Keyword,
+    Identifier,
+    Number,
+    String,
+    Operator,
+    Comment,
+    /// the part of the source the lexer failed on, nothing after it is
+    /// highlighted
+    Error,
+}
+
+impl Diagnostic {
+    fn from_diagnostic(diagnostic: &dyn miette::Diagnostic) -> Self {
+        let severity = match diagnostic.severity().unwrap_or(miette::Severity::Error) {
+            miette::Severity::Error => Severity::Error,
+            miette::Severity::Warning => Severity::Warning,
+            miette::Severity::Advice => Severity::Advice,
+        };
+
+        Self {
+            message: diagnostic.to_string(),
+            code: diagnostic.code().map(|code| code.to_string()),
+            severity,
+            labels: diagnostic
+                .labels()
+                .into_iter()
+                .flatten()
+                .map(|label| Label {
+                    message: label.label().map(String::from),
+                    start: label.offset(),
+                    end: label.offset() + label.len(),
+                })
+                .collect(),
+        }
+    }
+
+    /// reported instead of aborting when analysis panics, covering the whole
+    /// source as there is no telling which part caused it
+    fn internal_error(source: &str) -> Self {
+        Self {
+            message: String::from("internal error while analyzing the source"),
+            code: Some(String::from("kura::internal_error")),
+            severity: Severity::Error,
+            labels: vec![Label {
+                message: None,
+                start: 0,
+                end: source.len(),
+            }],
+        }
+    }
+}
+
+/// parses `source` and runs the lints over it. this is what the `parse`
+/// binding serializes, kept apart so it can be used and tested natively
+pub fn analyze(source: &str) -> Analysis<'_> {
+    let (statements, warnings) = match Parser::new(source, Lexer::new(source)).parse_with_warnings() {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            return Analysis {
+                ast: None,
+                diagnostics: vec![Diagnostic::from_diagnostic(&error)],
+            }
+        }
+    };
+
+    let diagnostics = check_comptime(&statements)
+        .into_iter()
+        .chain(warnings.into_iter().map(Report::new))
+        .chain(lint_all(&statements))
+        .map(|report| Diagnostic::from_diagnostic(report.as_ref()))
+        .collect();
+
+    Analysis {
+        ast: Some(statements),
+        diagnostics,
+    }
+}
+
+/// splits `source` into tokens for highlighting, comments included. lexing
+/// stops at the first error, which is reported as an `Error` token
+pub fn highlight(source: &str) -> Vec<HighlightToken> {
+    let mut tokens = vec![];
+
+    for token in Lexer::new(source).preserve_comments(true) {
+        let token = match token {
+            Ok(token) => token,
+            Err(error) => {
+                let location = error.location();
+                tokens.push(HighlightToken {
+                    kind: HighlightKind::Error,
+                    start: location.start_byte,
+                    end: location.end_byte,
+                });
+                break;
+            }
+        };
+
+        let kind = match token.kind {
+            Kind::Value(Value::Ident(_)) | Kind::Lifetime(_) => HighlightKind::Identifier,
+            Kind::Value(Value::String(_) | Value::RawString { .. }) => HighlightKind::String,
+            Kind::Value(Value::Primitive(Primitive::Bool(_))) => HighlightKind::Keyword,
+            Kind::Value(Value::Primitive(_)) => HighlightKind::Number,
+            Kind::Op(_) => HighlightKind::Operator,
+            Kind::LineComment(_) | Kind::BlockComment(_) | Kind::Shebang(_) => HighlightKind::Comment,
+            Kind::Var
+            | Kind::Const
+            | Kind::Match
+            | Kind::If
+            | Kind::Else
+            | Kind::Fun
+            | Kind::Struct
+            | Kind::Enum
+            | Kind::Impl
+            | Kind::For
+            | Kind::Let
+            | Kind::In
+            | Kind::Unsafe
+            | Kind::Assert
+            | Kind::Mod
+            | Kind::Extern
+            | Kind::Crate
+            | Kind::Use
+            | Kind::SelfType
+            | Kind::Return
+            | Kind::Yield
+            | Kind::Comptime
+            | Kind::Do
+            | Kind::Defer
+            | Kind::Where
+            | Kind::TryAs
+            | Kind::Eof => HighlightKind::Keyword,
+        };
+
+        tokens.push(HighlightToken {
+            kind,
+            start: token.location.start_byte,
+            end: token.location.end_byte,
+        });
+    }
+
+    tokens
+}
+
+/// runs `f`, turning a panic into `fallback` instead of letting it take the
+/// whole module down. this only helps when panics unwind, builds using
+/// `panic = "abort"` still abort
+fn guarded<T>(f: impl FnOnce() -> T + UnwindSafe, fallback: impl FnOnce() -> T) -> T {
+    catch_unwind(f).unwrap_or_else(|_| fallback())
+}
+
+fn to_js<T: Serialize>(value: &T) -> Result<JsValue, JsError> {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    value
+        .serialize(&serializer)
+        .map_err(|error| JsError::new(&error.to_string()))
+}
+
+/// parses `source`, returning an object with the `ast`, which is null when
+/// the source has syntax errors, and the `diagnostics` found
+#[wasm_bindgen]
+pub fn parse(source: &str) -> Result<JsValue, JsError> {
+    let analysis = guarded(
+        || analyze(source),
+        || Analysis {
+            ast: None,
+            diagnostics: vec![Diagnostic::internal_error(source)],
+        },
+    );
+    to_js(&analysis)
+}
+
+/// splits `source` into a list of `{ kind, start, end }` tokens for syntax
+/// highlighting
+#[wasm_bindgen]
+pub fn tokenize(source: &str) -> Result<JsValue, JsError> {
+    let tokens = guarded(|| highlight(source), Vec::new);
+    to_js(&tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MALFORMED: &[&str] = &[
+        "",
+        "fun",
+        "fun main(",
+        "fun main() {",
+        "fun main() { const a = ; }",
+        "fun main() { \"unterminated }",
+        "fun main() { \"{}\"; }",
+        "fun main() { print!(1, (2]; }",
+        "}}}",
+        "const a = 1;",
+        "@",
+        "fun main() { 1 +",
+        "/* unterminated comment",
+        "fun main() { 18446744073709551616; }",
+    ];
+
+    #[test]
+    fn malformed_input_is_reported_without_panicking() {
+        for source in MALFORMED {
+            let analysis = guarded(|| analyze(source), || panic!("analyzing {source:?} panicked"));
+            if analysis.ast.is_none() && !source.is_empty() {
+                assert!(
+                    analysis.diagnostics.iter().any(|d| d.severity == Severity::Error),
+                    "{source:?}"
+                );
+            }
+            guarded(|| highlight(source), || panic!("highlighting {source:?} panicked"));
+        }
+    }
+
+    #[test]
+    fn diagnostics_point_at_byte_ranges() {
+        let source = "fun main() { const unused = 1; }";
+        let analysis = analyze(source);
+        assert!(analysis.ast.is_some());
+
+        let [warning] = analysis.diagnostics.as_slice() else {
+            panic!("expected a single warning, got {:?}", analysis.diagnostics);
+        };
+        assert_eq!(warning.severity, Severity::Warning);
+        let label = &warning.labels[0];
+        assert_eq!(&source[label.start..label.end], "const unused = 1");
+
+        let source = "fun main() { const a = ; }";
+        let analysis = analyze(source);
+        assert!(analysis.ast.is_none());
+        assert_eq!(analysis.diagnostics[0].code.as_deref(), Some("kura::syntax_error"));
+        assert_eq!(analysis.diagnostics[0].labels[0].start, source.find(';').unwrap());
+    }
+
+    #[test]
+    fn tokens_are_classified_for_highlighting() {
+        let source = "fun main() { // hi\n var a = true + \"s\"; }";
+        let kinds = highlight(source)
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+
+        use HighlightKind::*;
+        assert_eq!(
+            kinds,
+            [
+                Keyword, Identifier, Operator, Operator, Operator, Comment, Keyword, Identifier, Operator, Keyword,
+                Operator, String, Operator, Operator,
+            ]
+        );
+
+        let kinds = highlight("a @ b")
+            .into_iter()
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(kinds, [Identifier, Error]);
+    }
+
+    #[test]
+    fn panics_are_turned_into_the_fallback() {
+        let value = guarded(|| -> u32 { panic!("boom") }, || 7);
+        assert_eq!(value, 7);
+    }
+}
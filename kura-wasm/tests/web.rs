@@ -0,0 +1,39 @@
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn field(value: &JsValue, name: &str) -> JsValue {
+    js_sys::Reflect::get(value, &JsValue::from_str(name)).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn malformed_input_returns_diagnostics() {
+    for source in [
+        "fun main(",
+        "fun main() { const a = ; }",
+        "}}}",
+        "@",
+        "fun main() { \"{}\"; }",
+    ] {
+        let output = kura_wasm::parse(source).unwrap();
+        assert!(field(&output, "ast").is_null(), "{source}");
+
+        let diagnostics = js_sys::Array::from(&field(&output, "diagnostics"));
+        assert!(diagnostics.length() > 0, "{source}");
+    }
+}
+
+#[wasm_bindgen_test]
+fn valid_input_returns_the_ast() {
+    let output = kura_wasm::parse("fun main() { return 1; }").unwrap();
+    assert!(js_sys::Array::is_array(&field(&output, "ast")));
+}
+
+#[wasm_bindgen_test]
+fn tokenize_never_fails() {
+    for source in ["", "@", "\"unterminated", "fun main() {}"] {
+        let tokens = kura_wasm::tokenize(source).unwrap();
+        assert!(js_sys::Array::is_array(&tokens));
+    }
+}
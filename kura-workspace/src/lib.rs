@@ -0,0 +1,283 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use kura_lexer::Lexer;
+use kura_parser::{ParseError, Parser, Statement};
+use miette::{Diagnostic, NamedSource, SourceSpan};
+use thiserror::Error;
+
+/// files a workspace can start from, looked for in this order
+pub const ROOT_FILES: &[&str] = &["main.rmb", "lib.rmb"];
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum ModuleError {
+    #[error("there is no {} in `{}`", ROOT_FILES.join(" or "), root.display())]
+    #[diagnostic(code(kura::workspace::no_root))]
+    NoRoot { root: PathBuf },
+    #[error("could not read `{}`: {error}", path.display())]
+    #[diagnostic(code(kura::workspace::io))]
+    Io { path: PathBuf, error: std::io::Error },
+    #[error("could not parse `{}`", path.display())]
+    #[diagnostic(code(kura::workspace::parse))]
+    Parse {
+        path: PathBuf,
+        #[source_code]
+        source_code: NamedSource<String>,
+        #[related]
+        errors: Vec<ParseError>,
+    },
+    #[error("there is no file for module `{name}`")]
+    #[diagnostic(
+        code(kura::workspace::module_not_found),
+        help("it is looked for in {}", display_paths(candidates, " or "))
+    )]
+    NotFound {
+        name: String,
+        candidates: Vec<PathBuf>,
+        #[source_code]
+        source_code: NamedSource<String>,
+        #[label("declared here")]
+        span: SourceSpan,
+    },
+    #[error("modules are declared in a cycle: {}", display_paths(cycle, " -> "))]
+    #[diagnostic(code(kura::workspace::cycle))]
+    Cycle {
+        /// files declaring each other, starting and ending with the same one
+        cycle: Vec<PathBuf>,
+    },
+}
+
+fn display_paths(paths: &[PathBuf], separator: &str) -> String {
+    paths
+        .iter()
+        .map(|path| format!("`{}`", path.display()))
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+#[derive(Debug)]
+pub struct Module {
+    pub path: PathBuf,
+    pub source: String,
+    pub statements: Vec<Statement<'static>>,
+}
+
+/// every module of a program, keyed by the names leading to it from the
+/// root module, which is keyed by no names at all
+#[derive(Debug)]
+pub struct Workspace {
+    pub root: PathBuf,
+    pub modules: HashMap<Vec<String>, Module>,
+}
+
+impl Workspace {
+    /// parses the root file in `root` and every module declared from it.
+    /// `mod name;` is looked for as `name.rmb` or `name/mod.rmb`, next to the
+    /// file declaring it. every error is collected before giving up
+    pub fn build(root: &Path) -> Result<Workspace, Vec<ModuleError>> {
+        let Some(entry) = ROOT_FILES
+            .iter()
+            .map(|file| root.join(file))
+            .find(|file| file.is_file())
+        else {
+            return Err(vec![ModuleError::NoRoot {
+                root: root.to_path_buf(),
+            }]);
+        };
+
+        let mut builder = Builder::default();
+        builder.load(vec![], entry);
+
+        match builder.errors.is_empty() {
+            true => Ok(Workspace {
+                root: root.to_path_buf(),
+                modules: builder.modules,
+            }),
+            false => Err(builder.errors),
+        }
+    }
+
+    pub fn module(&self, path: &[&str]) -> Option<&Module> {
+        let path = path.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        self.modules.get(&path)
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    modules: HashMap<Vec<String>, Module>,
+    errors: Vec<ModuleError>,
+    // files being loaded, each declared by the one before it. they are
+    // compared canonicalized, but reported as they were found
+    loading: Vec<(PathBuf, PathBuf)>,
+}
+
+impl Builder {
+    fn load(&mut self, module_path: Vec<String>, file: PathBuf) {
+        let canonical = file.canonicalize().unwrap_or_else(|_| file.clone());
+        if let Some(start) = self.loading.iter().position(|(loading, _)| *loading == canonical) {
+            let mut cycle = self.loading[start..]
+                .iter()
+                .map(|(_, file)| file.clone())
+                .collect::<Vec<_>>();
+            cycle.push(file);
+            self.errors.push(ModuleError::Cycle { cycle });
+            return;
+        }
+
+        let source = match std::fs::read_to_string(&file) {
+            Ok(source) => source,
+            Err(error) => {
+                self.errors.push(ModuleError::Io { path: file, error });
+                return;
+            }
+        };
+        let named = || NamedSource::new(file.display().to_string(), source.clone());
+
+        let statements = match Parser::new(&source, Lexer::new(&source)).parse() {
+            Ok(statements) => statements.into_iter().map(Statement::into_owned).collect::<Vec<_>>(),
+            Err(error) => {
+                self.errors.push(ModuleError::Parse {
+                    path: file.clone(),
+                    source_code: named(),
+                    errors: vec![error],
+                });
+                return;
+            }
+        };
+
+        let directory = file.parent().map(Path::to_path_buf).unwrap_or_default();
+        self.loading.push((canonical, file.clone()));
+        for statement in &statements {
+            let Statement::Mod { name, location } = statement else {
+                continue;
+            };
+
+            let candidates = vec![
+                directory.join(format!("{name}.rmb")),
+                directory.join(name.as_ref()).join("mod.rmb"),
+            ];
+            match candidates.iter().find(|candidate| candidate.is_file()) {
+                Some(found) => {
+                    let mut path = module_path.clone();
+                    path.push(name.to_string());
+                    self.load(path, found.clone());
+                }
+                None => self.errors.push(ModuleError::NotFound {
+                    name: name.to_string(),
+                    candidates,
+                    source_code: named(),
+                    span: (*location).into(),
+                }),
+            }
+        }
+        self.loading.pop();
+
+        self.modules.insert(
+            module_path,
+            Module {
+                path: file,
+                source,
+                statements,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// writes `files` into a directory of their own, giving back its path
+    fn workspace(files: &[(&str, &str)]) -> PathBuf {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, Ordering::Relaxed);
+        let root = std::env::temp_dir().join(format!("kura-workspace-{}-{count}", std::process::id()));
+
+        for (path, source) in files {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, source).unwrap();
+        }
+
+        root
+    }
+
+    fn relative(root: &Path, paths: &[PathBuf]) -> Vec<String> {
+        paths
+            .iter()
+            .map(|path| path.strip_prefix(root).unwrap().display().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn modules_are_found_next_to_their_declaration() {
+        let root = workspace(&[
+            ("main.rmb", "mod lexer;\nmod parser;\nfun main() {}"),
+            ("lexer.rmb", "fun lex() {}"),
+            ("parser/mod.rmb", "mod ast;\nfun parse() {}"),
+            ("parser/ast.rmb", "struct Ast { a: u8 }"),
+        ]);
+        let workspace = Workspace::build(&root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let mut paths = workspace.modules.keys().map(|path| path.join("::")).collect::<Vec<_>>();
+        paths.sort();
+        assert_eq!(paths, ["", "lexer", "parser", "parser::ast"]);
+
+        let ast = workspace.module(&["parser", "ast"]).unwrap();
+        assert_eq!(ast.path, root.join("parser").join("ast.rmb"));
+        assert!(matches!(&ast.statements[..], [Statement::Struct { name, .. }] if name == "Ast"));
+    }
+
+    #[test]
+    fn libraries_have_a_lib_root() {
+        let root = workspace(&[("lib.rmb", "fun f() {}")]);
+        let workspace = Workspace::build(&root).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(workspace.module(&[]).unwrap().path, root.join("lib.rmb"));
+
+        let errors = Workspace::build(&root).unwrap_err();
+        assert!(matches!(&errors[..], [ModuleError::NoRoot { .. }]));
+    }
+
+    #[test]
+    fn every_error_is_collected() {
+        let root = workspace(&[
+            ("main.rmb", "mod missing;\nmod broken;\nmod fine;"),
+            ("broken.rmb", "fun broken( {}"),
+            ("fine.rmb", "mod also_missing;"),
+        ]);
+        let errors = Workspace::build(&root).unwrap_err();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(errors.len(), 3);
+        let ModuleError::NotFound {
+            name, candidates, span, ..
+        } = &errors[0]
+        else {
+            panic!("expected a missing module, found {:?}", errors[0]);
+        };
+        assert_eq!(name, "missing");
+        assert_eq!(relative(&root, candidates), ["missing.rmb", "missing/mod.rmb"]);
+        assert_eq!((span.offset(), span.len()), (0, 12));
+        assert!(matches!(&errors[1], ModuleError::Parse { path, .. } if path.ends_with("broken.rmb")));
+        assert!(matches!(&errors[2], ModuleError::NotFound { name, .. } if name == "also_missing"));
+    }
+
+    #[test]
+    fn cycles_are_reported_with_every_file_in_them() {
+        let root = workspace(&[("main.rmb", "mod a;"), ("a.rmb", "mod b;"), ("b.rmb", "mod a;")]);
+        let errors = Workspace::build(&root).unwrap_err();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let [ModuleError::Cycle { cycle }] = &errors[..] else {
+            panic!("expected a cycle, found {errors:?}");
+        };
+        assert_eq!(relative(&root, cycle), ["a.rmb", "b.rmb", "a.rmb"]);
+        assert!(errors[0].to_string().contains("a.rmb` -> `"));
+    }
+}